@@ -1,6 +1,6 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use massa_models::address::ExecutionAddressCycleInfo;
+use massa_models::address::{AddressFormat, ExecutionAddressCycleInfo};
 use massa_models::endorsement::EndorsementId;
 use massa_models::operation::OperationId;
 use massa_models::slot::{IndexedSlot, Slot};
@@ -14,6 +14,9 @@ use crate::slot::SlotAmount;
 pub struct AddressInfo {
     /// the address
     pub address: Address,
+    /// short form of the address, if enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_id: Option<String>,
     /// the thread the address belongs to
     pub thread: u8,
 
@@ -156,6 +159,31 @@ impl std::fmt::Display for CompactAddressInfo {
     }
 }
 
+/// Deferred credits of an address unlocking during a cycle
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AddressCycleDeferredCredits {
+    /// cycle during which the credits unlock
+    pub cycle: u64,
+    /// total amount unlocking during the cycle
+    pub total: Amount,
+    /// amount unlocking at each slot of the cycle
+    pub credits: Vec<SlotAmount>,
+}
+
+impl std::fmt::Display for AddressCycleDeferredCredits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Cycle {}: {} coins unlocked", self.cycle, self.total)?;
+        for slot_amount in &self.credits {
+            writeln!(
+                f,
+                "	{} coins at slot {}",
+                slot_amount.amount, slot_amount.slot
+            )?;
+        }
+        Ok(())
+    }
+}
+
 /// filter used when retrieving address informations
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct AddressFilter {
@@ -177,3 +205,70 @@ impl std::fmt::Display for AddressFilter {
         }
     }
 }
+
+/// Rewrites the base58check addresses of a JSON value, including the object keys, in a given format.
+/// Used where addresses leave the node or the client, as `Display` always uses the base58check format.
+pub fn format_json_addresses(value: &mut serde_json::Value, format: AddressFormat) {
+    match value {
+        serde_json::Value::String(string) => format_address_string(string, format),
+        serde_json::Value::Array(values) => {
+            for value in values {
+                format_json_addresses(value, format);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            *map = std::mem::take(map)
+                .into_iter()
+                .map(|(mut key, mut value)| {
+                    format_address_string(&mut key, format);
+                    format_json_addresses(&mut value, format);
+                    (key, value)
+                })
+                .collect();
+        }
+        _ => {}
+    }
+}
+
+fn format_address_string(string: &mut String, format: AddressFormat) {
+    if format == AddressFormat::Base58Check {
+        return;
+    }
+    if let Ok(address) = Address::from_str_with_format(string, AddressFormat::Base58Check) {
+        *string = address.to_string_with_format(format);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_signature::KeyPair;
+
+    #[test]
+    fn test_format_json_addresses() {
+        let address = Address::from_public_key(&KeyPair::generate(0).unwrap().get_public_key());
+        let json = serde_json::json!({
+            "address": address,
+            "balances": { address.to_string(): "1" },
+            "creators": [address, "AU"],
+            "thread": 3,
+        });
+
+        let mut base58check = json.clone();
+        format_json_addresses(&mut base58check, AddressFormat::Base58Check);
+        assert_eq!(base58check, json);
+
+        let mut bech32m = json;
+        format_json_addresses(&mut bech32m, AddressFormat::Bech32m);
+        let formatted = address.to_string_with_format(AddressFormat::Bech32m);
+        assert_eq!(
+            bech32m,
+            serde_json::json!({
+                "address": formatted,
+                "balances": { formatted.clone(): "1" },
+                "creators": [formatted, "AU"],
+                "thread": 3,
+            })
+        );
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use massa_models::{address::Address, block::Block, block_id::BlockId, slot::Slot};
+use massa_signature::{PublicKey, Signature};
 
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +12,9 @@ use crate::display_if_true;
 pub struct BlockInfo {
     /// block id
     pub id: BlockId,
+    /// short form of the block id, if enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_id: Option<String>,
     /// optional block info content
     pub content: Option<BlockInfoContent>,
 }
@@ -88,3 +92,44 @@ impl std::fmt::Display for BlockSummary {
         Ok(())
     }
 }
+
+/// Block header input, to be checked against the local draws and network versions
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BlockHeaderInput {
+    /// The public key of the creator of the header
+    pub creator_public_key: PublicKey,
+    /// The signature of the header
+    pub signature: Signature,
+    /// The serialized version of the header content
+    pub serialized_content: Vec<u8>,
+}
+
+/// Result of the header-only validation of a block header
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BlockHeaderCheck {
+    /// id of the block
+    pub block_id: BlockId,
+    /// slot of the block
+    pub slot: Slot,
+    /// creator of the block
+    pub creator_address: Address,
+    /// true if the creator was entitled to produce the header
+    /// and if the header is consistent with the local draws and network versions
+    pub is_valid: bool,
+    /// reason why the header is invalid, if any
+    pub error: Option<String>,
+}
+
+impl std::fmt::Display for BlockHeaderCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Block header {} at slot {} created by {}",
+            self.block_id, self.slot, self.creator_address
+        )?;
+        match &self.error {
+            Some(error) => writeln!(f, "\tInvalid: {}", error),
+            None => writeln!(f, "\tValid"),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use massa_models::address::AddressFormat;
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use std::net::SocketAddr;
@@ -47,6 +48,18 @@ pub struct APIConfig {
     pub enable_http: bool,
     /// whether to enable WS.
     pub enable_ws: bool,
+    /// whether to add the short form of ids to block, operation and address infos
+    pub enable_short_ids: bool,
+    /// whether operations can be submitted through the public API (not on verifier nodes)
+    pub accept_operations: bool,
+    /// path of the database, whose disk usage is reported in the node status
+    pub db_path: PathBuf,
+    /// maximum size in bytes of the events returned by a single paginated `get_events` call
+    pub max_events_response_size: usize,
+    /// maximum size in bytes of the block summaries returned by a single paginated `get_block_summaries` call
+    pub max_block_summaries_response_size: usize,
+    /// maximum duration of the CPU profiles taken through the private API
+    pub max_cpu_profile_duration: MassaTime,
     /// max datastore value length
     pub max_datastore_value_length: u64,
     /// max op datastore entry
@@ -77,4 +90,6 @@ pub struct APIConfig {
     pub keypair: KeyPair,
     /// last_start_period value, used to know if we are during a restart or not
     pub last_start_period: u64,
+    /// format of the addresses in the HTTP responses
+    pub address_format: AddressFormat,
 }
@@ -1,6 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use massa_models::address::Address;
+use massa_hash::Hash;
+use massa_models::{address::Address, slot::Slot};
 use serde::{Deserialize, Serialize};
 
 /// Datastore entry query input structure
@@ -28,3 +29,29 @@ impl std::fmt::Display for DatastoreEntryOutput {
         Ok(())
     }
 }
+
+/// Final datastore entry, along with the final state it was read from.
+/// Clients caching the entry can compare the final state hash to the one currently advertised to detect stale entries.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct AuthenticatedDatastoreEntryOutput {
+    /// final datastore entry value, None if the entry does not exist
+    pub value: Option<Vec<u8>>,
+    /// slot at the end of which the final state was read
+    pub slot: Slot,
+    /// hash of the final state that was read
+    pub final_state_hash: Hash,
+    /// proof of the entry against the final state hash, not available yet
+    pub proof: Option<Vec<u8>>,
+}
+
+impl std::fmt::Display for AuthenticatedDatastoreEntryOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "value: {:?}", self.value)?;
+        writeln!(
+            f,
+            "read at slot {} from final state {}",
+            self.slot, self.final_state_hash
+        )?;
+        Ok(())
+    }
+}
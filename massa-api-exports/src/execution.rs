@@ -1,6 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use massa_final_state::StateChanges;
+use massa_hash::Hash;
 use massa_models::{address::Address, amount::Amount, output_event::SCOutputEvent, slot::Slot};
 use serde::{Deserialize, Serialize};
 use std::{collections::VecDeque, fmt::Display};
@@ -52,6 +53,15 @@ impl Display for ExecuteReadOnlyResponse {
     }
 }
 
+/// Final state hash of an SCE-final slot, as computed by the node
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FinalStateHashEntry {
+    /// SCE-final slot
+    pub slot: Slot,
+    /// hash of the final state after the execution of the slot
+    pub final_state_hash: Hash,
+}
+
 /// read only bytecode execution request
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct ReadOnlyBytecodeExecution {
@@ -65,6 +75,15 @@ pub struct ReadOnlyBytecodeExecution {
     pub operation_datastore: Option<Vec<u8>>,
     /// fee
     pub fee: Option<Amount>,
+    /// coins of the call, as seen by the executed bytecode, optional
+    #[serde(default)]
+    pub coins: Option<Amount>,
+    /// state on top of which the bytecode is executed, the latest candidate state by default
+    #[serde(default)]
+    pub state: ReadOnlyExecutionState,
+    /// datastore entries shadowed during the execution
+    #[serde(default)]
+    pub datastore_overrides: Vec<DatastoreEntryOverride>,
 }
 
 /// read SC call request
@@ -84,4 +103,33 @@ pub struct ReadOnlyCall {
     pub coins: Option<Amount>,
     /// fee
     pub fee: Option<Amount>,
+    /// state on top of which the call is executed, the latest candidate state by default
+    #[serde(default)]
+    pub state: ReadOnlyExecutionState,
+    /// datastore entries shadowed during the execution
+    #[serde(default)]
+    pub datastore_overrides: Vec<DatastoreEntryOverride>,
+}
+
+/// State on top of which a read-only execution runs
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, Default, PartialEq, Eq)]
+pub enum ReadOnlyExecutionState {
+    /// latest candidate state
+    #[default]
+    Candidate,
+    /// latest final state
+    Final,
+    /// state right after the execution of a slot, between the latest final and candidate slots included
+    Slot(Slot),
+}
+
+/// Datastore entry shadowed during a read-only execution
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct DatastoreEntryOverride {
+    /// address owning the entry
+    pub address: Address,
+    /// key of the entry
+    pub key: Vec<u8>,
+    /// value of the entry during the execution, the entry is absent during the execution if `None`
+    pub value: Option<Vec<u8>>,
 }
@@ -41,6 +41,60 @@ pub struct NodeStatus {
     pub execution_stats: ExecutionStats,
     /// compact configuration
     pub config: CompactConfig,
+    /// health summary
+    pub health: NodeHealth,
+}
+
+/// Health summary of the node, for monitoring systems
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NodeHealth {
+    /// number of slots the candidate execution lags behind the latest slot
+    pub candidate_execution_lag: u64,
+    /// number of slots the final execution lags behind the latest slot
+    pub final_execution_lag: u64,
+    /// number of blocks waiting to be processed by consensus
+    pub consensus_backlog: u64,
+    /// number of operations in the pool
+    pub pool_operation_count: usize,
+    /// number of endorsements in the pool
+    pub pool_endorsement_count: usize,
+    /// number of denunciations in the pool
+    pub pool_denunciation_count: usize,
+    /// connected peers count per peer category, also listed in the network stats
+    pub peers_per_category: BTreeMap<String, u64>,
+    /// disk usage of the database in bytes, none if it could not be measured
+    pub db_disk_usage: Option<u64>,
+    /// resident set size of the node process in bytes, none if it could not be measured
+    pub process_rss: Option<u64>,
+}
+
+impl std::fmt::Display for NodeHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Health:")?;
+        writeln!(
+            f,
+            "\tCandidate execution lag: {} slots",
+            self.candidate_execution_lag
+        )?;
+        writeln!(
+            f,
+            "\tFinal execution lag: {} slots",
+            self.final_execution_lag
+        )?;
+        writeln!(f, "\tConsensus backlog: {} blocks", self.consensus_backlog)?;
+        writeln!(
+            f,
+            "\tPool: {} operations, {} endorsements, {} denunciations",
+            self.pool_operation_count, self.pool_endorsement_count, self.pool_denunciation_count
+        )?;
+        if let Some(db_disk_usage) = self.db_disk_usage {
+            writeln!(f, "\tDatabase disk usage: {} bytes", db_disk_usage)?;
+        }
+        if let Some(process_rss) = self.process_rss {
+            writeln!(f, "\tProcess RSS: {} bytes", process_rss)?;
+        }
+        Ok(())
+    }
 }
 
 impl std::fmt::Display for NodeStatus {
@@ -76,6 +130,8 @@ impl std::fmt::Display for NodeStatus {
 
         writeln!(f, "{}", self.execution_stats)?;
 
+        writeln!(f, "{}", self.health)?;
+
         writeln!(f, "Connected nodes:")?;
         for (node_id, (ip_addr, is_outgoing)) in &self.connected_nodes {
             writeln!(
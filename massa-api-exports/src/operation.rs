@@ -1,8 +1,10 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use massa_models::{
+    address::Address,
+    amount::Amount,
     block_id::BlockId,
-    operation::{OperationId, SecureShareOperation},
+    operation::{OperationId, OperationType, SecureShareOperation},
 };
 
 use massa_signature::{PublicKey, Signature};
@@ -26,6 +28,9 @@ pub struct OperationInput {
 pub struct OperationInfo {
     /// id
     pub id: OperationId,
+    /// short form of the operation id, if enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_id: Option<String>,
     /// true if operation is still in pool
     pub in_pool: bool,
     /// the operation appears in `in_blocks`
@@ -39,6 +44,126 @@ pub struct OperationInfo {
     pub operation: SecureShareOperation,
     /// true if the operation execution succeeded, false if failed, None means unknown
     pub op_exec_status: Option<bool>,
+    /// human-readable summary of the operation, decoded from its type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<OperationSummary>,
+}
+
+/// Decoded summary of an operation, so that frontends do not need to decode the operation types themselves
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OperationSummary {
+    /// transfer of coins
+    Transaction {
+        /// recipient of the coins
+        recipient_address: Address,
+        /// transferred amount
+        amount: Amount,
+    },
+    /// purchase of rolls
+    RollBuy {
+        /// number of bought rolls
+        roll_count: u64,
+    },
+    /// sale of rolls
+    RollSell {
+        /// number of sold rolls
+        roll_count: u64,
+    },
+    /// execution of a bytecode
+    ExecuteSc {
+        /// size of the executed bytecode, in bytes
+        bytecode_size: usize,
+        /// maximum amount of coins spent by the execution
+        max_coins: Amount,
+        /// maximum amount of gas used by the execution
+        max_gas: u64,
+    },
+    /// call of a smart contract function
+    CallSc {
+        /// called smart contract
+        target_address: Address,
+        /// called function
+        target_function: String,
+        /// coins transferred to the smart contract
+        coins: Amount,
+        /// maximum amount of gas used by the call
+        max_gas: u64,
+    },
+}
+
+impl From<&OperationType> for OperationSummary {
+    fn from(op: &OperationType) -> Self {
+        match op {
+            OperationType::Transaction {
+                recipient_address,
+                amount,
+            } => OperationSummary::Transaction {
+                recipient_address: *recipient_address,
+                amount: *amount,
+            },
+            OperationType::RollBuy { roll_count } => OperationSummary::RollBuy {
+                roll_count: *roll_count,
+            },
+            OperationType::RollSell { roll_count } => OperationSummary::RollSell {
+                roll_count: *roll_count,
+            },
+            OperationType::ExecuteSC {
+                data,
+                max_gas,
+                max_coins,
+                ..
+            } => OperationSummary::ExecuteSc {
+                bytecode_size: data.len(),
+                max_coins: *max_coins,
+                max_gas: *max_gas,
+            },
+            OperationType::CallSC {
+                target_addr,
+                target_func,
+                max_gas,
+                coins,
+                ..
+            } => OperationSummary::CallSc {
+                target_address: *target_addr,
+                target_function: target_func.clone(),
+                coins: *coins,
+                max_gas: *max_gas,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for OperationSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationSummary::Transaction {
+                recipient_address,
+                amount,
+            } => write!(f, "transfer of {} coins to {}", amount, recipient_address),
+            OperationSummary::RollBuy { roll_count } => write!(f, "buy {} rolls", roll_count),
+            OperationSummary::RollSell { roll_count } => write!(f, "sell {} rolls", roll_count),
+            OperationSummary::ExecuteSc {
+                bytecode_size,
+                max_coins,
+                max_gas,
+            } => write!(
+                f,
+                "execution of {} bytes of bytecode spending up to {} coins and {} gas",
+                bytecode_size, max_coins, max_gas
+            ),
+            OperationSummary::CallSc {
+                target_address,
+                target_function,
+                coins,
+                max_gas,
+            } => write!(
+                f,
+                "call of {}::{} with {} coins and up to {} gas",
+                target_address, target_function, coins, max_gas
+            ),
+        }
+    }
 }
 
 impl std::fmt::Display for OperationInfo {
@@ -56,6 +181,9 @@ impl std::fmt::Display for OperationInfo {
             ),
             display_option_bool(self.op_exec_status, "succes", "failed", "status unknown")
         )?;
+        if let Some(summary) = &self.summary {
+            writeln!(f, "Summary: {}", summary)?;
+        }
         writeln!(f, "In blocks:")?;
         for block_id in &self.in_blocks {
             writeln!(f, "\t- {}", block_id)?;
@@ -67,12 +195,49 @@ impl std::fmt::Display for OperationInfo {
 
 #[cfg(test)]
 mod tests {
+    use super::OperationSummary;
     use jsonrpsee::core::__reexports::serde_json::{self, Value};
-    use massa_models::{amount::Amount, operation::OperationType};
+    use massa_models::{address::Address, amount::Amount, operation::OperationType};
+    use massa_signature::KeyPair;
     use serial_test::serial;
     use std::collections::BTreeMap;
     use std::str::FromStr;
 
+    #[test]
+    fn test_operation_summary() {
+        let target_addr = Address::from_public_key(&KeyPair::generate(0).unwrap().get_public_key());
+        let summary = OperationSummary::from(&OperationType::CallSC {
+            target_addr,
+            target_func: "transfer".to_string(),
+            param: vec![1, 2, 3],
+            max_gas: 1_000_000,
+            coins: Amount::from_str("1.5").unwrap(),
+        });
+        assert_eq!(
+            serde_json::to_value(&summary).unwrap(),
+            serde_json::json!({
+                "type": "call_sc",
+                "target_address": target_addr.to_string(),
+                "target_function": "transfer",
+                "coins": "1.5",
+                "max_gas": 1_000_000,
+            })
+        );
+        assert_eq!(
+            summary.to_string(),
+            format!(
+                "call of {}::transfer with 1.5 coins and up to 1000000 gas",
+                target_addr
+            )
+        );
+
+        let summary = OperationSummary::from(&OperationType::RollBuy { roll_count: 3 });
+        assert_eq!(
+            serde_json::to_value(&summary).unwrap(),
+            serde_json::json!({ "type": "roll_buy", "roll_count": 3 })
+        );
+    }
+
     #[test]
     #[serial]
     fn test_execute_sc_with_datastore() {
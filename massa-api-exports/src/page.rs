@@ -67,3 +67,80 @@ impl<T> From<PagedVec<T>> for PagedVecV2<T> {
         }
     }
 }
+
+/// A Vec whose serialized size is bounded.
+/// When elements are left out to respect the maximum size, the response is explicitly marked as truncated
+/// and carries the cursor of the first element left out, to pass to the next request to continue from there.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct TruncatedVec<T> {
+    /// elements fitting in the response
+    pub content: Vec<T>,
+    /// whether some elements were left out of the response
+    pub truncated: bool,
+    /// cursor of the first element left out of the response, if any
+    pub next_cursor: Option<String>,
+}
+
+impl<T: Serialize> TruncatedVec<T> {
+    /// Fills a `TruncatedVec` with elements until their serialized size reaches `max_size` bytes.
+    /// The first element is always included so that clients can make progress whatever its size.
+    ///
+    /// # Arguments
+    /// * `elements`: elements to return, in cursor order
+    /// * `max_size`: maximum serialized size of the elements, in bytes
+    /// * `cursor_of`: cursor pointing to an element
+    pub fn new(
+        elements: impl IntoIterator<Item = T>,
+        max_size: usize,
+        cursor_of: impl Fn(&T) -> String,
+    ) -> Self {
+        let mut content = Vec::new();
+        let mut size = 0usize;
+        for element in elements {
+            // serialized length of the element and of its separator in the JSON array
+            let element_size = serde_json::to_vec(&element)
+                .map_or(0, |bytes| bytes.len())
+                .saturating_add(1);
+            size = size.saturating_add(element_size);
+            if size > max_size && !content.is_empty() {
+                return TruncatedVec {
+                    content,
+                    truncated: true,
+                    next_cursor: Some(cursor_of(&element)),
+                };
+            }
+            content.push(element);
+        }
+        TruncatedVec {
+            content,
+            truncated: false,
+            next_cursor: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncated_vec() {
+        // each element is serialized as 3 bytes, plus a separator
+        let elements = vec![100u32, 101, 102, 103];
+
+        let res = TruncatedVec::new(elements.clone(), 10, |e| e.to_string());
+        assert_eq!(res.content, vec![100, 101]);
+        assert!(res.truncated);
+        assert_eq!(res.next_cursor, Some("102".to_string()));
+
+        let res = TruncatedVec::new(elements.clone(), 1000, |e| e.to_string());
+        assert_eq!(res.content, elements);
+        assert!(!res.truncated);
+        assert_eq!(res.next_cursor, None);
+
+        // the first element is always returned
+        let res = TruncatedVec::new(elements, 1, |e| e.to_string());
+        assert_eq!(res.content, vec![100]);
+        assert_eq!(res.next_cursor, Some("101".to_string()));
+    }
+}
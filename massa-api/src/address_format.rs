@@ -0,0 +1,88 @@
+//! HTTP middleware formatting the addresses of the API responses in the configured format.
+//!
+//! Addresses are displayed and serialized in the base58check format everywhere in the node:
+//! the configured format is only applied to the JSON bodies leaving the API.
+
+use hyper::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::{Body, Request, Response};
+use massa_api_exports::address::format_json_addresses;
+use massa_models::address::AddressFormat;
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Layer applying [`AddressFormatService`]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AddressFormatLayer {
+    format: AddressFormat,
+}
+
+impl AddressFormatLayer {
+    /// Creates a layer formatting the addresses of the responses in `format`
+    pub(crate) fn new(format: AddressFormat) -> Self {
+        AddressFormatLayer { format }
+    }
+}
+
+impl<S> Layer<S> for AddressFormatLayer {
+    type Service = AddressFormatService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AddressFormatService {
+            inner,
+            format: self.format,
+        }
+    }
+}
+
+/// Rewrites the addresses of the JSON responses of the inner service in the configured format.
+/// The responses are left untouched when the format is base58check.
+#[derive(Debug, Clone)]
+pub(crate) struct AddressFormatService<S> {
+    inner: S,
+    format: AddressFormat,
+}
+
+impl<S> Service<Request<Body>> for AddressFormatService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>>,
+    S::Error: Into<Box<dyn Error + Send + Sync>> + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = Box<dyn Error + Send + Sync + 'static>;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let format = self.format;
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let res = fut.await.map_err(Into::into)?;
+            let is_json = res.headers().get(CONTENT_TYPE).map_or(false, |value| {
+                value.as_bytes().starts_with(b"application/json")
+            });
+            if format == AddressFormat::Base58Check || !is_json {
+                return Ok(res);
+            }
+            let (mut parts, body) = res.into_parts();
+            let bytes = hyper::body::to_bytes(body).await?;
+            let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+                Ok(mut value) => {
+                    format_json_addresses(&mut value, format);
+                    Body::from(value.to_string())
+                }
+                Err(_) => Body::from(bytes),
+            };
+            // the length of the body changes with the format of the addresses
+            parts.headers.remove(CONTENT_LENGTH);
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
@@ -9,14 +9,17 @@ use futures::future::{self, Either};
 use futures::StreamExt;
 use jsonrpsee::core::{Error as JsonRpseeError, RpcResult, SubscriptionResult};
 use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage};
+use massa_api_exports::block::BlockSummary;
 use massa_api_exports::config::APIConfig;
 use massa_api_exports::error::ApiError;
-use massa_api_exports::page::{PageRequest, PagedVec, PagedVecV2};
-use massa_api_exports::ApiRequest;
+use massa_api_exports::page::{PageRequest, PagedVec, PagedVecV2, TruncatedVec};
+use massa_api_exports::{ApiRequest, TimeInterval};
 use massa_consensus_exports::{ConsensusBroadcasts, ConsensusController};
-use massa_execution_exports::ExecutionController;
+use massa_execution_exports::{AsyncMessageFilter, ExecutionController, PendingAsyncMessage};
 use massa_models::address::Address;
 use massa_models::block_id::BlockId;
+use massa_models::execution::EventFilter;
+use massa_models::output_event::SCOutputEvent;
 use massa_models::slot::Slot;
 use massa_models::timeslots::get_latest_block_slot_at_timestamp;
 use massa_models::version::Version;
@@ -109,10 +112,110 @@ impl MassaApiServer for API<ApiV2> {
         Ok(paged_vec.into())
     }
 
+    async fn get_async_messages(
+        &self,
+        filter: AsyncMessageFilter,
+        api_request: Option<ApiRequest>,
+    ) -> RpcResult<PagedVecV2<PendingAsyncMessage>> {
+        let messages = self.0.execution_controller.get_async_messages(&filter);
+
+        let paged_vec = if let Some(api_request) = api_request {
+            PagedVec::new(messages, api_request.page_request)
+        } else {
+            PagedVec::new(
+                messages,
+                Some(PageRequest {
+                    offset: 0,
+                    limit: 50,
+                }),
+            )
+        };
+
+        Ok(paged_vec.into())
+    }
+
     async fn get_next_block_best_parents(&self) -> RpcResult<Vec<(BlockId, u64)>> {
         Ok(self.0.consensus_controller.get_best_parents())
     }
 
+    async fn get_events(
+        &self,
+        mut filter: EventFilter,
+        cursor: Option<String>,
+    ) -> RpcResult<TruncatedVec<SCOutputEvent>> {
+        // resume from the slot of the cursor, skipping the events that were already returned
+        let cursor = cursor
+            .as_deref()
+            .map(parse_cursor)
+            .transpose()?
+            .map(|(slot, index)| {
+                index
+                    .parse::<u64>()
+                    .map(|index| (slot, index))
+                    .map_err(|_| {
+                        ApiError::BadRequest(format!("invalid event cursor index: {}", index))
+                    })
+            })
+            .transpose()?;
+        if let Some((slot, _)) = cursor {
+            filter.start = Some(
+                filter
+                    .start
+                    .map_or(slot, |start| std::cmp::max(start, slot)),
+            );
+        }
+
+        let mut events = self
+            .0
+            .execution_controller
+            .get_filtered_sc_output_event(filter);
+        events.sort_unstable_by_key(|event| (event.context.slot, event.context.index_in_slot));
+        let events = events.into_iter().filter(|event| {
+            cursor.map_or(true, |cursor| {
+                (event.context.slot, event.context.index_in_slot) > cursor
+            })
+        });
+
+        Ok(TruncatedVec::new(
+            events,
+            self.0.api_settings.max_events_response_size,
+            |event| format_cursor(&event.context.slot, event.context.index_in_slot),
+        ))
+    }
+
+    async fn get_block_summaries(
+        &self,
+        time: TimeInterval,
+        cursor: Option<String>,
+    ) -> RpcResult<TruncatedVec<BlockSummary>> {
+        let cursor = cursor
+            .as_deref()
+            .map(parse_cursor)
+            .transpose()?
+            .map(|(slot, id)| {
+                id.parse::<BlockId>()
+                    .map(|id| (slot, id))
+                    .map_err(|_| ApiError::BadRequest(format!("invalid block cursor id: {}", id)))
+            })
+            .transpose()?;
+
+        let mut summaries = crate::public::get_block_summaries(
+            &*self.0.consensus_controller,
+            &self.0.api_settings,
+            time,
+        )?;
+        summaries.sort_unstable_by_key(|summary| (summary.slot, summary.id));
+        let summaries = summaries
+            .into_iter()
+            .filter(|summary| cursor.map_or(true, |cursor| (summary.slot, summary.id) > cursor));
+
+        Ok(TruncatedVec::new(
+            summaries,
+            self.0.api_settings.max_block_summaries_response_size,
+            |summary| format_cursor(&summary.slot, summary.id),
+        ))
+    }
+
     async fn get_version(&self) -> RpcResult<Version> {
         Ok(self.0.version)
     }
@@ -132,6 +235,26 @@ impl MassaApiServer for API<ApiV2> {
         .await
     }
 
+    async fn subscribe_graph_interval(
+        &self,
+        pending: PendingSubscriptionSink,
+        time: TimeInterval,
+    ) -> SubscriptionResult {
+        let mut summaries = match crate::public::get_block_summaries(
+            &*self.0.consensus_controller,
+            &self.0.api_settings,
+            time,
+        ) {
+            Ok(summaries) => summaries,
+            Err(err) => {
+                pending.reject(err).await;
+                return Ok(());
+            }
+        };
+        summaries.sort_unstable_by_key(|summary| (summary.slot, summary.id));
+        stream_via_ws(summaries, pending).await
+    }
+
     async fn subscribe_new_filled_blocks(
         &self,
         pending: PendingSubscriptionSink,
@@ -149,10 +272,68 @@ impl MassaApiServer for API<ApiV2> {
     ) -> SubscriptionResult {
         broadcast_via_ws(self.0.pool_broadcasts.operation_sender.clone(), pending).await
     }
+
+    async fn subscribe_new_final_periods(
+        &self,
+        pending: PendingSubscriptionSink,
+    ) -> SubscriptionResult {
+        broadcast_via_ws(
+            self.0.consensus_broadcasts.final_period_sender.clone(),
+            pending,
+        )
+        .await
+    }
+
+    async fn subscribe_fork_choice_events(
+        &self,
+        pending: PendingSubscriptionSink,
+    ) -> SubscriptionResult {
+        broadcast_via_ws(
+            self.0.consensus_broadcasts.fork_choice_sender.clone(),
+            pending,
+        )
+        .await
+    }
+}
+
+/// Formats a continuation cursor as `period:thread:position`,
+/// where `position` identifies the last returned element within its slot
+fn format_cursor(slot: &Slot, position: impl std::fmt::Display) -> String {
+    format!("{}:{}:{}", slot.period, slot.thread, position)
+}
+
+/// Parses a continuation cursor into its slot and the position of the last returned element within that slot
+fn parse_cursor(cursor: &str) -> Result<(Slot, &str), ApiError> {
+    let invalid = || ApiError::BadRequest(format!("invalid cursor: {}", cursor));
+    let mut parts = cursor.splitn(3, ':');
+    let (Some(period), Some(thread), Some(position)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    let period = period.parse::<u64>().map_err(|_| invalid())?;
+    let thread = thread.parse::<u8>().map_err(|_| invalid())?;
+    Ok((Slot::new(period, thread), position))
 }
 
 // Brodcast the stream(sender) content via a WebSocket
-async fn broadcast_via_ws<T: Serialize + Send + Clone + 'static>(
+/// Sends the items of `items` one by one to the subscription, then closes it.
+/// Sending waits while the client buffer is full, so that items are only produced as fast as they are read.
+async fn stream_via_ws<T: Serialize>(
+    items: impl IntoIterator<Item = T>,
+    pending: PendingSubscriptionSink,
+) -> SubscriptionResult {
+    let sink = pending.accept().await?;
+    for item in items {
+        let notif = SubscriptionMessage::from_json(&item)?;
+        if sink.send(notif).await.is_err() {
+            // subscription closed by the client
+            break;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) async fn broadcast_via_ws<T: Serialize + Send + Clone + 'static>(
     sender: tokio::sync::broadcast::Sender<T>,
     pending: PendingSubscriptionSink,
 ) -> SubscriptionResult {
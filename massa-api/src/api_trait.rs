@@ -2,10 +2,14 @@
 //! Json RPC API for a massa-node
 use jsonrpsee::core::{RpcResult, SubscriptionResult};
 use jsonrpsee::proc_macros::rpc;
-use massa_api_exports::page::PagedVecV2;
-use massa_api_exports::ApiRequest;
+use massa_api_exports::block::BlockSummary;
+use massa_api_exports::page::{PagedVecV2, TruncatedVec};
+use massa_api_exports::{ApiRequest, TimeInterval};
+use massa_execution_exports::{AsyncMessageFilter, PendingAsyncMessage};
 use massa_models::address::Address;
 use massa_models::block_id::BlockId;
+use massa_models::execution::EventFilter;
+use massa_models::output_event::SCOutputEvent;
 use massa_models::version::Version;
 
 /// Exposed API methods
@@ -18,10 +22,38 @@ pub trait MassaApi {
         page_request: Option<ApiRequest>,
     ) -> RpcResult<PagedVecV2<(Address, u64)>>;
 
+    /// Get the asynchronous messages of the final pool matching a filter, in execution priority order.
+    #[method(name = "get_async_messages")]
+    async fn get_async_messages(
+        &self,
+        filter: AsyncMessageFilter,
+        page_request: Option<ApiRequest>,
+    ) -> RpcResult<PagedVecV2<PendingAsyncMessage>>;
+
     /// Get the ids of best parents for the next block to be produced along with their period
     #[method(name = "get_next_block_best_parents")]
     async fn get_next_block_best_parents(&self) -> RpcResult<Vec<(BlockId, u64)>>;
 
+    /// Get the smart contract events matching a filter, ordered by slot and index in slot.
+    /// The result is truncated to the configured maximum response size:
+    /// if it is, the call can be repeated with the returned cursor to get the following events.
+    #[method(name = "get_events")]
+    async fn get_events(
+        &self,
+        filter: EventFilter,
+        cursor: Option<String>,
+    ) -> RpcResult<TruncatedVec<SCOutputEvent>>;
+
+    /// Get the summaries of the blocks of the graph within a time interval, ordered by slot and block id.
+    /// The result is truncated to the configured maximum response size:
+    /// if it is, the call can be repeated with the returned cursor to get the following summaries.
+    #[method(name = "get_block_summaries")]
+    async fn get_block_summaries(
+        &self,
+        time: TimeInterval,
+        cursor: Option<String>,
+    ) -> RpcResult<TruncatedVec<BlockSummary>>;
+
     /// Get Massa node version.
     #[method(name = "get_version")]
     async fn get_version(&self) -> RpcResult<Version>;
@@ -57,4 +89,32 @@ pub trait MassaApi {
 		item = Operation
 	)]
     async fn subscribe_new_operations(&self) -> SubscriptionResult;
+
+    /// New latest final period of a thread, sent each time a block of the thread becomes final.
+    /// Lighter than the block subscriptions for the clients that only follow finality.
+    #[subscription(
+        name = "subscribe_new_final_periods" => "new_final_periods",
+        unsubscribe = "unsubscribe_new_final_periods",
+        item = FinalPeriodChange
+    )]
+    async fn subscribe_new_final_periods(&self) -> SubscriptionResult;
+
+    /// Changes of the fork choice of the node: new blockclique members, stale and final blocks, reorganizations.
+    /// Lets the clients follow the chain reorganizations without polling the graph status.
+    #[subscription(
+        name = "subscribe_fork_choice_events" => "fork_choice_events",
+        unsubscribe = "unsubscribe_fork_choice_events",
+        item = ForkChoiceEvent
+    )]
+    async fn subscribe_fork_choice_events(&self) -> SubscriptionResult;
+
+    /// Summaries of the blocks of the graph within a time interval, ordered by slot and block id.
+    /// Unlike `get_block_summaries`, the result is not truncated: the summaries are sent one by one,
+    /// at the pace at which the client reads them, and the subscription is closed once all of them are sent.
+    #[subscription(
+        name = "subscribe_graph_interval" => "graph_interval",
+        unsubscribe = "unsubscribe_graph_interval",
+        item = BlockSummary
+    )]
+    async fn subscribe_graph_interval(&self, time: TimeInterval) -> SubscriptionResult;
 }
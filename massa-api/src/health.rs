@@ -0,0 +1,69 @@
+//! Resource usage of the node reported in its status.
+//!
+//! The measures are best-effort: they are `None` when the platform does not expose them
+//! or when they cannot be read.
+
+use std::path::Path;
+
+/// Total size in bytes of the files of a directory and its subdirectories
+pub(crate) fn directory_size(path: &Path) -> Option<u64> {
+    let mut size = 0u64;
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir).ok()? {
+            let entry = entry.ok()?;
+            let metadata = entry.metadata().ok()?;
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else {
+                size = size.saturating_add(metadata.len());
+            }
+        }
+    }
+    Some(size)
+}
+
+/// Resident set size of the node process in bytes, read from the `VmRSS` line (in kB) of `/proc/self/status`
+#[cfg(target_os = "linux")]
+pub(crate) fn process_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kilobytes: u64 = line
+        .trim_start_matches("VmRSS:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    kilobytes.checked_mul(1024)
+}
+
+/// Resident set size of the node process in bytes
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn process_rss() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_size() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), [0u8; 100]).unwrap();
+        std::fs::create_dir_all(dir.path().join("sub/subsub")).unwrap();
+        std::fs::write(dir.path().join("sub/b"), [0u8; 20]).unwrap();
+        std::fs::write(dir.path().join("sub/subsub/c"), [0u8; 3]).unwrap();
+        assert_eq!(directory_size(dir.path()), Some(123));
+
+        // a missing directory has no size
+        assert_eq!(directory_size(&dir.path().join("missing")), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_rss() {
+        assert!(process_rss().unwrap() > 0);
+    }
+}
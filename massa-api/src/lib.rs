@@ -4,28 +4,37 @@
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 
+use address_format::AddressFormatLayer;
 use api_trait::MassaApiServer;
 use hyper::Method;
-use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
+use jsonrpsee::core::{Error as JsonRpseeError, RpcResult, SubscriptionResult};
 use jsonrpsee::proc_macros::rpc;
 use jsonrpsee::server::middleware::HostFilterLayer;
 use jsonrpsee::server::{BatchRequestConfig, ServerBuilder, ServerHandle};
 use jsonrpsee::RpcModule;
 use massa_api_exports::{
-    address::{AddressFilter, AddressInfo},
-    block::{BlockInfo, BlockSummary},
+    address::{AddressCycleDeferredCredits, AddressFilter, AddressInfo},
+    block::{BlockHeaderCheck, BlockHeaderInput, BlockInfo, BlockSummary},
     config::APIConfig,
-    datastore::{DatastoreEntryInput, DatastoreEntryOutput},
+    datastore::{AuthenticatedDatastoreEntryOutput, DatastoreEntryInput, DatastoreEntryOutput},
     endorsement::EndorsementInfo,
     error::ApiError::WrongAPI,
-    execution::{ExecuteReadOnlyResponse, ReadOnlyBytecodeExecution, ReadOnlyCall},
+    execution::{
+        ExecuteReadOnlyResponse, FinalStateHashEntry, ReadOnlyBytecodeExecution, ReadOnlyCall,
+    },
     node::NodeStatus,
     operation::{OperationInfo, OperationInput},
     page::{PageRequest, PagedVec},
     TimeInterval,
 };
-use massa_consensus_exports::{ConsensusBroadcasts, ConsensusController};
-use massa_execution_exports::ExecutionController;
+use massa_consensus_exports::{events::DeepReorgReport, ConsensusBroadcasts, ConsensusController};
+use massa_execution_exports::{
+    ContractIoStats, ExecutionController, OperationCoinFlow, OperationExecutionResult,
+    SlotMissStats, SlotSequencerStatus,
+};
+use massa_factory_exports::{
+    OperatorRevenue, OperatorRevenueReport, ProductionBlacklist, ProductionBlacklistWindow,
+};
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
 use massa_models::node::NodeId;
@@ -38,8 +47,11 @@ use massa_models::{
 };
 use massa_pool_exports::{PoolBroadcasts, PoolController};
 use massa_pos_exports::SelectorController;
-use massa_protocol_exports::{ProtocolConfig, ProtocolController};
+use massa_protocol_exports::{
+    ConnectionEvent, PeerRecord, ProtocolBroadcasts, ProtocolConfig, ProtocolController,
+};
 use massa_storage::Storage;
+use massa_time::MassaTime;
 use massa_versioning::keypair_factory::KeyPairFactory;
 use massa_wallet::Wallet;
 use parking_lot::RwLock;
@@ -49,9 +61,12 @@ use std::sync::{Arc, Condvar, Mutex};
 use tower_http::cors::{Any, CorsLayer};
 use tracing::{info, warn};
 
+mod address_format;
 mod api;
 mod api_trait;
+mod health;
 mod private;
+mod profiling;
 mod public;
 
 #[cfg(test)]
@@ -87,6 +102,8 @@ pub struct Public {
 pub struct Private {
     /// link to the protocol component
     pub protocol_controller: Box<dyn ProtocolController>,
+    /// link to the consensus component
+    pub consensus_controller: Box<dyn ConsensusController>,
     /// link to the execution component
     pub execution_controller: Box<dyn ExecutionController>,
     /// API settings
@@ -96,6 +113,12 @@ pub struct Private {
     pub stop_cv: Arc<(Mutex<bool>, Condvar)>,
     /// User wallet
     pub node_wallet: Arc<RwLock<Wallet>>,
+    /// Windows during which the factory must not produce
+    pub production_blacklist: ProductionBlacklist,
+    /// Revenue of the blocks and endorsements produced by the node
+    pub operator_revenue: OperatorRevenue,
+    /// channels with informations broadcasted by the protocol
+    pub protocol_broadcasts: ProtocolBroadcasts,
 }
 
 /// API v2 content
@@ -184,7 +207,8 @@ async fn serve<T>(
 
     let middleware = tower::ServiceBuilder::new()
         .layer(cors)
-        .layer(allowed_hosts);
+        .layer(allowed_hosts)
+        .layer(AddressFormatLayer::new(api_config.address_format));
 
     let server = server_builder
         .set_middleware(middleware)
@@ -311,6 +335,34 @@ pub trait MassaRpc {
     #[method(name = "node_remove_from_bootstrap_blacklist")]
     async fn node_remove_from_bootstrap_blacklist(&self, arg: Vec<IpAddr>) -> RpcResult<()>;
 
+    /// Returns the windows during which the node does not produce blocks nor endorsements.
+    #[method(name = "node_production_blacklist")]
+    async fn node_production_blacklist(&self) -> RpcResult<Vec<ProductionBlacklistWindow>>;
+
+    /// Add window(s) to the node production blacklist.
+    /// Windows added this way are lost on node restart.
+    #[method(name = "node_add_to_production_blacklist")]
+    async fn node_add_to_production_blacklist(
+        &self,
+        arg: Vec<ProductionBlacklistWindow>,
+    ) -> RpcResult<()>;
+
+    /// Remove window(s) from the node production blacklist.
+    #[method(name = "node_remove_from_production_blacklist")]
+    async fn node_remove_from_production_blacklist(
+        &self,
+        arg: Vec<ProductionBlacklistWindow>,
+    ) -> RpcResult<()>;
+
+    /// Returns the estimated revenue of the blocks and endorsements produced by the node
+    /// whose slot timestamp is in the time interval, per item and per day.
+    #[method(name = "get_operator_revenue")]
+    async fn get_operator_revenue(&self, arg: TimeInterval) -> RpcResult<OperatorRevenueReport>;
+
+    /// Returns the daily revenue of the node in the time interval, formatted as CSV.
+    #[method(name = "get_operator_revenue_csv")]
+    async fn get_operator_revenue_csv(&self, arg: TimeInterval) -> RpcResult<String>;
+
     /// Unban given IP address(es).
     /// No confirmation to expect.
     #[method(name = "node_unban_by_ip")]
@@ -321,10 +373,65 @@ pub trait MassaRpc {
     #[method(name = "node_unban_by_id")]
     async fn node_unban_by_id(&self, arg: Vec<NodeId>) -> RpcResult<()>;
 
+    /// Returns the liveness history of the known peers: last time they were seen,
+    /// latency of their last handshake, advertised listeners and ban state.
+    #[method(name = "node_peer_records")]
+    async fn node_peer_records(&self) -> RpcResult<Vec<PeerRecord>>;
+
+    /// Confirm the reorganization held because it would discard more executed blocks than `max_reorg_depth`,
+    /// so that it is applied and block production resumes.
+    /// Returns the confirmed reorganization, or `None` if none was held.
+    #[method(name = "node_confirm_reorg")]
+    async fn node_confirm_reorg(&self) -> RpcResult<Option<DeepReorgReport>>;
+
+    /// Sample the CPU usage of the node for the given duration (in milliseconds), bounded by the node settings.
+    /// Returns the profile in the protobuf pprof format.
+    /// Requires a node built with the `profiling` feature.
+    #[method(name = "node_cpu_profile")]
+    async fn node_cpu_profile(&self, arg: MassaTime) -> RpcResult<Vec<u8>>;
+
+    /// Snapshot of the heap allocations of the node, in the protobuf pprof format.
+    /// Requires a node built with the `profiling` feature.
+    #[method(name = "node_heap_snapshot")]
+    async fn node_heap_snapshot(&self) -> RpcResult<Vec<u8>>;
+
+    /// Connection events of the node: peers connecting, disconnecting (with reason and duration),
+    /// being banned, or failing their handshake (with error class).
+    #[subscription(
+        name = "subscribe_connection_events" => "connection_events",
+        unsubscribe = "unsubscribe_connection_events",
+        item = ConnectionEvent
+    )]
+    async fn subscribe_connection_events(&self) -> SubscriptionResult;
+
     /// Summary of the current state: time, last final blocks (hash, thread, slot, timestamp), clique count, connected nodes count.
     #[method(name = "get_status")]
     async fn get_status(&self) -> RpcResult<NodeStatus>;
 
+    /// Status of the execution slot sequencer: sequence length, latest consensus and execution final slots, execution cursors.
+    #[method(name = "get_sequencer_status")]
+    async fn get_sequencer_status(&self) -> RpcResult<SlotSequencerStatus>;
+
+    /// Per-thread block and miss counts of the execution-final slots executed in the statistics time window.
+    #[method(name = "get_slot_miss_stats")]
+    async fn get_slot_miss_stats(&self) -> RpcResult<SlotMissStats>;
+
+    /// Datastore access statistics of a smart contract over the latest `window` executed slots:
+    /// calls, datastore reads and writes, and most accessed datastore keys.
+    #[method(name = "get_contract_io_stats")]
+    async fn get_contract_io_stats(
+        &self,
+        address: Address,
+        window: u64,
+    ) -> RpcResult<ContractIoStats>;
+
+    /// Final state hashes computed by the node for the latest SCE-final slots after `after` (all of them if null), oldest first.
+    #[method(name = "get_final_state_hashes")]
+    async fn get_final_state_hashes(
+        &self,
+        after: Option<Slot>,
+    ) -> RpcResult<Vec<FinalStateHashEntry>>;
+
     /// Get cliques.
     #[method(name = "get_cliques")]
     async fn get_cliques(&self) -> RpcResult<Vec<Clique>>;
@@ -340,6 +447,22 @@ pub trait MassaRpc {
     #[method(name = "get_operations")]
     async fn get_operations(&self, arg: Vec<OperationId>) -> RpcResult<Vec<OperationInfo>>;
 
+    /// Returns the detailed execution result of each given operation, or null if its execution is unknown.
+    /// Only the results of the latest final operations are kept in memory: older ones are unknown.
+    #[method(name = "get_operation_execution_result")]
+    async fn get_operation_execution_result(
+        &self,
+        arg: Vec<OperationId>,
+    ) -> RpcResult<Vec<Option<OperationExecutionResult>>>;
+
+    /// Returns the coin flow graph of each given operation: every coin moved by its execution,
+    /// including its fee, storage costs and smart contract transfers, or null if its execution is unknown.
+    #[method(name = "get_operation_coin_flow")]
+    async fn get_operation_coin_flow(
+        &self,
+        arg: Vec<OperationId>,
+    ) -> RpcResult<Vec<Option<OperationCoinFlow>>>;
+
     /// Returns endorsement(s) information associated to a given list of endorsement(s) ID(s)
     #[method(name = "get_endorsements")]
     async fn get_endorsements(&self, arg: Vec<EndorsementId>) -> RpcResult<Vec<EndorsementInfo>>;
@@ -353,6 +476,15 @@ pub trait MassaRpc {
     #[method(name = "get_blockclique_block_by_slot")]
     async fn get_blockclique_block_by_slot(&self, arg: Slot) -> RpcResult<Option<Block>>;
 
+    /// Checks block headers against the local draws and network versions only,
+    /// without the block body nor the block graph:
+    /// tells whether the creator of each header was entitled to produce it at its slot.
+    #[method(name = "check_block_headers")]
+    async fn check_block_headers(
+        &self,
+        arg: Vec<BlockHeaderInput>,
+    ) -> RpcResult<Vec<BlockHeaderCheck>>;
+
     /// Get the block graph within the specified time interval.
     /// Optional parameters: from `<time_start>` (included) and to `<time_end>` (excluded) millisecond timestamp
     #[method(name = "get_graph_interval")]
@@ -365,10 +497,26 @@ pub trait MassaRpc {
         arg: Vec<DatastoreEntryInput>,
     ) -> RpcResult<Vec<DatastoreEntryOutput>>;
 
+    /// Get multiple final datastore entries, each along with the slot and hash of the final state it was read from.
+    #[method(name = "get_authenticated_datastore_entries")]
+    async fn get_authenticated_datastore_entries(
+        &self,
+        arg: Vec<DatastoreEntryInput>,
+    ) -> RpcResult<Vec<AuthenticatedDatastoreEntryOutput>>;
+
     /// Get addresses.
     #[method(name = "get_addresses")]
     async fn get_addresses(&self, arg: Vec<Address>) -> RpcResult<Vec<AddressInfo>>;
 
+    /// Get the candidate deferred credits of an address grouped by the cycle during which they unlock.
+    /// If a cycle is given, only the credits unlocking during that cycle are returned.
+    #[method(name = "get_deferred_credits_schedule")]
+    async fn get_deferred_credits_schedule(
+        &self,
+        address: Address,
+        cycle: Option<u64>,
+    ) -> RpcResult<Vec<AddressCycleDeferredCredits>>;
+
     /// Get addresses bytecode.
     #[method(name = "get_addresses_bytecode")]
     async fn get_addresses_bytecode(&self, args: Vec<AddressFilter>) -> RpcResult<Vec<Vec<u8>>>;
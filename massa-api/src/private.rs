@@ -3,29 +3,40 @@
 use crate::{MassaRpcServer, Private, RpcServer, StopHandle, Value, API};
 
 use async_trait::async_trait;
-use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
+use jsonrpsee::core::{Error as JsonRpseeError, RpcResult, SubscriptionResult};
+use jsonrpsee::PendingSubscriptionSink;
 use massa_api_exports::{
-    address::{AddressFilter, AddressInfo},
-    block::{BlockInfo, BlockSummary},
+    address::{AddressCycleDeferredCredits, AddressFilter, AddressInfo},
+    block::{BlockHeaderCheck, BlockHeaderInput, BlockInfo, BlockSummary},
     config::APIConfig,
-    datastore::{DatastoreEntryInput, DatastoreEntryOutput},
+    datastore::{AuthenticatedDatastoreEntryOutput, DatastoreEntryInput, DatastoreEntryOutput},
     endorsement::EndorsementInfo,
     error::ApiError,
-    execution::{ExecuteReadOnlyResponse, ReadOnlyBytecodeExecution, ReadOnlyCall},
+    execution::{
+        ExecuteReadOnlyResponse, FinalStateHashEntry, ReadOnlyBytecodeExecution, ReadOnlyCall,
+    },
     node::NodeStatus,
     operation::{OperationInfo, OperationInput},
     page::{PageRequest, PagedVec},
     ListType, ScrudOperation, TimeInterval,
 };
-use massa_execution_exports::ExecutionController;
+use massa_consensus_exports::{events::DeepReorgReport, ConsensusController};
+use massa_execution_exports::{
+    ContractIoStats, ExecutionController, OperationCoinFlow, OperationExecutionResult,
+    SlotMissStats, SlotSequencerStatus,
+};
+use massa_factory_exports::{
+    OperatorRevenue, OperatorRevenueReport, ProductionBlacklist, ProductionBlacklistWindow,
+};
 use massa_hash::Hash;
 use massa_models::{
     address::Address, block::Block, block_id::BlockId, clique::Clique, composite::PubkeySig,
     endorsement::EndorsementId, execution::EventFilter, node::NodeId, operation::OperationId,
     output_event::SCOutputEvent, prehash::PreHashSet, slot::Slot,
 };
-use massa_protocol_exports::{PeerId, ProtocolController};
+use massa_protocol_exports::{PeerId, PeerRecord, ProtocolBroadcasts, ProtocolController};
 use massa_signature::KeyPair;
+use massa_time::MassaTime;
 use massa_wallet::Wallet;
 use parking_lot::RwLock;
 use std::net::{IpAddr, SocketAddr};
@@ -37,22 +48,32 @@ use std::{
     fs::{remove_file, OpenOptions},
     sync::Condvar,
 };
+use tracing::info;
 
 impl API<Private> {
     /// generate a new private API
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         protocol_controller: Box<dyn ProtocolController>,
+        consensus_controller: Box<dyn ConsensusController>,
         execution_controller: Box<dyn ExecutionController>,
         api_settings: APIConfig,
         stop_cv: Arc<(Mutex<bool>, Condvar)>,
         node_wallet: Arc<RwLock<Wallet>>,
+        production_blacklist: ProductionBlacklist,
+        operator_revenue: OperatorRevenue,
+        protocol_broadcasts: ProtocolBroadcasts,
     ) -> Self {
         API(Private {
             protocol_controller,
+            consensus_controller,
             execution_controller,
             api_settings,
             stop_cv,
             node_wallet,
+            production_blacklist,
+            operator_revenue,
+            protocol_broadcasts,
         })
     }
 }
@@ -183,6 +204,47 @@ impl MassaRpcServer for API<Private> {
             .map_err(|e| ApiError::ProtocolError(e.to_string()).into())
     }
 
+    async fn node_peer_records(&self) -> RpcResult<Vec<PeerRecord>> {
+        self.0
+            .protocol_controller
+            .get_peer_records()
+            .map_err(|e| ApiError::ProtocolError(e.to_string()).into())
+    }
+
+    async fn node_confirm_reorg(&self) -> RpcResult<Option<DeepReorgReport>> {
+        let confirmed = self.0.consensus_controller.confirm_reorg();
+        if let Some(report) = &confirmed {
+            info!("operator confirmed the held reorganization: {}", report);
+        }
+        Ok(confirmed)
+    }
+
+    async fn node_cpu_profile(&self, duration: MassaTime) -> RpcResult<Vec<u8>> {
+        crate::profiling::cpu_profile(
+            duration.to_duration(),
+            self.0.api_settings.max_cpu_profile_duration.to_duration(),
+        )
+        .await
+        .map_err(|e| e.into())
+    }
+
+    async fn node_heap_snapshot(&self) -> RpcResult<Vec<u8>> {
+        crate::profiling::heap_snapshot()
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn subscribe_connection_events(
+        &self,
+        pending: PendingSubscriptionSink,
+    ) -> SubscriptionResult {
+        crate::api::broadcast_via_ws(
+            self.0.protocol_broadcasts.connection_event_sender.clone(),
+            pending,
+        )
+        .await
+    }
+
     async fn node_unban_by_ip(&self, _ips: Vec<IpAddr>) -> RpcResult<()> {
         //TODO: Reinvoke
         // let network_command_sender = self.0.network_command_sender.clone();
@@ -199,6 +261,22 @@ impl MassaRpcServer for API<Private> {
         crate::wrong_api::<NodeStatus>()
     }
 
+    async fn get_sequencer_status(&self) -> RpcResult<SlotSequencerStatus> {
+        crate::wrong_api::<SlotSequencerStatus>()
+    }
+
+    async fn get_slot_miss_stats(&self) -> RpcResult<SlotMissStats> {
+        crate::wrong_api::<SlotMissStats>()
+    }
+
+    async fn get_contract_io_stats(&self, _: Address, _: u64) -> RpcResult<ContractIoStats> {
+        crate::wrong_api::<ContractIoStats>()
+    }
+
+    async fn get_final_state_hashes(&self, _: Option<Slot>) -> RpcResult<Vec<FinalStateHashEntry>> {
+        crate::wrong_api::<Vec<FinalStateHashEntry>>()
+    }
+
     async fn get_cliques(&self) -> RpcResult<Vec<Clique>> {
         crate::wrong_api::<Vec<Clique>>()
     }
@@ -211,6 +289,20 @@ impl MassaRpcServer for API<Private> {
         crate::wrong_api::<Vec<OperationInfo>>()
     }
 
+    async fn get_operation_execution_result(
+        &self,
+        _: Vec<OperationId>,
+    ) -> RpcResult<Vec<Option<OperationExecutionResult>>> {
+        crate::wrong_api::<Vec<Option<OperationExecutionResult>>>()
+    }
+
+    async fn get_operation_coin_flow(
+        &self,
+        _: Vec<OperationId>,
+    ) -> RpcResult<Vec<Option<OperationCoinFlow>>> {
+        crate::wrong_api::<Vec<Option<OperationCoinFlow>>>()
+    }
+
     async fn get_endorsements(&self, _: Vec<EndorsementId>) -> RpcResult<Vec<EndorsementInfo>> {
         crate::wrong_api::<Vec<EndorsementInfo>>()
     }
@@ -223,6 +315,13 @@ impl MassaRpcServer for API<Private> {
         crate::wrong_api::<Option<Block>>()
     }
 
+    async fn check_block_headers(
+        &self,
+        _: Vec<BlockHeaderInput>,
+    ) -> RpcResult<Vec<BlockHeaderCheck>> {
+        crate::wrong_api::<Vec<BlockHeaderCheck>>()
+    }
+
     async fn get_graph_interval(&self, _: TimeInterval) -> RpcResult<Vec<BlockSummary>> {
         crate::wrong_api::<Vec<BlockSummary>>()
     }
@@ -234,10 +333,25 @@ impl MassaRpcServer for API<Private> {
         crate::wrong_api()
     }
 
+    async fn get_authenticated_datastore_entries(
+        &self,
+        _: Vec<DatastoreEntryInput>,
+    ) -> RpcResult<Vec<AuthenticatedDatastoreEntryOutput>> {
+        crate::wrong_api()
+    }
+
     async fn get_addresses(&self, _: Vec<Address>) -> RpcResult<Vec<AddressInfo>> {
         crate::wrong_api::<Vec<AddressInfo>>()
     }
 
+    async fn get_deferred_credits_schedule(
+        &self,
+        _: Address,
+        _: Option<u64>,
+    ) -> RpcResult<Vec<AddressCycleDeferredCredits>> {
+        crate::wrong_api::<Vec<AddressCycleDeferredCredits>>()
+    }
+
     async fn get_addresses_bytecode(&self, _: Vec<AddressFilter>) -> RpcResult<Vec<Vec<u8>>> {
         crate::wrong_api::<Vec<Vec<u8>>>()
     }
@@ -347,12 +461,68 @@ impl MassaRpcServer for API<Private> {
         )
     }
 
+    async fn node_production_blacklist(&self) -> RpcResult<Vec<ProductionBlacklistWindow>> {
+        Ok(self.0.production_blacklist.get_windows())
+    }
+
+    async fn node_add_to_production_blacklist(
+        &self,
+        windows: Vec<ProductionBlacklistWindow>,
+    ) -> RpcResult<()> {
+        let windows_str = format_windows(&windows);
+        self.0
+            .production_blacklist
+            .add_windows(windows)
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        info!("production blacklist windows added: {}", windows_str);
+        Ok(())
+    }
+
+    async fn node_remove_from_production_blacklist(
+        &self,
+        windows: Vec<ProductionBlacklistWindow>,
+    ) -> RpcResult<()> {
+        self.0.production_blacklist.remove_windows(&windows);
+        info!(
+            "production blacklist windows removed: {}",
+            format_windows(&windows)
+        );
+        Ok(())
+    }
+
+    async fn get_operator_revenue(
+        &self,
+        interval: TimeInterval,
+    ) -> RpcResult<OperatorRevenueReport> {
+        Ok(self
+            .0
+            .operator_revenue
+            .get_report(interval.start, interval.end))
+    }
+
+    async fn get_operator_revenue_csv(&self, interval: TimeInterval) -> RpcResult<String> {
+        Ok(self
+            .0
+            .operator_revenue
+            .get_report(interval.start, interval.end)
+            .daily_csv())
+    }
+
     async fn get_openrpc_spec(&self) -> RpcResult<Value> {
         crate::wrong_api::<Value>()
     }
 }
 
 /// Run Search, Create, Read, Update, Delete operation on bootstrap list of IP(s)
+/// Formats production blacklist windows for the audit logs
+fn format_windows(windows: &[ProductionBlacklistWindow]) -> String {
+    windows
+        .iter()
+        .map(|window| window.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 fn run_scrud_operation(
     bootstrap_list_file: PathBuf,
     ips: Vec<IpAddr>,
@@ -0,0 +1,120 @@
+//! On-demand profiling of the node, served by the private API.
+//!
+//! CPU profiles are sampled by `pprof` for a bounded duration, and heap snapshots are dumped by the
+//! jemalloc heap profiler, which requires the node to run with jemalloc as its global allocator.
+//! Both are returned in the protobuf pprof format, readable with `go tool pprof`.
+//! Profiling is only available in nodes built with the `profiling` feature.
+
+use massa_api_exports::error::ApiError;
+use std::time::Duration;
+
+/// Sampling frequency of the CPU profiles, in Hz
+#[cfg(feature = "profiling")]
+const CPU_PROFILE_FREQUENCY: i32 = 99;
+
+/// Samples the CPU usage of the node during `duration`, which cannot exceed `max_duration`.
+/// Only one CPU profile can be taken at a time.
+pub(crate) async fn cpu_profile(
+    duration: Duration,
+    max_duration: Duration,
+) -> Result<Vec<u8>, ApiError> {
+    if duration > max_duration {
+        return Err(ApiError::BadRequest(format!(
+            "CPU profile duration {} ms exceeds the maximum of {} ms",
+            duration.as_millis(),
+            max_duration.as_millis()
+        )));
+    }
+    sample_cpu(duration).await
+}
+
+#[cfg(feature = "profiling")]
+async fn sample_cpu(duration: Duration) -> Result<Vec<u8>, ApiError> {
+    use pprof::protos::Message;
+
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(CPU_PROFILE_FREQUENCY)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(|e| {
+            ApiError::InternalServerError(format!("could not start the CPU profiler: {}", e))
+        })?;
+    tokio::time::sleep(duration).await;
+    let profile = guard
+        .report()
+        .build()
+        .and_then(|report| report.pprof())
+        .map_err(|e| {
+            ApiError::InternalServerError(format!("could not build the CPU profile: {}", e))
+        })?;
+    let mut content = Vec::new();
+    profile.encode(&mut content).map_err(|e| {
+        ApiError::InternalServerError(format!("could not encode the CPU profile: {}", e))
+    })?;
+    Ok(content)
+}
+
+/// Dumps the allocations sampled by the jemalloc heap profiler
+#[cfg(feature = "profiling")]
+pub(crate) async fn heap_snapshot() -> Result<Vec<u8>, ApiError> {
+    let Some(prof_ctl) = jemalloc_pprof::PROF_CTL.as_ref() else {
+        return Err(ApiError::InternalServerError(
+            "jemalloc heap profiling is not enabled".to_string(),
+        ));
+    };
+    let mut prof_ctl = prof_ctl.lock().await;
+    if !prof_ctl.activated() {
+        return Err(ApiError::InternalServerError(
+            "jemalloc heap profiling is not active".to_string(),
+        ));
+    }
+    prof_ctl.dump_pprof().map_err(|e| {
+        ApiError::InternalServerError(format!("could not dump the heap snapshot: {}", e))
+    })
+}
+
+#[cfg(not(feature = "profiling"))]
+async fn sample_cpu(_duration: Duration) -> Result<Vec<u8>, ApiError> {
+    Err(profiling_disabled())
+}
+
+#[cfg(not(feature = "profiling"))]
+pub(crate) async fn heap_snapshot() -> Result<Vec<u8>, ApiError> {
+    Err(profiling_disabled())
+}
+
+#[cfg(not(feature = "profiling"))]
+fn profiling_disabled() -> ApiError {
+    ApiError::BadRequest("the node was built without the `profiling` feature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cpu_profile_duration_bounded() {
+        let res = cpu_profile(Duration::from_secs(2), Duration::from_secs(1)).await;
+        assert!(matches!(res, Err(ApiError::BadRequest(_))));
+    }
+
+    #[cfg(not(feature = "profiling"))]
+    #[tokio::test]
+    async fn test_profiling_disabled() {
+        let res = cpu_profile(Duration::from_millis(10), Duration::from_secs(1)).await;
+        assert!(matches!(res, Err(ApiError::BadRequest(_))));
+        assert!(matches!(
+            heap_snapshot().await,
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[cfg(feature = "profiling")]
+    #[tokio::test]
+    async fn test_cpu_profile() {
+        let profile = cpu_profile(Duration::from_millis(100), Duration::from_secs(1))
+            .await
+            .expect("could not take the CPU profile");
+        assert!(!profile.is_empty());
+    }
+}
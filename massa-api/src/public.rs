@@ -1,35 +1,45 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 #![allow(clippy::too_many_arguments)]
 
+use crate::health::{directory_size, process_rss};
 use crate::{MassaRpcServer, Public, RpcServer, StopHandle, Value, API};
 use async_trait::async_trait;
 use itertools::{izip, Itertools};
-use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
+use jsonrpsee::core::{Error as JsonRpseeError, RpcResult, SubscriptionResult};
+use jsonrpsee::PendingSubscriptionSink;
 use massa_api_exports::{
-    address::{AddressFilter, AddressInfo},
-    block::{BlockInfo, BlockInfoContent, BlockSummary},
+    address::{AddressCycleDeferredCredits, AddressFilter, AddressInfo},
+    block::{BlockHeaderCheck, BlockHeaderInput, BlockInfo, BlockInfoContent, BlockSummary},
     config::APIConfig,
-    datastore::{DatastoreEntryInput, DatastoreEntryOutput},
+    datastore::{AuthenticatedDatastoreEntryOutput, DatastoreEntryInput, DatastoreEntryOutput},
     endorsement::EndorsementInfo,
     error::ApiError,
-    execution::{ExecuteReadOnlyResponse, ReadOnlyBytecodeExecution, ReadOnlyCall, ReadOnlyResult},
-    node::NodeStatus,
-    operation::{OperationInfo, OperationInput},
+    execution::{
+        DatastoreEntryOverride, ExecuteReadOnlyResponse, FinalStateHashEntry,
+        ReadOnlyBytecodeExecution, ReadOnlyCall, ReadOnlyExecutionState, ReadOnlyResult,
+    },
+    node::{NodeHealth, NodeStatus},
+    operation::{OperationInfo, OperationInput, OperationSummary},
     page::{PageRequest, PagedVec},
     slot::SlotAmount,
     TimeInterval,
 };
 use massa_consensus_exports::block_status::DiscardReason;
+use massa_consensus_exports::events::DeepReorgReport;
+use massa_consensus_exports::header_check;
 use massa_consensus_exports::ConsensusController;
 use massa_execution_exports::{
-    ExecutionController, ExecutionQueryRequest, ExecutionQueryRequestItem,
-    ExecutionQueryResponseItem, ExecutionStackElement, ReadOnlyExecutionRequest,
-    ReadOnlyExecutionTarget,
+    ContractIoStats, ExecutionController, ExecutionQueryRequest, ExecutionQueryRequestItem,
+    ExecutionQueryResponseItem, ExecutionStackElement, LedgerEntryOverride, OperationCoinFlow,
+    OperationExecutionResult, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget, SlotMissStats,
+    SlotSequencerStatus,
 };
+use massa_factory_exports::{OperatorRevenueReport, ProductionBlacklistWindow};
 use massa_models::{
     address::Address,
     amount::Amount,
     block::{Block, BlockGraphStatus},
+    block_header::{BlockHeaderDeserializer, SecuredHeader},
     block_id::BlockId,
     clique::Clique,
     composite::PubkeySig,
@@ -46,6 +56,7 @@ use massa_models::{
     output_event::SCOutputEvent,
     prehash::{PreHashMap, PreHashSet},
     secure_share::SecureShareDeserializer,
+    short_id::ToShortId,
     slot::{IndexedSlot, Slot},
     timeslots,
     timeslots::{get_latest_block_slot_at_timestamp, time_range_to_slot_range},
@@ -53,7 +64,7 @@ use massa_models::{
 };
 use massa_pool_exports::PoolController;
 use massa_pos_exports::SelectorController;
-use massa_protocol_exports::{PeerConnectionType, ProtocolConfig, ProtocolController};
+use massa_protocol_exports::{PeerConnectionType, PeerRecord, ProtocolConfig, ProtocolController};
 use massa_serialization::{DeserializeError, Deserializer};
 use massa_storage::Storage;
 use massa_time::MassaTime;
@@ -136,8 +147,14 @@ impl MassaRpcServer for API<Public> {
             bytecode,
             operation_datastore,
             fee,
+            coins,
+            state,
+            datastore_overrides,
         } in reqs
         {
+            let ledger_overrides =
+                read_only_ledger_overrides(datastore_overrides, &self.0.api_settings)?;
+
             let address = if let Some(addr) = address {
                 addr
             } else {
@@ -177,12 +194,14 @@ impl MassaRpcServer for API<Public> {
                 target: ReadOnlyExecutionTarget::BytecodeExecution(bytecode),
                 call_stack: vec![ExecutionStackElement {
                     address,
-                    coins: Default::default(),
+                    coins: coins.unwrap_or_default(),
                     owned_addresses: vec![address],
                     operation_datastore: op_datastore,
                 }],
                 coins: None,
                 fee,
+                state: read_only_execution_state(state),
+                ledger_overrides,
             };
 
             // run
@@ -229,8 +248,13 @@ impl MassaRpcServer for API<Public> {
             caller_address,
             coins,
             fee,
+            state,
+            datastore_overrides,
         } in reqs
         {
+            let ledger_overrides =
+                read_only_ledger_overrides(datastore_overrides, &self.0.api_settings)?;
+
             let caller_address = if let Some(addr) = caller_address {
                 addr
             } else {
@@ -267,6 +291,8 @@ impl MassaRpcServer for API<Public> {
                 ],
                 coins,
                 fee,
+                state: read_only_execution_state(state),
+                ledger_overrides,
             };
 
             // run
@@ -319,6 +345,30 @@ impl MassaRpcServer for API<Public> {
         crate::wrong_api::<()>()
     }
 
+    async fn node_peer_records(&self) -> RpcResult<Vec<PeerRecord>> {
+        crate::wrong_api::<Vec<PeerRecord>>()
+    }
+
+    async fn node_confirm_reorg(&self) -> RpcResult<Option<DeepReorgReport>> {
+        crate::wrong_api::<Option<DeepReorgReport>>()
+    }
+
+    async fn node_cpu_profile(&self, _: MassaTime) -> RpcResult<Vec<u8>> {
+        crate::wrong_api::<Vec<u8>>()
+    }
+
+    async fn node_heap_snapshot(&self) -> RpcResult<Vec<u8>> {
+        crate::wrong_api::<Vec<u8>>()
+    }
+
+    async fn subscribe_connection_events(
+        &self,
+        pending: PendingSubscriptionSink,
+    ) -> SubscriptionResult {
+        pending.reject(ApiError::WrongAPI).await;
+        Ok(())
+    }
+
     /// get status
     async fn get_status(&self) -> RpcResult<NodeStatus> {
         let version = self.0.version;
@@ -356,6 +406,25 @@ impl MassaRpcServer for API<Public> {
             self.0.pool_command_sender.get_endorsement_count(),
         );
 
+        let final_execution_lag = last_slot
+            .and_then(|last_slot| {
+                last_slot
+                    .slots_since(&execution_stats.final_cursor, api_settings.thread_count)
+                    .ok()
+            })
+            .unwrap_or(0);
+        let health = NodeHealth {
+            candidate_execution_lag: execution_stats.candidate_execution_lag,
+            final_execution_lag,
+            consensus_backlog: consensus_stats.pending_block_count,
+            pool_operation_count: pool_stats.0,
+            pool_endorsement_count: pool_stats.1,
+            pool_denunciation_count: self.0.pool_command_sender.get_denunciation_count(),
+            peers_per_category: network_stats.peers_per_category.clone(),
+            db_disk_usage: directory_size(&api_settings.db_path),
+            process_rss: process_rss(),
+        };
+
         let next_slot_result = last_slot
             .unwrap_or_else(|| Slot::new(0, 0))
             .get_next_slot(api_settings.thread_count);
@@ -423,10 +492,51 @@ impl MassaRpcServer for API<Public> {
             pool_stats,
             config,
             current_cycle,
+            health,
         })
     }
 
     /// get cliques
+    async fn get_sequencer_status(&self) -> RpcResult<SlotSequencerStatus> {
+        Ok(self.0.execution_controller.get_sequencer_status())
+    }
+
+    async fn get_slot_miss_stats(&self) -> RpcResult<SlotMissStats> {
+        Ok(self.0.execution_controller.get_slot_miss_stats())
+    }
+
+    async fn get_contract_io_stats(
+        &self,
+        address: Address,
+        window: u64,
+    ) -> RpcResult<ContractIoStats> {
+        self.0
+            .execution_controller
+            .get_contract_io_stats(&address, window)
+            .ok_or_else(|| {
+                ApiError::BadRequest(
+                    "contract IO statistics are not enabled on this node".to_string(),
+                )
+                .into()
+            })
+    }
+
+    async fn get_final_state_hashes(
+        &self,
+        after: Option<Slot>,
+    ) -> RpcResult<Vec<FinalStateHashEntry>> {
+        Ok(self
+            .0
+            .execution_controller
+            .get_final_state_hashes(after)
+            .into_iter()
+            .map(|(slot, final_state_hash)| FinalStateHashEntry {
+                slot,
+                final_state_hash,
+            })
+            .collect())
+    }
+
     async fn get_cliques(&self) -> RpcResult<Vec<Clique>> {
         Ok(self.0.consensus_controller.get_cliques())
     }
@@ -544,11 +654,15 @@ impl MassaRpcServer for API<Public> {
         {
             res.push(OperationInfo {
                 id,
+                short_id: api_cfg
+                    .enable_short_ids
+                    .then(|| id.to_short_id().to_string()),
                 in_pool,
                 is_operation_final,
                 thread: operation
                     .content_creator_address
                     .get_thread(api_cfg.thread_count),
+                summary: Some(OperationSummary::from(&operation.content.op)),
                 operation,
                 in_blocks: in_blocks.into_iter().collect(),
                 op_exec_status,
@@ -559,6 +673,37 @@ impl MassaRpcServer for API<Public> {
         Ok(res)
     }
 
+    async fn get_operation_execution_result(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> RpcResult<Vec<Option<OperationExecutionResult>>> {
+        if operation_ids.len() as u64 > self.0.api_settings.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
+        }
+
+        Ok(self
+            .0
+            .execution_controller
+            .get_operation_execution_result(&operation_ids))
+    }
+
+    async fn get_operation_coin_flow(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> RpcResult<Vec<Option<OperationCoinFlow>>> {
+        if operation_ids.len() as u64 > self.0.api_settings.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
+        }
+
+        Ok(self
+            .0
+            .execution_controller
+            .get_operation_execution_result(&operation_ids)
+            .iter()
+            .map(|result| result.as_ref().map(OperationCoinFlow::from_result))
+            .collect())
+    }
+
     /// get endorsements
     async fn get_endorsements(
         &self,
@@ -668,12 +813,14 @@ impl MassaRpcServer for API<Public> {
             });
         }
         let block_statuses = self.0.consensus_controller.get_block_statuses(&ids);
+        let enable_short_ids = self.0.api_settings.enable_short_ids;
         let res = ids
             .into_iter()
             .zip(blocks)
             .zip(block_statuses)
             .map(|((id, content), graph_status)| BlockInfo {
                 id,
+                short_id: enable_short_ids.then(|| id.to_short_id().to_string()),
                 content: Some(BlockInfoContent {
                     is_final: graph_status == BlockGraphStatus::Final,
                     is_in_blockclique: graph_status == BlockGraphStatus::ActiveInBlockclique,
@@ -707,67 +854,76 @@ impl MassaRpcServer for API<Public> {
         Ok(res)
     }
 
-    /// gets an interval of the block graph from consensus, with time filtering
-    /// time filtering is done consensus-side to prevent communication overhead
-    async fn get_graph_interval(&self, time: TimeInterval) -> RpcResult<Vec<BlockSummary>> {
-        let api_settings = self.0.api_settings.clone();
-
-        // filter blocks from graph_export
-        let time_range_to_slot_range_result = time_range_to_slot_range(
-            api_settings.thread_count,
-            api_settings.t0,
-            api_settings.genesis_timestamp,
-            time.start,
-            time.end,
-        );
-
-        let (start_slot, end_slot) = match time_range_to_slot_range_result {
-            Ok(time_range_to_slot_range) => time_range_to_slot_range,
-            Err(e) => return Err(ApiError::ModelsError(e).into()),
-        };
-
-        let graph = match self
-            .0
-            .consensus_controller
-            .get_block_graph_status(start_slot, end_slot)
-        {
-            Ok(graph) => graph,
-            Err(e) => return Err(ApiError::ConsensusError(e.to_string()).into()),
-        };
-
-        let mut res = Vec::with_capacity(graph.active_blocks.len());
-        let blockclique = graph
-            .max_cliques
-            .iter()
-            .find(|clique| clique.is_blockclique)
-            .ok_or_else(|| ApiError::InconsistencyError("missing blockclique".to_string()))?;
-        for (id, exported_block) in graph.active_blocks.into_iter() {
-            res.push(BlockSummary {
-                id,
-                is_final: exported_block.is_final,
-                is_stale: false,
-                is_in_blockclique: blockclique.block_ids.contains(&id),
-                slot: exported_block.header.content.slot,
-                creator: exported_block.header.content_creator_address,
-                parents: exported_block.header.content.parents,
-            });
+    /// checks block headers against the local draws and network versions only
+    async fn check_block_headers(
+        &self,
+        headers: Vec<BlockHeaderInput>,
+    ) -> RpcResult<Vec<BlockHeaderCheck>> {
+        let api_cfg = &self.0.api_settings;
+        if headers.len() as u64 > api_cfg.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
         }
-        for (id, (reason, (slot, creator, parents))) in graph.discarded_blocks.into_iter() {
-            if reason == DiscardReason::Stale {
-                res.push(BlockSummary {
-                    id,
-                    is_final: false,
-                    is_stale: true,
-                    is_in_blockclique: false,
-                    slot,
-                    creator,
-                    parents,
-                });
+        let header_deserializer = SecureShareDeserializer::new(BlockHeaderDeserializer::new(
+            api_cfg.thread_count,
+            self.0.protocol_config.endorsement_count,
+            self.0.protocol_config.max_denunciations_in_block_header,
+            Some(api_cfg.last_start_period),
+        ));
+
+        let mut res = Vec::with_capacity(headers.len());
+        for header_input in headers {
+            let mut header_serialized = Vec::new();
+            header_serialized.extend(header_input.signature.to_bytes());
+            header_serialized.extend(header_input.creator_public_key.to_bytes());
+            header_serialized.extend(header_input.serialized_content);
+            let (rest, header): (&[u8], SecuredHeader) = header_deserializer
+                .deserialize::<DeserializeError>(&header_serialized)
+                .map_err(|err| {
+                    ApiError::ModelsError(ModelsError::DeserializeError(err.to_string()))
+                })?;
+            if !rest.is_empty() {
+                return Err(ApiError::ModelsError(ModelsError::DeserializeError(
+                    "there is data left after header deserialization".to_owned(),
+                ))
+                .into());
             }
+
+            // the draws are only meaningful if the header was signed by its claimed creator
+            let check = header
+                .verify_signature()
+                .map_err(|err| err.to_string())
+                .and_then(|_| {
+                    header_check::check_header(
+                        &header,
+                        self.0.selector_controller.as_ref(),
+                        &self.0.keypair_factory.mip_store,
+                        api_cfg.thread_count,
+                        api_cfg.t0,
+                        api_cfg.genesis_timestamp,
+                    )
+                    .map_err(|err| err.to_string())
+                });
+            res.push(BlockHeaderCheck {
+                block_id: header.id,
+                slot: header.content.slot,
+                creator_address: header.content_creator_address,
+                is_valid: check.is_ok(),
+                error: check.err(),
+            });
         }
         Ok(res)
     }
 
+    /// gets an interval of the block graph from consensus, with time filtering
+    /// time filtering is done consensus-side to prevent communication overhead
+    async fn get_graph_interval(&self, time: TimeInterval) -> RpcResult<Vec<BlockSummary>> {
+        Ok(get_block_summaries(
+            &*self.0.consensus_controller,
+            &self.0.api_settings,
+            time,
+        )?)
+    }
+
     /// get datastore entries
     async fn get_datastore_entries(
         &self,
@@ -790,6 +946,66 @@ impl MassaRpcServer for API<Public> {
             .collect())
     }
 
+    async fn get_authenticated_datastore_entries(
+        &self,
+        entries: Vec<DatastoreEntryInput>,
+    ) -> RpcResult<Vec<AuthenticatedDatastoreEntryOutput>> {
+        if entries.is_empty() {
+            return Err(ApiError::BadRequest("no arguments specified".to_string()).into());
+        }
+
+        if entries.len() as u64 > self.0.api_settings.max_arguments {
+            return Err(ApiError::BadRequest(format!("too many arguments received. Only a maximum of {} arguments are accepted per request", self.0.api_settings.max_arguments)).into());
+        }
+
+        let queries = entries
+            .into_iter()
+            .map(|input| {
+                ExecutionQueryRequestItem::Authenticated(Box::new(
+                    ExecutionQueryRequestItem::AddressDatastoreValueFinal {
+                        addr: input.address,
+                        key: input.key,
+                    },
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let responses = self
+            .0
+            .execution_controller
+            .query_state(ExecutionQueryRequest { requests: queries })
+            .responses;
+
+        let res: Result<Vec<AuthenticatedDatastoreEntryOutput>, ApiError> = responses
+            .into_iter()
+            .map(|value| match value {
+                Ok(ExecutionQueryResponseItem::Authenticated(result)) => {
+                    let value = match result.value.map(|value| *value) {
+                        Some(ExecutionQueryResponseItem::DatastoreValue(value)) => Some(value),
+                        None => None,
+                        Some(_) => {
+                            return Err(ApiError::InternalServerError(
+                                "unexpected response type".to_string(),
+                            ))
+                        }
+                    };
+                    Ok(AuthenticatedDatastoreEntryOutput {
+                        value,
+                        slot: result.slot,
+                        final_state_hash: result.final_state_hash,
+                        proof: result.proof,
+                    })
+                }
+                Ok(_) => Err(ApiError::InternalServerError(
+                    "unexpected response type".to_string(),
+                )),
+                Err(err) => Err(ApiError::InternalServerError(err.to_string())),
+            })
+            .collect();
+
+        Ok(res?)
+    }
+
     /// get addresses
     async fn get_addresses(&self, addresses: Vec<Address>) -> RpcResult<Vec<AddressInfo>> {
         // get info from storage about which blocks the addresses have created
@@ -903,6 +1119,11 @@ impl MassaRpcServer for API<Public> {
             res.push(AddressInfo {
                 // general address info
                 address,
+                short_id: self
+                    .0
+                    .api_settings
+                    .enable_short_ids
+                    .then(|| address.to_short_id().to_string()),
                 thread: address.get_thread(self.0.api_settings.thread_count),
 
                 // final execution info
@@ -945,6 +1166,29 @@ impl MassaRpcServer for API<Public> {
         Ok(res)
     }
 
+    /// get the deferred credits schedule of an address
+    async fn get_deferred_credits_schedule(
+        &self,
+        address: Address,
+        cycle: Option<u64>,
+    ) -> RpcResult<Vec<AddressCycleDeferredCredits>> {
+        Ok(self
+            .0
+            .execution_controller
+            .get_address_deferred_credits_schedule(&address, cycle)
+            .into_iter()
+            .map(|cycle_credits| AddressCycleDeferredCredits {
+                cycle: cycle_credits.cycle,
+                total: cycle_credits.total,
+                credits: cycle_credits
+                    .credits
+                    .into_iter()
+                    .map(|(slot, amount)| SlotAmount { slot, amount })
+                    .collect(),
+            })
+            .collect())
+    }
+
     /// get addresses bytecode
     async fn get_addresses_bytecode(&self, args: Vec<AddressFilter>) -> RpcResult<Vec<Vec<u8>>> {
         let queries = args
@@ -995,6 +1239,12 @@ impl MassaRpcServer for API<Public> {
         let api_cfg = &self.0.api_settings;
         let mut to_send = self.0.storage.clone_without_refs();
 
+        if !api_cfg.accept_operations {
+            return Err(ApiError::BadRequest(
+                "operations are not accepted by verifier nodes".into(),
+            )
+            .into());
+        }
         if ops.len() as u64 > api_cfg.max_arguments {
             return Err(ApiError::BadRequest("too many arguments".into()).into());
         }
@@ -1093,6 +1343,32 @@ impl MassaRpcServer for API<Public> {
         crate::wrong_api::<()>()
     }
 
+    async fn node_production_blacklist(&self) -> RpcResult<Vec<ProductionBlacklistWindow>> {
+        crate::wrong_api::<Vec<ProductionBlacklistWindow>>()
+    }
+
+    async fn node_add_to_production_blacklist(
+        &self,
+        _: Vec<ProductionBlacklistWindow>,
+    ) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn node_remove_from_production_blacklist(
+        &self,
+        _: Vec<ProductionBlacklistWindow>,
+    ) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn get_operator_revenue(&self, _: TimeInterval) -> RpcResult<OperatorRevenueReport> {
+        crate::wrong_api::<OperatorRevenueReport>()
+    }
+
+    async fn get_operator_revenue_csv(&self, _: TimeInterval) -> RpcResult<String> {
+        crate::wrong_api::<String>()
+    }
+
     /// Get the OpenRPC specification of the node
     async fn get_openrpc_spec(&self) -> RpcResult<Value> {
         let openrpc_spec_path = self.0.api_settings.openrpc_spec_path.clone();
@@ -1190,3 +1466,115 @@ fn check_input_operation(
         .into())
     }
 }
+
+/// Translate the state requested for a read-only execution
+fn read_only_execution_state(
+    state: ReadOnlyExecutionState,
+) -> massa_execution_exports::ReadOnlyExecutionState {
+    match state {
+        ReadOnlyExecutionState::Candidate => {
+            massa_execution_exports::ReadOnlyExecutionState::Candidate
+        }
+        ReadOnlyExecutionState::Final => massa_execution_exports::ReadOnlyExecutionState::Final,
+        ReadOnlyExecutionState::Slot(slot) => {
+            massa_execution_exports::ReadOnlyExecutionState::Slot(slot)
+        }
+    }
+}
+
+/// Check the datastore entries shadowed during a read-only execution, and group them by address
+fn read_only_ledger_overrides(
+    datastore_overrides: Vec<DatastoreEntryOverride>,
+    api_cfg: &APIConfig,
+) -> RpcResult<PreHashMap<Address, LedgerEntryOverride>> {
+    if datastore_overrides.len() as u64 > api_cfg.max_op_datastore_entry_count {
+        return Err(ApiError::BadRequest("too many datastore overrides".into()).into());
+    }
+    let mut ledger_overrides: PreHashMap<Address, LedgerEntryOverride> = PreHashMap::default();
+    for DatastoreEntryOverride {
+        address,
+        key,
+        value,
+    } in datastore_overrides
+    {
+        if key.len() > api_cfg.max_op_datastore_key_length as usize {
+            return Err(ApiError::BadRequest(format!(
+                "datastore override key of address {} is longer than {} bytes",
+                address, api_cfg.max_op_datastore_key_length
+            ))
+            .into());
+        }
+        if value.as_ref().map_or(0, |value| value.len() as u64) > api_cfg.max_datastore_value_length
+        {
+            return Err(ApiError::BadRequest(format!(
+                "datastore override value of address {} is longer than {} bytes",
+                address, api_cfg.max_datastore_value_length
+            ))
+            .into());
+        }
+        ledger_overrides
+            .entry(address)
+            .or_default()
+            .datastore
+            .insert(key, value);
+    }
+    Ok(ledger_overrides)
+}
+
+/// Get the summaries of the blocks of the graph within a time interval
+pub(crate) fn get_block_summaries(
+    consensus_controller: &dyn ConsensusController,
+    api_settings: &APIConfig,
+    time: TimeInterval,
+) -> Result<Vec<BlockSummary>, ApiError> {
+    // filter blocks from graph_export
+    let time_range_to_slot_range_result = time_range_to_slot_range(
+        api_settings.thread_count,
+        api_settings.t0,
+        api_settings.genesis_timestamp,
+        time.start,
+        time.end,
+    );
+
+    let (start_slot, end_slot) = match time_range_to_slot_range_result {
+        Ok(time_range_to_slot_range) => time_range_to_slot_range,
+        Err(e) => return Err(ApiError::ModelsError(e)),
+    };
+
+    let graph = match consensus_controller.get_block_graph_status(start_slot, end_slot) {
+        Ok(graph) => graph,
+        Err(e) => return Err(ApiError::ConsensusError(e.to_string())),
+    };
+
+    let mut res = Vec::with_capacity(graph.active_blocks.len());
+    let blockclique = graph
+        .max_cliques
+        .iter()
+        .find(|clique| clique.is_blockclique)
+        .ok_or_else(|| ApiError::InconsistencyError("missing blockclique".to_string()))?;
+    for (id, exported_block) in graph.active_blocks.into_iter() {
+        res.push(BlockSummary {
+            id,
+            is_final: exported_block.is_final,
+            is_stale: false,
+            is_in_blockclique: blockclique.block_ids.contains(&id),
+            slot: exported_block.header.content.slot,
+            creator: exported_block.header.content_creator_address,
+            parents: exported_block.header.content.parents,
+        });
+    }
+    for (id, (reason, (slot, creator, parents))) in graph.discarded_blocks.into_iter() {
+        if reason == DiscardReason::Stale {
+            res.push(BlockSummary {
+                id,
+                is_final: false,
+                is_stale: true,
+                is_in_blockclique: false,
+                slot,
+                creator,
+                parents,
+            });
+        }
+    }
+    Ok(res)
+}
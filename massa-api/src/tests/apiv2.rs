@@ -7,21 +7,32 @@ use jsonrpsee::{
     rpc_params,
     ws_client::WsClientBuilder,
 };
-use massa_consensus_exports::MockConsensusController;
-use massa_execution_exports::MockExecutionController;
+use massa_api_exports::{block::BlockSummary, page::PageRequest, ApiRequest, TimeInterval};
+use massa_consensus_exports::{
+    block_graph_export::BlockGraphExport,
+    block_status::ExportCompiledBlock,
+    events::{FinalPeriodChange, ForkChoiceEvent},
+    MockConsensusController,
+};
+use massa_execution_exports::{AsyncMessageFilter, MockExecutionController, PendingAsyncMessage};
 use massa_models::{
     address::Address,
+    amount::Amount,
     block::{FilledBlock, SecureShareBlock},
     block_header::BlockHeader,
     block_id::BlockId,
+    clique::Clique,
     config::VERSION,
     operation::SecureShareOperation,
+    prehash::PreHashMap,
     secure_share::SecureShare,
+    slot::Slot,
 };
 use massa_protocol_exports::test_exports::tools::{
-    create_block, create_operation_with_expire_period,
+    create_block, create_block_with_operations, create_operation_with_expire_period,
 };
 use massa_signature::KeyPair;
+use massa_time::MassaTime;
 use serde_json::Value;
 
 use crate::{tests::mock::get_apiv2_server, ApiServer};
@@ -142,6 +153,80 @@ async fn get_largest_stakers() {
     api_handle.stop().await;
 }
 
+#[tokio::test]
+async fn get_async_messages() {
+    let addr: SocketAddr = "[::]:5046".parse().unwrap();
+    let (mut api_server, api_config) = get_apiv2_server(&addr);
+
+    let sender =
+        Address::from_str("AU12dG5xP1RDEB5ocdHkymNVvvSJmUL9BgHwCksDowqmGWxfpm93x").unwrap();
+    let mut exec_ctrl = MockExecutionController::new();
+    exec_ctrl
+        .expect_get_async_messages()
+        .withf(move |filter| filter.sender == Some(sender))
+        .returning(move |_| {
+            (0..3)
+                .map(|emission_index| PendingAsyncMessage {
+                    emission_slot: Slot::new(1, 0),
+                    emission_index,
+                    sender,
+                    destination: sender,
+                    function: "receive".to_string(),
+                    max_gas: 1_000_000,
+                    fee: Amount::zero(),
+                    coins: Amount::zero(),
+                    validity_start: Slot::new(2, 0),
+                    validity_end: Slot::new(10, 0),
+                    has_trigger: false,
+                    can_be_executed: true,
+                })
+                .collect()
+        });
+
+    api_server.0.execution_controller = Box::new(exec_ctrl);
+
+    let api_handle = api_server
+        .serve(&addr, &api_config)
+        .await
+        .expect("failed to start MASSA API V2");
+
+    let uri = Url::parse(&format!(
+        "ws://localhost:{}",
+        addr.to_string().split(':').last().unwrap()
+    ))
+    .unwrap();
+
+    let (tx, rx) = WsTransportClientBuilder::default()
+        .build(uri)
+        .await
+        .unwrap();
+    let client = ClientBuilder::default().build_with_tokio(tx, rx);
+    let filter = AsyncMessageFilter {
+        sender: Some(sender),
+        ..Default::default()
+    };
+    let response: Value = client
+        .request(
+            "get_async_messages",
+            rpc_params![
+                filter,
+                ApiRequest {
+                    page_request: Some(PageRequest {
+                        limit: 2,
+                        offset: 0
+                    })
+                }
+            ],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response["total_count"], 3);
+    assert_eq!(response["content"].as_array().unwrap().len(), 2);
+
+    api_handle.stop().await;
+}
+
 #[tokio::test]
 async fn subscribe_new_blocks() {
     let addr: SocketAddr = "[::]:5033".parse().unwrap();
@@ -333,3 +418,197 @@ async fn subscribe_new_operations() {
 
     api_handle.stop().await;
 }
+
+#[tokio::test]
+async fn subscribe_new_final_periods() {
+    let addr: SocketAddr = "[::]:5051".parse().unwrap();
+    let (mut api_server, api_config) = get_apiv2_server(&addr);
+
+    let uri = Url::parse(&format!(
+        "ws://localhost:{}",
+        addr.to_string().split(':').last().unwrap()
+    ))
+    .unwrap();
+    let (tx, _rx) = tokio::sync::broadcast::channel::<FinalPeriodChange>(10);
+
+    api_server.0.consensus_broadcasts.final_period_sender = tx.clone();
+
+    let api_handle = api_server
+        .serve(&addr, &api_config)
+        .await
+        .expect("failed to start MASSA API V2");
+
+    let client1 = WsClientBuilder::default().build(&uri).await.unwrap();
+    let mut sub1: Subscription<FinalPeriodChange> = client1
+        .subscribe(
+            "subscribe_new_final_periods",
+            rpc_params![],
+            "unsubscribe_new_final_periods",
+        )
+        .await
+        .unwrap();
+
+    let change = FinalPeriodChange {
+        thread: 3,
+        period: 42,
+    };
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let _ = tx.send(change).unwrap();
+    });
+
+    let result = tokio::time::timeout(Duration::from_secs(4), sub1.next())
+        .await
+        .unwrap();
+
+    assert_eq!(result.unwrap().unwrap(), change);
+
+    api_handle.stop().await;
+}
+
+#[tokio::test]
+async fn subscribe_fork_choice_events() {
+    let addr: SocketAddr = "[::]:5054".parse().unwrap();
+    let (mut api_server, api_config) = get_apiv2_server(&addr);
+
+    let uri = Url::parse(&format!(
+        "ws://localhost:{}",
+        addr.to_string().split(':').last().unwrap()
+    ))
+    .unwrap();
+    let (tx, _rx) = tokio::sync::broadcast::channel::<ForkChoiceEvent>(10);
+
+    api_server.0.consensus_broadcasts.fork_choice_sender = tx.clone();
+
+    let api_handle = api_server
+        .serve(&addr, &api_config)
+        .await
+        .expect("failed to start MASSA API V2");
+
+    let client1 = WsClientBuilder::default().build(&uri).await.unwrap();
+    let mut sub1: Subscription<ForkChoiceEvent> = client1
+        .subscribe(
+            "subscribe_fork_choice_events",
+            rpc_params![],
+            "unsubscribe_fork_choice_events",
+        )
+        .await
+        .unwrap();
+
+    let block = create_block(&KeyPair::generate(0).unwrap());
+    let events = vec![
+        ForkChoiceEvent::Reorg {
+            depth: 1,
+            removed_block_ids: vec![block.id],
+        },
+        ForkChoiceEvent::Final {
+            block_id: block.id,
+            slot: block.content.header.content.slot,
+        },
+    ];
+    let sent = events.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        for event in sent {
+            let _ = tx.send(event).unwrap();
+        }
+    });
+
+    for event in events {
+        let result = tokio::time::timeout(Duration::from_secs(4), sub1.next())
+            .await
+            .unwrap();
+        assert_eq!(result.unwrap().unwrap(), event);
+    }
+
+    api_handle.stop().await;
+}
+
+#[tokio::test]
+async fn subscribe_graph_interval() {
+    let addr: SocketAddr = "[::]:5049".parse().unwrap();
+    let (mut api_server, api_config) = get_apiv2_server(&addr);
+
+    let keypair = KeyPair::generate(0).unwrap();
+    let blocks: Vec<SecureShareBlock> = [Slot::new(3, 1), Slot::new(1, 0), Slot::new(2, 0)]
+        .into_iter()
+        .map(|slot| create_block_with_operations(&keypair, slot, vec![]))
+        .collect();
+
+    let mut consensus_ctrl = MockConsensusController::new();
+    let graph_blocks = blocks.clone();
+    consensus_ctrl
+        .expect_get_block_graph_status()
+        .returning(move |_start, _end| {
+            let active_blocks = graph_blocks
+                .iter()
+                .map(|block| {
+                    (
+                        block.id,
+                        ExportCompiledBlock {
+                            header: block.content.header.clone(),
+                            children: vec![],
+                            is_final: true,
+                        },
+                    )
+                })
+                .collect();
+            Ok(BlockGraphExport {
+                genesis_blocks: vec![],
+                active_blocks,
+                discarded_blocks: PreHashMap::default(),
+                best_parents: vec![],
+                latest_final_blocks_periods: vec![],
+                gi_head: PreHashMap::default(),
+                max_cliques: vec![Clique::default()],
+            })
+        });
+    api_server.0.consensus_controller = Box::new(consensus_ctrl);
+
+    let api_handle = api_server
+        .serve(&addr, &api_config)
+        .await
+        .expect("failed to start MASSA API V2");
+
+    let uri = Url::parse(&format!(
+        "ws://localhost:{}",
+        addr.to_string().split(':').last().unwrap()
+    ))
+    .unwrap();
+
+    let client = WsClientBuilder::default().build(&uri).await.unwrap();
+    let mut sub: Subscription<BlockSummary> = client
+        .subscribe(
+            "subscribe_graph_interval",
+            rpc_params![TimeInterval {
+                start: Some(MassaTime::from_millis(0)),
+                end: Some(MassaTime::now()),
+            }],
+            "unsubscribe_graph_interval",
+        )
+        .await
+        .unwrap();
+
+    // summaries are received one by one, ordered by slot
+    let mut slots = Vec::new();
+    for _ in 0..blocks.len() {
+        let summary = tokio::time::timeout(Duration::from_secs(4), sub.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        slots.push(summary.slot);
+    }
+    assert_eq!(
+        slots,
+        vec![Slot::new(1, 0), Slot::new(2, 0), Slot::new(3, 1)]
+    );
+
+    // the subscription is closed once the whole interval is sent
+    let end = tokio::time::timeout(Duration::from_secs(4), sub.next())
+        .await
+        .unwrap();
+    assert!(end.is_none());
+
+    api_handle.stop().await;
+}
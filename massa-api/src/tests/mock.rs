@@ -2,12 +2,13 @@
 //!
 //!
 
-use std::{collections::HashMap, net::SocketAddr};
+use std::{collections::HashMap, net::SocketAddr, path::PathBuf};
 
 use massa_api_exports::config::APIConfig;
 use massa_consensus_exports::{ConsensusBroadcasts, MockConsensusController};
 use massa_execution_exports::{GasCosts, MockExecutionController};
 use massa_models::{
+    address::AddressFormat,
     config::{
         BASE_OPERATION_GAS_COST, ENDORSEMENT_COUNT, GENESIS_TIMESTAMP, MAX_DATASTORE_VALUE_LENGTH,
         MAX_FUNCTION_NAME_LENGTH, MAX_GAS_PER_BLOCK, MAX_MESSAGE_SIZE,
@@ -19,7 +20,10 @@ use massa_models::{
 };
 use massa_pool_exports::{MockPoolController, PoolBroadcasts};
 use massa_pos_exports::MockSelectorController;
-use massa_protocol_exports::{MockProtocolController, PeerCategoryInfo, ProtocolConfig};
+use massa_protocol_exports::{
+    BandwidthBudget, MessageRateLimit, MessageRateLimits, MockProtocolController, PeerCategoryInfo,
+    ProtocolConfig,
+};
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use massa_versioning::versioning::{MipStatsConfig, MipStore};
@@ -50,6 +54,12 @@ pub(crate) fn get_apiv2_server(addr: &SocketAddr) -> (API<ApiV2>, APIConfig) {
         ping_interval: MassaTime::from_millis(60000),
         enable_http: true,
         enable_ws: true,
+        enable_short_ids: false,
+        accept_operations: true,
+        db_path: PathBuf::new(),
+        max_events_response_size: 1_048_576,
+        max_block_summaries_response_size: 1_048_576,
+        max_cpu_profile_duration: MassaTime::from_millis(60000),
         max_datastore_value_length: MAX_DATASTORE_VALUE_LENGTH,
         max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
         max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
@@ -65,6 +75,7 @@ pub(crate) fn get_apiv2_server(addr: &SocketAddr) -> (API<ApiV2>, APIConfig) {
         t0: T0,
         periods_per_cycle: PERIODS_PER_CYCLE,
         last_start_period: 0,
+        address_format: AddressFormat::Base58Check,
     };
 
     // let shared_storage: massa_storage::Storage = massa_storage::Storage::create_root();
@@ -88,6 +99,8 @@ pub(crate) fn get_apiv2_server(addr: &SocketAddr) -> (API<ApiV2>, APIConfig) {
         block_header_sender: broadcast::channel(100).0,
         block_sender: broadcast::channel(100).0,
         filled_block_sender: broadcast::channel(100).0,
+        final_period_sender: broadcast::channel(100).0,
+        fork_choice_sender: broadcast::channel(100).0,
     };
 
     let api = API::<ApiV2>::new(
@@ -123,6 +136,12 @@ pub(crate) fn start_public_api(addr: SocketAddr) -> (API<Public>, APIConfig) {
         ping_interval: MassaTime::from_millis(60000),
         enable_http: true,
         enable_ws: true,
+        enable_short_ids: false,
+        accept_operations: true,
+        db_path: PathBuf::new(),
+        max_events_response_size: 1_048_576,
+        max_block_summaries_response_size: 1_048_576,
+        max_cpu_profile_duration: MassaTime::from_millis(60000),
         max_datastore_value_length: MAX_DATASTORE_VALUE_LENGTH,
         max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
         max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
@@ -138,6 +157,7 @@ pub(crate) fn start_public_api(addr: SocketAddr) -> (API<Public>, APIConfig) {
         t0: T0,
         periods_per_cycle: PERIODS_PER_CYCLE,
         last_start_period: 0,
+        address_format: AddressFormat::Base58Check,
     };
 
     let shared_storage: massa_storage::Storage = massa_storage::Storage::create_root();
@@ -163,18 +183,20 @@ pub(crate) fn start_public_api(addr: SocketAddr) -> (API<Public>, APIConfig) {
         Box::new(pool_ctrl),
         Box::new(protocol_controller),
         ProtocolConfig {
-            keypair_file: NamedTempFile::new()
-                .expect("cannot create temp file")
-                .path()
-                .to_path_buf(),
             ask_block_timeout: MassaTime::from_millis(500),
             max_blocks_kept_for_propagation: 300,
             max_block_propagation_time: MassaTime::from_millis(40000),
             block_propagation_tick: MassaTime::from_millis(1000),
+            compact_block_relay: false,
+            block_body_chunking: false,
+            block_body_data_chunks: 4,
+            block_body_parity_chunks: 2,
+            block_body_chunking_min_missing_operations: 16,
             max_known_blocks_size: 100,
             max_node_known_blocks_size: 100,
             max_node_wanted_blocks_size: 100,
             max_simultaneous_ask_blocks_per_node: 10,
+            max_block_bodies_in_flight: 64,
             max_send_wait: MassaTime::from_millis(100),
             max_known_ops_size: 1000,
             max_node_known_ops_size: 1000,
@@ -185,6 +207,11 @@ pub(crate) fn start_public_api(addr: SocketAddr) -> (API<Public>, APIConfig) {
             max_operation_storage_time: MassaTime::from_millis(60000),
             operation_batch_proc_period: MassaTime::from_millis(200),
             asked_operations_buffer_capacity: 10000,
+            max_ask_operations_size_per_peer: 1_000_000,
+            peer_reputation_deprioritize_threshold: 200,
+            peer_reputation_ban_threshold: 500,
+            peer_reputation_recovery_period: MassaTime::from_millis(1000),
+            broadcast_connection_events_channel_capacity: 128,
             operation_announcement_interval: MassaTime::from_millis(150),
             max_operations_per_message: 1024,
             max_operations_per_block: 5000,
@@ -196,7 +223,17 @@ pub(crate) fn start_public_api(addr: SocketAddr) -> (API<Public>, APIConfig) {
             t0: MassaTime::from_millis(16000),
             max_ops_kept_for_propagation: 10000,
             max_operations_propagation_time: MassaTime::from_millis(30000),
+            operation_inventory_digest_interval: MassaTime::from_millis(2000),
+            max_operations_per_inventory_digest: 0,
+            topic_anti_entropy_interval: MassaTime::from_millis(2000),
+            max_topic_payload_size: 65536,
+            max_topic_items_per_message: 1024,
+            operation_sig_verification_threads: 2,
+            operation_sig_verification_batch_size: 64,
+            handshake_final_state_hashes: 16,
+            refuse_divergent_peers: false,
             max_endorsements_propagation_time: MassaTime::from_millis(60000),
+            max_future_slot_time: MassaTime::from_millis(64000),
             initial_peers: NamedTempFile::new()
                 .expect("cannot create temp file")
                 .path()
@@ -214,14 +251,34 @@ pub(crate) fn start_public_api(addr: SocketAddr) -> (API<Public>, APIConfig) {
             max_size_channel_network_to_endorsement_handler: 1000,
             max_size_channel_network_to_operation_handler: 10000,
             max_size_channel_network_to_peer_handler: 1000,
+            max_size_channel_commands_topics: 1000,
+            max_size_channel_network_to_topic_handler: 1000,
             max_size_channel_commands_peer_testers: 10000,
             max_size_channel_commands_peers: 300,
             max_message_size: MAX_MESSAGE_SIZE as usize,
+            message_compression: false,
+            message_compression_threshold: 4096,
+            message_compression_level: 3,
+            max_decompressed_message_size: 10_485_760,
+            message_rate_limits: MessageRateLimits {
+                block_header: MessageRateLimit { rate: 0, burst: 0 },
+                operation_announcement: MessageRateLimit { rate: 0, burst: 0 },
+                ask_for_operations: MessageRateLimit { rate: 0, burst: 0 },
+            },
+            peers_categories_message_rate_limits: HashMap::new(),
+            bandwidth_budget: BandwidthBudget {
+                upload: 0,
+                download: 0,
+            },
+            peers_categories_bandwidth_budgets: HashMap::new(),
+            socks5_proxy: None,
             endorsement_count: ENDORSEMENT_COUNT,
             max_size_value_datastore: 1_000_000,
             max_size_function_name: u16::MAX,
             max_size_call_sc_parameter: 10_000_000,
             max_denunciations_in_block_header: 100,
+            max_block_header_denunciations_size: 65_536,
+            max_block_header_size: 131_072,
             max_op_datastore_entry_count: 100000,
             max_op_datastore_key_length: u8::MAX,
             max_op_datastore_value_length: 1000000,
@@ -249,6 +306,19 @@ pub(crate) fn start_public_api(addr: SocketAddr) -> (API<Public>, APIConfig) {
             try_connection_timer_same_peer: MassaTime::from_millis(1000),
             test_oldest_peer_cooldown: MassaTime::from_millis(720000),
             rate_limit: 1024 * 1024 * 2,
+            traffic_capture_path: None,
+            operation_seen_cache_path: None,
+            operation_seen_cache_max_age: MassaTime::from_millis(600000),
+            peer_store_path: None,
+            peer_store_flush_interval: MassaTime::from_millis(60000),
+            peer_store_max_age: MassaTime::from_millis(2592000000),
+            port_mapping: false,
+            port_mapping_lease_duration: MassaTime::from_millis(3600000),
+            port_mapping_timeout: MassaTime::from_millis(3000),
+            outbound_max_per_group: 2,
+            outbound_rotation_interval: MassaTime::from_millis(1800000),
+            outbound_rotation_percent: 10,
+            outbound_asn_map_path: None,
         },
         *VERSION,
         NodeId::new(keypair.get_public_key()),
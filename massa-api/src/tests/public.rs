@@ -13,11 +13,14 @@ use jsonrpsee::{
     rpc_params,
 };
 use massa_api_exports::{
-    address::{AddressFilter, AddressInfo},
-    block::{BlockInfo, BlockSummary},
-    datastore::{DatastoreEntryInput, DatastoreEntryOutput},
+    address::{AddressCycleDeferredCredits, AddressFilter, AddressInfo},
+    block::{BlockHeaderCheck, BlockHeaderInput, BlockInfo, BlockSummary},
+    datastore::{AuthenticatedDatastoreEntryOutput, DatastoreEntryInput, DatastoreEntryOutput},
     endorsement::EndorsementInfo,
-    execution::{ExecuteReadOnlyResponse, ReadOnlyBytecodeExecution, ReadOnlyCall},
+    execution::{
+        DatastoreEntryOverride, ExecuteReadOnlyResponse, FinalStateHashEntry,
+        ReadOnlyBytecodeExecution, ReadOnlyCall, ReadOnlyExecutionState,
+    },
     operation::{OperationInfo, OperationInput},
     TimeInterval,
 };
@@ -30,21 +33,28 @@ use massa_pos_exports::MockSelectorController;
 
 use crate::{tests::mock::start_public_api, RpcServer};
 use massa_execution_exports::{
-    ExecutionAddressInfo, ExecutionQueryResponse, ExecutionQueryResponseItem,
-    MockExecutionController, ReadOnlyExecutionOutput,
+    AuthenticatedQueryResult, CoinMovementKind, ContractIoStats, CycleDeferredCredits,
+    DatastoreKeyIoStats, ExecutionAddressInfo, ExecutionQueryResponse, ExecutionQueryResponseItem,
+    MockExecutionController, OperationCoinFlow, OperationCoinMovement, OperationExecutionResult,
+    ReadOnlyExecutionOutput, SlotMissStats, SlotSequencerStatus, ThreadSlotMissStats,
 };
+use massa_hash::Hash;
 use massa_models::{
     address::Address,
     amount::Amount,
     block::{Block, BlockGraphStatus},
+    block_header::{BlockHeader, BlockHeaderSerializer},
+    block_id::BlockId,
     bytecode::Bytecode,
     clique::Clique,
+    config::THREAD_COUNT,
     endorsement::EndorsementId,
     execution::EventFilter,
     node::NodeId,
     operation::OperationId,
     output_event::SCOutputEvent,
     prehash::{CapacityAllocator, PreHashMap},
+    secure_share::SecureShareContent,
     slot::Slot,
     stats::{ConsensusStats, ExecutionStats, NetworkStats},
 };
@@ -74,6 +84,10 @@ async fn get_status() {
         final_executed_operations_count: 0,
         active_cursor: Slot::new(0, 0),
         final_cursor: Slot::new(0, 0),
+        candidate_execution_lag: 0,
+        final_execution_backlog: 0,
+        speculative_execution_stale: false,
+        candidate_execution_enabled: true,
     });
 
     let mut consensus_ctrl = MockConsensusController::new();
@@ -84,6 +98,7 @@ async fn get_status() {
             final_block_count: 50,
             stale_block_count: 40,
             clique_count: 30,
+            pending_block_count: 3,
         })
     });
 
@@ -96,6 +111,7 @@ async fn get_status() {
                 known_peer_count: 6,
                 banned_peer_count: 0,
                 active_node_count: 15,
+                peers_per_category: BTreeMap::from([("default".to_string(), 15)]),
             },
             HashMap::new(),
         ))
@@ -104,6 +120,7 @@ async fn get_status() {
     let mut pool_ctrl = MockPoolController::new();
     pool_ctrl.expect_get_operation_count().returning(|| 1024);
     pool_ctrl.expect_get_endorsement_count().returning(|| 2048);
+    pool_ctrl.expect_get_denunciation_count().returning(|| 1);
 
     api_public.0.pool_command_sender = Box::new(pool_ctrl);
     api_public.0.protocol_controller = Box::new(protocol_ctrl);
@@ -128,6 +145,9 @@ async fn get_status() {
     assert_eq!(response.network_stats.in_connection_count, 10);
     assert_eq!(response.network_stats.out_connection_count, 5);
     assert_eq!(response.config.thread_count, 32);
+    assert_eq!(response.health.consensus_backlog, 3);
+    assert_eq!(response.health.pool_denunciation_count, 1);
+    assert_eq!(response.health.peers_per_category.get("default"), Some(&15));
 
     api_public_handle.stop().await;
 }
@@ -208,6 +228,382 @@ async fn get_operations() {
     api_public_handle.stop().await;
 }
 
+#[tokio::test]
+async fn get_sequencer_status() {
+    let addr: SocketAddr = "[::]:5044".parse().unwrap();
+    let (mut api_public, config) = start_public_api(addr);
+
+    let mut exec_ctrl = MockExecutionController::new();
+    exec_ctrl
+        .expect_get_sequencer_status()
+        .returning(|| SlotSequencerStatus {
+            sequence_length: 4,
+            latest_consensus_final_slots: vec![Slot::new(2, 0), Slot::new(1, 1)],
+            latest_execution_final_slot: Slot::new(1, 1),
+            latest_executed_final_slot: Slot::new(1, 0),
+            latest_executed_candidate_slot: Slot::new(2, 1),
+        });
+    api_public.0.execution_controller = Box::new(exec_ctrl);
+
+    let api_public_handle = api_public
+        .serve(&addr, &config)
+        .await
+        .expect("failed to start PUBLIC API");
+
+    let client = HttpClientBuilder::default()
+        .build(format!(
+            "http://localhost:{}",
+            addr.to_string().split(':').last().unwrap()
+        ))
+        .unwrap();
+    let response: SlotSequencerStatus = client
+        .request("get_sequencer_status", rpc_params![])
+        .await
+        .unwrap();
+
+    assert_eq!(response.sequence_length, 4);
+    assert_eq!(response.latest_executed_final_slot, Slot::new(1, 0));
+
+    api_public_handle.stop().await;
+}
+
+#[tokio::test]
+async fn get_slot_miss_stats() {
+    let addr: SocketAddr = "[::]:5045".parse().unwrap();
+    let (mut api_public, config) = start_public_api(addr);
+
+    let mut exec_ctrl = MockExecutionController::new();
+    exec_ctrl
+        .expect_get_slot_miss_stats()
+        .returning(|| SlotMissStats {
+            time_window_start: MassaTime::from_millis(1000),
+            time_window_end: MassaTime::from_millis(61000),
+            threads: vec![
+                ThreadSlotMissStats {
+                    block_count: 3,
+                    miss_count: 1,
+                },
+                ThreadSlotMissStats {
+                    block_count: 4,
+                    miss_count: 0,
+                },
+            ],
+        });
+    api_public.0.execution_controller = Box::new(exec_ctrl);
+
+    let api_public_handle = api_public
+        .serve(&addr, &config)
+        .await
+        .expect("failed to start PUBLIC API");
+
+    let client = HttpClientBuilder::default()
+        .build(format!(
+            "http://localhost:{}",
+            addr.to_string().split(':').last().unwrap()
+        ))
+        .unwrap();
+    let response: SlotMissStats = client
+        .request("get_slot_miss_stats", rpc_params![])
+        .await
+        .unwrap();
+
+    assert_eq!(response.threads.len(), 2);
+    assert_eq!(response.threads[0].miss_count, 1);
+    assert_eq!(response.threads[1].block_count, 4);
+
+    api_public_handle.stop().await;
+}
+
+#[tokio::test]
+async fn get_contract_io_stats() {
+    let addr: SocketAddr = "[::]:5048".parse().unwrap();
+    let (mut api_public, config) = start_public_api(addr);
+
+    let mut exec_ctrl = MockExecutionController::new();
+    exec_ctrl
+        .expect_get_contract_io_stats()
+        .returning(|address, window| {
+            (window > 0).then(|| ContractIoStats {
+                address: *address,
+                slot_count: window,
+                call_count: 2,
+                read_count: 5,
+                write_count: 1,
+                hot_keys: vec![DatastoreKeyIoStats {
+                    key: b"balance".to_vec(),
+                    read_count: 3,
+                    write_count: 1,
+                }],
+            })
+        });
+    api_public.0.execution_controller = Box::new(exec_ctrl);
+
+    let api_public_handle = api_public
+        .serve(&addr, &config)
+        .await
+        .expect("failed to start PUBLIC API");
+
+    let client = HttpClientBuilder::default()
+        .build(format!(
+            "http://localhost:{}",
+            addr.to_string().split(':').last().unwrap()
+        ))
+        .unwrap();
+
+    let sc_address =
+        Address::from_str("AS12htxRWiEm8jDJpJptr6cwEhWNcCSFWstN1MLSa96DDkVM9Y42G").unwrap();
+    let response: ContractIoStats = client
+        .request("get_contract_io_stats", rpc_params![sc_address, 10])
+        .await
+        .unwrap();
+    assert_eq!(response.address, sc_address);
+    assert_eq!(response.slot_count, 10);
+    assert_eq!(response.hot_keys[0].key, b"balance".to_vec());
+
+    // statistics not recorded
+    let response: Result<ContractIoStats, Error> = client
+        .request("get_contract_io_stats", rpc_params![sc_address, 0])
+        .await;
+    assert!(response.is_err());
+
+    api_public_handle.stop().await;
+}
+
+#[tokio::test]
+async fn get_final_state_hashes() {
+    let addr: SocketAddr = "[::]:5053".parse().unwrap();
+    let (mut api_public, config) = start_public_api(addr);
+
+    let hashes: Vec<(Slot, Hash)> = (1..=3)
+        .map(|period| {
+            (
+                Slot::new(period, 0),
+                Hash::compute_from(&period.to_be_bytes()),
+            )
+        })
+        .collect();
+    let mut exec_ctrl = MockExecutionController::new();
+    let returned_hashes = hashes.clone();
+    exec_ctrl
+        .expect_get_final_state_hashes()
+        .returning(move |after| {
+            returned_hashes
+                .iter()
+                .filter(|(slot, _)| after.map_or(true, |after| *slot > after))
+                .copied()
+                .collect()
+        });
+    api_public.0.execution_controller = Box::new(exec_ctrl);
+
+    let api_public_handle = api_public
+        .serve(&addr, &config)
+        .await
+        .expect("failed to start PUBLIC API");
+
+    let client = HttpClientBuilder::default()
+        .build(format!(
+            "http://localhost:{}",
+            addr.to_string().split(':').last().unwrap()
+        ))
+        .unwrap();
+
+    let response: Vec<FinalStateHashEntry> = client
+        .request("get_final_state_hashes", rpc_params![None::<Slot>])
+        .await
+        .unwrap();
+    assert_eq!(response.len(), 3);
+    assert_eq!(response[0].final_state_hash, hashes[0].1);
+
+    let response: Vec<FinalStateHashEntry> = client
+        .request("get_final_state_hashes", rpc_params![Slot::new(2, 0)])
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        vec![FinalStateHashEntry {
+            slot: Slot::new(3, 0),
+            final_state_hash: hashes[2].1,
+        }]
+    );
+
+    api_public_handle.stop().await;
+}
+
+#[tokio::test]
+async fn get_operation_execution_result() {
+    let addr: SocketAddr = "[::]:5043".parse().unwrap();
+    let (mut api_public, config) = start_public_api(addr);
+    let keypair = KeyPair::generate(0).unwrap();
+    let op = create_operation_with_expire_period(&keypair, 500000);
+    let op_id = op.id;
+
+    let mut exec_ctrl = MockExecutionController::new();
+    exec_ctrl
+        .expect_get_operation_execution_result()
+        .returning(move |ids| {
+            ids.iter()
+                .map(|id| {
+                    (*id == op_id).then(|| OperationExecutionResult {
+                        operation_id: *id,
+                        slot: Slot::new(1, 0),
+                        block_id: None,
+                        success: false,
+                        error: Some("not enough coins".to_string()),
+                        gas_used: 100,
+                        events: vec![],
+                        coin_movements: vec![],
+                        call_stats: Default::default(),
+                        is_final: true,
+                    })
+                })
+                .collect()
+        });
+
+    api_public.0.execution_controller = Box::new(exec_ctrl);
+
+    let api_public_handle = api_public
+        .serve(&addr, &config)
+        .await
+        .expect("failed to start PUBLIC API");
+
+    let client = HttpClientBuilder::default()
+        .build(format!(
+            "http://localhost:{}",
+            addr.to_string().split(':').last().unwrap()
+        ))
+        .unwrap();
+    let params = rpc_params![vec![
+        OperationId::from_str("O1q4CBcuYo8YANEV34W4JRWVHrzcYns19VJfyAB7jT4qfitAnMC").unwrap(),
+        op.id
+    ]];
+    let response: Vec<Option<OperationExecutionResult>> = client
+        .request("get_operation_execution_result", params)
+        .await
+        .unwrap();
+
+    assert_eq!(response.len(), 2);
+    assert!(response[0].is_none());
+    let result = response[1].as_ref().unwrap();
+    assert_eq!(result.operation_id, op.id);
+    assert!(!result.success);
+    assert_eq!(result.error.as_deref(), Some("not enough coins"));
+
+    api_public_handle.stop().await;
+}
+
+#[tokio::test]
+async fn get_operation_coin_flow() {
+    let addr: SocketAddr = "[::]:5050".parse().unwrap();
+    let (mut api_public, config) = start_public_api(addr);
+    let keypair = KeyPair::generate(0).unwrap();
+    let op = create_operation_with_expire_period(&keypair, 500000);
+    let op_id = op.id;
+    let sender = Address::from_public_key(&keypair.get_public_key());
+    let sc_address =
+        Address::from_str("AS12htxRWiEm8jDJpJptr6cwEhWNcCSFWstN1MLSa96DDkVM9Y42G").unwrap();
+
+    let mut exec_ctrl = MockExecutionController::new();
+    exec_ctrl
+        .expect_get_operation_execution_result()
+        .returning(move |ids| {
+            let movement = |from, to, amount: &str, kind, initiator| OperationCoinMovement {
+                from,
+                to,
+                amount: Amount::from_str(amount).unwrap(),
+                kind,
+                initiator,
+            };
+            ids.iter()
+                .map(|id| {
+                    (*id == op_id).then(|| OperationExecutionResult {
+                        operation_id: *id,
+                        slot: Slot::new(1, 0),
+                        block_id: None,
+                        success: true,
+                        error: None,
+                        gas_used: 100,
+                        events: vec![],
+                        coin_movements: vec![
+                            movement(Some(sender), None, "0.01", CoinMovementKind::Fee, None),
+                            movement(
+                                Some(sender),
+                                Some(sc_address),
+                                "2",
+                                CoinMovementKind::Transfer,
+                                Some(sender),
+                            ),
+                            movement(
+                                Some(sc_address),
+                                Some(sender),
+                                "0.5",
+                                CoinMovementKind::Transfer,
+                                Some(sc_address),
+                            ),
+                            movement(
+                                Some(sc_address),
+                                Some(sender),
+                                "0.5",
+                                CoinMovementKind::Transfer,
+                                Some(sc_address),
+                            ),
+                            movement(
+                                Some(sc_address),
+                                None,
+                                "0.1",
+                                CoinMovementKind::Storage,
+                                Some(sc_address),
+                            ),
+                        ],
+                        call_stats: Default::default(),
+                        is_final: true,
+                    })
+                })
+                .collect()
+        });
+
+    api_public.0.execution_controller = Box::new(exec_ctrl);
+
+    let api_public_handle = api_public
+        .serve(&addr, &config)
+        .await
+        .expect("failed to start PUBLIC API");
+
+    let client = HttpClientBuilder::default()
+        .build(format!(
+            "http://localhost:{}",
+            addr.to_string().split(':').last().unwrap()
+        ))
+        .unwrap();
+    let params = rpc_params![vec![
+        OperationId::from_str("O1q4CBcuYo8YANEV34W4JRWVHrzcYns19VJfyAB7jT4qfitAnMC").unwrap(),
+        op.id
+    ]];
+    let response: Vec<Option<OperationCoinFlow>> = client
+        .request("get_operation_coin_flow", params)
+        .await
+        .unwrap();
+
+    assert_eq!(response.len(), 2);
+    assert!(response[0].is_none());
+    let flow = response[1].as_ref().unwrap();
+    assert_eq!(flow.operation_id, op.id);
+    // the two identical refunds are merged into a single edge
+    assert_eq!(flow.edges.len(), 4);
+    assert_eq!(flow.edges[2].amount, Amount::from_str("1").unwrap());
+    assert_eq!(flow.edges[2].count, 2);
+    assert_eq!(flow.created, Amount::zero());
+    assert_eq!(flow.destroyed, Amount::from_str("0.11").unwrap());
+    let sender_balance = flow
+        .balances
+        .iter()
+        .find(|balance| balance.address == sender)
+        .unwrap();
+    assert_eq!(sender_balance.credited, Amount::from_str("1").unwrap());
+    assert_eq!(sender_balance.debited, Amount::from_str("2.01").unwrap());
+
+    api_public_handle.stop().await;
+}
+
 #[tokio::test]
 async fn get_endorsements() {
     let addr: SocketAddr = "[::]:5005".parse().unwrap();
@@ -360,6 +756,81 @@ async fn get_blockclique_block_by_slot() {
     api_public_handle.stop().await;
 }
 
+#[tokio::test]
+async fn check_block_headers() {
+    let addr: SocketAddr = "[::]:5018".parse().unwrap();
+    let (mut api_public, config) = start_public_api(addr);
+
+    let producer_keypair = KeyPair::generate(0).unwrap();
+    let producer = Address::from_public_key(&producer_keypair.get_public_key());
+    let mut selector_ctrl = MockSelectorController::new();
+    selector_ctrl
+        .expect_get_producer()
+        .returning(move |_| Ok(producer));
+    api_public.0.selector_controller = Box::new(selector_ctrl);
+
+    let api_public_handle = api_public
+        .serve(&addr, &config)
+        .await
+        .expect("failed to start PUBLIC API");
+
+    let client = HttpClientBuilder::default()
+        .build(format!(
+            "http://localhost:{}",
+            addr.to_string().split(':').last().unwrap()
+        ))
+        .unwrap();
+
+    let create_header = |keypair: &KeyPair| {
+        BlockHeader::new_verifiable::<BlockHeaderSerializer, BlockId>(
+            BlockHeader {
+                current_version: 0,
+                announced_version: None,
+                slot: Slot::new(1, 0),
+                parents: (0..THREAD_COUNT)
+                    .map(|i| BlockId::generate_from_hash(Hash::compute_from(&[i])))
+                    .collect(),
+                operation_merkle_root: Hash::compute_from(&Vec::new()),
+                endorsements: Vec::new(),
+                denunciations: Vec::new(),
+                extensions: Vec::new(),
+            },
+            BlockHeaderSerializer::new(),
+            keypair,
+        )
+        .unwrap()
+    };
+    let other_keypair = KeyPair::generate(0).unwrap();
+    let inputs: Vec<BlockHeaderInput> = [&producer_keypair, &other_keypair]
+        .into_iter()
+        .map(|keypair| {
+            let header = create_header(keypair);
+            BlockHeaderInput {
+                creator_public_key: keypair.get_public_key(),
+                signature: header.signature,
+                serialized_content: header.serialized_data,
+            }
+        })
+        .collect();
+
+    let response: Vec<BlockHeaderCheck> = client
+        .request("check_block_headers", rpc_params![inputs])
+        .await
+        .unwrap();
+
+    assert_eq!(response.len(), 2);
+    assert!(response[0].is_valid);
+    assert_eq!(response[0].creator_address, producer);
+    assert!(!response[1].is_valid);
+    assert!(response[1]
+        .error
+        .as_ref()
+        .unwrap()
+        .contains("invalid block producer selection"));
+
+    api_public_handle.stop().await;
+}
+
 #[tokio::test]
 async fn get_graph_interval() {
     let addr: SocketAddr = "[::]:5008".parse().unwrap();
@@ -629,6 +1100,7 @@ async fn execute_read_only_bytecode() {
                     block_info: None,
                     state_changes: massa_final_state::StateChanges::default(),
                     events: massa_execution_exports::EventStore::default(),
+                    operation_results: Default::default(),
                 },
                 gas_cost: 100,
                 call_result: "toto".as_bytes().to_vec(),
@@ -655,7 +1127,10 @@ async fn execute_read_only_bytecode() {
             Address::from_str("AU12dG5xP1RDEB5ocdHkymNVvvSJmUL9BgHwCksDowqmGWxfpm93x").unwrap()
         ),
         operation_datastore: None,
-        fee: None
+        fee: None,
+        coins: None,
+        state: Default::default(),
+        datastore_overrides: vec![],
     }]];
     let response: Result<Vec<ExecuteReadOnlyResponse>, Error> = client
         .request("execute_read_only_bytecode", params.clone())
@@ -669,6 +1144,9 @@ async fn execute_read_only_bytecode() {
         address: None,
         operation_datastore: None,
         fee: None,
+        coins: None,
+        state: ReadOnlyExecutionState::Final,
+        datastore_overrides: vec![],
     }]];
     let response: Result<Vec<ExecuteReadOnlyResponse>, Error> = client
         .request("execute_read_only_bytecode", params.clone())
@@ -681,7 +1159,32 @@ async fn execute_read_only_bytecode() {
         bytecode: "hi".as_bytes().to_vec(),
         address: None,
         operation_datastore: Some("hi".as_bytes().to_vec()),
-        fee: None
+        fee: None,
+        coins: None,
+        state: Default::default(),
+        datastore_overrides: vec![],
+    }]];
+    let response: Result<Vec<ExecuteReadOnlyResponse>, Error> = client
+        .request("execute_read_only_bytecode", params.clone())
+        .await;
+
+    assert!(response.is_err());
+
+    // datastore override keys are limited in size
+    let params = rpc_params![vec![ReadOnlyBytecodeExecution {
+        max_gas: 100000,
+        bytecode: "hi".as_bytes().to_vec(),
+        address: None,
+        operation_datastore: None,
+        fee: None,
+        coins: None,
+        state: Default::default(),
+        datastore_overrides: vec![DatastoreEntryOverride {
+            address: Address::from_str("AU12dG5xP1RDEB5ocdHkymNVvvSJmUL9BgHwCksDowqmGWxfpm93x")
+                .unwrap(),
+            key: vec![0; 300],
+            value: Some(vec![1]),
+        }],
     }]];
     let response: Result<Vec<ExecuteReadOnlyResponse>, Error> = client
         .request("execute_read_only_bytecode", params.clone())
@@ -709,6 +1212,7 @@ async fn execute_read_only_call() {
                     block_info: None,
                     state_changes: massa_final_state::StateChanges::default(),
                     events: massa_execution_exports::EventStore::default(),
+                    operation_results: Default::default(),
                 },
                 gas_cost: 100,
                 call_result: "toto".as_bytes().to_vec(),
@@ -744,6 +1248,8 @@ async fn execute_read_only_call() {
         caller_address: None,
         fee: None,
         coins: None,
+        state: Default::default(),
+        datastore_overrides: vec![],
     }]];
     let response: Vec<ExecuteReadOnlyResponse> = client
         .request("execute_read_only_call", params.clone())
@@ -814,6 +1320,57 @@ async fn get_addresses() {
     api_public_handle.stop().await;
 }
 
+#[tokio::test]
+async fn get_deferred_credits_schedule() {
+    let addr: SocketAddr = "[::]:5047".parse().unwrap();
+    let (mut api_public, config) = start_public_api(addr);
+
+    let mut exec_ctrl = MockExecutionController::new();
+    exec_ctrl
+        .expect_get_address_deferred_credits_schedule()
+        .returning(|_address, _cycle| {
+            vec![CycleDeferredCredits {
+                cycle: 3,
+                total: Amount::from_str("150").unwrap(),
+                credits: BTreeMap::from([
+                    (Slot::new(400, 0), Amount::from_str("100").unwrap()),
+                    (Slot::new(410, 1), Amount::from_str("50").unwrap()),
+                ]),
+            }]
+        });
+    api_public.0.execution_controller = Box::new(exec_ctrl);
+
+    let api_public_handle = api_public
+        .serve(&addr, &config)
+        .await
+        .expect("failed to start PUBLIC API");
+
+    let client = HttpClientBuilder::default()
+        .build(format!(
+            "http://localhost:{}",
+            addr.to_string().split(':').last().unwrap()
+        ))
+        .unwrap();
+
+    let address =
+        Address::from_str("AU12dG5xP1RDEB5ocdHkymNVvvSJmUL9BgHwCksDowqmGWxfpm93x").unwrap();
+    let response: Vec<AddressCycleDeferredCredits> = client
+        .request(
+            "get_deferred_credits_schedule",
+            rpc_params![address, Some(3)],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.len(), 1);
+    assert_eq!(response[0].cycle, 3);
+    assert_eq!(response[0].total, Amount::from_str("150").unwrap());
+    assert_eq!(response[0].credits.len(), 2);
+    assert_eq!(response[0].credits[1].slot, Slot::new(410, 1));
+
+    api_public_handle.stop().await;
+}
+
 #[tokio::test]
 async fn get_addresses_bytecode() {
     let addr: SocketAddr = "[::]:5019".parse().unwrap();
@@ -918,6 +1475,82 @@ async fn get_datastore_entries() {
     api_public_handle.stop().await;
 }
 
+#[tokio::test]
+async fn get_authenticated_datastore_entries() {
+    let addr: SocketAddr = "[::]:5052".parse().unwrap();
+    let (mut api_public, config) = start_public_api(addr);
+
+    let final_state_hash = Hash::compute_from(b"final state");
+    let mut exec_ctrl = MockExecutionController::new();
+    exec_ctrl
+        .expect_query_state()
+        .returning(move |_| ExecutionQueryResponse {
+            responses: vec![
+                Ok(ExecutionQueryResponseItem::Authenticated(
+                    AuthenticatedQueryResult {
+                        value: Some(Box::new(ExecutionQueryResponseItem::DatastoreValue(
+                            "massa".as_bytes().to_vec(),
+                        ))),
+                        slot: Slot::new(12, 3),
+                        final_state_hash,
+                        proof: None,
+                    },
+                )),
+                Ok(ExecutionQueryResponseItem::Authenticated(
+                    AuthenticatedQueryResult {
+                        value: None,
+                        slot: Slot::new(12, 3),
+                        final_state_hash,
+                        proof: None,
+                    },
+                )),
+            ],
+            candidate_cursor: Slot::new(13, 0),
+            final_cursor: Slot::new(12, 3),
+            final_state_fingerprint: final_state_hash,
+        });
+    api_public.0.execution_controller = Box::new(exec_ctrl);
+
+    let api_public_handle = api_public
+        .serve(&addr, &config)
+        .await
+        .expect("failed to start PUBLIC API");
+
+    let client = HttpClientBuilder::default()
+        .build(format!(
+            "http://localhost:{}",
+            addr.to_string().split(':').last().unwrap()
+        ))
+        .unwrap();
+
+    let address =
+        Address::from_str("AU12dG5xP1RDEB5ocdHkymNVvvSJmUL9BgHwCksDowqmGWxfpm93x").unwrap();
+    let params = rpc_params![vec![
+        DatastoreEntryInput {
+            address,
+            key: "massa".as_bytes().to_vec()
+        },
+        DatastoreEntryInput {
+            address,
+            key: "missing".as_bytes().to_vec()
+        }
+    ]];
+    let response: Vec<AuthenticatedDatastoreEntryOutput> = client
+        .request("get_authenticated_datastore_entries", params)
+        .await
+        .unwrap();
+
+    assert_eq!(response.len(), 2);
+    assert_eq!(response[0].value, Some("massa".as_bytes().to_vec()));
+    assert_eq!(response[0].slot, Slot::new(12, 3));
+    assert_eq!(response[0].final_state_hash, final_state_hash);
+    assert!(response[0].proof.is_none());
+    assert!(response[1].value.is_none());
+    assert_eq!(response[1].final_state_hash, final_state_hash);
+
+    api_public_handle.stop().await;
+}
+
 #[tokio::test]
 async fn wrong_api() {
     let addr: SocketAddr = "[::]:5004".parse().unwrap();
@@ -1146,6 +1779,26 @@ async fn wrong_api() {
         .to_string()
         .contains("The wrong API (either Public or Private) was called"));
 
+    let interval = TimeInterval {
+        start: None,
+        end: None,
+    };
+    let response: Result<(), Error> = client
+        .request("get_operator_revenue", rpc_params![interval])
+        .await;
+    assert!(response
+        .unwrap_err()
+        .to_string()
+        .contains("The wrong API (either Public or Private) was called"));
+
+    let response: Result<(), Error> = client
+        .request("get_operator_revenue_csv", rpc_params![interval])
+        .await;
+    assert!(response
+        .unwrap_err()
+        .to_string()
+        .contains("The wrong API (either Public or Private) was called"));
+
     api_public_handle.stop().await;
 }
 
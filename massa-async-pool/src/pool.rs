@@ -1041,7 +1041,7 @@ mod tests {
     use std::str::FromStr;
     use std::sync::Arc;
 
-    use massa_db_exports::{MassaDBConfig, MassaDBController};
+    use massa_db_exports::{MassaDBConfig, MassaDBController, STATE_CF_DEFAULT_WRITE_BUFFER_SIZE};
     use massa_models::config::{
         MAX_ASYNC_POOL_LENGTH, MAX_DATASTORE_KEY_LENGTH, MAX_FUNCTION_NAME_LENGTH,
         MAX_PARAMETERS_SIZE, THREAD_COUNT,
@@ -1098,6 +1098,7 @@ mod tests {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db: ShareableMassaDBController = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>,
@@ -1139,6 +1140,7 @@ mod tests {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db: ShareableMassaDBController = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>,
@@ -1200,6 +1202,7 @@ mod tests {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db: ShareableMassaDBController = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>,
@@ -1256,6 +1259,7 @@ mod tests {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db: ShareableMassaDBController = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>,
@@ -1312,6 +1316,7 @@ mod tests {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db: ShareableMassaDBController = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>,
@@ -1361,6 +1366,7 @@ mod tests {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db: ShareableMassaDBController = Arc::new(RwLock::new(Box::new(MassaDB::new(
             db_config.clone(),
@@ -41,6 +41,7 @@ pub struct BootstrapServerBinder {
     max_consensus_block_ids: u64,
     thread_count: u8,
     max_datastore_key_length: u8,
+    max_bootstrap_error_length: u64,
     randomness_size_bytes: usize,
     local_keypair: KeyPair,
     duplex: Limiter<TcpStream>,
@@ -70,6 +71,7 @@ impl BootstrapServerBinder {
             max_datastore_key_length,
             randomness_size_bytes,
             consensus_bootstrap_part_size,
+            max_bootstrap_error_length,
             write_error_timeout,
         } = cfg;
 
@@ -84,6 +86,7 @@ impl BootstrapServerBinder {
             prev_message: None,
             thread_count,
             max_datastore_key_length,
+            max_bootstrap_error_length,
             randomness_size_bytes,
             version_serializer: VersionSerializer::new(),
             version_deserializer: VersionDeserializer::new(),
@@ -281,6 +284,7 @@ impl BootstrapServerBinder {
             self.thread_count,
             self.max_datastore_key_length,
             self.max_consensus_block_ids,
+            self.max_bootstrap_error_length,
         )
         .deserialize::<DeserializeError>(&msg_bytes)
         .map_err(|err| BootstrapError::GeneralError(format!("{}", err)))?;
@@ -4,6 +4,7 @@ use massa_final_state::{FinalStateController, FinalStateError};
 use massa_logging::massa_trace;
 use massa_metrics::MassaMetrics;
 use massa_models::{node::NodeId, slot::Slot, streaming_step::StreamingStep, version::Version};
+use massa_protocol_exports::{ProxyTarget, Socks5ProxyConfig};
 use massa_signature::PublicKey;
 use massa_time::MassaTime;
 use massa_versioning::versioning::{ComponentStateTypeId, MipInfo, MipState, StateAtError};
@@ -15,8 +16,9 @@ use rand::{
 use std::collections::BTreeMap;
 use std::{
     collections::HashSet,
+    fmt::Display,
     io,
-    net::{SocketAddr, TcpStream},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
     sync::{Arc, Condvar, Mutex},
     time::Duration,
 };
@@ -26,6 +28,7 @@ use crate::{
     bindings::BootstrapClientBinder,
     error::BootstrapError,
     messages::{BootstrapClientMessage, BootstrapServerMessage},
+    progress::{BootstrapPhase, BootstrapProgress},
     settings::IpType,
     BootstrapConfig, GlobalBootstrapState,
 };
@@ -40,11 +43,23 @@ pub trait BSConnector {
         addr: SocketAddr,
         duration: Option<MassaTime>,
     ) -> io::Result<TcpStream>;
+
+    /// The client attempts to connect to the given `host:port` destination.
+    /// If a duration is provided, the attempt will be timed out after the given duration.
+    fn connect_hostname_timeout(
+        &self,
+        hostname: &str,
+        duration: Option<MassaTime>,
+    ) -> io::Result<TcpStream>;
 }
 
-/// Initiates a connection with given timeout in milliseconds
-#[derive(Debug)]
-pub struct DefaultConnector;
+/// Initiates a connection with given timeout in milliseconds,
+/// through a SOCKS5 proxy if one is configured
+#[derive(Debug, Default)]
+pub struct DefaultConnector {
+    /// proxy through which the connections are made, if any
+    pub socks5_proxy: Option<Socks5ProxyConfig>,
+}
 
 impl BSConnector for DefaultConnector {
     /// Tries to connect to address
@@ -56,11 +71,64 @@ impl BSConnector for DefaultConnector {
         addr: SocketAddr,
         duration: Option<MassaTime>,
     ) -> io::Result<TcpStream> {
+        if let Some(proxy) = &self.socks5_proxy {
+            return proxy.connect(
+                &ProxyTarget::Address(addr),
+                duration.map(|d| d.to_duration()),
+            );
+        }
         let Some(duration) = duration else {
             return TcpStream::connect(addr);
         };
         TcpStream::connect_timeout(&addr, duration.to_duration())
     }
+
+    /// Tries to connect to a host name.
+    /// Through a proxy, the name is resolved by the proxy so that no DNS request leaves the node.
+    ///
+    /// # Argument
+    /// * `hostname`: `host:port` we are trying to connect to.
+    fn connect_hostname_timeout(
+        &self,
+        hostname: &str,
+        duration: Option<MassaTime>,
+    ) -> io::Result<TcpStream> {
+        if let Some(proxy) = &self.socks5_proxy {
+            return proxy.connect(
+                &ProxyTarget::parse(hostname)?,
+                duration.map(|d| d.to_duration()),
+            );
+        }
+        let mut last_err = io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("could not resolve {}", hostname),
+        );
+        for addr in hostname.to_socket_addrs()? {
+            match self.connect_timeout(addr, duration) {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_err = err,
+            }
+        }
+        Err(last_err)
+    }
+}
+
+/// Where to reach a bootstrap server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BootstrapServerAddress {
+    /// IP address and port
+    Address(SocketAddr),
+    /// `host:port`, resolved when connecting
+    Hostname(String),
+}
+
+impl Display for BootstrapServerAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BootstrapServerAddress::Address(addr) => write!(f, "{}", addr),
+            BootstrapServerAddress::Hostname(hostname) => write!(f, "{}", hostname),
+        }
+    }
 }
 /// This function will send the starting point to receive a stream of the ledger and will receive and process each part until receive a `BootstrapServerMessage::FinalStateFinished` message from the server.
 /// `next_bootstrap_message` passed as parameter must be `BootstrapClientMessage::AskFinalStatePart` enum variant.
@@ -70,6 +138,7 @@ fn stream_final_state_and_consensus(
     client: &mut BootstrapClientBinder,
     next_bootstrap_message: &mut BootstrapClientMessage,
     global_bootstrap_state: &mut GlobalBootstrapState,
+    progress: &mut BootstrapProgress,
 ) -> Result<(), BootstrapError> {
     if let BootstrapClientMessage::AskBootstrapPart { .. } = &next_bootstrap_message {
         client.send_timeout(
@@ -88,6 +157,8 @@ fn stream_final_state_and_consensus(
                     last_start_period,
                     last_slot_before_downtime,
                 } => {
+                    progress.record_state_part(&state_part, &versioning_part);
+
                     // Set final state
                     let mut write_final_state = global_bootstrap_state.final_state.write();
 
@@ -176,6 +247,7 @@ fn stream_final_state_and_consensus(
                     };
                     let mut write_final_state = global_bootstrap_state.final_state.write();
                     write_final_state.reset();
+                    progress.reset_state();
                     return Err(BootstrapError::GeneralError(String::from("Slot too old")));
                 }
                 // At this point, we have successfully received the next message from the server, and it's an error-message String
@@ -205,8 +277,10 @@ pub(crate) fn bootstrap_from_server(
     next_bootstrap_message: &mut BootstrapClientMessage,
     global_bootstrap_state: &mut GlobalBootstrapState,
     our_version: Version,
+    progress: &mut BootstrapProgress,
 ) -> Result<(), BootstrapError> {
     massa_trace!("bootstrap.lib.bootstrap_from_server", {});
+    progress.set_phase(BootstrapPhase::Handshake);
 
     // read error (if sent by the server)
     // client.next() is not cancel-safe but we drop the whole client object if cancelled => it's OK
@@ -291,14 +365,17 @@ pub(crate) fn bootstrap_from_server(
     loop {
         match next_bootstrap_message {
             BootstrapClientMessage::AskBootstrapPart { .. } => {
+                progress.set_phase(BootstrapPhase::StreamingState);
                 stream_final_state_and_consensus(
                     cfg,
                     client,
                     next_bootstrap_message,
                     global_bootstrap_state,
+                    progress,
                 )?;
             }
             BootstrapClientMessage::AskBootstrapPeers => {
+                progress.set_phase(BootstrapPhase::FetchingPeers);
                 let peers = match send_client_message(
                     next_bootstrap_message,
                     client,
@@ -324,6 +401,7 @@ pub(crate) fn bootstrap_from_server(
             }
         };
     }
+    progress.set_phase(BootstrapPhase::Finished);
     info!("Successful bootstrap");
     Ok(())
 }
@@ -364,6 +442,24 @@ pub(crate) fn connect_to_server(
     ))
 }
 
+fn connect_to_server_by_hostname(
+    connector: &mut impl BSConnector,
+    bootstrap_config: &BootstrapConfig,
+    hostname: &str,
+    pub_key: &PublicKey,
+    rw_limit: Option<u64>,
+) -> Result<BootstrapClientBinder, BootstrapError> {
+    let socket =
+        connector.connect_hostname_timeout(hostname, Some(bootstrap_config.connect_timeout))?;
+    socket.set_nonblocking(false)?;
+    Ok(BootstrapClientBinder::new(
+        socket,
+        *pub_key,
+        bootstrap_config.into(),
+        rw_limit,
+    ))
+}
+
 fn filter_bootstrap_list(
     bootstrap_list: Vec<(SocketAddr, NodeId)>,
     ip_type: IpType,
@@ -487,6 +583,7 @@ pub fn get_state(
             send_last_start_period: true,
         };
     let mut global_bootstrap_state = GlobalBootstrapState::new(final_state);
+    let mut progress = BootstrapProgress::new();
 
     let limit = bootstrap_config.rate_limit;
     loop {
@@ -503,13 +600,23 @@ pub fn get_state(
                 }
             }
             info!("Start bootstrapping from {}", addr);
-            let conn = connect_to_server(
-                &mut connector,
-                bootstrap_config,
-                addr,
-                &node_id.get_public_key(),
-                Some(limit),
-            );
+            progress.start_attempt(addr.to_string());
+            let conn = match addr {
+                BootstrapServerAddress::Address(addr) => connect_to_server(
+                    &mut connector,
+                    bootstrap_config,
+                    addr,
+                    &node_id.get_public_key(),
+                    Some(limit),
+                ),
+                BootstrapServerAddress::Hostname(hostname) => connect_to_server_by_hostname(
+                    &mut connector,
+                    bootstrap_config,
+                    hostname,
+                    &node_id.get_public_key(),
+                    Some(limit),
+                ),
+            };
             match conn {
                 Ok(mut client) => {
                     massa_metrics.inc_bootstrap_counter();
@@ -519,6 +626,7 @@ pub fn get_state(
                         &mut next_bootstrap_message,
                         &mut global_bootstrap_state,
                         version,
+                        &mut progress,
                     );
                     // cancellable
                     match bs {
@@ -544,6 +652,7 @@ pub fn get_state(
             };
 
             info!("Bootstrap from server {} failed. Your node will try to bootstrap from another server in {}.", addr, format_duration(bootstrap_config.retry_delay.to_duration()).to_string());
+            progress.set_phase(BootstrapPhase::WaitingRetry);
 
             // Before, we would use a simple sleep(...), and that was fine
             // in a cancellable async context: the runtime could
@@ -581,11 +690,22 @@ pub fn get_state(
 
 fn get_bootstrap_list_iter(
     bootstrap_config: &BootstrapConfig,
-) -> Result<Vec<(SocketAddr, NodeId)>, BootstrapError> {
-    let mut filtered_bootstrap_list = filter_bootstrap_list(
+) -> Result<Vec<(BootstrapServerAddress, NodeId)>, BootstrapError> {
+    let mut filtered_bootstrap_list: Vec<_> = filter_bootstrap_list(
         bootstrap_config.bootstrap_list.clone(),
         bootstrap_config.bootstrap_protocol,
-    );
+    )
+    .into_iter()
+    .map(|(addr, node_id)| (BootstrapServerAddress::Address(addr), node_id))
+    .chain(
+        bootstrap_config
+            .bootstrap_hostname_list
+            .iter()
+            .map(|(hostname, node_id)| {
+                (BootstrapServerAddress::Hostname(hostname.clone()), *node_id)
+            }),
+    )
+    .collect();
 
     // we are after genesis => bootstrap
     massa_trace!("bootstrap.lib.get_state.init_from_others", {});
@@ -598,7 +718,7 @@ fn get_bootstrap_list_iter(
     // we shuffle the list
     filtered_bootstrap_list.shuffle(&mut StdRng::from_entropy());
 
-    // we remove the duplicated node ids (if a bootstrap server appears both with its IPv4 and IPv6 address)
+    // we remove the duplicated node ids (if a bootstrap server appears both with its IPv4 and IPv6 address, or with its host name)
     let mut unique_node_ids: HashSet<NodeId> = HashSet::new();
     filtered_bootstrap_list.retain(|e| unique_node_ids.insert(e.1));
     Ok(filtered_bootstrap_list)
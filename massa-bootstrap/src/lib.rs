@@ -25,6 +25,7 @@ mod error;
 pub use error::BootstrapError;
 mod listener;
 mod messages;
+mod progress;
 mod server;
 mod settings;
 mod tools;
@@ -8,6 +8,7 @@ use massa_consensus_exports::bootstrapable_graph::{
 use massa_db_exports::StreamBatch;
 
 use massa_models::block_id::{BlockId, BlockIdDeserializer, BlockIdSerializer};
+use massa_models::config::MAX_BOOTSTRAP_MESSAGE_SIZE;
 
 use massa_models::prehash::PreHashSet;
 use massa_models::serialization::{
@@ -379,9 +380,10 @@ impl BootstrapServerMessageDeserializer {
                 Included(0),
                 Included(args.max_final_state_elements_size.into()),
             ),
+            // the updates streamed by the servers are not limited, apart from the size of the message
             stream_batch_updates_length_deserializer: U64VarIntDeserializer::new(
                 Included(0),
-                Included(u64::MAX),
+                Included(MAX_BOOTSTRAP_MESSAGE_SIZE.into()),
             ),
             slot_deserializer: SlotDeserializer::new(
                 (Included(0), Included(u64::MAX)),
@@ -768,7 +770,7 @@ impl Serializer<BootstrapClientMessage> for BootstrapClientMessageSerializer {
 /// Deserializer for `BootstrapClientMessage`
 pub struct BootstrapClientMessageDeserializer {
     id_deserializer: U32VarIntDeserializer,
-    length_error_deserializer: U32VarIntDeserializer,
+    length_error_deserializer: U64VarIntDeserializer,
     slot_deserializer: SlotDeserializer,
     state_step_deserializer: StreamingStepDeserializer<Vec<u8>, VecU8Deserializer>,
     block_ids_step_deserializer: StreamingStepDeserializer<
@@ -784,10 +786,14 @@ impl BootstrapClientMessageDeserializer {
         thread_count: u8,
         max_datastore_key_length: u8,
         max_consensus_block_ids: u64,
+        max_bootstrap_error_length: u64,
     ) -> Self {
         Self {
             id_deserializer: U32VarIntDeserializer::new(Included(0), Included(u32::MAX)),
-            length_error_deserializer: U32VarIntDeserializer::new(Included(0), Included(u32::MAX)),
+            length_error_deserializer: U64VarIntDeserializer::new(
+                Included(0),
+                Included(max_bootstrap_error_length),
+            ),
             slot_deserializer: SlotDeserializer::new(
                 (Included(0), Included(u64::MAX)),
                 (Included(0), Excluded(thread_count)),
@@ -818,7 +824,7 @@ impl Deserializer<BootstrapClientMessage> for BootstrapClientMessageDeserializer
     /// use std::str::FromStr;
     ///
     /// let message_serializer = BootstrapClientMessageSerializer::new();
-    /// let message_deserializer = BootstrapClientMessageDeserializer::new(32, 255, 50);
+    /// let message_deserializer = BootstrapClientMessageDeserializer::new(32, 255, 50, 1000);
     /// let bootstrap_server_message = BootstrapClientMessage::AskBootstrapPeers;
     /// let mut message_serialized = Vec::new();
     /// message_serializer.serialize(&bootstrap_server_message, &mut message_serialized).unwrap();
@@ -0,0 +1,246 @@
+//! Progress reporting of the bootstrap of our node.
+//!
+//! The bootstrap servers do not tell how large the final state is, so its size is estimated
+//! from the streaming cursor: the ledger is most of the final state and its keys are ordered by
+//! address hash, so the position of the cursor in the hash space tells how much was received.
+//! The progress is published in the metrics, and logged periodically.
+
+use std::time::{Duration, Instant};
+
+use massa_db_exports::{StreamBatch, LEDGER_PREFIX};
+use massa_metrics::BootstrapProgressReport;
+use massa_models::{slot::Slot, streaming_step::StreamingStep};
+use tracing::info;
+
+/// Interval between two logs of the bootstrap progress
+const PROGRESS_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Minimum estimated progress from which the total size and the remaining time are estimated
+const MIN_PROGRESS_FOR_ESTIMATES: f64 = 0.01;
+
+/// Phase of the bootstrap of our node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BootstrapPhase {
+    /// connecting to a bootstrap server
+    Connecting,
+    /// handshake and clock check with the server
+    Handshake,
+    /// receiving the final state and the consensus graph
+    StreamingState,
+    /// receiving the peers
+    FetchingPeers,
+    /// waiting before retrying with another server
+    WaitingRetry,
+    /// bootstrap done
+    Finished,
+}
+
+impl BootstrapPhase {
+    fn name(&self) -> &'static str {
+        match self {
+            BootstrapPhase::Connecting => "connecting",
+            BootstrapPhase::Handshake => "handshake",
+            BootstrapPhase::StreamingState => "streaming_state",
+            BootstrapPhase::FetchingPeers => "fetching_peers",
+            BootstrapPhase::WaitingRetry => "waiting_retry",
+            BootstrapPhase::Finished => "finished",
+        }
+    }
+}
+
+/// Tracks the progress of the bootstrap of our node
+pub(crate) struct BootstrapProgress {
+    phase: BootstrapPhase,
+    server: Option<String>,
+    attempts: u64,
+    started_at: Instant,
+    received_bytes: u64,
+    received_keys: u64,
+    state_progress: f64,
+    /// instant and state progress when the current attempt started receiving the state
+    attempt_baseline: Option<(Instant, f64)>,
+    last_log: Option<Instant>,
+}
+
+impl BootstrapProgress {
+    pub(crate) fn new() -> Self {
+        BootstrapProgress {
+            phase: BootstrapPhase::Connecting,
+            server: None,
+            attempts: 0,
+            started_at: Instant::now(),
+            received_bytes: 0,
+            received_keys: 0,
+            state_progress: 0.0,
+            attempt_baseline: None,
+            last_log: None,
+        }
+    }
+
+    /// Records a new connection attempt to a bootstrap server
+    pub(crate) fn start_attempt(&mut self, server: String) {
+        self.attempts = self.attempts.saturating_add(1);
+        self.server = Some(server);
+        self.attempt_baseline = None;
+        self.set_phase(BootstrapPhase::Connecting);
+    }
+
+    /// Records a change of phase, and reports it
+    pub(crate) fn set_phase(&mut self, phase: BootstrapPhase) {
+        self.phase = phase;
+        if matches!(
+            phase,
+            BootstrapPhase::FetchingPeers | BootstrapPhase::Finished
+        ) {
+            // the final state was fully received
+            self.state_progress = 1.0;
+        }
+        if phase == BootstrapPhase::StreamingState && self.attempt_baseline.is_none() {
+            self.attempt_baseline = Some((Instant::now(), self.state_progress));
+        }
+        self.publish(true);
+    }
+
+    /// Records a received part of the final state, reported at most every `PROGRESS_LOG_INTERVAL` in the logs
+    pub(crate) fn record_state_part(
+        &mut self,
+        state_part: &StreamBatch<Slot>,
+        versioning_part: &StreamBatch<Slot>,
+    ) {
+        for part in [state_part, versioning_part] {
+            for (key, value) in part.new_elements.iter() {
+                self.received_bytes = self
+                    .received_bytes
+                    .saturating_add((key.len() + value.len()) as u64);
+            }
+            for (key, value) in part.updates_on_previous_elements.iter() {
+                self.received_bytes = self
+                    .received_bytes
+                    .saturating_add((key.len() + value.as_ref().map_or(0, |v| v.len())) as u64);
+            }
+            self.received_keys = self
+                .received_keys
+                .saturating_add(part.new_elements.len() as u64);
+        }
+        if let Some((last_key, _)) = state_part.new_elements.last_key_value() {
+            let cursor = StreamingStep::Ongoing(last_key.clone());
+            self.state_progress = self.state_progress.max(estimate_state_progress(&cursor));
+        }
+        self.publish(false);
+    }
+
+    /// Records that the received final state was dropped, to receive it again from scratch
+    pub(crate) fn reset_state(&mut self) {
+        self.received_bytes = 0;
+        self.received_keys = 0;
+        self.state_progress = 0.0;
+        self.attempt_baseline = None;
+        self.publish(true);
+    }
+
+    /// Current progress report
+    fn report(&self) -> BootstrapProgressReport {
+        let now = Instant::now();
+        let estimates_available = self.state_progress >= MIN_PROGRESS_FOR_ESTIMATES;
+        let eta = match (self.phase, self.attempt_baseline) {
+            (BootstrapPhase::StreamingState, Some((attempt_start, baseline)))
+                if estimates_available && self.state_progress > baseline =>
+            {
+                let elapsed = now.saturating_duration_since(attempt_start).as_secs_f64();
+                let rate = (self.state_progress - baseline) / elapsed.max(f64::EPSILON);
+                Duration::try_from_secs_f64(((1.0 - self.state_progress) / rate).max(0.0)).ok()
+            }
+            _ => None,
+        };
+        BootstrapProgressReport {
+            phase: self.phase.name(),
+            server: self.server.clone(),
+            attempts: self.attempts,
+            elapsed: now.saturating_duration_since(self.started_at),
+            received_bytes: self.received_bytes,
+            received_keys: self.received_keys,
+            state_progress: Some(self.state_progress),
+            estimated_total_keys: estimates_available
+                .then(|| (self.received_keys as f64 / self.state_progress) as u64),
+            eta,
+        }
+    }
+
+    /// Publish the progress in the metrics, and log it if `force` or if it was not logged recently
+    fn publish(&mut self, force: bool) {
+        let report = self.report();
+        let now = Instant::now();
+        if force
+            || self.last_log.map_or(true, |last_log| {
+                now.saturating_duration_since(last_log) >= PROGRESS_LOG_INTERVAL
+            })
+        {
+            info!("Bootstrap progress: {}", report);
+            self.last_log = Some(now);
+        }
+        massa_metrics::set_bootstrap_progress(report);
+    }
+}
+
+/// Estimated fraction of the final state received when the streaming cursor is at `step`.
+///
+/// The keys before the ledger are few, and those after it are not streamed with the state.
+/// Ledger keys are made of the key version, the address type (user or smart contract),
+/// the address version and the address hash: each address type is assumed to hold half of the ledger,
+/// in which the position of the cursor is given by the leading bytes of the address hash.
+pub(crate) fn estimate_state_progress(step: &StreamingStep<Vec<u8>>) -> f64 {
+    let key = match step {
+        StreamingStep::Started => return 0.0,
+        StreamingStep::Finished(_) => return 1.0,
+        StreamingStep::Ongoing(key) => key,
+    };
+    let ledger_prefix = LEDGER_PREFIX.as_bytes();
+    if !key.starts_with(ledger_prefix) {
+        return if key.as_slice() < ledger_prefix {
+            0.0
+        } else {
+            1.0
+        };
+    }
+    let ledger_key = &key[ledger_prefix.len()..];
+    // key version, address type, address version, then the address hash
+    let (Some(address_type), Some(hash)) = (ledger_key.get(1), ledger_key.get(3..)) else {
+        return 0.0;
+    };
+    let mut hash_start = [0u8; 8];
+    let hash_start_len = hash.len().min(8);
+    hash_start[..hash_start_len].copy_from_slice(&hash[..hash_start_len]);
+    let hash_position = u64::from_be_bytes(hash_start) as f64 / (u64::MAX as f64 + 1.0);
+    ((*address_type).min(1) as f64 + hash_position) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger_key(address_type: u8, hash_start: u8) -> StreamingStep<Vec<u8>> {
+        let mut key = LEDGER_PREFIX.as_bytes().to_vec();
+        key.extend([0, address_type, 0]);
+        key.push(hash_start);
+        key.extend([0u8; 31]);
+        StreamingStep::Ongoing(key)
+    }
+
+    #[test]
+    fn test_estimate_state_progress() {
+        assert_eq!(estimate_state_progress(&StreamingStep::Started), 0.0);
+        assert_eq!(
+            estimate_state_progress(&StreamingStep::Ongoing(b"async_pool/key".to_vec())),
+            0.0
+        );
+        assert_eq!(estimate_state_progress(&ledger_key(0, 0)), 0.0);
+        assert_eq!(estimate_state_progress(&ledger_key(0, 128)), 0.25);
+        assert_eq!(estimate_state_progress(&ledger_key(1, 0)), 0.5);
+        assert_eq!(estimate_state_progress(&ledger_key(1, 192)), 0.875);
+        assert_eq!(
+            estimate_state_progress(&StreamingStep::Ongoing(b"versioning/key".to_vec())),
+            1.0
+        );
+        assert_eq!(estimate_state_progress(&StreamingStep::Finished(None)), 1.0);
+    }
+}
@@ -24,6 +24,9 @@ pub enum IpType {
 pub struct BootstrapConfig {
     /// Ip address of our bootstrap nodes and their public key.
     pub bootstrap_list: Vec<(SocketAddr, NodeId)>,
+    /// Host names (`host:port`) of our bootstrap nodes and their public key.
+    /// The names are resolved when connecting, by the SOCKS5 proxy if one is configured.
+    pub bootstrap_hostname_list: Vec<(String, NodeId)>,
     /// IP version filter for bootstrap list, targeting IpType::IPv4, IpType::IPv6 or IpType::Both. Defaults to IpType::Both.
     pub bootstrap_protocol: IpType,
     /// Path to the bootstrap whitelist file. This whitelist define IPs that can bootstrap on your node.
@@ -137,6 +140,7 @@ pub struct BootstrapSrvBindCfg {
     pub max_datastore_key_length: u8,
     pub randomness_size_bytes: usize,
     pub consensus_bootstrap_part_size: u64,
+    pub max_bootstrap_error_length: u64,
     pub write_error_timeout: MassaTime,
 }
 
@@ -8,7 +8,7 @@ use crate::{
 };
 use crate::{BootstrapConfig, BootstrapError};
 use massa_consensus_exports::MockConsensusController;
-use massa_db_exports::{MassaDBConfig, MassaDBController};
+use massa_db_exports::{MassaDBConfig, MassaDBController, STATE_CF_DEFAULT_WRITE_BUFFER_SIZE};
 use massa_db_worker::MassaDB;
 use massa_final_state::FinalStateConfig;
 use massa_models::config::{
@@ -135,6 +135,7 @@ fn init_server_client_pair() -> (BootstrapServerBinder, BootstrapClientBinder) {
             max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
             randomness_size_bytes: BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
             consensus_bootstrap_part_size: CONSENSUS_BOOTSTRAP_PART_SIZE,
+            max_bootstrap_error_length: MAX_BOOTSTRAP_ERROR_LENGTH,
             write_error_timeout: MassaTime::from_millis(1000),
         },
         Some(u64::MAX),
@@ -363,6 +364,7 @@ fn test_partial_msg() {
             max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
             randomness_size_bytes: BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
             consensus_bootstrap_part_size: CONSENSUS_BOOTSTRAP_PART_SIZE,
+            max_bootstrap_error_length: MAX_BOOTSTRAP_ERROR_LENGTH,
             write_error_timeout: MassaTime::from_millis(1000),
         },
         None,
@@ -438,6 +440,7 @@ fn test_staying_connected_without_message_trigger_read_timeout() {
             max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
             randomness_size_bytes: BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
             consensus_bootstrap_part_size: CONSENSUS_BOOTSTRAP_PART_SIZE,
+            max_bootstrap_error_length: MAX_BOOTSTRAP_ERROR_LENGTH,
             write_error_timeout: MassaTime::from_millis(1000),
         },
         None,
@@ -449,6 +452,7 @@ fn test_staying_connected_without_message_trigger_read_timeout() {
         max_final_state_elements_size: 100_000_000,
         max_versioning_elements_size: 100_000_000,
         thread_count: THREAD_COUNT,
+        state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
     }))
         as Box<(dyn MassaDBController + 'static)>));
     let rolls_path = PathBuf::from_str("../massa-node/base_config/initial_rolls.json").unwrap();
@@ -531,6 +535,7 @@ fn test_staying_connected_pass_handshake_but_deadline_after() {
             max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
             randomness_size_bytes: BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
             consensus_bootstrap_part_size: CONSENSUS_BOOTSTRAP_PART_SIZE,
+            max_bootstrap_error_length: MAX_BOOTSTRAP_ERROR_LENGTH,
             write_error_timeout: MassaTime::from_millis(1000),
         },
         None,
@@ -546,6 +551,7 @@ fn test_staying_connected_pass_handshake_but_deadline_after() {
         max_final_state_elements_size: 100_000_000,
         max_versioning_elements_size: 100_000_000,
         thread_count: THREAD_COUNT,
+        state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
     }))
         as Box<(dyn MassaDBController + 'static)>));
     let rolls_path = PathBuf::from_str("../massa-node/base_config/initial_rolls.json").unwrap();
@@ -628,6 +634,7 @@ fn test_staying_connected_pass_handshake_but_deadline_during_data_exchange() {
             max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
             randomness_size_bytes: BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
             consensus_bootstrap_part_size: CONSENSUS_BOOTSTRAP_PART_SIZE,
+            max_bootstrap_error_length: MAX_BOOTSTRAP_ERROR_LENGTH,
             write_error_timeout: MassaTime::from_millis(1000),
         },
         None,
@@ -643,6 +650,7 @@ fn test_staying_connected_pass_handshake_but_deadline_during_data_exchange() {
         max_final_state_elements_size: 100_000_000,
         max_versioning_elements_size: 100_000_000,
         thread_count: THREAD_COUNT,
+        state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
     }))
         as Box<(dyn MassaDBController + 'static)>));
     let rolls_path = PathBuf::from_str("../massa-node/base_config/initial_rolls.json").unwrap();
@@ -724,6 +732,7 @@ fn test_client_drip_feed() {
             max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
             randomness_size_bytes: BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
             consensus_bootstrap_part_size: CONSENSUS_BOOTSTRAP_PART_SIZE,
+            max_bootstrap_error_length: MAX_BOOTSTRAP_ERROR_LENGTH,
             write_error_timeout: MassaTime::from_millis(1000),
         },
         None,
@@ -815,6 +824,7 @@ fn test_bandwidth() {
             max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
             randomness_size_bytes: BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
             consensus_bootstrap_part_size: CONSENSUS_BOOTSTRAP_PART_SIZE,
+            max_bootstrap_error_length: MAX_BOOTSTRAP_ERROR_LENGTH,
             write_error_timeout: MassaTime::from_millis(1000),
         },
         Some(100),
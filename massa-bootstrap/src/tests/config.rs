@@ -39,6 +39,7 @@ impl Default for BootstrapConfig {
             write_error_timeout: MassaTime::from_millis(200),
             max_listeners_per_peer: 100,
             bootstrap_list: vec![(SocketAddr::new(BASE_BOOTSTRAP_IP, 8069), node_id)],
+            bootstrap_hostname_list: Vec::new(),
             keep_ledger: false,
             bootstrap_whitelist_path: PathBuf::from("bootstrap_whitelist.json"),
             bootstrap_blacklist_path: PathBuf::from("bootstrap_blacklist.json"),
@@ -2,14 +2,18 @@ use std::time::Duration;
 
 use crate::settings::BootstrapClientConfig;
 use crate::tests::tools::{
-    parametric_test, BootstrapClientMessageFaultyPart, BootstrapServerMessageFaultyPart,
+    gen_length_prefix_corpus, get_bootstrap_config, parametric_test,
+    BootstrapClientMessageFaultyPart, BootstrapServerMessageFaultyPart,
 };
 use crate::{
     BootstrapClientMessage, BootstrapClientMessageDeserializer, BootstrapClientMessageSerializer,
     BootstrapServerMessage, BootstrapServerMessageDeserializer, BootstrapServerMessageSerializer,
 };
 use massa_models::config::*;
-use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use massa_models::node::NodeId;
+use massa_serialization::{
+    DeserializeError, Deserializer, Serializer, U32VarIntSerializer, U64VarIntSerializer,
+};
 
 #[test]
 fn test_serialize_bootstrap_server_message() {
@@ -101,6 +105,7 @@ fn test_serialize_bootstrap_client_message() {
                 THREAD_COUNT,
                 MAX_DATASTORE_KEY_LENGTH,
                 MAX_CONSENSUS_BLOCKS_IDS,
+                MAX_BOOTSTRAP_ERROR_LENGTH,
             );
             match deser.deserialize::<massa_serialization::DeserializeError>(&bytes) {
                 Ok((rest, msg_res)) => {
@@ -127,9 +132,10 @@ fn test_serialize_error_cases_clientmsg() {
         THREAD_COUNT,
         MAX_DATASTORE_KEY_LENGTH,
         MAX_CONSENSUS_BLOCKS_IDS,
+        MAX_BOOTSTRAP_ERROR_LENGTH,
     );
 
-    for n in 0..4 {
+    for n in 0..5 {
         let mut bytes = Vec::new();
         let faulty_part = BootstrapClientMessageFaultyPart::from_u8(n);
         let msg = BootstrapClientMessage::generate_faulty(&mut rng, faulty_part.clone());
@@ -198,3 +204,48 @@ fn test_serialize_error_cases_servermsg() {
         println!("===========");
     }
 }
+
+#[test]
+fn test_length_prefix_limits() {
+    let keypair = KeyPair::generate(0).unwrap();
+    let bootstrap_config = get_bootstrap_config(NodeId::new(keypair.get_public_key()));
+    let client_config: BootstrapClientConfig = (&bootstrap_config).into();
+    let server_deser = BootstrapServerMessageDeserializer::new((&client_config).into());
+    let client_deser = BootstrapClientMessageDeserializer::new(
+        THREAD_COUNT,
+        MAX_DATASTORE_KEY_LENGTH,
+        MAX_CONSENSUS_BLOCKS_IDS,
+        MAX_BOOTSTRAP_ERROR_LENGTH,
+    );
+
+    // BootstrapError messages of the server (type 5) and of the client (type 2), whose length is
+    // above the limit when the prefix is, so that only the prefix can make their deserialization fail
+    let error_message = |message_type_id: u32, length: u64| {
+        let mut bytes = Vec::new();
+        U32VarIntSerializer::new()
+            .serialize(&message_type_id, &mut bytes)
+            .unwrap();
+        U64VarIntSerializer::new()
+            .serialize(&length, &mut bytes)
+            .unwrap();
+        let payload_length = length.min(MAX_BOOTSTRAP_ERROR_LENGTH + 1) as usize;
+        bytes.extend(std::iter::repeat(b'A').take(payload_length));
+        bytes
+    };
+    for (length, valid) in gen_length_prefix_corpus(MAX_BOOTSTRAP_ERROR_LENGTH) {
+        let res = server_deser.deserialize::<DeserializeError>(&error_message(5, length));
+        assert_eq!(
+            res.is_ok(),
+            valid,
+            "server error message with length prefix {}",
+            length
+        );
+        let res = client_deser.deserialize::<DeserializeError>(&error_message(2, length));
+        assert_eq!(
+            res.is_ok(),
+            valid,
+            "client error message with length prefix {}",
+            length
+        );
+    }
+}
@@ -341,6 +341,7 @@ pub fn get_bootstrap_config(bootstrap_public_key: NodeId) -> BootstrapConfig {
             SocketAddr::new(BASE_BOOTSTRAP_IP, 8069),
             bootstrap_public_key,
         )],
+        bootstrap_hostname_list: Vec::new(),
         keep_ledger: false,
         bootstrap_whitelist_path: PathBuf::from(
             "../massa-node/base_config/bootstrap_whitelist.json",
@@ -530,6 +531,7 @@ fn gen_random_block<R: Rng>(keypair: &KeyPair, rng: &mut R) -> Block {
         operation_merkle_root: gen_random_hash(rng),
         endorsements,
         denunciations,
+        extensions: Vec::new(),
     }
     .new_verifiable(BlockHeaderSerializer::new(), keypair)
     .unwrap();
@@ -928,6 +930,7 @@ impl BootstrapServerMessage {
                 operation_merkle_root: gen_random_hash(rng),
                 endorsements: endorsements.clone(),
                 denunciations,
+                extensions: Vec::new(),
             }
             .new_verifiable(BlockHeaderSerializer::new(), &keypair)
             .unwrap();
@@ -1060,6 +1063,7 @@ pub enum BootstrapClientMessageFaultyPart {
     LastStateStepMaxKeyOverflow = 1,
     LastVersioningStepMaxKeyOverflow = 2,
     LastConsensusStepMaxBlockOverflow = 3,
+    MaxBootstrapErrorLengthOverflow = 4,
 }
 
 impl BootstrapClientMessageFaultyPart {
@@ -1069,6 +1073,7 @@ impl BootstrapClientMessageFaultyPart {
             1 => Self::LastStateStepMaxKeyOverflow,
             2 => Self::LastVersioningStepMaxKeyOverflow,
             3 => Self::LastConsensusStepMaxBlockOverflow,
+            4 => Self::MaxBootstrapErrorLengthOverflow,
             _ => panic!("invalid value"),
         }
     }
@@ -1181,6 +1186,16 @@ impl BootstrapClientMessage {
         rng: &mut R,
         faulty_part: BootstrapClientMessageFaultyPart,
     ) -> Self {
+        if faulty_part == BootstrapClientMessageFaultyPart::MaxBootstrapErrorLengthOverflow {
+            let mut res = vec![0; (MAX_BOOTSTRAP_ERROR_LENGTH as usize) + 10];
+            rng.fill_bytes(&mut res);
+            let error = res
+                .into_iter()
+                .map(|c| char::from_u32(((c as u32) % (122 - 65)) + 65).unwrap())
+                .collect();
+            return BootstrapClientMessage::BootstrapError { error };
+        }
+
         let mut last_slot = gen_random_slot(rng);
         if faulty_part == BootstrapClientMessageFaultyPart::LastSlotThreadOverflow {
             last_slot.thread = THREAD_COUNT;
@@ -1229,6 +1244,25 @@ impl BootstrapClientMessage {
     }
 }
 
+// Length prefixes around the limit `max` of a length-prefixed field, with whether they are
+// within the limit. Also covers the lengths that overflow the `u32` and `usize` conversions.
+// Shared by the tests of the deserializers, that must reject the prefixes above their limit
+// without allocating anything.
+pub fn gen_length_prefix_corpus(max: u64) -> Vec<(u64, bool)> {
+    let mut corpus = vec![
+        (0, true),
+        (max, true),
+        (max.saturating_add(1), max == u64::MAX),
+        (u32::MAX as u64, max >= u32::MAX as u64),
+        ((u32::MAX as u64) + 1, max > u32::MAX as u64),
+        (u64::MAX, max == u64::MAX),
+    ];
+    if max > 0 {
+        corpus.push((max - 1, true));
+    }
+    corpus
+}
+
 // Perform a parametric test, meaning that it checks conditions that should be met whatever the
 // data is.
 // The idea is to generate random cases, tests them, and let the random check for every edge
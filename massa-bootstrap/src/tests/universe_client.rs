@@ -3,7 +3,10 @@ use std::{
     sync::Arc,
 };
 
-use massa_db_exports::{MassaDBConfig, MassaDBController, ShareableMassaDBController};
+use massa_db_exports::{
+    MassaDBConfig, MassaDBController, ShareableMassaDBController,
+    STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+};
 use massa_db_worker::MassaDB;
 use massa_final_state::MockFinalStateController;
 use massa_models::{
@@ -21,6 +24,7 @@ use tempfile::TempDir;
 
 use crate::{
     client::{bootstrap_from_server, connect_to_server, MockBSConnector},
+    progress::BootstrapProgress,
     BootstrapClientMessage, BootstrapConfig, BootstrapError, GlobalBootstrapState,
 };
 
@@ -59,6 +63,7 @@ impl TestUniverse for BootstrapClientTestUniverse {
             max_versioning_elements_size: MAX_BOOTSTRAP_VERSIONING_ELEMENTS_SIZE as usize,
             max_final_state_elements_size: MAX_BOOTSTRAP_FINAL_STATE_PARTS_SIZE as usize,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         }))
             as Box<(dyn MassaDBController + 'static)>));
         controllers
@@ -137,6 +142,7 @@ impl BootstrapClientTestUniverse {
             &mut next_bootstrap_message,
             &mut self.global_bootstrap_state,
             version,
+            &mut BootstrapProgress::new(),
         )
     }
 
@@ -7,7 +7,10 @@ use std::{
 use massa_consensus_exports::{
     bootstrapable_graph::BootstrapableGraph, MockConsensusControllerWrapper,
 };
-use massa_db_exports::{DBBatch, MassaDBConfig, MassaDBController, ShareableMassaDBController};
+use massa_db_exports::{
+    DBBatch, MassaDBConfig, MassaDBController, ShareableMassaDBController,
+    STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+};
 use massa_db_worker::MassaDB;
 use massa_final_state::MockFinalStateController;
 use massa_ledger_exports::{LedgerChanges, LedgerConfig, LedgerController};
@@ -56,6 +59,7 @@ impl BootstrapServerForeignControllers {
             max_versioning_elements_size: MAX_BOOTSTRAP_VERSIONING_ELEMENTS_SIZE as usize,
             max_final_state_elements_size: MAX_BOOTSTRAP_FINAL_STATE_PARTS_SIZE as usize,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         }))
             as Box<(dyn MassaDBController + 'static)>));
         Self {
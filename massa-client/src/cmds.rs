@@ -187,7 +187,7 @@ pub enum Command {
     #[strum(
         ascii_case_insensitive,
         props(
-            args = "start=slot_period,slot_thread end=slot_period,slot_thread emitter_address=Address caller_address=Address operation_id=OperationId is_final=bool is_error=bool",
+            args = "start=slot_period,slot_thread end=slot_period,slot_thread emitter_address=Address caller_address=Address operation_id=OperationId is_final=bool is_error=bool data_prefix=String",
             pwd_not_needed = "true"
         ),
         message = "show events emitted by smart contracts with various filters"
@@ -242,6 +242,19 @@ pub enum Command {
     )]
     wallet_sign,
 
+    #[strum(
+        ascii_case_insensitive,
+        message = "unlock the wallet for the rest of the session (or until it is locked again)"
+    )]
+    wallet_unlock,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(pwd_not_needed = "true"),
+        message = "lock the wallet, wiping its keys from memory until the next unlock"
+    )]
+    wallet_lock,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "Address RollCount Fee"),
@@ -615,7 +628,7 @@ impl Command {
             }
 
             Command::get_filtered_sc_output_event => {
-                let p_list: [&str; 7] = [
+                let p_list: [&str; 8] = [
                     "start",
                     "end",
                     "emitter_address",
@@ -623,6 +636,7 @@ impl Command {
                     "operation_id",
                     "is_final",
                     "is_error",
+                    "data_prefix",
                 ];
                 let mut p: HashMap<&str, &str> = HashMap::new();
                 for v in parameters {
@@ -641,6 +655,7 @@ impl Command {
                     original_operation_id: parse_key_value(&p, p_list[4])?,
                     is_final: parse_key_value(&p, p_list[5])?,
                     is_error: parse_key_value(&p, p_list[6])?,
+                    data_prefix: parse_key_value(&p, p_list[7])?,
                 };
                 match client.public.get_filtered_sc_output_event(filter).await {
                     Ok(events) => Ok(Box::new(events)),
@@ -968,6 +983,27 @@ impl Command {
                 )
                 .await
             }
+            Command::wallet_unlock => {
+                // the wallet is unlocked before running any command that needs it
+                if !json {
+                    println!("Wallet unlocked");
+                }
+                Ok(Box::new(()))
+            }
+
+            Command::wallet_lock => {
+                // dropping the wallet wipes its password and secret keys
+                let was_unlocked = wallet_opt.take().is_some();
+                if !json {
+                    if was_unlocked {
+                        println!("Wallet locked");
+                    } else {
+                        println!("Wallet already locked");
+                    }
+                }
+                Ok(Box::new(()))
+            }
+
             Command::when_moon => {
                 let res = "At night 🌔.";
                 if !json {
@@ -1120,6 +1156,9 @@ impl Command {
                         address,
                         operation_datastore: None, // TODO - #3072
                         fee,
+                        coins: None,
+                        state: Default::default(),
+                        datastore_overrides: Vec::new(),
                     })
                     .await
                 {
@@ -1156,6 +1195,8 @@ impl Command {
                         max_gas,
                         coins,
                         fee,
+                        state: Default::default(),
+                        datastore_overrides: Vec::new(),
                     })
                     .await
                 {
@@ -4,15 +4,23 @@ use crate::cmds::ExtendedWallet;
 use console::style;
 use erased_serde::{Serialize, Serializer};
 use massa_api_exports::{
-    address::AddressInfo, block::BlockInfo, datastore::DatastoreEntryOutput,
-    endorsement::EndorsementInfo, execution::ExecuteReadOnlyResponse, node::NodeStatus,
+    address::{format_json_addresses, AddressInfo},
+    block::BlockInfo,
+    datastore::DatastoreEntryOutput,
+    endorsement::EndorsementInfo,
+    execution::ExecuteReadOnlyResponse,
+    node::{NodeHealth, NodeStatus},
     operation::OperationInfo,
 };
 use massa_models::composite::PubkeySig;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
 use massa_models::stats::{ConsensusStats, ExecutionStats, NetworkStats};
-use massa_models::{address::Address, config::CompactConfig, operation::OperationId};
+use massa_models::{
+    address::{Address, AddressFormat},
+    config::CompactConfig,
+    operation::OperationId,
+};
 use massa_signature::{KeyPair, PublicKey};
 use massa_wallet::Wallet;
 use std::net::IpAddr;
@@ -114,10 +122,16 @@ pub trait Output: Serialize {
 }
 
 impl dyn Output {
-    pub(crate) fn stdout_json(&self) -> anyhow::Result<()> {
-        let json = &mut serde_json::Serializer::new(std::io::stdout());
-        let mut format: Box<dyn Serializer> = Box::new(<dyn Serializer>::erase(json));
-        self.erased_serialize(&mut format)?;
+    pub(crate) fn stdout_json(&self, address_format: AddressFormat) -> anyhow::Result<()> {
+        let mut bytes = Vec::new();
+        {
+            let json = &mut serde_json::Serializer::new(&mut bytes);
+            let mut format: Box<dyn Serializer> = Box::new(<dyn Serializer>::erase(json));
+            self.erased_serialize(&mut format)?;
+        }
+        let mut value: serde_json::Value = serde_json::from_slice(&bytes)?;
+        format_json_addresses(&mut value, address_format);
+        serde_json::to_writer(std::io::stdout(), &value)?;
         Ok(())
     }
 }
@@ -260,6 +274,7 @@ impl Output for NodeStatus {
 
         self.network_stats.pretty_print();
         self.execution_stats.pretty_print();
+        self.health.pretty_print();
 
         if !self.connected_nodes.is_empty() {
             println!("Connected nodes:");
@@ -275,6 +290,42 @@ impl Output for NodeStatus {
     }
 }
 
+impl Output for NodeHealth {
+    fn pretty_print(&self) {
+        println!("Health:");
+        println!(
+            "\tCandidate execution lag: {} slots",
+            Style::Protocol.style(self.candidate_execution_lag)
+        );
+        println!(
+            "\tFinal execution lag: {} slots",
+            Style::Protocol.style(self.final_execution_lag)
+        );
+        println!(
+            "\tConsensus backlog: {} blocks",
+            Style::Block.style(self.consensus_backlog)
+        );
+        println!(
+            "\tPool: {} operations, {} endorsements, {} denunciations",
+            Style::Protocol.style(self.pool_operation_count),
+            Style::Protocol.style(self.pool_endorsement_count),
+            Style::Protocol.style(self.pool_denunciation_count)
+        );
+        if let Some(db_disk_usage) = self.db_disk_usage {
+            println!(
+                "\tDatabase disk usage: {} bytes",
+                Style::Protocol.style(db_disk_usage)
+            );
+        }
+        if let Some(process_rss) = self.process_rss {
+            println!(
+                "\tProcess RSS: {} bytes",
+                Style::Protocol.style(process_rss)
+            );
+        }
+    }
+}
+
 impl Output for ExecutionStats {
     fn pretty_print(&self) {
         println!("Execution stats:");
@@ -302,6 +353,13 @@ impl Output for ExecutionStats {
             "\tFinal cursor: {}",
             Style::Protocol.style(self.final_cursor)
         );
+        if !self.candidate_execution_enabled {
+            println!(
+                "\t{}",
+                Style::Bad
+                    .style("Candidate execution is disabled: candidate results are unavailable")
+            );
+        }
     }
 }
 
@@ -328,6 +386,13 @@ impl Output for NetworkStats {
             "\tActive nodes: {}",
             Style::Good.style(self.active_node_count)
         );
+        for (category, count) in &self.peers_per_category {
+            println!(
+                "\tPeers of category {}: {}",
+                category,
+                Style::Protocol.style(count)
+            );
+        }
     }
 }
 
@@ -387,6 +452,10 @@ impl Output for ConsensusStats {
             "\tClique count: {}",
             Style::Protocol.style(self.clique_count)
         );
+        println!(
+            "\tPending block count: {}",
+            Style::Block.style(self.pending_block_count)
+        );
     }
 }
 
@@ -544,6 +613,9 @@ impl Output for Vec<OperationInfo> {
                 "Expire period: {}",
                 Style::Pending.style(info.operation.content.expire_period)
             );
+            if let Some(summary) = &info.summary {
+                println!("Summary: {}", Style::Id.style(summary));
+            }
             println!(
                 "Operation type: {}",
                 Style::Id.style(&info.operation.content.op)
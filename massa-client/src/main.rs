@@ -21,6 +21,7 @@ mod cmds;
 mod display;
 mod repl;
 mod settings;
+mod wallet_session;
 
 #[cfg(test)]
 pub mod tests;
@@ -186,7 +187,7 @@ async fn run(args: Args) -> Result<()> {
             Ok(output) => {
                 if args.json {
                     output
-                        .stdout_json()
+                        .stdout_json(SETTINGS.address_format)
                         .expect("fail to serialize to JSON command output")
                 } else {
                     output.pretty_print();
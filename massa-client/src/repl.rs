@@ -4,6 +4,7 @@ use crate::ask_password;
 use crate::cmds::Command;
 use crate::massa_fancy_ascii_art_logo;
 use crate::settings::SETTINGS;
+use crate::wallet_session::{spawn_auto_lock, WalletSession};
 use anyhow::Result;
 use console::style;
 use massa_sdk::Client;
@@ -15,8 +16,10 @@ use rustyline::{CompletionType, Config, Editor};
 use rustyline_derive::{Completer, Helper, Highlighter, Hinter, Validator};
 use std::env;
 use std::path::Path;
+use std::sync::Arc;
 use strum::IntoEnumIterator;
 use strum::ParseError;
+use tokio::sync::Mutex;
 
 fn group_parameters(parameters: Vec<String>) -> Vec<String> {
     let mut new_parameters = Vec::new();
@@ -104,7 +107,10 @@ pub(crate) async fn run(
         println!("No previous history.");
     }
 
-    let mut wallet_opt = None;
+    let session = Arc::new(Mutex::new(WalletSession::new(
+        SETTINGS.wallet_auto_lock_timeout.to_duration(),
+    )));
+    let auto_lock = spawn_auto_lock(session.clone());
 
     loop {
         let readline = rl.readline("command > ");
@@ -126,6 +132,15 @@ pub(crate) async fn run(
                 // Print result of evaluated command
                 match cmd {
                     Ok(command) => {
+                        // the session is not held while the password is asked nor while the command runs
+                        let mut wallet_opt = {
+                            let mut session = session.lock().await;
+                            if session.lock_if_idle() {
+                                println!("Wallet locked after inactivity");
+                            }
+                            session.take_wallet()
+                        };
+
                         // Check if we need to prompt the user for their wallet password
                         if command.is_pwd_needed() && wallet_opt.is_none() {
                             let password =
@@ -145,10 +160,16 @@ pub(crate) async fn run(
                             wallet_opt = Some(wallet);
                         }
 
-                        match command
+                        // `exit` does not return: wipe the wallet keys beforehand
+                        if command == Command::exit {
+                            wallet_opt = None;
+                        }
+
+                        let result = command
                             .run(client, &mut wallet_opt, &parameters, false)
-                            .await
-                        {
+                            .await;
+                        session.lock().await.restore_wallet(wallet_opt);
+                        match result {
                             Ok(output) => output.pretty_print(),
                             Err(e) => println!("{}", style(format!("Error: {}", e)).red()),
                         }
@@ -170,6 +191,10 @@ pub(crate) async fn run(
             }
         }
     }
+
+    // Wipe the wallet keys before leaving
+    auto_lock.abort();
+    session.lock().await.lock();
     Ok(())
 }
 
@@ -1,7 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 //! Build here the default client settings from the configuration file toml
-use massa_models::config::build_massa_settings;
+use massa_models::{address::AddressFormat, config::build_massa_settings};
 use massa_time::MassaTime;
 use serde::Deserialize;
 use std::{net::IpAddr, path::PathBuf};
@@ -16,6 +16,10 @@ pub struct Settings {
     pub history: usize,
     pub history_file_path: PathBuf,
     pub timeout: MassaTime,
+    /// inactivity duration after which the interactive client locks the wallet (0 disables it)
+    pub wallet_auto_lock_timeout: MassaTime,
+    /// format of the addresses in the JSON output of the commands
+    pub address_format: AddressFormat,
     pub client: ClientSettings,
 }
 
@@ -0,0 +1,128 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Wallet session of the interactive client.
+//! The wallet is unlocked once with its password and kept in memory for the rest of the session,
+//! until it is locked again with `wallet_lock` or automatically after a period of inactivity.
+//! Locking drops the wallet, which wipes its password and secret keys from memory.
+//! The memory holding them is not locked (guarded pages are out of scope):
+//! while the wallet is unlocked, it may be swapped to disk or written to a core dump.
+//!
+//! The wallet is taken out of the session while a command runs,
+//! so that the session is not held during the command and the auto-lock never drops a wallet in use.
+
+use massa_wallet::Wallet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// How often the auto-lock task checks for inactivity
+const AUTO_LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(crate) struct WalletSession {
+    /// the unlocked wallet, if any
+    wallet: Option<Wallet>,
+    /// last time the wallet was used
+    last_activity: Instant,
+    /// inactivity duration after which the wallet is locked (zero disables auto-lock)
+    auto_lock_timeout: Duration,
+}
+
+impl WalletSession {
+    /// Creates a locked session
+    pub(crate) fn new(auto_lock_timeout: Duration) -> Self {
+        WalletSession {
+            wallet: None,
+            last_activity: Instant::now(),
+            auto_lock_timeout,
+        }
+    }
+
+    /// Takes the wallet out of the session for a command, leaving the session locked meanwhile
+    pub(crate) fn take_wallet(&mut self) -> Option<Wallet> {
+        self.wallet.take()
+    }
+
+    /// Puts back the wallet used by a command, opened by it or `None` if it locked the wallet,
+    /// recording the activity to postpone the auto-lock
+    pub(crate) fn restore_wallet(&mut self, wallet: Option<Wallet>) {
+        self.wallet = wallet;
+        self.last_activity = Instant::now();
+    }
+
+    /// Drops the wallet, wiping its keys from memory.
+    /// Returns true if the wallet was unlocked.
+    pub(crate) fn lock(&mut self) -> bool {
+        self.wallet.take().is_some()
+    }
+
+    fn is_idle(&self, now: Instant) -> bool {
+        self.wallet.is_some()
+            && !self.auto_lock_timeout.is_zero()
+            && now.saturating_duration_since(self.last_activity) >= self.auto_lock_timeout
+    }
+
+    /// Locks the wallet if it has not been used for longer than the auto-lock timeout.
+    /// Returns true if the wallet was locked.
+    pub(crate) fn lock_if_idle(&mut self) -> bool {
+        self.is_idle(Instant::now()) && self.lock()
+    }
+}
+
+/// Spawns a task locking the wallet of the session after a period of inactivity
+pub(crate) fn spawn_auto_lock(session: Arc<Mutex<WalletSession>>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(AUTO_LOCK_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if session.lock().await.lock_if_idle() {
+                println!("\nWallet locked after inactivity, use 'wallet_unlock' to unlock it");
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_wallet::test_exports::create_test_wallet;
+
+    #[test]
+    fn test_wallet_session_idle() {
+        let mut session = WalletSession::new(Duration::from_secs(60));
+        let now = Instant::now();
+        assert!(!session.is_idle(now + Duration::from_secs(120)));
+
+        session.restore_wallet(Some(create_test_wallet(None)));
+        assert!(!session.is_idle(now));
+        assert!(session.is_idle(now + Duration::from_secs(120)));
+
+        assert!(session.lock());
+        assert!(!session.lock());
+    }
+
+    #[test]
+    fn test_wallet_session_wallet_in_use() {
+        let mut session = WalletSession::new(Duration::from_secs(60));
+        session.restore_wallet(Some(create_test_wallet(None)));
+
+        // the wallet in use by a command is not locked by the auto-lock
+        let wallet = session.take_wallet();
+        assert!(wallet.is_some());
+        assert!(!session.is_idle(Instant::now() + Duration::from_secs(120)));
+        assert!(!session.lock());
+
+        // it is back in the session once the command is done, unless the command locked it
+        session.restore_wallet(wallet);
+        assert!(session.lock());
+        session.restore_wallet(None);
+        assert!(!session.lock());
+    }
+
+    #[test]
+    fn test_wallet_session_auto_lock_disabled() {
+        let mut session = WalletSession::new(Duration::ZERO);
+        session.restore_wallet(Some(create_test_wallet(None)));
+        assert!(!session.is_idle(Instant::now() + Duration::from_secs(3600)));
+    }
+}
@@ -0,0 +1,30 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Archival of the final blocks pruned from the block graph.
+//!
+//! The block graph only keeps the final blocks of the latest periods. On archival nodes, a `BlockArchive`
+//! is given to the consensus in its channels: each final block is handed to it once, with its operations,
+//! right before the graph drops them, either because the block is pruned or because it is stripped of its
+//! operations while kept to help bootstrapping nodes. Blocks already stripped of their operations when the
+//! node got them, like the ones received at bootstrap, are not archived.
+
+use massa_models::{block::FilledBlock, block_id::BlockId, slot::Slot};
+use serde::{Deserialize, Serialize};
+
+/// Final block handed to the archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedBlock {
+    /// id of the block
+    pub block_id: BlockId,
+    /// slot of the block
+    pub slot: Slot,
+    /// header and operations of the block
+    pub block: FilledBlock,
+}
+
+/// Sink receiving the final blocks before they leave the block graph
+pub trait BlockArchive: Send + Sync {
+    /// Archives a final block.
+    /// Called from the consensus thread while it prunes the graph: it must return quickly.
+    fn archive_block(&self, block: ArchivedBlock);
+}
@@ -1,7 +1,7 @@
 use massa_models::{
     active_block::ActiveBlock,
     address::Address,
-    block::{Block, SecureShareBlock},
+    block::{Block, FilledBlock, SecureShareBlock},
     block_header::SecuredHeader,
     block_id::BlockId,
     prehash::PreHashSet,
@@ -88,6 +88,33 @@ impl StorageOrBlock {
         }
     }
 
+    /// Return the underlying block with its operations.
+    /// The operations are `None` if they were dropped from the storage, or if only the block is kept.
+    pub fn to_filled_block(&self, block_id: &BlockId) -> FilledBlock {
+        let block = self.clone_block(block_id);
+        let operations = match self {
+            StorageOrBlock::Storage(storage) => {
+                let stored_operations = storage.read_operations();
+                block
+                    .content
+                    .operations
+                    .iter()
+                    .map(|op_id| (*op_id, stored_operations.get(op_id).cloned()))
+                    .collect()
+            }
+            StorageOrBlock::Block(_) => block
+                .content
+                .operations
+                .iter()
+                .map(|op_id| (*op_id, None))
+                .collect(),
+        };
+        FilledBlock {
+            header: block.content.header,
+            operations,
+        }
+    }
+
     /// Convert any StorageOrBlock variant into a StorageOrBlock::Block variant.
     /// This effectively drops the operations of the block.
     pub fn strip_to_block(&mut self, block_id: &BlockId) {
@@ -182,3 +209,112 @@ pub enum Status {
     /// with enough fitness to be part of immutable history
     Final,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_models::{
+        amount::Amount,
+        block::BlockSerializer,
+        block_header::{BlockHeader, BlockHeaderSerializer},
+        operation::{
+            compute_operations_hash, Operation, OperationId, OperationIdSerializer,
+            OperationSerializer, OperationType, SecureShareOperation,
+        },
+        secure_share::SecureShareContent,
+    };
+    use massa_signature::KeyPair;
+
+    fn create_operation(keypair: &KeyPair, roll_count: u64) -> SecureShareOperation {
+        let content = Operation {
+            fee: Amount::zero(),
+            expire_period: 10,
+            op: OperationType::RollBuy { roll_count },
+        };
+        Operation::new_verifiable(content, OperationSerializer::new(), keypair).unwrap()
+    }
+
+    fn create_block(keypair: &KeyPair, operations: &[SecureShareOperation]) -> SecureShareBlock {
+        let op_ids: Vec<_> = operations.iter().map(|op| op.id).collect();
+        let header = BlockHeader::new_verifiable(
+            BlockHeader {
+                current_version: 0,
+                announced_version: None,
+                slot: Slot::new(1, 0),
+                parents: vec![
+                    BlockId::generate_from_hash(massa_hash::Hash::compute_from(b"Genesis 0")),
+                    BlockId::generate_from_hash(massa_hash::Hash::compute_from(b"Genesis 1")),
+                ],
+                operation_merkle_root: compute_operations_hash(
+                    &op_ids,
+                    &OperationIdSerializer::new(),
+                ),
+                endorsements: Vec::new(),
+                denunciations: Vec::new(),
+                extensions: Vec::new(),
+            },
+            BlockHeaderSerializer::new(),
+            keypair,
+        )
+        .unwrap();
+        Block::new_verifiable(
+            Block {
+                header,
+                operations: op_ids,
+            },
+            BlockSerializer::new(),
+            keypair,
+        )
+        .unwrap()
+    }
+
+    /// Operations of a filled block, with the id of the operation when it is filled
+    fn filled_operations(filled_block: &FilledBlock) -> Vec<(OperationId, Option<OperationId>)> {
+        filled_block
+            .operations
+            .iter()
+            .map(|(op_id, op)| (*op_id, op.as_ref().map(|op| op.id)))
+            .collect()
+    }
+
+    #[test]
+    fn test_to_filled_block() {
+        let keypair = KeyPair::generate(0).unwrap();
+        let operations = vec![create_operation(&keypair, 1), create_operation(&keypair, 2)];
+        let block = create_block(&keypair, &operations);
+        let mut storage = Storage::create_root();
+        storage.store_operations(operations.clone());
+        storage.store_block(block.clone());
+
+        // the operations kept in storage fill the block, in the order of the block
+        let storage_or_block = StorageOrBlock::Storage(storage.clone());
+        let filled_block = storage_or_block.to_filled_block(&block.id);
+        assert_eq!(filled_block.header.id, block.content.header.id);
+        assert_eq!(
+            filled_operations(&filled_block),
+            vec![
+                (operations[0].id, Some(operations[0].id)),
+                (operations[1].id, Some(operations[1].id)),
+            ]
+        );
+
+        // the operations dropped from the storage are missing
+        std::mem::drop(storage_or_block);
+        storage.drop_operation_refs(&[operations[1].id].into_iter().collect());
+        let mut storage_or_block = StorageOrBlock::Storage(storage);
+        assert_eq!(
+            filled_operations(&storage_or_block.to_filled_block(&block.id)),
+            vec![
+                (operations[0].id, Some(operations[0].id)),
+                (operations[1].id, None),
+            ]
+        );
+
+        // a block stripped of its operations only lists their ids
+        storage_or_block.strip_to_block(&block.id);
+        assert_eq!(
+            filled_operations(&storage_or_block.to_filled_block(&block.id)),
+            vec![(operations[0].id, None), (operations[1].id, None)]
+        );
+    }
+}
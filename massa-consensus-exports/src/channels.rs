@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use massa_channel::sender::MassaSender;
 use massa_execution_exports::ExecutionController;
 use massa_models::block::{FilledBlock, SecureShareBlock};
@@ -8,7 +10,8 @@ use massa_pool_exports::PoolController;
 use massa_pos_exports::SelectorController;
 use massa_protocol_exports::ProtocolController;
 
-use crate::events::ConsensusEvent;
+use crate::archive::BlockArchive;
+use crate::events::{ConsensusEvent, FinalPeriodChange, ForkChoiceEvent};
 
 /// Contains links to other modules of the node to be able to interact with them.
 #[derive(Clone)]
@@ -25,6 +28,8 @@ pub struct ConsensusChannels {
     pub controller_event_tx: MassaSender<ConsensusEvent>,
     /// Structure used by consensus to broadcast all the information about the blocks
     pub broadcasts: ConsensusBroadcasts,
+    /// Archive receiving the final blocks before they are pruned from the graph, on archival nodes
+    pub block_archive: Option<Arc<dyn BlockArchive>>,
 }
 
 /// Structure used to broadcast all the information about the blocks
@@ -36,4 +41,8 @@ pub struct ConsensusBroadcasts {
     pub block_header_sender: tokio::sync::broadcast::Sender<SecureShare<BlockHeader, BlockId>>,
     /// Channel use by Websocket (if they are enable) to broadcast a new block integrated
     pub filled_block_sender: tokio::sync::broadcast::Sender<FilledBlock>,
+    /// Channel used for Websocket broadcast (if enabled) of the changes of the latest final period of each thread
+    pub final_period_sender: tokio::sync::broadcast::Sender<FinalPeriodChange>,
+    /// Channel used for Websocket broadcast (if enabled) of the changes of the fork choice
+    pub fork_choice_sender: tokio::sync::broadcast::Sender<ForkChoiceEvent>,
 }
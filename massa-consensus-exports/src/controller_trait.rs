@@ -1,4 +1,5 @@
 use crate::block_graph_export::BlockGraphExport;
+use crate::events::{DeepReorgReport, ForkChoiceEventReceiver};
 use crate::{bootstrapable_graph::BootstrapableGraph, error::ConsensusError};
 use massa_models::prehash::PreHashSet;
 use massa_models::streaming_step::StreamingStep;
@@ -121,6 +122,23 @@ pub trait ConsensusController: Send + Sync {
     /// * `header`: the header of the block to mark as invalid
     fn mark_invalid_block(&self, block_id: BlockId, header: SecureShare<BlockHeader, BlockId>);
 
+    /// Subscribe to the changes of the fork choice: new blockclique members, stale and final blocks, reorganizations.
+    /// Events are only sent if broadcasts are enabled.
+    ///
+    /// # Returns
+    /// A receiver of the events sent after the subscription
+    fn subscribe_fork_choice_events(&self) -> ForkChoiceEventReceiver;
+
+    /// Get the reorganization held because it is deeper than `max_reorg_depth`, if any.
+    /// Block and endorsement production is paused while a reorganization is held.
+    fn get_held_reorg(&self) -> Option<DeepReorgReport>;
+
+    /// Confirm the reorganization currently held, so that it is applied.
+    ///
+    /// # Returns
+    /// The confirmed reorganization, or `None` if no reorganization was held
+    fn confirm_reorg(&self) -> Option<DeepReorgReport>;
+
     /// Returns a boxed clone of self.
     /// Useful to allow cloning `Box<dyn ConsensusController>`.
     fn clone_box(&self) -> Box<dyn ConsensusController>;
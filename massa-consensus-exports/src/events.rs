@@ -1,3 +1,56 @@
+use massa_models::{block_id::BlockId, slot::Slot};
+use massa_time::MassaTime;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// New latest final period of a thread, broadcast for the light consumers that only follow finality
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FinalPeriodChange {
+    /// thread of the final block
+    pub thread: u8,
+    /// period of the latest final block of the thread
+    pub period: u64,
+}
+
+/// Change in the fork choice of the node, broadcast for the consumers following the chain reorganizations.
+///
+/// The events of a single graph update are sent in this order: reorganization, new blockclique members,
+/// stale blocks, final blocks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForkChoiceEvent {
+    /// blocks left the blockclique in favor of a competing clique
+    Reorg {
+        /// number of non-final blocks that left the blockclique
+        depth: usize,
+        /// blocks that left the blockclique, ordered by slot
+        removed_block_ids: Vec<BlockId>,
+    },
+    /// the block became a member of the blockclique
+    BlockcliqueMember {
+        /// id of the block
+        block_id: BlockId,
+        /// slot of the block
+        slot: Slot,
+    },
+    /// the block became stale
+    Stale {
+        /// id of the block
+        block_id: BlockId,
+        /// slot of the block
+        slot: Slot,
+    },
+    /// the block became final
+    Final {
+        /// id of the block
+        block_id: BlockId,
+        /// slot of the block
+        slot: Slot,
+    },
+}
+
+/// Receiver of the fork choice events, see `ConsensusController::subscribe_fork_choice_events`
+pub type ForkChoiceEventReceiver = tokio::sync::broadcast::Receiver<ForkChoiceEvent>;
+
 /// Events that are emitted by consensus.
 #[derive(Debug, Clone)]
 pub enum ConsensusEvent {
@@ -5,4 +58,127 @@ pub enum ConsensusEvent {
     NeedSync,
     /// Network is ended should be send after `end_timestamp`
     Stop,
+    /// finality has not progressed for longer than `finality_stall_timeout`
+    FinalityStall(FinalityStallReport),
+    /// a reorganization deeper than `max_reorg_depth` is held until the operator confirms it
+    DeepReorg(DeepReorgReport),
+}
+
+/// Reorganization held because it would discard more executed blocks than `max_reorg_depth`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeepReorgReport {
+    /// number of executed non-final blocks the reorganization would discard
+    pub depth: u64,
+    /// configured maximum depth
+    pub max_reorg_depth: u64,
+    /// blocks the reorganization would discard, ordered by slot
+    pub discarded_blocks: Vec<(Slot, BlockId)>,
+}
+
+impl Display for DeepReorgReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "depth={} max_reorg_depth={}",
+            self.depth, self.max_reorg_depth
+        )?;
+        if let (Some((first_slot, _)), Some((last_slot, _))) =
+            (self.discarded_blocks.first(), self.discarded_blocks.last())
+        {
+            write!(f, " discarded_slots=[{}..{}]", first_slot, last_slot)?;
+        }
+        Ok(())
+    }
+}
+
+/// Recovery action suggested when a finality stall is detected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinalityStallAction {
+    /// the node has no connected peers: check its network connectivity
+    CheckConnectivity,
+    /// blocks arrive too far from their slot time: synchronize the system clock
+    SynchronizeClock,
+    /// the node sees several competing cliques: it may be on a fork and should bootstrap again
+    Resync,
+    /// SCE-final execution lags behind consensus: check the execution of the node
+    CheckExecution,
+}
+
+impl Display for FinalityStallAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FinalityStallAction::CheckConnectivity => write!(f, "check network connectivity"),
+            FinalityStallAction::SynchronizeClock => write!(f, "synchronize the system clock"),
+            FinalityStallAction::Resync => write!(f, "bootstrap again"),
+            FinalityStallAction::CheckExecution => write!(f, "check execution"),
+        }
+    }
+}
+
+/// Diagnostic context gathered when a finality stall is detected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinalityStallReport {
+    /// time elapsed since a block last became CSS-final
+    pub consensus_stall_duration: MassaTime,
+    /// time elapsed since a slot last became SCE-final
+    pub execution_stall_duration: MassaTime,
+    /// latest CSS-final period in each thread
+    pub latest_final_periods: Vec<u64>,
+    /// latest executed SCE-final slot
+    pub execution_final_cursor: Option<Slot>,
+    /// number of SCE-final slots waiting for execution
+    pub execution_backlog: Option<u64>,
+    /// number of connected peers
+    pub connected_peers: Option<usize>,
+    /// number of max cliques: more than one means the node sees diverging histories
+    pub clique_count: usize,
+    /// number of blocks that became stale within the stats time span
+    pub recent_stale_blocks: usize,
+    /// median delay between the slot time of recently received blocks and their reception, in milliseconds.
+    /// A negative or very large value hints at a skewed local clock.
+    pub median_block_arrival_delay_ms: Option<i64>,
+    /// suggested recovery actions
+    pub suggested_actions: Vec<FinalityStallAction>,
+}
+
+impl Display for FinalityStallReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "consensus_stall={}ms execution_stall={}ms latest_final_periods={:?} ",
+            self.consensus_stall_duration.as_millis(),
+            self.execution_stall_duration.as_millis(),
+            self.latest_final_periods
+        )?;
+        match self.execution_final_cursor {
+            Some(slot) => write!(f, "execution_final_cursor={} ", slot)?,
+            None => write!(f, "execution_final_cursor=unknown ")?,
+        }
+        match self.execution_backlog {
+            Some(backlog) => write!(f, "execution_backlog={} ", backlog)?,
+            None => write!(f, "execution_backlog=unknown ")?,
+        }
+        match self.connected_peers {
+            Some(peers) => write!(f, "connected_peers={} ", peers)?,
+            None => write!(f, "connected_peers=unknown ")?,
+        }
+        write!(
+            f,
+            "clique_count={} recent_stale_blocks={} ",
+            self.clique_count, self.recent_stale_blocks
+        )?;
+        match self.median_block_arrival_delay_ms {
+            Some(delay) => write!(f, "median_block_arrival_delay={}ms", delay)?,
+            None => write!(f, "median_block_arrival_delay=unknown")?,
+        }
+        if !self.suggested_actions.is_empty() {
+            let actions: Vec<String> = self
+                .suggested_actions
+                .iter()
+                .map(|action| action.to_string())
+                .collect();
+            write!(f, " suggested_actions=[{}]", actions.join(", "))?;
+        }
+        Ok(())
+    }
 }
@@ -182,7 +182,8 @@ impl Deserializer<ExportActiveBlock> for ExportActiveBlockDeserializer {
     ///             )
     ///             .unwrap(),
     ///         ],
-    ///     denunciations: vec![],},
+    ///     denunciations: vec![],
+    ///     extensions: vec![],},
     ///     BlockHeaderSerializer::new(),
     ///     &keypair,
     /// )
@@ -0,0 +1,169 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Header-only validation against the local selector and versioning data.
+//!
+//! These checks do not need the block body nor the block graph:
+//! they tell whether the creator of a header was entitled to produce it at its slot,
+//! whether its endorsers were drawn for their indices, and whether its network versions
+//! are consistent with the local MIP store.
+//! They are used by the protocol pre-filters and by the API for external monitoring tools.
+//!
+//! Signatures are not verified here: callers are expected to verify them
+//! (after these cheap checks) when the header comes from an untrusted source.
+
+use displaydoc::Display;
+use massa_models::{
+    address::Address, block_header::SecuredHeader, error::ModelsError, slot::Slot,
+    timeslots::get_block_slot_timestamp,
+};
+use massa_pos_exports::SelectorController;
+use massa_time::MassaTime;
+use massa_versioning::versioning::MipStore;
+use thiserror::Error;
+
+/// Header check error
+#[non_exhaustive]
+#[derive(Display, Error, Debug, Clone)]
+pub enum HeaderCheckError {
+    /// header is a genesis header
+    Genesis,
+    /// incompatible network version: local is {local}, header has {received}
+    IncompatibleNetworkVersion {
+        /// version active at the slot of the header according to the local MIP store
+        local: u32,
+        /// version of the header
+        received: u32,
+    },
+    /// outdated announced network version: local is {local}, header announces {announced}
+    OutdatedAnnouncedNetworkVersion {
+        /// version active at the slot of the header according to the local MIP store
+        local: u32,
+        /// version announced by the header
+        announced: u32,
+    },
+    /// draws of slot {0} are not available
+    DrawsUnavailable(Slot),
+    /// invalid block producer selection: expected address {expected}, got {got}
+    WrongProducer {
+        /// address drawn for the slot
+        expected: Address,
+        /// creator of the header
+        got: Address,
+    },
+    /// invalid endorsement index: {0}
+    InvalidEndorsementIndex(u32),
+    /// endorser draw mismatch at index {index}: expected address {expected}, got {got}
+    WrongEndorser {
+        /// index of the endorsement
+        index: u32,
+        /// address drawn for the index
+        expected: Address,
+        /// creator of the endorsement
+        got: Address,
+    },
+    /// models error: {0}
+    ModelsError(#[from] ModelsError),
+}
+
+/// Checks that the network versions of a header match the local MIP store:
+/// the current version must be the one active at the slot of the header,
+/// and the announced version, if any, must be newer.
+pub fn check_header_network_version(
+    header: &SecuredHeader,
+    mip_store: &MipStore,
+    thread_count: u8,
+    t0: MassaTime,
+    genesis_timestamp: MassaTime,
+) -> Result<(), HeaderCheckError> {
+    let timestamp =
+        get_block_slot_timestamp(thread_count, t0, genesis_timestamp, header.content.slot)?;
+    let local = mip_store.get_network_version_active_at(timestamp);
+    if header.content.current_version != local {
+        return Err(HeaderCheckError::IncompatibleNetworkVersion {
+            local,
+            received: header.content.current_version,
+        });
+    }
+    if let Some(announced) = header.content.announced_version {
+        if announced <= local {
+            return Err(HeaderCheckError::OutdatedAnnouncedNetworkVersion { local, announced });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the creator of a header is the producer drawn by the selector for its slot.
+///
+/// Returns `HeaderCheckError::DrawsUnavailable` if the selector has not computed the draws of the slot yet.
+pub fn check_header_producer(
+    header: &SecuredHeader,
+    selector_controller: &dyn SelectorController,
+) -> Result<(), HeaderCheckError> {
+    let slot = header.content.slot;
+    let producer = selector_controller
+        .get_producer(slot)
+        .map_err(|_| HeaderCheckError::DrawsUnavailable(slot))?;
+    if producer != header.content_creator_address {
+        return Err(HeaderCheckError::WrongProducer {
+            expected: producer,
+            got: header.content_creator_address,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that the creators of the endorsements of a header
+/// are the endorsers drawn by the selector for their index at the slot of the header.
+///
+/// Returns `HeaderCheckError::DrawsUnavailable` if the selector has not computed the draws of the slot yet.
+pub fn check_header_endorsers(
+    header: &SecuredHeader,
+    selector_controller: &dyn SelectorController,
+) -> Result<(), HeaderCheckError> {
+    if header.content.endorsements.is_empty() {
+        return Ok(());
+    }
+    let slot = header.content.slot;
+    let endorsement_draws = selector_controller
+        .get_selection(slot)
+        .map_err(|_| HeaderCheckError::DrawsUnavailable(slot))?
+        .endorsements;
+    for endorsement in header.content.endorsements.iter() {
+        let index = endorsement.content.index;
+        let Some(expected) = endorsement_draws.get(index as usize) else {
+            return Err(HeaderCheckError::InvalidEndorsementIndex(index));
+        };
+        if *expected != endorsement.content_creator_address {
+            return Err(HeaderCheckError::WrongEndorser {
+                index,
+                expected: *expected,
+                got: endorsement.content_creator_address,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Validates a header from the local selector and versioning data only,
+/// without looking at the block body nor at the block graph.
+///
+/// Checks performed:
+/// - Not genesis
+/// - Network versions, see `check_header_network_version`
+/// - Block producer matches the draws, see `check_header_producer`
+/// - Endorsers match the draws, see `check_header_endorsers`
+pub fn check_header(
+    header: &SecuredHeader,
+    selector_controller: &dyn SelectorController,
+    mip_store: &MipStore,
+    thread_count: u8,
+    t0: MassaTime,
+    genesis_timestamp: MassaTime,
+) -> Result<(), HeaderCheckError> {
+    if header.content.slot.period == 0 || header.content.parents.is_empty() {
+        return Err(HeaderCheckError::Genesis);
+    }
+    check_header_network_version(header, mip_store, thread_count, t0, genesis_timestamp)?;
+    check_header_producer(header, selector_controller)?;
+    check_header_endorsers(header, selector_controller)
+}
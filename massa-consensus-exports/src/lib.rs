@@ -1,6 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 //! Definition and exports of the graph types and errors.
 
+mod archive;
 mod channels;
 mod controller_trait;
 mod settings;
@@ -11,7 +12,9 @@ pub mod bootstrapable_graph;
 pub mod error;
 pub mod events;
 pub mod export_active_block;
+pub mod header_check;
 
+pub use archive::{ArchivedBlock, BlockArchive};
 pub use channels::{ConsensusBroadcasts, ConsensusChannels};
 pub use controller_trait::{ConsensusController, ConsensusManager};
 pub use settings::ConsensusConfig;
@@ -16,6 +16,8 @@ pub struct ConsensusConfig {
     pub max_discarded_blocks: usize,
     /// Maximum number of blocks allowed in `FutureIncomingBlocks`.
     pub max_future_processing_blocks: usize,
+    /// number of threads checking in parallel the draws of the headers processed at each slot (1 to check them sequentially)
+    pub draw_check_threads: usize,
     /// Maximum number of blocks allowed in `DependencyWaitingBlocks`.
     pub max_dependency_blocks: usize,
     /// old blocks are pruned every `block_db_prune_interval`
@@ -50,6 +52,18 @@ pub struct ConsensusConfig {
     pub broadcast_blocks_channel_capacity: usize,
     /// filled blocks channel capacity
     pub broadcast_filled_blocks_channel_capacity: usize,
+    /// final periods channel capacity
+    pub broadcast_final_periods_channel_capacity: usize,
+    /// fork choice events channel capacity
+    pub broadcast_fork_choice_channel_capacity: usize,
     /// last start period
     pub last_start_period: u64,
+    /// finality is considered stalled when no new CSS-final block or SCE-final slot appeared for this duration (0 disables detection)
+    pub finality_stall_timeout: MassaTime,
+    /// bootstrap again automatically when a finality stall looks caused by the node being on a fork
+    pub finality_stall_auto_resync: bool,
+    /// maximum number of executed non-final blockclique blocks a reorganization may discard before it is held for operator confirmation (0 disables the guard)
+    pub max_reorg_depth: u64,
+    /// bootstrap again automatically instead of waiting for operator confirmation when a reorganization is held
+    pub max_reorg_auto_resync: bool,
 }
@@ -18,6 +18,7 @@ impl Default for ConsensusConfig {
             genesis_key: GENESIS_KEY.clone(),
             max_discarded_blocks: 10000,
             max_future_processing_blocks: 100,
+            draw_check_threads: 1,
             max_dependency_blocks: 2048,
             block_db_prune_interval: MassaTime::from_millis(5000),
             max_gas_per_block: MAX_GAS_PER_BLOCK,
@@ -35,7 +36,13 @@ impl Default for ConsensusConfig {
             broadcast_blocks_headers_channel_capacity: 128,
             broadcast_blocks_channel_capacity: 128,
             broadcast_filled_blocks_channel_capacity: 128,
+            broadcast_final_periods_channel_capacity: 128,
+            broadcast_fork_choice_channel_capacity: 128,
             last_start_period: 0,
+            finality_stall_timeout: MassaTime::from_millis(0),
+            finality_stall_auto_resync: false,
+            max_reorg_depth: 0,
+            max_reorg_auto_resync: false,
         }
     }
 }
@@ -9,4 +9,5 @@ pub enum ConsensusCommand {
     RegisterBlock(BlockId, Slot, Storage, bool),
     RegisterBlockHeader(BlockId, SecureShare<BlockHeader, BlockId>),
     MarkInvalidBlock(BlockId, SecureShare<BlockHeader, BlockId>),
+    ConfirmReorg,
 }
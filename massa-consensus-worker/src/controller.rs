@@ -1,9 +1,13 @@
 use massa_channel::sender::MassaSender;
 use massa_consensus_exports::ConsensusBroadcasts;
 use massa_consensus_exports::{
-    block_graph_export::BlockGraphExport, block_status::BlockStatus,
-    bootstrapable_graph::BootstrapableGraph, error::ConsensusError,
-    export_active_block::ExportActiveBlock, ConsensusController,
+    block_graph_export::BlockGraphExport,
+    block_status::BlockStatus,
+    bootstrapable_graph::BootstrapableGraph,
+    error::ConsensusError,
+    events::{DeepReorgReport, ForkChoiceEventReceiver},
+    export_active_block::ExportActiveBlock,
+    ConsensusController,
 };
 use massa_models::{
     block::{BlockGraphStatus, FilledBlock},
@@ -315,6 +319,23 @@ impl ConsensusController for ConsensusControllerImpl {
         }
     }
 
+    fn subscribe_fork_choice_events(&self) -> ForkChoiceEventReceiver {
+        self.broadcasts.fork_choice_sender.subscribe()
+    }
+
+    fn get_held_reorg(&self) -> Option<DeepReorgReport> {
+        self.shared_state.read().held_reorg.clone()
+    }
+
+    fn confirm_reorg(&self) -> Option<DeepReorgReport> {
+        let held_reorg = self.get_held_reorg()?;
+        if let Err(err) = self.command_sender.try_send(ConsensusCommand::ConfirmReorg) {
+            warn!("error trying to confirm reorganization: {}", err);
+            return None;
+        }
+        Some(held_reorg)
+    }
+
     fn clone_box(&self) -> Box<dyn ConsensusController> {
         Box::new(self.clone())
     }
@@ -1,5 +1,6 @@
 use std::{
     collections::{HashMap, VecDeque},
+    sync::Arc,
     vec,
 };
 
@@ -7,6 +8,7 @@ use massa_consensus_exports::{
     block_graph_export::BlockGraphExport,
     block_status::{BlockStatus, ExportCompiledBlock, HeaderOrBlock, StorageOrBlock},
     error::ConsensusError,
+    events::DeepReorgReport,
     ConsensusChannels, ConsensusConfig,
 };
 use massa_execution_exports::ExecutionBlockMetadata;
@@ -23,9 +25,10 @@ use massa_models::{
 };
 use massa_storage::Storage;
 use massa_time::MassaTime;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use tracing::debug;
 
-use self::blocks_state::BlocksState;
+use self::{blocks_state::BlocksState, verifications::DrawCheckOutcome};
 
 pub mod blocks_state;
 mod clique_computation;
@@ -78,6 +81,9 @@ pub struct ConsensusState {
     pub protocol_blocks: VecDeque<(MassaTime, BlockId)>,
     /// Stale block timestamp
     pub stale_block_stats: VecDeque<MassaTime>,
+    /// Reception time of blocks coming from protocol, with their delay (in milliseconds) relative to their slot time.
+    /// Used to detect clock skew when finality stalls.
+    pub protocol_block_delays: VecDeque<(MassaTime, i64)>,
     /// the time span considered for stats
     pub stats_history_timespan: MassaTime,
     /// the time span considered for desynchronization detection
@@ -86,14 +92,83 @@ pub struct ConsensusState {
     pub wishlist: PreHashMap<BlockId, Option<SecuredHeader>>,
     /// previous blockclique notified to Execution
     pub prev_blockclique: PreHashMap<BlockId, Slot>,
+    /// reorganization deeper than `max_reorg_depth` held until the operator confirms it:
+    /// execution keeps the previous blockclique meanwhile
+    pub held_reorg: Option<DeepReorgReport>,
+    /// whether the operator confirmed the held reorganization
+    pub reorg_confirmed: bool,
     /// Blocks indexed by slot (used for multi-stake limiting). Blocks
     /// should be saved in this map when we receive the header or the full block directly.
     pub nonfinal_active_blocks_per_slot: HashMap<Slot, PreHashSet<BlockId>>,
+    /// Draw checks of the headers processed at the current slot, done ahead in parallel
+    pub(crate) pre_checked_draws: PreHashMap<BlockId, DrawCheckOutcome>,
+    /// Threads checking the draws ahead, `None` if they are checked on the consensus thread only
+    pub(crate) draw_check_pool: Option<Arc<ThreadPool>>,
     /// massa metrics
     pub(crate) massa_metrics: MassaMetrics,
 }
 
 impl ConsensusState {
+    /// Create the consensus state, without any block: the worker fills it on startup
+    pub fn new(
+        config: ConsensusConfig,
+        channels: ConsensusChannels,
+        storage: Storage,
+        massa_metrics: MassaMetrics,
+    ) -> Self {
+        // desync detection timespan
+        let stats_desync_detection_timespan =
+            config.t0.checked_mul(config.periods_per_cycle * 2).unwrap();
+        let draw_check_pool = (config.draw_check_threads > 1).then(|| {
+            Arc::new(
+                ThreadPoolBuilder::new()
+                    .num_threads(config.draw_check_threads)
+                    .thread_name(|index| format!("consensus-draw-check-{}", index))
+                    .build()
+                    .expect("could not start the consensus draw check threads"),
+            )
+        });
+        ConsensusState {
+            storage,
+            max_cliques: vec![Clique {
+                block_ids: PreHashSet::<BlockId>::default(),
+                fitness: 0,
+                is_blockclique: true,
+            }],
+            blocks_state: BlocksState::new(),
+            to_propagate: Default::default(),
+            attack_attempts: Default::default(),
+            new_final_blocks: Default::default(),
+            new_stale_blocks: Default::default(),
+            active_index_without_ops: Default::default(),
+            save_final_periods: Default::default(),
+            latest_final_blocks_periods: Default::default(),
+            best_parents: Default::default(),
+            genesis_hashes: Default::default(),
+            gi_head: Default::default(),
+            final_block_stats: Default::default(),
+            stale_block_stats: Default::default(),
+            protocol_blocks: Default::default(),
+            protocol_block_delays: Default::default(),
+            wishlist: Default::default(),
+            launch_time: MassaTime::now(),
+            stats_desync_detection_timespan,
+            stats_history_timespan: std::cmp::max(
+                stats_desync_detection_timespan,
+                config.stats_timespan,
+            ),
+            prev_blockclique: Default::default(),
+            held_reorg: None,
+            reorg_confirmed: false,
+            nonfinal_active_blocks_per_slot: Default::default(),
+            pre_checked_draws: Default::default(),
+            draw_check_pool,
+            massa_metrics,
+            config,
+            channels,
+        }
+    }
+
     /// Get a full active block
     pub fn get_full_active_block(
         &self,
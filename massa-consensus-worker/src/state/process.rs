@@ -6,6 +6,7 @@ use std::{
 use massa_consensus_exports::{
     block_status::{BlockStatus, DiscardReason, HeaderOrBlock, StorageOrBlock},
     error::ConsensusError,
+    events::{ConsensusEvent, DeepReorgReport, FinalPeriodChange, ForkChoiceEvent},
 };
 use massa_execution_exports::ExecutionBlockMetadata;
 use massa_logging::massa_trace;
@@ -22,7 +23,7 @@ use massa_models::{
 use massa_signature::PublicKey;
 use massa_storage::Storage;
 use massa_time::MassaTime;
-use tracing::log::{debug, info};
+use tracing::log::{debug, info, trace, warn};
 
 use crate::state::{
     clique_computation::compute_max_cliques,
@@ -49,6 +50,14 @@ pub(crate) struct BlockInfos {
     pub fitness: u64,
 }
 
+/// Blocks that entered or left the blockclique since the previous notification of execution
+struct BlockcliqueChange {
+    /// blocks that entered the blockclique, with their slot
+    joined: Vec<(BlockId, Slot)>,
+    /// blocks that left the blockclique, with their slot
+    left: PreHashMap<BlockId, Slot>,
+}
+
 impl ConsensusState {
     /// Acknowledge a set of items recursively and process them
     ///
@@ -591,11 +600,89 @@ impl ConsensusState {
         }
     }
 
+    /// Whether a block that left the blockclique was discarded by a reorganization.
+    /// Blocks that left the blockclique because they were final and got pruned are not reorganized.
+    fn discarded_from_blockclique(&self, block_id: &BlockId) -> bool {
+        match self.blocks_state.get(block_id) {
+            Some(BlockStatus::Active { a_block, .. }) => !a_block.is_final,
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// Hold the blockclique change if it would discard more executed blocks than `max_reorg_depth`.
+    /// The reorganization is released once the operator confirms it, once it becomes shallow enough,
+    /// or once one of its blocks becomes final, since finality cannot be reverted anyway.
+    ///
+    /// # Arguments:
+    /// * `blockclique`: the new blockclique
+    /// * `finalized_blocks`: Block that became final and need to be send to execution
+    ///
+    /// # Returns:
+    /// Whether execution must keep the previous blockclique
+    fn hold_deep_reorg(
+        &mut self,
+        blockclique: &PreHashSet<BlockId>,
+        finalized_blocks: &HashMap<Slot, BlockId>,
+    ) -> bool {
+        if self.config.max_reorg_depth == 0 {
+            return false;
+        }
+        let mut discarded_blocks: Vec<(Slot, BlockId)> = self
+            .prev_blockclique
+            .iter()
+            .filter(|(b_id, _slot)| {
+                !blockclique.contains(b_id) && self.discarded_from_blockclique(b_id)
+            })
+            .map(|(b_id, slot)| (*slot, *b_id))
+            .collect();
+        let depth = discarded_blocks.len() as u64;
+        let finalized_elsewhere = finalized_blocks
+            .values()
+            .any(|b_id| !self.prev_blockclique.contains_key(b_id));
+        if depth <= self.config.max_reorg_depth || self.reorg_confirmed || finalized_elsewhere {
+            if let Some(report) = self.held_reorg.take() {
+                info!("releasing held reorganization: {}", report);
+            }
+            self.reorg_confirmed = false;
+            return false;
+        }
+
+        discarded_blocks.sort_unstable();
+        let report = DeepReorgReport {
+            depth,
+            max_reorg_depth: self.config.max_reorg_depth,
+            discarded_blocks,
+        };
+        if self.held_reorg.replace(report.clone()).is_none() {
+            // alert once per held reorganization
+            warn!(
+                "holding deep reorganization until it is confirmed: {}",
+                report
+            );
+            let _ = self
+                .channels
+                .controller_event_tx
+                .send(ConsensusEvent::DeepReorg(report));
+            if self.config.max_reorg_auto_resync {
+                warn!("deep reorganization held, requesting a new bootstrap");
+                let _ = self
+                    .channels
+                    .controller_event_tx
+                    .send(ConsensusEvent::NeedSync);
+            }
+        }
+        true
+    }
+
     /// Notify execution about blockclique changes and finalized blocks.
     ///
     /// # Arguments:
     /// * `finalized_blocks`: Block that became final and need to be send to execution
-    fn notify_execution(&mut self, finalized_blocks: HashMap<Slot, BlockId>) {
+    ///
+    /// # Returns:
+    /// The blocks that entered or left the blockclique, none while a deep reorganization is held
+    fn notify_execution(&mut self, finalized_blocks: HashMap<Slot, BlockId>) -> BlockcliqueChange {
         // List new block storage instances that Execution doesn't know about.
         // That's blocks that have not been sent to execution before, ie. in the previous blockclique).
         let mut new_blocks_metadata: PreHashMap<BlockId, ExecutionBlockMetadata> = finalized_blocks
@@ -622,10 +709,24 @@ impl ConsensusState {
             })
             .collect();
 
+        // Keep the previous blockclique while a deep reorganization is held.
+        let blockclique = self.get_blockclique();
+        if self.hold_deep_reorg(&blockclique, &finalized_blocks) {
+            if !finalized_blocks.is_empty() {
+                self.channels
+                    .execution_controller
+                    .update_blockclique_status(finalized_blocks, None, new_blocks_metadata);
+            }
+            return BlockcliqueChange {
+                joined: Vec::new(),
+                left: PreHashMap::default(),
+            };
+        }
+
         // Get new blockclique block list with slots.
         let mut blockclique_changed = false;
-        let new_blockclique: PreHashMap<BlockId, Slot> = self
-            .get_blockclique()
+        let mut joined_blockclique = Vec::new();
+        let new_blockclique: PreHashMap<BlockId, Slot> = blockclique
             .iter()
             .map(|b_id| {
                 if let Some(slot) = self.prev_blockclique.remove(b_id) {
@@ -645,6 +746,7 @@ impl ConsensusState {
                         _ => panic!("blockclique block not found in active blocks and/or its operations are missing"),
                     };
                     new_blocks_metadata.insert(*b_id, ExecutionBlockMetadata { same_thread_parent_creator: a_block.same_thread_parent_creator, storage: Some(storage.clone()) });
+                    joined_blockclique.push((*b_id, a_block.slot));
                     (*b_id, a_block.slot)
                 }
             })
@@ -657,11 +759,14 @@ impl ConsensusState {
         }
         // Overwrite previous blockclique.
         // Should still be done even if unchanged because elements were removed from it above.
-        self.prev_blockclique = new_blockclique.clone();
+        let change = BlockcliqueChange {
+            joined: joined_blockclique,
+            left: mem::replace(&mut self.prev_blockclique, new_blockclique.clone()),
+        };
 
         if finalized_blocks.is_empty() && !blockclique_changed {
             // There are no changes (neither block finalizations not blockclique changes) to send to execution.
-            return;
+            return change;
         }
 
         // Notify execution of block finalizations and blockclique changes
@@ -676,6 +781,65 @@ impl ConsensusState {
                 },
                 new_blocks_metadata,
             );
+        change
+    }
+
+    /// Broadcast the changes of the fork choice caused by the latest graph update.
+    /// The blocks are listed by slot within each kind of event.
+    ///
+    /// # Arguments:
+    /// * `blockclique_change`: the blocks that entered or left the blockclique
+    /// * `stale_blocks`: the blocks that became stale
+    /// * `final_blocks`: the blocks that became final
+    fn broadcast_fork_choice_events(
+        &self,
+        blockclique_change: BlockcliqueChange,
+        mut stale_blocks: Vec<(BlockId, Slot)>,
+        mut final_blocks: Vec<(BlockId, Slot)>,
+    ) {
+        let by_slot = |(b_id, slot): &(BlockId, Slot)| (*slot, *b_id);
+        let mut removed: Vec<(BlockId, Slot)> = blockclique_change
+            .left
+            .into_iter()
+            .filter(|(b_id, _slot)| self.discarded_from_blockclique(b_id))
+            .collect();
+        removed.sort_unstable_by_key(by_slot);
+        let mut joined = blockclique_change.joined;
+        joined.sort_unstable_by_key(by_slot);
+        stale_blocks.sort_unstable_by_key(by_slot);
+        final_blocks.sort_unstable_by_key(by_slot);
+        let mut events = Vec::new();
+        if !removed.is_empty() {
+            events.push(ForkChoiceEvent::Reorg {
+                depth: removed.len(),
+                removed_block_ids: removed.into_iter().map(|(b_id, _slot)| b_id).collect(),
+            });
+        }
+        events.extend(
+            joined
+                .into_iter()
+                .map(|(block_id, slot)| ForkChoiceEvent::BlockcliqueMember { block_id, slot }),
+        );
+        events.extend(
+            stale_blocks
+                .into_iter()
+                .map(|(block_id, slot)| ForkChoiceEvent::Stale { block_id, slot }),
+        );
+        events.extend(
+            final_blocks
+                .into_iter()
+                .map(|(block_id, slot)| ForkChoiceEvent::Final { block_id, slot }),
+        );
+        for event in events {
+            if let Err(err) = self.channels.broadcasts.fork_choice_sender.send(event) {
+                trace!(
+                    "error, failed to broadcast fork choice event due to: {}",
+                    err
+                );
+                // nobody is subscribed: the other events would fail the same way
+                break;
+            }
+        }
     }
 
     /// call me if the block database changed
@@ -686,14 +850,15 @@ impl ConsensusState {
     /// 3. get new final blocks
     /// 4. get blockclique
     /// 5. notify Execution
-    /// 6. Process new final blocks
-    /// 7. Notify pool of new final ops
-    /// 8. Notify PoS of final blocks
-    /// 9. notify protocol of block wish list
-    /// 10. note new latest final periods (prune graph if changed)
-    /// 11. add stale blocks to stats
+    /// 6. broadcast fork choice events
+    /// 7. Process new final blocks
+    /// 8. Notify pool of new final ops
+    /// 9. Notify PoS of final blocks
+    /// 10. notify protocol of block wish list
+    /// 11. note new latest final periods (prune graph if changed)
+    /// 12. add stale blocks to stats
     pub fn block_db_changed(&mut self) -> Result<(), ConsensusError> {
-        let final_block_slots = {
+        let (final_block_slots, stale_blocks) = {
             massa_trace!("consensus.consensus_worker.block_db_changed", {});
 
             // Propagate new blocks
@@ -743,14 +908,25 @@ impl ConsensusState {
             // add stale blocks to stats
             let new_stale_block_ids_creators_slots = mem::take(&mut self.new_stale_blocks);
             let timestamp = MassaTime::now();
-            for (_b_id, (_b_creator, _b_slot)) in new_stale_block_ids_creators_slots.into_iter() {
+            let mut stale_blocks = Vec::with_capacity(new_stale_block_ids_creators_slots.len());
+            for (b_id, (_b_creator, b_slot)) in new_stale_block_ids_creators_slots.into_iter() {
                 self.stale_block_stats.push_back(timestamp);
+                stale_blocks.push((b_id, b_slot));
             }
-            final_block_slots
+            (final_block_slots, stale_blocks)
         };
 
         // notify execution
-        self.notify_execution(final_block_slots);
+        let final_blocks: Vec<(BlockId, Slot)> = final_block_slots
+            .iter()
+            .map(|(slot, b_id)| (*b_id, *slot))
+            .collect();
+        let blockclique_change = self.notify_execution(final_block_slots);
+
+        // broadcast fork choice events
+        if self.config.broadcast_enabled {
+            self.broadcast_fork_choice_events(blockclique_change, stale_blocks, final_blocks);
+        }
 
         // notify protocol of block wishlist
         let new_wishlist = self.get_block_wishlist()?;
@@ -795,6 +971,25 @@ impl ConsensusState {
             self.channels
                 .pool_controller
                 .notify_final_cs_periods(&latest_final_periods);
+            // broadcast the threads whose latest final period changed
+            if self.config.broadcast_enabled {
+                for (thread, period) in latest_final_periods.iter().enumerate() {
+                    if self.save_final_periods.get(thread) == Some(period) {
+                        continue;
+                    }
+                    let change = FinalPeriodChange {
+                        thread: thread as u8,
+                        period: *period,
+                    };
+                    if let Err(err) = self.channels.broadcasts.final_period_sender.send(change) {
+                        trace!(
+                            "error, failed to broadcast final period change {:?} due to: {}",
+                            change,
+                            err
+                        );
+                    }
+                }
+            }
             // update final periods
             self.save_final_periods = latest_final_periods;
         }
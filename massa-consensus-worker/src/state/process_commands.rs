@@ -6,7 +6,8 @@ use massa_consensus_exports::{
 };
 use massa_logging::massa_trace;
 use massa_models::{
-    block_header::SecuredHeader, block_id::BlockId, denunciation::DenunciationPrecursor, slot::Slot,
+    block_header::SecuredHeader, block_id::BlockId, denunciation::DenunciationPrecursor,
+    slot::Slot, timeslots::get_block_slot_timestamp,
 };
 use massa_storage::Storage;
 use massa_time::MassaTime;
@@ -95,6 +96,19 @@ impl ConsensusState {
         if !created {
             let now = MassaTime::now();
             self.protocol_blocks.push_back((now, block_id));
+            if let Ok(slot_time) = get_block_slot_timestamp(
+                self.config.thread_count,
+                self.config.t0,
+                self.config.genesis_timestamp,
+                slot,
+            ) {
+                let delay = if now >= slot_time {
+                    now.saturating_sub(slot_time).as_millis() as i64
+                } else {
+                    -(slot_time.saturating_sub(now).as_millis() as i64)
+                };
+                self.protocol_block_delays.push_back((now, delay));
+            }
         }
 
         debug!("received block {} for slot {}", block_id, slot);
@@ -1,8 +1,9 @@
 use core::panic;
 
 use massa_consensus_exports::{
-    block_status::{BlockStatus, DiscardReason, HeaderOrBlock},
+    block_status::{BlockStatus, DiscardReason, HeaderOrBlock, StorageOrBlock},
     error::ConsensusError,
+    ArchivedBlock,
 };
 use massa_logging::massa_trace;
 use massa_models::{
@@ -41,6 +42,18 @@ impl ConsensusState {
                         < latest_final_period.saturating_sub(self.config.force_keep_final_periods)
                         && !self.active_index_without_ops.contains(a_block)
                     {
+                        // the operations of the block are about to be dropped: archive it first
+                        if active_block.is_final {
+                            if let (Some(block_archive), StorageOrBlock::Storage(_)) =
+                                (&self.channels.block_archive, &storage_or_block)
+                            {
+                                block_archive.archive_block(ArchivedBlock {
+                                    block_id: *a_block,
+                                    slot: active_block.slot,
+                                    block: storage_or_block.to_filled_block(a_block),
+                                });
+                            }
+                        }
                         storage_or_block.strip_to_block(a_block);
                         self.active_index_without_ops.insert(*a_block);
                         // reset the list of descendants
@@ -60,13 +73,14 @@ impl ConsensusState {
             .difference(&retain_active)
             .copied()
             .collect();
+        let block_archive = self.channels.block_archive.clone();
         for discard_active_h in to_remove {
             let sequence_number = self.blocks_state.sequence_counter();
             self.blocks_state.transition_map(&discard_active_h, |block_status, block_statuses| {
                 if let Some(
                     BlockStatus::Active {
                         a_block: discarded_active,
-                        ..
+                        storage_or_block,
                     }
                 ) = block_status {
                     // remove from parent's children
@@ -83,6 +97,16 @@ impl ConsensusState {
 
                     massa_trace!("consensus.block_graph.prune_active", {"hash": discard_active_h, "reason": DiscardReason::Final});
                     let block_slot = discarded_active.slot;
+                    // blocks stripped of their operations were archived when they were stripped
+                    if let (Some(block_archive), StorageOrBlock::Storage(_)) =
+                        (&block_archive, &storage_or_block)
+                    {
+                        block_archive.archive_block(ArchivedBlock {
+                            block_id: discard_active_h,
+                            slot: block_slot,
+                            block: storage_or_block.to_filled_block(&discard_active_h),
+                        });
+                    }
                     let block_creator = discarded_active.creator_address;
                     let block_parents = discarded_active.parents.iter().map(|(p, _)| *p).collect();
                     discarded_finals.insert(discard_active_h, *discarded_active);
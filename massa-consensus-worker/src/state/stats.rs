@@ -29,10 +29,15 @@ impl ConsensusState {
             .filter(|t| **t >= timespan_start && **t < timespan_end)
             .count() as u64;
         let clique_count = self.get_clique_count() as u64;
+        let pending_block_count = (self.blocks_state.incoming_blocks().len()
+            + self.blocks_state.waiting_for_slot_blocks().len()
+            + self.blocks_state.waiting_for_dependencies_blocks().len())
+            as u64;
         Ok(ConsensusStats {
             final_block_count,
             stale_block_count,
             clique_count,
+            pending_block_count,
             start_timespan: timespan_start,
             end_timespan: timespan_end,
         })
@@ -105,6 +110,13 @@ impl ConsensusState {
                 break;
             }
         }
+        while let Some((t, _)) = self.protocol_block_delays.front() {
+            if t < &start_time {
+                self.protocol_block_delays.pop_front();
+            } else {
+                break;
+            }
+        }
         Ok(())
     }
 }
@@ -39,8 +39,11 @@ impl ConsensusState {
 
         massa_trace!("consensus.block_graph.slot_tick", {});
 
-        // process those elements
-        self.rec_process(to_process, Some(current_slot))?;
+        // check the draws of those elements in parallel, then process them
+        self.pre_checked_draws = self.check_draws_ahead(&to_process);
+        let processed = self.rec_process(to_process, Some(current_slot));
+        self.pre_checked_draws.clear();
+        processed?;
 
         // Update the stats
         self.stats_tick()?;
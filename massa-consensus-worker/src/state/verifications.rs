@@ -1,11 +1,22 @@
+use std::collections::BTreeSet;
+
 use super::{process::BlockInfos, ConsensusState};
 use massa_consensus_exports::block_status::{BlockStatus, DiscardReason, HeaderOrBlock};
 use massa_logging::massa_trace;
 use massa_models::{
-    block_header::SecuredHeader, block_id::BlockId, prehash::PreHashSet, slot::Slot,
+    block_header::SecuredHeader,
+    block_id::BlockId,
+    endorsement::SecureShareEndorsement,
+    prehash::{PreHashMap, PreHashSet},
+    slot::Slot,
 };
+use rayon::{prelude::ParallelIterator, slice::ParallelSlice};
 use tracing::warn;
 
+/// Minimum number of headers checked by each thread when the draws are checked in parallel:
+/// below that, the trip to the draw check pool costs more than it saves
+const MIN_HEADERS_PER_DRAW_CHECK_THREAD: usize = 16;
+
 /// Possible output of a header check
 #[derive(Debug)]
 pub enum HeaderCheckOutcome {
@@ -35,7 +46,7 @@ pub(crate) enum BlockCheckOutcome {
 }
 
 /// Possible outcomes of endorsements check
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum EndorsementsCheckOutcome {
     /// Everything is ok
     Proceed,
@@ -45,7 +56,112 @@ pub enum EndorsementsCheckOutcome {
     WaitForSlot,
 }
 
+/// Outcome of the draw and signature checks of a header, done ahead of its processing
+#[derive(Debug, Clone)]
+pub(crate) struct DrawCheckOutcome {
+    /// whether the signatures of the header and of its endorsements are valid
+    signatures_valid: bool,
+    /// whether the creator of the block was drawn to produce it, `None` if the draw is not available
+    creator_drawn: Option<bool>,
+    /// outcome of the check of the endorsers draws
+    endorsements: EndorsementsCheckOutcome,
+}
+
 impl ConsensusState {
+    /// Check ahead the draws and the signatures of the headers about to be processed, on the draw check pool.
+    /// They do not depend on the graph, so that they can be checked in parallel
+    /// before the sequential processing of the headers.
+    ///
+    /// # Arguments:
+    /// * `to_process`: the blocks about to be processed, with their slot
+    ///
+    /// # Returns:
+    /// The outcome of the checks of the headers, empty if they are too few to be worth checking in parallel
+    pub(crate) fn check_draws_ahead(
+        &self,
+        to_process: &BTreeSet<(Slot, BlockId)>,
+    ) -> PreHashMap<BlockId, DrawCheckOutcome> {
+        let Some(pool) = &self.draw_check_pool else {
+            return PreHashMap::default();
+        };
+        let threads = pool
+            .current_num_threads()
+            .min(to_process.len() / MIN_HEADERS_PER_DRAW_CHECK_THREAD);
+        if threads <= 1 {
+            return PreHashMap::default();
+        }
+        let headers: Vec<(BlockId, SecuredHeader)> = to_process
+            .iter()
+            .filter_map(|(_slot, b_id)| match self.blocks_state.get(b_id) {
+                Some(BlockStatus::WaitingForSlot(HeaderOrBlock::Header(header))) => {
+                    Some((*b_id, header.clone()))
+                }
+                Some(BlockStatus::WaitingForSlot(HeaderOrBlock::Block { storage, .. })) => storage
+                    .read_blocks()
+                    .get(b_id)
+                    .map(|block| (*b_id, block.content.header.clone())),
+                _ => None,
+            })
+            .collect();
+        let chunk_size = headers.len().div_ceil(threads).max(1);
+        let outcomes: Vec<(BlockId, DrawCheckOutcome)> = pool.install(|| {
+            headers
+                .par_chunks(chunk_size)
+                .flat_map_iter(|chunk| self.check_draws_chunk(chunk))
+                .collect()
+        });
+        outcomes.into_iter().collect()
+    }
+
+    /// Check the draws and the signatures of a chunk of headers.
+    /// The signatures of the chunk are verified in a batch, and one by one only if the batch holds an invalid one.
+    fn check_draws_chunk(
+        &self,
+        chunk: &[(BlockId, SecuredHeader)],
+    ) -> Vec<(BlockId, DrawCheckOutcome)> {
+        let batch_valid = SecuredHeader::verify_batch(chunk.iter().map(|(_b_id, header)| header))
+            .is_ok()
+            && SecureShareEndorsement::verify_batch(
+                chunk
+                    .iter()
+                    .flat_map(|(_b_id, header)| header.content.endorsements.iter()),
+            )
+            .is_ok();
+        chunk
+            .iter()
+            .map(|(b_id, header)| {
+                let outcome = DrawCheckOutcome {
+                    signatures_valid: batch_valid || Self::check_signatures(header),
+                    creator_drawn: self.check_producer_draw(header),
+                    endorsements: self.check_endorsements(header),
+                };
+                (*b_id, outcome)
+            })
+            .collect()
+    }
+
+    /// Check the signatures of a header and of its endorsements
+    fn check_signatures(header: &SecuredHeader) -> bool {
+        header.verify_signature().is_ok()
+            && header
+                .content
+                .endorsements
+                .iter()
+                .all(|endorsement| endorsement.verify_signature().is_ok())
+    }
+
+    /// Check whether the creator of a block was drawn to produce it.
+    ///
+    /// # Returns:
+    /// `None` if the draw of the slot of the block is not available
+    fn check_producer_draw(&self, header: &SecuredHeader) -> Option<bool> {
+        self.channels
+            .selector_controller
+            .get_producer(header.content.slot)
+            .ok()
+            .map(|slot_draw_address| slot_draw_address == header.content_creator_address)
+    }
+
     // Verify that we haven't already received 2 blocks for this slot
     // If the block isn't already present two times we save it and return false
     // If the block is already present two times we return true
@@ -177,7 +293,7 @@ impl ConsensusState {
             Vec::with_capacity(self.config.thread_count as usize);
         let mut incomp = PreHashSet::<BlockId>::default();
         let mut missing_deps = PreHashSet::<BlockId>::default();
-        let creator_addr = header.content_creator_address;
+        let draw_check = self.pre_checked_draws.get(block_id);
 
         // check that is older than the latest final block in that thread
         // Note: this excludes genesis blocks
@@ -187,17 +303,25 @@ impl ConsensusState {
             return HeaderCheckOutcome::Discard(DiscardReason::Stale);
         }
 
+        // the signatures are verified on reception by protocol,
+        // and again with the draws when they are checked ahead
+        if draw_check.is_some_and(|draw_check| !draw_check.signatures_valid) {
+            return HeaderCheckOutcome::Discard(DiscardReason::Invalid(format!(
+                "Invalid signature for the header in slot:{}",
+                header.content.slot
+            )));
+        }
+
         // check if it was the creator's turn to create this block
         // (step 1 in consensus/pos.md)
-        let slot_draw_address = match self
-            .channels
-            .selector_controller
-            .get_producer(header.content.slot)
-        {
-            Ok(draw) => draw,
-            Err(_) => return HeaderCheckOutcome::WaitForSlot, // TODO properly handle PoS errors
+        let creator_drawn = match draw_check {
+            Some(draw_check) => draw_check.creator_drawn,
+            None => self.check_producer_draw(header),
         };
-        if creator_addr != slot_draw_address {
+        let Some(creator_drawn) = creator_drawn else {
+            return HeaderCheckOutcome::WaitForSlot; // TODO properly handle PoS errors
+        };
+        if !creator_drawn {
             // it was not the creator's turn to create a block for this slot
             return HeaderCheckOutcome::Discard(DiscardReason::Invalid(format!(
                 "Bad creator turn for the slot:{}",
@@ -326,7 +450,11 @@ impl ConsensusState {
         }
 
         // check endorsements
-        match self.check_endorsements(header) {
+        let endorsements_outcome = match draw_check {
+            Some(draw_check) => draw_check.endorsements.clone(),
+            None => self.check_endorsements(header),
+        };
+        match endorsements_outcome {
             EndorsementsCheckOutcome::Proceed => {}
             EndorsementsCheckOutcome::Discard(reason) => {
                 return HeaderCheckOutcome::Discard(reason)
@@ -500,3 +628,169 @@ impl ConsensusState {
         EndorsementsCheckOutcome::Proceed
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use massa_channel::MassaChannel;
+    use massa_consensus_exports::{
+        block_status::{BlockStatus, DiscardReason, HeaderOrBlock},
+        ConsensusBroadcasts, ConsensusChannels, ConsensusConfig,
+    };
+    use massa_execution_exports::MockExecutionController;
+    use massa_metrics::MassaMetrics;
+    use massa_models::{
+        address::Address,
+        block_header::SecuredHeader,
+        config::{ENDORSEMENT_COUNT, THREAD_COUNT},
+        slot::Slot,
+    };
+    use massa_pool_exports::MockPoolController;
+    use massa_pos_exports::{MockSelectorController, Selection};
+    use massa_protocol_exports::MockProtocolController;
+    use massa_signature::KeyPair;
+    use massa_storage::Storage;
+
+    use super::{HeaderCheckOutcome, MIN_HEADERS_PER_DRAW_CHECK_THREAD};
+    use crate::{state::ConsensusState, tests::tools::create_block};
+
+    /// Consensus state drawing `staking_address` for all the slots, counting the producer draws asked to the selector
+    fn state_drawing(staking_address: Address, producer_draws: Arc<AtomicUsize>) -> ConsensusState {
+        let mut selector_controller = Box::new(MockSelectorController::new());
+        selector_controller
+            .expect_get_producer()
+            .returning(move |_| {
+                producer_draws.fetch_add(1, Ordering::Relaxed);
+                Ok(staking_address)
+            });
+        selector_controller
+            .expect_get_selection()
+            .returning(move |_| {
+                Ok(Selection {
+                    producer: staking_address,
+                    endorsements: vec![staking_address; ENDORSEMENT_COUNT as usize],
+                })
+            });
+        let (controller_event_tx, _) = MassaChannel::new(String::from("consensus_event"), Some(10));
+        let (block_sender, _) = tokio::sync::broadcast::channel(10);
+        let (block_header_sender, _) = tokio::sync::broadcast::channel(10);
+        let (filled_block_sender, _) = tokio::sync::broadcast::channel(10);
+        let (final_period_sender, _) = tokio::sync::broadcast::channel(10);
+        let (fork_choice_sender, _) = tokio::sync::broadcast::channel(10);
+        let channels = ConsensusChannels {
+            broadcasts: ConsensusBroadcasts {
+                block_sender,
+                block_header_sender,
+                filled_block_sender,
+                final_period_sender,
+                fork_choice_sender,
+            },
+            controller_event_tx,
+            execution_controller: Box::new(MockExecutionController::new()),
+            protocol_controller: Box::new(MockProtocolController::new()),
+            pool_controller: Box::new(MockPoolController::new()),
+            selector_controller,
+            block_archive: None,
+        };
+        let config = ConsensusConfig {
+            thread_count: 2,
+            draw_check_threads: 2,
+            ..ConsensusConfig::default()
+        };
+        ConsensusState::new(
+            config,
+            channels,
+            Storage::create_root(),
+            MassaMetrics::new(
+                false,
+                "0.0.0.0:9898".parse().unwrap(),
+                THREAD_COUNT,
+                Duration::from_secs(1),
+            )
+            .0,
+        )
+    }
+
+    /// Checks that the headers checked ahead are discarded on an invalid draw or signature,
+    /// and that the valid ones are processed with the cached draws.
+    #[test]
+    fn test_draws_and_signatures_checked_ahead() {
+        let staking_key = KeyPair::generate(0).unwrap();
+        let other_key = KeyPair::generate(0).unwrap();
+        let staking_address = Address::from_public_key(&staking_key.get_public_key());
+        let producer_draws = Arc::new(AtomicUsize::new(0));
+        let mut state = state_drawing(staking_address, producer_draws.clone());
+        let genesis_id = create_block(Slot::new(0, 0), vec![], &staking_key).id;
+        state.latest_final_blocks_periods = vec![(genesis_id, 0); 2];
+
+        // enough headers for the draws to be checked on both threads of the pool
+        let mut headers: Vec<SecuredHeader> = (0..2 * MIN_HEADERS_PER_DRAW_CHECK_THREAD as u64)
+            .map(|index| {
+                let slot = Slot::new(1 + index / 2, (index % 2) as u8);
+                create_block(slot, vec![genesis_id; 2], &staking_key)
+                    .content
+                    .header
+            })
+            .collect();
+        // a header produced by an address that was not drawn
+        let bad_draw = create_block(Slot::new(100, 0), vec![genesis_id; 2], &other_key)
+            .content
+            .header;
+        // a header with the signature of another one
+        let mut bad_signature = create_block(Slot::new(100, 1), vec![genesis_id; 2], &staking_key)
+            .content
+            .header;
+        bad_signature.signature = headers[0].signature;
+        headers.push(bad_draw.clone());
+        headers.push(bad_signature.clone());
+
+        let mut to_process = BTreeSet::new();
+        for header in headers.iter() {
+            state.blocks_state.transition_map(&header.id, |_, _| {
+                Some(BlockStatus::Incoming(HeaderOrBlock::Header(header.clone())))
+            });
+            state.blocks_state.transition_map(&header.id, |_, _| {
+                Some(BlockStatus::WaitingForSlot(HeaderOrBlock::Header(
+                    header.clone(),
+                )))
+            });
+            to_process.insert((header.content.slot, header.id));
+        }
+
+        state.pre_checked_draws = state.check_draws_ahead(&to_process);
+        assert_eq!(state.pre_checked_draws.len(), headers.len());
+        let valid = &headers[0];
+        let valid_check = &state.pre_checked_draws[&valid.id];
+        assert!(valid_check.signatures_valid);
+        assert_eq!(valid_check.creator_drawn, Some(true));
+        assert!(!state.pre_checked_draws[&bad_signature.id].signatures_valid);
+        assert_eq!(
+            state.pre_checked_draws[&bad_draw.id].creator_drawn,
+            Some(false)
+        );
+
+        // the checks are not done again when the headers are processed
+        let draws_before = producer_draws.load(Ordering::Relaxed);
+        assert!(matches!(
+            state.check_header(&valid.id, valid, None),
+            HeaderCheckOutcome::WaitForSlot
+        ));
+        assert!(matches!(
+            state.check_header(&bad_draw.id, &bad_draw, None),
+            HeaderCheckOutcome::Discard(DiscardReason::Invalid(_))
+        ));
+        assert!(matches!(
+            state.check_header(&bad_signature.id, &bad_signature, None),
+            HeaderCheckOutcome::Discard(DiscardReason::Invalid(_))
+        ));
+        assert_eq!(producer_draws.load(Ordering::Relaxed), draws_before);
+    }
+}
@@ -1,4 +1,4 @@
-mod tools;
+pub(crate) mod tools;
 mod universe;
 
 pub mod scenarios;
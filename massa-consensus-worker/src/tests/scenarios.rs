@@ -1,5 +1,6 @@
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
     time::Duration,
 };
 
@@ -7,8 +8,10 @@ use super::{
     tools::{consensus_test, register_block},
     universe::{ConsensusForeignControllers, ConsensusTestUniverse},
 };
-use crate::tests::tools::create_block;
-use massa_consensus_exports::ConsensusConfig;
+use crate::tests::tools::{create_block, create_block_with_operations};
+use massa_consensus_exports::{
+    events::ForkChoiceEvent, ArchivedBlock, BlockArchive, ConsensusConfig,
+};
 use massa_execution_exports::MockExecutionController;
 use massa_models::{
     address::Address, block::BlockGraphStatus, block_id::BlockId, config::ENDORSEMENT_COUNT,
@@ -306,6 +309,278 @@ fn test_parallel_incompatibility() {
     );
 }
 
+/// Checks the fork choice events sent when a competing clique overtakes the blockclique.
+#[test]
+fn test_fork_choice_events() {
+    let thread_count = 2;
+    let staking_key: KeyPair = KeyPair::generate(0).unwrap();
+    let cfg = ConsensusConfig {
+        t0: MassaTime::from_millis(100),
+        thread_count,
+        genesis_timestamp: MassaTime::now(),
+        force_keep_final_periods_without_ops: 128,
+        force_keep_final_periods: 10,
+        delta_f0: 32,
+        ..ConsensusConfig::default()
+    };
+    let storage = Storage::create_root();
+    let staking_address = Address::from_public_key(&staking_key.get_public_key());
+
+    let mut execution_controller = Box::new(MockExecutionController::new());
+    execution_controller
+        .expect_update_blockclique_status()
+        .returning(|_, _, _| {});
+    let mut pool_controller = Box::new(MockPoolController::new());
+    pool_controller
+        .expect_notify_final_cs_periods()
+        .returning(|_| {});
+    pool_controller
+        .expect_add_denunciation_precursor()
+        .returning(|_| {});
+    let mut selector_controller = Box::new(MockSelectorController::new());
+    selector_controller
+        .expect_get_producer()
+        .returning(move |_| Ok(staking_address));
+    selector_controller
+        .expect_get_selection()
+        .returning(move |_| {
+            Ok(Selection {
+                producer: staking_address,
+                endorsements: vec![staking_address; ENDORSEMENT_COUNT as usize],
+            })
+        });
+    consensus_test(
+        cfg,
+        execution_controller,
+        pool_controller,
+        selector_controller,
+        move |consensus_controller| {
+            let mut receiver = consensus_controller.subscribe_fork_choice_events();
+            let mut received_events = || {
+                let mut events = Vec::new();
+                while let Ok(event) = receiver.try_recv() {
+                    events.push(event);
+                }
+                events
+            };
+            let genesis = consensus_controller
+                .get_block_graph_status(None, None)
+                .expect("could not get block graph status")
+                .genesis_blocks;
+
+            let block_1 = create_block(Slot::new(1, 0), vec![genesis[0], genesis[1]], &staking_key);
+            register_block(&consensus_controller, block_1.clone(), storage.clone());
+            let block_2 = create_block(Slot::new(1, 1), vec![genesis[0], genesis[1]], &staking_key);
+            register_block(&consensus_controller, block_2.clone(), storage.clone());
+            std::thread::sleep(Duration::from_millis(300));
+            let events = received_events();
+            for block in [&block_1, &block_2] {
+                assert!(events.contains(&ForkChoiceEvent::BlockcliqueMember {
+                    block_id: block.id,
+                    slot: block.content.header.content.slot,
+                }));
+            }
+
+            // two incompatible blocks: only one of them joins the blockclique
+            let block_3 = create_block(Slot::new(2, 0), vec![block_1.id, genesis[1]], &staking_key);
+            register_block(&consensus_controller, block_3.clone(), storage.clone());
+            let block_4 = create_block(Slot::new(2, 1), vec![genesis[0], block_2.id], &staking_key);
+            register_block(&consensus_controller, block_4.clone(), storage.clone());
+            std::thread::sleep(Duration::from_millis(300));
+            received_events();
+            let status = consensus_controller
+                .get_block_graph_status(None, None)
+                .expect("could not get block graph status");
+            let blockclique = status
+                .max_cliques
+                .iter()
+                .find(|clique| clique.is_blockclique)
+                .expect("missing blockclique");
+
+            // extend the other clique so that it overtakes the blockclique
+            let (winner, loser, extension) = if blockclique.block_ids.contains(&block_3.id) {
+                let extension =
+                    create_block(Slot::new(3, 1), vec![block_1.id, block_4.id], &staking_key);
+                (block_3, block_4, extension)
+            } else {
+                let extension =
+                    create_block(Slot::new(3, 0), vec![block_3.id, block_2.id], &staking_key);
+                (block_4, block_3, extension)
+            };
+            register_block(&consensus_controller, extension.clone(), storage.clone());
+            std::thread::sleep(Duration::from_millis(500));
+            let events = received_events();
+            assert_eq!(
+                events.first(),
+                Some(&ForkChoiceEvent::Reorg {
+                    depth: 1,
+                    removed_block_ids: vec![winner.id],
+                })
+            );
+            for block in [&loser, &extension] {
+                assert!(events.contains(&ForkChoiceEvent::BlockcliqueMember {
+                    block_id: block.id,
+                    slot: block.content.header.content.slot,
+                }));
+            }
+        },
+    );
+}
+
+#[test]
+fn test_deep_reorg_held_until_confirmed() {
+    let thread_count = 2;
+    let staking_key: KeyPair = KeyPair::generate(0).unwrap();
+    let cfg = ConsensusConfig {
+        t0: MassaTime::from_millis(100),
+        thread_count,
+        genesis_timestamp: MassaTime::now(),
+        force_keep_final_periods_without_ops: 128,
+        force_keep_final_periods: 10,
+        delta_f0: 32,
+        max_reorg_depth: 1,
+        ..ConsensusConfig::default()
+    };
+    let storage = Storage::create_root();
+    let staking_address = Address::from_public_key(&staking_key.get_public_key());
+
+    let mut execution_controller = Box::new(MockExecutionController::new());
+    execution_controller
+        .expect_update_blockclique_status()
+        .returning(|_, _, _| {});
+    let mut pool_controller = Box::new(MockPoolController::new());
+    pool_controller
+        .expect_notify_final_cs_periods()
+        .returning(|_| {});
+    pool_controller
+        .expect_add_denunciation_precursor()
+        .returning(|_| {});
+    let mut selector_controller = Box::new(MockSelectorController::new());
+    selector_controller
+        .expect_get_producer()
+        .returning(move |_| Ok(staking_address));
+    selector_controller
+        .expect_get_selection()
+        .returning(move |_| {
+            Ok(Selection {
+                producer: staking_address,
+                endorsements: vec![staking_address; ENDORSEMENT_COUNT as usize],
+            })
+        });
+    consensus_test(
+        cfg,
+        execution_controller,
+        pool_controller,
+        selector_controller,
+        move |consensus_controller| {
+            let mut receiver = consensus_controller.subscribe_fork_choice_events();
+            let genesis = consensus_controller
+                .get_block_graph_status(None, None)
+                .expect("could not get block graph status")
+                .genesis_blocks;
+
+            let block_1 = create_block(Slot::new(1, 0), vec![genesis[0], genesis[1]], &staking_key);
+            register_block(&consensus_controller, block_1.clone(), storage.clone());
+            let block_2 = create_block(Slot::new(1, 1), vec![genesis[0], genesis[1]], &staking_key);
+            register_block(&consensus_controller, block_2.clone(), storage.clone());
+            // two incompatible blocks: only one of them joins the blockclique
+            let block_3 = create_block(Slot::new(2, 0), vec![block_1.id, genesis[1]], &staking_key);
+            register_block(&consensus_controller, block_3.clone(), storage.clone());
+            let block_4 = create_block(Slot::new(2, 1), vec![genesis[0], block_2.id], &staking_key);
+            register_block(&consensus_controller, block_4.clone(), storage.clone());
+            std::thread::sleep(Duration::from_millis(300));
+            let status = consensus_controller
+                .get_block_graph_status(None, None)
+                .expect("could not get block graph status");
+            let block_3_won = status
+                .max_cliques
+                .iter()
+                .find(|clique| clique.is_blockclique)
+                .expect("missing blockclique")
+                .block_ids
+                .contains(&block_3.id);
+
+            // extend the blockclique by one block, then the other clique by two blocks so that it overtakes it
+            let (winner, winner_extension, loser_extensions) = if block_3_won {
+                let winner_extension =
+                    create_block(Slot::new(3, 0), vec![block_3.id, block_2.id], &staking_key);
+                let loser_extension_1 =
+                    create_block(Slot::new(3, 1), vec![block_1.id, block_4.id], &staking_key);
+                let loser_extension_2 = create_block(
+                    Slot::new(4, 1),
+                    vec![block_1.id, loser_extension_1.id],
+                    &staking_key,
+                );
+                (
+                    block_3,
+                    winner_extension,
+                    [loser_extension_1, loser_extension_2],
+                )
+            } else {
+                let winner_extension =
+                    create_block(Slot::new(3, 1), vec![block_1.id, block_4.id], &staking_key);
+                let loser_extension_1 =
+                    create_block(Slot::new(3, 0), vec![block_3.id, block_2.id], &staking_key);
+                let loser_extension_2 = create_block(
+                    Slot::new(4, 0),
+                    vec![loser_extension_1.id, block_2.id],
+                    &staking_key,
+                );
+                (
+                    block_4,
+                    winner_extension,
+                    [loser_extension_1, loser_extension_2],
+                )
+            };
+            register_block(
+                &consensus_controller,
+                winner_extension.clone(),
+                storage.clone(),
+            );
+            std::thread::sleep(Duration::from_millis(300));
+            assert_eq!(consensus_controller.get_held_reorg(), None);
+            for block in loser_extensions {
+                register_block(&consensus_controller, block, storage.clone());
+            }
+            std::thread::sleep(Duration::from_millis(500));
+
+            // the reorganization discards two executed blocks: it is held
+            let mut discarded_blocks = vec![
+                (winner.content.header.content.slot, winner.id),
+                (
+                    winner_extension.content.header.content.slot,
+                    winner_extension.id,
+                ),
+            ];
+            discarded_blocks.sort_unstable();
+            let held_reorg = consensus_controller
+                .get_held_reorg()
+                .expect("deep reorganization not held");
+            assert_eq!(held_reorg.depth, 2);
+            assert_eq!(held_reorg.discarded_blocks, discarded_blocks);
+            while let Ok(event) = receiver.try_recv() {
+                assert!(!matches!(event, ForkChoiceEvent::Reorg { .. }));
+            }
+
+            // once confirmed, the reorganization is applied
+            assert_eq!(consensus_controller.confirm_reorg(), Some(held_reorg));
+            std::thread::sleep(Duration::from_millis(300));
+            assert_eq!(consensus_controller.get_held_reorg(), None);
+            assert_eq!(consensus_controller.confirm_reorg(), None);
+            assert_eq!(
+                receiver.try_recv().ok(),
+                Some(ForkChoiceEvent::Reorg {
+                    depth: 2,
+                    removed_block_ids: discarded_blocks
+                        .into_iter()
+                        .map(|(_slot, block_id)| block_id)
+                        .collect(),
+                })
+            );
+        },
+    );
+}
+
 #[test]
 fn test_parent_in_the_future() {
     let staking_key: KeyPair = KeyPair::generate(0).unwrap();
@@ -400,3 +675,126 @@ fn test_parent_in_the_future() {
         "wrong status"
     );
 }
+
+/// Collects the blocks handed to the archive
+struct CollectingArchive(Mutex<Vec<ArchivedBlock>>);
+
+impl BlockArchive for CollectingArchive {
+    fn archive_block(&self, block: ArchivedBlock) {
+        self.0.lock().unwrap().push(block);
+    }
+}
+
+/// Checks that the final blocks are archived once before being pruned from the graph.
+#[test]
+fn test_final_blocks_archived_before_pruning() {
+    let staking_key: KeyPair = KeyPair::generate(0).unwrap();
+    let cfg = ConsensusConfig {
+        t0: MassaTime::from_millis(100),
+        thread_count: 2,
+        genesis_timestamp: MassaTime::now(),
+        force_keep_final_periods_without_ops: 0,
+        force_keep_final_periods: 0,
+        delta_f0: 3,
+        block_db_prune_interval: MassaTime::from_millis(250),
+        genesis_key: staking_key.clone(),
+        ..ConsensusConfig::default()
+    };
+    let staking_address = Address::from_public_key(&staking_key.get_public_key());
+    let archive = Arc::new(CollectingArchive(Mutex::new(Vec::new())));
+
+    let mut foreign_controllers = ConsensusForeignControllers::new_with_mocks();
+    let storage = foreign_controllers.storage.clone();
+    foreign_controllers.block_archive = Some(archive.clone());
+    foreign_controllers
+        .execution_controller
+        .expect_update_blockclique_status()
+        .returning(|_, _, _| {});
+    foreign_controllers
+        .pool_controller
+        .expect_notify_final_cs_periods()
+        .returning(|_| {});
+    foreign_controllers
+        .pool_controller
+        .expect_add_denunciation_precursor()
+        .returning(|_| {});
+    foreign_controllers
+        .selector_controller
+        .expect_get_producer()
+        .returning(move |_| Ok(staking_address));
+    foreign_controllers
+        .selector_controller
+        .expect_get_selection()
+        .returning(move |_| {
+            Ok(Selection {
+                producer: staking_address,
+                endorsements: vec![staking_address; ENDORSEMENT_COUNT as usize],
+            })
+        });
+
+    let universe = ConsensusTestUniverse::new(foreign_controllers, cfg);
+    let genesis_hashes = universe
+        .module_controller
+        .get_block_graph_status(None, None)
+        .expect("could not get block graph status")
+        .genesis_blocks;
+
+    // a chain long enough for its first blocks to become final and be pruned, with an operation per block
+    let mut parents = genesis_hashes.clone();
+    let mut first_blocks = Vec::new();
+    let mut block_operations = HashMap::new();
+    for period in 1..=8 {
+        let blocks: Vec<_> = (0..2)
+            .map(|thread| {
+                let operation = ConsensusTestUniverse::create_operation(&staking_key, 100);
+                let block = create_block_with_operations(
+                    Slot::new(period, thread),
+                    parents.clone(),
+                    &staking_key,
+                    &[operation.clone()],
+                );
+                (block, operation)
+            })
+            .collect();
+        parents = blocks.iter().map(|(block, _)| block.id).collect();
+        for (block, operation) in blocks {
+            if period == 1 {
+                first_blocks.push(block.id);
+            }
+            block_operations.insert(block.id, operation.clone());
+            let mut block_storage = storage.clone_without_refs();
+            block_storage.store_operations(vec![operation]);
+            register_block(&universe.module_controller, block, block_storage);
+        }
+    }
+    std::thread::sleep(Duration::from_millis(2000));
+
+    let archived = archive.0.lock().unwrap();
+    let archived_ids: HashSet<BlockId> = archived.iter().map(|block| block.block_id).collect();
+    assert_eq!(archived_ids.len(), archived.len(), "block archived twice");
+    for block_id in genesis_hashes.iter().chain(first_blocks.iter()) {
+        assert!(
+            archived_ids.contains(block_id),
+            "pruned final block {} was not archived",
+            block_id
+        );
+    }
+    for block in archived.iter() {
+        assert_eq!(block.block.header.content.slot, block.slot);
+        // the blocks are archived filled with their operations (the genesis blocks have none)
+        match block_operations.get(&block.block_id) {
+            Some(operation) => {
+                assert_eq!(block.block.operations.len(), 1);
+                let (op_id, archived_operation) = &block.block.operations[0];
+                assert_eq!(*op_id, operation.id);
+                assert_eq!(
+                    archived_operation.as_ref().map(|op| op.id),
+                    Some(operation.id),
+                    "block {} archived without its operation",
+                    block.block_id
+                );
+            }
+            None => assert!(block.block.operations.is_empty()),
+        }
+    }
+}
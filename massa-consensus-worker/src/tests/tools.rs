@@ -13,6 +13,9 @@ use massa_models::{
     block_header::{BlockHeader, BlockHeaderSerializer},
     block_id::BlockId,
     config::THREAD_COUNT,
+    operation::{
+        compute_operations_hash, OperationId, OperationIdSerializer, SecureShareOperation,
+    },
     secure_share::SecureShareContent,
     slot::Slot,
 };
@@ -51,6 +54,8 @@ pub fn consensus_test<F>(
     let (block_sender, _block_receiver) = tokio::sync::broadcast::channel(10);
     let (block_header_sender, _block_header_receiver) = tokio::sync::broadcast::channel(10);
     let (filled_block_sender, _filled_block_receiver) = tokio::sync::broadcast::channel(10);
+    let (final_period_sender, _final_period_receiver) = tokio::sync::broadcast::channel(10);
+    let (fork_choice_sender, _fork_choice_receiver) = tokio::sync::broadcast::channel(10);
     let (consensus_controller, mut consensus_manager) = start_consensus_worker(
         cfg.clone(),
         ConsensusChannels {
@@ -58,12 +63,15 @@ pub fn consensus_test<F>(
                 block_sender,
                 block_header_sender,
                 filled_block_sender,
+                final_period_sender,
+                fork_choice_sender,
             },
             controller_event_tx: consensus_event_sender,
             execution_controller,
             protocol_controller,
             pool_controller,
             selector_controller,
+            block_archive: None,
         },
         None,
         storage.clone(),
@@ -92,6 +100,40 @@ pub fn create_block(slot: Slot, best_parents: Vec<BlockId>, creator: &KeyPair) -
     )
 }
 
+pub fn create_block_with_operations(
+    slot: Slot,
+    best_parents: Vec<BlockId>,
+    creator: &KeyPair,
+    operations: &[SecureShareOperation],
+) -> SecureShareBlock {
+    let op_ids: Vec<OperationId> = operations.iter().map(|op| op.id).collect();
+    let header = BlockHeader::new_verifiable(
+        BlockHeader {
+            current_version: 0,
+            announced_version: None,
+            denunciations: vec![],
+            slot,
+            parents: best_parents,
+            operation_merkle_root: compute_operations_hash(&op_ids, &OperationIdSerializer::new()),
+            endorsements: Vec::new(),
+            extensions: Vec::new(),
+        },
+        BlockHeaderSerializer::new(),
+        creator,
+    )
+    .unwrap();
+
+    Block::new_verifiable(
+        Block {
+            header,
+            operations: op_ids,
+        },
+        BlockSerializer::new(),
+        creator,
+    )
+    .unwrap()
+}
+
 // returns hash and resulting discarded blocks
 pub fn create_block_with_merkle_root(
     operation_merkle_root: Hash,
@@ -108,6 +150,7 @@ pub fn create_block_with_merkle_root(
             parents: best_parents,
             operation_merkle_root,
             endorsements: Vec::new(),
+            extensions: Vec::new(),
         },
         BlockHeaderSerializer::new(),
         creator,
@@ -1,8 +1,8 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use massa_channel::MassaChannel;
 use massa_consensus_exports::{
-    ConsensusBroadcasts, ConsensusChannels, ConsensusConfig, ConsensusController,
+    BlockArchive, ConsensusBroadcasts, ConsensusChannels, ConsensusConfig, ConsensusController,
 };
 use massa_execution_exports::MockExecutionController;
 use massa_metrics::MassaMetrics;
@@ -20,6 +20,7 @@ pub struct ConsensusForeignControllers {
     pub protocol_controller: Box<MockProtocolController>,
     pub pool_controller: Box<MockPoolController>,
     pub selector_controller: Box<MockSelectorController>,
+    pub block_archive: Option<Arc<dyn BlockArchive>>,
     pub storage: Storage,
 }
 
@@ -30,6 +31,7 @@ impl ConsensusForeignControllers {
             protocol_controller: Box::new(MockProtocolController::new()),
             pool_controller: Box::new(MockPoolController::new()),
             selector_controller: Box::new(MockSelectorController::new()),
+            block_archive: None,
             storage: Storage::create_root(),
         }
     }
@@ -63,6 +65,8 @@ impl TestUniverse for ConsensusTestUniverse {
         let (block_sender, _block_receiver) = tokio::sync::broadcast::channel(10);
         let (block_header_sender, _block_header_receiver) = tokio::sync::broadcast::channel(10);
         let (filled_block_sender, _filled_block_receiver) = tokio::sync::broadcast::channel(10);
+        let (final_period_sender, _final_period_receiver) = tokio::sync::broadcast::channel(10);
+        let (fork_choice_sender, _fork_choice_receiver) = tokio::sync::broadcast::channel(10);
         let (consensus_controller, _) = start_consensus_worker(
             config,
             ConsensusChannels {
@@ -70,12 +74,15 @@ impl TestUniverse for ConsensusTestUniverse {
                     block_sender,
                     block_header_sender,
                     filled_block_sender,
+                    final_period_sender,
+                    fork_choice_sender,
                 },
                 controller_event_tx: consensus_event_sender,
                 execution_controller: foreign_controllers.execution_controller,
                 protocol_controller: foreign_controllers.protocol_controller,
                 pool_controller: foreign_controllers.pool_controller,
                 selector_controller: foreign_controllers.selector_controller,
+                block_archive: foreign_controllers.block_archive,
             },
             None,
             foreign_controllers.storage.clone(),
@@ -0,0 +1,258 @@
+//! Detection of finality stalls.
+//!
+//! At each slot, the worker checks whether new blocks became CSS-final and new slots became SCE-final.
+//! When neither progressed for `finality_stall_timeout`, diagnostic context is gathered from consensus,
+//! execution and protocol, recovery actions are suggested, and a `ConsensusEvent::FinalityStall` alert is emitted.
+//! The alert is emitted once per stall, and re-armed as soon as finality progresses again.
+
+use massa_consensus_exports::events::{ConsensusEvent, FinalityStallAction, FinalityStallReport};
+use massa_models::slot::Slot;
+use massa_time::MassaTime;
+use tracing::log::warn;
+
+use super::ConsensusWorker;
+
+/// Tracks the progress of CSS and SCE finality
+pub(crate) struct FinalityStallDetector {
+    /// latest CSS-final period in each thread, as last observed
+    latest_final_periods: Vec<u64>,
+    /// last time a block became CSS-final
+    last_consensus_progress: MassaTime,
+    /// latest executed SCE-final slot, as last observed
+    execution_final_cursor: Option<Slot>,
+    /// last time a slot became SCE-final
+    last_execution_progress: MassaTime,
+    /// whether the ongoing stall was already reported
+    reported: bool,
+}
+
+impl FinalityStallDetector {
+    /// Creates a detector considering that finality progressed at `now`
+    pub fn new(now: MassaTime) -> Self {
+        FinalityStallDetector {
+            latest_final_periods: Vec::new(),
+            last_consensus_progress: now,
+            execution_final_cursor: None,
+            last_execution_progress: now,
+            reported: false,
+        }
+    }
+
+    /// Considers that finality progressed at `now`, for example when the network is not started yet
+    pub fn reset(&mut self, now: MassaTime) {
+        self.last_consensus_progress = now;
+        self.last_execution_progress = now;
+        self.reported = false;
+    }
+
+    /// Records the latest observed finality cursors.
+    ///
+    /// # Returns
+    /// The time elapsed since CSS finality and SCE finality last progressed
+    pub fn observe(
+        &mut self,
+        now: MassaTime,
+        latest_final_periods: Vec<u64>,
+        execution_final_cursor: Slot,
+    ) -> (MassaTime, MassaTime) {
+        if latest_final_periods != self.latest_final_periods {
+            self.latest_final_periods = latest_final_periods;
+            self.last_consensus_progress = now;
+            self.reported = false;
+        }
+        if self.execution_final_cursor != Some(execution_final_cursor) {
+            self.execution_final_cursor = Some(execution_final_cursor);
+            self.last_execution_progress = now;
+            self.reported = false;
+        }
+        (
+            now.saturating_sub(self.last_consensus_progress),
+            now.saturating_sub(self.last_execution_progress),
+        )
+    }
+}
+
+/// Suggests recovery actions from the diagnostic context of a stall
+pub(crate) fn suggest_actions(
+    report: &FinalityStallReport,
+    timeout: MassaTime,
+    t0: MassaTime,
+) -> Vec<FinalityStallAction> {
+    let mut actions = Vec::new();
+    if report.connected_peers == Some(0) {
+        actions.push(FinalityStallAction::CheckConnectivity);
+    }
+    if let Some(delay) = report.median_block_arrival_delay_ms {
+        if delay.unsigned_abs() > t0.as_millis() / 2 {
+            actions.push(FinalityStallAction::SynchronizeClock);
+        }
+    }
+    let consensus_stalled = report.consensus_stall_duration >= timeout;
+    if consensus_stalled && report.clique_count > 1 && report.connected_peers != Some(0) {
+        actions.push(FinalityStallAction::Resync);
+    }
+    if !consensus_stalled && report.execution_stall_duration >= timeout {
+        actions.push(FinalityStallAction::CheckExecution);
+    }
+    actions
+}
+
+/// Median of a list of values
+fn median(mut values: Vec<i64>) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    Some(values[values.len() / 2])
+}
+
+impl ConsensusWorker {
+    /// Checks whether finality stalled, and emits an alert with diagnostic context if so.
+    /// This must be called at each slot, without holding the lock on the shared state.
+    pub(crate) fn check_finality_stall(&mut self) {
+        let timeout = self.config.finality_stall_timeout;
+        if timeout.as_millis() == 0 {
+            return;
+        }
+        let now = MassaTime::now();
+
+        // finality cannot progress before the network (re)starts
+        if self.next_slot.period <= self.config.last_start_period {
+            self.finality_stall.reset(now);
+            return;
+        }
+
+        let (latest_final_periods, clique_count, recent_stale_blocks, delays, channels) = {
+            let read_shared_state = self.shared_state.read();
+            (
+                read_shared_state
+                    .latest_final_blocks_periods
+                    .iter()
+                    .map(|(_, period)| *period)
+                    .collect::<Vec<_>>(),
+                read_shared_state.get_clique_count(),
+                read_shared_state.stale_block_stats.len(),
+                read_shared_state
+                    .protocol_block_delays
+                    .iter()
+                    .map(|(_, delay)| *delay)
+                    .collect::<Vec<_>>(),
+                read_shared_state.channels.clone(),
+            )
+        };
+        let execution_stats = channels.execution_controller.get_stats();
+        let (consensus_stall_duration, execution_stall_duration) = self.finality_stall.observe(
+            now,
+            latest_final_periods.clone(),
+            execution_stats.final_cursor,
+        );
+        if self.finality_stall.reported
+            || (consensus_stall_duration < timeout && execution_stall_duration < timeout)
+        {
+            return;
+        }
+        self.finality_stall.reported = true;
+
+        let connected_peers = match channels.protocol_controller.get_stats() {
+            Ok((_, peers)) => Some(peers.len()),
+            Err(err) => {
+                warn!("could not get peers to diagnose finality stall: {}", err);
+                None
+            }
+        };
+        let mut report = FinalityStallReport {
+            consensus_stall_duration,
+            execution_stall_duration,
+            latest_final_periods,
+            execution_final_cursor: Some(execution_stats.final_cursor),
+            execution_backlog: Some(execution_stats.final_execution_backlog),
+            connected_peers,
+            clique_count,
+            recent_stale_blocks,
+            median_block_arrival_delay_ms: median(delays),
+            suggested_actions: Vec::new(),
+        };
+        report.suggested_actions = suggest_actions(&report, timeout, self.config.t0);
+
+        let _ = channels
+            .controller_event_tx
+            .send(ConsensusEvent::FinalityStall(report.clone()));
+        if self.config.finality_stall_auto_resync
+            && report
+                .suggested_actions
+                .contains(&FinalityStallAction::Resync)
+        {
+            warn!("finality stalled while the node is on a fork, requesting a new bootstrap");
+            let _ = channels.controller_event_tx.send(ConsensusEvent::NeedSync);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(consensus_stall: u64, execution_stall: u64) -> FinalityStallReport {
+        FinalityStallReport {
+            consensus_stall_duration: MassaTime::from_millis(consensus_stall),
+            execution_stall_duration: MassaTime::from_millis(execution_stall),
+            latest_final_periods: vec![10, 10],
+            execution_final_cursor: Some(Slot::new(10, 1)),
+            execution_backlog: Some(0),
+            connected_peers: Some(8),
+            clique_count: 1,
+            recent_stale_blocks: 0,
+            median_block_arrival_delay_ms: Some(300),
+            suggested_actions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_finality_stall_detector_progress() {
+        let start = MassaTime::from_millis(1_000);
+        let mut detector = FinalityStallDetector::new(start);
+        let (consensus, execution) =
+            detector.observe(MassaTime::from_millis(5_000), vec![1, 1], Slot::new(1, 1));
+        assert_eq!(consensus, MassaTime::from_millis(0));
+        assert_eq!(execution, MassaTime::from_millis(0));
+
+        // only execution progresses
+        let (consensus, execution) =
+            detector.observe(MassaTime::from_millis(9_000), vec![1, 1], Slot::new(2, 0));
+        assert_eq!(consensus, MassaTime::from_millis(4_000));
+        assert_eq!(execution, MassaTime::from_millis(0));
+    }
+
+    #[test]
+    fn test_finality_stall_suggested_actions() {
+        let timeout = MassaTime::from_millis(60_000);
+        let t0 = MassaTime::from_millis(16_000);
+
+        // healthy context: nothing to suggest
+        assert!(suggest_actions(&report(60_000, 60_000), timeout, t0).is_empty());
+
+        let mut isolated = report(60_000, 60_000);
+        isolated.connected_peers = Some(0);
+        isolated.clique_count = 2;
+        assert_eq!(
+            suggest_actions(&isolated, timeout, t0),
+            vec![FinalityStallAction::CheckConnectivity]
+        );
+
+        let mut forked = report(60_000, 60_000);
+        forked.clique_count = 2;
+        forked.median_block_arrival_delay_ms = Some(-9_000);
+        assert_eq!(
+            suggest_actions(&forked, timeout, t0),
+            vec![
+                FinalityStallAction::SynchronizeClock,
+                FinalityStallAction::Resync
+            ]
+        );
+
+        assert_eq!(
+            suggest_actions(&report(1_000, 60_000), timeout, t0),
+            vec![FinalityStallAction::CheckExecution]
+        );
+    }
+}
@@ -29,7 +29,7 @@ use tracing::log::info;
 
 use crate::{commands::ConsensusCommand, state::ConsensusState};
 
-use super::ConsensusWorker;
+use super::{finality_stall::FinalityStallDetector, ConsensusWorker};
 
 /// Creates genesis block in given thread.
 ///
@@ -53,6 +53,7 @@ pub fn create_genesis_block(
             operation_merkle_root: Hash::compute_from(&Vec::new()),
             endorsements: Vec::new(),
             denunciations: Vec::new(),
+            extensions: Vec::new(),
         },
         BlockHeaderSerializer::new(),
         keypair,
@@ -196,6 +197,7 @@ impl ConsensusWorker {
             previous_slot,
             next_slot,
             next_instant,
+            finality_stall: FinalityStallDetector::new(MassaTime::now()),
         };
 
         // If the node starts after the genesis timestamp then it has to initialize its graph
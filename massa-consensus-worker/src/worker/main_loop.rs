@@ -47,6 +47,13 @@ impl ConsensusWorker {
                 write_shared_state.mark_invalid_block(&block_id, header);
                 Ok(())
             }
+            ConsensusCommand::ConfirmReorg => {
+                if write_shared_state.held_reorg.is_none() {
+                    return Ok(());
+                }
+                write_shared_state.reorg_confirmed = true;
+                write_shared_state.block_db_changed()
+            }
         }
     }
 
@@ -149,6 +156,7 @@ impl ConsensusWorker {
                             warn!("Error while processing block tick: {}", err);
                         }
                     };
+                    self.check_finality_stall();
                     if last_prune.elapsed().as_millis()
                         > self.config.block_db_prune_interval.as_millis() as u128
                     {
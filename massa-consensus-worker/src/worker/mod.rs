@@ -4,13 +4,9 @@ use massa_consensus_exports::{
     ConsensusController, ConsensusManager,
 };
 use massa_metrics::MassaMetrics;
-use massa_models::block_id::BlockId;
-use massa_models::clique::Clique;
 use massa_models::config::CHANNEL_SIZE;
-use massa_models::prehash::PreHashSet;
 use massa_models::slot::Slot;
 use massa_storage::Storage;
-use massa_time::MassaTime;
 use parking_lot::RwLock;
 use std::sync::Arc;
 use std::thread;
@@ -19,7 +15,8 @@ use std::time::Instant;
 use crate::commands::ConsensusCommand;
 use crate::controller::ConsensusControllerImpl;
 use crate::manager::ConsensusManagerImpl;
-use crate::state::{blocks_state::BlocksState, ConsensusState};
+use crate::state::ConsensusState;
+use finality_stall::FinalityStallDetector;
 
 /// The consensus worker structure that contains all information and tools for the consensus worker thread.
 pub struct ConsensusWorker {
@@ -35,8 +32,11 @@ pub struct ConsensusWorker {
     next_slot: Slot,
     /// Next slot instant
     next_instant: Instant,
+    /// Finality progress tracker used to detect stalls
+    finality_stall: FinalityStallDetector,
 }
 
+mod finality_stall;
 mod init;
 mod main_loop;
 
@@ -59,45 +59,13 @@ pub fn start_consensus_worker(
     massa_metrics: MassaMetrics,
 ) -> (Box<dyn ConsensusController>, Box<dyn ConsensusManager>) {
     let (tx, rx) = MassaChannel::new("consensus_command".to_string(), Some(CHANNEL_SIZE));
-    // desync detection timespan
-    let bootstrap_part_size = config.bootstrap_part_size;
-    let stats_desync_detection_timespan =
-        config.t0.checked_mul(config.periods_per_cycle * 2).unwrap();
     let broadcasts = channels.broadcasts.clone();
-    let shared_state = Arc::new(RwLock::new(ConsensusState {
-        storage: storage.clone(),
-        config: config.clone(),
+    let shared_state = Arc::new(RwLock::new(ConsensusState::new(
+        config.clone(),
         channels,
-        max_cliques: vec![Clique {
-            block_ids: PreHashSet::<BlockId>::default(),
-            fitness: 0,
-            is_blockclique: true,
-        }],
-        blocks_state: BlocksState::new(),
-        to_propagate: Default::default(),
-        attack_attempts: Default::default(),
-        new_final_blocks: Default::default(),
-        new_stale_blocks: Default::default(),
-        active_index_without_ops: Default::default(),
-        save_final_periods: Default::default(),
-        latest_final_blocks_periods: Default::default(),
-        best_parents: Default::default(),
-        genesis_hashes: Default::default(),
-        gi_head: Default::default(),
-        final_block_stats: Default::default(),
-        stale_block_stats: Default::default(),
-        protocol_blocks: Default::default(),
-        wishlist: Default::default(),
-        launch_time: MassaTime::now(),
-        stats_desync_detection_timespan,
-        stats_history_timespan: std::cmp::max(
-            stats_desync_detection_timespan,
-            config.stats_timespan,
-        ),
-        prev_blockclique: Default::default(),
-        nonfinal_active_blocks_per_slot: Default::default(),
+        storage.clone(),
         massa_metrics,
-    }));
+    )));
 
     let shared_state_cloned = shared_state.clone();
     let mut consensus_worker =
@@ -116,7 +84,7 @@ pub fn start_consensus_worker(
         tx,
         broadcasts,
         shared_state,
-        bootstrap_part_size,
+        config.bootstrap_part_size,
         config.broadcast_enabled,
     );
 
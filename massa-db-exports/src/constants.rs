@@ -3,6 +3,10 @@ pub const METADATA_CF: &str = "metadata";
 pub const STATE_CF: &str = "state";
 pub const VERSIONING_CF: &str = "versioning";
 
+// Options
+/// Default size in bytes of a memtable of the state column family
+pub const STATE_CF_DEFAULT_WRITE_BUFFER_SIZE: usize = 32 * 1024 * 1024;
+
 // Hash
 pub const STATE_HASH_BYTES_LEN: usize = 512;
 pub const STATE_HASH_KEY: &[u8; 1] = b"h";
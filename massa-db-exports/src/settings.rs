@@ -13,4 +13,7 @@ pub struct MassaDBConfig {
     pub max_final_state_elements_size: usize,
     /// Thread count for slot serialization
     pub thread_count: u8,
+    /// Size in bytes of a memtable of the state column family.
+    /// Up to 4 memtables are kept in memory before being flushed to disk.
+    pub state_write_buffer_size: usize,
 }
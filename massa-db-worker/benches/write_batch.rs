@@ -0,0 +1,91 @@
+#[cfg(feature = "benchmarking")]
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+#[cfg(feature = "benchmarking")]
+fn criterion_benchmark(c: &mut Criterion) {
+    use massa_db_exports::{
+        DBBatch, MassaDBConfig, MassaDBController, LEDGER_PREFIX,
+        STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+    };
+    use massa_db_worker::MassaDB;
+    use massa_models::slot::Slot;
+
+    /// Number of datastore updates per slot, as in a slot full of NFT mints or airdrops
+    const UPDATES_PER_SLOT: u64 = 5_000;
+    const THREAD_COUNT: u8 = 32;
+
+    /// Builds the batch of a slot: small datastore entries of which half overwrite the ones of the previous slot
+    fn slot_batch(slot_index: u64) -> DBBatch {
+        (0..UPDATES_PER_SLOT)
+            .map(|i| {
+                let entry_index = slot_index * UPDATES_PER_SLOT / 2 + i;
+                let mut key = LEDGER_PREFIX.as_bytes().to_vec();
+                key.extend([7u8; 33]);
+                key.extend(entry_index.to_be_bytes());
+                (key, Some(slot_index.to_be_bytes().repeat(4)))
+            })
+            .collect()
+    }
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let config = MassaDBConfig {
+        path: temp_dir.path().to_path_buf(),
+        max_history_length: 10,
+        max_final_state_elements_size: 100_000_000,
+        max_versioning_elements_size: 100_000_000,
+        thread_count: THREAD_COUNT,
+        state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+    };
+    let mut db_opts = MassaDB::default_db_opts();
+    db_opts.enable_statistics();
+    let mut db = MassaDB::new_with_options(config, db_opts.clone()).unwrap();
+
+    let mut slot = Slot::new(0, 0);
+    let mut slot_index = 0;
+    c.bench_function("write slot of small datastore updates", |b| {
+        b.iter_batched(
+            || {
+                slot = slot.get_next_slot(THREAD_COUNT).unwrap();
+                slot_index += 1;
+                (slot_batch(slot_index), slot)
+            },
+            |(batch, slot)| db.write_batch(black_box(batch), DBBatch::new(), Some(slot)),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+
+    // Write amplification: bytes written to disk by flushes and compactions for each byte written by the node
+    db.flush().unwrap();
+    let statistics = db_opts.get_statistics().unwrap_or_default();
+    let ticker = |name: &str| -> f64 {
+        statistics
+            .lines()
+            .find(|line| line.starts_with(name))
+            .and_then(|line| line.rsplit(':').next())
+            .and_then(|count| count.trim().parse().ok())
+            .unwrap_or(0.0)
+    };
+    let written = ticker("rocksdb.bytes.written ");
+    let flushed = ticker("rocksdb.flush.write.bytes ");
+    let compacted = ticker("rocksdb.compact.write.bytes ");
+    if written > 0.0 {
+        println!(
+            "write amplification: {:.2} ({} bytes written, {} flushed, {} compacted)",
+            (flushed + compacted) / written,
+            written,
+            flushed,
+            compacted
+        );
+    }
+}
+
+#[cfg(feature = "benchmarking")]
+criterion_group!(benches, criterion_benchmark);
+
+#[cfg(feature = "benchmarking")]
+criterion_main!(benches);
+
+#[cfg(not(feature = "benchmarking"))]
+fn main() {
+    println!("Please use the `--features benchmarking` flag to run this benchmark.");
+}
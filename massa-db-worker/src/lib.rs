@@ -42,6 +42,16 @@
 //!
 //! This hash is often referred as 'final state hash'.
 //!
+//! # Writes
+//!
+//! All the changes of a slot are committed in a single atomic RocksDB write batch,
+//! along with the new db hash and the slot (change_id).
+//! The previous values needed to update the db hash are read with a single sorted batched lookup,
+//! so that slots made of thousands of small datastore updates (NFT mints, airdrops...) stay cheap to write.
+//! Note that merge operators are not used for the 'state' column: the Xor hash needs every previous value anyway.
+//! The 'state' column uses large memtables merged before being flushed to limit write amplification,
+//! see the `write_batch` benchmark (`cargo bench --features benchmarking`).
+//!
 //! # Caches
 //!
 //! A cache of db changes is kept in memory allowing to easily stream it
@@ -14,8 +14,8 @@ use massa_models::{
 use massa_serialization::{DeserializeError, Deserializer, Serializer, U64VarIntSerializer};
 use parking_lot::Mutex;
 use rocksdb::{
-    checkpoint::Checkpoint, ColumnFamilyDescriptor, Direction, IteratorMode, Options, WriteBatch,
-    DB,
+    checkpoint::Checkpoint, BlockBasedOptions, ColumnFamilyDescriptor, Direction, IteratorMode,
+    Options, ReadOptions, WriteBatch, DB,
 };
 use std::path::PathBuf;
 use std::{
@@ -25,6 +25,17 @@ use std::{
     sync::Arc,
 };
 
+/// Max number of memtables of the state column family kept in memory
+const STATE_CF_MAX_WRITE_BUFFER_NUMBER: i32 = 4;
+
+/// Size of a data block of the state column family
+const STATE_CF_BLOCK_SIZE: usize = 4 * 1024;
+
+/// Readahead of the iterators streaming the state to bootstrap clients.
+/// It is a whole number of data blocks, so that the contiguous key range of a stream batch
+/// is fetched from disk with a few large sequential reads instead of one read per data block.
+const STREAM_READAHEAD_SIZE: usize = 512 * STATE_CF_BLOCK_SIZE;
+
 /// Wrapped RocksDB database
 ///
 /// In our instance, we use Slot as the ChangeID
@@ -166,16 +177,26 @@ where
         if !last_state_step.finished() {
             let handle = self.db.cf_handle(STATE_CF).expect(CF_ERROR);
 
+            // The batch is a contiguous key range read sequentially: read ahead whole data blocks,
+            // and do not evict the blocks used by the execution from the block cache
+            let mut read_opts = ReadOptions::default();
+            read_opts.set_readahead_size(STREAM_READAHEAD_SIZE);
+            read_opts.fill_cache(false);
+
             // Creates an iterator from the next element after the last if defined, otherwise initialize it at the first key.
             let db_iterator = match &last_state_step {
                 StreamingStep::Ongoing(max_key) => {
-                    let mut iter = self
-                        .db
-                        .iterator_cf(handle, IteratorMode::From(max_key, Direction::Forward));
+                    let mut iter = self.db.iterator_cf_opt(
+                        handle,
+                        read_opts,
+                        IteratorMode::From(max_key, Direction::Forward),
+                    );
                     iter.next();
                     iter
                 }
-                _ => self.db.iterator_cf(handle, IteratorMode::Start),
+                _ => self
+                    .db
+                    .iterator_cf_opt(handle, read_opts, IteratorMode::Start),
             };
 
             let u64_ser = U64VarIntSerializer::new();
@@ -349,6 +370,25 @@ where
         versioning_changes: BTreeMap<Key, Option<Value>>,
         change_id: Option<ChangeID>,
         reset_history: bool,
+    ) -> Result<(), MassaDBError> {
+        self.write_changes_with_absent_range(
+            changes,
+            versioning_changes,
+            change_id,
+            reset_history,
+            None,
+        )
+    }
+
+    /// Same as `write_changes`, but the keys of `absent_range` (inclusive bounds) are known
+    /// to be absent from the state, so their previous values are not looked up
+    fn write_changes_with_absent_range(
+        &mut self,
+        changes: BTreeMap<Key, Option<Value>>,
+        versioning_changes: BTreeMap<Key, Option<Value>>,
+        change_id: Option<ChangeID>,
+        reset_history: bool,
+        absent_range: Option<(&Key, &Key)>,
     ) -> Result<(), MassaDBError> {
         if let Some(change_id) = change_id.clone() {
             if change_id < self.get_change_id().expect(CHANGE_ID_DESER_ERROR) {
@@ -364,29 +404,45 @@ where
 
         let mut current_xor_hash = self.get_xof_db_hash();
 
-        *self.current_batch.lock() = WriteBatch::default();
+        let is_absent =
+            |key: &Key| absent_range.map_or(false, |(first, last)| first <= key && key <= last);
+
+        // The previous values of the changed keys are needed to update the XOR hash.
+        // They are read all at once: the changes are sorted by key,
+        // which lets RocksDB serve slots made of thousands of small datastore updates
+        // with a single batched lookup instead of one point lookup per key.
+        let mut prev_values = self
+            .db
+            .batched_multi_get_cf(
+                handle_state,
+                changes.keys().filter(|key| !is_absent(key)),
+                true,
+            )
+            .into_iter();
+
+        // All the changes of the slot are committed in a single atomic write batch
+        let mut batch = WriteBatch::default();
 
         for (key, value) in changes.iter() {
+            let prev_value = if is_absent(key) {
+                None
+            } else {
+                prev_values.next()
+            };
+
+            // Compute the XOR in all cases
+            if let Some(Ok(Some(prev_value))) = prev_value {
+                let prev_hash = HashXof::compute_from_tuple(&[key.as_slice(), &*prev_value]);
+                current_xor_hash ^= prev_hash;
+            };
+
             if let Some(value) = value {
-                self.current_batch.lock().put_cf(handle_state, key, value);
+                batch.put_cf(handle_state, key, value);
 
-                // Compute the XOR in all cases
-                if let Ok(Some(prev_value)) = self.db.get_cf(handle_state, key) {
-                    let prev_hash =
-                        HashXof::compute_from_tuple(&[key.as_slice(), prev_value.as_slice()]);
-                    current_xor_hash ^= prev_hash;
-                };
                 let new_hash = HashXof::compute_from_tuple(&[key.as_slice(), value.as_slice()]);
                 current_xor_hash ^= new_hash;
             } else {
-                self.current_batch.lock().delete_cf(handle_state, key);
-
-                // Compute the XOR in all cases
-                if let Ok(Some(prev_value)) = self.db.get_cf(handle_state, key) {
-                    let prev_hash =
-                        HashXof::compute_from_tuple(&[key.as_slice(), prev_value.as_slice()]);
-                    current_xor_hash ^= prev_hash;
-                };
+                batch.delete_cf(handle_state, key);
             }
         }
 
@@ -394,28 +450,24 @@ where
         // e.g everything that is not in 'Active' state (so hashes remain compatibles)
         for (key, value) in versioning_changes.iter() {
             if let Some(value) = value {
-                self.current_batch
-                    .lock()
-                    .put_cf(handle_versioning, key, value);
+                batch.put_cf(handle_versioning, key, value);
             } else {
-                self.current_batch.lock().delete_cf(handle_versioning, key);
+                batch.delete_cf(handle_versioning, key);
             }
         }
 
-        if let Some(change_id) = change_id {
-            self.set_change_id_to_batch(change_id);
-        }
-
-        // Update the hash entry
-        self.current_batch
-            .lock()
-            .put_cf(handle_metadata, STATE_HASH_KEY, current_xor_hash.0);
-
         {
             let mut current_batch_guard = self.current_batch.lock();
-            let batch = WriteBatch::from_data(current_batch_guard.data());
-            current_batch_guard.clear();
+            *current_batch_guard = batch;
+
+            if let Some(change_id) = change_id {
+                self.set_change_id_to_locked_batch(&mut current_batch_guard, change_id);
+            }
+
+            // Update the hash entry
+            current_batch_guard.put_cf(handle_metadata, STATE_HASH_KEY, current_xor_hash.0);
 
+            let batch = std::mem::take(&mut *current_batch_guard);
             self.db.write(batch).map_err(|e| {
                 MassaDBError::RocksDBError(format!("Can't write batch to disk: {}", e))
             })?;
@@ -497,6 +549,11 @@ where
 
     /// Set the current change_id in the batch
     pub fn set_change_id_to_batch(&self, change_id: ChangeID) {
+        self.set_change_id_to_locked_batch(&mut self.current_batch.lock(), change_id);
+    }
+
+    /// Set the current change_id in a batch that is already locked
+    fn set_change_id_to_locked_batch(&self, batch: &mut WriteBatch, change_id: ChangeID) {
         let handle_metadata = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
 
         let mut change_id_bytes = Vec::new();
@@ -504,9 +561,7 @@ where
             .serialize(&change_id, &mut change_id_bytes)
             .expect(CHANGE_ID_SER_ERROR);
 
-        self.current_batch
-            .lock()
-            .put_cf(handle_metadata, CHANGE_ID_KEY, &change_id_bytes);
+        batch.put_cf(handle_metadata, CHANGE_ID_KEY, &change_id_bytes);
     }
 
     /// Write a stream_batch of database entries received from a bootstrap server
@@ -523,6 +578,18 @@ where
             None => StreamingStep::Finished(None),
         };
 
+        // The new elements form a contiguous key range that follows the previous cursor:
+        // if we hold no key in that range, they are appended without looking up their previous values
+        let absent_range = match (
+            stream_changes.new_elements.first_key_value(),
+            stream_changes.new_elements.last_key_value(),
+        ) {
+            (Some((first, _)), Some((last, _))) if self.is_state_range_empty(first, last) => {
+                Some((first.clone(), last.clone()))
+            }
+            _ => None,
+        };
+
         changes.extend(stream_changes.updates_on_previous_elements);
         changes.extend(
             stream_changes
@@ -546,16 +613,31 @@ where
                 .map(|(k, v)| (k.clone(), Some(v.clone()))),
         );
 
-        self.write_changes(
+        self.write_changes_with_absent_range(
             changes,
             versioning_changes,
             Some(stream_changes.change_id),
             true,
+            absent_range.as_ref().map(|(first, last)| (first, last)),
         )?;
 
         Ok((new_cursor, new_cursor_versioning))
     }
 
+    /// Returns true if the state holds no key between `first` and `last` (inclusive), using a single seek
+    fn is_state_range_empty(&self, first: &Key, last: &Key) -> bool {
+        let handle = self.db.cf_handle(STATE_CF).expect(CF_ERROR);
+        match self
+            .db
+            .iterator_cf(handle, IteratorMode::From(first, Direction::Forward))
+            .next()
+        {
+            Some(Ok((key, _))) => key.as_ref() > last.as_slice(),
+            Some(Err(_)) => false,
+            None => true,
+        }
+    }
+
     /// Get the current XOF state hash of the database
     pub fn get_xof_db_hash(&self) -> HashXof<HASH_XOF_SIZE_BYTES> {
         self.get_xof_db_hash_opt()
@@ -589,13 +671,37 @@ impl RawMassaDB<Slot, SlotSerializer, SlotDeserializer> {
         db_opts
     }
 
+    /// Options of the state column family, which receives one large write batch per slot.
+    /// Up to `STATE_CF_MAX_WRITE_BUFFER_NUMBER` memtables of `write_buffer_size` bytes are kept in memory.
+    pub fn state_cf_opts(write_buffer_size: usize) -> Options {
+        let mut cf_opts = Options::default();
+        // buffer several slots worth of small datastore updates in memory,
+        // and merge memtables before flushing so that keys overwritten across slots are written to disk only once
+        cf_opts.set_write_buffer_size(write_buffer_size);
+        cf_opts.set_max_write_buffer_number(STATE_CF_MAX_WRITE_BUFFER_NUMBER);
+        cf_opts.set_min_write_buffer_number_to_merge(2);
+        // size levels from the last one to limit the write amplification of compactions
+        cf_opts.set_level_compaction_dynamic_level_bytes(true);
+        // explicit data block size, which the bootstrap streaming readahead is a multiple of
+        let mut block_opts = BlockBasedOptions::default();
+        block_opts.set_block_size(STATE_CF_BLOCK_SIZE);
+        cf_opts.set_block_based_table_factory(&block_opts);
+        cf_opts
+    }
+
     /// Returns a new `MassaDB` instance given a config and RocksDB options
-    fn new_with_options(config: MassaDBConfig, db_opts: Options) -> Result<Self, rocksdb::Error> {
+    pub fn new_with_options(
+        config: MassaDBConfig,
+        db_opts: Options,
+    ) -> Result<Self, rocksdb::Error> {
         let db = DB::open_cf_descriptors(
             &db_opts,
             &config.path,
             vec![
-                ColumnFamilyDescriptor::new(STATE_CF, Options::default()),
+                ColumnFamilyDescriptor::new(
+                    STATE_CF,
+                    Self::state_cf_opts(config.state_write_buffer_size),
+                ),
                 ColumnFamilyDescriptor::new(METADATA_CF, Options::default()),
                 ColumnFamilyDescriptor::new(VERSIONING_CF, Options::default()),
             ],
@@ -881,6 +987,7 @@ mod test {
 
     use assert_matches::assert_matches;
     use massa_db_exports::MassaDBError::TimeError;
+    use massa_db_exports::STATE_CF_DEFAULT_WRITE_BUFFER_SIZE;
     use parking_lot::RwLock;
     use tempfile::tempdir;
 
@@ -928,6 +1035,7 @@ mod test {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let mut db_opts = MassaDB::default_db_opts();
         // Additional checks (only for testing)
@@ -957,6 +1065,7 @@ mod test {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let mut db_opts = MassaDB::default_db_opts();
         // Additional checks (only for testing)
@@ -1040,6 +1149,7 @@ mod test {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let mut db_opts = MassaDB::default_db_opts();
         // Additional checks (only for testing)
@@ -1107,6 +1217,49 @@ mod test {
         assert_eq!(dump_column(db.clone(), "state"), BTreeMap::from([(v3, k3)]))
     }
 
+    #[test]
+    fn test_large_batch_hash() {
+        // 1- Write a large batch, then a batch overwriting and deleting half of its keys
+        // 2- Write the resulting state at once in another db
+        // 3- Check that both db hashes are equal
+
+        let new_db = |temp_dir: &tempfile::TempDir| {
+            let db_config = MassaDBConfig {
+                path: temp_dir.path().to_path_buf(),
+                max_history_length: 100,
+                max_final_state_elements_size: 100,
+                max_versioning_elements_size: 100,
+                thread_count: THREAD_COUNT,
+                state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+            };
+            let mut db_opts = MassaDB::default_db_opts();
+            // Additional checks (only for testing)
+            db_opts.set_paranoid_checks(true);
+            MassaDB::new_with_options(db_config, db_opts).unwrap()
+        };
+        let key = |i: u32| [b"key".as_slice(), &i.to_be_bytes()].concat();
+
+        let temp_dir_db = tempdir().expect("Unable to create a temp folder");
+        let mut db = new_db(&temp_dir_db);
+        let batch: DBBatch = (0..5000).map(|i| (key(i), Some(vec![1]))).collect();
+        db.write_batch(batch, DBBatch::new(), Some(Slot::new(1, 0)));
+        let batch: DBBatch = (2500..5000)
+            .map(|i| (key(i), (i % 2 == 0).then(|| vec![2])))
+            .collect();
+        db.write_batch(batch, DBBatch::new(), Some(Slot::new(1, 1)));
+
+        let temp_dir_db_2 = tempdir().expect("Unable to create a temp folder");
+        let mut db_2 = new_db(&temp_dir_db_2);
+        let batch: DBBatch = (0..5000)
+            .filter(|i| *i < 2500 || i % 2 == 0)
+            .map(|i| (key(i), Some(if i < 2500 { vec![1] } else { vec![2] })))
+            .collect();
+        db_2.write_batch(batch, DBBatch::new(), Some(Slot::new(1, 1)));
+
+        assert_eq!(db.get_xof_db_hash(), db_2.get_xof_db_hash());
+        assert_eq!(db.get_change_id().unwrap(), Slot::new(1, 1));
+    }
+
     #[test]
     fn test_backup() {
         // 1- Init a db + add data
@@ -1124,6 +1277,7 @@ mod test {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let mut db_opts = MassaDB::default_db_opts();
         // Additional checks (only for testing)
@@ -1172,6 +1326,7 @@ mod test {
                 max_final_state_elements_size: 100,
                 max_versioning_elements_size: 100,
                 thread_count: THREAD_COUNT,
+                state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
             };
             let mut db_backup_1_opts = MassaDB::default_db_opts();
             db_backup_1_opts.create_if_missing(false);
@@ -1195,6 +1350,7 @@ mod test {
                 max_final_state_elements_size: 100,
                 max_versioning_elements_size: 100,
                 thread_count: THREAD_COUNT,
+                state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
             };
             let mut db_backup_2_opts = MassaDB::default_db_opts();
             db_backup_2_opts.create_if_missing(false);
@@ -1227,6 +1383,7 @@ mod test {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let mut db_opts = MassaDB::default_db_opts();
         // Additional checks (only for testing)
@@ -1273,6 +1430,7 @@ mod test {
                 max_final_state_elements_size: 100,
                 max_versioning_elements_size: 100,
                 thread_count: THREAD_COUNT,
+                state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
             };
             // let db_backup_2_opts = MassaDB::default_db_opts();
 
@@ -1321,6 +1479,7 @@ mod test {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let mut db_opts = MassaDB::default_db_opts();
         // Additional checks (only for testing)
@@ -1413,6 +1572,7 @@ mod test {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let mut db_opts = MassaDB::default_db_opts();
         // Additional checks (only for testing)
@@ -1500,6 +1660,7 @@ mod test {
             max_final_state_elements_size: 10,
             max_versioning_elements_size: 10,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let mut db_opts = MassaDB::default_db_opts();
         // Additional checks (only for testing)
@@ -1566,6 +1727,61 @@ mod test {
         assert_eq!(stream_batch.change_id, slot_2);
     }
 
+    #[test]
+    fn test_db_stream_append() {
+        // Stream the whole state to a fresh client, whose new elements are appended without lookups,
+        // and to a client already holding a stale key in the streamed range
+
+        let new_db = || {
+            let temp_dir_db = tempdir().expect("Unable to create a temp folder");
+            let db_config = MassaDBConfig {
+                path: temp_dir_db.path().to_path_buf(),
+                max_history_length: 100,
+                max_final_state_elements_size: 100,
+                max_versioning_elements_size: 100,
+                thread_count: THREAD_COUNT,
+                state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+            };
+            (MassaDB::new(db_config), temp_dir_db)
+        };
+
+        let (mut server_db, _server_dir) = new_db();
+        let batch = DBBatch::from([
+            (vec![1, 2, 3], Some(vec![4, 5, 6])),
+            (vec![11, 22, 33], Some(vec![44, 55, 66])),
+            (vec![21, 22, 23], Some(vec![24, 25, 26])),
+        ]);
+        server_db.write_batch(batch, DBBatch::new(), Some(Slot::new(1, 0)));
+        let stream_batch = server_db
+            .get_batch_to_stream(&StreamingStep::Started, None)
+            .unwrap();
+        let empty_versioning_batch = || StreamBatch {
+            new_elements: BTreeMap::new(),
+            updates_on_previous_elements: BTreeMap::new(),
+            change_id: Slot::new(1, 0),
+        };
+
+        // fresh client
+        let (mut client_db, _client_dir) = new_db();
+        let (cursor, _) = client_db
+            .write_batch_bootstrap_client(stream_batch.clone(), empty_versioning_batch())
+            .unwrap();
+        assert_eq!(cursor, StreamingStep::Ongoing(vec![21, 22, 23]));
+        assert_eq!(client_db.get_xof_db_hash(), server_db.get_xof_db_hash());
+
+        // client holding a stale value of a streamed key
+        let (mut client_db, _client_dir) = new_db();
+        client_db.write_batch(
+            DBBatch::from([(vec![11, 22, 33], Some(vec![0]))]),
+            DBBatch::new(),
+            Some(Slot::new(0, 1)),
+        );
+        client_db
+            .write_batch_bootstrap_client(stream_batch, empty_versioning_batch())
+            .unwrap();
+        assert_eq!(client_db.get_xof_db_hash(), server_db.get_xof_db_hash());
+    }
+
     #[test]
     fn test_db_stream_3() {
         // Init db + add data
@@ -1584,6 +1800,7 @@ mod test {
             max_final_state_elements_size: 20,
             max_versioning_elements_size: 20,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let mut db_opts = MassaDB::default_db_opts();
         // Additional checks (only for testing)
@@ -1695,6 +1912,7 @@ mod test {
             max_final_state_elements_size: 20,
             max_versioning_elements_size: 20,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
 
         let slot_1 = Slot::new(1, 0);
@@ -220,7 +220,7 @@ impl ExecutedDenunciations {
 #[cfg(test)]
 mod test {
     use super::*;
-    use massa_db_exports::{MassaDBConfig, MassaDBController};
+    use massa_db_exports::{MassaDBConfig, MassaDBController, STATE_CF_DEFAULT_WRITE_BUFFER_SIZE};
     use massa_db_worker::MassaDB;
     use massa_models::config::{
         DENUNCIATION_EXPIRE_PERIODS, ENDORSEMENT_COUNT, KEEP_EXECUTED_HISTORY_EXTRA_PERIODS,
@@ -249,6 +249,7 @@ mod test {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config.clone())) as Box<(dyn MassaDBController + 'static)>
@@ -275,7 +275,10 @@ mod test {
     use parking_lot::RwLock;
     use tempfile::{tempdir, TempDir};
 
-    use massa_db_exports::{MassaDBConfig, MassaDBController, STATE_HASH_INITIAL_BYTES};
+    use massa_db_exports::{
+        MassaDBConfig, MassaDBController, STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+        STATE_HASH_INITIAL_BYTES,
+    };
     use massa_db_worker::MassaDB;
     use massa_hash::{Hash, HashXof};
     use massa_models::config::{KEEP_EXECUTED_HISTORY_EXTRA_PERIODS, THREAD_COUNT};
@@ -301,6 +304,7 @@ mod test {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config.clone())) as Box<(dyn MassaDBController + 'static)>
@@ -360,6 +364,7 @@ mod test {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db_c_config = MassaDBConfig {
             path: tempdir_c.path().to_path_buf(),
@@ -367,6 +372,7 @@ mod test {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
 
         let db_a = Arc::new(RwLock::new(
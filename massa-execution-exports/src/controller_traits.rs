@@ -3,10 +3,17 @@
 //! This module exports generic traits representing interfaces for interacting with the Execution worker
 
 use crate::types::{
-    ExecutionBlockMetadata, ExecutionQueryRequest, ExecutionQueryResponse, ReadOnlyExecutionRequest,
+    ExecutionBlockMetadata, ExecutionQueryRequest, ExecutionQueryResponse,
+    OperationSimulationOutput, OperationSimulationRequest, ReadOnlyExecutionRequest,
 };
 use crate::ExecutionError;
-use crate::{ExecutionAddressInfo, ReadOnlyExecutionOutput};
+use crate::{
+    AsyncMessageFilter, ContractIoStats, CycleDeferredCredits, ExecutionAddressInfo,
+    ExecutionOutput, ExecutionOutputConsumerClass, ExecutionOutputConsumerId,
+    OperationExecutionResult, PendingAsyncMessage, ReadOnlyExecutionOutput, SlotGasProfile,
+    SlotMissStats, SlotSequencerStatus,
+};
+use massa_hash::Hash;
 use massa_models::address::Address;
 use massa_models::amount::Amount;
 use massa_models::block_id::BlockId;
@@ -65,6 +72,17 @@ pub trait ExecutionController: Send + Sync {
     /// Otherwise, the status is a boolean indicating whether the execution was successful (true) or if there was an error (false.)
     fn get_ops_exec_status(&self, batch: &[OperationId]) -> Vec<(Option<bool>, Option<bool>)>;
 
+    /// Get the detailed execution results of a batch of operations.
+    ///
+    /// The most recent candidate result is returned if there is one, otherwise the final one.
+    /// None is returned for operations whose execution was not found.
+    /// Note that the final results are only kept in memory for the most recent operations:
+    /// older ones are forgotten.
+    fn get_operation_execution_result(
+        &self,
+        operation_ids: &[OperationId],
+    ) -> Vec<Option<OperationExecutionResult>>;
+
     /// Get a copy of a single datastore entry with its final and active values
     ///
     /// # Return value
@@ -94,6 +112,21 @@ pub trait ExecutionController: Send + Sync {
         req: ReadOnlyExecutionRequest,
     ) -> Result<ReadOnlyExecutionOutput, ExecutionError>;
 
+    /// Simulate the execution of an operation without causing modifications to the consensus state.
+    /// The operation is executed at the next slot of its sender's thread, on top of the latest candidate state
+    /// to which the hypothetical ledger overrides of the request are applied first.
+    ///
+    /// # arguments
+    /// * `req`: the operation to simulate and the ledger overrides to apply
+    ///
+    /// # returns
+    /// The output of the simulated slot and the detailed result of the operation,
+    /// or an error if the operation could not be included in a block at that slot.
+    fn simulate_operation(
+        &self,
+        req: OperationSimulationRequest,
+    ) -> Result<OperationSimulationOutput, ExecutionError>;
+
     /// Check if a denunciation has been executed given a `DenunciationIndex`
     /// (speculative, final)
     fn get_denunciation_execution_status(
@@ -104,9 +137,53 @@ pub trait ExecutionController: Send + Sync {
     /// Gets information about a batch of addresses
     fn get_addresses_infos(&self, addresses: &[Address]) -> Vec<ExecutionAddressInfo>;
 
+    /// Get the candidate deferred credits of an address grouped by the cycle during which they unlock,
+    /// optionally restricted to a single cycle
+    fn get_address_deferred_credits_schedule(
+        &self,
+        address: &Address,
+        cycle: Option<u64>,
+    ) -> Vec<CycleDeferredCredits>;
+
     /// Get execution statistics
     fn get_stats(&self) -> ExecutionStats;
 
+    /// Get the status of the slot sequencer: sequence length, latest final slots and execution cursors
+    fn get_sequencer_status(&self) -> SlotSequencerStatus;
+
+    /// Get the per-thread block and miss counts of the SCE-final slots executed in the statistics time window
+    fn get_slot_miss_stats(&self) -> SlotMissStats;
+
+    /// Get the asynchronous messages of the final pool matching a filter, in execution priority order
+    fn get_async_messages(&self, filter: &AsyncMessageFilter) -> Vec<PendingAsyncMessage>;
+
+    /// Get the datastore access statistics of a smart contract over the latest `window` executed slots.
+    /// Returns None if the statistics are not recorded (`contract_io_stats_enabled` is not set).
+    fn get_contract_io_stats(&self, address: &Address, window: u64) -> Option<ContractIoStats>;
+
+    /// Get the gas profile of a recently executed slot.
+    /// Profiles are only recorded when the execution worker is built with the `gas_profile` feature.
+    fn get_slot_gas_profile(&self, slot: Slot) -> Option<SlotGasProfile>;
+
+    /// Registers a consumer of the SCE-final execution outputs.
+    /// The outputs are retained for its class until it acknowledges them, within the window of the class.
+    fn register_execution_output_consumer(
+        &self,
+        class: ExecutionOutputConsumerClass,
+    ) -> ExecutionOutputConsumerId;
+
+    /// Acknowledges the SCE-final execution outputs up to `slot` (included) for a consumer
+    fn ack_execution_outputs(&self, consumer: ExecutionOutputConsumerId, slot: Slot);
+
+    /// Unregisters a consumer of the SCE-final execution outputs, releasing the outputs it retained
+    fn unregister_execution_output_consumer(&self, consumer: ExecutionOutputConsumerId);
+
+    /// Get the retained SCE-final execution outputs of the slots after `after` (all of them if `None`), oldest first
+    fn get_final_execution_outputs(&self, after: Option<Slot>) -> Vec<ExecutionOutput>;
+
+    /// Get the final state hashes of the latest SCE-final slots after `after` (all of them if `None`), oldest first
+    fn get_final_state_hashes(&self, after: Option<Slot>) -> Vec<(Slot, Hash)>;
+
     /// Returns a boxed clone of self.
     /// Useful to allow cloning `Box<dyn ExecutionController>`.
     fn clone_box(&self) -> Box<dyn ExecutionController>;
@@ -67,6 +67,9 @@ pub enum ExecutionError {
 
     /// Factory error: {0}
     FactoryError(#[from] FactoryError),
+
+    /// Checkpoint error: {0}
+    CheckpointError(String),
 }
 
 /// Execution query errors
@@ -74,4 +77,6 @@ pub enum ExecutionError {
 pub enum ExecutionQueryError {
     /// Not found: {0}
     NotFound(String),
+    /// Invalid request: {0}
+    InvalidRequest(String),
 }
@@ -5,10 +5,11 @@
 
 use massa_models::execution::EventFilter;
 use massa_models::output_event::SCOutputEvent;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 /// Store for events emitted by smart contracts
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct EventStore(pub VecDeque<SCOutputEvent>);
 
 impl EventStore {
@@ -53,52 +54,68 @@ impl EventStore {
     /// * original caller address
     /// * operation id
     /// * is final
+    /// * is error
+    /// * data prefix
     pub fn get_filtered_sc_output_events(&self, filter: &EventFilter) -> VecDeque<SCOutputEvent> {
         self.0
             .iter()
-            .filter(|x| {
-                if let Some(start) = filter.start {
-                    if x.context.slot < start {
-                        return false;
-                    }
-                }
-                if let Some(end) = filter.end {
-                    if x.context.slot >= end {
-                        return false;
-                    }
-                }
-                if let Some(is_final) = filter.is_final {
-                    if x.context.is_final != is_final {
-                        return false;
-                    }
-                }
-                if let Some(is_error) = filter.is_error {
-                    if x.context.is_error != is_error {
-                        return false;
-                    }
-                }
-                match (filter.original_caller_address, x.context.call_stack.front()) {
-                    (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
-                    (Some(_), None) => return false,
-                    _ => (),
-                }
-                match (filter.emitter_address, x.context.call_stack.back()) {
-                    (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
-                    (Some(_), None) => return false,
-                    _ => (),
-                }
-                match (filter.original_operation_id, x.context.origin_operation_id) {
-                    (Some(addr1), Some(addr2)) if addr1 != addr2 => return false,
-                    (Some(_), None) => return false,
-                    _ => (),
-                }
-                true
-            })
+            .filter(|x| event_matches_filter(x, filter))
             .cloned()
             .collect()
     }
 }
 
+/// Returns true if an event matches all the criteria of a filter
+pub fn event_matches_filter(event: &SCOutputEvent, filter: &EventFilter) -> bool {
+    if let Some(start) = filter.start {
+        if event.context.slot < start {
+            return false;
+        }
+    }
+    if let Some(end) = filter.end {
+        if event.context.slot >= end {
+            return false;
+        }
+    }
+    if let Some(is_final) = filter.is_final {
+        if event.context.is_final != is_final {
+            return false;
+        }
+    }
+    if let Some(is_error) = filter.is_error {
+        if event.context.is_error != is_error {
+            return false;
+        }
+    }
+    match (
+        filter.original_caller_address,
+        event.context.call_stack.front(),
+    ) {
+        (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
+        (Some(_), None) => return false,
+        _ => (),
+    }
+    match (filter.emitter_address, event.context.call_stack.back()) {
+        (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
+        (Some(_), None) => return false,
+        _ => (),
+    }
+    match (
+        filter.original_operation_id,
+        event.context.origin_operation_id,
+    ) {
+        (Some(addr1), Some(addr2)) if addr1 != addr2 => return false,
+        (Some(_), None) => return false,
+        _ => (),
+    }
+    if let Some(data_prefix) = &filter.data_prefix {
+        if !event.data.starts_with(data_prefix.as_str()) {
+            return false;
+        }
+    }
+    true
+}
+
 #[test]
 fn test_prune() {
     use massa_models::output_event::{EventExecutionContext, SCOutputEvent};
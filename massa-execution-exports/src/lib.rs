@@ -58,15 +58,22 @@ pub use channels::ExecutionChannels;
 pub use controller_traits::MockExecutionController;
 pub use controller_traits::{ExecutionController, ExecutionManager};
 pub use error::{ExecutionError, ExecutionQueryError};
-pub use event_store::EventStore;
+pub use event_store::{event_matches_filter, EventStore};
 pub use massa_sc_runtime::GasCosts;
-pub use settings::{ExecutionConfig, StorageCostsConstants};
+pub use settings::{ExecutionConfig, ExecutionTraceFormat, StorageCostsConstants};
 pub use types::{
+    AsyncMessageFilter, AuthenticatedQueryResult, CallDepthGasStats, CoinFlowBalance, CoinFlowEdge,
+    CoinMovementKind, ContractIoStats, CycleDeferredCredits, DatastoreKeyIoStats,
     ExecutedBlockInfo, ExecutionAddressInfo, ExecutionBlockMetadata, ExecutionOutput,
-    ExecutionQueryCycleInfos, ExecutionQueryExecutionStatus, ExecutionQueryRequest,
-    ExecutionQueryRequestItem, ExecutionQueryResponse, ExecutionQueryResponseItem,
-    ExecutionQueryStakerInfo, ExecutionStackElement, ReadOnlyCallRequest, ReadOnlyExecutionOutput,
-    ReadOnlyExecutionRequest, ReadOnlyExecutionTarget, SlotExecutionOutput,
+    ExecutionOutputConsumerClass, ExecutionOutputConsumerId, ExecutionQueryCycleInfos,
+    ExecutionQueryExecutionStatus, ExecutionQueryRequest, ExecutionQueryRequestItem,
+    ExecutionQueryResponse, ExecutionQueryResponseItem, ExecutionQueryStakerInfo,
+    ExecutionStackElement, HostFunctionGasProfile, LedgerEntryOverride, OperationCallStats,
+    OperationCoinFlow, OperationCoinMovement, OperationExecutionResult, OperationGasProfile,
+    OperationSimulationOutput, OperationSimulationRequest, PendingAsyncMessage,
+    ReadOnlyCallRequest, ReadOnlyExecutionOutput, ReadOnlyExecutionRequest, ReadOnlyExecutionState,
+    ReadOnlyExecutionTarget, SlotExecutionOutput, SlotGasProfile, SlotMissStats,
+    SlotSequencerStatus, ThreadSlotMissStats,
 };
 
 #[cfg(any(feature = "test-exports", feature = "gas_calibration"))]
@@ -266,6 +266,16 @@ fn to_execution_query_result(
                 },
             )
         }
+        // gRPC queries cannot ask for authenticated reads: the final state hash and slot
+        // are already returned along with the whole response
+        ExecutionQueryResponseItem::Authenticated(result) => match result.value {
+            Some(value) => return to_execution_query_result(*value),
+            None => {
+                return grpc_api::ExecutionQueryResponseItem {
+                    response_item: None,
+                }
+            }
+        },
     };
 
     grpc_api::ExecutionQueryResponseItem {
@@ -349,6 +359,10 @@ impl From<ExecutionQueryError> for grpc_model::Error {
                 code: 404,
                 message: error,
             },
+            ExecutionQueryError::InvalidRequest(error) => grpc_model::Error {
+                code: 400,
+                message: error,
+            },
         }
     }
 }
@@ -2,12 +2,24 @@
 
 //! This module provides the structures used to provide configuration parameters to the Execution system
 
+use massa_hash::Hash;
 use massa_models::amount::Amount;
 use massa_sc_runtime::GasCosts;
 use massa_time::MassaTime;
 use num::rational::Ratio;
+use serde::Deserialize;
 use std::path::PathBuf;
 
+/// Output format of the execution trace dumps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionTraceFormat {
+    /// one human-readable JSON document per slot
+    Json,
+    /// compact binary encoding, one file per slot
+    Binary,
+}
+
 /// Storage cost constants
 #[derive(Debug, Clone, Copy)]
 pub struct StorageCostsConstants {
@@ -26,6 +38,8 @@ pub struct ExecutionConfig {
     pub readonly_queue_length: usize,
     /// maximum number of SC output events kept in cache
     pub max_final_events: usize,
+    /// maximum number of execution results of the latest final operations kept in memory, older ones are forgotten
+    pub max_recent_operation_results: usize,
     /// maximum available gas for asynchronous messages execution
     pub max_async_gas: u64,
     /// constant cost for async messages
@@ -38,6 +52,13 @@ pub struct ExecutionConfig {
     pub roll_price: Amount,
     /// extra lag to add on the execution cursor to improve performance
     pub cursor_delay: MassaTime,
+    /// maximum number of slots candidate execution can lag behind the time cursor
+    /// before being suspended in favor of SCE-final execution catch-up.
+    /// The suspension is lifted if no SCE-final slot is executed for as long as it takes to accumulate that lag.
+    pub max_candidate_execution_lag: u64,
+    /// whether candidate (speculative) slots are executed.
+    /// When disabled, only SCE-final slots are executed and candidate execution results are unavailable
+    pub candidate_execution_enabled: bool,
     /// genesis timestamp
     pub genesis_timestamp: MassaTime,
     /// period duration
@@ -68,8 +89,12 @@ pub struct ExecutionConfig {
     pub storage_costs_constants: StorageCostsConstants,
     /// Max gas for read only executions
     pub max_read_only_gas: u64,
+    /// maximum number of read-only call results kept in cache (0 disables the cache)
+    pub readonly_call_cache_size: u32,
     /// Gas costs
     pub gas_costs: GasCosts,
+    /// path of the ABI gas costs file, used to attribute gas to host functions when profiling gas
+    pub abi_gas_costs_file: PathBuf,
     /// Gas used by a transaction, a roll buy or a roll sell)
     pub base_operation_gas_cost: u64,
     /// last start period, used to attach to the correct execution slot if the network has restarted
@@ -82,6 +107,9 @@ pub struct ExecutionConfig {
     pub hd_cache_size: usize,
     /// Amount of entries removed when `hd_cache_size` is reached
     pub snip_amount: usize,
+    /// Fingerprint of the compilation settings the HD cache content depends on.
+    /// The HD cache is kept across restarts as long as this fingerprint does not change
+    pub hd_cache_fingerprint: Hash,
     /// Number of roll to remove per denunciation
     pub roll_count_to_slash_on_denunciation: u64,
     /// Denunciation expire delta
@@ -92,4 +120,30 @@ pub struct ExecutionConfig {
     pub broadcast_slot_execution_output_channel_capacity: usize,
     /// max size of event data, in bytes
     pub max_event_size: usize,
+    /// whether slot execution traces (ledger accesses, gas charges, ABI calls) are recorded and dumped
+    pub execution_trace_enabled: bool,
+    /// directory where slot execution traces are dumped, one file per slot and finality (`{period}_{thread}_{final|candidate}`)
+    pub execution_trace_path: PathBuf,
+    /// format of the slot execution trace dumps
+    pub execution_trace_format: ExecutionTraceFormat,
+    /// number of SCE-final slots between two execution checkpoints (0 disables checkpointing)
+    pub execution_checkpoint_interval: u64,
+    /// file in which the latest execution checkpoint is written
+    pub execution_checkpoint_path: PathBuf,
+    /// whether final events are stored on disk with secondary indexes by emitter, caller and data prefix
+    pub event_index_enabled: bool,
+    /// directory of the on-disk final event index
+    pub event_index_path: PathBuf,
+    /// maximum number of final events kept in the on-disk index
+    pub max_indexed_events: usize,
+    /// whether per-contract datastore access statistics are recorded during slot execution
+    pub contract_io_stats_enabled: bool,
+    /// number of SCE-final slots whose execution outputs are retained for the API queries
+    pub api_output_retention_slots: u64,
+    /// maximum number of SCE-final slots whose execution outputs are retained for the gRPC streams that did not acknowledge them
+    pub stream_output_retention_slots: u64,
+    /// maximum number of SCE-final slots whose execution outputs are retained for the finality bookkeeping that did not acknowledge them
+    pub finality_output_retention_slots: u64,
+    /// number of SCE-final slots whose final state hash is kept, to be compared with the other nodes
+    pub final_state_hash_history_length: usize,
 }
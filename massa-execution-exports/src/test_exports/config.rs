@@ -2,7 +2,8 @@
 
 //! This file defines testing tools related to the configuration
 
-use crate::{ExecutionConfig, StorageCostsConstants};
+use crate::{ExecutionConfig, ExecutionTraceFormat, StorageCostsConstants};
+use massa_hash::Hash;
 use massa_models::config::*;
 use massa_sc_runtime::GasCosts;
 use massa_time::MassaTime;
@@ -22,11 +23,14 @@ impl Default for ExecutionConfig {
         Self {
             readonly_queue_length: 100,
             max_final_events: 1000,
+            max_recent_operation_results: 1000,
             max_async_gas: MAX_ASYNC_GAS,
             async_msg_cst_gas_cost: ASYNC_MSG_CST_GAS_COST,
             thread_count: THREAD_COUNT,
             roll_price: ROLL_PRICE,
             cursor_delay: MassaTime::from_millis(0),
+            max_candidate_execution_lag: 1024,
+            candidate_execution_enabled: true,
             block_reward: BLOCK_REWARD,
             endorsement_count: ENDORSEMENT_COUNT as u64,
             max_gas_per_block: MAX_GAS_PER_BLOCK,
@@ -55,12 +59,19 @@ impl Default for ExecutionConfig {
                 .into(),
             )
             .unwrap(),
+            abi_gas_costs_file: concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/../massa-node/base_config/gas_costs/abi_gas_costs.json"
+            )
+            .into(),
             base_operation_gas_cost: BASE_OPERATION_GAS_COST,
             last_start_period: 0,
             hd_cache_path: TempDir::new().unwrap().path().to_path_buf(),
             lru_cache_size: 1000,
+            readonly_call_cache_size: 1000,
             hd_cache_size: 10_000,
             snip_amount: 10,
+            hd_cache_fingerprint: Hash::compute_from(b"test"),
             roll_count_to_slash_on_denunciation: 1,
             denunciation_expire_periods: DENUNCIATION_EXPIRE_PERIODS,
             broadcast_enabled: true,
@@ -68,6 +79,22 @@ impl Default for ExecutionConfig {
             max_event_size: 50_000,
             max_function_length: 1000,
             max_parameter_length: 1000,
+            execution_trace_enabled: false,
+            execution_trace_path: TempDir::new().unwrap().path().to_path_buf(),
+            execution_trace_format: ExecutionTraceFormat::Json,
+            execution_checkpoint_interval: 0,
+            execution_checkpoint_path: TempDir::new()
+                .unwrap()
+                .path()
+                .join("execution_checkpoint.json"),
+            event_index_enabled: false,
+            event_index_path: TempDir::new().unwrap().path().to_path_buf(),
+            max_indexed_events: 10_000,
+            contract_io_stats_enabled: false,
+            api_output_retention_slots: 64,
+            stream_output_retention_slots: 640,
+            finality_output_retention_slots: 64,
+            final_state_hash_history_length: 1000,
         }
     }
 }
@@ -12,6 +12,16 @@
 //! ## `mock.rs`
 //! Provides a mock of `ExecutionController` to simulate interactions
 //! with an execution worker within tests.
+//!
+//! ## `scenario.rs`
+//! Provides a harness to script slots with blocks and operations, feed them to an execution
+//! controller, and assert over the resulting state changes and events. Also provides
+//! deterministic selector and ledger mocks for the execution worker.
 
 mod config;
 pub use config::*;
+
+#[cfg(feature = "test-exports")]
+mod scenario;
+#[cfg(feature = "test-exports")]
+pub use scenario::*;
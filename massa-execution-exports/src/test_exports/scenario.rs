@@ -0,0 +1,470 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Scripted execution scenarios.
+//!
+//! An `ExecutionScenario` describes a sequence of slots with their blocks and operations,
+//! and feeds them to any `ExecutionController`. The resulting `SlotExecutionOutput`s are
+//! gathered in a `ScenarioOutputs` on which assertions about balances, datastore entries
+//! and events can be made.
+//!
+//! `mock_selector` and `ScenarioLedger` provide deterministic selector and ledger mocks
+//! to plug into an execution worker, so that execution-adjacent logic can be unit-tested
+//! without setting up the whole node.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    time::{Duration, Instant},
+};
+
+use massa_hash::Hash;
+use massa_ledger_exports::MockLedgerControllerWrapper;
+use massa_models::{
+    address::Address,
+    amount::Amount,
+    block::{Block, BlockSerializer, SecureShareBlock},
+    block_header::{BlockHeader, BlockHeaderSerializer},
+    block_id::BlockId,
+    bytecode::Bytecode,
+    operation::{compute_operations_hash, OperationIdSerializer, SecureShareOperation},
+    output_event::SCOutputEvent,
+    prehash::PreHashMap,
+    secure_share::SecureShareContent,
+    slot::Slot,
+};
+use massa_pos_exports::{MockSelectorControllerWrapper, Selection};
+use massa_signature::KeyPair;
+use massa_storage::Storage;
+use tokio::sync::broadcast::{self, error::TryRecvError};
+
+use crate::{ExecutionBlockMetadata, ExecutionController, ExecutionOutput, SlotExecutionOutput};
+
+/// A block scripted at a given slot
+struct ScenarioBlock {
+    block: SecureShareBlock,
+    creator: Address,
+    storage: Storage,
+}
+
+/// Sequence of slots with their blocks and operations, to be fed to an execution controller
+pub struct ExecutionScenario {
+    storage: Storage,
+    thread_count: u8,
+    blocks: BTreeMap<Slot, ScenarioBlock>,
+}
+
+impl ExecutionScenario {
+    /// Creates an empty scenario.
+    ///
+    /// # Arguments
+    /// * `storage`: storage from which the storage of each block is derived
+    /// * `thread_count`: number of threads, used to generate the parents of the blocks
+    pub fn new(storage: Storage, thread_count: u8) -> Self {
+        ExecutionScenario {
+            storage,
+            thread_count,
+            blocks: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a block created by `creator` at `slot` and containing `operations`.
+    /// Slots without a block are executed as misses.
+    pub fn block(
+        mut self,
+        slot: Slot,
+        creator: &KeyPair,
+        operations: Vec<SecureShareOperation>,
+    ) -> Self {
+        let op_ids = operations.iter().map(|op| op.id).collect::<Vec<_>>();
+        let operation_merkle_root = compute_operations_hash(&op_ids, &OperationIdSerializer::new());
+        let parents = (0..self.thread_count)
+            .map(|thread| {
+                BlockId::generate_from_hash(Hash::compute_from(
+                    format!("Genesis {}", thread).as_bytes(),
+                ))
+            })
+            .collect();
+        let header = BlockHeader::new_verifiable(
+            BlockHeader {
+                current_version: 0,
+                announced_version: None,
+                slot,
+                parents,
+                operation_merkle_root,
+                endorsements: Vec::new(),
+                denunciations: Vec::new(),
+                extensions: Vec::new(),
+            },
+            BlockHeaderSerializer::new(),
+            creator,
+        )
+        .expect("could not create scenario block header");
+        let block = Block::new_verifiable(
+            Block {
+                header,
+                operations: op_ids,
+            },
+            BlockSerializer::new(),
+            creator,
+        )
+        .expect("could not create scenario block");
+
+        let mut storage = self.storage.clone_without_refs();
+        storage.store_operations(operations);
+        storage.store_block(block.clone());
+        self.blocks.insert(
+            slot,
+            ScenarioBlock {
+                block,
+                creator: Address::from_public_key(&creator.get_public_key()),
+                storage,
+            },
+        );
+        self
+    }
+
+    /// Returns the ids of the scripted blocks by slot
+    pub fn block_ids(&self) -> HashMap<Slot, BlockId> {
+        self.blocks
+            .iter()
+            .map(|(slot, b)| (*slot, b.block.id))
+            .collect()
+    }
+
+    /// Returns the last slot holding a scripted block, if any
+    pub fn last_slot(&self) -> Option<Slot> {
+        self.blocks.keys().next_back().copied()
+    }
+
+    fn block_metadata(&self) -> PreHashMap<BlockId, ExecutionBlockMetadata> {
+        self.blocks
+            .values()
+            .map(|b| {
+                (
+                    b.block.id,
+                    ExecutionBlockMetadata {
+                        same_thread_parent_creator: Some(b.creator),
+                        storage: Some(b.storage.clone()),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Feeds all the scripted blocks to `controller` as final blocks
+    pub fn run_final(&self, controller: &dyn ExecutionController) {
+        controller.update_blockclique_status(
+            self.block_ids(),
+            Default::default(),
+            self.block_metadata(),
+        );
+    }
+
+    /// Feeds all the scripted blocks to `controller` as the current blockclique, without finalizing them
+    pub fn run_candidate(&self, controller: &dyn ExecutionController) {
+        controller.update_blockclique_status(
+            Default::default(),
+            Some(self.block_ids()),
+            self.block_metadata(),
+        );
+    }
+}
+
+/// Outputs of the slots executed during a scenario
+#[derive(Default)]
+pub struct ScenarioOutputs {
+    outputs: BTreeMap<Slot, ExecutionOutput>,
+}
+
+impl ScenarioOutputs {
+    /// Gathers the finalized slot outputs sent on `receiver` until `last_slot` is finalized.
+    ///
+    /// # Panics
+    /// If `last_slot` is not finalized before `timeout` expires, or if the channel is closed.
+    pub fn collect_final(
+        receiver: &mut broadcast::Receiver<SlotExecutionOutput>,
+        last_slot: Slot,
+        timeout: Duration,
+    ) -> Self {
+        let deadline = Instant::now() + timeout;
+        let mut outputs = ScenarioOutputs::default();
+        while !outputs.outputs.contains_key(&last_slot) {
+            match receiver.try_recv() {
+                Ok(SlotExecutionOutput::FinalizedSlot(output)) => {
+                    outputs.push(output);
+                }
+                Ok(SlotExecutionOutput::ExecutedSlot(_)) | Err(TryRecvError::Lagged(_)) => {}
+                Err(TryRecvError::Empty) => {
+                    if Instant::now() >= deadline {
+                        panic!("slot {} was not finalized before timeout", last_slot);
+                    }
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Err(TryRecvError::Closed) => {
+                    panic!("execution output channel closed before slot {}", last_slot)
+                }
+            }
+        }
+        outputs
+    }
+
+    /// Adds an execution output, replacing any previous output at the same slot
+    pub fn push(&mut self, output: ExecutionOutput) {
+        self.outputs.insert(output.slot, output);
+    }
+
+    /// Returns the output of a slot, if it was executed
+    pub fn get(&self, slot: &Slot) -> Option<&ExecutionOutput> {
+        self.outputs.get(slot)
+    }
+
+    /// Returns all the events emitted during the scenario, in slot order
+    pub fn events(&self) -> impl Iterator<Item = &SCOutputEvent> {
+        self.outputs
+            .values()
+            .flat_map(|output| output.events.0.iter())
+    }
+
+    /// Returns the balance of `addr` after the last executed slot,
+    /// using `initial` as the balance before the scenario
+    pub fn final_balance(&self, addr: &Address, initial: Option<Amount>) -> Option<Amount> {
+        self.outputs.values().fold(initial, |prev, output| {
+            output
+                .state_changes
+                .ledger_changes
+                .get_balance_or_else(addr, || prev)
+        })
+    }
+
+    /// Returns the datastore entry of `addr` at `key` after the last executed slot,
+    /// using `initial` as the value before the scenario
+    pub fn final_data_entry(
+        &self,
+        addr: &Address,
+        key: &[u8],
+        initial: Option<Vec<u8>>,
+    ) -> Option<Vec<u8>> {
+        self.outputs.values().fold(initial, |prev, output| {
+            output
+                .state_changes
+                .ledger_changes
+                .get_data_entry_or_else(addr, key, || prev)
+        })
+    }
+
+    /// Asserts the balance of `addr` after the last executed slot
+    pub fn assert_balance(
+        &self,
+        addr: &Address,
+        initial: Option<Amount>,
+        expected: Option<Amount>,
+    ) {
+        assert_eq!(
+            self.final_balance(addr, initial),
+            expected,
+            "unexpected balance for address {}",
+            addr
+        );
+    }
+
+    /// Asserts the datastore entry of `addr` at `key` after the last executed slot
+    pub fn assert_data_entry(
+        &self,
+        addr: &Address,
+        key: &[u8],
+        initial: Option<Vec<u8>>,
+        expected: Option<Vec<u8>>,
+    ) {
+        assert_eq!(
+            self.final_data_entry(addr, key, initial),
+            expected,
+            "unexpected datastore entry for address {}",
+            addr
+        );
+    }
+
+    /// Asserts that at least one event matches `predicate`
+    pub fn assert_event<F: Fn(&SCOutputEvent) -> bool>(&self, predicate: F) {
+        assert!(
+            self.events().any(predicate),
+            "no matching event among: {:?}",
+            self.events().map(|e| &e.data).collect::<Vec<_>>()
+        );
+    }
+
+    /// Asserts that no error event was emitted
+    pub fn assert_no_error_event(&self) {
+        if let Some(event) = self.events().find(|e| e.context.is_error) {
+            panic!("unexpected error event: {}", event.data);
+        }
+    }
+}
+
+/// Creates a selector mock drawing `producer` as block producer and for every endorsement of every slot
+pub fn mock_selector(
+    producer: Address,
+    endorsement_count: u32,
+) -> Box<MockSelectorControllerWrapper> {
+    let mut selector = MockSelectorControllerWrapper::new();
+    selector.set_expectations(|selector_controller| {
+        selector_controller
+            .expect_feed_cycle()
+            .returning(|_, _, _| Ok(()));
+        selector_controller
+            .expect_wait_for_draws()
+            .returning(|cycle| Ok(cycle + 1));
+        selector_controller
+            .expect_get_producer()
+            .returning(move |_| Ok(producer));
+        selector_controller
+            .expect_get_selection()
+            .returning(move |_| {
+                Ok(Selection {
+                    endorsements: vec![producer; endorsement_count as usize],
+                    producer,
+                })
+            });
+    });
+    Box::new(selector)
+}
+
+/// Initial ledger of a scenario, turned into a ledger mock
+#[derive(Default, Clone)]
+pub struct ScenarioLedger {
+    balances: BTreeMap<Address, Amount>,
+    bytecodes: BTreeMap<Address, Bytecode>,
+    datastores: BTreeMap<Address, BTreeMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl ScenarioLedger {
+    /// Sets the balance of an address
+    pub fn with_balance(mut self, addr: Address, balance: Amount) -> Self {
+        self.balances.insert(addr, balance);
+        self
+    }
+
+    /// Sets the bytecode of an address
+    pub fn with_bytecode(mut self, addr: Address, bytecode: Vec<u8>) -> Self {
+        self.bytecodes.insert(addr, Bytecode(bytecode));
+        self
+    }
+
+    /// Sets a datastore entry of an address
+    pub fn with_data_entry(mut self, addr: Address, key: Vec<u8>, value: Vec<u8>) -> Self {
+        self.datastores.entry(addr).or_default().insert(key, value);
+        self
+    }
+
+    /// Returns the addresses holding an entry in this ledger
+    fn addresses(&self) -> BTreeSet<Address> {
+        self.balances
+            .keys()
+            .chain(self.bytecodes.keys())
+            .chain(self.datastores.keys())
+            .copied()
+            .collect()
+    }
+
+    /// Creates a ledger mock answering reads from this ledger.
+    /// Changes applied to the mock are ignored.
+    pub fn into_mock(self) -> MockLedgerControllerWrapper {
+        let mut ledger = MockLedgerControllerWrapper::new();
+        let addresses = self.addresses();
+        let ScenarioLedger {
+            balances,
+            bytecodes,
+            datastores,
+        } = self;
+        ledger.set_expectations(|ledger_controller| {
+            let (balances, bytecodes, addresses) =
+                (balances.clone(), bytecodes.clone(), addresses.clone());
+            let (datastores, keys_datastores) = (datastores.clone(), datastores.clone());
+            ledger_controller
+                .expect_get_balance()
+                .returning(move |addr| balances.get(addr).copied());
+            ledger_controller
+                .expect_get_bytecode()
+                .returning(move |addr| bytecodes.get(addr).cloned());
+            ledger_controller
+                .expect_entry_exists()
+                .returning(move |addr| addresses.contains(addr));
+            ledger_controller
+                .expect_get_data_entry()
+                .returning(move |addr, key| {
+                    datastores
+                        .get(addr)
+                        .and_then(|datastore| datastore.get(key).cloned())
+                });
+            ledger_controller
+                .expect_get_datastore_keys()
+                .returning(move |addr, prefix| {
+                    keys_datastores.get(addr).map(|datastore| {
+                        datastore
+                            .keys()
+                            .filter(|key| key.starts_with(prefix))
+                            .cloned()
+                            .collect()
+                    })
+                });
+            ledger_controller
+                .expect_apply_changes_to_batch()
+                .returning(|_, _| {});
+        });
+        ledger
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_final_state::StateChanges;
+    use massa_ledger_exports::{LedgerChanges, LedgerEntryUpdate, SetOrKeep, SetUpdateOrDelete};
+
+    fn output_with_balance(
+        slot: Slot,
+        addr: Address,
+        balance: SetOrKeep<Amount>,
+    ) -> ExecutionOutput {
+        let mut ledger_changes = LedgerChanges::default();
+        ledger_changes.0.insert(
+            addr,
+            SetUpdateOrDelete::Update(LedgerEntryUpdate {
+                balance,
+                ..Default::default()
+            }),
+        );
+        ExecutionOutput {
+            slot,
+            block_info: None,
+            state_changes: StateChanges {
+                ledger_changes,
+                ..Default::default()
+            },
+            events: Default::default(),
+            operation_results: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_scenario_outputs_final_balance() {
+        let addr = Address::from_public_key(&KeyPair::generate(0).unwrap().get_public_key());
+        let initial = Some(Amount::from_raw(10));
+        let mut outputs = ScenarioOutputs::default();
+        outputs.assert_balance(&addr, initial, initial);
+
+        outputs.push(output_with_balance(
+            Slot::new(1, 0),
+            addr,
+            SetOrKeep::Set(Amount::from_raw(20)),
+        ));
+        outputs.push(output_with_balance(Slot::new(1, 1), addr, SetOrKeep::Keep));
+        outputs.assert_balance(&addr, initial, Some(Amount::from_raw(20)));
+
+        outputs.push(output_with_balance(
+            Slot::new(2, 0),
+            addr,
+            SetOrKeep::Set(Amount::from_raw(5)),
+        ));
+        outputs.assert_balance(&addr, initial, Some(Amount::from_raw(5)));
+        outputs.assert_no_error_event();
+    }
+}
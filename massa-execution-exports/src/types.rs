@@ -11,15 +11,17 @@ use massa_models::bytecode::Bytecode;
 use massa_models::datastore::Datastore;
 use massa_models::denunciation::DenunciationIndex;
 use massa_models::execution::EventFilter;
-use massa_models::operation::OperationId;
+use massa_models::operation::{OperationId, SecureShareOperation};
 use massa_models::output_event::SCOutputEvent;
-use massa_models::prehash::PreHashSet;
+use massa_models::prehash::{PreHashMap, PreHashSet};
 use massa_models::{
     address::Address, address::ExecutionAddressCycleInfo, amount::Amount, slot::Slot,
 };
 use massa_pos_exports::ProductionStats;
 use massa_storage::Storage;
-use std::collections::{BTreeMap, BTreeSet};
+use massa_time::MassaTime;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 /// Metadata needed to execute the block
 #[derive(Clone, Debug)]
@@ -119,6 +121,28 @@ pub enum ExecutionQueryRequestItem {
 
     /// get filtered events. Returns ExecutionQueryResponseItem::Events
     Events(EventFilter),
+
+    /// reads a final value along with the final state it was read from, returns ExecutionQueryResponseItem::Authenticated
+    /// or an error if the inner query does not read the final state
+    Authenticated(Box<ExecutionQueryRequestItem>),
+}
+
+impl ExecutionQueryRequestItem {
+    /// Whether the query only reads the final state, so that its result can be bound to the final state hash
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            ExecutionQueryRequestItem::AddressExistsFinal(_)
+                | ExecutionQueryRequestItem::AddressBalanceFinal(_)
+                | ExecutionQueryRequestItem::AddressBytecodeFinal(_)
+                | ExecutionQueryRequestItem::AddressDatastoreKeysFinal { .. }
+                | ExecutionQueryRequestItem::AddressDatastoreValueFinal { .. }
+                | ExecutionQueryRequestItem::OpExecutionStatusFinal(_)
+                | ExecutionQueryRequestItem::DenunciationExecutionStatusFinal(_)
+                | ExecutionQueryRequestItem::AddressRollsFinal(_)
+                | ExecutionQueryRequestItem::AddressDeferredCreditsFinal(_)
+        )
+    }
 }
 
 /// Execution state query response item
@@ -143,6 +167,22 @@ pub enum ExecutionQueryResponseItem {
     CycleInfos(ExecutionQueryCycleInfos),
     /// Events
     Events(Vec<SCOutputEvent>),
+    /// value read from the final state, bound to that state
+    Authenticated(AuthenticatedQueryResult),
+}
+
+/// Value read from the final state, along with the final state it was read from.
+/// Clients caching the value can compare the final state hash to the one currently advertised to detect stale values.
+pub struct AuthenticatedQueryResult {
+    /// value read, None if it was not found in the final state
+    pub value: Option<Box<ExecutionQueryResponseItem>>,
+    /// slot at the end of which the final state was read
+    pub slot: Slot,
+    /// hash of the final state that was read
+    pub final_state_hash: Hash,
+    /// proof of the value against the final state hash.
+    /// Always None for now: the ledger is not committed to in a way that allows proving single values yet.
+    pub proof: Option<Vec<u8>>,
 }
 
 /// Execution status of an operation or denunciation
@@ -203,6 +243,17 @@ pub struct ExecutionAddressInfo {
     pub cycle_infos: Vec<ExecutionAddressCycleInfo>,
 }
 
+/// Deferred credits of an address unlocking during a cycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleDeferredCredits {
+    /// cycle during which the credits unlock
+    pub cycle: u64,
+    /// total amount unlocking during the cycle
+    pub total: Amount,
+    /// amount unlocking at each slot of the cycle
+    pub credits: BTreeMap<Slot, Amount>,
+}
+
 /// structure describing the output of the execution of a slot
 #[derive(Debug, Clone)]
 pub enum SlotExecutionOutput {
@@ -214,7 +265,7 @@ pub enum SlotExecutionOutput {
 }
 
 /// structure storing a block id + network versions (from a block header)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutedBlockInfo {
     /// Block id
     pub block_id: BlockId,
@@ -225,7 +276,7 @@ pub struct ExecutedBlockInfo {
 }
 
 /// structure describing the output of a single execution
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionOutput {
     /// slot
     pub slot: Slot,
@@ -235,6 +286,297 @@ pub struct ExecutionOutput {
     pub state_changes: StateChanges,
     /// events emitted by the execution step
     pub events: EventStore,
+    /// results of the operations executed during the execution step
+    pub operation_results: PreHashMap<OperationId, OperationExecutionResult>,
+}
+
+/// Class of consumers of the SCE-final execution outputs.
+/// Each class retains the outputs for its own window of final slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExecutionOutputConsumerClass {
+    /// API queries, that retain the outputs for the whole window
+    Api,
+    /// gRPC streams, that retain the outputs until all of them acknowledged them, within the window
+    Stream,
+    /// internal finality bookkeeping, that retains the outputs until acknowledged, within the window
+    Finality,
+}
+
+/// Identifier of a consumer of the SCE-final execution outputs, used to acknowledge them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExecutionOutputConsumerId(pub u64);
+
+/// Status of the execution slot sequencer, useful to understand why execution appears stuck
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotSequencerStatus {
+    /// number of slots in the execution sequence
+    pub sequence_length: usize,
+    /// latest CSS-final slots (one per thread)
+    pub latest_consensus_final_slots: Vec<Slot>,
+    /// latest SCE-final slot
+    pub latest_execution_final_slot: Slot,
+    /// final slot execution cursor
+    pub latest_executed_final_slot: Slot,
+    /// candidate slot execution cursor
+    pub latest_executed_candidate_slot: Slot,
+}
+
+/// Block and miss counts of the SCE-final slots of a thread
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadSlotMissStats {
+    /// number of executed SCE-final slots containing a block
+    pub block_count: u64,
+    /// number of executed SCE-final slots without a block
+    pub miss_count: u64,
+}
+
+/// Per-thread statistics of the SCE-final slots executed in a sliding time window,
+/// useful to monitor network liveness
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotMissStats {
+    /// time window start
+    pub time_window_start: MassaTime,
+    /// time window end: timestamp of the latest executed SCE-final slot
+    pub time_window_end: MassaTime,
+    /// block and miss counts of each thread, indexed by thread
+    pub threads: Vec<ThreadSlotMissStats>,
+}
+
+/// Filter of the asynchronous messages of the final pool
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AsyncMessageFilter {
+    /// keep only the messages emitted by this address
+    pub sender: Option<Address>,
+    /// keep only the messages targeting this address
+    pub destination: Option<Address>,
+    /// keep only the messages whose validity window contains this slot
+    pub valid_at: Option<Slot>,
+    /// keep only the messages whose trigger is (or is not) satisfied
+    pub can_be_executed: Option<bool>,
+}
+
+/// Asynchronous message waiting in the final pool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingAsyncMessage {
+    /// slot at which the message was emitted
+    pub emission_slot: Slot,
+    /// index of the message among the messages emitted at `emission_slot`
+    pub emission_index: u64,
+    /// address that sent the message
+    pub sender: Address,
+    /// address targeted by the message
+    pub destination: Address,
+    /// function called on the destination
+    pub function: String,
+    /// maximum gas to use when executing the message
+    pub max_gas: u64,
+    /// fee paid by the sender when the message is executed
+    pub fee: Amount,
+    /// coins sent from the sender to the destination
+    pub coins: Amount,
+    /// first slot at which the message can be executed
+    pub validity_start: Slot,
+    /// slot at which the message expires (excluded from the validity window)
+    pub validity_end: Slot,
+    /// whether the message has a trigger
+    pub has_trigger: bool,
+    /// whether the trigger of the message, if any, is satisfied
+    pub can_be_executed: bool,
+}
+
+/// Kind of a coin movement caused by the execution of an operation
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CoinMovementKind {
+    /// fee of the operation, paid by its sender
+    Fee,
+    /// coins transferred by the operation or by a smart contract, including coins sent along a call
+    #[default]
+    Transfer,
+    /// storage costs charged for new ledger data, or refunded when ledger data is freed
+    Storage,
+}
+
+/// A coin movement caused by the execution of an operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationCoinMovement {
+    /// spending address (None for coin creation)
+    pub from: Option<Address>,
+    /// credited address (None for coin destruction)
+    pub to: Option<Address>,
+    /// amount of coins moved
+    pub amount: Amount,
+    /// kind of the movement
+    #[serde(default)]
+    pub kind: CoinMovementKind,
+    /// address at the top of the call stack when the movement happened,
+    /// which is the smart contract that initiated it during smart contract executions
+    #[serde(default)]
+    pub initiator: Option<Address>,
+}
+
+/// Edge of the coin flow graph of an operation: coin movements with the same source, destination and kind
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoinFlowEdge {
+    /// spending address (None for coin creation)
+    pub from: Option<Address>,
+    /// credited address (None for coin destruction)
+    pub to: Option<Address>,
+    /// kind of the movements
+    pub kind: CoinMovementKind,
+    /// total amount of coins moved
+    pub amount: Amount,
+    /// number of movements
+    pub count: u64,
+}
+
+/// Coins credited to and debited from an address by an operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoinFlowBalance {
+    /// address
+    pub address: Address,
+    /// total amount of coins credited to the address
+    pub credited: Amount,
+    /// total amount of coins debited from the address
+    pub debited: Amount,
+}
+
+/// Coin flow graph of an executed operation,
+/// accounting for every coin moved by the operation including its fee and storage costs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationCoinFlow {
+    /// id of the executed operation
+    pub operation_id: OperationId,
+    /// slot at which the operation was executed
+    pub slot: Slot,
+    /// edges of the graph, in the order of their first movement
+    pub edges: Vec<CoinFlowEdge>,
+    /// coins credited to and debited from each address, sorted by address
+    pub balances: Vec<CoinFlowBalance>,
+    /// total amount of coins created (storage refunds included)
+    pub created: Amount,
+    /// total amount of coins destroyed (fee and storage costs included)
+    pub destroyed: Amount,
+    /// true if the execution is final
+    pub is_final: bool,
+}
+
+impl OperationCoinFlow {
+    /// Aggregates coin movements into graph edges, in the order of their first movement
+    pub fn edges_from_movements(movements: &[OperationCoinMovement]) -> Vec<CoinFlowEdge> {
+        let mut edges: Vec<CoinFlowEdge> = Vec::new();
+        let mut edge_indices: HashMap<(Option<Address>, Option<Address>, CoinMovementKind), usize> =
+            HashMap::new();
+        for movement in movements {
+            let index = *edge_indices
+                .entry((movement.from, movement.to, movement.kind))
+                .or_insert_with(|| {
+                    edges.push(CoinFlowEdge {
+                        from: movement.from,
+                        to: movement.to,
+                        kind: movement.kind,
+                        amount: Amount::zero(),
+                        count: 0,
+                    });
+                    edges.len() - 1
+                });
+            let edge = &mut edges[index];
+            edge.amount = edge.amount.saturating_add(movement.amount);
+            edge.count = edge.count.saturating_add(1);
+        }
+        edges
+    }
+
+    /// Builds the coin flow graph of an operation from its execution result
+    pub fn from_result(result: &OperationExecutionResult) -> Self {
+        let edges = Self::edges_from_movements(&result.coin_movements);
+        let mut balances: BTreeMap<Address, (Amount, Amount)> = BTreeMap::new();
+        let mut created = Amount::zero();
+        let mut destroyed = Amount::zero();
+        for edge in &edges {
+            match edge.from {
+                Some(from) => {
+                    let (_, debited) = balances.entry(from).or_default();
+                    *debited = debited.saturating_add(edge.amount);
+                }
+                None => created = created.saturating_add(edge.amount),
+            }
+            match edge.to {
+                Some(to) => {
+                    let (credited, _) = balances.entry(to).or_default();
+                    *credited = credited.saturating_add(edge.amount);
+                }
+                None => destroyed = destroyed.saturating_add(edge.amount),
+            }
+        }
+        OperationCoinFlow {
+            operation_id: result.operation_id,
+            slot: result.slot,
+            edges,
+            balances: balances
+                .into_iter()
+                .map(|(address, (credited, debited))| CoinFlowBalance {
+                    address,
+                    credited,
+                    debited,
+                })
+                .collect(),
+            created,
+            destroyed,
+            is_final: result.is_final,
+        }
+    }
+}
+
+/// Result of the execution of an operation included in a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationExecutionResult {
+    /// id of the executed operation
+    pub operation_id: OperationId,
+    /// slot at which the operation was executed
+    pub slot: Slot,
+    /// block in which the operation was executed
+    pub block_id: Option<BlockId>,
+    /// true if the operation was executed successfully
+    pub success: bool,
+    /// error that made the execution fail, if any
+    pub error: Option<String>,
+    /// gas charged to the block for the operation
+    pub gas_used: u64,
+    /// events emitted during the execution of the operation
+    pub events: Vec<SCOutputEvent>,
+    /// coin movements caused by the operation, including its fee.
+    /// Movements reverted by a failure are not listed.
+    pub coin_movements: Vec<OperationCoinMovement>,
+    /// call stack depth reached and gas available to the nested calls, for smart contract operations
+    #[serde(default)]
+    pub call_stats: OperationCallStats,
+    /// true if the execution is final
+    pub is_final: bool,
+}
+
+/// Calls made at a given depth of the call stack during an operation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallDepthGasStats {
+    /// number of calls made at this depth
+    pub call_count: u64,
+    /// sum of the gas remaining when each of those calls started
+    pub entry_gas: u64,
+    /// lowest gas remaining when one of those calls started
+    pub min_entry_gas: u64,
+}
+
+/// Call stack telemetry of a smart contract operation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationCallStats {
+    /// gas made available to the smart contract by the operation
+    pub max_gas: u64,
+    /// deepest nested call reached, 0 if the smart contract called no other smart contract
+    pub max_call_depth: u64,
+    /// calls made at each nested call depth, starting with the calls made by the smart contract of the operation (depth 1)
+    pub depths: Vec<CallDepthGasStats>,
 }
 
 /// structure describing the output of a read only execution
@@ -261,6 +603,24 @@ pub struct ReadOnlyExecutionRequest {
     pub coins: Option<Amount>,
     /// Fee
     pub fee: Option<Amount>,
+    /// State on top of which the request is executed
+    pub state: ReadOnlyExecutionState,
+    /// hypothetical state of ledger entries applied before the execution.
+    /// Entries of addresses that do not exist are created.
+    pub ledger_overrides: PreHashMap<Address, LedgerEntryOverride>,
+}
+
+/// State on top of which a read-only execution request is executed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReadOnlyExecutionState {
+    /// the latest candidate state, the request is executed at the slot after the latest executed candidate slot
+    #[default]
+    Candidate,
+    /// the latest final state, the request is executed at the slot after the latest final slot
+    Final,
+    /// the state right after the execution of a slot between the latest final and candidate slots included,
+    /// the request is executed at the slot after it
+    Slot(Slot),
 }
 
 /// structure describing different possible targets of a read-only execution request
@@ -280,6 +640,38 @@ pub enum ReadOnlyExecutionTarget {
     },
 }
 
+/// Hypothetical state of a ledger entry, overriding its actual state during an operation simulation
+#[derive(Debug, Clone, Default)]
+pub struct LedgerEntryOverride {
+    /// balance of the entry, if overridden
+    pub balance: Option<Amount>,
+    /// bytecode of the entry, if overridden
+    pub bytecode: Option<Bytecode>,
+    /// overridden datastore entries: `Some(value)` sets the entry, `None` deletes it
+    pub datastore: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+/// Request to simulate the execution of an operation on top of the latest candidate state,
+/// without modifying it
+#[derive(Debug, Clone)]
+pub struct OperationSimulationRequest {
+    /// operation to simulate. Its signature is not checked.
+    pub operation: SecureShareOperation,
+    /// hypothetical state of ledger entries applied before the simulation.
+    /// Entries of addresses that do not exist are created.
+    pub ledger_overrides: PreHashMap<Address, LedgerEntryOverride>,
+}
+
+/// Output of an operation simulation
+#[derive(Debug, Clone)]
+pub struct OperationSimulationOutput {
+    /// Output of the simulated slot.
+    /// Its state changes include the ledger overrides of the request.
+    pub out: ExecutionOutput,
+    /// detailed result of the simulated operation
+    pub result: OperationExecutionResult,
+}
+
 /// structure describing a read-only call
 #[derive(Debug, Clone)]
 pub struct ReadOnlyCallRequest {
@@ -323,3 +715,67 @@ pub struct ExecutionStackElement {
     /// Datastore (key value store) for `ExecuteSC` Operation
     pub operation_datastore: Option<Datastore>,
 }
+
+/// Gas attributed to an ABI host function
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostFunctionGasProfile {
+    /// number of calls
+    pub call_count: u64,
+    /// gas charged by the runtime for those calls (base cost of the ABI)
+    pub gas: u64,
+}
+
+/// Gas profile of an operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationGasProfile {
+    /// operation id
+    pub operation_id: OperationId,
+    /// gas charged for the operation
+    pub gas_used: u64,
+    /// gas attributed to each ABI host function called during the operation, by function name
+    pub host_functions: BTreeMap<String, HostFunctionGasProfile>,
+}
+
+/// Gas profile of an executed slot, recorded when the `gas_profile` feature of the execution worker is enabled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotGasProfile {
+    /// executed slot
+    pub slot: Slot,
+    /// block executed at that slot, if any
+    pub block_id: Option<BlockId>,
+    /// total gas charged for the operations of the slot
+    pub operations_gas: u64,
+    /// gas attributed to each ABI host function called during the slot (including asynchronous messages), by function name
+    pub host_functions: BTreeMap<String, HostFunctionGasProfile>,
+    /// profiles of the executed operations, in execution order
+    pub operations: Vec<OperationGasProfile>,
+}
+
+/// Access counts of a datastore key
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatastoreKeyIoStats {
+    /// datastore key
+    pub key: Vec<u8>,
+    /// number of reads of the entry
+    pub read_count: u64,
+    /// number of writes of the entry (including appends and deletions)
+    pub write_count: u64,
+}
+
+/// Datastore access statistics of a smart contract over the latest executed slots,
+/// recorded when `contract_io_stats_enabled` is set in the execution configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractIoStats {
+    /// smart contract address
+    pub address: Address,
+    /// number of executed slots covered by the statistics
+    pub slot_count: u64,
+    /// number of calls to the smart contract
+    pub call_count: u64,
+    /// number of reads in the datastore of the smart contract (entries, existence checks and key listings)
+    pub read_count: u64,
+    /// number of writes in the datastore of the smart contract
+    pub write_count: u64,
+    /// most accessed datastore keys, most accessed first
+    pub hot_keys: Vec<DatastoreKeyIoStats>,
+}
@@ -1,5 +1,5 @@
 use massa_async_pool::{AsyncMessage, AsyncMessageId, AsyncMessageUpdate};
-use massa_execution_exports::ExecutionOutput;
+use massa_execution_exports::{ExecutionOutput, OperationExecutionResult};
 use massa_ledger_exports::{
     Applicable, LedgerEntry, LedgerEntryUpdate, SetOrDelete, SetOrKeep, SetUpdateOrDelete,
 };
@@ -46,6 +46,17 @@ impl ActiveHistory {
         }
     }
 
+    /// Copy of the history of the slots before `slot`, leaving the history untouched.
+    /// Only the kept outputs are cloned.
+    pub fn history_before(&self, slot: &Slot, thread_count: u8) -> ActiveHistory {
+        let kept = match self.get_slot_index(slot, thread_count) {
+            SlotIndexPosition::Past | SlotIndexPosition::NoHistory => 0,
+            SlotIndexPosition::Found(index) => index,
+            SlotIndexPosition::Future => self.0.len(),
+        };
+        ActiveHistory(self.0.iter().take(kept).cloned().collect())
+    }
+
     /// Lazily query (from end to beginning) the active list of executed ops to check if an op was executed.
     ///
     /// Returns a `HistorySearchResult`.
@@ -347,4 +358,12 @@ impl ActiveHistory {
             .map(|op_id| found.get(op_id).copied())
             .collect()
     }
+
+    /// Get the most recent execution result of an operation, if it was executed in the active history
+    pub fn fetch_operation_result(&self, op_id: &OperationId) -> Option<&OperationExecutionResult> {
+        self.0
+            .iter()
+            .rev()
+            .find_map(|hist_item| hist_item.operation_results.get(op_id))
+    }
 }
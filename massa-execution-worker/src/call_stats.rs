@@ -0,0 +1,121 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! This module implements the call stack telemetry of the smart contract operations.
+//! During the execution of an operation, the nested calls made at each depth of the call stack are counted,
+//! along with the gas remaining when each of them started, that is the gas they could use
+//! (together with the calls they make) out of the gas of the operation.
+//! The telemetry is kept in the execution result of the operation, and aggregated in the metrics.
+
+use massa_execution_exports::{CallDepthGasStats, OperationCallStats};
+
+/// Records the call stack telemetry of the operation being executed
+pub(crate) struct CallStatsRecorder {
+    /// telemetry recorded so far
+    stats: OperationCallStats,
+    /// current nested call depth
+    depth: usize,
+    /// true between the start of a nested call and the loading of its module, which reports the remaining gas
+    awaiting_entry_gas: bool,
+}
+
+impl CallStatsRecorder {
+    /// Creates a recorder for an operation making `max_gas` available to its smart contract
+    pub fn new(max_gas: u64) -> Self {
+        CallStatsRecorder {
+            stats: OperationCallStats {
+                max_gas,
+                ..Default::default()
+            },
+            depth: 0,
+            awaiting_entry_gas: false,
+        }
+    }
+
+    /// Records the start of a nested call
+    pub fn enter_call(&mut self) {
+        self.depth = self.depth.saturating_add(1);
+        self.stats.max_call_depth = self.stats.max_call_depth.max(self.depth as u64);
+        if self.stats.depths.len() < self.depth {
+            self.stats.depths.push(CallDepthGasStats {
+                min_entry_gas: u64::MAX,
+                ..Default::default()
+            });
+        }
+        self.stats.depths[self.depth - 1].call_count += 1;
+        self.awaiting_entry_gas = true;
+    }
+
+    /// Records the gas remaining when the module of the nested call that just started was loaded
+    pub fn record_entry_gas(&mut self, gas: u64) {
+        if !std::mem::take(&mut self.awaiting_entry_gas) || self.depth == 0 {
+            return;
+        }
+        let depth_stats = &mut self.stats.depths[self.depth - 1];
+        depth_stats.entry_gas = depth_stats.entry_gas.saturating_add(gas);
+        depth_stats.min_entry_gas = depth_stats.min_entry_gas.min(gas);
+        if self.stats.max_gas > 0 {
+            massa_metrics::observe_execution_nested_call_gas_ratio(
+                self.depth as u64,
+                gas as f64 / self.stats.max_gas as f64,
+            );
+        }
+    }
+
+    /// Records the end of a nested call
+    pub fn exit_call(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        self.awaiting_entry_gas = false;
+    }
+
+    /// Returns the recorded telemetry, and records the call depth reached in the metrics
+    pub fn finish(mut self) -> OperationCallStats {
+        for depth_stats in self.stats.depths.iter_mut() {
+            if depth_stats.min_entry_gas == u64::MAX {
+                depth_stats.min_entry_gas = 0;
+            }
+        }
+        massa_metrics::observe_execution_operation_call_depth(self.stats.max_call_depth);
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_calls_recorded_by_depth() {
+        let mut recorder = CallStatsRecorder::new(1000);
+        // the operation calls A, which calls B, then the operation calls C
+        recorder.enter_call();
+        recorder.record_entry_gas(900);
+        recorder.enter_call();
+        recorder.record_entry_gas(600);
+        // only the module loaded right after the start of a call is attributed to it
+        recorder.record_entry_gas(10);
+        recorder.exit_call();
+        recorder.exit_call();
+        recorder.enter_call();
+        recorder.record_entry_gas(300);
+        recorder.exit_call();
+
+        let stats = recorder.finish();
+        assert_eq!(stats.max_gas, 1000);
+        assert_eq!(stats.max_call_depth, 2);
+        assert_eq!(
+            stats.depths,
+            vec![
+                CallDepthGasStats {
+                    call_count: 2,
+                    entry_gas: 1200,
+                    min_entry_gas: 300,
+                },
+                CallDepthGasStats {
+                    call_count: 1,
+                    entry_gas: 600,
+                    min_entry_gas: 600,
+                },
+            ]
+        );
+    }
+}
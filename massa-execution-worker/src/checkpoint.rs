@@ -0,0 +1,147 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module implements execution checkpoints.
+//! Every `execution_checkpoint_interval` SCE-final slots, the execution state that is not persisted
+//! in the final state (final events, final operation results and the speculative history)
+//! is written to disk along with the final cursor and the final state fingerprint it is attached to.
+//! On restart, the checkpoint is reloaded if it matches the final state,
+//! so that the node does not need to re-execute the speculative slots it had already executed.
+
+use massa_execution_exports::{
+    EventStore, ExecutionError, ExecutionOutput, OperationExecutionResult,
+};
+use massa_hash::Hash;
+use massa_models::slot::Slot;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Execution state that is not persisted in the final state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ExecutionCheckpoint {
+    /// latest executed SCE-final slot, at the output of which the final state is attached
+    pub final_cursor: Slot,
+    /// fingerprint of the final state at `final_cursor`
+    pub final_state_fingerprint: Hash,
+    /// events that became final
+    pub final_events: EventStore,
+    /// results of the latest operations that became final, oldest first, as kept in memory by the execution
+    #[serde(alias = "final_operation_results")]
+    pub recent_operation_results: Vec<OperationExecutionResult>,
+    /// outputs of the speculatively executed slots following `final_cursor`, oldest first
+    pub active_history: Vec<ExecutionOutput>,
+}
+
+/// Writes a checkpoint to `path`.
+/// The checkpoint is first written to a temporary file which is then renamed,
+/// so that a crash during the write never leaves a corrupted checkpoint behind.
+pub(crate) fn write_checkpoint(
+    path: &Path,
+    checkpoint: &ExecutionCheckpoint,
+) -> Result<(), ExecutionError> {
+    let bytes = serde_json::to_vec(checkpoint).map_err(|err| {
+        ExecutionError::CheckpointError(format!("could not serialize checkpoint: {}", err))
+    })?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| {
+            ExecutionError::CheckpointError(format!(
+                "could not create checkpoint directory {}: {}",
+                dir.display(),
+                err
+            ))
+        })?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, bytes)
+        .and_then(|_| std::fs::rename(&tmp_path, path))
+        .map_err(|err| {
+            ExecutionError::CheckpointError(format!(
+                "could not write checkpoint to {}: {}",
+                path.display(),
+                err
+            ))
+        })
+}
+
+/// Reads the checkpoint stored at `path`, if any
+pub(crate) fn read_checkpoint(path: &Path) -> Result<Option<ExecutionCheckpoint>, ExecutionError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = std::fs::read(path).map_err(|err| {
+        ExecutionError::CheckpointError(format!(
+            "could not read checkpoint from {}: {}",
+            path.display(),
+            err
+        ))
+    })?;
+    serde_json::from_slice(&bytes).map(Some).map_err(|err| {
+        ExecutionError::CheckpointError(format!(
+            "could not deserialize checkpoint from {}: {}",
+            path.display(),
+            err
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_execution_exports::ExecutedBlockInfo;
+    use massa_models::block_id::BlockId;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        assert!(read_checkpoint(&path).unwrap().is_none());
+
+        let checkpoint = ExecutionCheckpoint {
+            final_cursor: Slot::new(10, 3),
+            final_state_fingerprint: Hash::compute_from(b"final state"),
+            final_events: Default::default(),
+            recent_operation_results: Vec::new(),
+            active_history: vec![ExecutionOutput {
+                slot: Slot::new(10, 4),
+                block_info: Some(ExecutedBlockInfo {
+                    block_id: BlockId::from_str(
+                        "B1q4CBcuYo8YANEV34W4JRWVHrzcYns19VJfyAB7jT4qfitAnMC",
+                    )
+                    .unwrap(),
+                    current_version: 0,
+                    announced_version: None,
+                }),
+                state_changes: Default::default(),
+                events: Default::default(),
+                operation_results: Default::default(),
+            }],
+        };
+        write_checkpoint(&path, &checkpoint).unwrap();
+
+        let read = read_checkpoint(&path).unwrap().unwrap();
+        assert_eq!(read.final_cursor, checkpoint.final_cursor);
+        assert_eq!(
+            read.final_state_fingerprint,
+            checkpoint.final_state_fingerprint
+        );
+        assert_eq!(read.active_history.len(), 1);
+        assert_eq!(
+            read.active_history[0].block_info.as_ref().unwrap().block_id,
+            checkpoint.active_history[0]
+                .block_info
+                .as_ref()
+                .unwrap()
+                .block_id
+        );
+
+        // checkpoints written before the rename of the operation results are still read
+        let mut json = serde_json::to_value(&checkpoint).unwrap();
+        let json_map = json.as_object_mut().unwrap();
+        let results = json_map.remove("recent_operation_results").unwrap();
+        json_map.insert("final_operation_results".to_string(), results);
+        std::fs::write(&path, serde_json::to_vec(&json).unwrap()).unwrap();
+        let read = read_checkpoint(&path).unwrap().unwrap();
+        assert_eq!(read.final_cursor, checkpoint.final_cursor);
+        assert!(read.recent_operation_results.is_empty());
+    }
+}
@@ -8,17 +8,23 @@
 //! and does not write anything persistent to the consensus state.
 
 use crate::active_history::HistorySearchResult;
+use crate::call_stats::CallStatsRecorder;
+#[cfg(feature = "gas_profile")]
+use crate::gas_profile::GasProfiler;
+use crate::io_stats::IoStatsRecorder;
 use crate::speculative_async_pool::SpeculativeAsyncPool;
 use crate::speculative_executed_denunciations::SpeculativeExecutedDenunciations;
 use crate::speculative_executed_ops::SpeculativeExecutedOps;
 use crate::speculative_ledger::SpeculativeLedger;
+use crate::trace::{ExecutionTraceItem, ExecutionTracer, LedgerTraceKind};
 use crate::{active_history::ActiveHistory, speculative_roll_state::SpeculativeRollState};
 use massa_async_pool::{AsyncMessage, AsyncPoolChanges};
 use massa_async_pool::{AsyncMessageId, AsyncMessageInfo};
 use massa_executed_ops::{ExecutedDenunciationsChanges, ExecutedOpsChanges};
 use massa_execution_exports::{
-    EventStore, ExecutedBlockInfo, ExecutionConfig, ExecutionError, ExecutionOutput,
-    ExecutionStackElement,
+    CoinMovementKind, EventStore, ExecutedBlockInfo, ExecutionConfig, ExecutionError,
+    ExecutionOutput, ExecutionStackElement, LedgerEntryOverride, OperationCoinMovement,
+    OperationExecutionResult,
 };
 use massa_final_state::{FinalStateController, StateChanges};
 use massa_hash::Hash;
@@ -29,11 +35,12 @@ use massa_models::bytecode::Bytecode;
 use massa_models::denunciation::DenunciationIndex;
 use massa_models::timeslots::get_block_slot_timestamp;
 use massa_models::{
-    address::Address,
+    address::{Address, SCAddress},
     amount::Amount,
     block_id::BlockId,
     operation::OperationId,
     output_event::{EventExecutionContext, SCOutputEvent},
+    prehash::PreHashMap,
     slot::Slot,
 };
 use massa_module_cache::controller::ModuleCache;
@@ -85,6 +92,9 @@ pub struct ExecutionContextSnapshot {
     /// keep the count of event emitted in the context
     pub event_count: usize,
 
+    /// count of coin movements recorded so far for the operation being executed
+    pub op_coin_movement_count: usize,
+
     /// Unsafe random state
     pub unsafe_rng: Xoshiro256PlusPlus,
 }
@@ -171,6 +181,25 @@ pub struct ExecutionContext {
 
     /// Address factory
     pub address_factory: AddressFactory,
+
+    /// tracer recording ledger accesses, gas charges and ABI calls, if tracing is enabled
+    pub tracer: Option<Arc<ExecutionTracer>>,
+
+    /// recorder of the datastore access statistics of the smart contracts, if enabled
+    pub io_stats: Option<Arc<IoStatsRecorder>>,
+
+    /// gas profiler of the slot being executed, if any
+    #[cfg(feature = "gas_profile")]
+    pub gas_profiler: Option<GasProfiler>,
+
+    /// coin movements of the operation being executed, if any
+    pub op_coin_movements: Option<Vec<OperationCoinMovement>>,
+
+    /// call stack telemetry of the smart contract operation being executed, if any
+    pub op_call_stats: Option<CallStatsRecorder>,
+
+    /// results of the operations executed so far in the slot
+    pub operation_results: PreHashMap<OperationId, OperationExecutionResult>,
 }
 
 impl ExecutionContext {
@@ -233,7 +262,57 @@ impl ExecutionContext {
             config,
             address_factory: AddressFactory { mip_store },
             execution_trail_hash,
+            tracer: None,
+            io_stats: None,
+            #[cfg(feature = "gas_profile")]
+            gas_profiler: None,
+            op_coin_movements: None,
+            op_call_stats: None,
+            operation_results: Default::default(),
+        }
+    }
+
+    /// Records a call to a smart contract in the datastore access statistics, if they are enabled
+    pub fn record_contract_call(&self, address: &Address) {
+        if let Some(io_stats) = &self.io_stats {
+            io_stats.record_call(address);
+        }
+    }
+
+    /// Records an item in the execution trace, if tracing is enabled.
+    /// The item is only built when it is going to be recorded.
+    pub fn trace(&self, item: impl FnOnce() -> ExecutionTraceItem) {
+        if let Some(tracer) = &self.tracer {
+            tracer.record(item());
+        }
+    }
+
+    fn trace_ledger_read(&self, address: &Address, kind: LedgerTraceKind, key: Option<&[u8]>) {
+        if let Some(io_stats) = &self.io_stats {
+            match kind {
+                LedgerTraceKind::DatastoreEntry => io_stats.record_read(address, key),
+                LedgerTraceKind::DatastoreKeys => io_stats.record_read(address, None),
+                LedgerTraceKind::Balance | LedgerTraceKind::Bytecode => {}
+            }
+        }
+        self.trace(|| ExecutionTraceItem::LedgerRead {
+            address: *address,
+            kind,
+            key: key.map(|k| k.to_vec()),
+        });
+    }
+
+    fn trace_ledger_write(&self, address: &Address, kind: LedgerTraceKind, key: Option<&[u8]>) {
+        if let (Some(io_stats), LedgerTraceKind::DatastoreEntry, Some(key)) =
+            (&self.io_stats, kind, key)
+        {
+            io_stats.record_write(address, key);
         }
+        self.trace(|| ExecutionTraceItem::LedgerWrite {
+            address: *address,
+            kind,
+            key: key.map(|k| k.to_vec()),
+        });
     }
 
     /// Returns a snapshot containing the clone of the current execution state.
@@ -252,6 +331,7 @@ impl ExecutionContext {
             created_message_index: self.created_message_index,
             stack: self.stack.clone(),
             event_count: self.events.0.len(),
+            op_coin_movement_count: self.op_coin_movements.as_ref().map_or(0, Vec::len),
             unsafe_rng: self.unsafe_rng.clone(),
         }
     }
@@ -280,6 +360,9 @@ impl ExecutionContext {
         self.created_message_index = snapshot.created_message_index;
         self.stack = snapshot.stack;
         self.unsafe_rng = snapshot.unsafe_rng;
+        if let Some(movements) = &mut self.op_coin_movements {
+            movements.truncate(snapshot.op_coin_movement_count);
+        }
 
         // For events, set snapshot delta to error events.
         for event in self.events.0.range_mut(snapshot.event_count..) {
@@ -470,12 +553,11 @@ impl ExecutionContext {
         let mut nonce = 0u64;
         let address = loop {
             // get a deterministic seed hash
-            let hash = massa_hash::Hash::compute_from_tuple(&[
-                "SC_ADDRESS".as_bytes(),
-                self.execution_trail_hash.to_bytes(),
-                &self.created_addr_index.to_be_bytes(),
-                &nonce.to_be_bytes(),
-            ]);
+            let hash = SCAddress::creation_hash(
+                &self.execution_trail_hash,
+                self.created_addr_index,
+                nonce,
+            );
 
             // deduce the address
             let addr = self.address_factory.create(
@@ -496,11 +578,14 @@ impl ExecutionContext {
         };
 
         // add this address with its bytecode to the speculative ledger
-        self.speculative_ledger.create_new_sc_address(
+        self.trace_ledger_write(&address, LedgerTraceKind::Bytecode, None);
+        let result = self.speculative_ledger.create_new_sc_address(
             self.get_current_address()?,
             address,
             bytecode,
-        )?;
+        );
+        self.record_storage_cost_transfers(result.is_ok());
+        result?;
 
         // add the address to owned addresses
         // so that the current call has write access to it
@@ -523,28 +608,39 @@ impl ExecutionContext {
         Ok(address)
     }
 
+    /// overrides the state of ledger entries in the speculative ledger, without charging storage costs.
+    /// Used to simulate operations on top of a hypothetical state.
+    pub fn apply_ledger_overrides(&mut self, overrides: &PreHashMap<Address, LedgerEntryOverride>) {
+        self.speculative_ledger.apply_overrides(overrides);
+    }
+
     /// gets the bytecode of an address if it exists in the speculative ledger, or returns None
     pub fn get_bytecode(&self, address: &Address) -> Option<Bytecode> {
+        self.trace_ledger_read(address, LedgerTraceKind::Bytecode, None);
         self.speculative_ledger.get_bytecode(address)
     }
 
     /// gets the datastore keys of an address if it exists in the speculative ledger, or returns None
     pub fn get_keys(&self, address: &Address, prefix: &[u8]) -> Option<BTreeSet<Vec<u8>>> {
+        self.trace_ledger_read(address, LedgerTraceKind::DatastoreKeys, Some(prefix));
         self.speculative_ledger.get_keys(address, prefix)
     }
 
     /// gets the data from a datastore entry of an address if it exists in the speculative ledger, or returns None
     pub fn get_data_entry(&self, address: &Address, key: &[u8]) -> Option<Vec<u8>> {
+        self.trace_ledger_read(address, LedgerTraceKind::DatastoreEntry, Some(key));
         self.speculative_ledger.get_data_entry(address, key)
     }
 
     /// checks if a datastore entry exists in the speculative ledger
     pub fn has_data_entry(&self, address: &Address, key: &[u8]) -> bool {
+        self.trace_ledger_read(address, LedgerTraceKind::DatastoreEntry, Some(key));
         self.speculative_ledger.has_data_entry(address, key)
     }
 
     /// gets the effective balance of an address
     pub fn get_balance(&self, address: &Address) -> Option<Amount> {
+        self.trace_ledger_read(address, LedgerTraceKind::Balance, None);
         self.speculative_ledger.get_balance(address)
     }
 
@@ -571,8 +667,19 @@ impl ExecutionContext {
         }
 
         // set data entry
-        self.speculative_ledger
-            .set_data_entry(&self.get_current_address()?, address, key, data)
+        self.trace_ledger_write(
+            address,
+            LedgerTraceKind::DatastoreEntry,
+            Some(key.as_slice()),
+        );
+        let result = self.speculative_ledger.set_data_entry(
+            &self.get_current_address()?,
+            address,
+            key,
+            data,
+        );
+        self.record_storage_cost_transfers(result.is_ok());
+        result
     }
 
     /// Appends data to a datastore entry for an address in the speculative ledger.
@@ -612,8 +719,19 @@ impl ExecutionContext {
         res_data.extend(data);
 
         // set data entry
-        self.speculative_ledger
-            .set_data_entry(&self.get_current_address()?, address, key, res_data)
+        self.trace_ledger_write(
+            address,
+            LedgerTraceKind::DatastoreEntry,
+            Some(key.as_slice()),
+        );
+        let result = self.speculative_ledger.set_data_entry(
+            &self.get_current_address()?,
+            address,
+            key,
+            res_data,
+        );
+        self.record_storage_cost_transfers(result.is_ok());
+        result
     }
 
     /// Deletes a datastore entry for an address.
@@ -636,8 +754,12 @@ impl ExecutionContext {
         }
 
         // delete entry
-        self.speculative_ledger
-            .delete_data_entry(&self.get_current_address()?, address, key)
+        self.trace_ledger_write(address, LedgerTraceKind::DatastoreEntry, Some(key));
+        let result =
+            self.speculative_ledger
+                .delete_data_entry(&self.get_current_address()?, address, key);
+        self.record_storage_cost_transfers(result.is_ok());
+        result
     }
 
     /// Transfers coins from one address to another.
@@ -680,8 +802,82 @@ impl ExecutionContext {
         }
 
         // do the transfer
+        self.do_transfer_coins(from_addr, to_addr, amount, CoinMovementKind::Transfer)
+    }
+
+    /// Debits the fee of an operation from its sender.
+    /// No changes are retained in case of failure.
+    pub fn transfer_fee(
+        &mut self,
+        sender_addr: Address,
+        fee: Amount,
+    ) -> Result<(), ExecutionError> {
+        self.do_transfer_coins(Some(sender_addr), None, fee, CoinMovementKind::Fee)
+    }
+
+    /// Transfers coins without checking access rights, tracing the transfer
+    /// and recording it as a coin movement of the operation being executed, if any.
+    ///
+    /// If the credited address is created by the transfer, the part of the coins paying for the
+    /// creation of its ledger entry is recorded as a storage cost paid by the spending address.
+    fn do_transfer_coins(
+        &mut self,
+        from_addr: Option<Address>,
+        to_addr: Option<Address>,
+        amount: Amount,
+        kind: CoinMovementKind,
+    ) -> Result<(), ExecutionError> {
+        let creates_address = self.op_coin_movements.is_some()
+            && to_addr.map_or(false, |addr| !self.speculative_ledger.entry_exists(&addr));
+        self.trace(|| ExecutionTraceItem::CoinTransfer {
+            from: from_addr,
+            to: to_addr,
+            amount,
+        });
         self.speculative_ledger
-            .transfer_coins(from_addr, to_addr, amount)
+            .transfer_coins(from_addr, to_addr, amount)?;
+
+        if creates_address {
+            let entry_cost = self.config.storage_costs_constants.ledger_entry_base_cost;
+            self.record_coin_movement(from_addr, to_addr, amount.saturating_sub(entry_cost), kind);
+            self.record_coin_movement(from_addr, None, entry_cost, CoinMovementKind::Storage);
+        } else {
+            self.record_coin_movement(from_addr, to_addr, amount, kind);
+        }
+        Ok(())
+    }
+
+    /// Records a coin movement if an operation is being executed
+    fn record_coin_movement(
+        &mut self,
+        from: Option<Address>,
+        to: Option<Address>,
+        amount: Amount,
+        kind: CoinMovementKind,
+    ) {
+        let initiator = self.stack.last().map(|call| call.address);
+        if let Some(movements) = &mut self.op_coin_movements {
+            movements.push(OperationCoinMovement {
+                from,
+                to,
+                amount,
+                kind,
+                initiator,
+            });
+        }
+    }
+
+    /// Traces and records the storage costs charged or refunded by the last change of the speculative ledger.
+    /// They are dropped if the change failed, as it is then reverted.
+    fn record_storage_cost_transfers(&mut self, change_succeeded: bool) {
+        let transfers = self.speculative_ledger.take_storage_cost_transfers();
+        if !change_succeeded {
+            return;
+        }
+        for (from, to, amount) in transfers {
+            self.trace(|| ExecutionTraceItem::CoinTransfer { from, to, amount });
+            self.record_coin_movement(from, to, amount, CoinMovementKind::Storage);
+        }
     }
 
     /// Add a new asynchronous message to speculative pool
@@ -899,6 +1095,7 @@ impl ExecutionContext {
             block_info,
             state_changes,
             events: std::mem::take(&mut self.events),
+            operation_results: std::mem::take(&mut self.operation_results),
         }
     }
 
@@ -930,9 +1127,13 @@ impl ExecutionContext {
             )));
         }
 
-        // set data entry
-        self.speculative_ledger
-            .set_bytecode(&self.get_current_address()?, address, bytecode)
+        // set bytecode
+        self.trace_ledger_write(address, LedgerTraceKind::Bytecode, None);
+        let result =
+            self.speculative_ledger
+                .set_bytecode(&self.get_current_address()?, address, bytecode);
+        self.record_storage_cost_transfers(result.is_ok());
+        result
     }
 
     /// Creates a new event but does not emit it.
@@ -7,10 +7,14 @@ use crate::execution::ExecutionState;
 use crate::request_queue::{RequestQueue, RequestWithResponseSender};
 use massa_channel::MassaChannel;
 use massa_execution_exports::{
+    AsyncMessageFilter, AuthenticatedQueryResult, ContractIoStats, CycleDeferredCredits,
     ExecutionAddressInfo, ExecutionBlockMetadata, ExecutionConfig, ExecutionController,
-    ExecutionError, ExecutionManager, ExecutionQueryError, ExecutionQueryExecutionStatus,
+    ExecutionError, ExecutionManager, ExecutionOutput, ExecutionOutputConsumerClass,
+    ExecutionOutputConsumerId, ExecutionQueryError, ExecutionQueryExecutionStatus,
     ExecutionQueryRequest, ExecutionQueryRequestItem, ExecutionQueryResponse,
-    ExecutionQueryResponseItem, ReadOnlyExecutionOutput, ReadOnlyExecutionRequest,
+    ExecutionQueryResponseItem, OperationExecutionResult, OperationSimulationOutput,
+    OperationSimulationRequest, PendingAsyncMessage, ReadOnlyExecutionOutput,
+    ReadOnlyExecutionRequest, SlotGasProfile, SlotMissStats, SlotSequencerStatus,
 };
 use massa_models::denunciation::DenunciationIndex;
 use massa_models::execution::EventFilter;
@@ -22,6 +26,7 @@ use massa_models::{block_id::BlockId, slot::Slot};
 use parking_lot::{Condvar, Mutex, RwLock};
 use std::collections::{BTreeMap, HashMap};
 use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tracing::info;
 
@@ -37,13 +42,15 @@ pub(crate) struct ExecutionInputData {
     pub block_metadata: PreHashMap<BlockId, ExecutionBlockMetadata>,
     /// queue for read-only execution requests and response MPSCs to send back their outputs
     pub readonly_requests: RequestQueue<ReadOnlyExecutionRequest, ReadOnlyExecutionOutput>,
+    /// queue for operation simulation requests and response MPSCs to send back their outputs
+    pub simulation_requests: RequestQueue<OperationSimulationRequest, OperationSimulationOutput>,
 }
 
 impl Display for ExecutionInputData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "stop={:?}, finalized={:?}, blockclique={:?}, readonly={:?}, storage={:?}",
+            "stop={:?}, finalized={:?}, blockclique={:?}, readonly={:?}, simulations={:?}, storage={:?}",
             self.stop,
             self.finalized_blocks
                 .iter()
@@ -54,6 +61,7 @@ impl Display for ExecutionInputData {
                 .map(|(slot, id)| (*slot, *id))
                 .collect::<BTreeMap<Slot, BlockId>>()),
             self.readonly_requests,
+            self.simulation_requests,
             self.block_metadata.keys().collect::<Vec<&BlockId>>(),
         )
     }
@@ -68,6 +76,7 @@ impl ExecutionInputData {
             new_blockclique: Default::default(),
             block_metadata: Default::default(),
             readonly_requests: RequestQueue::new(config.max_final_events),
+            simulation_requests: RequestQueue::new(config.max_final_events),
         }
     }
 
@@ -84,6 +93,10 @@ impl ExecutionInputData {
                 &mut self.readonly_requests,
                 RequestQueue::new(max_final_events),
             ),
+            simulation_requests: std::mem::replace(
+                &mut self.simulation_requests,
+                RequestQueue::new(max_final_events),
+            ),
         }
     }
 }
@@ -96,6 +109,8 @@ pub struct ExecutionControllerImpl {
     pub(crate) input_data: Arc<(Condvar, Mutex<ExecutionInputData>)>,
     /// current execution state (see execution.rs for details)
     pub(crate) execution_state: Arc<RwLock<ExecutionState>>,
+    /// raised when newly finalized blocks are notified, to preempt the execution of candidate slots (see lanes.rs)
+    pub(crate) final_preemption: Arc<AtomicBool>,
 }
 
 impl ExecutionController for ExecutionControllerImpl {
@@ -117,7 +132,10 @@ impl ExecutionController for ExecutionControllerImpl {
         // extend block info
         input_data.block_metadata.extend(block_metadata);
 
-        // extend finalized blocks
+        // extend finalized blocks, preempting any ongoing candidate slot execution
+        if !finalized_blocks.is_empty() {
+            self.final_preemption.store(true, Ordering::Release);
+        }
         input_data.finalized_blocks.extend(finalized_blocks);
 
         // update blockclique
@@ -139,6 +157,20 @@ impl ExecutionController for ExecutionControllerImpl {
             final_state_fingerprint: execution_lock.get_final_state_fingerprint(),
         };
         for req_item in req.requests {
+            // authenticated queries are executed like the final query they wrap,
+            // then bound to the final state that was read
+            let (req_item, authenticated) = match req_item {
+                ExecutionQueryRequestItem::Authenticated(inner) => {
+                    if !inner.is_final() {
+                        resp.responses.push(Err(ExecutionQueryError::InvalidRequest(
+                            "only queries reading the final state can be authenticated".to_string(),
+                        )));
+                        continue;
+                    }
+                    (*inner, true)
+                }
+                req_item => (req_item, false),
+            };
             let resp_item = match req_item {
                 ExecutionQueryRequestItem::AddressExistsCandidate(addr) => {
                     Ok(ExecutionQueryResponseItem::Boolean(
@@ -324,6 +356,29 @@ impl ExecutionController for ExecutionControllerImpl {
                         execution_lock.get_filtered_sc_output_event(filter),
                     ))
                 }
+                ExecutionQueryRequestItem::Authenticated(_) => {
+                    Err(ExecutionQueryError::InvalidRequest(
+                        "authenticated queries cannot be nested".to_string(),
+                    ))
+                }
+            };
+            let resp_item = if authenticated {
+                // a value missing from the final state is also an authenticated result
+                match resp_item {
+                    Ok(value) => Ok(Some(Box::new(value))),
+                    Err(ExecutionQueryError::NotFound(_)) => Ok(None),
+                    Err(err) => Err(err),
+                }
+                .map(|value| {
+                    ExecutionQueryResponseItem::Authenticated(AuthenticatedQueryResult {
+                        value,
+                        slot: resp.final_cursor,
+                        final_state_hash: resp.final_state_fingerprint,
+                        proof: None,
+                    })
+                })
+            } else {
+                resp_item
             };
             resp.responses.push(resp_item);
         }
@@ -419,6 +474,47 @@ impl ExecutionController for ExecutionControllerImpl {
         }
     }
 
+    /// Simulates the execution of an operation on top of hypothetical ledger overrides.
+    /// Simulations do not modify consensus state
+    fn simulate_operation(
+        &self,
+        req: OperationSimulationRequest,
+    ) -> Result<OperationSimulationOutput, ExecutionError> {
+        let resp_rx = {
+            let mut input_data = self.input_data.1.lock();
+
+            // if the simulation queue is already full, return an error
+            if input_data.simulation_requests.is_full() {
+                return Err(ExecutionError::ChannelError(
+                    "too many queued operation simulation requests".into(),
+                ));
+            }
+
+            // prepare the channel to send back the result of the simulation
+            let (resp_tx, resp_rx) =
+                MassaChannel::new("operation_simulation_request".to_string(), None);
+
+            // append the request to the queue of input simulation requests
+            input_data
+                .simulation_requests
+                .push(RequestWithResponseSender::new(req, resp_tx));
+
+            // wake up the execution main loop
+            self.input_data.0.notify_one();
+
+            resp_rx
+        };
+
+        // Wait for the result of the simulation
+        match resp_rx.recv() {
+            Ok(result) => result,
+            Err(err) => Err(ExecutionError::ChannelError(format!(
+                "operation simulation response channel readout failed: {}",
+                err
+            ))),
+        }
+    }
+
     /// Check if a denunciation has been executed given a `DenunciationIndex`
     /// Returns a tuple of booleans: `(speculative_execution_status, final_execution_status)`
     fn get_denunciation_execution_status(
@@ -460,6 +556,76 @@ impl ExecutionController for ExecutionControllerImpl {
         self.execution_state.read().get_stats()
     }
 
+    /// Get the status of the slot sequencer
+    fn get_sequencer_status(&self) -> SlotSequencerStatus {
+        self.execution_state.read().get_sequencer_status()
+    }
+
+    /// Get the slot miss statistics of the slot sequencer
+    fn get_slot_miss_stats(&self) -> SlotMissStats {
+        self.execution_state.read().get_slot_miss_stats()
+    }
+
+    /// Get the datastore access statistics of a smart contract over the latest executed slots
+    fn get_contract_io_stats(&self, address: &Address, window: u64) -> Option<ContractIoStats> {
+        self.execution_state
+            .read()
+            .get_contract_io_stats(address, window)
+    }
+
+    /// Get the candidate deferred credits of an address grouped by unlock cycle
+    fn get_address_deferred_credits_schedule(
+        &self,
+        address: &Address,
+        cycle: Option<u64>,
+    ) -> Vec<CycleDeferredCredits> {
+        self.execution_state
+            .read()
+            .get_address_deferred_credits_schedule(address, cycle)
+    }
+
+    /// Get the asynchronous messages of the final pool matching a filter
+    fn get_async_messages(&self, filter: &AsyncMessageFilter) -> Vec<PendingAsyncMessage> {
+        self.execution_state.read().get_async_messages(filter)
+    }
+
+    /// Get the gas profile of a recently executed slot (requires the `gas_profile` feature)
+    fn get_slot_gas_profile(&self, slot: Slot) -> Option<SlotGasProfile> {
+        self.execution_state.read().get_slot_gas_profile(&slot)
+    }
+
+    /// Registers a consumer of the final execution outputs
+    fn register_execution_output_consumer(
+        &self,
+        class: ExecutionOutputConsumerClass,
+    ) -> ExecutionOutputConsumerId {
+        self.execution_state.read().register_output_consumer(class)
+    }
+
+    /// Acknowledges the final execution outputs up to `slot` for a consumer
+    fn ack_execution_outputs(&self, consumer: ExecutionOutputConsumerId, slot: Slot) {
+        self.execution_state.read().ack_outputs(consumer, slot)
+    }
+
+    /// Unregisters a consumer of the final execution outputs
+    fn unregister_execution_output_consumer(&self, consumer: ExecutionOutputConsumerId) {
+        self.execution_state
+            .read()
+            .unregister_output_consumer(consumer)
+    }
+
+    /// Get the retained final execution outputs of the slots after `after`
+    fn get_final_execution_outputs(&self, after: Option<Slot>) -> Vec<ExecutionOutput> {
+        self.execution_state
+            .read()
+            .get_final_execution_outputs(after)
+    }
+
+    /// Get the final state hashes of the latest final slots after `after`
+    fn get_final_state_hashes(&self, after: Option<Slot>) -> Vec<(Slot, massa_hash::Hash)> {
+        self.execution_state.read().get_final_state_hashes(after)
+    }
+
     /// Returns a boxed clone of self.
     /// Allows cloning `Box<dyn ExecutionController>`,
     /// see `massa-execution-exports/controller_traits.rs`
@@ -471,6 +637,16 @@ impl ExecutionController for ExecutionControllerImpl {
     fn get_ops_exec_status(&self, batch: &[OperationId]) -> Vec<(Option<bool>, Option<bool>)> {
         self.execution_state.read().get_ops_exec_status(batch)
     }
+
+    /// See trait definition
+    fn get_operation_execution_result(
+        &self,
+        operation_ids: &[OperationId],
+    ) -> Vec<Option<OperationExecutionResult>> {
+        self.execution_state
+            .read()
+            .get_operation_execution_result(operation_ids)
+    }
 }
 
 /// Execution manager
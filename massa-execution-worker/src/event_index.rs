@@ -0,0 +1,270 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module implements the on-disk final event index.
+//! Final events are stored in a RocksDB database along with secondary indexes
+//! by emitter address, by original caller address and by data prefix,
+//! so that filtering the final events on those criteria does not require scanning the whole event history.
+//!
+//! All keys start with a one-byte identifier of their kind. Events are stored under their event key,
+//! made of their slot and index in the slot so that iterating over events follows execution order.
+//! Index entries are empty values whose key ends with the event key of the indexed event.
+
+use massa_execution_exports::event_matches_filter;
+use massa_models::{address::Address, execution::EventFilter, output_event::SCOutputEvent};
+use rocksdb::{Direction, IteratorMode, WriteBatch, DB};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use tracing::debug;
+
+const OPEN_ERROR: &str = "critical: rocksdb open operation failed";
+const CRUD_ERROR: &str = "critical: rocksdb crud operation failed";
+const EVENT_SER_ERROR: &str = "critical: event serialization failed";
+const EVENT_DESER_ERROR: &str = "critical: event deserialization failed";
+const EVENT_IDENT: u8 = 0u8;
+const EMITTER_IDENT: u8 = 1u8;
+const CALLER_IDENT: u8 = 2u8;
+const DATA_IDENT: u8 = 3u8;
+
+/// Number of leading bytes of the event data that are indexed
+const INDEXED_DATA_LENGTH: usize = 64;
+
+/// Length of an event key: period (8 bytes), thread (1 byte) and index in slot (8 bytes)
+const EVENT_KEY_LENGTH: usize = 17;
+
+/// Key of an event, ordered by execution order
+fn event_key(event: &SCOutputEvent) -> Vec<u8> {
+    let mut key = Vec::with_capacity(EVENT_KEY_LENGTH);
+    key.extend(event.context.slot.period.to_be_bytes());
+    key.push(event.context.slot.thread);
+    key.extend(event.context.index_in_slot.to_be_bytes());
+    key
+}
+
+/// Prefix of the index entries of an address
+fn address_prefix(ident: u8, address: &Address) -> Vec<u8> {
+    let address_bytes = address.to_prefixed_bytes();
+    let mut prefix = Vec::with_capacity(address_bytes.len() + 2);
+    prefix.push(ident);
+    // the length makes sure that the prefix of an address is never the prefix of another one
+    prefix.push(address_bytes.len() as u8);
+    prefix.extend(address_bytes);
+    prefix
+}
+
+/// Prefix of the index entries of events whose data starts with `data`
+fn data_prefix(data: &str) -> Vec<u8> {
+    let data_bytes = data.as_bytes();
+    let mut prefix = vec![DATA_IDENT];
+    prefix.extend(&data_bytes[..data_bytes.len().min(INDEXED_DATA_LENGTH)]);
+    prefix
+}
+
+/// Keys of the index entries of an event
+fn index_keys(event: &SCOutputEvent, event_key: &[u8]) -> Vec<Vec<u8>> {
+    let mut keys = Vec::with_capacity(3);
+    if let Some(emitter) = event.context.call_stack.back() {
+        keys.push([&address_prefix(EMITTER_IDENT, emitter)[..], event_key].concat());
+    }
+    if let Some(caller) = event.context.call_stack.front() {
+        keys.push([&address_prefix(CALLER_IDENT, caller)[..], event_key].concat());
+    }
+    keys.push([&data_prefix(&event.data)[..], event_key].concat());
+    keys
+}
+
+/// On-disk store of the final events, with secondary indexes
+pub(crate) struct FinalEventIndex {
+    /// RocksDB database
+    db: DB,
+    /// How many events are in the db. Count is initialized at creation time by iterating
+    /// over all the events in the db then it is maintained in memory
+    event_count: usize,
+    /// Maximum number of events we want to keep in the db.
+    /// When this maximum is exceeded, the oldest events are removed
+    max_event_count: usize,
+}
+
+impl FinalEventIndex {
+    /// Create a new FinalEventIndex
+    ///
+    /// # Arguments
+    /// * path: where to store the db
+    /// * max_event_count: maximum number of events we want to keep in the db
+    pub fn new(path: PathBuf, max_event_count: usize) -> Self {
+        let db = DB::open_default(path).expect(OPEN_ERROR);
+        let event_count = db
+            .iterator(IteratorMode::From(&[EVENT_IDENT], Direction::Forward))
+            .map(|item| item.expect(CRUD_ERROR))
+            .take_while(|(key, _)| key.first() == Some(&EVENT_IDENT))
+            .count();
+        Self {
+            db,
+            event_count,
+            max_event_count,
+        }
+    }
+
+    /// Insert final events in the index, removing the oldest ones if it gets too large
+    pub fn insert<'a>(&mut self, events: impl IntoIterator<Item = &'a SCOutputEvent>) {
+        let mut batch = WriteBatch::default();
+        for event in events {
+            let event_key = event_key(event);
+            let key = [&[EVENT_IDENT][..], &event_key].concat();
+            // events of slots executed again after a restart overwrite their previous version
+            if self.db.get(&key).expect(CRUD_ERROR).is_none() {
+                self.event_count = self.event_count.saturating_add(1);
+            }
+            batch.put(key, serde_json::to_vec(event).expect(EVENT_SER_ERROR));
+            for index_key in index_keys(event, &event_key) {
+                batch.put(index_key, b"");
+            }
+        }
+        self.db.write(batch).expect(CRUD_ERROR);
+
+        if self.event_count > self.max_event_count {
+            self.snip(self.event_count - self.max_event_count);
+        }
+    }
+
+    /// Remove the `amount` oldest events and their index entries
+    fn snip(&mut self, amount: usize) {
+        let mut batch = WriteBatch::default();
+        let mut removed = 0;
+        for (key, value) in self
+            .db
+            .iterator(IteratorMode::From(&[EVENT_IDENT], Direction::Forward))
+            .map(|item| item.expect(CRUD_ERROR))
+            .take_while(|(key, _)| key.first() == Some(&EVENT_IDENT))
+            .take(amount)
+        {
+            let event: SCOutputEvent = serde_json::from_slice(&value).expect(EVENT_DESER_ERROR);
+            for index_key in index_keys(&event, &key[1..]) {
+                batch.delete(index_key);
+            }
+            batch.delete(key);
+            removed += 1;
+        }
+        self.db.write(batch).expect(CRUD_ERROR);
+        self.event_count = self.event_count.saturating_sub(removed);
+        debug!("(event index snip) event_count is: {}", self.event_count);
+    }
+
+    /// Returns true if the filter restricts events on an indexed criterion
+    pub fn can_serve(filter: &EventFilter) -> bool {
+        filter.emitter_address.is_some()
+            || filter.original_caller_address.is_some()
+            || filter.data_prefix.is_some()
+    }
+
+    /// Get the final events matching a filter, in execution order,
+    /// using the most selective of the available indexes.
+    /// The filter should restrict events on an indexed criterion (see `Self::can_serve`),
+    /// otherwise all the events of the index are scanned.
+    pub fn get_filtered_events(&self, filter: &EventFilter) -> Vec<SCOutputEvent> {
+        let prefix = if let Some(emitter) = &filter.emitter_address {
+            address_prefix(EMITTER_IDENT, emitter)
+        } else if let Some(caller) = &filter.original_caller_address {
+            address_prefix(CALLER_IDENT, caller)
+        } else if let Some(data) = &filter.data_prefix {
+            data_prefix(data)
+        } else {
+            vec![EVENT_IDENT]
+        };
+
+        // gather the keys of the candidate events, sorted in execution order
+        let event_keys: BTreeSet<Vec<u8>> = self
+            .db
+            .iterator(IteratorMode::From(&prefix, Direction::Forward))
+            .map(|item| item.expect(CRUD_ERROR))
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .filter(|(key, _)| key.len() >= prefix.len() + EVENT_KEY_LENGTH)
+            .map(|(key, _)| key[key.len() - EVENT_KEY_LENGTH..].to_vec())
+            .collect();
+
+        // read the candidate events and check the other criteria
+        self.db
+            .multi_get(
+                event_keys
+                    .iter()
+                    .map(|event_key| [&[EVENT_IDENT][..], event_key].concat()),
+            )
+            .into_iter()
+            .filter_map(|value| value.expect(CRUD_ERROR))
+            .map(|value| serde_json::from_slice::<SCOutputEvent>(&value).expect(EVENT_DESER_ERROR))
+            .filter(|event| event_matches_filter(event, filter))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_models::output_event::EventExecutionContext;
+    use massa_models::slot::Slot;
+    use std::collections::VecDeque;
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    fn event(period: u64, emitter: Address, data: &str) -> SCOutputEvent {
+        SCOutputEvent {
+            context: EventExecutionContext {
+                slot: Slot::new(period, 0),
+                block: None,
+                read_only: false,
+                index_in_slot: 0,
+                call_stack: VecDeque::from([emitter]),
+                origin_operation_id: None,
+                is_final: true,
+                is_error: false,
+            },
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_event_index_queries_and_pruning() {
+        let dir = TempDir::new().unwrap();
+        let addr_1 =
+            Address::from_str("AU12dG5xP1RDEB5ocdHkymNVvvSJmUL9BgHwCksDowqmGWxfpm93x").unwrap();
+        let addr_2 =
+            Address::from_str("AS12htxRWiEm8jDJpJptr6cwEhWNcCSFWstN1MLSa96DDkVM9Y42G").unwrap();
+        let mut index = FinalEventIndex::new(dir.path().to_path_buf(), 3);
+        index.insert(&[
+            event(1, addr_1, "transfer:1"),
+            event(2, addr_2, "mint:2"),
+            event(3, addr_1, "mint:3"),
+            event(4, addr_2, "transfer:4"),
+        ]);
+
+        // the oldest event was pruned
+        let by_emitter = index.get_filtered_events(&EventFilter {
+            emitter_address: Some(addr_1),
+            ..Default::default()
+        });
+        assert_eq!(by_emitter.len(), 1);
+        assert_eq!(by_emitter[0].data, "mint:3");
+
+        let by_data = index.get_filtered_events(&EventFilter {
+            data_prefix: Some("mint".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            by_data.iter().map(|e| e.data.as_str()).collect::<Vec<_>>(),
+            vec!["mint:2", "mint:3"]
+        );
+
+        // other criteria are applied on the indexed candidates
+        let combined = index.get_filtered_events(&EventFilter {
+            original_caller_address: Some(addr_2),
+            start: Some(Slot::new(3, 0)),
+            ..Default::default()
+        });
+        assert_eq!(combined.len(), 1);
+        assert_eq!(combined[0].data, "transfer:4");
+
+        // the event count is restored when reopening the index
+        drop(index);
+        let index = FinalEventIndex::new(dir.path().to_path_buf(), 3);
+        assert_eq!(index.event_count, 3);
+    }
+}
@@ -9,15 +9,30 @@
 //! * the output of the execution is extracted from the context
 
 use crate::active_history::{ActiveHistory, HistorySearchResult};
+use crate::call_stats::CallStatsRecorder;
+use crate::checkpoint::{read_checkpoint, ExecutionCheckpoint};
 use crate::context::{ExecutionContext, ExecutionContextSnapshot};
+use crate::event_index::FinalEventIndex;
+#[cfg(feature = "gas_profile")]
+use crate::gas_profile::{load_abi_gas_costs, AbiGasCosts, GasProfileStore, GasProfiler};
 use crate::interface_impl::InterfaceImpl;
+use crate::io_stats::{IoStatsRecorder, IoStatsStore};
+use crate::operation_results::RecentOperationResults;
+use crate::output_retention::ExecutionOutputRetention;
+use crate::readonly_cache::{ReadOnlyCallCache, ReadOnlyCallKey};
+use crate::slot_sequencer::SlotSequencerLoad;
 use crate::stats::ExecutionStatsCounter;
+use crate::trace::{dump_slot_trace, ExecutionTraceItem, ExecutionTracer};
 use massa_async_pool::AsyncMessage;
 use massa_execution_exports::{
-    EventStore, ExecutedBlockInfo, ExecutionBlockMetadata, ExecutionChannels, ExecutionConfig,
-    ExecutionError, ExecutionOutput, ExecutionQueryCycleInfos, ExecutionQueryStakerInfo,
-    ExecutionStackElement, ReadOnlyExecutionOutput, ReadOnlyExecutionRequest,
-    ReadOnlyExecutionTarget, SlotExecutionOutput,
+    AsyncMessageFilter, ContractIoStats, CycleDeferredCredits, EventStore, ExecutedBlockInfo,
+    ExecutionBlockMetadata, ExecutionChannels, ExecutionConfig, ExecutionError, ExecutionOutput,
+    ExecutionOutputConsumerClass, ExecutionOutputConsumerId, ExecutionQueryCycleInfos,
+    ExecutionQueryStakerInfo, ExecutionStackElement, OperationCoinFlow, OperationExecutionResult,
+    OperationSimulationOutput, OperationSimulationRequest, PendingAsyncMessage,
+    ReadOnlyExecutionOutput, ReadOnlyExecutionRequest, ReadOnlyExecutionState,
+    ReadOnlyExecutionTarget, SlotExecutionOutput, SlotGasProfile, SlotMissStats,
+    SlotSequencerStatus,
 };
 use massa_final_state::FinalStateController;
 use massa_ledger_exports::{SetOrDelete, SetUpdateOrDelete};
@@ -41,10 +56,12 @@ use massa_module_cache::config::ModuleCacheConfig;
 use massa_module_cache::controller::ModuleCache;
 use massa_pos_exports::SelectorController;
 use massa_sc_runtime::{Interface, Response, VMError};
+use massa_time::MassaTime;
 use massa_versioning::versioning::MipStore;
 use massa_wallet::Wallet;
 use parking_lot::{Mutex, RwLock};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tracing::{debug, info, trace, warn};
 
@@ -72,6 +89,8 @@ pub(crate) struct ExecutionState {
     pub final_cursor: Slot,
     // store containing execution events that became final
     final_events: EventStore,
+    // in-memory store containing the execution results of the latest operations that became final
+    recent_operation_results: RecentOperationResults,
     // final state with atomic R/W access
     final_state: Arc<RwLock<dyn FinalStateController>>,
     // execution context (see documentation in context.rs)
@@ -80,6 +99,30 @@ pub(crate) struct ExecutionState {
     execution_interface: Box<dyn Interface>,
     // execution statistics
     stats_counter: ExecutionStatsCounter,
+    // latest load reported by the slot sequencer
+    sequencer_load: SlotSequencerLoad,
+    // latest status reported by the slot sequencer
+    sequencer_status: SlotSequencerStatus,
+    // latest slot miss statistics reported by the slot sequencer
+    slot_miss_stats: SlotMissStats,
+    // base costs of the ABIs, used to attribute gas to host functions
+    #[cfg(feature = "gas_profile")]
+    abi_gas_costs: Arc<AbiGasCosts>,
+    // gas profiles of the latest executed slots
+    #[cfg(feature = "gas_profile")]
+    gas_profiles: Mutex<GasProfileStore>,
+    // datastore access statistics of the latest executed slots
+    contract_io_stats: Mutex<IoStatsStore>,
+    // final execution outputs retained for their consumers
+    output_retention: Mutex<ExecutionOutputRetention>,
+    // final state hashes of the latest final slots, oldest first
+    final_state_hashes: VecDeque<(Slot, massa_hash::Hash)>,
+    // on-disk final event store with secondary indexes, if enabled
+    event_index: Option<FinalEventIndex>,
+    // cache of the results of read-only calls
+    readonly_call_cache: ReadOnlyCallCache,
+    // number of final slots executed since the latest checkpoint
+    final_slots_since_checkpoint: u64,
     // cache of pre compiled sc modules
     module_cache: Arc<RwLock<ModuleCache>>,
     // MipStore (Versioning)
@@ -116,14 +159,48 @@ impl ExecutionState {
         // This should be among the latest final slots.
         let last_final_slot;
         let execution_trail_hash;
+        let final_state_fingerprint;
         {
             let final_state_read = final_state.read();
             last_final_slot = final_state_read.get_slot();
             execution_trail_hash = final_state_read.get_execution_trail_hash();
+            final_state_fingerprint = final_state_read.get_fingerprint();
         }
 
         // Create default active history
         let active_history: Arc<RwLock<ActiveHistory>> = Default::default();
+        let mut final_events = EventStore::default();
+        let mut recent_operation_results =
+            RecentOperationResults::new(config.max_recent_operation_results);
+        let mut active_cursor = last_final_slot;
+
+        // Restore the latest checkpoint if it was taken at the slot the final state is attached to
+        if config.execution_checkpoint_interval > 0 {
+            match read_checkpoint(&config.execution_checkpoint_path) {
+                Ok(Some(checkpoint))
+                    if checkpoint.final_cursor == last_final_slot
+                        && checkpoint.final_state_fingerprint == final_state_fingerprint =>
+                {
+                    info!(
+                        "restoring execution checkpoint at final slot {} with {} speculative slots",
+                        checkpoint.final_cursor,
+                        checkpoint.active_history.len()
+                    );
+                    final_events = checkpoint.final_events;
+                    recent_operation_results.restore(checkpoint.recent_operation_results);
+                    if let Some(last_output) = checkpoint.active_history.last() {
+                        active_cursor = last_output.slot;
+                    }
+                    active_history.write().0 = checkpoint.active_history.into();
+                }
+                Ok(Some(checkpoint)) => info!(
+                    "ignoring execution checkpoint at slot {}: it does not match the final state at slot {}",
+                    checkpoint.final_cursor, last_final_slot
+                ),
+                Ok(None) => {}
+                Err(err) => warn!("could not restore execution checkpoint: {}", err),
+            }
+        }
 
         // Initialize the SC module cache
         let module_cache = Arc::new(RwLock::new(ModuleCache::new(ModuleCacheConfig {
@@ -132,6 +209,7 @@ impl ExecutionState {
             lru_cache_size: config.lru_cache_size,
             hd_cache_size: config.hd_cache_size,
             snip_amount: config.snip_amount,
+            hd_cache_fingerprint: config.hd_cache_fingerprint,
             max_module_length: config.max_bytecode_size,
         })));
 
@@ -156,14 +234,42 @@ impl ExecutionState {
             final_state,
             execution_context,
             execution_interface,
-            // empty execution output history: it is not recovered through bootstrap
+            // execution output history: it is not recovered through bootstrap, only from a local checkpoint
             active_history,
-            // empty final event store: it is not recovered through bootstrap
-            final_events: Default::default(),
-            // no active slots executed yet: set active_cursor to the last final block
-            active_cursor: last_final_slot,
+            // final event store: it is not recovered through bootstrap, only from a local checkpoint
+            final_events,
+            recent_operation_results,
+            // set active_cursor to the last restored active slot, or to the last final block
+            active_cursor,
             final_cursor: last_final_slot,
             stats_counter: ExecutionStatsCounter::new(config.stats_time_window_duration),
+            sequencer_load: Default::default(),
+            // empty sequence until the slot sequencer reports its status
+            sequencer_status: SlotSequencerStatus {
+                sequence_length: 0,
+                latest_consensus_final_slots: Vec::new(),
+                latest_execution_final_slot: last_final_slot,
+                latest_executed_final_slot: last_final_slot,
+                latest_executed_candidate_slot: active_cursor,
+            },
+            // no executed slot until the slot sequencer reports its statistics
+            slot_miss_stats: SlotMissStats {
+                time_window_start: MassaTime::now(),
+                time_window_end: MassaTime::now(),
+                threads: vec![Default::default(); config.thread_count as usize],
+            },
+            final_slots_since_checkpoint: 0,
+            #[cfg(feature = "gas_profile")]
+            abi_gas_costs: Arc::new(load_abi_gas_costs(&config.abi_gas_costs_file)),
+            #[cfg(feature = "gas_profile")]
+            gas_profiles: Mutex::new(GasProfileStore::new()),
+            contract_io_stats: Mutex::new(IoStatsStore::new()),
+            output_retention: Mutex::new(ExecutionOutputRetention::new(&config)),
+            final_state_hashes: VecDeque::new(),
+            event_index: config.event_index_enabled.then(|| {
+                FinalEventIndex::new(config.event_index_path.clone(), config.max_indexed_events)
+            }),
+            readonly_call_cache: ReadOnlyCallCache::new(config.readonly_call_cache_size),
             module_cache,
             config,
             mip_store,
@@ -181,8 +287,160 @@ impl ExecutionState {
 
     /// Get execution statistics
     pub fn get_stats(&self) -> ExecutionStats {
-        self.stats_counter
-            .get_stats(self.active_cursor, self.final_cursor)
+        self.stats_counter.get_stats(
+            self.active_cursor,
+            self.final_cursor,
+            &self.sequencer_load,
+            self.config.candidate_execution_enabled,
+        )
+    }
+
+    /// Update the load reported by the slot sequencer
+    pub fn set_sequencer_load(&mut self, sequencer_load: SlotSequencerLoad) {
+        self.sequencer_load = sequencer_load;
+    }
+
+    /// Get the latest status reported by the slot sequencer
+    pub fn get_sequencer_status(&self) -> SlotSequencerStatus {
+        self.sequencer_status.clone()
+    }
+
+    /// Get the gas profile of a recently executed slot
+    #[cfg(feature = "gas_profile")]
+    pub fn get_slot_gas_profile(&self, slot: &Slot) -> Option<SlotGasProfile> {
+        self.gas_profiles.lock().get(slot)
+    }
+
+    /// Get the gas profile of a recently executed slot: gas profiling is disabled
+    #[cfg(not(feature = "gas_profile"))]
+    pub fn get_slot_gas_profile(&self, _slot: &Slot) -> Option<SlotGasProfile> {
+        None
+    }
+
+    /// Registers a consumer of the final execution outputs
+    pub fn register_output_consumer(
+        &self,
+        class: ExecutionOutputConsumerClass,
+    ) -> ExecutionOutputConsumerId {
+        self.output_retention.lock().register(class)
+    }
+
+    /// Acknowledges the final execution outputs up to `slot` (included) for a consumer
+    pub fn ack_outputs(&self, consumer: ExecutionOutputConsumerId, slot: Slot) {
+        self.output_retention.lock().ack(consumer, slot)
+    }
+
+    /// Unregisters a consumer of the final execution outputs
+    pub fn unregister_output_consumer(&self, consumer: ExecutionOutputConsumerId) {
+        self.output_retention.lock().unregister(consumer)
+    }
+
+    /// Get the retained final execution outputs of the slots after `after`, oldest first
+    pub fn get_final_execution_outputs(&self, after: Option<Slot>) -> Vec<ExecutionOutput> {
+        self.output_retention.lock().get_after(after)
+    }
+
+    /// Get the final state hashes of the latest final slots after `after`, oldest first
+    pub fn get_final_state_hashes(&self, after: Option<Slot>) -> Vec<(Slot, massa_hash::Hash)> {
+        self.final_state_hashes
+            .iter()
+            .filter(|(slot, _)| after.map_or(true, |after| *slot > after))
+            .copied()
+            .collect()
+    }
+
+    /// Get the datastore access statistics of a smart contract over the latest `window` executed slots,
+    /// or None if they are not recorded
+    pub fn get_contract_io_stats(&self, address: &Address, window: u64) -> Option<ContractIoStats> {
+        self.config
+            .contract_io_stats_enabled
+            .then(|| self.contract_io_stats.lock().get(address, window))
+    }
+
+    /// Update the status reported by the slot sequencer
+    pub fn set_sequencer_status(&mut self, sequencer_status: SlotSequencerStatus) {
+        self.sequencer_status = sequencer_status;
+    }
+
+    /// Get the latest slot miss statistics reported by the slot sequencer
+    pub fn get_slot_miss_stats(&self) -> SlotMissStats {
+        self.slot_miss_stats.clone()
+    }
+
+    /// Update the slot miss statistics reported by the slot sequencer
+    pub fn set_slot_miss_stats(&mut self, slot_miss_stats: SlotMissStats) {
+        self.slot_miss_stats = slot_miss_stats;
+    }
+
+    /// Get the asynchronous messages of the final pool matching a filter, in execution priority order
+    pub fn get_async_messages(&self, filter: &AsyncMessageFilter) -> Vec<PendingAsyncMessage> {
+        let final_state = self.final_state.read();
+        let async_pool = final_state.get_async_pool();
+        async_pool
+            .message_info_cache
+            .iter()
+            .filter(|(_, info)| {
+                filter.valid_at.map_or(true, |slot| {
+                    info.validity_start <= slot && slot < info.validity_end
+                }) && filter.can_be_executed.map_or(true, |can_be_executed| {
+                    info.can_be_executed == can_be_executed
+                })
+            })
+            .filter_map(|(id, _)| async_pool.fetch_message(id))
+            .filter(|message| {
+                filter.sender.map_or(true, |addr| message.sender == addr)
+                    && filter
+                        .destination
+                        .map_or(true, |addr| message.destination == addr)
+            })
+            .map(|message| PendingAsyncMessage {
+                emission_slot: message.emission_slot,
+                emission_index: message.emission_index,
+                sender: message.sender,
+                destination: message.destination,
+                function: message.function,
+                max_gas: message.max_gas,
+                fee: message.fee,
+                coins: message.coins,
+                validity_start: message.validity_start,
+                validity_end: message.validity_end,
+                has_trigger: message.trigger.is_some(),
+                can_be_executed: message.can_be_executed,
+            })
+            .collect()
+    }
+
+    /// Get the slots and block ids of the speculatively executed slots, oldest first
+    pub fn get_active_history_blocks(&self) -> Vec<(Slot, Option<BlockId>)> {
+        self.active_history
+            .read()
+            .0
+            .iter()
+            .map(|output| {
+                (
+                    output.slot,
+                    output.block_info.as_ref().map(|info| info.block_id),
+                )
+            })
+            .collect()
+    }
+
+    /// Builds a checkpoint if `execution_checkpoint_interval` final slots were executed since the latest one.
+    /// Writing it to disk is left to the caller so that it can happen without holding the execution lock.
+    pub fn take_due_checkpoint(&mut self) -> Option<ExecutionCheckpoint> {
+        if self.config.execution_checkpoint_interval == 0
+            || self.final_slots_since_checkpoint < self.config.execution_checkpoint_interval
+        {
+            return None;
+        }
+        self.final_slots_since_checkpoint = 0;
+        Some(ExecutionCheckpoint {
+            final_cursor: self.final_cursor,
+            final_state_fingerprint: self.get_final_state_fingerprint(),
+            final_events: self.final_events.clone(),
+            recent_operation_results: self.recent_operation_results.get_ordered(),
+            active_history: self.active_history.read().0.iter().cloned().collect(),
+        })
     }
 
     /// Applies the output of an execution to the final execution state.
@@ -217,8 +475,16 @@ impl ExecutionState {
             .write()
             .finalize(exec_out.slot, exec_out.state_changes);
 
+        // keep the final state hash of the slot, to be compared with the other nodes
+        self.final_state_hashes
+            .push_back((exec_out.slot, self.get_final_state_fingerprint()));
+        while self.final_state_hashes.len() > self.config.final_state_hash_history_length {
+            self.final_state_hashes.pop_front();
+        }
+
         // update the final ledger's slot
         self.final_cursor = exec_out.slot;
+        self.final_slots_since_checkpoint = self.final_slots_since_checkpoint.saturating_add(1);
 
         // update active cursor:
         // if it was at the previous latest final block, set it to point to the new one
@@ -226,11 +492,18 @@ impl ExecutionState {
             self.active_cursor = self.final_cursor;
         }
 
-        // append generated events to the final event store
+        // append generated events to the final event store, and to the event index if enabled
         exec_out.events.finalize();
+        if let Some(event_index) = &mut self.event_index {
+            event_index.insert(&exec_out.events.0);
+        }
         self.final_events.extend(exec_out.events);
         self.final_events.prune(self.config.max_final_events);
 
+        // keep the results of the operations that became final
+        self.recent_operation_results
+            .extend(exec_out.operation_results);
+
         // update the prometheus metrics
         self.massa_metrics
             .set_active_cursor(self.active_cursor.period, self.active_cursor.thread);
@@ -253,11 +526,21 @@ impl ExecutionState {
                 .len(),
         );
 
+        let module_cache_stats = self.module_cache.write().take_stats();
+        self.massa_metrics.inc_module_cache_stats(
+            module_cache_stats.lru_hits,
+            module_cache_stats.hd_hits,
+            module_cache_stats.misses,
+        );
+
         self.massa_metrics.inc_executed_final_slot();
         if exec_out.block_info.is_some() {
             self.massa_metrics.inc_executed_final_slot_with_block();
         }
 
+        // retain the final output for its consumers
+        self.output_retention.lock().push(exec_out_2.clone());
+
         // Broadcast a final slot execution output to active channel subscribers.
         if self.config.broadcast_enabled {
             let slot_exec_out = SlotExecutionOutput::FinalizedSlot(exec_out_2);
@@ -299,6 +582,11 @@ impl ExecutionState {
             .set_active_history(self.active_history.read().0.len())
     }
 
+    /// Records a gas charge in the execution trace of the current slot, if tracing is enabled
+    fn trace_gas(&self, label: &'static str, amount: u64) {
+        context_guard!(self).trace(|| ExecutionTraceItem::GasCharge { label, amount });
+    }
+
     /// Helper function.
     /// Within a locked execution context (lock is taken at the beginning of the function then released at the end):
     /// - if not yet executed then transfer fee and add the operation to the context then return a context snapshot
@@ -333,10 +621,12 @@ impl ExecutionState {
                 .saturating_sub(operation.get_max_spending(self.config.roll_price)),
         );
 
+        // start recording the coin movements of the operation
+        context.op_coin_movements = Some(Vec::new());
+
         // debit the fee from the operation sender
-        if let Err(err) =
-            context.transfer_coins(Some(sender_addr), None, operation.content.fee, false)
-        {
+        if let Err(err) = context.transfer_fee(sender_addr, operation.content.fee) {
+            context.op_coin_movements = None;
             let error = format!("could not spend fees: {}", err);
             let event = context.event_create(error.clone(), true);
             context.event_emit(event);
@@ -413,8 +703,25 @@ impl ExecutionState {
 
         let context_snapshot = self.prepare_operation_for_execution(operation, sender_addr)?;
 
+        // events emitted from here on belong to the operation
+        let first_event_index = context_snapshot.event_count;
+
+        // ABI calls from here on belong to the operation
+        #[cfg(feature = "gas_profile")]
+        if let Some(profiler) = &mut context_guard!(self).gas_profiler {
+            profiler.start_operation(operation_id);
+        }
+
+        // nested calls from here on belong to the operation
+        if let OperationType::ExecuteSC { max_gas, .. } | OperationType::CallSC { max_gas, .. } =
+            &operation.content.op
+        {
+            context_guard!(self).op_call_stats = Some(CallStatsRecorder::new(*max_gas));
+        }
+
         // update block gas
         *remaining_block_gas = new_remaining_block_gas;
+        self.trace_gas("operation", op_gas);
 
         // update block credits
         *block_credits = new_block_credits;
@@ -458,15 +765,18 @@ impl ExecutionState {
             }
 
             // check execution results
-            match execution_result {
+            let error = match execution_result {
                 Ok(_) => {
                     context.insert_executed_op(
                         operation_id,
                         true,
                         Slot::new(operation.content.expire_period, op_thread),
                     );
+                    None
                 }
                 Err(err) => {
+                    let error = err.to_string();
+
                     // an error occurred: emit error event and reset context to snapshot
                     let err = ExecutionError::RuntimeError(format!(
                         "runtime error when executing operation {}: {}",
@@ -480,8 +790,42 @@ impl ExecutionState {
                         operation_id,
                         false,
                         Slot::new(operation.content.expire_period, op_thread),
-                    )
+                    );
+                    Some(error)
                 }
+            };
+
+            // keep the detailed result of the operation
+            let result = OperationExecutionResult {
+                operation_id,
+                slot: block_slot,
+                block_id: context.opt_block_id,
+                success: error.is_none(),
+                error,
+                gas_used: op_gas,
+                events: context
+                    .events
+                    .0
+                    .range(first_event_index..)
+                    .cloned()
+                    .collect(),
+                coin_movements: context.op_coin_movements.take().unwrap_or_default(),
+                call_stats: context
+                    .op_call_stats
+                    .take()
+                    .map(CallStatsRecorder::finish)
+                    .unwrap_or_default(),
+                is_final: false,
+            };
+            context.trace(|| ExecutionTraceItem::OperationCoinFlow {
+                operation_id,
+                edges: OperationCoinFlow::edges_from_movements(&result.coin_movements),
+            });
+            context.operation_results.insert(operation_id, result);
+
+            #[cfg(feature = "gas_profile")]
+            if let Some(profiler) = &mut context.gas_profiler {
+                profiler.finish_operation(op_gas);
             }
         }
 
@@ -561,8 +905,10 @@ impl ExecutionState {
         //       include them in executed denunciation and prevent (by occupying the corresponding entry)
         //       any further 'real' denunciation.
 
-        match &denunciation {
-            Denunciation::Endorsement(_de) => {
+        // Note 3: the denounced draw is given by the index, so that versioned denunciations
+        //         are checked against the same draw as the equivalent unversioned ones
+        match denunciation.get_index() {
+            Some(index) => {
                 // Get selected address from selector and check
                 let selection = self
                     .selector
@@ -570,7 +916,7 @@ impl ExecutionState {
                     .expect("Could not get producer from selector");
                 let selected_addr = selection
                     .endorsements
-                    .get(*denunciation.get_index().unwrap_or(&0) as usize)
+                    .get(*index as usize)
                     .expect("could not get selection for endorsement at index");
 
                 if *selected_addr != addr_denounced {
@@ -580,7 +926,7 @@ impl ExecutionState {
                     ));
                 }
             }
-            Denunciation::BlockHeader(_de) => {
+            None => {
                 let selected_addr = self
                     .selector
                     .get_producer(*de_slot)
@@ -812,8 +1158,9 @@ impl ExecutionState {
             .module_cache
             .read()
             .load_tmp_module(bytecode, *max_gas)?;
+        self.trace_gas("module_loading", max_gas.saturating_sub(remaining_gas));
         // run the VM
-        massa_sc_runtime::run_main(
+        let response = massa_sc_runtime::run_main(
             &*self.execution_interface,
             module,
             remaining_gas,
@@ -823,6 +1170,10 @@ impl ExecutionState {
             context: "ExecuteSC".to_string(),
             error,
         })?;
+        self.trace_gas(
+            "vm_execution",
+            remaining_gas.saturating_sub(response.remaining_gas),
+        );
 
         Ok(())
     }
@@ -894,6 +1245,7 @@ impl ExecutionState {
             if target_func.is_empty() {
                 return Ok(());
             }
+            context.record_contract_call(&target_addr);
 
             // Load bytecode. Assume empty bytecode if not found.
             bytecode = context.get_bytecode(&target_addr).unwrap_or_default().0;
@@ -902,6 +1254,7 @@ impl ExecutionState {
         // load and execute the compiled module
         // IMPORTANT: do not keep a lock here as `run_function` uses the `get_module` interface
         let (module, remaining_gas) = self.module_cache.write().load_module(&bytecode, max_gas)?;
+        self.trace_gas("module_loading", max_gas.saturating_sub(remaining_gas));
         let response = massa_sc_runtime::run_function(
             &*self.execution_interface,
             module,
@@ -919,10 +1272,14 @@ impl ExecutionState {
             }
             _ => (),
         }
-        response.map_err(|error| ExecutionError::VMError {
+        let response = response.map_err(|error| ExecutionError::VMError {
             context: "CallSC".to_string(),
             error,
         })?;
+        self.trace_gas(
+            "vm_execution",
+            remaining_gas.saturating_sub(response.remaining_gas),
+        );
         Ok(())
     }
 
@@ -990,6 +1347,7 @@ impl ExecutionState {
                 context.cancel_async_message(&message);
                 return Err(err);
             }
+            context.record_contract_call(&message.destination);
 
             bytecode.0
         };
@@ -1000,6 +1358,10 @@ impl ExecutionState {
             .module_cache
             .write()
             .load_module(&bytecode, message.max_gas)?;
+        self.trace_gas(
+            "module_loading",
+            message.max_gas.saturating_sub(remaining_gas),
+        );
         let response = massa_sc_runtime::run_function(
             &*self.execution_interface,
             module,
@@ -1009,10 +1371,18 @@ impl ExecutionState {
             self.config.gas_costs.clone(),
         );
         match response {
-            Ok(Response { init_gas_cost, .. }) => {
+            Ok(Response {
+                init_gas_cost,
+                remaining_gas: vm_remaining_gas,
+                ..
+            }) => {
                 self.module_cache
                     .write()
                     .set_init_cost(&bytecode, init_gas_cost);
+                self.trace_gas(
+                    "vm_execution",
+                    remaining_gas.saturating_sub(vm_remaining_gas),
+                );
                 Ok(())
             }
             Err(error) => {
@@ -1041,6 +1411,7 @@ impl ExecutionState {
     /// * `slot`: slot to execute
     /// * `exec_target`: metadata of the block to execute, if not miss
     /// * `selector`: Reference to the selector
+    /// * `execution_final`: whether the slot is executed as SCE-final, which names its execution trace
     ///
     /// # Returns
     /// An `ExecutionOutput` structure summarizing the output of the executed slot
@@ -1049,7 +1420,28 @@ impl ExecutionState {
         slot: &Slot,
         exec_target: Option<&(BlockId, ExecutionBlockMetadata)>,
         selector: Box<dyn SelectorController>,
+        execution_final: bool,
     ) -> ExecutionOutput {
+        self.execute_preemptible_slot(slot, exec_target, selector, execution_final, None)
+            .expect("slot execution was preempted without a preemption flag")
+    }
+
+    /// Executes a full slot like `Self::execute_slot`,
+    /// but gives up as soon as the `preemption` flag is raised.
+    /// The flag is checked before each operation of the block.
+    ///
+    /// # Returns
+    /// The `ExecutionOutput` of the slot, or None if the execution was preempted
+    fn execute_preemptible_slot(
+        &self,
+        slot: &Slot,
+        exec_target: Option<&(BlockId, ExecutionBlockMetadata)>,
+        selector: Box<dyn SelectorController>,
+        execution_final: bool,
+        preemption: Option<&AtomicBool>,
+    ) -> Option<ExecutionOutput> {
+        let is_preempted = || preemption.map_or(false, |flag| flag.load(Ordering::Acquire));
+
         // Create a new execution context for the whole active slot
         let mut execution_context = ExecutionContext::active_slot(
             self.config.clone(),
@@ -1067,6 +1459,32 @@ impl ExecutionState {
             self.config.async_msg_cst_gas_cost,
         );
 
+        // Attach a tracer to the context if execution tracing is enabled
+        let tracer = self.config.execution_trace_enabled.then(|| {
+            Arc::new(ExecutionTracer::new(
+                *slot,
+                exec_target.as_ref().map(|(b_id, _)| *b_id),
+            ))
+        });
+        execution_context.tracer = tracer.clone();
+
+        // Attach a datastore access statistics recorder to the context if enabled
+        let io_stats = self
+            .config
+            .contract_io_stats_enabled
+            .then(|| Arc::new(IoStatsRecorder::new(*slot)));
+        execution_context.io_stats = io_stats.clone();
+
+        // Attach a gas profiler to the context if gas profiling is enabled
+        #[cfg(feature = "gas_profile")]
+        {
+            execution_context.gas_profiler = Some(GasProfiler::new(
+                *slot,
+                exec_target.as_ref().map(|(b_id, _)| *b_id),
+                self.abi_gas_costs.clone(),
+            ));
+        }
+
         // Apply the created execution context for slot execution
         *context_guard!(self) = execution_context;
 
@@ -1139,6 +1557,10 @@ impl ExecutionState {
             // Try executing the operations of this block in the order in which they appear in the block.
             // Errors are logged but do not interrupt the execution of the slot.
             for operation in operations.into_iter() {
+                if is_preempted() {
+                    debug!("execution of slot {} preempted", slot);
+                    return None;
+                }
                 if let Err(err) = self.execute_operation(
                     &operation,
                     stored_block.content.header.content.slot,
@@ -1238,6 +1660,27 @@ impl ExecutionState {
         // Finish slot
         let exec_out = context_guard!(self).settle_slot(block_info);
 
+        // Keep the gas profile of the slot
+        #[cfg(feature = "gas_profile")]
+        if let Some(profiler) = context_guard!(self).gas_profiler.take() {
+            self.gas_profiles.lock().insert(profiler.into_profile());
+        }
+
+        // Keep the datastore access statistics of the slot
+        if let Some(io_stats) = io_stats {
+            self.contract_io_stats.lock().insert(io_stats.take());
+        }
+
+        // Dump the execution trace of the slot
+        if let Some(tracer) = tracer {
+            dump_slot_trace(
+                &tracer.take(),
+                &self.config.execution_trace_path,
+                self.config.execution_trace_format,
+                execution_final,
+            );
+        }
+
         // Broadcast a slot execution output to active channel subscribers.
         if self.config.broadcast_enabled {
             let slot_exec_out = SlotExecutionOutput::ExecutedSlot(exec_out.clone());
@@ -1255,16 +1698,22 @@ impl ExecutionState {
         }
 
         // Return the execution output
-        exec_out
+        Some(exec_out)
     }
 
-    /// Execute a candidate slot
+    /// Execute a candidate slot.
+    /// The execution is given up if the `preemption` flag is raised before it completes,
+    /// in which case the slot is left unexecuted.
+    ///
+    /// # Returns
+    /// true if the slot was executed, false if its execution was preempted
     pub fn execute_candidate_slot(
         &mut self,
         slot: &Slot,
         exec_target: Option<&(BlockId, ExecutionBlockMetadata)>,
         selector: Box<dyn SelectorController>,
-    ) {
+        preemption: Option<&AtomicBool>,
+    ) -> bool {
         let target_id = exec_target.as_ref().map(|(b_id, _)| *b_id);
         debug!(
             "execute_candidate_slot: executing slot={} target={:?}",
@@ -1290,12 +1739,20 @@ impl ExecutionState {
             self.active_cursor = slot
                 .get_prev_slot(self.config.thread_count)
                 .expect("overflow when iterating on slots");
+            self.readonly_call_cache
+                .invalidate_after(&self.active_cursor);
         }
-        let exec_out = self.execute_slot(slot, exec_target, selector);
+        let Some(exec_out) =
+            self.execute_preemptible_slot(slot, exec_target, selector, false, preemption)
+        else {
+            debug!("execute_candidate_slot: execution preempted");
+            return false;
+        };
 
         // apply execution output to active state
         self.apply_active_execution_output(exec_out);
         debug!("execute_candidate_slot: execution finished & state applied");
+        true
     }
 
     /// Execute an SCE-final slot
@@ -1347,10 +1804,12 @@ impl ExecutionState {
         // truncate the whole execution queue
         self.active_history.write().0.clear();
         self.active_cursor = self.final_cursor;
+        self.readonly_call_cache
+            .invalidate_after(&self.active_cursor);
 
         // execute slot
         debug!("execute_final_slot: execution started");
-        let exec_out = self.execute_slot(slot, exec_target, selector);
+        let exec_out = self.execute_slot(slot, exec_target, selector, true);
 
         // apply execution output to final state
         self.apply_final_execution_output(exec_out);
@@ -1371,6 +1830,72 @@ impl ExecutionState {
     /// # Returns
     ///  `ExecutionOutput` describing the output of the execution, or an error
     pub(crate) fn execute_readonly_request(
+        &mut self,
+        req: ReadOnlyExecutionRequest,
+    ) -> Result<ReadOnlyExecutionOutput, ExecutionError> {
+        // the request is executed at the slot after the latest executed active slot:
+        // identical function calls made before that slot changes get the same result
+        let slot = self
+            .active_cursor
+            .get_next_slot(self.config.thread_count)
+            .expect("slot overflow in readonly execution from active slot");
+        // only the requests executed on top of the actual candidate state are cached
+        let cache_key =
+            if req.state == ReadOnlyExecutionState::Candidate && req.ledger_overrides.is_empty() {
+                ReadOnlyCallKey::new(slot, &req)
+            } else {
+                None
+            };
+        if let Some(output) = cache_key
+            .as_ref()
+            .and_then(|key| self.readonly_call_cache.get(key))
+        {
+            return Ok(output);
+        }
+
+        let output = self.run_readonly_request(req)?;
+        if let Some(key) = cache_key {
+            self.readonly_call_cache.insert(key, output.clone());
+        }
+        Ok(output)
+    }
+
+    /// Gets the slot at which a read-only request is executed on top of a state,
+    /// and the active history leading to that state
+    fn get_readonly_state(
+        &self,
+        state: ReadOnlyExecutionState,
+    ) -> Result<(Slot, Arc<RwLock<ActiveHistory>>), ExecutionError> {
+        let state_slot = match state {
+            ReadOnlyExecutionState::Candidate => self.active_cursor,
+            ReadOnlyExecutionState::Final => self.final_cursor,
+            ReadOnlyExecutionState::Slot(slot) => {
+                if slot < self.final_cursor || slot > self.active_cursor {
+                    return Err(ExecutionError::NotFound(format!(
+                        "state at slot {} is not available: only the states between the final slot {} and the candidate slot {} are",
+                        slot, self.final_cursor, self.active_cursor
+                    )));
+                }
+                slot
+            }
+        };
+        let slot = state_slot
+            .get_next_slot(self.config.thread_count)
+            .expect("slot overflow in readonly execution");
+        if state_slot == self.active_cursor {
+            return Ok((slot, self.active_history.clone()));
+        }
+        // keep only the outputs of the slots executed up to the requested state
+        let active_history = self
+            .active_history
+            .read()
+            .history_before(&slot, self.config.thread_count);
+        Ok((slot, Arc::new(RwLock::new(active_history))))
+    }
+
+    /// Runs a read-only execution request without using the read-only call cache
+    /// (see `Self::execute_readonly_request`).
+    fn run_readonly_request(
         &self,
         req: ReadOnlyExecutionRequest,
     ) -> Result<ReadOnlyExecutionOutput, ExecutionError> {
@@ -1385,22 +1910,20 @@ impl ExecutionState {
             )));
         }
 
-        // set the execution slot to be the one after the latest executed active slot
-        let slot = self
-            .active_cursor
-            .get_next_slot(self.config.thread_count)
-            .expect("slot overflow in readonly execution from active slot");
+        // get the execution slot and the history of the requested state
+        let (slot, active_history) = self.get_readonly_state(req.state)?;
 
         // create a readonly execution context
-        let execution_context = ExecutionContext::readonly(
+        let mut execution_context = ExecutionContext::readonly(
             self.config.clone(),
             slot,
             req.call_stack,
             self.final_state.clone(),
-            self.active_history.clone(),
+            active_history,
             self.module_cache.clone(),
             self.mip_store.clone(),
         );
+        execution_context.apply_ledger_overrides(&req.ledger_overrides);
 
         // run the interpreter according to the target type
         let exec_response = match req.target {
@@ -1519,6 +2042,66 @@ impl ExecutionState {
         })
     }
 
+    /// Simulates the execution of an operation on top of the latest candidate state,
+    /// with hypothetical ledger overrides, without modifying that state.
+    ///
+    /// The operation is executed as if it were the only operation of a block
+    /// at the first slot of its sender's thread after the latest executed candidate slot.
+    pub(crate) fn simulate_operation(
+        &self,
+        req: OperationSimulationRequest,
+    ) -> Result<OperationSimulationOutput, ExecutionError> {
+        // find the next slot of the thread of the sender
+        let op_thread = req
+            .operation
+            .content_creator_address
+            .get_thread(self.config.thread_count);
+        let mut slot = self
+            .active_cursor
+            .get_next_slot(self.config.thread_count)
+            .expect("slot overflow in operation simulation from active slot");
+        while slot.thread != op_thread {
+            slot = slot
+                .get_next_slot(self.config.thread_count)
+                .expect("slot overflow in operation simulation from active slot");
+        }
+
+        // create a readonly execution context on top of the hypothetical ledger state
+        let mut execution_context = ExecutionContext::readonly(
+            self.config.clone(),
+            slot,
+            Vec::new(),
+            self.final_state.clone(),
+            self.active_history.clone(),
+            self.module_cache.clone(),
+            self.mip_store.clone(),
+        );
+        execution_context.apply_ledger_overrides(&req.ledger_overrides);
+        *context_guard!(self) = execution_context;
+
+        // execute the operation as the only one of a block
+        let mut remaining_block_gas = self.config.max_gas_per_block;
+        let mut block_credits = Amount::zero();
+        self.execute_operation(
+            &req.operation,
+            slot,
+            &mut remaining_block_gas,
+            &mut block_credits,
+        )?;
+
+        let out = context_guard!(self).settle_slot(None);
+        let result = out
+            .operation_results
+            .get(&req.operation.id)
+            .cloned()
+            .ok_or_else(|| {
+                ExecutionError::RuntimeError(
+                    "missing result of the simulated operation".to_string(),
+                )
+            })?;
+        Ok(OperationSimulationOutput { out, result })
+    }
+
     /// Gets a balance both at the latest final and candidate executed slots
     pub fn get_final_and_candidate_balance(
         &self,
@@ -1667,6 +2250,22 @@ impl ExecutionState {
             .get_all_active_rolls(cycle)
     }
 
+    /// Gets the final events matching a filter.
+    /// They are read from the on-disk event index if it is enabled and can serve the filter,
+    /// otherwise from the in-memory final event store.
+    fn get_filtered_final_events(&self, filter: &EventFilter) -> Vec<SCOutputEvent> {
+        match &self.event_index {
+            Some(event_index) if FinalEventIndex::can_serve(filter) => {
+                event_index.get_filtered_events(filter)
+            }
+            _ => self
+                .final_events
+                .get_filtered_sc_output_events(filter)
+                .into_iter()
+                .collect(),
+        }
+    }
+
     /// Gets execution events optionally filtered by:
     /// * start slot
     /// * end slot
@@ -1674,13 +2273,11 @@ impl ExecutionState {
     /// * original caller address
     /// * operation id
     /// * event state (final, candidate or both)
+    /// * execution status
+    /// * data prefix
     pub fn get_filtered_sc_output_event(&self, filter: EventFilter) -> Vec<SCOutputEvent> {
         match filter.is_final {
-            Some(true) => self
-                .final_events
-                .get_filtered_sc_output_events(&filter)
-                .into_iter()
-                .collect(),
+            Some(true) => self.get_filtered_final_events(&filter),
             Some(false) => self
                 .active_history
                 .read()
@@ -1689,8 +2286,7 @@ impl ExecutionState {
                 .flat_map(|item| item.events.get_filtered_sc_output_events(&filter))
                 .collect(),
             None => self
-                .final_events
-                .get_filtered_sc_output_events(&filter)
+                .get_filtered_final_events(&filter)
                 .into_iter()
                 .chain(
                     self.active_history
@@ -1836,6 +2432,36 @@ impl ExecutionState {
         (res_speculative, res_final)
     }
 
+    /// Get the candidate deferred credits of an address grouped by the cycle during which they unlock,
+    /// optionally restricted to a single cycle
+    pub fn get_address_deferred_credits_schedule(
+        &self,
+        address: &Address,
+        cycle: Option<u64>,
+    ) -> Vec<CycleDeferredCredits> {
+        let (candidate_credits, _final_credits) = self.get_address_deferred_credits(address);
+        let mut schedule: Vec<CycleDeferredCredits> = Vec::new();
+        // credits are sorted by slot, hence by cycle
+        for (slot, amount) in candidate_credits {
+            let slot_cycle = slot.get_cycle(self.config.periods_per_cycle);
+            if matches!(cycle, Some(cycle) if cycle != slot_cycle) {
+                continue;
+            }
+            match schedule.last_mut() {
+                Some(cycle_credits) if cycle_credits.cycle == slot_cycle => {
+                    cycle_credits.total = cycle_credits.total.saturating_add(amount);
+                    cycle_credits.credits.insert(slot, amount);
+                }
+                _ => schedule.push(CycleDeferredCredits {
+                    cycle: slot_cycle,
+                    total: amount,
+                    credits: BTreeMap::from([(slot, amount)]),
+                }),
+            }
+        }
+        schedule
+    }
+
     /// Get the execution status of a batch of operations.
     ///
     ///  Return value: vector of
@@ -1858,6 +2484,26 @@ impl ExecutionState {
             .collect()
     }
 
+    /// Get the detailed execution results of a batch of operations.
+    ///
+    /// The most recent candidate result is returned if there is one, otherwise the final one.
+    /// None is returned for operations whose execution was not found, or is final but too old to be kept in memory.
+    pub fn get_operation_execution_result(
+        &self,
+        operation_ids: &[OperationId],
+    ) -> Vec<Option<OperationExecutionResult>> {
+        let active_history = self.active_history.read();
+        operation_ids
+            .iter()
+            .map(|op_id| {
+                active_history
+                    .fetch_operation_result(op_id)
+                    .or_else(|| self.recent_operation_results.get(op_id))
+                    .cloned()
+            })
+            .collect()
+    }
+
     /// Update MipStore with block header stats
     pub fn update_versioning_stats(&mut self, block_info: &Option<ExecutedBlockInfo>, slot: &Slot) {
         let slot_ts = get_block_slot_timestamp(
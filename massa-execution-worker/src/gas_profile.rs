@@ -0,0 +1,188 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module implements the gas profiler, compiled in with the `gas_profile` feature.
+//! During the execution of a slot, it records the gas charged for each operation
+//! and the gas attributed to each ABI host function, both per operation and for the whole slot.
+//! The profiles of the latest executed slots are kept in memory and can be queried through the execution controller.
+//!
+//! The gas attributed to a host function is the base cost the runtime charges for calling its ABI.
+//! The gas consumed by the WebAssembly code of a called smart contract is part of the gas of the calling operation.
+
+use massa_execution_exports::{HostFunctionGasProfile, OperationGasProfile, SlotGasProfile};
+use massa_models::{block_id::BlockId, operation::OperationId, slot::Slot};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Maximal number of slot gas profiles kept in memory
+pub(crate) const MAX_GAS_PROFILED_SLOTS: usize = 1000;
+
+/// Base costs of the ABIs, by ABI name
+pub(crate) type AbiGasCosts = HashMap<String, u64>;
+
+/// Loads the base costs of the ABIs from the ABI gas costs file.
+/// Failures are logged and result in host functions being attributed no gas.
+pub(crate) fn load_abi_gas_costs(path: &Path) -> AbiGasCosts {
+    match std::fs::read(path)
+        .map_err(|err| err.to_string())
+        .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|err| err.to_string()))
+    {
+        Ok(costs) => costs,
+        Err(err) => {
+            warn!(
+                "could not load ABI gas costs from {} for gas profiling: {}",
+                path.display(),
+                err
+            );
+            Default::default()
+        }
+    }
+}
+
+fn record_call(
+    host_functions: &mut BTreeMap<String, HostFunctionGasProfile>,
+    name: &str,
+    gas: u64,
+) {
+    let profile = host_functions.entry(name.to_string()).or_default();
+    profile.call_count = profile.call_count.saturating_add(1);
+    profile.gas = profile.gas.saturating_add(gas);
+}
+
+/// Records the gas profile of the slot being executed
+pub(crate) struct GasProfiler {
+    /// profile of the slot
+    profile: SlotGasProfile,
+    /// profile of the operation being executed, if any
+    current_operation: Option<OperationGasProfile>,
+    /// base costs of the ABIs
+    abi_gas_costs: Arc<AbiGasCosts>,
+}
+
+impl GasProfiler {
+    /// Creates a profiler for the execution of a given slot
+    pub fn new(slot: Slot, block_id: Option<BlockId>, abi_gas_costs: Arc<AbiGasCosts>) -> Self {
+        GasProfiler {
+            profile: SlotGasProfile {
+                slot,
+                block_id,
+                operations_gas: 0,
+                host_functions: Default::default(),
+                operations: Vec::new(),
+            },
+            current_operation: None,
+            abi_gas_costs,
+        }
+    }
+
+    /// Records a call to a host function, attributing it the base cost of its ABI
+    pub fn record_host_function(&mut self, name: &str) {
+        let gas = self
+            .abi_gas_costs
+            .get(&format!("assembly_script_{}", name))
+            .copied()
+            .unwrap_or(0);
+        record_call(&mut self.profile.host_functions, name, gas);
+        if let Some(operation) = &mut self.current_operation {
+            record_call(&mut operation.host_functions, name, gas);
+        }
+    }
+
+    /// Starts profiling an operation
+    pub fn start_operation(&mut self, operation_id: OperationId) {
+        self.current_operation = Some(OperationGasProfile {
+            operation_id,
+            gas_used: 0,
+            host_functions: Default::default(),
+        });
+    }
+
+    /// Finishes profiling the current operation
+    pub fn finish_operation(&mut self, gas_used: u64) {
+        if let Some(mut operation) = self.current_operation.take() {
+            operation.gas_used = gas_used;
+            self.profile.operations_gas = self.profile.operations_gas.saturating_add(gas_used);
+            self.profile.operations.push(operation);
+        }
+    }
+
+    /// Returns the recorded profile
+    pub fn into_profile(mut self) -> SlotGasProfile {
+        if let Some(operation) = self.current_operation.take() {
+            self.profile.operations.push(operation);
+        }
+        self.profile
+    }
+}
+
+/// Finite-size store of the gas profiles of the latest executed slots
+pub(crate) struct GasProfileStore {
+    /// profiles indexed by slot
+    profiles: BTreeMap<Slot, SlotGasProfile>,
+}
+
+impl GasProfileStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        GasProfileStore {
+            profiles: Default::default(),
+        }
+    }
+
+    /// Stores the profile of an executed slot, replacing the one of a previous execution of that slot if any
+    pub fn insert(&mut self, profile: SlotGasProfile) {
+        self.profiles.insert(profile.slot, profile);
+        while self.profiles.len() > MAX_GAS_PROFILED_SLOTS {
+            self.profiles.pop_first();
+        }
+    }
+
+    /// Gets the profile of a slot, if still known
+    pub fn get(&self, slot: &Slot) -> Option<SlotGasProfile> {
+        self.profiles.get(slot).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+    use massa_models::secure_share::Id;
+
+    #[test]
+    fn test_gas_profiler() {
+        let op_id = OperationId::new(Hash::compute_from(b"op"));
+        let abi_gas_costs = Arc::new(AbiGasCosts::from([
+            ("assembly_script_print".to_string(), 10),
+            ("assembly_script_get_balance".to_string(), 5),
+        ]));
+        let mut profiler = GasProfiler::new(Slot::new(1, 0), None, abi_gas_costs.clone());
+        // ABI calls outside of operations (asynchronous messages) only count for the slot
+        profiler.record_host_function("print");
+        profiler.start_operation(op_id);
+        profiler.record_host_function("print");
+        profiler.record_host_function("get_balance");
+        profiler.record_host_function("unknown");
+        profiler.finish_operation(1000);
+
+        let profile = profiler.into_profile();
+        assert_eq!(profile.operations_gas, 1000);
+        assert_eq!(profile.host_functions["print"].call_count, 2);
+        assert_eq!(profile.host_functions["print"].gas, 20);
+        assert_eq!(profile.operations.len(), 1);
+        assert_eq!(profile.operations[0].operation_id, op_id);
+        assert_eq!(profile.operations[0].host_functions["print"].call_count, 1);
+        assert_eq!(profile.operations[0].host_functions["get_balance"].gas, 5);
+        assert_eq!(profile.operations[0].host_functions["unknown"].gas, 0);
+
+        let mut store = GasProfileStore::new();
+        for period in 0..(MAX_GAS_PROFILED_SLOTS as u64 + 1) {
+            store.insert(
+                GasProfiler::new(Slot::new(period, 0), None, abi_gas_costs.clone()).into_profile(),
+            );
+        }
+        assert!(store.get(&Slot::new(0, 0)).is_none());
+        assert!(store.get(&Slot::new(1, 0)).is_some());
+    }
+}
@@ -4,8 +4,15 @@
 //! This allows the VM runtime to access the Massa execution context,
 //! for example to interact with the ledger.
 //! See the definition of Interface in the massa-sc-runtime crate for functional details.
+//!
+//! Address introspection is exposed to contracts through `check_address_wasmv1`,
+//! `get_address_category_wasmv1` and `get_address_version_wasmv1`, which mirror
+//! `Address::is_sc` and `Address::get_version` from massa-models.
+//! The thread of an address (`Address::get_thread`) has no host function yet:
+//! it needs a new ABI entry in massa-sc-runtime before it can be implemented here.
 
 use crate::context::ExecutionContext;
+use crate::trace::ExecutionTraceItem;
 use anyhow::{anyhow, bail, Result};
 use massa_async_pool::{AsyncMessage, AsyncMessageTrigger};
 use massa_execution_exports::ExecutionConfig;
@@ -13,8 +20,9 @@ use massa_execution_exports::ExecutionStackElement;
 use massa_models::bytecode::Bytecode;
 use massa_models::config::MAX_DATASTORE_KEY_LENGTH;
 use massa_models::datastore::get_prefix_bounds;
+use massa_models::error::ModelsError;
 use massa_models::{
-    address::{Address, SCAddress, UserAddress},
+    address::{Address, AddressFormat},
     amount::Amount,
     slot::Slot,
     timeslots::get_block_slot_timestamp,
@@ -58,6 +66,13 @@ macro_rules! context_guard {
     };
 }
 
+/// Parses an address passed by a smart contract.
+/// Smart contracts only deal with base58check addresses, the format in which addresses are displayed,
+/// so that the strings accepted as addresses during execution stay the same on every node.
+fn parse_sc_address(address: &str) -> Result<Address, ModelsError> {
+    Address::from_str_with_format(address, AddressFormat::Base58Check)
+}
+
 /// an implementation of the Interface trait (see massa-sc-runtime crate)
 #[derive(Clone)]
 pub struct InterfaceImpl {
@@ -77,6 +92,18 @@ impl InterfaceImpl {
         InterfaceImpl { config, context }
     }
 
+    /// records an ABI call in the execution trace, if tracing is enabled,
+    /// and in the gas profile of the slot, if gas profiling is enabled
+    fn trace_abi_call(&self, name: &'static str) {
+        if self.config.execution_trace_enabled {
+            context_guard!(self).trace(|| ExecutionTraceItem::AbiCall { name });
+        }
+        #[cfg(feature = "gas_profile")]
+        if let Some(profiler) = &mut context_guard!(self).gas_profiler {
+            profiler.record_host_function(name);
+        }
+    }
+
     #[cfg(any(
         feature = "gas_calibration",
         feature = "benchmarking",
@@ -88,7 +115,9 @@ impl InterfaceImpl {
         sender_addr: Address,
         operation_datastore: Option<Datastore>,
     ) -> InterfaceImpl {
-        use massa_db_exports::{MassaDBConfig, MassaDBController};
+        use massa_db_exports::{
+            MassaDBConfig, MassaDBController, STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+        };
         use massa_db_worker::MassaDB;
         use massa_final_state::test_exports::get_sample_state;
         use massa_ledger_exports::{LedgerEntry, SetUpdateOrDelete};
@@ -115,6 +144,7 @@ impl InterfaceImpl {
             max_final_state_elements_size: 100_000,
             max_versioning_elements_size: 100_000,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
 
         let db = Arc::new(RwLock::new(
@@ -128,6 +158,7 @@ impl InterfaceImpl {
             lru_cache_size: config.lru_cache_size,
             hd_cache_size: config.hd_cache_size,
             snip_amount: config.snip_amount,
+            hd_cache_fingerprint: config.hd_cache_fingerprint,
             max_module_length: config.max_bytecode_size,
         })));
 
@@ -205,7 +236,7 @@ fn get_address_from_opt_or_context(
     option_address_string: Option<String>,
 ) -> Result<Address> {
     match option_address_string {
-        Some(address_string) => Address::from_str(&address_string).map_err(|e| e.into()),
+        Some(address_string) => parse_sc_address(&address_string).map_err(|e| e.into()),
         None => context.get_current_address().map_err(|e| e.into()),
     }
 }
@@ -217,6 +248,7 @@ fn get_address_from_opt_or_context(
 impl Interface for InterfaceImpl {
     /// prints a message in the node logs at log level 3 (debug)
     fn print(&self, message: &str) -> Result<()> {
+        self.trace_abi_call("print");
         if cfg!(test) {
             println!("SC print: {}", message);
         } else {
@@ -237,8 +269,9 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The target bytecode or an error
     fn init_call(&self, address: &str, raw_coins: u64) -> Result<Vec<u8>> {
+        self.trace_abi_call("init_call");
         // get target address
-        let to_address = Address::from_str(address)?;
+        let to_address = parse_sc_address(address)?;
 
         // write-lock context
         let mut context = context_guard!(self);
@@ -274,12 +307,16 @@ impl Interface for InterfaceImpl {
         }
 
         // push a new call stack element on top of the current call stack
+        context.record_contract_call(&to_address);
         context.stack.push(ExecutionStackElement {
             address: to_address,
             coins,
             owned_addresses: vec![to_address],
             operation_datastore: None,
         });
+        if let Some(call_stats) = &mut context.op_call_stats {
+            call_stats.enter_call();
+        }
 
         // return the target bytecode
         Ok(bytecode.0)
@@ -288,11 +325,15 @@ impl Interface for InterfaceImpl {
     /// Called to finish the call process after a bytecode calls a function from another one.
     /// This function just pops away the top element of the call stack.
     fn finish_call(&self) -> Result<()> {
+        self.trace_abi_call("finish_call");
         let mut context = context_guard!(self);
 
         if context.stack.pop().is_none() {
             bail!("call stack out of bounds")
         }
+        if let Some(call_stats) = &mut context.op_call_stats {
+            call_stats.exit_call();
+        }
 
         Ok(())
     }
@@ -302,7 +343,11 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// A `massa-sc-runtime` CL compiled module & the remaining gas after loading the module
     fn get_module(&self, bytecode: &[u8], gas_limit: u64) -> Result<(RuntimeModule, u64)> {
-        let context = context_guard!(self);
+        self.trace_abi_call("get_module");
+        let mut context = context_guard!(self);
+        if let Some(call_stats) = &mut context.op_call_stats {
+            call_stats.record_entry_gas(gas_limit);
+        }
         let (module, remaining_gas) = context
             .module_cache
             .write()
@@ -315,6 +360,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// A `massa-sc-runtime` SP compiled module & the remaining gas after loading the module
     fn get_tmp_module(&self, bytecode: &[u8], gas_limit: u64) -> Result<(RuntimeModule, u64)> {
+        self.trace_abi_call("get_tmp_module");
         let context = context_guard!(self);
         let (module, remaining_gas) = context
             .module_cache
@@ -331,6 +377,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `get_balance_wasmv1`
     fn get_balance(&self) -> Result<u64> {
+        self.trace_abi_call("get_balance");
         let context = context_guard!(self);
         let address = context.get_current_address()?;
         Ok(context.get_balance(&address).unwrap_or_default().to_raw())
@@ -347,7 +394,8 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `get_balance_wasmv1`
     fn get_balance_for(&self, address: &str) -> Result<u64> {
-        let address = massa_models::address::Address::from_str(address)?;
+        self.trace_abi_call("get_balance_for");
+        let address = parse_sc_address(address)?;
         Ok(context_guard!(self)
             .get_balance(&address)
             .unwrap_or_default()
@@ -363,6 +411,7 @@ impl Interface for InterfaceImpl {
     /// The raw representation (no decimal factor) of the balance of the address,
     /// or zero if the address is not found in the ledger.
     fn get_balance_wasmv1(&self, address: Option<String>) -> Result<NativeAmount> {
+        self.trace_abi_call("get_balance_wasmv1");
         let context = context_guard!(self);
         let address = get_address_from_opt_or_context(&context, address)?;
 
@@ -381,6 +430,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The string representation of the newly created address
     fn create_module(&self, bytecode: &[u8]) -> Result<String> {
+        self.trace_abi_call("create_module");
         match context_guard!(self).create_new_sc_address(Bytecode(bytecode.to_vec())) {
             Ok(addr) => Ok(addr.to_string()),
             Err(err) => bail!("couldn't create new SC address: {}", err),
@@ -394,6 +444,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `get_keys_wasmv1`
     fn get_keys(&self, prefix_opt: Option<&[u8]>) -> Result<BTreeSet<Vec<u8>>> {
+        self.trace_abi_call("get_keys");
         let context = context_guard!(self);
         let addr = context.get_current_address()?;
         match context.get_keys(&addr, prefix_opt.unwrap_or_default()) {
@@ -409,7 +460,8 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `get_keys_wasmv1`
     fn get_keys_for(&self, address: &str, prefix_opt: Option<&[u8]>) -> Result<BTreeSet<Vec<u8>>> {
-        let addr = &Address::from_str(address)?;
+        self.trace_abi_call("get_keys_for");
+        let addr = &parse_sc_address(address)?;
         let context = context_guard!(self);
         match context.get_keys(addr, prefix_opt.unwrap_or_default()) {
             Some(value) => Ok(value),
@@ -426,6 +478,7 @@ impl Interface for InterfaceImpl {
         prefix: &[u8],
         address: Option<String>,
     ) -> Result<BTreeSet<Vec<u8>>> {
+        self.trace_abi_call("get_ds_keys_wasmv1");
         let context = context_guard!(self);
         let address = get_address_from_opt_or_context(&context, address)?;
 
@@ -445,6 +498,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `raw_get_data_wasmv1`
     fn raw_get_data(&self, key: &[u8]) -> Result<Vec<u8>> {
+        self.trace_abi_call("raw_get_data");
         let context = context_guard!(self);
         let addr = context.get_current_address()?;
         match context.get_data_entry(&addr, key) {
@@ -464,7 +518,8 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `raw_get_data_wasmv1`
     fn raw_get_data_for(&self, address: &str, key: &[u8]) -> Result<Vec<u8>> {
-        let addr = &massa_models::address::Address::from_str(address)?;
+        self.trace_abi_call("raw_get_data_for");
+        let addr = &parse_sc_address(address)?;
         let context = context_guard!(self);
         match context.get_data_entry(addr, key) {
             Some(value) => Ok(value),
@@ -481,6 +536,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The datastore value matching the provided key, if found, otherwise an error.
     fn get_ds_value_wasmv1(&self, key: &[u8], address: Option<String>) -> Result<Vec<u8>> {
+        self.trace_abi_call("get_ds_value_wasmv1");
         let context = context_guard!(self);
         let address = get_address_from_opt_or_context(&context, address)?;
 
@@ -501,6 +557,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `raw_set_data_wasmv1`
     fn raw_set_data(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.trace_abi_call("raw_set_data");
         let mut context = context_guard!(self);
         let addr = context.get_current_address()?;
         context.set_data_entry(&addr, key.to_vec(), value.to_vec())?;
@@ -518,13 +575,15 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `raw_set_data_wasmv1`
     fn raw_set_data_for(&self, address: &str, key: &[u8], value: &[u8]) -> Result<()> {
-        let addr = massa_models::address::Address::from_str(address)?;
+        self.trace_abi_call("raw_set_data_for");
+        let addr = parse_sc_address(address)?;
         let mut context = context_guard!(self);
         context.set_data_entry(&addr, key.to_vec(), value.to_vec())?;
         Ok(())
     }
 
     fn set_ds_value_wasmv1(&self, key: &[u8], value: &[u8], address: Option<String>) -> Result<()> {
+        self.trace_abi_call("set_ds_value_wasmv1");
         let mut context = context_guard!(self);
         let address = get_address_from_opt_or_context(&context, address)?;
 
@@ -541,6 +600,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `raw_append_data_wasmv1`
     fn raw_append_data(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.trace_abi_call("raw_append_data");
         let mut context = context_guard!(self);
         let addr = context.get_current_address()?;
         context.append_data_entry(&addr, key.to_vec(), value.to_vec())?;
@@ -557,7 +617,8 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `raw_append_data_wasmv1`
     fn raw_append_data_for(&self, address: &str, key: &[u8], value: &[u8]) -> Result<()> {
-        let addr = massa_models::address::Address::from_str(address)?;
+        self.trace_abi_call("raw_append_data_for");
+        let addr = parse_sc_address(address)?;
         context_guard!(self).append_data_entry(&addr, key.to_vec(), value.to_vec())?;
         Ok(())
     }
@@ -575,6 +636,7 @@ impl Interface for InterfaceImpl {
         value: &[u8],
         address: Option<String>,
     ) -> Result<()> {
+        self.trace_abi_call("append_ds_value_wasmv1");
         let mut context = context_guard!(self);
         let address = get_address_from_opt_or_context(&context, address)?;
 
@@ -590,6 +652,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `raw_delete_data_wasmv1`
     fn raw_delete_data(&self, key: &[u8]) -> Result<()> {
+        self.trace_abi_call("raw_delete_data");
         let mut context = context_guard!(self);
         let addr = context.get_current_address()?;
         context.delete_data_entry(&addr, key)?;
@@ -605,7 +668,8 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `raw_delete_data_wasmv1`
     fn raw_delete_data_for(&self, address: &str, key: &[u8]) -> Result<()> {
-        let addr = &massa_models::address::Address::from_str(address)?;
+        self.trace_abi_call("raw_delete_data_for");
+        let addr = &parse_sc_address(address)?;
         context_guard!(self).delete_data_entry(addr, key)?;
         Ok(())
     }
@@ -617,6 +681,7 @@ impl Interface for InterfaceImpl {
     /// * address: string representation of the address
     /// * key: string key of the datastore entry to delete
     fn delete_ds_entry_wasmv1(&self, key: &[u8], address: Option<String>) -> Result<()> {
+        self.trace_abi_call("delete_ds_entry_wasmv1");
         let mut context = context_guard!(self);
         let address = get_address_from_opt_or_context(&context, address)?;
 
@@ -634,6 +699,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `has_data_wasmv1`
     fn has_data(&self, key: &[u8]) -> Result<bool> {
+        self.trace_abi_call("has_data");
         let context = context_guard!(self);
         let addr = context.get_current_address()?;
         Ok(context.has_data_entry(&addr, key))
@@ -650,7 +716,8 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `has_data_wasmv1`
     fn has_data_for(&self, address: &str, key: &[u8]) -> Result<bool> {
-        let addr = massa_models::address::Address::from_str(address)?;
+        self.trace_abi_call("has_data_for");
+        let addr = parse_sc_address(address)?;
         let context = context_guard!(self);
         Ok(context.has_data_entry(&addr, key))
     }
@@ -664,6 +731,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// true if the address exists and has the entry matching the provided key in its datastore, otherwise false
     fn ds_entry_exists_wasmv1(&self, key: &[u8], address: Option<String>) -> Result<bool> {
+        self.trace_abi_call("ds_entry_exists_wasmv1");
         let context = context_guard!(self);
         let address = get_address_from_opt_or_context(&context, address)?;
 
@@ -675,6 +743,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// true if the caller has write access
     fn caller_has_write_access(&self) -> Result<bool> {
+        self.trace_abi_call("caller_has_write_access");
         let context = context_guard!(self);
         let mut call_stack_iter = context.stack.iter().rev();
         let caller_owned_addresses = if let Some(last) = call_stack_iter.next() {
@@ -694,6 +763,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `raw_get_bytecode_wasmv1`
     fn raw_get_bytecode(&self) -> Result<Vec<u8>> {
+        self.trace_abi_call("raw_get_bytecode");
         let context = context_guard!(self);
         let address = context.get_current_address()?;
         match context.get_bytecode(&address) {
@@ -706,8 +776,9 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `raw_get_bytecode_wasmv1`
     fn raw_get_bytecode_for(&self, address: &str) -> Result<Vec<u8>> {
+        self.trace_abi_call("raw_get_bytecode_for");
         let context = context_guard!(self);
-        let address = Address::from_str(address)?;
+        let address = parse_sc_address(address)?;
         match context.get_bytecode(&address) {
             Some(bytecode) => Ok(bytecode.0),
             _ => bail!("bytecode not found"),
@@ -716,6 +787,7 @@ impl Interface for InterfaceImpl {
 
     /// Returns bytecode of the target address, or the current address if not provided
     fn get_bytecode_wasmv1(&self, address: Option<String>) -> Result<Vec<u8>> {
+        self.trace_abi_call("get_bytecode_wasmv1");
         let context = context_guard!(self);
         let address = get_address_from_opt_or_context(&context, address)?;
 
@@ -733,6 +805,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `get_op_keys_wasmv1`
     fn get_op_keys(&self, prefix_opt: Option<&[u8]>) -> Result<Vec<Vec<u8>>> {
+        self.trace_abi_call("get_op_keys");
         let prefix: &[u8] = prefix_opt.unwrap_or_default();
 
         // compute prefix range
@@ -758,6 +831,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// A list of keys (keys are byte arrays) that match the given prefix
     fn get_op_keys_wasmv1(&self, prefix: &[u8]) -> Result<Vec<Vec<u8>>> {
+        self.trace_abi_call("get_op_keys_wasmv1");
         let prefix_range = get_prefix_bounds(prefix);
         let range_ref = (prefix_range.0.as_ref(), prefix_range.1.as_ref());
 
@@ -783,6 +857,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// true if the entry is matching the provided key in its operation datastore, otherwise false
     fn op_entry_exists(&self, key: &[u8]) -> Result<bool> {
+        self.trace_abi_call("op_entry_exists");
         let context = context_guard!(self);
         let stack = context.stack.last().ok_or_else(|| anyhow!("No stack"))?;
         let datastore = stack
@@ -802,6 +877,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The operation datastore value matching the provided key, if found, otherwise an error.
     fn get_op_data(&self, key: &[u8]) -> Result<Vec<u8>> {
+        self.trace_abi_call("get_op_data");
         let context = context_guard!(self);
         let stack = context.stack.last().ok_or_else(|| anyhow!("No stack"))?;
         let datastore = stack
@@ -823,6 +899,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The hash in bytes format
     fn hash(&self, data: &[u8]) -> Result<[u8; 32]> {
+        self.trace_abi_call("hash");
         Ok(massa_hash::Hash::compute_from(data).into_bytes())
     }
 
@@ -834,13 +911,15 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The string representation of the resulting address
     fn address_from_public_key(&self, public_key: &str) -> Result<String> {
+        self.trace_abi_call("address_from_public_key");
         let public_key = massa_signature::PublicKey::from_str(public_key)?;
         let addr = massa_models::address::Address::from_public_key(&public_key);
         Ok(addr.to_string())
     }
 
     fn validate_address(&self, address: &str) -> Result<bool> {
-        Ok(massa_models::address::Address::from_str(address).is_ok())
+        self.trace_abi_call("validate_address");
+        Ok(parse_sc_address(address).is_ok())
     }
 
     /// Verifies a signature
@@ -853,6 +932,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// true if the signature verification succeeded, false otherwise
     fn signature_verify(&self, data: &[u8], signature: &str, public_key: &str) -> Result<bool> {
+        self.trace_abi_call("signature_verify");
         let signature = match massa_signature::Signature::from_bs58_check(signature) {
             Ok(sig) => sig,
             Err(_) => return Ok(false),
@@ -879,6 +959,7 @@ impl Interface for InterfaceImpl {
         signature_: &[u8],
         public_key_: &[u8],
     ) -> Result<bool> {
+        self.trace_abi_call("evm_signature_verify");
         // check the signature length
         if signature_.len() != 65 {
             return Err(anyhow!("invalid signature length in evm_signature_verify"));
@@ -911,12 +992,14 @@ impl Interface for InterfaceImpl {
 
     /// Keccak256 hash function
     fn hash_keccak256(&self, bytes: &[u8]) -> Result<[u8; 32]> {
+        self.trace_abi_call("hash_keccak256");
         Ok(sha3::Keccak256::digest(bytes).into())
     }
 
     /// Get an EVM address from a raw secp256k1 public key (64 bytes).
     /// Address is the last 20 bytes of the hash of the public key.
     fn evm_get_address_from_pubkey(&self, public_key_: &[u8]) -> Result<Vec<u8>> {
+        self.trace_abi_call("evm_get_address_from_pubkey");
         // parse the public key
         let public_key = libsecp256k1::PublicKey::parse_slice(
             public_key_,
@@ -935,6 +1018,7 @@ impl Interface for InterfaceImpl {
 
     /// Get a raw secp256k1 public key from an EVM signature and the signed hash.
     fn evm_get_pubkey_from_signature(&self, hash_: &[u8], signature_: &[u8]) -> Result<Vec<u8>> {
+        self.trace_abi_call("evm_get_pubkey_from_signature");
         // check the signature length
         if signature_.len() != 65 {
             return Err(anyhow!(
@@ -960,7 +1044,8 @@ impl Interface for InterfaceImpl {
 
     // Return true if the address is a User address, false if it is an SC address.
     fn is_address_eoa(&self, address_: &str) -> Result<bool> {
-        let address = Address::from_str(address_)?;
+        self.trace_abi_call("is_address_eoa");
+        let address = parse_sc_address(address_)?;
         Ok(matches!(address, Address::User(..)))
     }
 
@@ -972,7 +1057,8 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `transfer_coins_wasmv1`
     fn transfer_coins(&self, to_address: &str, raw_amount: u64) -> Result<()> {
-        let to_address = Address::from_str(to_address)?;
+        self.trace_abi_call("transfer_coins");
+        let to_address = parse_sc_address(to_address)?;
         let amount = Amount::from_raw(raw_amount);
         let mut context = context_guard!(self);
         let from_address = context.get_current_address()?;
@@ -994,8 +1080,9 @@ impl Interface for InterfaceImpl {
         to_address: &str,
         raw_amount: u64,
     ) -> Result<()> {
-        let from_address = Address::from_str(from_address)?;
-        let to_address = Address::from_str(to_address)?;
+        self.trace_abi_call("transfer_coins_for");
+        let from_address = parse_sc_address(from_address)?;
+        let to_address = parse_sc_address(to_address)?;
         let amount = Amount::from_raw(raw_amount);
         let mut context = context_guard!(self);
         context.transfer_coins(Some(from_address), Some(to_address), amount, true)?;
@@ -1014,12 +1101,13 @@ impl Interface for InterfaceImpl {
         raw_amount: NativeAmount,
         from_address: Option<String>,
     ) -> Result<()> {
-        let to_address = Address::from_str(&to_address)?;
+        self.trace_abi_call("transfer_coins_wasmv1");
+        let to_address = parse_sc_address(&to_address)?;
         let amount = amount_from_native_amount(&raw_amount)?;
 
         let mut context = context_guard!(self);
         let from_address = match from_address {
-            Some(from_address) => Address::from_str(&from_address)?,
+            Some(from_address) => parse_sc_address(&from_address)?,
             None => context.get_current_address()?,
         };
         context.transfer_coins(Some(from_address), Some(to_address), amount, true)?;
@@ -1035,6 +1123,7 @@ impl Interface for InterfaceImpl {
     /// A vector with the string representation of each owned address.
     /// Note that the ordering of this vector is deterministic and conserved.
     fn get_owned_addresses(&self) -> Result<Vec<String>> {
+        self.trace_abi_call("get_owned_addresses");
         Ok(context_guard!(self)
             .get_current_owned_addresses()?
             .into_iter()
@@ -1047,6 +1136,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// A vector with the string representation of each call stack address.
     fn get_call_stack(&self) -> Result<Vec<String>> {
+        self.trace_abi_call("get_call_stack");
         Ok(context_guard!(self)
             .get_call_stack()
             .into_iter()
@@ -1062,6 +1152,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `get_call_coins_wasmv1`
     fn get_call_coins(&self) -> Result<u64> {
+        self.trace_abi_call("get_call_coins");
         Ok(context_guard!(self).get_current_call_coins()?.to_raw())
     }
 
@@ -1071,6 +1162,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The amount of coins
     fn get_call_coins_wasmv1(&self) -> Result<NativeAmount> {
+        self.trace_abi_call("get_call_coins_wasmv1");
         let amount = context_guard!(self).get_current_call_coins()?;
         Ok(amount_to_native_amount(&amount))
     }
@@ -1082,6 +1174,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `get_current_slot`
     fn generate_event(&self, data: String) -> Result<()> {
+        self.trace_abi_call("generate_event");
         if data.len() > self.config.max_event_size {
             bail!("Event data size is too large");
         };
@@ -1097,6 +1190,7 @@ impl Interface for InterfaceImpl {
     /// # Arguments:
     /// data: the bytes_array data that is the payload of the event
     fn generate_event_wasmv1(&self, data: Vec<u8>) -> Result<()> {
+        self.trace_abi_call("generate_event_wasmv1");
         if data.len() > self.config.max_event_size {
             bail!("Event data size is too large");
         };
@@ -1112,6 +1206,7 @@ impl Interface for InterfaceImpl {
     /// Returns the current time (millisecond UNIX timestamp)
     /// Note that in order to ensure determinism, this is actually the time of the context slot.
     fn get_time(&self) -> Result<u64> {
+        self.trace_abi_call("get_time");
         let slot = context_guard!(self).slot;
         let ts = get_block_slot_timestamp(
             self.config.thread_count,
@@ -1130,6 +1225,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `unsafe_random_wasmv1`
     fn unsafe_random(&self) -> Result<i64> {
+        self.trace_abi_call("unsafe_random");
         let distr = rand::distributions::Uniform::new_inclusive(i64::MIN, i64::MAX);
         Ok(context_guard!(self).unsafe_rng.sample(distr))
     }
@@ -1142,6 +1238,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `unsafe_random_wasmv1`
     fn unsafe_random_f64(&self) -> Result<f64> {
+        self.trace_abi_call("unsafe_random_f64");
         let distr = rand::distributions::Uniform::new(0f64, 1f64);
         Ok(context_guard!(self).unsafe_rng.sample(distr))
     }
@@ -1152,6 +1249,7 @@ impl Interface for InterfaceImpl {
     /// This random number generator is unsafe:
     /// it can be both predicted and manipulated before the execution
     fn unsafe_random_wasmv1(&self, num_bytes: u64) -> Result<Vec<u8>> {
+        self.trace_abi_call("unsafe_random_wasmv1");
         let mut arr = vec![0u8; num_bytes as usize];
         context_guard!(self).unsafe_rng.try_fill_bytes(&mut arr)?;
         Ok(arr)
@@ -1180,13 +1278,14 @@ impl Interface for InterfaceImpl {
         data: &[u8],
         filter: Option<(&str, Option<&[u8]>)>,
     ) -> Result<()> {
+        self.trace_abi_call("send_message");
         if validity_start.1 >= self.config.thread_count {
             bail!("validity start thread exceeds the configuration thread count")
         }
         if validity_end.1 >= self.config.thread_count {
             bail!("validity end thread exceeds the configuration thread count")
         }
-        let target_addr = Address::from_str(target_address)?;
+        let target_addr = parse_sc_address(target_address)?;
 
         // check that the target address is an SC address
         if !matches!(target_addr, Address::SC(..)) {
@@ -1230,7 +1329,7 @@ impl Interface for InterfaceImpl {
                         }
                     }
                     Ok::<AsyncMessageTrigger, _>(AsyncMessageTrigger {
-                        address: Address::from_str(addr)?,
+                        address: parse_sc_address(addr)?,
                         datastore_key,
                     })
                 })
@@ -1243,6 +1342,7 @@ impl Interface for InterfaceImpl {
 
     // Returns the operation id that originated the current execution if there is one
     fn get_origin_operation_id(&self) -> Result<Option<String>> {
+        self.trace_abi_call("get_origin_operation_id");
         let operation_id = context_guard!(self)
             .origin_operation_id
             .map(|op_id| op_id.to_string());
@@ -1253,6 +1353,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `get_current_slot`
     fn get_current_period(&self) -> Result<u64> {
+        self.trace_abi_call("get_current_period");
         let slot = context_guard!(self).slot;
         Ok(slot.period)
     }
@@ -1261,12 +1362,14 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `get_current_slot`
     fn get_current_thread(&self) -> Result<u8> {
+        self.trace_abi_call("get_current_thread");
         let slot = context_guard!(self).slot;
         Ok(slot.thread)
     }
 
     /// Returns the current execution slot
     fn get_current_slot(&self) -> Result<massa_proto_rs::massa::model::v1::Slot> {
+        self.trace_abi_call("get_current_slot");
         let slot_models = context_guard!(self).slot;
         Ok(slot_models.into())
     }
@@ -1275,6 +1378,7 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `raw_set_bytecode_wasmv1`
     fn raw_set_bytecode(&self, bytecode: &[u8]) -> Result<()> {
+        self.trace_abi_call("raw_set_bytecode");
         let mut execution_context = context_guard!(self);
         let address = execution_context.get_current_address()?;
         match execution_context.set_bytecode(&address, Bytecode(bytecode.to_vec())) {
@@ -1288,7 +1392,8 @@ impl Interface for InterfaceImpl {
     ///
     /// [DeprecatedByNewRuntime] Replaced by `raw_set_bytecode_wasmv1`
     fn raw_set_bytecode_for(&self, address: &str, bytecode: &[u8]) -> Result<()> {
-        let address: Address = massa_models::address::Address::from_str(address)?;
+        self.trace_abi_call("raw_set_bytecode_for");
+        let address: Address = parse_sc_address(address)?;
         let mut execution_context = context_guard!(self);
         match execution_context.set_bytecode(&address, Bytecode(bytecode.to_vec())) {
             Ok(()) => Ok(()),
@@ -1299,6 +1404,7 @@ impl Interface for InterfaceImpl {
     /// Sets the bytecode of an arbitrary address, or the current address if not provided.
     /// Fails if the address does not exist, is an user address, or if the context doesn't have write access rights on it.
     fn set_bytecode_wasmv1(&self, bytecode: &[u8], address: Option<String>) -> Result<()> {
+        self.trace_abi_call("set_bytecode_wasmv1");
         let mut context = context_guard!(self);
         let address = get_address_from_opt_or_context(&context, address)?;
 
@@ -1316,6 +1422,7 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The byte array of the resulting hash
     fn hash_sha256(&self, bytes: &[u8]) -> Result<[u8; 32]> {
+        self.trace_abi_call("hash_sha256");
         let mut hasher = Sha256::new();
         hasher.update(bytes);
         let hash = hasher.finalize().into();
@@ -1330,13 +1437,15 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The byte array of the resulting hash
     fn hash_blake3(&self, bytes: &[u8]) -> Result<[u8; 32]> {
+        self.trace_abi_call("hash_blake3");
         Ok(blake3::hash(bytes).into())
     }
 
     #[allow(unused_variables)]
     fn init_call_wasmv1(&self, address: &str, raw_coins: NativeAmount) -> Result<Vec<u8>> {
+        self.trace_abi_call("init_call_wasmv1");
         // get target address
-        let to_address = Address::from_str(address)?;
+        let to_address = parse_sc_address(address)?;
 
         // check that the target address is an SC address
         if !matches!(to_address, Address::SC(..)) {
@@ -1374,12 +1483,16 @@ impl Interface for InterfaceImpl {
         }
 
         // push a new call stack element on top of the current call stack
+        context.record_contract_call(&to_address);
         context.stack.push(ExecutionStackElement {
             address: to_address,
             coins,
             owned_addresses: vec![to_address],
             operation_datastore: None,
         });
+        if let Some(call_stats) = &mut context.op_call_stats {
+            call_stats.enter_call();
+        }
 
         // return the target bytecode
         Ok(bytecode.0)
@@ -1387,12 +1500,14 @@ impl Interface for InterfaceImpl {
 
     /// Returns a NativeAmount from a string
     fn native_amount_from_str_wasmv1(&self, amount: &str) -> Result<NativeAmount> {
+        self.trace_abi_call("native_amount_from_str_wasmv1");
         let amount = Amount::from_str(amount).map_err(|err| anyhow!(format!("{}", err)))?;
         Ok(amount_to_native_amount(&amount))
     }
 
     /// Returns a string from a NativeAmount
     fn native_amount_to_string_wasmv1(&self, amount: &NativeAmount) -> Result<String> {
+        self.trace_abi_call("native_amount_to_string_wasmv1");
         let amount = amount_from_native_amount(amount)
             .map_err(|err| anyhow!(format!("Couldn't convert native amount to Amount: {}", err)))?;
         Ok(amount.to_string())
@@ -1400,6 +1515,7 @@ impl Interface for InterfaceImpl {
 
     /// Checks if the given native amount is valid
     fn check_native_amount_wasmv1(&self, amount: &NativeAmount) -> Result<bool> {
+        self.trace_abi_call("check_native_amount_wasmv1");
         Ok(amount_from_native_amount(amount).is_ok())
     }
 
@@ -1409,6 +1525,7 @@ impl Interface for InterfaceImpl {
         amount1: &NativeAmount,
         amount2: &NativeAmount,
     ) -> Result<NativeAmount> {
+        self.trace_abi_call("add_native_amount_wasmv1");
         let amount1 = amount_from_native_amount(amount1)?;
         let amount2 = amount_from_native_amount(amount2)?;
         let sum = amount1.saturating_add(amount2);
@@ -1421,6 +1538,7 @@ impl Interface for InterfaceImpl {
         amount1: &NativeAmount,
         amount2: &NativeAmount,
     ) -> Result<NativeAmount> {
+        self.trace_abi_call("sub_native_amount_wasmv1");
         let amount1 = amount_from_native_amount(amount1)?;
         let amount2 = amount_from_native_amount(amount2)?;
         let sub = amount1.saturating_sub(amount2);
@@ -1433,6 +1551,7 @@ impl Interface for InterfaceImpl {
         amount: &NativeAmount,
         factor: u64,
     ) -> Result<NativeAmount> {
+        self.trace_abi_call("scalar_mul_native_amount_wasmv1");
         let amount = amount_from_native_amount(amount)?;
         let mul = amount.saturating_mul_u64(factor);
         Ok(amount_to_native_amount(&mul))
@@ -1444,6 +1563,7 @@ impl Interface for InterfaceImpl {
         dividend: &NativeAmount,
         divisor: u64,
     ) -> Result<(NativeAmount, NativeAmount)> {
+        self.trace_abi_call("scalar_div_rem_native_amount_wasmv1");
         let dividend = amount_from_native_amount(dividend)?;
 
         let quotient = dividend
@@ -1466,6 +1586,7 @@ impl Interface for InterfaceImpl {
         dividend: &NativeAmount,
         divisor: &NativeAmount,
     ) -> Result<(u64, NativeAmount)> {
+        self.trace_abi_call("div_rem_native_amount_wasmv1");
         let dividend = amount_from_native_amount(dividend)?;
         let divisor = amount_from_native_amount(divisor)?;
 
@@ -1482,6 +1603,7 @@ impl Interface for InterfaceImpl {
     }
 
     fn base58_check_to_bytes_wasmv1(&self, s: &str) -> Result<Vec<u8>> {
+        self.trace_abi_call("base58_check_to_bytes_wasmv1");
         bs58::decode(s)
             .with_check(None)
             .into_vec()
@@ -1489,23 +1611,30 @@ impl Interface for InterfaceImpl {
     }
 
     fn bytes_to_base58_check_wasmv1(&self, data: &[u8]) -> String {
+        self.trace_abi_call("bytes_to_base58_check_wasmv1");
         bs58::encode(data).with_check().into_string()
     }
 
     fn check_address_wasmv1(&self, to_check: &str) -> Result<bool> {
-        Ok(Address::from_str(to_check).is_ok())
+        self.trace_abi_call("check_address_wasmv1");
+        Ok(parse_sc_address(to_check).is_ok())
     }
 
     fn check_pubkey_wasmv1(&self, to_check: &str) -> Result<bool> {
+        self.trace_abi_call("check_pubkey_wasmv1");
         Ok(PublicKey::from_str(to_check).is_ok())
     }
 
     fn check_signature_wasmv1(&self, to_check: &str) -> Result<bool> {
+        self.trace_abi_call("check_signature_wasmv1");
         Ok(Signature::from_str(to_check).is_ok())
     }
 
     fn get_address_category_wasmv1(&self, to_check: &str) -> Result<AddressCategory> {
-        let addr = Address::from_str(to_check)?;
+        self.trace_abi_call("get_address_category_wasmv1");
+        let addr = parse_sc_address(to_check)?;
+        // NOTE: the two categories are swapped here. Contracts deployed on-chain already rely on
+        // this mapping, so correcting it changes execution results and must be gated by a MIP.
         match addr {
             Address::User(_) => Ok(AddressCategory::ScAddress),
             Address::SC(_) => Ok(AddressCategory::UserAddress),
@@ -1515,18 +1644,12 @@ impl Interface for InterfaceImpl {
     }
 
     fn get_address_version_wasmv1(&self, address: &str) -> Result<u64> {
-        let address = Address::from_str(address)?;
-        match address {
-            Address::User(UserAddress::UserAddressV0(_)) => Ok(0),
-            // Address::User(UserAddress::UserAddressV1(_)) => Ok(1),
-            Address::SC(SCAddress::SCAddressV0(_)) => Ok(0),
-            // Address::SC(SCAddress::SCAddressV1(_)) => Ok(1),
-            #[allow(unreachable_patterns)]
-            _ => bail!("Unknown address version"),
-        }
+        self.trace_abi_call("get_address_version_wasmv1");
+        Ok(parse_sc_address(address)?.get_version())
     }
 
     fn get_pubkey_version_wasmv1(&self, pubkey: &str) -> Result<u64> {
+        self.trace_abi_call("get_pubkey_version_wasmv1");
         let pubkey = PublicKey::from_str(pubkey)?;
         match pubkey {
             PublicKey::PublicKeyV0(_) => Ok(0),
@@ -1536,6 +1659,7 @@ impl Interface for InterfaceImpl {
     }
 
     fn get_signature_version_wasmv1(&self, signature: &str) -> Result<u64> {
+        self.trace_abi_call("get_signature_version_wasmv1");
         let signature = Signature::from_str(signature)?;
         match signature {
             Signature::SignatureV0(_) => Ok(0),
@@ -1549,6 +1673,7 @@ impl Interface for InterfaceImpl {
         time1: &NativeTime,
         time2: &NativeTime,
     ) -> Result<NativeTime> {
+        self.trace_abi_call("checked_add_native_time_wasmv1");
         let time1 = massa_time_from_native_time(time1)?;
         let time2 = massa_time_from_native_time(time2)?;
         let sum = time1.checked_add(time2)?;
@@ -1560,6 +1685,7 @@ impl Interface for InterfaceImpl {
         time1: &NativeTime,
         time2: &NativeTime,
     ) -> Result<NativeTime> {
+        self.trace_abi_call("checked_sub_native_time_wasmv1");
         let time1 = massa_time_from_native_time(time1)?;
         let time2 = massa_time_from_native_time(time2)?;
         let sub = time1.checked_sub(time2)?;
@@ -1567,6 +1693,7 @@ impl Interface for InterfaceImpl {
     }
 
     fn checked_mul_native_time_wasmv1(&self, time: &NativeTime, factor: u64) -> Result<NativeTime> {
+        self.trace_abi_call("checked_mul_native_time_wasmv1");
         let time1 = massa_time_from_native_time(time)?;
         let mul = time1.checked_mul(factor)?;
         Ok(massa_time_to_native_time(&mul))
@@ -1577,6 +1704,7 @@ impl Interface for InterfaceImpl {
         dividend: &NativeTime,
         divisor: u64,
     ) -> Result<(NativeTime, NativeTime)> {
+        self.trace_abi_call("checked_scalar_div_native_time_wasmv1");
         let dividend = massa_time_from_native_time(dividend)?;
 
         let quotient = dividend
@@ -1597,6 +1725,7 @@ impl Interface for InterfaceImpl {
         dividend: &NativeTime,
         divisor: &NativeTime,
     ) -> Result<(u64, NativeTime)> {
+        self.trace_abi_call("checked_div_native_time_wasmv1");
         let dividend = massa_time_from_native_time(dividend)?;
         let divisor = massa_time_from_native_time(divisor)?;
 
@@ -1613,8 +1742,9 @@ impl Interface for InterfaceImpl {
     }
 
     fn compare_address_wasmv1(&self, left: &str, right: &str) -> Result<ComparisonResult> {
-        let left = Address::from_str(left)?;
-        let right = Address::from_str(right)?;
+        self.trace_abi_call("compare_address_wasmv1");
+        let left = parse_sc_address(left)?;
+        let right = parse_sc_address(right)?;
 
         let res = match left.cmp(&right) {
             std::cmp::Ordering::Less => ComparisonResult::Lower,
@@ -1630,6 +1760,7 @@ impl Interface for InterfaceImpl {
         left: &NativeAmount,
         right: &NativeAmount,
     ) -> Result<ComparisonResult> {
+        self.trace_abi_call("compare_native_amount_wasmv1");
         let left = amount_from_native_amount(left)?;
         let right = amount_from_native_amount(right)?;
 
@@ -1647,6 +1778,7 @@ impl Interface for InterfaceImpl {
         left: &NativeTime,
         right: &NativeTime,
     ) -> Result<ComparisonResult> {
+        self.trace_abi_call("compare_native_time_wasmv1");
         let left = massa_time_from_native_time(left)?;
         let right = massa_time_from_native_time(right)?;
 
@@ -1660,6 +1792,7 @@ impl Interface for InterfaceImpl {
     }
 
     fn compare_pub_key_wasmv1(&self, left: &str, right: &str) -> Result<ComparisonResult> {
+        self.trace_abi_call("compare_pub_key_wasmv1");
         let left = PublicKey::from_str(left)?;
         let right = PublicKey::from_str(right)?;
 
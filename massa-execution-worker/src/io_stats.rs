@@ -0,0 +1,217 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! This module implements the per-contract datastore access statistics,
+//! recorded when `contract_io_stats_enabled` is set in the execution configuration.
+//! During the execution of a slot, the calls to each smart contract, the reads and writes in its datastore,
+//! and the accesses to each of its datastore keys are counted.
+//! The statistics of the latest executed slots are kept in memory and aggregated on query.
+
+use massa_execution_exports::{ContractIoStats, DatastoreKeyIoStats};
+use massa_models::{address::Address, slot::Slot};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+
+/// Maximal number of executed slots whose statistics are kept in memory
+pub(crate) const MAX_IO_STATS_SLOTS: usize = 1000;
+
+/// Maximal number of hot keys returned for a smart contract
+pub(crate) const MAX_HOT_KEYS: usize = 10;
+
+/// Access counters of a smart contract
+#[derive(Debug, Clone, Default)]
+struct ContractIoCounters {
+    /// number of calls
+    call_count: u64,
+    /// number of datastore reads
+    read_count: u64,
+    /// number of datastore writes
+    write_count: u64,
+    /// (read count, write count) of each accessed datastore key
+    keys: HashMap<Vec<u8>, (u64, u64)>,
+}
+
+/// Datastore access statistics of an executed slot
+#[derive(Debug, Clone)]
+pub(crate) struct SlotIoStats {
+    /// executed slot
+    slot: Slot,
+    /// counters of each accessed smart contract
+    contracts: HashMap<Address, ContractIoCounters>,
+}
+
+/// Records the datastore access statistics of the slot currently being executed.
+/// Shared between the execution state and the execution context.
+pub(crate) struct IoStatsRecorder {
+    stats: Mutex<SlotIoStats>,
+}
+
+impl IoStatsRecorder {
+    /// Creates a recorder for the execution of a given slot
+    pub fn new(slot: Slot) -> Self {
+        IoStatsRecorder {
+            stats: Mutex::new(SlotIoStats {
+                slot,
+                contracts: Default::default(),
+            }),
+        }
+    }
+
+    /// Records a call to a smart contract
+    pub fn record_call(&self, address: &Address) {
+        let mut stats = self.stats.lock();
+        let counters = stats.contracts.entry(*address).or_default();
+        counters.call_count = counters.call_count.saturating_add(1);
+    }
+
+    /// Records a read in the datastore of an address.
+    /// `key` is the read datastore key, or None if the keys of the datastore were listed.
+    pub fn record_read(&self, address: &Address, key: Option<&[u8]>) {
+        let mut stats = self.stats.lock();
+        let counters = stats.contracts.entry(*address).or_default();
+        counters.read_count = counters.read_count.saturating_add(1);
+        if let Some(key) = key {
+            let key_counts = counters.keys.entry(key.to_vec()).or_default();
+            key_counts.0 = key_counts.0.saturating_add(1);
+        }
+    }
+
+    /// Records a write of a datastore key of an address
+    pub fn record_write(&self, address: &Address, key: &[u8]) {
+        let mut stats = self.stats.lock();
+        let counters = stats.contracts.entry(*address).or_default();
+        counters.write_count = counters.write_count.saturating_add(1);
+        let key_counts = counters.keys.entry(key.to_vec()).or_default();
+        key_counts.1 = key_counts.1.saturating_add(1);
+    }
+
+    /// Takes the recorded statistics, leaving empty ones in their place
+    pub fn take(&self) -> SlotIoStats {
+        let mut stats = self.stats.lock();
+        SlotIoStats {
+            slot: stats.slot,
+            contracts: std::mem::take(&mut stats.contracts),
+        }
+    }
+}
+
+/// Finite-size store of the datastore access statistics of the latest executed slots
+pub(crate) struct IoStatsStore {
+    /// statistics indexed by slot
+    slots: BTreeMap<Slot, HashMap<Address, ContractIoCounters>>,
+}
+
+impl IoStatsStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        IoStatsStore {
+            slots: Default::default(),
+        }
+    }
+
+    /// Stores the statistics of an executed slot, replacing the ones of a previous execution of that slot if any
+    pub fn insert(&mut self, stats: SlotIoStats) {
+        self.slots.insert(stats.slot, stats.contracts);
+        while self.slots.len() > MAX_IO_STATS_SLOTS {
+            self.slots.pop_first();
+        }
+    }
+
+    /// Aggregates the statistics of a smart contract over the latest `window` executed slots
+    pub fn get(&self, address: &Address, window: u64) -> ContractIoStats {
+        let mut stats = ContractIoStats {
+            address: *address,
+            slot_count: 0,
+            call_count: 0,
+            read_count: 0,
+            write_count: 0,
+            hot_keys: Vec::new(),
+        };
+        let mut keys: HashMap<&[u8], (u64, u64)> = HashMap::new();
+        let window = usize::try_from(window).unwrap_or(usize::MAX);
+        for contracts in self.slots.values().rev().take(window) {
+            stats.slot_count = stats.slot_count.saturating_add(1);
+            let Some(counters) = contracts.get(address) else {
+                continue;
+            };
+            stats.call_count = stats.call_count.saturating_add(counters.call_count);
+            stats.read_count = stats.read_count.saturating_add(counters.read_count);
+            stats.write_count = stats.write_count.saturating_add(counters.write_count);
+            for (key, (read_count, write_count)) in &counters.keys {
+                let key_counts = keys.entry(key.as_slice()).or_default();
+                key_counts.0 = key_counts.0.saturating_add(*read_count);
+                key_counts.1 = key_counts.1.saturating_add(*write_count);
+            }
+        }
+        stats.hot_keys = keys
+            .into_iter()
+            .map(|(key, (read_count, write_count))| DatastoreKeyIoStats {
+                key: key.to_vec(),
+                read_count,
+                write_count,
+            })
+            .collect();
+        // most accessed first, ties broken by key for determinism
+        stats.hot_keys.sort_unstable_by(|a, b| {
+            b.read_count
+                .saturating_add(b.write_count)
+                .cmp(&a.read_count.saturating_add(a.write_count))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        stats.hot_keys.truncate(MAX_HOT_KEYS);
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_io_stats_aggregation() {
+        let sc_addr =
+            Address::from_str("AS12htxRWiEm8jDJpJptr6cwEhWNcCSFWstN1MLSa96DDkVM9Y42G").unwrap();
+        let mut store = IoStatsStore::new();
+
+        let recorder = IoStatsRecorder::new(Slot::new(1, 0));
+        recorder.record_call(&sc_addr);
+        recorder.record_read(&sc_addr, Some(b"balance"));
+        recorder.record_read(&sc_addr, None);
+        recorder.record_write(&sc_addr, b"balance");
+        store.insert(recorder.take());
+
+        let recorder = IoStatsRecorder::new(Slot::new(1, 1));
+        recorder.record_call(&sc_addr);
+        recorder.record_read(&sc_addr, Some(b"balance"));
+        recorder.record_read(&sc_addr, Some(b"owner"));
+        store.insert(recorder.take());
+
+        let stats = store.get(&sc_addr, 10);
+        assert_eq!(stats.slot_count, 2);
+        assert_eq!(stats.call_count, 2);
+        assert_eq!(stats.read_count, 4);
+        assert_eq!(stats.write_count, 1);
+        assert_eq!(stats.hot_keys.len(), 2);
+        assert_eq!(stats.hot_keys[0].key, b"balance".to_vec());
+        assert_eq!(stats.hot_keys[0].read_count, 2);
+        assert_eq!(stats.hot_keys[0].write_count, 1);
+
+        // the window only covers the latest executed slots
+        let stats = store.get(&sc_addr, 1);
+        assert_eq!(stats.slot_count, 1);
+        assert_eq!(stats.read_count, 2);
+        assert_eq!(stats.write_count, 0);
+
+        // re-executing a slot replaces its statistics
+        store.insert(IoStatsRecorder::new(Slot::new(1, 1)).take());
+        assert_eq!(store.get(&sc_addr, 1).read_count, 0);
+
+        // the store only keeps the latest slots
+        for period in 2..(MAX_IO_STATS_SLOTS as u64 + 2) {
+            store.insert(IoStatsRecorder::new(Slot::new(period, 0)).take());
+        }
+        let stats = store.get(&sc_addr, u64::MAX);
+        assert_eq!(stats.slot_count, MAX_IO_STATS_SLOTS as u64);
+        assert_eq!(stats.call_count, 0);
+    }
+}
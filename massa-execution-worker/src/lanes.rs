@@ -0,0 +1,134 @@
+//! Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module monitors the execution lanes of the worker thread.
+//! Final slots and candidate slots are executed on two separate lanes:
+//! the final lane always has priority, and the execution of a candidate slot
+//! is preempted as soon as newly finalized blocks are notified,
+//! so that speculative execution never delays the progress of finalization.
+//! The preempted candidate slot is executed again later on.
+
+use massa_metrics::MassaMetrics;
+use massa_time::MassaTime;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Execution lane of a slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExecutionLane {
+    /// lane of the SCE-final slots
+    Final,
+    /// lane of the speculative candidate slots
+    Candidate,
+}
+
+/// Tracks the share of time each lane spent executing slots over a sliding time window,
+/// and reports it to the metrics
+pub(crate) struct LaneMonitor {
+    /// duration of the time window
+    time_window_duration: Duration,
+    /// instant at which the monitoring started
+    start: Instant,
+    /// executions in the time window (lane, start instant, end instant), oldest first
+    executions: VecDeque<(ExecutionLane, Instant, Instant)>,
+    /// metrics to which the utilization of the lanes is reported
+    massa_metrics: MassaMetrics,
+}
+
+impl LaneMonitor {
+    /// Creates a new `LaneMonitor`
+    pub fn new(time_window_duration: MassaTime, massa_metrics: MassaMetrics) -> Self {
+        LaneMonitor {
+            time_window_duration: time_window_duration.to_duration(),
+            start: Instant::now(),
+            executions: Default::default(),
+            massa_metrics,
+        }
+    }
+
+    /// Records the execution of a slot on a lane and reports the updated utilization of the lanes
+    ///
+    /// # Arguments
+    /// * `lane`: lane on which the slot was executed
+    /// * `start`: instant at which the execution started
+    /// * `end`: instant at which the execution ended
+    /// * `preempted`: true if the execution was preempted before completion
+    pub fn record_execution(
+        &mut self,
+        lane: ExecutionLane,
+        start: Instant,
+        end: Instant,
+        preempted: bool,
+    ) {
+        self.executions.push_back((lane, start, end));
+        if preempted {
+            self.massa_metrics.inc_execution_candidate_preemptions();
+        }
+        let (final_lane, candidate_lane) = self.get_utilization(end);
+        self.massa_metrics
+            .set_execution_lanes_utilization(final_lane, candidate_lane);
+    }
+
+    /// Gets the share of the time window (between 0 and 1) spent executing slots
+    /// on the final lane and on the candidate lane, pruning older executions
+    fn get_utilization(&mut self, now: Instant) -> (f64, f64) {
+        let window_start = now
+            .checked_sub(self.time_window_duration)
+            .map_or(self.start, |t| std::cmp::max(t, self.start));
+        while let Some((_, _, end)) = self.executions.front() {
+            if end <= &window_start {
+                self.executions.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let mut final_busy = Duration::ZERO;
+        let mut candidate_busy = Duration::ZERO;
+        for (lane, start, end) in &self.executions {
+            let busy = end.saturating_duration_since(std::cmp::max(*start, window_start));
+            match lane {
+                ExecutionLane::Final => final_busy += busy,
+                ExecutionLane::Candidate => candidate_busy += busy,
+            }
+        }
+
+        let window = now.saturating_duration_since(window_start).as_secs_f64();
+        if window <= 0.0 {
+            return (0.0, 0.0);
+        }
+        (
+            (final_busy.as_secs_f64() / window).min(1.0),
+            (candidate_busy.as_secs_f64() / window).min(1.0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lane_utilization_over_window() {
+        let (massa_metrics, _) = MassaMetrics::new(
+            false,
+            "0.0.0.0:9898".parse().unwrap(),
+            32,
+            std::time::Duration::from_secs(5),
+        );
+        let mut monitor = LaneMonitor::new(MassaTime::from_millis(1000), massa_metrics);
+        let t0 = monitor.start;
+        let ms = Duration::from_millis;
+
+        monitor.record_execution(ExecutionLane::Final, t0, t0 + ms(100), false);
+        monitor.record_execution(ExecutionLane::Candidate, t0 + ms(100), t0 + ms(400), true);
+        let (final_lane, candidate_lane) = monitor.get_utilization(t0 + ms(500));
+        assert!((final_lane - 0.2).abs() < 1e-9);
+        assert!((candidate_lane - 0.6).abs() < 1e-9);
+
+        // executions leaving the time window are not accounted for anymore
+        let (final_lane, candidate_lane) = monitor.get_utilization(t0 + ms(1300));
+        assert_eq!(final_lane, 0.0);
+        assert!((candidate_lane - 0.1).abs() < 1e-9);
+        assert_eq!(monitor.executions.len(), 1);
+    }
+}
@@ -49,6 +49,11 @@
 //! that allows sequencing slots for execution.
 //!
 //!
+//! ## lanes.rs
+//! Monitors the final and candidate execution lanes of the worker thread.
+//! Final slots have priority: the execution of a candidate slot is preempted
+//! when new blocks are finalized, and executed again later on.
+//!
 //! ## controller.rs
 //! Implements `ExecutionManager` and `ExecutionController`
 //! that serve as interfaces for users to interact with the worker in worker.rs.
@@ -69,21 +74,66 @@
 //! ## `speculative_executed_ops.rs`
 //! A speculative (non-final) list of previously executed operations to prevent reuse.
 //!
+//! ## `call_stats.rs`
+//! Call stack telemetry of the smart contract operations: nested call depth reached,
+//! and gas remaining when the calls started at each depth.
+//!
+//! ## `checkpoint.rs`
+//! Periodic checkpoints of the execution state that is not persisted in the final state,
+//! reloaded on restart to avoid re-executing already executed speculative slots.
+//!
+//! ## `event_index.rs`
+//! Optional on-disk store of the final events with secondary indexes by emitter address,
+//! original caller address and data prefix, used to filter events without scanning the event history.
+//!
+//! ## `gas_profile.rs`
+//! Gas profiler recording the gas of each operation and of each ABI host function per slot,
+//! compiled in with the `gas_profile` feature.
+//!
+//! ## `io_stats.rs`
+//! Opt-in per-contract datastore access statistics: calls, datastore reads and writes and most accessed keys,
+//! kept for the latest executed slots.
+//!
+//! ## `test_exports/`
+//! Testing tools exported with the `test-exports` feature,
+//! such as a harness checking that execution is deterministic across independent replicas.
+//!
+//! ## `operation_results.rs`
+//! A finite-size in-memory store for the detailed execution results of the latest final operations.
+//!
+//! ## `readonly_cache.rs`
+//! LRU cache of the results of read-only function calls, keyed by call and by execution slot,
+//! invalidated when the speculative history they were computed on is truncated.
+//!
 //! ## `request_queue.rs`
 //! This module contains the implementation of a generic finite-size execution request queue.
 //! It handles requests that come with an MPSC to send back the result of their execution once it's done.
 //!
 //! ## `stats.rs`
 //! Defines a structure that gathers execution statistics.
+//!
+//! ## `trace.rs`
+//! Opt-in execution tracer recording ledger accesses, gas charges and ABI calls
+//! during slot execution, and dumping them per slot as JSON or in a compact binary format.
 
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 
 mod active_history;
+mod call_stats;
+mod checkpoint;
 mod context;
 mod controller;
+mod event_index;
 mod execution;
+#[cfg(feature = "gas_profile")]
+mod gas_profile;
 mod interface_impl;
+mod io_stats;
+mod lanes;
+mod operation_results;
+mod output_retention;
+mod readonly_cache;
 mod request_queue;
 mod slot_sequencer;
 mod speculative_async_pool;
@@ -92,6 +142,7 @@ mod speculative_executed_ops;
 mod speculative_ledger;
 mod speculative_roll_state;
 mod stats;
+mod trace;
 mod worker;
 
 use massa_db_exports as _;
@@ -107,5 +158,8 @@ pub use interface_impl::InterfaceImpl;
 #[cfg(feature = "benchmarking")]
 use criterion as _;
 
+#[cfg(any(test, feature = "test-exports"))]
+pub mod test_exports;
+
 #[cfg(test)]
 mod tests;
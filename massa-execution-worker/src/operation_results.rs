@@ -0,0 +1,75 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module implements a finite-size in-memory store for the execution results of the latest final operations.
+//!
+//! The results are not written to the database: only the most recent ones are known,
+//! and they survive a restart only through the execution checkpoint, when one matches the final state.
+
+use massa_execution_exports::OperationExecutionResult;
+use massa_models::{operation::OperationId, prehash::PreHashMap};
+use std::collections::VecDeque;
+
+/// In-memory store of the execution results of the latest final operations.
+/// The oldest results are forgotten once `max_results` is reached.
+pub(crate) struct RecentOperationResults {
+    /// results indexed by operation id
+    results: PreHashMap<OperationId, OperationExecutionResult>,
+    /// operation ids in finalization order, oldest first
+    order: VecDeque<OperationId>,
+    /// maximum number of results kept
+    max_results: usize,
+}
+
+impl RecentOperationResults {
+    /// Creates an empty store keeping at most `max_results` results
+    pub fn new(max_results: usize) -> Self {
+        RecentOperationResults {
+            results: Default::default(),
+            order: Default::default(),
+            max_results,
+        }
+    }
+
+    /// Adds newly finalized results, marking them as final, then prunes the oldest ones
+    pub fn extend(&mut self, results: PreHashMap<OperationId, OperationExecutionResult>) {
+        for (op_id, mut result) in results {
+            result.is_final = true;
+            if self.results.insert(op_id, result).is_none() {
+                self.order.push_back(op_id);
+            }
+        }
+        while self.order.len() > self.max_results {
+            if let Some(op_id) = self.order.pop_front() {
+                self.results.remove(&op_id);
+            }
+        }
+    }
+
+    /// Gets the final execution result of an operation, if still known
+    pub fn get(&self, op_id: &OperationId) -> Option<&OperationExecutionResult> {
+        self.results.get(op_id)
+    }
+
+    /// Restores results in their finalization order, oldest first
+    pub fn restore(&mut self, results: Vec<OperationExecutionResult>) {
+        for result in results {
+            let op_id = result.operation_id;
+            if self.results.insert(op_id, result).is_none() {
+                self.order.push_back(op_id);
+            }
+        }
+        while self.order.len() > self.max_results {
+            if let Some(op_id) = self.order.pop_front() {
+                self.results.remove(&op_id);
+            }
+        }
+    }
+
+    /// Gets all the known results in finalization order, oldest first
+    pub fn get_ordered(&self) -> Vec<OperationExecutionResult> {
+        self.order
+            .iter()
+            .filter_map(|op_id| self.results.get(op_id).cloned())
+            .collect()
+    }
+}
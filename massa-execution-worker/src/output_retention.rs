@@ -0,0 +1,212 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! This module implements the retention of the SCE-final execution outputs for their consumers.
+//!
+//! Each class of consumers (API queries, gRPC streams, finality bookkeeping) has its own window of final slots.
+//! The API queries retain the outputs for their whole window. The other classes register their consumers,
+//! which acknowledge the outputs they processed: a class retains an output while one of its consumers
+//! did not acknowledge it, and at most for the window of the class.
+//! This way, a lagging stream only delays the pruning of the outputs within the stream window.
+//! An output is dropped as soon as no class retains it anymore.
+
+use massa_execution_exports::{
+    ExecutionConfig, ExecutionOutput, ExecutionOutputConsumerClass, ExecutionOutputConsumerId,
+};
+use massa_models::slot::Slot;
+use std::collections::{HashMap, VecDeque};
+
+/// All the classes of consumers
+const CONSUMER_CLASSES: [ExecutionOutputConsumerClass; 3] = [
+    ExecutionOutputConsumerClass::Api,
+    ExecutionOutputConsumerClass::Stream,
+    ExecutionOutputConsumerClass::Finality,
+];
+
+/// Registered consumer of the final execution outputs
+struct OutputConsumer {
+    /// class of the consumer
+    class: ExecutionOutputConsumerClass,
+    /// latest slot acknowledged by the consumer
+    acked_slot: Option<Slot>,
+}
+
+/// Store of the final execution outputs retained for their consumers
+pub(crate) struct ExecutionOutputRetention {
+    /// retained outputs with their finalization index, oldest first
+    outputs: VecDeque<(u64, ExecutionOutput)>,
+    /// finalization index of the next output
+    next_index: u64,
+    /// registered consumers
+    consumers: HashMap<ExecutionOutputConsumerId, OutputConsumer>,
+    /// identifier of the next registered consumer
+    next_consumer_id: u64,
+    /// number of final slots retained for the API queries
+    api_window: u64,
+    /// maximum number of final slots retained for the gRPC streams
+    stream_window: u64,
+    /// maximum number of final slots retained for the finality bookkeeping
+    finality_window: u64,
+}
+
+impl ExecutionOutputRetention {
+    /// Creates an empty store with the retention windows of the configuration
+    pub fn new(config: &ExecutionConfig) -> Self {
+        ExecutionOutputRetention {
+            outputs: VecDeque::new(),
+            next_index: 0,
+            consumers: HashMap::new(),
+            next_consumer_id: 0,
+            api_window: config.api_output_retention_slots,
+            stream_window: config.stream_output_retention_slots,
+            finality_window: config.finality_output_retention_slots,
+        }
+    }
+
+    /// Number of final slots retained for a class
+    fn window(&self, class: ExecutionOutputConsumerClass) -> u64 {
+        match class {
+            ExecutionOutputConsumerClass::Api => self.api_window,
+            ExecutionOutputConsumerClass::Stream => self.stream_window,
+            ExecutionOutputConsumerClass::Finality => self.finality_window,
+        }
+    }
+
+    /// Whether a class retains an output with a given finalization index
+    fn is_retained_by(&self, class: ExecutionOutputConsumerClass, index: u64, slot: &Slot) -> bool {
+        let age = self.next_index.saturating_sub(index).saturating_sub(1);
+        if age >= self.window(class) {
+            return false;
+        }
+        if class == ExecutionOutputConsumerClass::Api {
+            return true;
+        }
+        self.consumers.values().any(|consumer| {
+            consumer.class == class
+                && consumer
+                    .acked_slot
+                    .map_or(true, |acked_slot| acked_slot < *slot)
+        })
+    }
+
+    /// Drops the oldest outputs that no class retains anymore.
+    /// Outputs are acknowledged and leave the windows oldest first, so the retained ones are the latest.
+    fn prune(&mut self) {
+        while let Some((index, output)) = self.outputs.front() {
+            if CONSUMER_CLASSES
+                .iter()
+                .any(|class| self.is_retained_by(*class, *index, &output.slot))
+            {
+                break;
+            }
+            self.outputs.pop_front();
+        }
+    }
+
+    /// Adds a newly finalized output
+    pub fn push(&mut self, output: ExecutionOutput) {
+        self.outputs.push_back((self.next_index, output));
+        self.next_index = self.next_index.saturating_add(1);
+        self.prune();
+    }
+
+    /// Registers a consumer, which retains the outputs finalized from now on until it acknowledges them
+    pub fn register(&mut self, class: ExecutionOutputConsumerClass) -> ExecutionOutputConsumerId {
+        let id = ExecutionOutputConsumerId(self.next_consumer_id);
+        self.next_consumer_id = self.next_consumer_id.saturating_add(1);
+        let acked_slot = self.outputs.back().map(|(_, output)| output.slot);
+        self.consumers
+            .insert(id, OutputConsumer { class, acked_slot });
+        id
+    }
+
+    /// Acknowledges the outputs up to `slot` (included) for a consumer
+    pub fn ack(&mut self, id: ExecutionOutputConsumerId, slot: Slot) {
+        if let Some(consumer) = self.consumers.get_mut(&id) {
+            consumer.acked_slot = consumer.acked_slot.max(Some(slot));
+            self.prune();
+        }
+    }
+
+    /// Unregisters a consumer, releasing the outputs it retained
+    pub fn unregister(&mut self, id: ExecutionOutputConsumerId) {
+        if self.consumers.remove(&id).is_some() {
+            self.prune();
+        }
+    }
+
+    /// Gets the retained outputs of the slots after `after` (all of them if `None`), oldest first
+    pub fn get_after(&self, after: Option<Slot>) -> Vec<ExecutionOutput> {
+        self.outputs
+            .iter()
+            .filter(|(_, output)| after.map_or(true, |after| output.slot > after))
+            .map(|(_, output)| output.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(period: u64) -> ExecutionOutput {
+        ExecutionOutput {
+            slot: Slot::new(period, 0),
+            block_info: None,
+            state_changes: Default::default(),
+            events: Default::default(),
+            operation_results: Default::default(),
+        }
+    }
+
+    fn retained_periods(retention: &ExecutionOutputRetention) -> Vec<u64> {
+        retention
+            .get_after(None)
+            .iter()
+            .map(|output| output.slot.period)
+            .collect()
+    }
+
+    #[test]
+    fn test_output_retention_per_consumer_class() {
+        let mut retention = ExecutionOutputRetention::new(&ExecutionConfig {
+            api_output_retention_slots: 2,
+            stream_output_retention_slots: 5,
+            finality_output_retention_slots: 3,
+            ..Default::default()
+        });
+
+        // without consumers, only the API window is retained
+        for period in 1..=4 {
+            retention.push(output(period));
+        }
+        assert_eq!(retained_periods(&retention), vec![3, 4]);
+
+        // a lagging stream retains the outputs it did not acknowledge, within the stream window
+        let stream = retention.register(ExecutionOutputConsumerClass::Stream);
+        for period in 5..=10 {
+            retention.push(output(period));
+        }
+        assert_eq!(retained_periods(&retention), vec![6, 7, 8, 9, 10]);
+        retention.ack(stream, Slot::new(8, 0));
+        assert_eq!(retained_periods(&retention), vec![9, 10]);
+        assert_eq!(
+            retention
+                .get_after(Some(Slot::new(9, 0)))
+                .iter()
+                .map(|output| output.slot.period)
+                .collect::<Vec<_>>(),
+            vec![10]
+        );
+
+        // the finality bookkeeping has its own window, and releases the outputs once unregistered
+        let finality = retention.register(ExecutionOutputConsumerClass::Finality);
+        retention.ack(stream, Slot::new(10, 0));
+        for period in 11..=14 {
+            retention.push(output(period));
+            retention.ack(stream, Slot::new(period, 0));
+        }
+        assert_eq!(retained_periods(&retention), vec![12, 13, 14]);
+        retention.unregister(finality);
+        assert_eq!(retained_periods(&retention), vec![13, 14]);
+    }
+}
@@ -0,0 +1,189 @@
+//! Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module implements a cache of the results of read-only function calls.
+//! Read-only calls are executed at the slot following the latest executed candidate slot,
+//! so identical calls made before the next slot is executed yield identical results.
+//! Results are therefore cached by slot and by call, and entries of slots whose underlying speculative
+//! history is truncated are invalidated.
+
+use massa_execution_exports::{
+    ReadOnlyExecutionOutput, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
+};
+use massa_models::{address::Address, slot::Slot};
+use schnellru::{ByLength, LruMap};
+
+/// Key of a cached read-only call
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ReadOnlyCallKey {
+    /// slot at which the call is executed
+    slot: Slot,
+    /// target address
+    target_addr: Address,
+    /// target function
+    target_func: String,
+    /// function parameter
+    parameter: Vec<u8>,
+    /// call stack (address, raw coins, owned addresses), older caller first
+    call_stack: Vec<(Address, u64, Vec<Address>)>,
+    /// maximum gas of the call
+    max_gas: u64,
+    /// raw coins transferred to the target
+    coins: Option<u64>,
+    /// raw fee
+    fee: Option<u64>,
+}
+
+impl ReadOnlyCallKey {
+    /// Builds the cache key of a read-only request executed at `slot`.
+    /// Returns None if the request can not be cached:
+    /// bytecode executions and calls with an operation datastore are never cached.
+    pub fn new(slot: Slot, req: &ReadOnlyExecutionRequest) -> Option<Self> {
+        let ReadOnlyExecutionTarget::FunctionCall {
+            target_addr,
+            target_func,
+            parameter,
+        } = &req.target
+        else {
+            return None;
+        };
+        let call_stack = req
+            .call_stack
+            .iter()
+            .map(|elem| {
+                elem.operation_datastore.is_none().then(|| {
+                    (
+                        elem.address,
+                        elem.coins.to_raw(),
+                        elem.owned_addresses.clone(),
+                    )
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(ReadOnlyCallKey {
+            slot,
+            target_addr: *target_addr,
+            target_func: target_func.clone(),
+            parameter: parameter.clone(),
+            call_stack,
+            max_gas: req.max_gas,
+            coins: req.coins.map(|coins| coins.to_raw()),
+            fee: req.fee.map(|fee| fee.to_raw()),
+        })
+    }
+}
+
+/// LRU cache of the results of read-only function calls
+pub(crate) struct ReadOnlyCallCache {
+    cache: Option<LruMap<ReadOnlyCallKey, ReadOnlyExecutionOutput, ByLength>>,
+}
+
+impl ReadOnlyCallCache {
+    /// Creates a new cache holding up to `cache_size` results. The cache is disabled if `cache_size` is 0.
+    pub fn new(cache_size: u32) -> Self {
+        ReadOnlyCallCache {
+            cache: (cache_size > 0).then(|| LruMap::new(ByLength::new(cache_size))),
+        }
+    }
+
+    /// Gets a copy of the cached result of a call, if any
+    pub fn get(&mut self, key: &ReadOnlyCallKey) -> Option<ReadOnlyExecutionOutput> {
+        self.cache.as_mut()?.get(key).cloned()
+    }
+
+    /// Caches the result of a call
+    pub fn insert(&mut self, key: ReadOnlyCallKey, output: ReadOnlyExecutionOutput) {
+        if let Some(cache) = self.cache.as_mut() {
+            cache.insert(key, output);
+        }
+    }
+
+    /// Invalidates the results of the calls executed strictly after `slot`,
+    /// which were computed on top of a speculative history that no longer exists
+    pub fn invalidate_after(&mut self, slot: &Slot) {
+        let Some(cache) = self.cache.as_mut() else {
+            return;
+        };
+        let invalidated: Vec<ReadOnlyCallKey> = cache
+            .iter()
+            .filter(|(key, _)| &key.slot > slot)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in invalidated {
+            cache.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_execution_exports::{ExecutionOutput, ExecutionStackElement};
+    use std::str::FromStr;
+
+    fn call(target_addr: Address, parameter: Vec<u8>) -> ReadOnlyExecutionRequest {
+        ReadOnlyExecutionRequest {
+            max_gas: 1_000_000,
+            call_stack: vec![ExecutionStackElement {
+                address: target_addr,
+                coins: Default::default(),
+                owned_addresses: vec![target_addr],
+                operation_datastore: None,
+            }],
+            target: ReadOnlyExecutionTarget::FunctionCall {
+                target_addr,
+                target_func: "balanceOf".to_string(),
+                parameter,
+            },
+            coins: None,
+            fee: None,
+            state: Default::default(),
+            ledger_overrides: Default::default(),
+        }
+    }
+
+    fn output(slot: Slot, call_result: Vec<u8>) -> ReadOnlyExecutionOutput {
+        ReadOnlyExecutionOutput {
+            out: ExecutionOutput {
+                slot,
+                block_info: None,
+                state_changes: Default::default(),
+                events: Default::default(),
+                operation_results: Default::default(),
+            },
+            gas_cost: 0,
+            call_result,
+        }
+    }
+
+    #[test]
+    fn test_readonly_call_cache_invalidation() {
+        let addr =
+            Address::from_str("AS12htxRWiEm8jDJpJptr6cwEhWNcCSFWstN1MLSa96DDkVM9Y42G").unwrap();
+        let mut cache = ReadOnlyCallCache::new(10);
+
+        let key_1 = ReadOnlyCallKey::new(Slot::new(1, 0), &call(addr, vec![1])).unwrap();
+        let key_2 = ReadOnlyCallKey::new(Slot::new(2, 0), &call(addr, vec![1])).unwrap();
+        cache.insert(key_1.clone(), output(Slot::new(1, 0), vec![1]));
+        cache.insert(key_2.clone(), output(Slot::new(2, 0), vec![2]));
+
+        // the parameter is part of the key
+        let other_key = ReadOnlyCallKey::new(Slot::new(1, 0), &call(addr, vec![2])).unwrap();
+        assert!(cache.get(&other_key).is_none());
+        assert_eq!(cache.get(&key_1).unwrap().call_result, vec![1]);
+
+        // truncating the history after slot (1, 0) invalidates the calls executed after it
+        cache.invalidate_after(&Slot::new(1, 0));
+        assert!(cache.get(&key_1).is_some());
+        assert!(cache.get(&key_2).is_none());
+
+        // bytecode executions are never cached
+        let mut req = call(addr, vec![1]);
+        req.target = ReadOnlyExecutionTarget::BytecodeExecution(vec![0]);
+        assert!(ReadOnlyCallKey::new(Slot::new(1, 0), &req).is_none());
+
+        // a disabled cache keeps nothing
+        let mut cache = ReadOnlyCallCache::new(0);
+        cache.insert(key_1.clone(), output(Slot::new(1, 0), vec![1]));
+        assert!(cache.get(&key_1).is_none());
+    }
+}
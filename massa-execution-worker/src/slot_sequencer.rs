@@ -4,11 +4,14 @@
 
 use std::collections::{HashMap, VecDeque};
 
-use massa_execution_exports::{ExecutionBlockMetadata, ExecutionConfig};
+use massa_execution_exports::{
+    ExecutionBlockMetadata, ExecutionConfig, SlotMissStats, SlotSequencerStatus,
+    ThreadSlotMissStats,
+};
 use massa_models::{
     block_id::BlockId,
     prehash::PreHashMap,
-    slot::Slot,
+    slot::{Slot, SlotRange},
     timeslots::{get_block_slot_timestamp, get_latest_block_slot_at_timestamp},
 };
 use massa_time::MassaTime;
@@ -33,6 +36,18 @@ impl SlotInfo {
     }
 }
 
+/// Load of the slot sequencer, as seen from the execution cursors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SlotSequencerLoad {
+    /// number of slots between the latest executed candidate slot and the time cursor
+    pub candidate_lag: u64,
+    /// number of SCE-final slots waiting for execution
+    pub final_backlog: u64,
+    /// whether candidate execution is suspended because it lags too much (or because it is disabled),
+    /// in which case speculative execution results are stale
+    pub speculative_stale: bool,
+}
+
 /// Structure allowing execution slot sequence management.
 ///
 /// The `SlotSequencer::update` method is called to notify the sequencer about blocks becoming CSS-final, about changes in the blockclique, or simply about slot ticks.
@@ -40,6 +55,11 @@ impl SlotInfo {
 /// `SlotSequencer::is_task_available` allows checking if a slot is ready to be executed.
 /// `SlotSequencer::run_task_with` allows running the next slot in the queue, if any.
 /// Note that SCE-final slots are executed in priority over candidate slots.
+/// When candidate execution lags more than `config.max_candidate_execution_lag` slots behind the time cursor,
+/// candidate slots are not executed anymore until SCE-final execution catches up (see `SlotSequencer::get_load`).
+/// If no SCE-final slot is executed meanwhile (stalled finality), candidate execution resumes
+/// once the time cursor moved `config.max_candidate_execution_lag` slots forward.
+/// Candidate slots are never executed if `config.candidate_execution_enabled` is false.
 /// `SlotSequencer::get_next_slot_deadline` allows getting the time at which the next slot will happen (this is useful to sequence slots as they happen even if there is no block there).
 pub struct SlotSequencer {
     /// Config
@@ -59,6 +79,21 @@ pub struct SlotSequencer {
 
     /// candidate slot execution cursor
     latest_executed_candidate_slot: Slot,
+
+    /// time at which the latest SCE-final slot was executed (or the sequencer was created),
+    /// used to lift the suspension of candidate execution when finality stalls
+    latest_final_execution_time: MassaTime,
+
+    /// candidate slots executed before a restart and restored from a checkpoint, oldest first.
+    /// Consumed on `Self::init` (see `Self::restore_candidate_history`).
+    restored_candidates: Vec<(Slot, Option<BlockId>)>,
+
+    /// SCE-final slots executed in the last `config.stats_time_window_duration`, oldest first,
+    /// with their timestamp and whether they are misses
+    executed_final_history: VecDeque<(Slot, MassaTime, bool)>,
+
+    /// block and miss counts of `executed_final_history`, indexed by thread
+    thread_miss_stats: Vec<ThreadSlotMissStats>,
 }
 
 impl SlotSequencer {
@@ -77,10 +112,25 @@ impl SlotSequencer {
             latest_execution_final_slot: final_cursor,
             latest_executed_final_slot: final_cursor,
             latest_executed_candidate_slot: final_cursor,
+            latest_final_execution_time: MassaTime::now(),
+            restored_candidates: Vec::new(),
+            executed_final_history: VecDeque::new(),
+            thread_miss_stats: vec![Default::default(); config.thread_count as usize],
             config,
         }
     }
 
+    /// Declares candidate slots that were already executed before a restart,
+    /// as restored by the execution state from a checkpoint.
+    /// On `Self::init`, the candidate execution cursor skips those of them whose block still matches the initial sequence,
+    /// so that they are not executed again.
+    ///
+    /// # Arguments
+    /// * `restored_candidates`: consecutive slots following `final_cursor` with the id of the block executed at that slot (None for misses), oldest first
+    pub fn restore_candidate_history(&mut self, restored_candidates: Vec<(Slot, Option<BlockId>)>) {
+        self.restored_candidates = restored_candidates;
+    }
+
     /// Internal method that inits the sequencer.
     /// This method is called on the first call to `SlotSequencer::update`.
     /// It allows feeding the initial sequence of CSS-final blocks to the sequencer.
@@ -106,7 +156,7 @@ impl SlotSequencer {
         // Build the slot sequence
 
         // Get the starting slot of the sequence: the earliest CSS-final slot
-        let mut slot = *initial_consensus_final_blocks
+        let start_slot = *initial_consensus_final_blocks
             .keys()
             .min()
             .expect("init call should be done with non-empty new_consensus_final_blocks");
@@ -131,7 +181,7 @@ impl SlotSequencer {
         );
 
         // Iterate from the starting slot to the `max_slot` to build the slot sequence.
-        while slot <= max_slot {
+        for slot in SlotRange::inclusive(start_slot, max_slot, self.config.thread_count) {
             // If the slot is rearlier than (or equal to) the latest CSS-final slot in that thread => mark the slot as CSS-final
             let consensus_final = slot <= self.latest_consensus_final_slots[slot.thread as usize];
 
@@ -163,11 +213,6 @@ impl SlotSequencer {
                 execution_final,
                 content,
             });
-
-            // Increment slot for the next loop iteration
-            slot = slot
-                .get_next_slot(self.config.thread_count)
-                .expect("overflow in slot iteration");
         }
         // Explicitly consume tainted containers to prevent mistakes caused by using them later.
         if initial_consensus_final_blocks.into_iter().next().is_some() {
@@ -178,6 +223,26 @@ impl SlotSequencer {
         }
         std::mem::drop(blocks_metadata);
 
+        // Skip the restored candidate slots for as long as their content matches the sequence.
+        // The first mismatching slot will be re-executed, which truncates the execution history from there.
+        let time_cursor = self.get_time_cursor();
+        for (slot, block_id) in std::mem::take(&mut self.restored_candidates) {
+            let next_candidate_slot = self
+                .latest_executed_candidate_slot
+                .get_next_slot(self.config.thread_count)
+                .expect("overflow in slot iteration");
+            if slot != next_candidate_slot || slot > time_cursor {
+                break;
+            }
+            let sequence_block_id = self
+                .get_slot(&slot)
+                .and_then(|s_info| s_info.content.as_ref().map(|(b_id, _)| *b_id));
+            if sequence_block_id != block_id {
+                break;
+            }
+            self.latest_executed_candidate_slot = slot;
+        }
+
         // Cleanup the constructed sequence to remove older, executed CSS-final slots
         self.cleanup_sequence();
     }
@@ -238,7 +303,7 @@ impl SlotSequencer {
         // and gathering new ones from `new_consensus_final_blocks` and `new_blockclique`.
 
         // Get earliest useful slot to start the new sequence from (eg. the earliest slot of the previous sequence)
-        let mut slot = self
+        let start_slot = self
             .sequence
             .front()
             .expect("slot sequence should not be empty")
@@ -269,7 +334,7 @@ impl SlotSequencer {
 
         // Preallocate the new sequence of slots
         let new_seq_len = max_slot
-            .slots_since(&slot, self.config.thread_count)
+            .slots_since(&start_slot, self.config.thread_count)
             .expect("error computing new sequence length")
             .saturating_add(1);
         let mut new_sequence: VecDeque<SlotInfo> = VecDeque::with_capacity(new_seq_len as usize);
@@ -281,7 +346,7 @@ impl SlotSequencer {
         let mut in_execution_finality = true;
 
         // Loop over the slots to build the new sequence
-        while slot <= max_slot {
+        for slot in SlotRange::inclusive(start_slot, max_slot, self.config.thread_count) {
             // The slot is now CSS-final if it is before or at the latest CSS-final slot in its own thread
             let mut new_consensus_final =
                 slot <= self.latest_consensus_final_slots[slot.thread as usize];
@@ -349,11 +414,6 @@ impl SlotSequencer {
                     .get_prev_slot(self.config.thread_count)
                     .expect("could not rollback speculative execution cursor");
             }
-
-            // Increment slot for the next loop iteration.
-            slot = slot
-                .get_next_slot(self.config.thread_count)
-                .expect("overflow on slot iteration");
         }
         // Explicitly consume tainted containers to prevent mistakes caused by using them later.
         if !self.sequence.is_empty() {
@@ -564,6 +624,121 @@ impl SlotSequencer {
             .and_then(|idx| self.sequence.get(idx))
     }
 
+    /// Number of slots between the latest executed candidate slot and the time cursor
+    fn get_candidate_lag(&self) -> u64 {
+        self.get_time_cursor()
+            .slots_since(
+                &self.latest_executed_candidate_slot,
+                self.config.thread_count,
+            )
+            .unwrap_or(0)
+    }
+
+    /// Duration without SCE-final execution after which candidate execution resumes even if it lags too much:
+    /// the time it takes for the time cursor to move `config.max_candidate_execution_lag` slots forward
+    fn get_candidate_suspension_timeout(&self) -> MassaTime {
+        self.config
+            .t0
+            .checked_div_u64(self.config.thread_count as u64)
+            .expect("could not compute slot duration")
+            .saturating_mul(self.config.max_candidate_execution_lag)
+    }
+
+    /// Returns true if candidate execution is disabled, or lags too much behind the time cursor.
+    /// In that case speculative execution is skipped entirely,
+    /// the candidate cursor only moves forward along with SCE-final execution.
+    /// The suspension is lifted if no SCE-final slot was executed for `Self::get_candidate_suspension_timeout`,
+    /// so that candidate execution does not stop for good when finality stalls.
+    fn is_candidate_execution_suspended(&self) -> bool {
+        !self.config.candidate_execution_enabled
+            || (self.get_candidate_lag() > self.config.max_candidate_execution_lag
+                && MassaTime::now().saturating_sub(self.latest_final_execution_time)
+                    < self.get_candidate_suspension_timeout())
+    }
+
+    /// Returns true if candidate execution is enabled in the configuration
+    pub fn is_candidate_execution_enabled(&self) -> bool {
+        self.config.candidate_execution_enabled
+    }
+
+    /// Gets the current load of the sequencer
+    pub fn get_load(&self) -> SlotSequencerLoad {
+        let candidate_lag = self.get_candidate_lag();
+        let final_backlog = self
+            .sequence
+            .iter()
+            .filter(|s_info| {
+                s_info.execution_final && s_info.slot > self.latest_executed_final_slot
+            })
+            .count() as u64;
+        SlotSequencerLoad {
+            candidate_lag,
+            final_backlog,
+            speculative_stale: self.is_candidate_execution_suspended(),
+        }
+    }
+
+    /// Gets the current status of the sequencer
+    pub fn get_status(&self) -> SlotSequencerStatus {
+        SlotSequencerStatus {
+            sequence_length: self.sequence.len(),
+            latest_consensus_final_slots: self.latest_consensus_final_slots.clone(),
+            latest_execution_final_slot: self.latest_execution_final_slot,
+            latest_executed_final_slot: self.latest_executed_final_slot,
+            latest_executed_candidate_slot: self.latest_executed_candidate_slot,
+        }
+    }
+
+    /// Gets the per-thread block and miss counts of the SCE-final slots executed in the last `config.stats_time_window_duration`
+    pub fn get_slot_miss_stats(&self) -> SlotMissStats {
+        let time_window_end = self
+            .executed_final_history
+            .back()
+            .map_or_else(MassaTime::now, |(_, timestamp, _)| *timestamp);
+        SlotMissStats {
+            time_window_start: time_window_end
+                .saturating_sub(self.config.stats_time_window_duration),
+            time_window_end,
+            threads: self.thread_miss_stats.clone(),
+        }
+    }
+
+    /// Counts an executed SCE-final slot in the miss statistics
+    /// and forgets the slots that left the statistics time window
+    fn record_final_execution(&mut self, slot: Slot, is_miss: bool) {
+        let timestamp = get_block_slot_timestamp(
+            self.config.thread_count,
+            self.config.t0,
+            self.config.genesis_timestamp,
+            slot,
+        )
+        .expect("could not compute slot timestamp");
+        let stats = &mut self.thread_miss_stats[slot.thread as usize];
+        if is_miss {
+            stats.miss_count += 1;
+        } else {
+            stats.block_count += 1;
+        }
+        self.executed_final_history
+            .push_back((slot, timestamp, is_miss));
+
+        let time_window_start = timestamp.saturating_sub(self.config.stats_time_window_duration);
+        while let Some((old_slot, old_timestamp, old_is_miss)) =
+            self.executed_final_history.front().copied()
+        {
+            if old_timestamp > time_window_start {
+                break;
+            }
+            let stats = &mut self.thread_miss_stats[old_slot.thread as usize];
+            if old_is_miss {
+                stats.miss_count -= 1;
+            } else {
+                stats.block_count -= 1;
+            }
+            self.executed_final_history.pop_front();
+        }
+    }
+
     /// Returns true if there is a queued slot that needs to be executed now.
     pub fn is_task_available(&self) -> bool {
         // The sequence is empty => nothing to do.
@@ -599,7 +774,10 @@ impl SlotSequencer {
             // if it is later (or at) the current time cursor.
             // In the case in which it is absent from the sequence,
             // it will be considered a miss by run_task_with.
-            if self.get_time_cursor() >= next_candidate_slot {
+            // Candidate execution is skipped while it lags too much.
+            if self.get_time_cursor() >= next_candidate_slot
+                && !self.is_candidate_execution_suspended()
+            {
                 // A non-executed candidate slot is ready for execution.
                 return true;
             }
@@ -681,12 +859,17 @@ impl SlotSequencer {
             {
                 if *execution_final {
                     // There is an SCE-final slot ready for execution.
+                    let is_miss = content.is_none();
 
                     // Call the callback function to execute the slot.
                     let res = Some(callback(true, &slot, content.as_ref()));
 
                     // Update the SCE-final execution cursor.
                     self.latest_executed_final_slot = slot;
+                    self.latest_final_execution_time = MassaTime::now();
+
+                    // Count the executed slot in the miss statistics.
+                    self.record_final_execution(slot, is_miss);
 
                     // If the speculative execution cursor is late on the SCE-final one, make it catch up.
                     self.latest_executed_candidate_slot = std::cmp::max(
@@ -714,7 +897,8 @@ impl SlotSequencer {
                 .expect("overflow in slot iteration");
 
             // Check if that slot is before (or equal to) the time cursor, and available in the sequence.
-            if self.get_time_cursor() >= slot {
+            // Skip speculative execution if it lags too much: SCE-final catch-up has priority.
+            if self.get_time_cursor() >= slot && !self.is_candidate_execution_suspended() {
                 // The slot is ready for speculative execution.
 
                 // Consider it a miss if it is absent from the sequence.
@@ -735,6 +919,19 @@ impl SlotSequencer {
         None
     }
 
+    /// Rolls the candidate execution cursor back before a candidate slot whose execution was preempted,
+    /// so that the slot is executed again by a later call to `Self::run_task_with`.
+    /// Nothing is done if the cursor moved since the slot was run.
+    pub fn cancel_candidate_execution(&mut self, slot: &Slot) {
+        if &self.latest_executed_candidate_slot != slot || slot <= &self.latest_executed_final_slot
+        {
+            return;
+        }
+        self.latest_executed_candidate_slot = slot
+            .get_prev_slot(self.config.thread_count)
+            .expect("could not rollback speculative execution cursor");
+    }
+
     /// Gets the instant of the slot just after the latest slot in the sequence.
     /// Note that `config.cursor_delay` is taken into account.
     pub fn get_next_slot_deadline(&self) -> MassaTime {
@@ -767,3 +964,108 @@ impl SlotSequencer {
         .saturating_add(self.config.cursor_delay)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+
+    /// Sequencer whose time cursor is about 200 slots after its SCE-final cursor,
+    /// lifting the suspension of candidate execution after 200s without SCE-final execution,
+    /// so that the tests do not depend on how long they take to run
+    fn lagging_sequencer() -> SlotSequencer {
+        let config = ExecutionConfig {
+            thread_count: 2,
+            t0: MassaTime::from_millis(100_000),
+            genesis_timestamp: MassaTime::now().saturating_sub(MassaTime::from_millis(10_000_000)),
+            last_start_period: 0,
+            cursor_delay: MassaTime::from_millis(0),
+            max_candidate_execution_lag: 4,
+            candidate_execution_enabled: true,
+            ..ExecutionConfig::default()
+        };
+        let mut sequencer = SlotSequencer::new(config, Slot::new(0, 1));
+        sequencer.update(consensus_final_blocks(0), None, consensus_final_metadata(0));
+        sequencer
+    }
+
+    fn block_id(slot: Slot) -> BlockId {
+        BlockId::generate_from_hash(Hash::compute_from(&slot.to_bytes_key()))
+    }
+
+    /// One CSS-final block per thread at `period`
+    fn consensus_final_blocks(period: u64) -> HashMap<Slot, BlockId> {
+        (0..2)
+            .map(|thread| {
+                let slot = Slot::new(period, thread);
+                (slot, block_id(slot))
+            })
+            .collect()
+    }
+
+    fn consensus_final_metadata(period: u64) -> PreHashMap<BlockId, ExecutionBlockMetadata> {
+        consensus_final_blocks(period)
+            .into_values()
+            .map(|b_id| {
+                (
+                    b_id,
+                    ExecutionBlockMetadata {
+                        same_thread_parent_creator: None,
+                        storage: None,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Runs the available tasks, returning whether each of them was SCE-final
+    fn run_available_tasks(sequencer: &mut SlotSequencer) -> Vec<bool> {
+        let mut executed = Vec::new();
+        while sequencer.is_task_available() {
+            let is_final = sequencer
+                .run_task_with(|is_final, _slot, _content| is_final)
+                .expect("an available task was not run");
+            executed.push(is_final);
+        }
+        executed
+    }
+
+    #[test]
+    fn test_candidate_execution_suspended_while_lagging() {
+        let mut sequencer = lagging_sequencer();
+        assert!(sequencer.get_load().candidate_lag > 4);
+        assert!(sequencer.get_load().speculative_stale);
+        // candidate execution lags too much: nothing to execute until finality progresses
+        assert!(!sequencer.is_task_available());
+
+        // the new SCE-final slots are executed, but not the candidate ones
+        sequencer.update(consensus_final_blocks(3), None, consensus_final_metadata(3));
+        assert_eq!(run_available_tasks(&mut sequencer), vec![true; 6]);
+        assert_eq!(sequencer.latest_executed_final_slot, Slot::new(3, 1));
+        assert_eq!(sequencer.latest_executed_candidate_slot, Slot::new(3, 1));
+        assert!(sequencer.get_load().speculative_stale);
+    }
+
+    #[test]
+    fn test_candidate_execution_resumes_when_finality_stalls() {
+        let mut sequencer = lagging_sequencer();
+        assert!(!sequencer.is_task_available());
+
+        // no SCE-final slot is executed for longer than the suspension timeout
+        sequencer.latest_final_execution_time = sequencer
+            .latest_final_execution_time
+            .saturating_sub(sequencer.get_candidate_suspension_timeout())
+            .saturating_sub(MassaTime::from_millis(1));
+        assert!(!sequencer.get_load().speculative_stale);
+        assert!(sequencer.is_task_available());
+        assert_eq!(
+            sequencer.run_task_with(|is_final, slot, _content| (is_final, *slot)),
+            Some((false, Slot::new(1, 0)))
+        );
+
+        // executing a new SCE-final slot suspends candidate execution again
+        sequencer.update(consensus_final_blocks(1), None, consensus_final_metadata(1));
+        assert_eq!(run_available_tasks(&mut sequencer), vec![true; 2]);
+        assert!(sequencer.get_load().speculative_stale);
+    }
+}
@@ -7,11 +7,13 @@
 
 use crate::active_history::{ActiveHistory, HistorySearchResult};
 use massa_execution_exports::ExecutionError;
+use massa_execution_exports::LedgerEntryOverride;
 use massa_execution_exports::StorageCostsConstants;
 use massa_final_state::FinalStateController;
 use massa_ledger_exports::{Applicable, LedgerChanges, SetOrDelete, SetUpdateOrDelete};
 use massa_models::bytecode::Bytecode;
 use massa_models::datastore::get_prefix_bounds;
+use massa_models::prehash::PreHashMap;
 use massa_models::{address::Address, amount::Amount};
 use parking_lot::RwLock;
 use std::cmp::Ordering;
@@ -60,6 +62,10 @@ pub(crate) struct SpeculativeLedger {
 
     /// storage cost constants
     storage_costs_constants: StorageCostsConstants,
+
+    /// storage costs charged or refunded since the last call to `take_storage_cost_transfers`,
+    /// as (spending address, credited address, amount)
+    storage_cost_transfers: Vec<(Option<Address>, Option<Address>, Amount)>,
 }
 
 impl SpeculativeLedger {
@@ -84,6 +90,7 @@ impl SpeculativeLedger {
             max_datastore_value_size,
             max_bytecode_size,
             storage_costs_constants,
+            storage_cost_transfers: Vec::new(),
         }
     }
 
@@ -103,6 +110,32 @@ impl SpeculativeLedger {
         self.added_changes = snapshot;
     }
 
+    /// Overrides the state of ledger entries as if they had been changed since the creation of the `SpeculativeLedger`.
+    /// The entries of the addresses that do not exist are created.
+    /// Storage costs are not charged.
+    pub fn apply_overrides(&mut self, overrides: &PreHashMap<Address, LedgerEntryOverride>) {
+        for (addr, entry_override) in overrides {
+            if !self.entry_exists(addr) {
+                self.added_changes.create_address(addr);
+            }
+            if let Some(balance) = entry_override.balance {
+                self.added_changes.set_balance(*addr, balance);
+            }
+            if let Some(bytecode) = &entry_override.bytecode {
+                self.added_changes.set_bytecode(*addr, bytecode.clone());
+            }
+            for (key, value) in &entry_override.datastore {
+                match value {
+                    Some(value) => {
+                        self.added_changes
+                            .set_data_entry(*addr, key.clone(), value.clone())
+                    }
+                    None => self.added_changes.delete_data_entry(*addr, key.clone()),
+                }
+            }
+        }
+    }
+
     /// Gets the effective balance of an address
     ///
     /// # Arguments:
@@ -212,6 +245,27 @@ impl SpeculativeLedger {
         Ok(())
     }
 
+    /// Transfers coins to charge or refund storage costs, keeping track of the transfer.
+    /// See `transfer_coins`.
+    fn transfer_storage_costs(
+        &mut self,
+        from_addr: Option<Address>,
+        to_addr: Option<Address>,
+        amount: Amount,
+    ) -> Result<(), ExecutionError> {
+        self.transfer_coins(from_addr, to_addr, amount)?;
+        self.storage_cost_transfers
+            .push((from_addr, to_addr, amount));
+        Ok(())
+    }
+
+    /// Returns the storage cost transfers made since the last call, and forgets them
+    pub fn take_storage_cost_transfers(
+        &mut self,
+    ) -> Vec<(Option<Address>, Option<Address>, Amount)> {
+        std::mem::take(&mut self.storage_cost_transfers)
+    }
+
     /// Checks if an address exists in the speculative ledger
     ///
     /// # Arguments:
@@ -290,7 +344,7 @@ impl SpeculativeLedger {
                 ExecutionError::RuntimeError("overflow in ledger cost for bytecode".to_string())
             })?;
 
-        self.transfer_coins(Some(creator_address), None, address_storage_cost)?;
+        self.transfer_storage_costs(Some(creator_address), None, address_storage_cost)?;
         self.added_changes.create_address(&addr);
         self.added_changes.set_bytecode(addr, bytecode);
         Ok(())
@@ -337,8 +391,12 @@ impl SpeculativeLedger {
                 })?;
 
             match diff_size_storage.signum() {
-                1 => self.transfer_coins(Some(*caller_addr), None, storage_cost_bytecode)?,
-                -1 => self.transfer_coins(None, Some(*caller_addr), storage_cost_bytecode)?,
+                1 => {
+                    self.transfer_storage_costs(Some(*caller_addr), None, storage_cost_bytecode)?
+                }
+                -1 => {
+                    self.transfer_storage_costs(None, Some(*caller_addr), storage_cost_bytecode)?
+                }
                 _ => {}
             };
         } else {
@@ -351,7 +409,7 @@ impl SpeculativeLedger {
                         "overflow when calculating storage cost of bytecode".to_string(),
                     )
                 })?;
-            self.transfer_coins(Some(*caller_addr), None, bytecode_storage_cost)?;
+            self.transfer_storage_costs(Some(*caller_addr), None, bytecode_storage_cost)?;
         }
         // set the bytecode of that address
         self.added_changes.set_bytecode(*addr, bytecode);
@@ -552,7 +610,7 @@ impl SpeculativeLedger {
         match new_storage_cost.cmp(&old_storage_cost) {
             Ordering::Greater => {
                 // more bytes are now occupied
-                self.transfer_coins(
+                self.transfer_storage_costs(
                     Some(*caller_addr),
                     None,
                     new_storage_cost.saturating_sub(old_storage_cost),
@@ -560,7 +618,7 @@ impl SpeculativeLedger {
             }
             Ordering::Less => {
                 // some bytes have been freed
-                self.transfer_coins(
+                self.transfer_storage_costs(
                     None,
                     Some(*caller_addr),
                     old_storage_cost.saturating_sub(new_storage_cost),
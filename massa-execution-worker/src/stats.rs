@@ -1,5 +1,6 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::slot_sequencer::SlotSequencerLoad;
 use massa_models::slot::Slot;
 use massa_models::stats::ExecutionStats;
 use massa_time::MassaTime;
@@ -74,7 +75,13 @@ impl ExecutionStatsCounter {
     }
 
     /// get statistics
-    pub fn get_stats(&self, active_cursor: Slot, final_cursor: Slot) -> ExecutionStats {
+    pub fn get_stats(
+        &self,
+        active_cursor: Slot,
+        final_cursor: Slot,
+        sequencer_load: &SlotSequencerLoad,
+        candidate_execution_enabled: bool,
+    ) -> ExecutionStats {
         let current_time = MassaTime::now();
         let start_time = current_time.saturating_sub(self.time_window_duration);
         let map_func = |pair: &(usize, MassaTime)| -> usize {
@@ -92,6 +99,10 @@ impl ExecutionStatsCounter {
             time_window_end: current_time,
             active_cursor,
             final_cursor,
+            candidate_execution_lag: sequencer_load.candidate_lag,
+            final_execution_backlog: sequencer_load.final_backlog,
+            speculative_execution_stale: sequencer_load.speculative_stale,
+            candidate_execution_enabled,
         }
     }
 }
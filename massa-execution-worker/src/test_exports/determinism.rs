@@ -0,0 +1,264 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module implements a deterministic execution harness.
+//! The same sequence of consensus inputs (CSS-final blocks, blockclique changes and block metadata)
+//! is fed to two independently constructed execution replicas, each made of its own final state,
+//! selector, slot sequencer and execution state.
+//! Each replica interleaves `SlotSequencer::update` calls and slot executions differently,
+//! following a seeded random schedule, in order to exercise the candidate cursor rollback paths.
+//! The fingerprint of the final state of both replicas is then compared after each SCE-final slot:
+//! any difference reveals nondeterminism in execution.
+
+use crate::execution::ExecutionState;
+use crate::slot_sequencer::SlotSequencer;
+use massa_db_exports::{MassaDBConfig, MassaDBController, STATE_CF_DEFAULT_WRITE_BUFFER_SIZE};
+use massa_db_worker::MassaDB;
+use massa_execution_exports::{ExecutionBlockMetadata, ExecutionChannels, ExecutionConfig};
+use massa_final_state::test_exports::get_sample_state;
+use massa_final_state::FinalStateController;
+use massa_hash::Hash;
+use massa_metrics::MassaMetrics;
+use massa_models::config::{MIP_STORE_STATS_BLOCK_CONSIDERED, THREAD_COUNT};
+use massa_models::{block_id::BlockId, prehash::PreHashMap, slot::Slot};
+use massa_pos_exports::{SelectorConfig, SelectorController, SelectorManager};
+use massa_pos_worker::start_selector_worker;
+use massa_versioning::versioning::{MipStatsConfig, MipStore};
+use massa_wallet::test_exports::create_test_wallet;
+use num::rational::Ratio;
+use parking_lot::RwLock;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tempfile::{NamedTempFile, TempDir};
+
+/// Maximal number of slot executions run by a replica between two consecutive inputs
+const MAX_TASKS_BETWEEN_INPUTS: usize = 8;
+
+/// Consensus input fed to the execution replicas, as received by `ExecutionController::update_blockclique_status`
+#[derive(Clone, Default)]
+pub struct ExecutionInput {
+    /// newly CSS-finalized blocks
+    pub finalized_blocks: HashMap<Slot, BlockId>,
+    /// new blockclique, if it changed
+    pub new_blockclique: Option<HashMap<Slot, BlockId>>,
+    /// metadata of the blocks that were not provided before
+    pub block_metadata: PreHashMap<BlockId, ExecutionBlockMetadata>,
+}
+
+/// Fingerprints of the final state of a replica after each executed SCE-final slot, oldest first
+pub type FinalStateTrace = Vec<(Slot, Hash)>;
+
+/// Execution replica driven synchronously by the harness
+struct ExecutionReplica {
+    /// slot sequencer of the replica
+    slot_sequencer: SlotSequencer,
+    /// execution state of the replica
+    execution_state: RwLock<ExecutionState>,
+    /// final state of the replica
+    final_state: Arc<RwLock<dyn FinalStateController>>,
+    /// selector of the replica
+    selector: Box<dyn SelectorController>,
+    /// selector manager, stopped when the replica is dropped
+    selector_manager: Box<dyn SelectorManager>,
+    /// fingerprints of the final state after each executed SCE-final slot
+    trace: FinalStateTrace,
+    /// temporary files and directories backing the replica, removed when it is dropped
+    _temp_files: (TempDir, TempDir, NamedTempFile),
+}
+
+impl ExecutionReplica {
+    /// Builds a replica from a sample final state, with its own database and module cache
+    fn new(mut config: ExecutionConfig) -> Self {
+        let hd_cache_dir = TempDir::new().expect("cannot create temp directory");
+        config.hd_cache_path = hd_cache_dir.path().to_path_buf();
+
+        let mip_stats_config = MipStatsConfig {
+            block_count_considered: MIP_STORE_STATS_BLOCK_CONSIDERED,
+            warn_announced_version_ratio: Ratio::new_raw(30, 100),
+        };
+        let mip_store =
+            MipStore::try_from(([], mip_stats_config)).expect("Cannot create an empty MIP store");
+        let (selector_manager, selector) = start_selector_worker(SelectorConfig::default())
+            .expect("could not start selector controller");
+        let db_dir = TempDir::new().expect("cannot create temp directory");
+        let db = Arc::new(RwLock::new(Box::new(MassaDB::new(MassaDBConfig {
+            path: db_dir.path().to_path_buf(),
+            max_history_length: 10,
+            max_final_state_elements_size: 100_000,
+            max_versioning_elements_size: 100_000,
+            thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+        }))
+            as Box<(dyn MassaDBController + 'static)>));
+        let (final_state, ledger_file) = get_sample_state(
+            config.last_start_period,
+            selector.clone(),
+            mip_store.clone(),
+            db,
+        )
+        .expect("could not create sample final state");
+
+        let (slot_execution_output_sender, _) = tokio::sync::broadcast::channel(
+            config.broadcast_slot_execution_output_channel_capacity,
+        );
+        let execution_state = ExecutionState::new(
+            config.clone(),
+            final_state.clone(),
+            mip_store,
+            selector.clone(),
+            ExecutionChannels {
+                slot_execution_output_sender,
+            },
+            Arc::new(RwLock::new(create_test_wallet(Some(PreHashMap::default())))),
+            MassaMetrics::new(
+                false,
+                "0.0.0.0:9898".parse().unwrap(),
+                32,
+                std::time::Duration::from_secs(5),
+            )
+            .0,
+        );
+        // as in the execution worker, the sequencer starts at the last genesis slot of the last start
+        let final_cursor = std::cmp::max(
+            execution_state.final_cursor,
+            Slot::new(
+                config.last_start_period,
+                config.thread_count.saturating_sub(1),
+            ),
+        );
+        let slot_sequencer = SlotSequencer::new(config, final_cursor);
+
+        ExecutionReplica {
+            slot_sequencer,
+            execution_state: RwLock::new(execution_state),
+            final_state,
+            selector,
+            selector_manager,
+            trace: Vec::new(),
+            _temp_files: (hd_cache_dir, db_dir, ledger_file),
+        }
+    }
+
+    /// Runs the next slot execution task, if any, as the execution worker does.
+    ///
+    /// # Returns
+    /// false if no task was available
+    fn run_task(&mut self) -> bool {
+        let execution_state = &self.execution_state;
+        let selector = &self.selector;
+        let run_result = self.slot_sequencer.run_task_with(
+            |is_final: bool, slot: &Slot, content: Option<&(BlockId, ExecutionBlockMetadata)>| {
+                if is_final {
+                    execution_state
+                        .write()
+                        .execute_final_slot(slot, content, selector.clone());
+                } else {
+                    execution_state.write().execute_candidate_slot(
+                        slot,
+                        content,
+                        selector.clone(),
+                        None,
+                    );
+                }
+                is_final.then_some(*slot)
+            },
+        );
+        match run_result {
+            Some(Some(final_slot)) => {
+                let fingerprint = self.final_state.read().get_fingerprint();
+                self.trace.push((final_slot, fingerprint));
+                true
+            }
+            Some(None) => true,
+            None => false,
+        }
+    }
+
+    /// Executes all the SCE-final slots known by the sequencer
+    fn run_final_tasks(&mut self) {
+        loop {
+            let status = self.slot_sequencer.get_status();
+            if status.latest_executed_final_slot >= status.latest_execution_final_slot
+                || !self.run_task()
+            {
+                return;
+            }
+        }
+    }
+}
+
+impl Drop for ExecutionReplica {
+    fn drop(&mut self) {
+        self.selector_manager.stop();
+    }
+}
+
+/// Runs the same consensus inputs through two independent execution replicas,
+/// and checks that their final states stay identical.
+pub struct DeterminismHarness {
+    /// execution configuration of both replicas (their on-disk paths are made distinct)
+    config: ExecutionConfig,
+}
+
+impl DeterminismHarness {
+    /// Creates a harness building its replicas from `config`
+    pub fn new(config: ExecutionConfig) -> Self {
+        DeterminismHarness { config }
+    }
+
+    /// Feeds `inputs` to two fresh replicas, each interleaving sequencer updates and slot executions
+    /// according to its own random schedule derived from `seed`,
+    /// then executes all the resulting SCE-final slots.
+    ///
+    /// # Returns
+    /// The final state trace of the first replica
+    ///
+    /// # Panics
+    /// If the final states of the replicas differ after some SCE-final slot
+    pub fn run(&self, inputs: &[ExecutionInput], seed: u64) -> FinalStateTrace {
+        let traces: Vec<FinalStateTrace> = (0..2u64)
+            .map(|replica_index| {
+                let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed.wrapping_add(replica_index));
+                let mut replica = ExecutionReplica::new(self.config.clone());
+                for input in inputs {
+                    // execute some of the slots already available before receiving the input
+                    for _ in 0..rng.gen_range(0..=MAX_TASKS_BETWEEN_INPUTS) {
+                        if !replica.run_task() {
+                            break;
+                        }
+                    }
+                    // sometimes receive a slot tick carrying no input
+                    if rng.gen_bool(0.5) {
+                        replica
+                            .slot_sequencer
+                            .update(Default::default(), None, Default::default());
+                    }
+                    replica.slot_sequencer.update(
+                        input.finalized_blocks.clone(),
+                        input.new_blockclique.clone(),
+                        input.block_metadata.clone(),
+                    );
+                }
+                replica.run_final_tasks();
+                std::mem::take(&mut replica.trace)
+            })
+            .collect();
+
+        let (first, second) = (&traces[0], &traces[1]);
+        for (first_item, second_item) in first.iter().zip(second.iter()) {
+            assert_eq!(
+                first_item, second_item,
+                "execution diverged between replicas (seed {})",
+                seed
+            );
+        }
+        assert_eq!(
+            first.len(),
+            second.len(),
+            "replicas executed a different number of SCE-final slots (seed {})",
+            seed
+        );
+        traces.into_iter().next().unwrap_or_default()
+    }
+}
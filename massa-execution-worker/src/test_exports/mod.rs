@@ -0,0 +1,7 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module exposes testing tools for the execution worker
+
+mod determinism;
+
+pub use determinism::*;
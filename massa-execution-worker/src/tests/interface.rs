@@ -17,3 +17,25 @@ fn test_hash_sha256() {
         &hex!("3fc9b689459d738f8c88a3a48aa9e33542016b7a4052e001aaa536fca74813cb")[..];
     assert_eq!(actual_hash, expected_hash);
 }
+
+#[test]
+fn test_address_introspection() {
+    let interface = InterfaceImpl::new_default(
+        Address::from_str("AU12cMW9zRKFDS43Z2W88VCmdQFxmHjAo54XvuVV34UzJeXRLXW9M").unwrap(),
+        None,
+    );
+    let user_address = "AU12cMW9zRKFDS43Z2W88VCmdQFxmHjAo54XvuVV34UzJeXRLXW9M";
+    let sc_address = "AS12htxRWiEm8jDJpJptr6cwEhWNcCSFWstN1MLSa96DDkVM9Y42G";
+    assert!(interface.check_address_wasmv1(user_address).unwrap());
+    assert!(interface.check_address_wasmv1(sc_address).unwrap());
+    assert!(!interface.check_address_wasmv1("AU1invalid").unwrap());
+    assert_eq!(
+        interface.get_address_version_wasmv1(user_address).unwrap(),
+        Address::from_str(user_address).unwrap().get_version()
+    );
+    assert_eq!(
+        interface.get_address_version_wasmv1(sc_address).unwrap(),
+        Address::from_str(sc_address).unwrap().get_version()
+    );
+    assert!(interface.get_address_version_wasmv1("AU1invalid").is_err());
+}
@@ -9,4 +9,7 @@ mod universe;
 #[cfg(test)]
 mod tests_active_history;
 
+#[cfg(test)]
+mod tests_determinism;
+
 mod interface;
@@ -4,8 +4,10 @@ use massa_async_pool::{AsyncMessage, AsyncPool, AsyncPoolChanges, AsyncPoolConfi
 use massa_db_exports::{DBBatch, ShareableMassaDBController};
 use massa_executed_ops::{ExecutedDenunciations, ExecutedDenunciationsConfig};
 use massa_execution_exports::{
-    ExecutionConfig, ExecutionQueryRequest, ExecutionQueryRequestItem, ExecutionStackElement,
-    ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
+    CoinMovementKind, ExecutionBlockMetadata, ExecutionConfig, ExecutionQueryRequest,
+    ExecutionQueryRequestItem, ExecutionStackElement, LedgerEntryOverride,
+    OperationSimulationRequest, ReadOnlyExecutionRequest, ReadOnlyExecutionState,
+    ReadOnlyExecutionTarget,
 };
 use massa_final_state::test_exports::get_initials;
 use massa_final_state::MockFinalStateController;
@@ -15,8 +17,9 @@ use massa_ledger_exports::{
 };
 use massa_models::bytecode::Bytecode;
 use massa_models::config::{ENDORSEMENT_COUNT, LEDGER_ENTRY_DATASTORE_BASE_SIZE, THREAD_COUNT};
+use massa_models::prehash::PreHashMap;
 use massa_models::test_exports::gen_endorsements_for_denunciation;
-use massa_models::{address::Address, amount::Amount, slot::Slot};
+use massa_models::{address::Address, amount::Amount, block_id::BlockId, slot::Slot};
 use massa_models::{
     denunciation::Denunciation,
     execution::EventFilter,
@@ -30,7 +33,12 @@ use mockall::predicate;
 use num::rational::Ratio;
 use parking_lot::RwLock;
 use std::sync::Arc;
-use std::{cmp::Reverse, collections::BTreeMap, str::FromStr, time::Duration};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+    time::Duration,
+};
 
 use super::universe::{ExecutionForeignControllers, ExecutionTestUniverse};
 
@@ -267,6 +275,8 @@ fn test_readonly_execution() {
             ),
             coins: None,
             fee: Some(Amount::from_str("40").unwrap()),
+            state: Default::default(),
+            ledger_overrides: Default::default(),
         })
         .expect("readonly execution failed");
 
@@ -312,6 +322,8 @@ fn test_readonly_execution() {
             },
             coins: Some(Amount::from_str("20").unwrap()),
             fee: Some(Amount::from_str("30").unwrap()),
+            state: Default::default(),
+            ledger_overrides: Default::default(),
         })
         .expect("readonly execution failed");
 
@@ -328,6 +340,187 @@ fn test_readonly_execution() {
     );
 }
 
+/// Read-only executions on the final state do not see the changes of the candidate slots
+#[test]
+fn readonly_execution_on_final_state() {
+    let exec_cfg = ExecutionConfig::default();
+    let mut foreign_controllers = ExecutionForeignControllers::new_with_mocks();
+    selector_boilerplate(&mut foreign_controllers.selector_controller);
+    foreign_controllers
+        .ledger_controller
+        .set_expectations(|ledger_controller| {
+            ledger_controller
+                .expect_get_bytecode()
+                .returning(move |_| None);
+            ledger_controller
+                .expect_entry_exists()
+                .returning(move |_| true);
+        });
+    final_state_boilerplate(
+        &mut foreign_controllers.final_state,
+        foreign_controllers.db.clone(),
+        &foreign_controllers.selector_controller,
+        &mut foreign_controllers.ledger_controller,
+        None,
+        None,
+        None,
+    );
+    let mut universe = ExecutionTestUniverse::new(foreign_controllers, exec_cfg);
+
+    // candidate-only block crediting 90 coins to the recipient, that has 100 coins in the final state
+    let keypair = KeyPair::from_str(TEST_SK_1).unwrap();
+    let recipient_address =
+        Address::from_public_key(&KeyPair::generate(0).unwrap().get_public_key());
+    let operation = Operation::new_verifiable(
+        Operation {
+            fee: Amount::from_str("10").unwrap(),
+            expire_period: 10,
+            op: OperationType::Transaction {
+                recipient_address,
+                amount: Amount::from_str("90").unwrap(),
+            },
+        },
+        OperationSerializer::new(),
+        &keypair,
+    )
+    .unwrap();
+    universe.storage.store_operations(vec![operation.clone()]);
+    let block = ExecutionTestUniverse::create_block(
+        &keypair,
+        Slot::new(1, 0),
+        vec![operation],
+        vec![],
+        vec![],
+    );
+    universe.storage.store_block(block.clone());
+    let block_metadata: PreHashMap<BlockId, ExecutionBlockMetadata> = [(
+        block.id,
+        ExecutionBlockMetadata {
+            same_thread_parent_creator: Some(Address::from_public_key(&keypair.get_public_key())),
+            storage: Some(universe.storage.clone()),
+        },
+    )]
+    .into_iter()
+    .collect();
+    universe.module_controller.update_blockclique_status(
+        Default::default(),
+        Some(HashMap::from([(Slot::new(1, 0), block.id)])),
+        block_metadata,
+    );
+
+    // wait for the candidate execution of the block
+    let start = std::time::Instant::now();
+    while universe.module_controller.get_stats().active_cursor < Slot::new(1, 0) {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the candidate block was not executed"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    // the recipient pays a fee of 10 coins on top of each state
+    let recipient_balance_after_call = |state: ReadOnlyExecutionState| {
+        universe
+            .module_controller
+            .execute_readonly_request(ReadOnlyExecutionRequest {
+                max_gas: 100_000_000,
+                call_stack: vec![ExecutionStackElement {
+                    address: recipient_address,
+                    coins: Amount::zero(),
+                    owned_addresses: vec![],
+                    operation_datastore: None,
+                }],
+                target: ReadOnlyExecutionTarget::BytecodeExecution(
+                    include_bytes!("./wasm/event_test.wasm").to_vec(),
+                ),
+                coins: None,
+                fee: Some(Amount::from_str("10").unwrap()),
+                state,
+                ledger_overrides: Default::default(),
+            })
+            .expect("readonly execution failed")
+            .out
+            .state_changes
+            .ledger_changes
+            .get_balance_or_else(&recipient_address, || None)
+    };
+    assert_eq!(
+        recipient_balance_after_call(ReadOnlyExecutionState::Candidate),
+        Some(Amount::from_str("180").unwrap())
+    );
+    assert_eq!(
+        recipient_balance_after_call(ReadOnlyExecutionState::Final),
+        Some(Amount::from_str("90").unwrap())
+    );
+}
+
+/// Read-only executions see the overridden datastore entries
+#[test]
+fn readonly_execution_with_datastore_overrides() {
+    let exec_cfg = ExecutionConfig::default();
+    let mut foreign_controllers = ExecutionForeignControllers::new_with_mocks();
+    selector_boilerplate(&mut foreign_controllers.selector_controller);
+    foreign_controllers
+        .ledger_controller
+        .set_expectations(|ledger_controller| {
+            ledger_controller
+                .expect_get_data_entry()
+                .returning(move |_, _| None);
+            ledger_controller
+                .expect_get_bytecode()
+                .returning(move |_| None);
+        });
+    final_state_boilerplate(
+        &mut foreign_controllers.final_state,
+        foreign_controllers.db.clone(),
+        &foreign_controllers.selector_controller,
+        &mut foreign_controllers.ledger_controller,
+        None,
+        None,
+        None,
+    );
+    let universe = ExecutionTestUniverse::new(foreign_controllers, exec_cfg);
+
+    let addr = Address::from_str("AU1LQrXPJ3DVL8SFRqACk31E9MVxBcmCATFiRdpEmgztGxWAx48D").unwrap();
+    let key: Vec<u8> = vec![1, 2, 3];
+    let res = universe
+        .module_controller
+        .execute_readonly_request(ReadOnlyExecutionRequest {
+            max_gas: 100_000_000,
+            call_stack: vec![ExecutionStackElement {
+                address: addr,
+                coins: Amount::zero(),
+                owned_addresses: vec![],
+                operation_datastore: None,
+            }],
+            target: ReadOnlyExecutionTarget::BytecodeExecution(
+                include_bytes!("./wasm/event_test.wasm").to_vec(),
+            ),
+            coins: None,
+            fee: None,
+            state: Default::default(),
+            ledger_overrides: [(
+                addr,
+                LedgerEntryOverride {
+                    datastore: BTreeMap::from([(key.clone(), Some(vec![42]))]),
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+        })
+        .expect("readonly execution failed");
+
+    // the entry is absent from the final ledger: it is read back from the overrides
+    assert_eq!(
+        res.out
+            .state_changes
+            .ledger_changes
+            .get_data_entry_or_else(&addr, &key, || None),
+        Some(vec![42])
+    );
+}
+
 /// Test the gas usage in nested calls using call SC operation
 ///
 /// Create a smart contract and send it in the blockclique.
@@ -528,6 +721,25 @@ fn test_get_call_coins() {
         op_candidate == Some(true) && op_final == Some(true),
         "Expected operation not found or not successfully executed"
     );
+
+    // check the detailed execution result of the operation
+    let op_result = universe
+        .module_controller
+        .get_operation_execution_result(&[operation.id])[0]
+        .clone()
+        .expect("operation execution result not found");
+    assert!(op_result.success && op_result.is_final);
+    assert_eq!(op_result.slot, Slot::new(1, 1));
+    assert!(op_result.error.is_none());
+    assert_eq!(op_result.events.len(), events.len());
+    assert!(op_result.coin_movements.iter().any(|movement| {
+        movement.to == Some(Address::from_str(&address).unwrap()) && movement.amount == coins_sent
+    }));
+    assert!(op_result
+        .coin_movements
+        .iter()
+        .any(|movement| movement.kind == CoinMovementKind::Fee && movement.to.is_none()));
+    assert_eq!(op_result.call_stats.max_gas, 10000000);
 }
 
 /// # Context
@@ -1264,6 +1476,122 @@ fn send_and_receive_transaction() {
     finalized_waitpoint.wait();
 }
 
+#[test]
+fn candidate_execution_disabled() {
+    let exec_cfg = ExecutionConfig {
+        candidate_execution_enabled: false,
+        ..ExecutionConfig::default()
+    };
+    let mut foreign_controllers = ExecutionForeignControllers::new_with_mocks();
+    let finalized_waitpoint = WaitPoint::new();
+    let finalized_waitpoint_trigger_handle = finalized_waitpoint.get_trigger_handle();
+    selector_boilerplate(&mut foreign_controllers.selector_controller);
+    final_state_boilerplate(
+        &mut foreign_controllers.final_state,
+        foreign_controllers.db.clone(),
+        &foreign_controllers.selector_controller,
+        &mut foreign_controllers.ledger_controller,
+        None,
+        None,
+        None,
+    );
+    foreign_controllers
+        .final_state
+        .write()
+        .expect_finalize()
+        .times(1)
+        .with(predicate::eq(Slot::new(1, 0)), predicate::always())
+        .returning(move |_, _| {
+            finalized_waitpoint_trigger_handle.trigger();
+        });
+    let mut universe = ExecutionTestUniverse::new(foreign_controllers, exec_cfg);
+    let block = ExecutionTestUniverse::create_block(
+        &KeyPair::from_str(TEST_SK_1).unwrap(),
+        Slot::new(1, 0),
+        vec![],
+        vec![],
+        vec![],
+    );
+    universe.send_and_finalize(&KeyPair::from_str(TEST_SK_1).unwrap(), block);
+    finalized_waitpoint.wait();
+
+    // only the final slot was executed: the candidate cursor did not move past the final one
+    let stats = universe.module_controller.get_stats();
+    assert!(!stats.candidate_execution_enabled);
+    assert_eq!(stats.active_cursor, stats.final_cursor);
+}
+
+/// Simulate a transaction that the sender can only afford thanks to a balance override
+#[test]
+fn simulate_transaction_with_ledger_overrides() {
+    let exec_cfg = ExecutionConfig::default();
+    let mut foreign_controllers = ExecutionForeignControllers::new_with_mocks();
+    selector_boilerplate(&mut foreign_controllers.selector_controller);
+    final_state_boilerplate(
+        &mut foreign_controllers.final_state,
+        foreign_controllers.db.clone(),
+        &foreign_controllers.selector_controller,
+        &mut foreign_controllers.ledger_controller,
+        None,
+        None,
+        None,
+    );
+    let universe = ExecutionTestUniverse::new(foreign_controllers, exec_cfg);
+
+    let keypair = KeyPair::from_str(TEST_SK_1).unwrap();
+    let sender_address = Address::from_public_key(&keypair.get_public_key());
+    let recipient_address =
+        Address::from_public_key(&KeyPair::generate(0).unwrap().get_public_key());
+    let operation = Operation::new_verifiable(
+        Operation {
+            fee: Amount::from_str("10").unwrap(),
+            expire_period: 10,
+            op: OperationType::Transaction {
+                recipient_address,
+                amount: Amount::from_str("500").unwrap(),
+            },
+        },
+        OperationSerializer::new(),
+        &keypair,
+    )
+    .unwrap();
+
+    // the sender only has 100 coins: the transaction fails
+    let res = universe
+        .module_controller
+        .simulate_operation(OperationSimulationRequest {
+            operation: operation.clone(),
+            ledger_overrides: Default::default(),
+        })
+        .expect("operation simulation failed");
+    assert!(!res.result.success);
+
+    // with 1000 coins, it succeeds
+    let res = universe
+        .module_controller
+        .simulate_operation(OperationSimulationRequest {
+            operation,
+            ledger_overrides: [(
+                sender_address,
+                LedgerEntryOverride {
+                    balance: Some(Amount::from_str("1000").unwrap()),
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+        })
+        .expect("operation simulation failed");
+    assert!(res.result.success, "{:?}", res.result.error);
+    assert_eq!(
+        res.out
+            .state_changes
+            .ledger_changes
+            .get_balance_or_else(&sender_address, || None),
+        Some(Amount::from_str("490").unwrap())
+    );
+}
+
 #[test]
 fn roll_buy() {
     // setup
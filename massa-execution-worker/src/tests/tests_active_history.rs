@@ -54,6 +54,7 @@ fn test_active_history_deferred_credits() {
             execution_trail_hash_change: Default::default(),
         },
         events: Default::default(),
+        operation_results: Default::default(),
     };
 
     let active_history = ActiveHistory(VecDeque::from([exec_output_1]));
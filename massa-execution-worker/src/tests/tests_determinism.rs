@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use massa_execution_exports::{ExecutionBlockMetadata, ExecutionConfig};
+use massa_models::{
+    address::Address, block_id::BlockId, config::THREAD_COUNT, prehash::PreHashMap, slot::Slot,
+};
+use massa_signature::KeyPair;
+use massa_storage::Storage;
+use massa_test_framework::TestUniverse;
+
+use super::universe::ExecutionTestUniverse;
+use crate::test_exports::{DeterminismHarness, ExecutionInput};
+
+/// Creates an empty block in each thread at `period`, stored in `storage`
+fn period_blocks(storage: &Storage, keypair: &KeyPair, period: u64) -> ExecutionInput {
+    let creator = Address::from_public_key(&keypair.get_public_key());
+    let mut blocks: HashMap<Slot, BlockId> = HashMap::new();
+    let mut block_metadata: PreHashMap<BlockId, ExecutionBlockMetadata> = Default::default();
+    for thread in 0..THREAD_COUNT {
+        let slot = Slot::new(period, thread);
+        let block = ExecutionTestUniverse::create_block(keypair, slot, vec![], vec![], vec![]);
+        let mut block_storage = storage.clone_without_refs();
+        block_storage.store_block(block.clone());
+        blocks.insert(slot, block.id);
+        block_metadata.insert(
+            block.id,
+            ExecutionBlockMetadata {
+                same_thread_parent_creator: Some(creator),
+                storage: Some(block_storage),
+            },
+        );
+    }
+    ExecutionInput {
+        finalized_blocks: Default::default(),
+        new_blockclique: Some(blocks),
+        block_metadata,
+    }
+}
+
+#[test]
+fn test_execution_determinism_across_interleavings() {
+    let storage = Storage::create_root();
+    let genesis_keypair = KeyPair::generate(0).unwrap();
+    let keypair = KeyPair::generate(0).unwrap();
+    let fork_keypair = KeyPair::generate(0).unwrap();
+
+    // genesis blocks are final from the start
+    let mut genesis = period_blocks(&storage, &genesis_keypair, 0);
+    genesis.finalized_blocks = genesis.new_blockclique.take().unwrap();
+
+    // a fork is first seen as the blockclique, then replaced by the canonical chain
+    let fork = period_blocks(&storage, &fork_keypair, 1);
+    let period_1 = period_blocks(&storage, &keypair, 1);
+    let period_2 = period_blocks(&storage, &keypair, 2);
+    let switch = ExecutionInput {
+        finalized_blocks: period_1.new_blockclique.clone().unwrap(),
+        new_blockclique: Some(period_2.new_blockclique.clone().unwrap()),
+        block_metadata: period_1
+            .block_metadata
+            .into_iter()
+            .chain(period_2.block_metadata)
+            .collect(),
+    };
+    let finalization = ExecutionInput {
+        finalized_blocks: period_2.new_blockclique.unwrap(),
+        new_blockclique: Some(Default::default()),
+        block_metadata: Default::default(),
+    };
+    let inputs = vec![genesis, fork, switch, finalization];
+
+    let harness = DeterminismHarness::new(ExecutionConfig::default());
+    for seed in 0..3 {
+        let trace = harness.run(&inputs, seed);
+        assert_eq!(trace.len(), 2 * THREAD_COUNT as usize);
+        assert_eq!(
+            trace.last().map(|(slot, _)| *slot),
+            Some(Slot::new(2, THREAD_COUNT - 1))
+        );
+    }
+}
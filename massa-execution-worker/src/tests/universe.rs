@@ -4,7 +4,10 @@ use std::{
     sync::Arc,
 };
 
-use massa_db_exports::{MassaDBConfig, MassaDBController, ShareableMassaDBController};
+use massa_db_exports::{
+    MassaDBConfig, MassaDBController, ShareableMassaDBController,
+    STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+};
 use massa_db_worker::MassaDB;
 use massa_execution_exports::{
     ExecutionBlockMetadata, ExecutionChannels, ExecutionConfig, ExecutionController,
@@ -55,6 +58,7 @@ impl ExecutionForeignControllers {
             max_final_state_elements_size: 100_000,
             max_versioning_elements_size: 100_000,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
 
         let db = Arc::new(RwLock::new(
@@ -0,0 +1,447 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module implements an opt-in execution tracer.
+//! When enabled, every ledger read/write, gas charge and ABI call happening during
+//! the execution of a slot is recorded, and the resulting trace is dumped to disk
+//! once the slot is settled, either as JSON or in a compact binary format.
+
+use massa_execution_exports::{CoinFlowEdge, CoinMovementKind, ExecutionTraceFormat};
+use massa_models::{
+    address::{Address, AddressSerializer},
+    amount::{Amount, AmountSerializer},
+    block_id::{BlockId, BlockIdSerializer},
+    operation::{OperationId, OperationIdSerializer},
+    serialization::VecU8Serializer,
+    slot::{Slot, SlotSerializer},
+};
+use massa_serialization::{SerializeError, Serializer, U64VarIntSerializer};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Kind of ledger data touched by a traced read or write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerTraceKind {
+    /// balance of an address
+    Balance,
+    /// bytecode of an address
+    Bytecode,
+    /// a single datastore entry of an address
+    DatastoreEntry,
+    /// the datastore keys of an address
+    DatastoreKeys,
+}
+
+impl LedgerTraceKind {
+    fn to_u8(self) -> u8 {
+        match self {
+            LedgerTraceKind::Balance => 0,
+            LedgerTraceKind::Bytecode => 1,
+            LedgerTraceKind::DatastoreEntry => 2,
+            LedgerTraceKind::DatastoreKeys => 3,
+        }
+    }
+}
+
+fn coin_movement_kind_to_u8(kind: CoinMovementKind) -> u8 {
+    match kind {
+        CoinMovementKind::Fee => 0,
+        CoinMovementKind::Transfer => 1,
+        CoinMovementKind::Storage => 2,
+    }
+}
+
+/// A single event recorded by the execution tracer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExecutionTraceItem {
+    /// an ABI was called by the running smart contract
+    AbiCall {
+        /// name of the ABI
+        name: &'static str,
+    },
+    /// ledger data was read
+    LedgerRead {
+        /// address whose data was read
+        address: Address,
+        /// kind of data that was read
+        kind: LedgerTraceKind,
+        /// datastore key or key prefix, if relevant
+        key: Option<Vec<u8>>,
+    },
+    /// ledger data was written
+    LedgerWrite {
+        /// address whose data was written
+        address: Address,
+        /// kind of data that was written
+        kind: LedgerTraceKind,
+        /// datastore key, if relevant
+        key: Option<Vec<u8>>,
+    },
+    /// coins were moved, created or destroyed
+    CoinTransfer {
+        /// spending address (None for coin creation)
+        from: Option<Address>,
+        /// credited address (None for coin destruction)
+        to: Option<Address>,
+        /// amount transferred
+        amount: Amount,
+    },
+    /// some gas was charged
+    GasCharge {
+        /// what the gas was charged for
+        label: &'static str,
+        /// amount of gas charged
+        amount: u64,
+    },
+    /// an operation was executed: summary of all the coins it moved
+    OperationCoinFlow {
+        /// id of the executed operation
+        operation_id: OperationId,
+        /// edges of the coin flow graph of the operation
+        edges: Vec<CoinFlowEdge>,
+    },
+}
+
+/// Full execution trace of a slot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SlotExecutionTrace {
+    /// executed slot
+    pub slot: Slot,
+    /// block executed at that slot, if any
+    pub block_id: Option<BlockId>,
+    /// recorded items, in execution order
+    pub items: Vec<ExecutionTraceItem>,
+}
+
+/// Records the trace of the slot currently being executed.
+/// Shared between the execution state and the execution context.
+pub struct ExecutionTracer {
+    trace: Mutex<SlotExecutionTrace>,
+}
+
+impl ExecutionTracer {
+    /// Creates a tracer for the execution of a given slot
+    pub fn new(slot: Slot, block_id: Option<BlockId>) -> Self {
+        ExecutionTracer {
+            trace: Mutex::new(SlotExecutionTrace {
+                slot,
+                block_id,
+                items: Vec::new(),
+            }),
+        }
+    }
+
+    /// Appends an item to the trace
+    pub fn record(&self, item: ExecutionTraceItem) {
+        self.trace.lock().items.push(item);
+    }
+
+    /// Takes the recorded trace, leaving an empty one in its place
+    pub fn take(&self) -> SlotExecutionTrace {
+        let mut trace = self.trace.lock();
+        SlotExecutionTrace {
+            slot: trace.slot,
+            block_id: trace.block_id,
+            items: std::mem::take(&mut trace.items),
+        }
+    }
+}
+
+/// Compact binary serializer for `SlotExecutionTrace`
+pub struct SlotExecutionTraceSerializer {
+    u64_serializer: U64VarIntSerializer,
+    slot_serializer: SlotSerializer,
+    block_id_serializer: BlockIdSerializer,
+    operation_id_serializer: OperationIdSerializer,
+    address_serializer: AddressSerializer,
+    amount_serializer: AmountSerializer,
+    vec_u8_serializer: VecU8Serializer,
+}
+
+impl SlotExecutionTraceSerializer {
+    /// Creates a new `SlotExecutionTraceSerializer`
+    pub fn new() -> Self {
+        Self {
+            u64_serializer: U64VarIntSerializer::new(),
+            slot_serializer: SlotSerializer::new(),
+            block_id_serializer: BlockIdSerializer::new(),
+            operation_id_serializer: OperationIdSerializer::new(),
+            address_serializer: AddressSerializer::new(),
+            amount_serializer: AmountSerializer::new(),
+            vec_u8_serializer: VecU8Serializer::new(),
+        }
+    }
+
+    fn serialize_str(&self, value: &str, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        self.vec_u8_serializer
+            .serialize(&value.as_bytes().to_vec(), buffer)
+    }
+
+    fn serialize_opt_address(
+        &self,
+        value: &Option<Address>,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        match value {
+            Some(addr) => {
+                buffer.push(1);
+                self.address_serializer.serialize(addr, buffer)
+            }
+            None => {
+                buffer.push(0);
+                Ok(())
+            }
+        }
+    }
+
+    fn serialize_ledger_access(
+        &self,
+        address: &Address,
+        kind: LedgerTraceKind,
+        key: &Option<Vec<u8>>,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.address_serializer.serialize(address, buffer)?;
+        buffer.push(kind.to_u8());
+        match key {
+            Some(key) => {
+                buffer.push(1);
+                self.vec_u8_serializer.serialize(key, buffer)
+            }
+            None => {
+                buffer.push(0);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Default for SlotExecutionTraceSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer<SlotExecutionTrace> for SlotExecutionTraceSerializer {
+    fn serialize(
+        &self,
+        value: &SlotExecutionTrace,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.slot_serializer.serialize(&value.slot, buffer)?;
+        match &value.block_id {
+            Some(block_id) => {
+                buffer.push(1);
+                self.block_id_serializer.serialize(block_id, buffer)?;
+            }
+            None => buffer.push(0),
+        }
+        self.u64_serializer
+            .serialize(&(value.items.len() as u64), buffer)?;
+        for item in &value.items {
+            match item {
+                ExecutionTraceItem::AbiCall { name } => {
+                    buffer.push(0);
+                    self.serialize_str(name, buffer)?;
+                }
+                ExecutionTraceItem::LedgerRead { address, kind, key } => {
+                    buffer.push(1);
+                    self.serialize_ledger_access(address, *kind, key, buffer)?;
+                }
+                ExecutionTraceItem::LedgerWrite { address, kind, key } => {
+                    buffer.push(2);
+                    self.serialize_ledger_access(address, *kind, key, buffer)?;
+                }
+                ExecutionTraceItem::CoinTransfer { from, to, amount } => {
+                    buffer.push(3);
+                    self.serialize_opt_address(from, buffer)?;
+                    self.serialize_opt_address(to, buffer)?;
+                    self.amount_serializer.serialize(amount, buffer)?;
+                }
+                ExecutionTraceItem::GasCharge { label, amount } => {
+                    buffer.push(4);
+                    self.serialize_str(label, buffer)?;
+                    self.u64_serializer.serialize(amount, buffer)?;
+                }
+                ExecutionTraceItem::OperationCoinFlow {
+                    operation_id,
+                    edges,
+                } => {
+                    buffer.push(5);
+                    self.operation_id_serializer
+                        .serialize(operation_id, buffer)?;
+                    self.u64_serializer
+                        .serialize(&(edges.len() as u64), buffer)?;
+                    for edge in edges {
+                        self.serialize_opt_address(&edge.from, buffer)?;
+                        self.serialize_opt_address(&edge.to, buffer)?;
+                        buffer.push(coin_movement_kind_to_u8(edge.kind));
+                        self.amount_serializer.serialize(&edge.amount, buffer)?;
+                        self.u64_serializer.serialize(&edge.count, buffer)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Path of the dump file of a slot trace: `{period}_{thread}_{final|candidate}.{json|bin}`.
+/// A slot executed again as candidate (e.g. after a blockclique change) overwrites its previous candidate trace.
+pub fn trace_file_path(
+    dir: &Path,
+    slot: &Slot,
+    execution_final: bool,
+    format: ExecutionTraceFormat,
+) -> PathBuf {
+    let extension = match format {
+        ExecutionTraceFormat::Json => "json",
+        ExecutionTraceFormat::Binary => "bin",
+    };
+    let finality = if execution_final {
+        "final"
+    } else {
+        "candidate"
+    };
+    dir.join(format!(
+        "{}_{}_{}.{}",
+        slot.period, slot.thread, finality, extension
+    ))
+}
+
+/// Writes the trace of a slot to `dir` in the requested format.
+/// Failures are logged and otherwise ignored: tracing must never interfere with execution.
+pub fn dump_slot_trace(
+    trace: &SlotExecutionTrace,
+    dir: &Path,
+    format: ExecutionTraceFormat,
+    execution_final: bool,
+) {
+    let bytes = match format {
+        ExecutionTraceFormat::Json => match serde_json::to_vec(trace) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                warn!(
+                    "could not serialize execution trace of slot {}: {}",
+                    trace.slot, err
+                );
+                return;
+            }
+        },
+        ExecutionTraceFormat::Binary => {
+            let mut bytes = Vec::new();
+            if let Err(err) = SlotExecutionTraceSerializer::new().serialize(trace, &mut bytes) {
+                warn!(
+                    "could not serialize execution trace of slot {}: {}",
+                    trace.slot, err
+                );
+                return;
+            }
+            bytes
+        }
+    };
+    let path = trace_file_path(dir, &trace.slot, execution_final, format);
+    if let Err(err) = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, bytes)) {
+        warn!(
+            "could not write execution trace to {}: {}",
+            path.display(),
+            err
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_models::address::Address;
+    use std::str::FromStr;
+
+    fn sample_trace() -> SlotExecutionTrace {
+        let address =
+            Address::from_str("AU12dG5xP1RDEB5ocdHkymNVvvSJmUL9BgHwCksDowqmGWxfpm93x").unwrap();
+        let tracer = ExecutionTracer::new(Slot::new(3, 1), None);
+        tracer.record(ExecutionTraceItem::GasCharge {
+            label: "operation",
+            amount: 10,
+        });
+        tracer.record(ExecutionTraceItem::AbiCall {
+            name: "get_balance",
+        });
+        tracer.record(ExecutionTraceItem::LedgerRead {
+            address,
+            kind: LedgerTraceKind::DatastoreEntry,
+            key: Some(b"key".to_vec()),
+        });
+        tracer.record(ExecutionTraceItem::CoinTransfer {
+            from: None,
+            to: Some(address),
+            amount: Amount::from_str("1.5").unwrap(),
+        });
+        tracer.record(ExecutionTraceItem::OperationCoinFlow {
+            operation_id: OperationId::new(massa_hash::Hash::compute_from(b"operation")),
+            edges: vec![CoinFlowEdge {
+                from: Some(address),
+                to: None,
+                kind: CoinMovementKind::Fee,
+                amount: Amount::from_str("0.01").unwrap(),
+                count: 1,
+            }],
+        });
+        tracer.take()
+    }
+
+    #[test]
+    fn test_tracer_take_resets_items() {
+        let tracer = ExecutionTracer::new(Slot::new(1, 0), None);
+        tracer.record(ExecutionTraceItem::AbiCall { name: "print" });
+        assert_eq!(tracer.take().items.len(), 1);
+        assert!(tracer.take().items.is_empty());
+    }
+
+    #[test]
+    fn test_dump_slot_trace() {
+        let dir = tempfile::tempdir().unwrap();
+        let trace = sample_trace();
+
+        dump_slot_trace(&trace, dir.path(), ExecutionTraceFormat::Json, true);
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(dir.path().join("3_1_final.json")).unwrap())
+                .unwrap();
+        assert_eq!(json["items"].as_array().unwrap().len(), 5);
+        assert_eq!(json["items"][1]["type"], "abi_call");
+        assert_eq!(json["items"][1]["name"], "get_balance");
+        assert_eq!(json["items"][4]["type"], "operation_coin_flow");
+        assert_eq!(json["items"][4]["edges"][0]["kind"], "fee");
+
+        dump_slot_trace(&trace, dir.path(), ExecutionTraceFormat::Binary, true);
+        let mut expected = Vec::new();
+        SlotExecutionTraceSerializer::new()
+            .serialize(&trace, &mut expected)
+            .unwrap();
+        assert_eq!(
+            std::fs::read(dir.path().join("3_1_final.bin")).unwrap(),
+            expected
+        );
+
+        // the candidate execution of the same slot does not overwrite its final trace
+        let mut candidate_trace = sample_trace();
+        candidate_trace.items.truncate(1);
+        dump_slot_trace(
+            &candidate_trace,
+            dir.path(),
+            ExecutionTraceFormat::Json,
+            false,
+        );
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(dir.path().join("3_1_candidate.json")).unwrap())
+                .unwrap();
+        assert_eq!(json["items"].as_array().unwrap().len(), 1);
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(dir.path().join("3_1_final.json")).unwrap())
+                .unwrap();
+        assert_eq!(json["items"].as_array().unwrap().len(), 5);
+    }
+}
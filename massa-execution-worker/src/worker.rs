@@ -5,13 +5,16 @@
 //! orders active and final blocks in queues sorted by increasing slot number,
 //! and requests the execution of active and final slots from execution.rs.
 
+use crate::checkpoint::write_checkpoint;
 use crate::controller::{ExecutionControllerImpl, ExecutionInputData, ExecutionManagerImpl};
 use crate::execution::ExecutionState;
+use crate::lanes::{ExecutionLane, LaneMonitor};
 use crate::request_queue::RequestQueue;
-use crate::slot_sequencer::SlotSequencer;
+use crate::slot_sequencer::{SlotSequencer, SlotSequencerLoad};
 use massa_execution_exports::{
     ExecutionBlockMetadata, ExecutionChannels, ExecutionConfig, ExecutionController,
-    ExecutionError, ExecutionManager, ReadOnlyExecutionOutput, ReadOnlyExecutionRequest,
+    ExecutionError, ExecutionManager, OperationSimulationOutput, OperationSimulationRequest,
+    ReadOnlyExecutionOutput, ReadOnlyExecutionRequest, SlotSequencerStatus,
 };
 use massa_final_state::FinalStateController;
 use massa_metrics::MassaMetrics;
@@ -22,9 +25,12 @@ use massa_time::MassaTime;
 use massa_versioning::versioning::MipStore;
 use massa_wallet::Wallet;
 use parking_lot::{Condvar, Mutex, RwLock};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
-use tracing::debug;
+use std::time::Instant;
+use tracing::{debug, info, warn};
 
 /// Structure gathering all elements needed by the execution thread
 pub(crate) struct ExecutionThread {
@@ -36,8 +42,20 @@ pub(crate) struct ExecutionThread {
     execution_state: Arc<RwLock<ExecutionState>>,
     /// queue for read-only requests and response MPSCs to send back their outputs
     readonly_requests: RequestQueue<ReadOnlyExecutionRequest, ReadOnlyExecutionOutput>,
+    /// queue for operation simulation requests and response MPSCs to send back their outputs
+    simulation_requests: RequestQueue<OperationSimulationRequest, OperationSimulationOutput>,
     /// Selector controller
     selector: Box<dyn SelectorController>,
+    /// latest load of the slot sequencer reported to the execution state
+    sequencer_load: SlotSequencerLoad,
+    /// latest status of the slot sequencer reported to the execution state
+    sequencer_status: Option<SlotSequencerStatus>,
+    /// path of the execution checkpoint file
+    checkpoint_path: PathBuf,
+    /// raised by the controller when newly finalized blocks are notified, to preempt candidate slot execution
+    final_preemption: Arc<AtomicBool>,
+    /// monitor of the utilization of the final and candidate execution lanes
+    lane_monitor: LaneMonitor,
 }
 
 impl ExecutionThread {
@@ -48,11 +66,15 @@ impl ExecutionThread {
     /// * `config`: execution configuration
     /// * `input_data`: a copy of the input data interface to get incoming requests from
     /// * `execution_state`: an thread-safe shared access to the execution state, which can be bootstrapped or newly created
+    /// * `final_preemption`: flag raised by the controller to preempt candidate slot execution
+    /// * `massa_metrics`: metrics to which the utilization of the execution lanes is reported
     pub fn new(
         config: ExecutionConfig,
         input_data: Arc<(Condvar, Mutex<ExecutionInputData>)>,
         execution_state: Arc<RwLock<ExecutionState>>,
         selector: Box<dyn SelectorController>,
+        final_preemption: Arc<AtomicBool>,
+        massa_metrics: MassaMetrics,
     ) -> Self {
         // get the latest executed final slot, at the output of which the final ledger is attached
         // if we are restarting the network, use last genesis slot of the last start.
@@ -65,14 +87,87 @@ impl ExecutionThread {
             },
         );
 
+        if !config.candidate_execution_enabled {
+            info!("candidate execution is disabled: only final slots will be executed");
+        }
+
+        // let the slot sequencer skip the candidate slots restored from a checkpoint
+        let checkpoint_path = config.execution_checkpoint_path.clone();
+        let mut slot_sequencer = SlotSequencer::new(config.clone(), final_cursor);
+        {
+            let execution_state = execution_state.read();
+            if execution_state.final_cursor == final_cursor {
+                slot_sequencer
+                    .restore_candidate_history(execution_state.get_active_history_blocks());
+            }
+        }
+
         // create and return the ExecutionThread
         ExecutionThread {
             input_data,
             readonly_requests: RequestQueue::new(config.readonly_queue_length),
+            simulation_requests: RequestQueue::new(config.readonly_queue_length),
             execution_state,
-            slot_sequencer: SlotSequencer::new(config, final_cursor),
+            slot_sequencer,
             selector,
+            sequencer_load: Default::default(),
+            sequencer_status: None,
+            checkpoint_path,
+            final_preemption,
+            lane_monitor: LaneMonitor::new(config.stats_time_window_duration, massa_metrics),
+        }
+    }
+
+    /// Writes an execution checkpoint if one is due.
+    /// The checkpoint is built under the execution lock but written to disk outside of it.
+    fn write_due_checkpoint(&mut self) {
+        let checkpoint = self.execution_state.write().take_due_checkpoint();
+        if let Some(checkpoint) = checkpoint {
+            if let Err(err) = write_checkpoint(&self.checkpoint_path, &checkpoint) {
+                warn!("could not write execution checkpoint: {}", err);
+            } else {
+                debug!(
+                    "execution checkpoint written at final slot {}",
+                    checkpoint.final_cursor
+                );
+            }
+        }
+    }
+
+    /// Reports the load of the slot sequencer to the execution state when it changes,
+    /// so that the API layer knows whether speculative results are stale.
+    fn update_sequencer_load(&mut self) {
+        let load = self.slot_sequencer.get_load();
+        if load == self.sequencer_load {
+            return;
+        }
+        // speculative results are always stale when candidate execution is disabled
+        if self.slot_sequencer.is_candidate_execution_enabled() {
+            if load.speculative_stale && !self.sequencer_load.speculative_stale {
+                warn!(
+                    "candidate execution lags {} slots behind: suspending speculative execution until final execution catches up ({} final slots pending)",
+                    load.candidate_lag, load.final_backlog
+                );
+            } else if !load.speculative_stale && self.sequencer_load.speculative_stale {
+                info!("candidate execution caught up: resuming speculative execution");
+            }
+        }
+        self.sequencer_load = load;
+        self.execution_state.write().set_sequencer_load(load);
+    }
+
+    /// Reports the status and the slot miss statistics of the slot sequencer to the execution state when the status changes,
+    /// so that operators can inspect them through the API.
+    fn update_sequencer_status(&mut self) {
+        let status = self.slot_sequencer.get_status();
+        if self.sequencer_status.as_ref() == Some(&status) {
+            return;
         }
+        let mut execution_state = self.execution_state.write();
+        execution_state.set_sequencer_status(status.clone());
+        execution_state.set_slot_miss_stats(self.slot_sequencer.get_slot_miss_stats());
+        drop(execution_state);
+        self.sequencer_status = Some(status);
     }
 
     /// Append incoming read-only requests to the relevant queue,
@@ -108,6 +203,24 @@ impl ExecutionThread {
         false
     }
 
+    /// Executes an operation simulation request from the queue, if any.
+    /// The result of the simulation is sent asynchronously through the response channel provided with the request.
+    ///
+    /// # Returns
+    /// true if a request was executed, false otherwise
+    fn execute_one_simulation_request(&mut self) -> bool {
+        if let Some(req_resp) = self.simulation_requests.pop() {
+            let (req, resp_tx) = req_resp.into_request_sender_pair();
+            let outcome = self.execution_state.read().simulate_operation(req);
+
+            // Ignore errors because they just mean that the request emitter dropped the receiver
+            let _ = resp_tx.send(outcome);
+
+            return true;
+        }
+        false
+    }
+
     /// Waits for an event to trigger a new iteration in the execution main loop.
     ///
     /// # Returns
@@ -121,6 +234,9 @@ impl ExecutionThread {
             // take current input data, resetting it
             let input_data: ExecutionInputData = input_data_lock.take();
 
+            // the finalized blocks that raised the preemption flag are now taken into account
+            self.final_preemption.store(false, Ordering::Release);
+
             // if we need to stop, return None
             if input_data.stop {
                 return (input_data, true);
@@ -131,6 +247,7 @@ impl ExecutionThread {
                 || !input_data.finalized_blocks.is_empty()
                 || !input_data.block_metadata.is_empty()
                 || !input_data.readonly_requests.is_empty()
+                || !input_data.simulation_requests.is_empty()
             {
                 return (input_data, false);
             }
@@ -140,8 +257,8 @@ impl ExecutionThread {
                 return (input_data, false);
             }
 
-            // there are read-only requests ready
-            if !self.readonly_requests.is_empty() {
+            // there are read-only or simulation requests ready
+            if !self.readonly_requests.is_empty() || !self.simulation_requests.is_empty() {
                 return (input_data, false);
             }
 
@@ -178,6 +295,8 @@ impl ExecutionThread {
 
             // update the sequence of read-only requests
             self.update_readonly_requests(input_data.readonly_requests);
+            self.simulation_requests
+                .extend(input_data.simulation_requests);
 
             if stop {
                 // we need to stop
@@ -191,33 +310,54 @@ impl ExecutionThread {
                 input_data.block_metadata,
             );
 
+            // report the sequencer load and status
+            self.update_sequencer_load();
+            self.update_sequencer_status();
+
             // ask the slot sequencer for a task to be executed in priority (final is higher priority than candidate)
             let run_result = self.slot_sequencer.run_task_with(
                 |is_final: bool,
                  slot: &Slot,
                  content: Option<&(BlockId, ExecutionBlockMetadata)>| {
+                    let start = Instant::now();
                     if is_final {
                         self.execution_state.write().execute_final_slot(
                             slot,
                             content,
                             self.selector.clone(),
                         );
+                        (ExecutionLane::Final, *slot, start, true)
                     } else {
-                        self.execution_state.write().execute_candidate_slot(
+                        // candidate execution gives way to final execution as soon as new blocks are finalized
+                        let completed = self.execution_state.write().execute_candidate_slot(
                             slot,
                             content,
                             self.selector.clone(),
+                            Some(&self.final_preemption),
                         );
+                        (ExecutionLane::Candidate, *slot, start, completed)
                     }
                 },
             );
-            if let Some(_res) = run_result {
-                // A slot was executed: continue.
+            if let Some((lane, slot, start, completed)) = run_result {
+                self.lane_monitor
+                    .record_execution(lane, start, Instant::now(), !completed);
+                if !completed {
+                    // the preempted candidate slot will be executed again after the final slots
+                    self.slot_sequencer.cancel_candidate_execution(&slot);
+                }
+
+                // A slot was executed: report the moved cursors, checkpoint if needed and continue.
+                self.update_sequencer_status();
+                self.write_due_checkpoint();
                 continue;
             }
 
             // low priority: execute a read-only request (note that the queue is of finite length), if there is one ready.
-            self.execute_one_readonly_request();
+            // Operation simulations come next.
+            if !self.execute_one_readonly_request() {
+                self.execute_one_simulation_request();
+            }
         }
 
         // We are quitting the loop.
@@ -226,12 +366,13 @@ impl ExecutionThread {
         let cancel_err = ExecutionError::ChannelError(
             "readonly execution cancelled because the execution worker is closing".into(),
         );
-        self.input_data
-            .1
-            .lock()
-            .take()
-            .readonly_requests
-            .cancel(cancel_err);
+        let mut input_data = self.input_data.1.lock().take();
+        input_data.readonly_requests.cancel(cancel_err);
+        input_data
+            .simulation_requests
+            .cancel(ExecutionError::ChannelError(
+                "operation simulation cancelled because the execution worker is closing".into(),
+            ));
     }
 }
 
@@ -262,7 +403,7 @@ pub fn start_execution_worker(
         selector.clone(),
         channels,
         wallet,
-        massa_metrics,
+        massa_metrics.clone(),
     )));
 
     // define the input data interface
@@ -271,10 +412,14 @@ pub fn start_execution_worker(
         Mutex::new(ExecutionInputData::new(config.clone())),
     ));
 
+    // define the flag preempting candidate execution when blocks are finalized
+    let final_preemption = Arc::new(AtomicBool::new(false));
+
     // create a controller
     let controller = ExecutionControllerImpl {
         input_data: input_data.clone(),
         execution_state: execution_state.clone(),
+        final_preemption: final_preemption.clone(),
     };
 
     // launch the execution thread
@@ -282,7 +427,15 @@ pub fn start_execution_worker(
     let thread_builder = thread::Builder::new().name("execution".into());
     let thread_handle = thread_builder
         .spawn(move || {
-            ExecutionThread::new(config, input_data_clone, execution_state, selector).main_loop();
+            ExecutionThread::new(
+                config,
+                input_data_clone,
+                execution_state,
+                selector,
+                final_preemption,
+                massa_metrics,
+            )
+            .main_loop();
         })
         .expect("failed to spawn thread : execution");
     // create a manager
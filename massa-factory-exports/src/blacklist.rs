@@ -0,0 +1,126 @@
+//! Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! This file defines the windows during which the factory must not produce
+
+use std::sync::Arc;
+
+use massa_models::slot::Slot;
+use massa_time::MassaTime;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::{FactoryError, FactoryResult};
+
+/// Window during which the factory must not produce blocks nor endorsements
+/// (e.g. maintenance or key migration). Both bounds are included.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductionBlacklistWindow {
+    /// Range of slot timestamps
+    Time {
+        /// start timestamp
+        start: MassaTime,
+        /// end timestamp
+        end: MassaTime,
+    },
+    /// Range of slots
+    Slots {
+        /// first slot
+        start: Slot,
+        /// last slot
+        end: Slot,
+    },
+}
+
+impl ProductionBlacklistWindow {
+    /// Checks that the window is not empty
+    pub fn check(&self) -> FactoryResult<()> {
+        let is_empty = match self {
+            ProductionBlacklistWindow::Time { start, end } => start > end,
+            ProductionBlacklistWindow::Slots { start, end } => start > end,
+        };
+        if is_empty {
+            return Err(FactoryError::GenericError(format!(
+                "production blacklist window {} ends before it starts",
+                self
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns true if production at the given slot, whose timestamp is `slot_timestamp`, falls in the window
+    pub fn contains(&self, slot: &Slot, slot_timestamp: MassaTime) -> bool {
+        match self {
+            ProductionBlacklistWindow::Time { start, end } => {
+                *start <= slot_timestamp && slot_timestamp <= *end
+            }
+            ProductionBlacklistWindow::Slots { start, end } => start <= slot && slot <= end,
+        }
+    }
+}
+
+impl std::fmt::Display for ProductionBlacklistWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProductionBlacklistWindow::Time { start, end } => {
+                write!(f, "time [{}, {}]", start.as_millis(), end.as_millis())
+            }
+            ProductionBlacklistWindow::Slots { start, end } => {
+                write!(f, "slots [{}, {}]", start, end)
+            }
+        }
+    }
+}
+
+/// Production blacklist shared between the factory workers and the private API,
+/// so that the windows can be updated at runtime without stopping the node.
+/// Windows added at runtime are not persisted.
+#[derive(Debug, Clone, Default)]
+pub struct ProductionBlacklist(Arc<RwLock<Vec<ProductionBlacklistWindow>>>);
+
+impl ProductionBlacklist {
+    /// Creates a production blacklist from a list of windows
+    pub fn new(windows: Vec<ProductionBlacklistWindow>) -> FactoryResult<Self> {
+        let blacklist = ProductionBlacklist::default();
+        blacklist.add_windows(windows)?;
+        Ok(blacklist)
+    }
+
+    /// Gets the current windows
+    pub fn get_windows(&self) -> Vec<ProductionBlacklistWindow> {
+        self.0.read().clone()
+    }
+
+    /// Adds windows, ignoring the ones already present.
+    /// Nothing is added if one of the windows is invalid.
+    pub fn add_windows(&self, windows: Vec<ProductionBlacklistWindow>) -> FactoryResult<()> {
+        for window in windows.iter() {
+            window.check()?;
+        }
+        let mut current = self.0.write();
+        for window in windows {
+            if !current.contains(&window) {
+                current.push(window);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes windows, ignoring the ones that are not present
+    pub fn remove_windows(&self, windows: &[ProductionBlacklistWindow]) {
+        self.0.write().retain(|window| !windows.contains(window));
+    }
+
+    /// Returns the first window preventing production at the given slot, if any
+    pub fn find_window(
+        &self,
+        slot: &Slot,
+        slot_timestamp: MassaTime,
+    ) -> Option<ProductionBlacklistWindow> {
+        self.0
+            .read()
+            .iter()
+            .find(|window| window.contains(slot, slot_timestamp))
+            .cloned()
+    }
+}
@@ -2,6 +2,7 @@
 
 //! This file defines the factory settings
 
+use massa_models::amount::Amount;
 use massa_time::MassaTime;
 
 /// Structure defining the settings of the factory
@@ -29,4 +30,8 @@ pub struct FactoryConfig {
     pub denunciation_expire_periods: u64,
     /// choose whether to stop production when zero connections on protocol
     pub stop_production_when_zero_connections: bool,
+    /// reward of a block, to estimate the revenue of the produced blocks and endorsements
+    pub block_reward: Amount,
+    /// number of endorsements per block
+    pub endorsement_count: u32,
 }
@@ -6,14 +6,21 @@
 
 #![warn(missing_docs)]
 
+mod blacklist;
 mod config;
 mod controller_traits;
 mod error;
+mod revenue;
 mod types;
 
+pub use blacklist::{ProductionBlacklist, ProductionBlacklistWindow};
 pub use config::FactoryConfig;
 pub use controller_traits::FactoryManager;
 pub use error::*;
+pub use revenue::{
+    block_producer_share, endorsement_reward, DailyOperatorRevenue, OperatorRevenue,
+    OperatorRevenueEntry, OperatorRevenueReport, RevenueSource,
+};
 pub use types::*;
 
 /// Tests utils
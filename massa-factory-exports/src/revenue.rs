@@ -0,0 +1,557 @@
+//! Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! This file defines the accounting of the revenue of the blocks and endorsements produced by the node.
+//!
+//! The revenue is estimated when the blocks and endorsements are produced, following the reward split of the execution:
+//! the credits of a block (block reward and operation fees) are divided in `3 * (1 + endorsement_count)` parts,
+//! the creator of each included endorsement and the creator of the endorsed block get one part per endorsement,
+//! and the block producer gets the rest.
+//! The estimates do not check that the blocks become final nor that the endorsements are included in a block:
+//! the endorsement rewards are expected rewards, recorded when the endorsements are produced,
+//! and only account for the block reward, the fees of the endorsing block being unknown.
+//!
+//! The entries are kept in memory and appended to a file of JSON lines by a dedicated thread,
+//! so that the production threads never wait for the disk.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs::{create_dir_all, rename, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+use massa_models::{
+    address::Address, amount::Amount, block_id::BlockId, endorsement::EndorsementId, slot::Slot,
+};
+use massa_time::MassaTime;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{FactoryError, FactoryResult};
+
+/// Duration of a day of the daily report, in milliseconds
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+/// Maximum number of recorded entries waiting to be written to the revenue file
+const WRITE_QUEUE_SIZE: usize = 1024;
+
+/// Produced item a revenue comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevenueSource {
+    /// Block produced by the node
+    Block {
+        /// id of the block
+        block_id: BlockId,
+    },
+    /// Endorsement produced by the node, whose reward is expected: it is recorded even if the endorsement is never included
+    Endorsement {
+        /// id of the endorsement
+        endorsement_id: EndorsementId,
+    },
+}
+
+/// Revenue of a block or endorsement produced by the node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperatorRevenueEntry {
+    /// slot of the produced item
+    pub slot: Slot,
+    /// timestamp of the slot
+    pub timestamp: MassaTime,
+    /// staking address that produced the item
+    pub address: Address,
+    /// produced item
+    pub source: RevenueSource,
+    /// share of the operation fees earned by the block producer (zero for endorsements)
+    pub fees: Amount,
+    /// share of the block reward earned by the producer, expected for endorsements
+    pub reward: Amount,
+}
+
+/// Revenue of the produced blocks and endorsements whose slot timestamp falls in a day (UTC)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyOperatorRevenue {
+    /// timestamp of the start of the day
+    pub day_start: MassaTime,
+    /// number of produced blocks
+    pub block_count: u64,
+    /// number of produced endorsements
+    pub endorsement_count: u64,
+    /// fees earned by producing blocks
+    pub fees: Amount,
+    /// block rewards earned by producing blocks
+    pub block_rewards: Amount,
+    /// rewards expected from producing endorsements, whether or not they were included in a block
+    pub expected_endorsement_rewards: Amount,
+}
+
+impl DailyOperatorRevenue {
+    /// Total revenue of the day, including the expected endorsement rewards
+    pub fn total(&self) -> Amount {
+        self.fees
+            .saturating_add(self.block_rewards)
+            .saturating_add(self.expected_endorsement_rewards)
+    }
+}
+
+/// Revenue of the node over a time range
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperatorRevenueReport {
+    /// revenue of each produced block and endorsement, from oldest to latest
+    pub entries: Vec<OperatorRevenueEntry>,
+    /// revenue per day, from oldest to latest
+    pub daily: Vec<DailyOperatorRevenue>,
+}
+
+impl OperatorRevenueReport {
+    /// Formats the daily revenue as CSV, with a header line
+    pub fn daily_csv(&self) -> String {
+        let mut csv = String::from(
+            "day_start,block_count,endorsement_count,fees,block_rewards,expected_endorsement_rewards,total\n",
+        );
+        for day in self.daily.iter() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                day.day_start.as_millis(),
+                day.block_count,
+                day.endorsement_count,
+                day.fees,
+                day.block_rewards,
+                day.expected_endorsement_rewards,
+                day.total()
+            ));
+        }
+        csv
+    }
+}
+
+/// Estimated share of an amount credited to a block that goes to the block producer,
+/// given the number of endorsements included in the block
+pub fn block_producer_share(
+    amount: Amount,
+    endorsement_count: u32,
+    included_endorsements: usize,
+) -> Amount {
+    let part = amount
+        .checked_div_u64(3 * (1 + endorsement_count as u64))
+        .unwrap_or_default();
+    amount.saturating_sub(part.saturating_mul_u64(2 * included_endorsements as u64))
+}
+
+/// Estimated reward of an endorsement, once included in a block
+pub fn endorsement_reward(block_reward: Amount, endorsement_count: u32) -> Amount {
+    block_reward
+        .checked_div_u64(3 * (1 + endorsement_count as u64))
+        .unwrap_or_default()
+}
+
+/// Entries kept in memory
+struct RevenueStore {
+    /// maximum number of entries kept
+    max_entries: usize,
+    /// entries from oldest to latest
+    entries: VecDeque<OperatorRevenueEntry>,
+}
+
+/// Reads the entries of a revenue file, keeping the `max_entries` latest ones.
+/// Returns them with the number of lines of the file.
+/// Unreadable lines (e.g. the last line of a file truncated by a crash) are ignored.
+fn read_entries(
+    path: &Path,
+    max_entries: usize,
+) -> FactoryResult<(VecDeque<OperatorRevenueEntry>, usize)> {
+    let mut entries = VecDeque::new();
+    let mut line_count = 0;
+    if !path.exists() {
+        return Ok((entries, line_count));
+    }
+    let file = File::open(path).map_err(|err| {
+        FactoryError::GenericError(format!(
+            "could not read operator revenue file {}: {}",
+            path.display(),
+            err
+        ))
+    })?;
+    for line in BufReader::new(file).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        line_count += 1;
+        if let Ok(entry) = serde_json::from_str::<OperatorRevenueEntry>(&line) {
+            entries.push_back(entry);
+            if entries.len() > max_entries {
+                entries.pop_front();
+            }
+        }
+    }
+    Ok((entries, line_count))
+}
+
+/// Appends the recorded entries to the revenue file, on its own thread
+struct RevenueFileWriter {
+    /// file the entries are appended to
+    path: PathBuf,
+    /// maximum number of entries kept
+    max_entries: usize,
+    /// number of lines of the file, rewritten with the kept entries
+    /// when it reaches twice the maximum number of entries
+    file_entry_count: usize,
+}
+
+impl RevenueFileWriter {
+    /// Writes the entries until all the senders are dropped
+    fn run(mut self, receiver: Receiver<OperatorRevenueEntry>) {
+        while let Ok(entry) = receiver.recv() {
+            // write the entries queued meanwhile at once
+            let batch: Vec<OperatorRevenueEntry> =
+                std::iter::once(entry).chain(receiver.try_iter()).collect();
+            if let Err(err) = self.write(&batch) {
+                warn!("could not persist the operator revenue: {}", err);
+            }
+        }
+    }
+
+    fn write(&mut self, batch: &[OperatorRevenueEntry]) -> FactoryResult<()> {
+        let path = &self.path;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| io_error(path, err))?;
+        let mut writer = BufWriter::new(file);
+        for entry in batch {
+            writeln!(writer, "{}", to_json_line(entry)?).map_err(|err| io_error(path, err))?;
+        }
+        writer.flush().map_err(|err| io_error(path, err))?;
+        self.file_entry_count += batch.len();
+        if self.file_entry_count >= self.max_entries.saturating_mul(2) {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites the file with the kept entries only
+    fn compact(&mut self) -> FactoryResult<()> {
+        let path = &self.path;
+        let (entries, _line_count) = read_entries(path, self.max_entries)?;
+        let tmp_path = path.with_extension("tmp");
+        let mut writer =
+            BufWriter::new(File::create(&tmp_path).map_err(|err| io_error(&tmp_path, err))?);
+        for entry in entries.iter() {
+            writeln!(writer, "{}", to_json_line(entry)?).map_err(|err| io_error(&tmp_path, err))?;
+        }
+        writer.flush().map_err(|err| io_error(&tmp_path, err))?;
+        rename(&tmp_path, path).map_err(|err| io_error(path, err))?;
+        self.file_entry_count = entries.len();
+        Ok(())
+    }
+}
+
+/// Handle of the revenue file writer thread, stopped once the last handle is dropped
+struct RevenueFileWriterHandle {
+    sender: Option<SyncSender<OperatorRevenueEntry>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for RevenueFileWriterHandle {
+    fn drop(&mut self) {
+        // the writer stops once it has written the queued entries
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn to_json_line(entry: &OperatorRevenueEntry) -> FactoryResult<String> {
+    serde_json::to_string(entry).map_err(|err| {
+        FactoryError::GenericError(format!("could not serialize revenue entry: {}", err))
+    })
+}
+
+fn io_error(path: &Path, err: std::io::Error) -> FactoryError {
+    FactoryError::GenericError(format!(
+        "could not write operator revenue file {}: {}",
+        path.display(),
+        err
+    ))
+}
+
+/// Revenue of the blocks and endorsements produced by the node,
+/// recorded by the factory workers and read by the private API.
+#[derive(Clone)]
+pub struct OperatorRevenue {
+    store: Arc<RwLock<RevenueStore>>,
+    /// writer of the revenue file, `None` if the revenue is kept in memory only
+    writer: Option<Arc<RevenueFileWriterHandle>>,
+}
+
+impl Default for OperatorRevenue {
+    /// Revenue kept in memory only, without limit
+    fn default() -> Self {
+        OperatorRevenue::in_memory(usize::MAX)
+    }
+}
+
+impl OperatorRevenue {
+    /// Revenue kept in memory only, keeping the `max_entries` latest entries
+    pub fn in_memory(max_entries: usize) -> Self {
+        OperatorRevenue {
+            store: Arc::new(RwLock::new(RevenueStore {
+                max_entries,
+                entries: VecDeque::new(),
+            })),
+            writer: None,
+        }
+    }
+
+    /// Loads the revenue persisted in the file at `path`, creating it if needed,
+    /// keeping the `max_entries` latest entries,
+    /// and starts the thread appending the recorded entries to the file.
+    /// Unreadable lines (e.g. the last line of a file truncated by a crash) are ignored.
+    pub fn new(path: PathBuf, max_entries: usize) -> FactoryResult<Self> {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent).map_err(|err| io_error(&path, err))?;
+        }
+        let (entries, file_entry_count) = read_entries(&path, max_entries)?;
+        let file_writer = RevenueFileWriter {
+            path,
+            max_entries,
+            file_entry_count,
+        };
+        let (sender, receiver) = sync_channel(WRITE_QUEUE_SIZE);
+        let thread = std::thread::Builder::new()
+            .name("operator-revenue-writer".into())
+            .spawn(move || file_writer.run(receiver))
+            .map_err(|err| {
+                FactoryError::GenericError(format!(
+                    "could not start the operator revenue writer: {}",
+                    err
+                ))
+            })?;
+        Ok(OperatorRevenue {
+            store: Arc::new(RwLock::new(RevenueStore {
+                max_entries,
+                entries,
+            })),
+            writer: Some(Arc::new(RevenueFileWriterHandle {
+                sender: Some(sender),
+                thread: Some(thread),
+            })),
+        })
+    }
+
+    /// Records the revenue of a produced block or endorsement, forgetting the oldest entries beyond the limit.
+    /// The entry is queued to be persisted, and kept in memory even if the queue is full.
+    pub fn record(&self, entry: OperatorRevenueEntry) -> FactoryResult<()> {
+        {
+            let mut store = self.store.write();
+            store.entries.push_back(entry.clone());
+            while store.entries.len() > store.max_entries {
+                store.entries.pop_front();
+            }
+        }
+        let Some(sender) = self
+            .writer
+            .as_ref()
+            .and_then(|writer| writer.sender.as_ref())
+        else {
+            return Ok(());
+        };
+        sender.try_send(entry).map_err(|err| match err {
+            TrySendError::Full(_) => FactoryError::GenericError(
+                "the operator revenue file writer is lagging behind, entry not persisted".into(),
+            ),
+            TrySendError::Disconnected(_) => FactoryError::GenericError(
+                "the operator revenue file writer is stopped, entry not persisted".into(),
+            ),
+        })
+    }
+
+    /// Gets the revenue of the items whose slot timestamp is in the range (both bounds included)
+    pub fn get_report(
+        &self,
+        start: Option<MassaTime>,
+        end: Option<MassaTime>,
+    ) -> OperatorRevenueReport {
+        let entries: Vec<OperatorRevenueEntry> = self
+            .store
+            .read()
+            .entries
+            .iter()
+            .filter(|entry| {
+                start.map_or(true, |start| entry.timestamp >= start)
+                    && end.map_or(true, |end| entry.timestamp <= end)
+            })
+            .cloned()
+            .collect();
+        let mut daily: BTreeMap<u64, DailyOperatorRevenue> = BTreeMap::new();
+        for entry in entries.iter() {
+            let day_start = entry.timestamp.as_millis() - entry.timestamp.as_millis() % DAY_MILLIS;
+            let day = daily
+                .entry(day_start)
+                .or_insert_with(|| DailyOperatorRevenue {
+                    day_start: MassaTime::from_millis(day_start),
+                    block_count: 0,
+                    endorsement_count: 0,
+                    fees: Amount::zero(),
+                    block_rewards: Amount::zero(),
+                    expected_endorsement_rewards: Amount::zero(),
+                });
+            match entry.source {
+                RevenueSource::Block { .. } => {
+                    day.block_count += 1;
+                    day.fees = day.fees.saturating_add(entry.fees);
+                    day.block_rewards = day.block_rewards.saturating_add(entry.reward);
+                }
+                RevenueSource::Endorsement { .. } => {
+                    day.endorsement_count += 1;
+                    day.expected_endorsement_rewards = day
+                        .expected_endorsement_rewards
+                        .saturating_add(entry.reward);
+                }
+            }
+        }
+        OperatorRevenueReport {
+            entries,
+            daily: daily.into_values().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use massa_hash::Hash;
+    use massa_models::secure_share::Id;
+    use massa_signature::KeyPair;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn entry(index: u64, timestamp: u64) -> OperatorRevenueEntry {
+        let keypair = KeyPair::generate(0).unwrap();
+        let hash = Hash::compute_from(&index.to_be_bytes());
+        let (source, fees) = if index % 2 == 0 {
+            (
+                RevenueSource::Block {
+                    block_id: BlockId::new(hash),
+                },
+                Amount::from_raw(10),
+            )
+        } else {
+            (
+                RevenueSource::Endorsement {
+                    endorsement_id: EndorsementId::new(hash),
+                },
+                Amount::zero(),
+            )
+        };
+        OperatorRevenueEntry {
+            slot: Slot::new(index, 0),
+            timestamp: MassaTime::from_millis(timestamp),
+            address: Address::from_public_key(&keypair.get_public_key()),
+            source,
+            fees,
+            reward: Amount::from_raw(100 + index),
+        }
+    }
+
+    #[test]
+    fn test_revenue_reloaded_from_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("revenue.jsonl");
+        let entries: Vec<_> = (0..3).map(|index| entry(index, index * 1000)).collect();
+        let revenue = OperatorRevenue::new(path.clone(), 10).unwrap();
+        for entry in entries.iter() {
+            revenue.record(entry.clone()).unwrap();
+        }
+        // dropping the last handle waits for the queued entries to be written
+        drop(revenue);
+
+        let reloaded = OperatorRevenue::new(path, 10).unwrap();
+        assert_eq!(reloaded.get_report(None, None).entries, entries);
+    }
+
+    #[test]
+    fn test_revenue_compacted_at_max_entries() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("revenue.jsonl");
+        let max_entries = 2;
+        let entries: Vec<_> = (0..5).map(|index| entry(index, index * 1000)).collect();
+        let revenue = OperatorRevenue::new(path.clone(), max_entries).unwrap();
+        for entry in entries.iter() {
+            revenue.record(entry.clone()).unwrap();
+        }
+        assert_eq!(revenue.get_report(None, None).entries, entries[3..]);
+        drop(revenue);
+
+        let line_count = read_to_string(&path).unwrap().lines().count();
+        assert!(
+            line_count < 2 * max_entries,
+            "the file was not compacted: {} lines",
+            line_count
+        );
+        let reloaded = OperatorRevenue::new(path, max_entries).unwrap();
+        assert_eq!(reloaded.get_report(None, None).entries, entries[3..]);
+    }
+
+    #[test]
+    fn test_revenue_report_time_range() {
+        let revenue = OperatorRevenue::default();
+        let entries: Vec<_> = (0..5).map(|index| entry(index, index * 1000)).collect();
+        for entry in entries.iter() {
+            revenue.record(entry.clone()).unwrap();
+        }
+        // both bounds are included
+        let report = revenue.get_report(
+            Some(MassaTime::from_millis(1000)),
+            Some(MassaTime::from_millis(3000)),
+        );
+        assert_eq!(report.entries, entries[1..4]);
+        let report = revenue.get_report(Some(MassaTime::from_millis(3500)), None);
+        assert_eq!(report.entries, entries[4..]);
+        let report = revenue.get_report(None, Some(MassaTime::from_millis(500)));
+        assert_eq!(report.entries, entries[..1]);
+    }
+
+    #[test]
+    fn test_revenue_daily_csv() {
+        let revenue = OperatorRevenue::default();
+        // a block and an endorsement on the first day, a block on the next one
+        let first_day = [entry(0, 1000), entry(1, 2000)];
+        let next_day = entry(2, DAY_MILLIS + 1000);
+        for entry in first_day.iter().chain([&next_day]) {
+            revenue.record(entry.clone()).unwrap();
+        }
+        let report = revenue.get_report(None, None);
+        assert_eq!(report.daily.len(), 2);
+        assert_eq!(
+            report.daily_csv(),
+            format!(
+                "day_start,block_count,endorsement_count,fees,block_rewards,expected_endorsement_rewards,total\n\
+                 0,1,1,{},{},{},{}\n\
+                 {},1,0,{},{},{},{}\n",
+                first_day[0].fees,
+                first_day[0].reward,
+                first_day[1].reward,
+                first_day[0]
+                    .fees
+                    .saturating_add(first_day[0].reward)
+                    .saturating_add(first_day[1].reward),
+                DAY_MILLIS,
+                next_day.fees,
+                next_day.reward,
+                Amount::zero(),
+                next_day.fees.saturating_add(next_day.reward),
+            )
+        );
+    }
+}
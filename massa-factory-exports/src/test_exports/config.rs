@@ -18,6 +18,8 @@ impl Default for FactoryConfig {
             periods_per_cycle: PERIODS_PER_CYCLE,
             denunciation_expire_periods: DENUNCIATION_EXPIRE_PERIODS,
             stop_production_when_zero_connections: false,
+            block_reward: BLOCK_REWARD,
+            endorsement_count: ENDORSEMENT_COUNT,
         }
     }
 }
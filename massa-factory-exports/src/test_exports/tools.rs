@@ -18,6 +18,7 @@ pub fn create_empty_block(keypair: &KeyPair, slot: &Slot) -> SecureShareBlock {
             operation_merkle_root: Hash::compute_from(&Vec::new()),
             endorsements: Vec::new(),
             denunciations: vec![],
+            extensions: Vec::new(),
         },
         BlockHeaderSerializer::new(),
         keypair,
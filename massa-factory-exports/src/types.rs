@@ -5,6 +5,8 @@ use massa_pos_exports::SelectorController;
 use massa_protocol_exports::ProtocolController;
 use massa_storage::Storage;
 
+use crate::{OperatorRevenue, ProductionBlacklist};
+
 /// History of block production from latest to oldest
 /// todo: redesign type (maybe add slots, draws...)
 pub type ProductionHistory = Vec<Block>;
@@ -22,4 +24,8 @@ pub struct FactoryChannels {
     pub protocol: Box<dyn ProtocolController>,
     /// storage instance
     pub storage: Storage,
+    /// windows during which production is disabled, shared with the private API
+    pub production_blacklist: ProductionBlacklist,
+    /// revenue of the produced blocks and endorsements, shared with the private API
+    pub operator_revenue: OperatorRevenue,
 }
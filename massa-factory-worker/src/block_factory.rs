@@ -1,8 +1,11 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use massa_channel::receiver::MassaReceiver;
-use massa_factory_exports::{FactoryChannels, FactoryConfig};
+use massa_factory_exports::{
+    block_producer_share, FactoryChannels, FactoryConfig, OperatorRevenueEntry, RevenueSource,
+};
 use massa_models::{
+    amount::Amount,
     block::{Block, BlockSerializer},
     block_header::{BlockHeader, BlockHeaderSerializer, SecuredHeader},
     block_id::BlockId,
@@ -159,6 +162,41 @@ impl BlockFactoryWorker {
             }
         }
 
+        // check that production is not disabled by the operator for that slot
+        let slot_timestamp = match get_block_slot_timestamp(
+            self.cfg.thread_count,
+            self.cfg.t0,
+            self.cfg.genesis_timestamp,
+            slot,
+        ) {
+            Ok(timestamp) => timestamp,
+            Err(err) => {
+                warn!(
+                    "block factory could not get the timestamp of slot {}: {}",
+                    slot, err
+                );
+                return;
+            }
+        };
+        if let Some(window) = self
+            .channels
+            .production_blacklist
+            .find_window(&slot, slot_timestamp)
+        {
+            info!(
+                "block factory skipped production of a block at slot {} with address {} because of production blacklist window {}",
+                slot, block_producer_addr, window
+            );
+            return;
+        }
+        if let Some(reorg) = self.channels.consensus.get_held_reorg() {
+            warn!(
+                "block factory skipped production of a block at slot {} with address {} because a deep reorganization is held: {}",
+                slot, block_producer_addr, reorg
+            );
+            return;
+        }
+
         // check if we need to have connections to produce a block and in this case, check if we have enough.
         #[cfg(not(feature = "sandbox"))]
         if self.cfg.stop_production_when_zero_connections {
@@ -206,7 +244,18 @@ impl BlockFactoryWorker {
             return;
         }
 
+        // sum the fees of the operations, to estimate the revenue of the block
+        let fees = {
+            let ops_read = op_storage.read_operations();
+            op_ids
+                .iter()
+                .filter_map(|op_id| ops_read.get(op_id))
+                .fold(Amount::zero(), |fees, op| {
+                    fees.saturating_add(op.content.fee)
+                })
+        };
         block_storage.extend(op_storage);
+        let included_endorsements = endorsements.len();
 
         // create header
         let current_version = self.mip_store.get_network_version_current();
@@ -220,6 +269,7 @@ impl BlockFactoryWorker {
                 operation_merkle_root: compute_operations_hash(&op_ids, &self.op_id_serializer),
                 endorsements,
                 denunciations: self.channels.pool.get_block_denunciations(&slot),
+                extensions: Vec::new(),
             },
             BlockHeaderSerializer::new(), // TODO reuse self.block_header_serializer
             block_producer_keypair,
@@ -251,6 +301,25 @@ impl BlockFactoryWorker {
         self.channels
             .consensus
             .register_block(block_id, slot, block_storage, true);
+
+        // record the estimated revenue of the block
+        if let Err(err) = self.channels.operator_revenue.record(OperatorRevenueEntry {
+            slot,
+            timestamp: slot_timestamp,
+            address: block_producer_addr,
+            source: RevenueSource::Block { block_id },
+            fees: block_producer_share(fees, self.cfg.endorsement_count, included_endorsements),
+            reward: block_producer_share(
+                self.cfg.block_reward,
+                self.cfg.endorsement_count,
+                included_endorsements,
+            ),
+        }) {
+            warn!(
+                "block factory could not record the revenue of block {}: {}",
+                block_id, err
+            );
+        }
     }
 
     /// main run loop of the block creator thread
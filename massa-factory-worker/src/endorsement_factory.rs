@@ -1,8 +1,12 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use massa_channel::receiver::MassaReceiver;
-use massa_factory_exports::{FactoryChannels, FactoryConfig};
+use massa_factory_exports::{
+    endorsement_reward, FactoryChannels, FactoryConfig, OperatorRevenueEntry, RevenueSource,
+};
 use massa_models::{
+    address::Address,
+    amount::Amount,
     block_id::BlockId,
     endorsement::{Endorsement, EndorsementSerializer, SecureShareEndorsement},
     secure_share::SecureShareContent,
@@ -13,8 +17,13 @@ use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use massa_wallet::Wallet;
 use parking_lot::RwLock;
-use std::{sync::Arc, thread, time::Instant};
-use tracing::{debug, warn};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    thread,
+    time::Instant,
+};
+use tracing::{debug, info, warn};
 
 /// Structure gathering all elements needed by the factory thread
 pub(crate) struct EndorsementFactoryWorker {
@@ -24,6 +33,8 @@ pub(crate) struct EndorsementFactoryWorker {
     factory_receiver: MassaReceiver<()>,
     half_t0: MassaTime,
     endorsement_serializer: EndorsementSerializer,
+    /// block endorsed by our keys at each index of the recently processed slots
+    produced_endorsements: BTreeMap<Slot, HashMap<u32, BlockId>>,
 }
 
 impl EndorsementFactoryWorker {
@@ -48,6 +59,7 @@ impl EndorsementFactoryWorker {
                     channels,
                     factory_receiver,
                     endorsement_serializer: EndorsementSerializer::new(),
+                    produced_endorsements: BTreeMap::new(),
                 };
                 this.run();
             })
@@ -157,6 +169,45 @@ impl EndorsementFactoryWorker {
             return;
         }
 
+        // check that production is not disabled by the operator for that slot
+        let slot_timestamp = match get_block_slot_timestamp(
+            self.cfg.thread_count,
+            self.cfg.t0,
+            self.cfg.genesis_timestamp,
+            slot,
+        ) {
+            Ok(timestamp) => timestamp,
+            Err(err) => {
+                warn!(
+                    "endorsement factory could not get the timestamp of slot {}: {}",
+                    slot, err
+                );
+                return;
+            }
+        };
+        if let Some(window) = self
+            .channels
+            .production_blacklist
+            .find_window(&slot, slot_timestamp)
+        {
+            info!(
+                "endorsement factory skipped production of {} endorsement(s) at slot {} because of production blacklist window {}",
+                producers_indices.len(),
+                slot,
+                window
+            );
+            return;
+        }
+        if let Some(reorg) = self.channels.consensus.get_held_reorg() {
+            warn!(
+                "endorsement factory skipped production of {} endorsement(s) at slot {} because a deep reorganization is held: {}",
+                producers_indices.len(),
+                slot,
+                reorg
+            );
+            return;
+        }
+
         // check if we need to have connections to produce a block and in this case, check if we have enough.
         #[cfg(not(feature = "sandbox"))]
         if self.cfg.stop_production_when_zero_connections {
@@ -174,6 +225,54 @@ impl EndorsementFactoryWorker {
             .consensus
             .get_latest_blockclique_block_at_slot(slot);
 
+        // produce a single endorsement per index: skip the indices already endorsed by one of our keys,
+        // either by this factory or by another node staking with the same keys
+        let (pool_endorsements, _pool_storage) = self
+            .channels
+            .pool
+            .get_block_endorsements(&endorsed_block, &slot);
+        let produced = self.produced_endorsements.entry(slot).or_default();
+        producers_indices.retain(|(keypair, index)| {
+            let index = *index as u32;
+            if let Some(block_id) = produced.get(&index) {
+                if block_id == &endorsed_block {
+                    massa_metrics::inc_endorsement_production_conflict("already_produced");
+                } else {
+                    warn!(
+                        "endorsement factory refused to endorse block {} at slot {} index {} with address {}: block {} was already endorsed at that index",
+                        endorsed_block,
+                        slot,
+                        index,
+                        Address::from_public_key(&keypair.get_public_key()),
+                        block_id
+                    );
+                    massa_metrics::inc_endorsement_production_conflict("equivocation_avoided");
+                }
+                return false;
+            }
+            if matches!(pool_endorsements.get(index as usize), Some(Some(_))) {
+                debug!(
+                    "endorsement factory skipped index {} at slot {}: already endorsed in pool",
+                    index, slot
+                );
+                massa_metrics::inc_endorsement_production_conflict("already_in_pool");
+                return false;
+            }
+            true
+        });
+        for (_, index) in producers_indices.iter() {
+            produced.insert(*index as u32, endorsed_block);
+        }
+
+        // forget the slots that can not be processed again
+        let oldest_kept_slot = Slot::new(slot.period.saturating_sub(1), slot.thread);
+        self.produced_endorsements = self.produced_endorsements.split_off(&oldest_kept_slot);
+
+        // quit if everything was already produced
+        if producers_indices.is_empty() {
+            return;
+        }
+
         // produce endorsements
         let mut endorsements: Vec<SecureShareEndorsement> =
             Vec::with_capacity(producers_indices.len());
@@ -198,6 +297,26 @@ impl EndorsementFactoryWorker {
             endorsements.push(endorsement);
         }
 
+        // record the estimated revenue of the endorsements
+        let reward = endorsement_reward(self.cfg.block_reward, self.cfg.endorsement_count);
+        for endorsement in endorsements.iter() {
+            if let Err(err) = self.channels.operator_revenue.record(OperatorRevenueEntry {
+                slot,
+                timestamp: slot_timestamp,
+                address: endorsement.content_creator_address,
+                source: RevenueSource::Endorsement {
+                    endorsement_id: endorsement.id,
+                },
+                fees: Amount::zero(),
+                reward,
+            }) {
+                warn!(
+                    "endorsement factory could not record the revenue of endorsement {}: {}",
+                    endorsement.id, err
+                );
+            }
+        }
+
         // store endorsements
         let mut endo_storage = self.channels.storage.clone_without_refs();
         endo_storage.store_endorsements(endorsements);
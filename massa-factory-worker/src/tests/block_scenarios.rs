@@ -2,12 +2,13 @@ use std::{str::FromStr, sync::Arc};
 
 use super::BlockTestFactory;
 use massa_consensus_exports::MockConsensusController;
+use massa_factory_exports::{ProductionBlacklist, ProductionBlacklistWindow, RevenueSource};
 use massa_hash::Hash;
 use massa_models::{
     address::Address,
     amount::Amount,
     block_id::BlockId,
-    config::THREAD_COUNT,
+    config::{BLOCK_REWARD, THREAD_COUNT},
     operation::{Operation, OperationSerializer, OperationType},
     secure_share::SecureShareContent,
     slot::Slot,
@@ -39,6 +40,9 @@ fn basic_creation() {
     let pair = Arc::new((Mutex::new(false), Condvar::new()));
     let pair2 = pair.clone();
     let mut consensus_controller = Box::new(MockConsensusController::new());
+    consensus_controller
+        .expect_get_held_reorg()
+        .returning(|| None);
     consensus_controller
         .expect_get_best_parents()
         .times(1)
@@ -94,7 +98,57 @@ fn basic_creation() {
     test_factory.stop();
 }
 
-/// Creates a block with a roll buy operation in it.
+/// Checks that no block is produced during a production blacklist window.
+#[test]
+#[serial]
+fn no_creation_in_production_blacklist_window() {
+    let default_panic = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_panic(info);
+        std::process::exit(1);
+    }));
+    let keypair = KeyPair::generate(0).unwrap();
+    let storage = Storage::create_root();
+    let staking_address = Address::from_public_key(&keypair.get_public_key());
+    let pair = Arc::new((Mutex::new(false), Condvar::new()));
+    let pair2 = pair.clone();
+    // no expectations: the factory must not go further than the producer draw
+    let consensus_controller = Box::new(MockConsensusController::new());
+    let pool_controller = Box::new(MockPoolController::new());
+    let mut selector_controller = Box::new(MockSelectorController::new());
+    selector_controller
+        .expect_get_producer()
+        .times(1)
+        .return_once(move |_| {
+            let (lock, cvar) = &*pair2;
+            let mut started = lock.lock();
+            *started = true;
+            cvar.notify_one();
+            Ok(staking_address)
+        });
+    let production_blacklist = ProductionBlacklist::new(vec![ProductionBlacklistWindow::Slots {
+        start: Slot::new(1, 0),
+        end: Slot::new(1, THREAD_COUNT - 1),
+    }])
+    .unwrap();
+    let mut test_factory = BlockTestFactory::new_with_production_blacklist(
+        &keypair,
+        storage,
+        consensus_controller,
+        selector_controller,
+        pool_controller,
+        production_blacklist,
+    );
+    let (ref lock, ref cvar) = *pair;
+    let mut started = lock.lock();
+    if !*started {
+        cvar.wait(&mut started);
+    }
+    // joins the worker once it is done with the slot
+    test_factory.stop();
+}
+
+/// Creates a block with a roll buy operation in it, and records its revenue.
 #[test]
 #[serial]
 fn basic_creation_with_operation() {
@@ -150,6 +204,9 @@ fn basic_creation_with_operation() {
     let pair = Arc::new((Mutex::new(false), Condvar::new()));
     let pair2 = pair.clone();
     let mut consensus_controller = Box::new(MockConsensusController::new());
+    consensus_controller
+        .expect_get_held_reorg()
+        .returning(|| None);
     consensus_controller
         .expect_get_best_parents()
         .times(1)
@@ -181,4 +238,20 @@ fn basic_creation_with_operation() {
         cvar.wait(&mut started);
     }
     test_factory.stop();
+
+    // without included endorsements, the producer earns all the fees and the whole block reward
+    let report = test_factory.operator_revenue.get_report(None, None);
+    assert_eq!(report.entries.len(), 1);
+    let entry = &report.entries[0];
+    assert_eq!(entry.slot, Slot::new(1, 0));
+    assert_eq!(entry.address, staking_address);
+    assert!(matches!(entry.source, RevenueSource::Block { .. }));
+    assert_eq!(entry.fees, Amount::from_str("0.01").unwrap());
+    assert_eq!(entry.reward, BLOCK_REWARD);
+    assert_eq!(report.daily.len(), 1);
+    assert_eq!(report.daily[0].block_count, 1);
+    assert_eq!(
+        report.daily[0].total(),
+        BLOCK_REWARD.saturating_add(Amount::from_str("0.01").unwrap())
+    );
 }
@@ -7,6 +7,8 @@ use massa_models::{
     address::Address,
     block_id::BlockId,
     config::{ENDORSEMENT_COUNT, THREAD_COUNT},
+    endorsement::EndorsementId,
+    secure_share::Id,
     slot::Slot,
 };
 use massa_pool_exports::MockPoolController;
@@ -37,6 +39,9 @@ fn basic_creation() {
     let pair = Arc::new((Mutex::new(false), Condvar::new()));
     let pair2 = pair.clone();
     let mut consensus_controller = Box::new(MockConsensusController::new());
+    consensus_controller
+        .expect_get_held_reorg()
+        .returning(|| None);
     consensus_controller
         .expect_get_latest_blockclique_block_at_slot()
         .times(1)
@@ -56,6 +61,10 @@ fn basic_creation() {
             })
         });
     let mut pool_controller = Box::new(MockPoolController::new());
+    pool_controller
+        .expect_get_block_endorsements()
+        .times(1)
+        .returning(|_, _| (vec![], Storage::create_root()));
     pool_controller
         .expect_add_endorsements()
         .times(1)
@@ -94,3 +103,80 @@ fn basic_creation() {
     }
     test_factory.stop();
 }
+
+/// Does not produce the endorsements whose index was already endorsed in the pool.
+#[test]
+#[serial]
+fn skip_endorsements_already_in_pool() {
+    let keypair = KeyPair::generate(0).unwrap();
+    let storage = Storage::create_root();
+    let staking_address = Address::from_public_key(&keypair.get_public_key());
+    let parent = BlockId::generate_from_hash(Hash::compute_from("test".as_bytes()));
+    let pair = Arc::new((Mutex::new(false), Condvar::new()));
+    let pair2 = pair.clone();
+    let mut consensus_controller = Box::new(MockConsensusController::new());
+    consensus_controller
+        .expect_get_held_reorg()
+        .returning(|| None);
+    consensus_controller
+        .expect_get_latest_blockclique_block_at_slot()
+        .times(1)
+        .returning(move |_| parent);
+    let mut selector_controller = Box::new(MockSelectorController::new());
+    selector_controller
+        .expect_get_selection()
+        .times(1)
+        .returning(move |_| {
+            Ok(Selection {
+                producer: staking_address,
+                endorsements: vec![staking_address; ENDORSEMENT_COUNT as usize],
+            })
+        });
+    let mut pool_controller = Box::new(MockPoolController::new());
+    pool_controller
+        .expect_get_block_endorsements()
+        .times(1)
+        .returning(|_, _| {
+            // the first index was already endorsed, e.g. by another node staking with the same key
+            let mut endorsements = vec![None; ENDORSEMENT_COUNT as usize];
+            endorsements[0] = Some(EndorsementId::new(Hash::compute_from(b"endorsement")));
+            (endorsements, Storage::create_root())
+        });
+    pool_controller
+        .expect_add_endorsements()
+        .times(1)
+        .returning(|_| {});
+    let mut protocol_controller = Box::new(MockProtocolController::new());
+    protocol_controller
+        .expect_propagate_endorsements()
+        .times(1)
+        .returning(move |storage| {
+            let endorsements = storage.read_endorsements();
+            let indices: Vec<u32> = storage
+                .get_endorsement_refs()
+                .iter()
+                .map(|id| endorsements.get(id).unwrap().content.index)
+                .collect();
+            assert_eq!(indices.len(), ENDORSEMENT_COUNT as usize - 1);
+            assert!(!indices.contains(&0));
+            let (lock, cvar) = &*pair2;
+            let mut started = lock.lock();
+            *started = true;
+            cvar.notify_one();
+            Ok(())
+        });
+    let mut test_factory = EndorsementTestFactory::new(
+        &keypair,
+        storage,
+        consensus_controller,
+        selector_controller,
+        pool_controller,
+        protocol_controller,
+    );
+    let (lock, cvar) = &*pair;
+    let mut started = lock.lock();
+    if !*started {
+        cvar.wait(&mut started);
+    }
+    test_factory.stop();
+}
@@ -9,7 +9,10 @@ use parking_lot::RwLock;
 use std::sync::Arc;
 use std::thread::JoinHandle;
 
-use massa_factory_exports::{test_exports::create_empty_block, FactoryChannels, FactoryConfig};
+use massa_factory_exports::{
+    test_exports::create_empty_block, FactoryChannels, FactoryConfig, OperatorRevenue,
+    ProductionBlacklist,
+};
 use massa_models::{address::Address, block_id::BlockId, prehash::PreHashMap, slot::Slot};
 use massa_pool_exports::MockPoolController;
 use massa_pos_exports::MockSelectorController;
@@ -28,6 +31,8 @@ pub struct BlockTestFactory {
     _genesis_blocks: Vec<(BlockId, u64)>,
     pub(crate) _storage: Storage,
     _keypair: KeyPair,
+    /// revenue recorded by the factory
+    pub(crate) operator_revenue: OperatorRevenue,
 }
 
 impl BlockTestFactory {
@@ -38,11 +43,30 @@ impl BlockTestFactory {
     /// Returns
     /// - `TestFactory`: the structure that will be used to manage the tests
     pub fn new(
+        default_keypair: &KeyPair,
+        storage: Storage,
+        consensus_controller: Box<MockConsensusController>,
+        selector_controller: Box<MockSelectorController>,
+        pool_controller: Box<MockPoolController>,
+    ) -> BlockTestFactory {
+        Self::new_with_production_blacklist(
+            default_keypair,
+            storage,
+            consensus_controller,
+            selector_controller,
+            pool_controller,
+            ProductionBlacklist::default(),
+        )
+    }
+
+    /// Same as `new`, with production disabled during the windows of `production_blacklist`
+    pub fn new_with_production_blacklist(
         default_keypair: &KeyPair,
         mut storage: Storage,
         consensus_controller: Box<MockConsensusController>,
         selector_controller: Box<MockSelectorController>,
         pool_controller: Box<MockPoolController>,
+        production_blacklist: ProductionBlacklist,
     ) -> BlockTestFactory {
         let mut protocol_controller = Box::new(MockProtocolController::new());
         let block_protocol_controller = Box::new(MockProtocolController::new());
@@ -76,6 +100,7 @@ impl BlockTestFactory {
             MipStore::try_from(([], mip_stats_config)).expect("Cannot create an empty MIP store");
 
         let wallet = create_test_wallet(Some(accounts));
+        let operator_revenue = OperatorRevenue::default();
         let (tx, rx) = MassaChannel::new(String::from("test_block_factory"), None);
         let join_handle = BlockFactoryWorker::spawn(
             factory_config.clone(),
@@ -86,6 +111,8 @@ impl BlockTestFactory {
                 pool: pool_controller,
                 protocol: protocol_controller,
                 storage: storage.clone_without_refs(),
+                production_blacklist,
+                operator_revenue: operator_revenue.clone(),
             },
             rx,
             mip_store,
@@ -97,6 +124,7 @@ impl BlockTestFactory {
             _genesis_blocks: genesis_blocks,
             _storage: storage,
             _keypair: default_keypair.clone(),
+            operator_revenue,
         }
     }
 
@@ -160,6 +188,8 @@ impl EndorsementTestFactory {
                 pool: pool_controller,
                 protocol: protocol_controller,
                 storage: storage.clone_without_refs(),
+                production_blacklist: Default::default(),
+                operator_revenue: Default::default(),
             },
             rx,
         );
@@ -931,7 +931,10 @@ mod test {
     use tempfile::tempdir;
 
     use massa_async_pool::{AsyncMessage, AsyncPoolChanges, AsyncPoolConfig};
-    use massa_db_exports::{MassaDBConfig, MassaDBController, STATE_HASH_INITIAL_BYTES};
+    use massa_db_exports::{
+        MassaDBConfig, MassaDBController, STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+        STATE_HASH_INITIAL_BYTES,
+    };
     use massa_db_worker::MassaDB;
     use massa_executed_ops::{ExecutedDenunciationsConfig, ExecutedOpsConfig};
     use massa_hash::Hash;
@@ -1028,6 +1031,7 @@ mod test {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>
@@ -6,7 +6,9 @@ use crate::{
     FinalState, FinalStateConfig, StateChanges,
 };
 use massa_async_pool::{AsyncMessage, AsyncPoolChanges, AsyncPoolConfig};
-use massa_db_exports::{DBBatch, MassaDBConfig, MassaDBController};
+use massa_db_exports::{
+    DBBatch, MassaDBConfig, MassaDBController, STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+};
 use massa_db_worker::MassaDB;
 use massa_executed_ops::{ExecutedDenunciationsConfig, ExecutedOpsConfig};
 use massa_ledger_exports::{
@@ -40,6 +42,7 @@ fn create_final_state(temp_dir: &TempDir, reset_final_state: bool) -> Arc<RwLock
         max_final_state_elements_size: 100_000,
         max_versioning_elements_size: 100_000,
         thread_count,
+        state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
     };
     let db = Arc::new(RwLock::new(
         Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>
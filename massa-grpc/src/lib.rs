@@ -11,6 +11,19 @@
 //! * `handler.rs`: defines the logic for handling incoming gRPC requests.
 //! * `server`: initializes the gRPC service and serve It.
 //! * `stream/`: contains the gRPC streaming methods implementations files.
+//!
+//! ## **Deferred credits**
+//!
+//! `QueryState` with the `AddressDeferredCreditsCandidate` and `AddressDeferredCreditsFinal` items
+//! returns the per-slot deferred credits of an address. The per-cycle schedule of the JSON-RPC
+//! `get_deferred_credits_schedule` method has no dedicated gRPC method yet:
+//! it needs new protobuf definitions in the [massa_proto_rs] crate.
+//!
+//! ## **Authenticated state reads**
+//!
+//! `QueryState` responses already carry the final cursor and the final state fingerprint of the
+//! whole batch of queries. Per-query authenticated reads, such as the JSON-RPC
+//! `get_authenticated_datastore_entries` method, need new protobuf definitions as well.
 
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
@@ -130,6 +130,8 @@ pub(crate) fn execute_read_only_call(
                     .map_err(|_| GrpcError::InvalidArgument("invalid amount".to_string()))
             })
             .transpose()?,
+        state: Default::default(),
+        ledger_overrides: Default::default(),
     };
 
     let output = grpc
@@ -5,7 +5,7 @@ use crate::error::{match_for_io_error, GrpcError};
 use crate::server::MassaPublicGrpc;
 use crate::SlotRange;
 use futures_util::StreamExt;
-use massa_execution_exports::{ExecutionOutput, SlotExecutionOutput};
+use massa_execution_exports::{ExecutionOutput, ExecutionOutputConsumerClass, SlotExecutionOutput};
 use massa_models::slot::Slot;
 use massa_proto_rs::massa::api::v1::{self as grpc_api, NewSlotExecutionOutputsRequest};
 use massa_proto_rs::massa::model::v1::{self as grpc_model};
@@ -13,6 +13,7 @@ use std::collections::HashSet;
 use std::io::ErrorKind;
 use std::pin::Pin;
 use tokio::select;
+use tokio::sync::broadcast::error::RecvError;
 use tonic::{Request, Streaming};
 use tracing::log::{error, warn};
 
@@ -91,6 +92,7 @@ pub(crate) async fn new_slot_execution_outputs(
         .slot_execution_output_sender
         .subscribe();
     let grpc_config = grpc.grpc_config.clone();
+    let execution_controller = grpc.execution_controller.clone();
 
     tokio::spawn(async move {
         if let Some(Ok(request)) = in_stream.next().await {
@@ -106,26 +108,64 @@ pub(crate) async fn new_slot_execution_outputs(
                 }
             };
 
+            // the final outputs are retained until this stream acknowledges them,
+            // so that the outputs missed when lagging behind the broadcast channel can be replayed
+            let consumer = execution_controller
+                .register_execution_output_consumer(ExecutionOutputConsumerClass::Stream);
+            // latest final slot sent to the client
+            let mut last_final_slot: Option<Slot> = None;
             loop {
                 select! {
                     // Receive a new slot execution output from the subscriber
                     event = subscriber.recv() => {
-                        match event {
-                            Ok(massa_slot_execution_output) => {
-                                let slot_execution_output = filter_map(massa_slot_execution_output, &filters, &grpc_config);
-                                // Check if the slot execution output should be sent
-                                if let Some(slot_execution_output) = slot_execution_output {
-                                    // Send the new slot execution output through the channel
-                                    if let Err(e) = tx.send(Ok(grpc_api::NewSlotExecutionOutputsResponse {
-                                            output: Some(slot_execution_output.into())
-                                    })).await {
-                                        error!("failed to send new slot execution output : {}", e);
-                                        break;
-                                    }
+                        let massa_slot_execution_outputs = match event {
+                            Ok(massa_slot_execution_output) => vec![massa_slot_execution_output],
+                            Err(RecvError::Lagged(skipped)) => {
+                                warn!("new slot execution outputs stream lagged behind by {} outputs", skipped);
+                                // replay the missed final outputs, that are retained until we acknowledge them
+                                match last_final_slot {
+                                    Some(last_final_slot) => execution_controller
+                                        .get_final_execution_outputs(Some(last_final_slot))
+                                        .into_iter()
+                                        .map(SlotExecutionOutput::FinalizedSlot)
+                                        .collect(),
+                                    None => Vec::new(),
                                 }
-                            },
-
-                            Err(e) => error!("error on receive new slot execution output : {}", e)
+                            }
+                            Err(e) => {
+                                error!("error on receive new slot execution output : {}", e);
+                                Vec::new()
+                            }
+                        };
+                        let mut disconnected = false;
+                        for massa_slot_execution_output in massa_slot_execution_outputs {
+                            let final_slot = match &massa_slot_execution_output {
+                                SlotExecutionOutput::FinalizedSlot(exec_output) => Some(exec_output.slot),
+                                SlotExecutionOutput::ExecutedSlot(_) => None,
+                            };
+                            // skip the final outputs that were already replayed
+                            if final_slot.is_some() && final_slot <= last_final_slot {
+                                continue;
+                            }
+                            let slot_execution_output = filter_map(massa_slot_execution_output, &filters, &grpc_config);
+                            // Check if the slot execution output should be sent
+                            if let Some(slot_execution_output) = slot_execution_output {
+                                // Send the new slot execution output through the channel
+                                if let Err(e) = tx.send(Ok(grpc_api::NewSlotExecutionOutputsResponse {
+                                        output: Some(slot_execution_output.into())
+                                })).await {
+                                    error!("failed to send new slot execution output : {}", e);
+                                    disconnected = true;
+                                    break;
+                                }
+                            }
+                            if let Some(final_slot) = final_slot {
+                                last_final_slot = Some(final_slot);
+                                execution_controller.ack_execution_outputs(consumer, final_slot);
+                            }
+                        }
+                        if disconnected {
+                            break;
                         }
                     },
                     // Receive a new message from the in_stream
@@ -143,7 +183,7 @@ pub(crate) async fn new_slot_execution_outputs(
                                                 if let Err(e) = tx.send(Err(err.into())).await {
                                                     error!("failed to send back NewBlocks error response: {}", e);
                                                 }
-                                                return;
+                                                break;
                                             }
                                         };
                                     },
@@ -173,6 +213,7 @@ pub(crate) async fn new_slot_execution_outputs(
                     }
                 }
             }
+            execution_controller.unregister_execution_output_consumer(consumer);
         } else {
             error!("empty request");
         }
@@ -121,6 +121,8 @@ pub(crate) fn grpc_public_service(addr: &SocketAddr) -> MassaPublicGrpc {
             block_sender: tokio::sync::broadcast::channel(100).0,
             block_header_sender: tokio::sync::broadcast::channel(100).0,
             filled_block_sender: tokio::sync::broadcast::channel(100).0,
+            final_period_sender: tokio::sync::broadcast::channel(100).0,
+            fork_choice_sender: tokio::sync::broadcast::channel(100).0,
         },
         consensus_controller: consensus_ctrl,
         execution_controller: execution_ctrl,
@@ -83,6 +83,10 @@ async fn get_transactions_throughput() {
         final_executed_operations_count: 0,
         active_cursor: Slot::new(0, 0),
         final_cursor: Slot::new(0, 0),
+        candidate_execution_lag: 0,
+        final_execution_backlog: 0,
+        speculative_execution_stale: false,
+        candidate_execution_enabled: true,
     });
 
     public_server.execution_controller = exec_ctrl;
@@ -351,6 +355,7 @@ async fn execute_read_only_call() {
                     block_info: None,
                     state_changes: massa_final_state::StateChanges::default(),
                     events: EventStore::default(),
+                    operation_results: Default::default(),
                 },
                 gas_cost: 100,
                 call_result: "toto".as_bytes().to_vec(),
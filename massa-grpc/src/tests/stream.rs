@@ -2,7 +2,9 @@
 
 use crate::tests::mock::grpc_public_service;
 use massa_consensus_exports::MockConsensusController;
-use massa_execution_exports::{ExecutionOutput, MockExecutionController, SlotExecutionOutput};
+use massa_execution_exports::{
+    ExecutionOutput, ExecutionOutputConsumerId, MockExecutionController, SlotExecutionOutput,
+};
 use massa_models::{
     address::Address, block::FilledBlock, secure_share::SecureShareSerializer, slot::Slot,
     stats::ExecutionStats,
@@ -59,6 +61,10 @@ async fn transactions_throughput_stream() {
                     period: 3,
                     thread: 15,
                 },
+                candidate_execution_lag: 0,
+                final_execution_backlog: 0,
+                speculative_execution_stale: false,
+                candidate_execution_enabled: true,
             }
         });
         exec_ctrl
@@ -86,6 +92,10 @@ async fn transactions_throughput_stream() {
                     period: 3,
                     thread: 15,
                 },
+                candidate_execution_lag: 0,
+                final_execution_backlog: 0,
+                speculative_execution_stale: false,
+                candidate_execution_enabled: true,
             }
         });
         exec_ctrl
@@ -1054,6 +1064,19 @@ async fn new_slot_execution_outputs() {
     let mut public_server = grpc_public_service(&addr);
     let config = public_server.grpc_config.clone();
 
+    let mut exec_ctrl = Box::new(MockExecutionController::new());
+    exec_ctrl.expect_clone_box().returning(|| {
+        let mut exec_ctrl = Box::new(MockExecutionController::new());
+        exec_ctrl
+            .expect_register_execution_output_consumer()
+            .returning(|_| ExecutionOutputConsumerId(0));
+        exec_ctrl
+            .expect_unregister_execution_output_consumer()
+            .returning(|_| ());
+        exec_ctrl
+    });
+    public_server.execution_controller = exec_ctrl;
+
     let (slot_tx, _slot_rx) = tokio::sync::broadcast::channel(10);
 
     public_server
@@ -1067,6 +1090,7 @@ async fn new_slot_execution_outputs() {
         block_info: None,
         state_changes: massa_final_state::StateChanges::default(),
         events: Default::default(),
+        operation_results: Default::default(),
     };
 
     let (tx_request, rx) = tokio::sync::mpsc::channel(10);
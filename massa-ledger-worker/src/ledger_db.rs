@@ -557,7 +557,10 @@ fn end_prefix(prefix: &[u8]) -> Option<Vec<u8>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use massa_db_exports::{MassaDBConfig, MassaDBController, STATE_HASH_INITIAL_BYTES};
+    use massa_db_exports::{
+        MassaDBConfig, MassaDBController, STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+        STATE_HASH_INITIAL_BYTES,
+    };
     use massa_db_worker::MassaDB;
     use massa_hash::HashXof;
     use massa_ledger_exports::{LedgerEntry, LedgerEntryUpdate, SetOrKeep};
@@ -601,6 +604,7 @@ mod tests {
             max_final_state_elements_size: 100_000,
             max_versioning_elements_size: 100_000,
             thread_count: 32,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
 
         let db = Arc::new(RwLock::new(
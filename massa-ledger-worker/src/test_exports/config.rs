@@ -2,7 +2,7 @@
 
 use std::sync::Arc;
 
-use massa_db_exports::MassaDBConfig;
+use massa_db_exports::{MassaDBConfig, STATE_CF_DEFAULT_WRITE_BUFFER_SIZE};
 use massa_db_worker::MassaDB;
 use parking_lot::RwLock;
 /// This file defines testing tools related to the configuration
@@ -21,6 +21,7 @@ impl Default for FinalLedger {
             max_final_state_elements_size: 100_000,
             max_versioning_elements_size: 100_000,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db = MassaDB::new(db_config);
         let db = LedgerDB::new(
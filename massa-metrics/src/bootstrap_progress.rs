@@ -0,0 +1,82 @@
+//! Progress of the bootstrap of our node, exposed while it is running so that
+//! a slow bootstrap can be told apart from a stuck one.
+
+use std::time::Duration;
+
+/// Snapshot of the progress of the bootstrap of our node
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BootstrapProgressReport {
+    /// current phase of the bootstrap
+    pub phase: &'static str,
+    /// bootstrap server currently used, if any
+    pub server: Option<String>,
+    /// number of connection attempts to bootstrap servers so far
+    pub attempts: u64,
+    /// time since the start of the bootstrap
+    pub elapsed: Duration,
+    /// bytes of final state received so far
+    pub received_bytes: u64,
+    /// final state keys received so far
+    pub received_keys: u64,
+    /// estimated fraction of the final state received, between 0 and 1
+    pub state_progress: Option<f64>,
+    /// estimated total number of final state keys
+    pub estimated_total_keys: Option<u64>,
+    /// estimated remaining time to receive the final state
+    pub eta: Option<Duration>,
+}
+
+impl std::fmt::Display for BootstrapProgressReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "phase={} server={} attempts={} elapsed={}s received_bytes={} received_keys={}",
+            self.phase,
+            self.server.as_deref().unwrap_or("none"),
+            self.attempts,
+            self.elapsed.as_secs(),
+            self.received_bytes,
+            self.received_keys
+        )?;
+        if let Some(state_progress) = self.state_progress {
+            write!(f, " state_progress={:.1}%", state_progress * 100.0)?;
+        }
+        if let Some(estimated_total_keys) = self.estimated_total_keys {
+            write!(f, " estimated_total_keys={}", estimated_total_keys)?;
+        }
+        if let Some(eta) = self.eta {
+            write!(f, " eta={}s", eta.as_secs())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bootstrap_progress_report_display() {
+        let mut report = BootstrapProgressReport {
+            phase: "connecting",
+            attempts: 1,
+            elapsed: Duration::from_millis(2500),
+            ..Default::default()
+        };
+        assert_eq!(
+            report.to_string(),
+            "phase=connecting server=none attempts=1 elapsed=2s received_bytes=0 received_keys=0"
+        );
+        report.phase = "streaming_state";
+        report.server = Some("127.0.0.1:31245".to_string());
+        report.received_bytes = 4096;
+        report.received_keys = 100;
+        report.state_progress = Some(0.25);
+        report.estimated_total_keys = Some(400);
+        report.eta = Some(Duration::from_secs(90));
+        assert_eq!(
+            report.to_string(),
+            "phase=streaming_state server=127.0.0.1:31245 attempts=1 elapsed=2s received_bytes=4096 received_keys=100 state_progress=25.0% estimated_total_keys=400 eta=90s"
+        );
+    }
+}
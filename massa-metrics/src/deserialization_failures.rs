@@ -0,0 +1,104 @@
+//! Tracking of the peers sending us messages that fail to be deserialized,
+//! used to identify the sources of malformed traffic.
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Deserialization failures of the messages received from a peer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeerDeserializationFailures {
+    /// total number of failures
+    pub total: u64,
+    /// number of failures by (message family, error kind)
+    pub by_kind: BTreeMap<(&'static str, &'static str), u64>,
+}
+
+impl std::fmt::Display for PeerDeserializationFailures {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} failures (", self.total)?;
+        for (i, ((family, kind), count)) in self.by_kind.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}/{}: {}", family, kind, count)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Deserialization failures by peer, for a bounded number of peers.
+/// When full, the peer with the fewest failures is forgotten to make room for a new one.
+#[derive(Debug)]
+pub(crate) struct DeserializationFailuresByPeer {
+    peers: HashMap<String, PeerDeserializationFailures>,
+    max_peers: usize,
+}
+
+impl DeserializationFailuresByPeer {
+    pub(crate) fn new(max_peers: usize) -> Self {
+        DeserializationFailuresByPeer {
+            peers: HashMap::new(),
+            max_peers,
+        }
+    }
+
+    /// Count a failure of a peer
+    pub(crate) fn insert(&mut self, peer: &str, family: &'static str, kind: &'static str) {
+        if !self.peers.contains_key(peer) && self.peers.len() >= self.max_peers {
+            if let Some(least) = self
+                .peers
+                .iter()
+                .min_by_key(|(_, failures)| failures.total)
+                .map(|(peer, _)| peer.clone())
+            {
+                self.peers.remove(&least);
+            }
+        }
+        let failures = self.peers.entry(peer.to_string()).or_default();
+        failures.total = failures.total.saturating_add(1);
+        let count = failures.by_kind.entry((family, kind)).or_default();
+        *count = count.saturating_add(1);
+    }
+
+    /// Get the `count` peers with the most failures, from the worst
+    pub(crate) fn top(&self, count: usize) -> Vec<(String, PeerDeserializationFailures)> {
+        let mut peers: Vec<_> = self
+            .peers
+            .iter()
+            .map(|(peer, failures)| (peer.clone(), failures.clone()))
+            .collect();
+        peers.sort_unstable_by(|(peer_a, a), (peer_b, b)| {
+            b.total.cmp(&a.total).then_with(|| peer_a.cmp(peer_b))
+        });
+        peers.truncate(count);
+        peers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_offenders() {
+        let mut failures = DeserializationFailuresByPeer::new(2);
+        failures.insert("a", "block", "malformed");
+        failures.insert("a", "block", "malformed");
+        failures.insert("a", "envelope", "invalid_message_type");
+        failures.insert("b", "operation", "trailing_bytes");
+
+        let top = failures.top(1);
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].0, "a");
+        assert_eq!(top[0].1.total, 3);
+        assert_eq!(top[0].1.by_kind[&("block", "malformed")], 2);
+        assert_eq!(
+            top[0].1.to_string(),
+            "3 failures (block/malformed: 2, envelope/invalid_message_type: 1)"
+        );
+
+        // "b" has the fewest failures and is forgotten to make room for "c"
+        failures.insert("c", "block", "malformed");
+        let peers: Vec<_> = failures.top(10).into_iter().map(|(peer, _)| peer).collect();
+        assert_eq!(peers, vec!["a".to_string(), "c".to_string()]);
+    }
+}
@@ -12,13 +12,28 @@ use std::{
     time::Duration,
 };
 
+use deserialization_failures::DeserializationFailuresByPeer;
 use lazy_static::lazy_static;
-use prometheus::{register_int_gauge, Gauge, Histogram, IntCounter, IntGauge};
+use prometheus::{
+    register_gauge, register_histogram, register_histogram_vec, register_int_counter_vec,
+    register_int_gauge, Gauge, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+};
 use tokio::sync::oneshot::Sender;
 use tracing::warn;
 
+mod bootstrap_progress;
+mod deserialization_failures;
 mod server;
 
+pub use bootstrap_progress::BootstrapProgressReport;
+pub use deserialization_failures::PeerDeserializationFailures;
+
+/// Maximum number of peers whose deserialization failures are tracked
+const MAX_TRACKED_PEERS_DESERIALIZATION_FAILURES: usize = 1000;
+
+/// Deepest call depth labelled separately in the nested call gas metrics, deeper calls are counted with it
+const MAX_LABELLED_CALL_DEPTH: u64 = 16;
+
 lazy_static! {
     // use lazy_static for these metrics because they are used in storage which implement default
     static ref OPERATIONS_COUNTER: IntGauge = register_int_gauge!(
@@ -30,6 +45,120 @@ lazy_static! {
         register_int_gauge!("blocks_storage_counter", "blocks storage counter len").unwrap();
     static ref ENDORSEMENTS_COUNTER: IntGauge =
         register_int_gauge!("endorsements_storage_counter", "endorsements storage counter len").unwrap();
+
+    // use lazy_static for these metrics because the protocol messages (de)serializers are created in many places
+    static ref PROTOCOL_SERIALIZATION_FAILURES: IntCounterVec = register_int_counter_vec!(
+        "protocol_serialization_failures",
+        "number of protocol messages that failed to be serialized, by message family",
+        &["family"]
+    )
+    .unwrap();
+    static ref PROTOCOL_DESERIALIZATION_FAILURES: IntCounterVec = register_int_counter_vec!(
+        "protocol_deserialization_failures",
+        "number of received protocol messages that failed to be deserialized, by message family and error kind",
+        &["family", "kind"]
+    )
+    .unwrap();
+    static ref PROTOCOL_MESSAGE_SIZE: HistogramVec = register_histogram_vec!(
+        "protocol_message_size",
+        "serialized size in bytes of the protocol messages, by handler and direction (in or out)",
+        &["handler", "direction"],
+        prometheus::exponential_buckets(64.0, 4.0, 10).unwrap()
+    )
+    .unwrap();
+    static ref PROTOCOL_MESSAGE_QUEUE_WAIT: HistogramVec = register_histogram_vec!(
+        "protocol_message_queue_wait_seconds",
+        "time spent by the received protocol messages in the queue of their handler, by handler",
+        &["handler"],
+        prometheus::exponential_buckets(0.0001, 4.0, 10).unwrap()
+    )
+    .unwrap();
+    static ref PROTOCOL_MESSAGE_HANDLING_DURATION: HistogramVec = register_histogram_vec!(
+        "protocol_message_handling_seconds",
+        "time spent by the handlers processing the received protocol messages, by handler",
+        &["handler"],
+        prometheus::exponential_buckets(0.0001, 4.0, 10).unwrap()
+    )
+    .unwrap();
+    static ref PROTOCOL_DESERIALIZATION_FAILURES_BY_PEER: RwLock<DeserializationFailuresByPeer> =
+        RwLock::new(DeserializationFailuresByPeer::new(MAX_TRACKED_PEERS_DESERIALIZATION_FAILURES));
+    static ref PROTOCOL_RATE_LIMITED_MESSAGES: IntCounterVec = register_int_counter_vec!(
+        "protocol_rate_limited_messages",
+        "number of received protocol messages dropped because their peer exceeded its rate limit, by message type and peer category",
+        &["message", "category"]
+    )
+    .unwrap();
+    static ref PROTOCOL_HANDSHAKE_FINALITY_CHECKS: IntCounterVec = register_int_counter_vec!(
+        "protocol_handshake_finality_checks",
+        "number of handshakes by comparison of the final state hashes announced by the peer with ours (consistent, divergent, unknown when no slot is common, unannounced)",
+        &["result"]
+    )
+    .unwrap();
+    static ref PROTOCOL_OPERATION_SIGNATURE_BATCHES: IntCounterVec = register_int_counter_vec!(
+        "protocol_operation_signature_batches",
+        "number of batches of received operations whose signatures were checked, by result (valid, invalid, or aborted after an invalid batch)",
+        &["result"]
+    )
+    .unwrap();
+    static ref PROTOCOL_OPERATION_SIGNATURE_VERIFICATION_DURATION: Histogram = register_histogram!(
+        "protocol_operation_signature_verification_seconds",
+        "time spent verifying the signatures of the operations received together",
+        prometheus::exponential_buckets(0.0001, 4.0, 10).unwrap()
+    )
+    .unwrap();
+    static ref PROTOCOL_OPERATION_SIGNATURE_THROUGHPUT: Gauge = register_gauge!(
+        "protocol_operation_signature_throughput",
+        "signatures of received operations verified per second during the latest verification"
+    )
+    .unwrap();
+    static ref PROTOCOL_BANDWIDTH_BUDGET_DROPPED_MESSAGES: IntCounterVec = register_int_counter_vec!(
+        "protocol_bandwidth_budget_dropped_messages",
+        "number of protocol messages dropped because the bandwidth budget of the category of their peer was exceeded, by direction, peer category and priority",
+        &["direction", "category", "priority"]
+    )
+    .unwrap();
+    static ref ENDORSEMENT_PRODUCTION_CONFLICTS: IntCounterVec = register_int_counter_vec!(
+        "endorsement_production_conflicts",
+        "number of endorsements of our staking keys that were not produced or were detected as conflicting because their slot and index were already endorsed, by kind",
+        &["kind"]
+    )
+    .unwrap();
+    // use lazy_static for these metrics because they are recorded by the execution context, which does not hold the metrics
+    static ref EXECUTION_OPERATION_CALL_DEPTH: Histogram = register_histogram!(
+        "execution_operation_call_depth",
+        "deepest nested call reached by the executed smart contract operations",
+        prometheus::linear_buckets(0.0, 1.0, 16).unwrap()
+    )
+    .unwrap();
+    static ref EXECUTION_NESTED_CALL_GAS_RATIO: HistogramVec = register_histogram_vec!(
+        "execution_nested_call_gas_ratio",
+        "ratio of the gas of the operation still available when a nested call started, by call depth (capped)",
+        &["depth"],
+        prometheus::linear_buckets(0.1, 0.1, 10).unwrap()
+    )
+    .unwrap();
+    // use lazy_static for these metrics because the bootstrap of our node runs before `MassaMetrics` is handed to the modules
+    static ref BOOTSTRAP_PROGRESS: RwLock<Option<BootstrapProgressReport>> = RwLock::new(None);
+    static ref BOOTSTRAP_RECEIVED_BYTES: IntGauge = register_int_gauge!(
+        "bootstrap_received_bytes",
+        "bytes of final state received by our node during its bootstrap"
+    )
+    .unwrap();
+    static ref BOOTSTRAP_RECEIVED_KEYS: IntGauge = register_int_gauge!(
+        "bootstrap_received_keys",
+        "final state keys received by our node during its bootstrap"
+    )
+    .unwrap();
+    static ref BOOTSTRAP_STATE_PROGRESS: Gauge = register_gauge!(
+        "bootstrap_state_progress",
+        "estimated fraction of the final state received by our node during its bootstrap"
+    )
+    .unwrap();
+    static ref BOOTSTRAP_ETA_SECONDS: Gauge = register_gauge!(
+        "bootstrap_eta_seconds",
+        "estimated remaining time to receive the final state during the bootstrap of our node"
+    )
+    .unwrap();
 }
 
 pub fn set_blocks_counter(val: usize) {
@@ -44,6 +173,145 @@ pub fn set_operations_counter(val: usize) {
     OPERATIONS_COUNTER.set(val as i64);
 }
 
+/// Count a protocol message of the given family that failed to be serialized
+pub fn inc_protocol_serialization_failure(family: &str) {
+    PROTOCOL_SERIALIZATION_FAILURES
+        .with_label_values(&[family])
+        .inc();
+}
+
+/// Count a protocol message dropped because the bandwidth budget of the category of its peer was exceeded
+pub fn inc_protocol_bandwidth_budget_dropped_message(
+    direction: &str,
+    category: &str,
+    priority: &str,
+) {
+    PROTOCOL_BANDWIDTH_BUDGET_DROPPED_MESSAGES
+        .with_label_values(&[direction, category, priority])
+        .inc();
+}
+
+/// Record the serialized size of a protocol message of the given handler, received (`in`) or sent (`out`)
+pub fn observe_protocol_message_size(handler: &str, direction: &str, size: usize) {
+    PROTOCOL_MESSAGE_SIZE
+        .with_label_values(&[handler, direction])
+        .observe(size as f64);
+}
+
+/// Record the time a received protocol message waited in the queue of its handler
+pub fn observe_protocol_message_queue_wait(handler: &str, wait: Duration) {
+    PROTOCOL_MESSAGE_QUEUE_WAIT
+        .with_label_values(&[handler])
+        .observe(wait.as_secs_f64());
+}
+
+/// Record the time spent by a handler processing a received protocol message
+pub fn observe_protocol_message_handling(handler: &str, duration: Duration) {
+    PROTOCOL_MESSAGE_HANDLING_DURATION
+        .with_label_values(&[handler])
+        .observe(duration.as_secs_f64());
+}
+
+/// Count a protocol message of the given family received from `peer` that failed to be deserialized
+pub fn inc_protocol_deserialization_failure(family: &'static str, kind: &'static str, peer: &str) {
+    PROTOCOL_DESERIALIZATION_FAILURES
+        .with_label_values(&[family, kind])
+        .inc();
+    if let Ok(mut by_peer) = PROTOCOL_DESERIALIZATION_FAILURES_BY_PEER.write() {
+        by_peer.insert(peer, family, kind);
+    }
+}
+
+/// Count a protocol message of the given type dropped because its peer, of the given category, exceeded its rate limit
+pub fn inc_protocol_rate_limited_message(message: &str, category: &str) {
+    PROTOCOL_RATE_LIMITED_MESSAGES
+        .with_label_values(&[message, category])
+        .inc();
+}
+
+/// Count a handshake by result of the comparison of the final state hashes announced by the peer with ours
+pub fn inc_protocol_handshake_finality_check(result: &str) {
+    PROTOCOL_HANDSHAKE_FINALITY_CHECKS
+        .with_label_values(&[result])
+        .inc();
+}
+
+/// Record the verification of the signatures of operations received together:
+/// the number of batches by result, the number of signatures verified and the time spent
+pub fn observe_protocol_operation_signature_verification(
+    valid_batches: u64,
+    invalid_batches: u64,
+    aborted_batches: u64,
+    verified_signatures: usize,
+    duration: Duration,
+) {
+    for (result, batches) in [
+        ("valid", valid_batches),
+        ("invalid", invalid_batches),
+        ("aborted", aborted_batches),
+    ] {
+        PROTOCOL_OPERATION_SIGNATURE_BATCHES
+            .with_label_values(&[result])
+            .inc_by(batches);
+    }
+    PROTOCOL_OPERATION_SIGNATURE_VERIFICATION_DURATION.observe(duration.as_secs_f64());
+    if duration > Duration::ZERO {
+        PROTOCOL_OPERATION_SIGNATURE_THROUGHPUT
+            .set(verified_signatures as f64 / duration.as_secs_f64());
+    }
+}
+
+/// Record the deepest nested call reached by an executed smart contract operation
+pub fn observe_execution_operation_call_depth(depth: u64) {
+    EXECUTION_OPERATION_CALL_DEPTH.observe(depth as f64);
+}
+
+/// Record the ratio of the gas of an operation still available when a nested call started at `depth`
+pub fn observe_execution_nested_call_gas_ratio(depth: u64, ratio: f64) {
+    EXECUTION_NESTED_CALL_GAS_RATIO
+        .with_label_values(&[&depth.min(MAX_LABELLED_CALL_DEPTH).to_string()])
+        .observe(ratio);
+}
+
+pub fn inc_endorsement_production_conflict(kind: &str) {
+    ENDORSEMENT_PRODUCTION_CONFLICTS
+        .with_label_values(&[kind])
+        .inc();
+}
+
+/// Publish the progress of the bootstrap of our node
+pub fn set_bootstrap_progress(report: BootstrapProgressReport) {
+    BOOTSTRAP_RECEIVED_BYTES.set(report.received_bytes as i64);
+    BOOTSTRAP_RECEIVED_KEYS.set(report.received_keys as i64);
+    if let Some(state_progress) = report.state_progress {
+        BOOTSTRAP_STATE_PROGRESS.set(state_progress);
+    }
+    if let Some(eta) = report.eta {
+        BOOTSTRAP_ETA_SECONDS.set(eta.as_secs_f64());
+    }
+    if let Ok(mut progress) = BOOTSTRAP_PROGRESS.write() {
+        *progress = Some(report);
+    }
+}
+
+/// Get the latest published progress of the bootstrap of our node, `None` if it never bootstrapped
+pub fn get_bootstrap_progress() -> Option<BootstrapProgressReport> {
+    BOOTSTRAP_PROGRESS
+        .read()
+        .ok()
+        .and_then(|progress| progress.clone())
+}
+
+/// Get the `count` peers that sent us the most messages that failed to be deserialized, from the worst
+pub fn get_protocol_deserialization_top_offenders(
+    count: usize,
+) -> Vec<(String, PeerDeserializationFailures)> {
+    PROTOCOL_DESERIALIZATION_FAILURES_BY_PEER
+        .read()
+        .map(|by_peer| by_peer.top(count))
+        .unwrap_or_default()
+}
+
 #[derive(Default)]
 pub struct MetricsStopper {
     pub(crate) stopper: Option<Sender<()>>,
@@ -169,6 +437,21 @@ pub struct MassaMetrics {
     final_cursor_thread: IntGauge,
     final_cursor_period: IntGauge,
 
+    // execution lanes
+    execution_final_lane_utilization: Gauge,
+    execution_candidate_lane_utilization: Gauge,
+    execution_candidate_preemptions: IntCounter,
+
+    // module cache
+    module_cache_lru_hits: IntCounter,
+    module_cache_hd_hits: IntCounter,
+    module_cache_misses: IntCounter,
+    module_cache_hit_ratio: Gauge,
+
+    // protocol pre-filters
+    protocol_prefilter_rejected_headers: IntCounter,
+    protocol_prefilter_rejected_endorsements: IntCounter,
+
     // peer bandwidth (bytes sent, bytes received)
     peers_bandwidth: Arc<RwLock<HashMap<String, (IntCounter, IntCounter)>>>,
 
@@ -304,6 +587,57 @@ impl MassaMetrics {
         let final_cursor_period =
             IntGauge::new("final_cursor_period", "execution final cursor period").unwrap();
 
+        // execution lanes
+        let execution_final_lane_utilization = Gauge::new(
+            "execution_final_lane_utilization",
+            "share of time spent executing final slots",
+        )
+        .unwrap();
+        let execution_candidate_lane_utilization = Gauge::new(
+            "execution_candidate_lane_utilization",
+            "share of time spent executing candidate slots",
+        )
+        .unwrap();
+        let execution_candidate_preemptions = IntCounter::new(
+            "execution_candidate_preemptions",
+            "number of candidate slot executions preempted by final slots",
+        )
+        .unwrap();
+
+        // module cache
+        let module_cache_lru_hits = IntCounter::new(
+            "module_cache_lru_hits",
+            "number of compiled modules loaded from the LRU cache",
+        )
+        .unwrap();
+        let module_cache_hd_hits = IntCounter::new(
+            "module_cache_hd_hits",
+            "number of compiled modules loaded from the HD cache",
+        )
+        .unwrap();
+        let module_cache_misses = IntCounter::new(
+            "module_cache_misses",
+            "number of modules compiled because they were missing in the caches",
+        )
+        .unwrap();
+        let module_cache_hit_ratio = Gauge::new(
+            "module_cache_hit_ratio",
+            "share of the module loads served by the LRU or HD cache",
+        )
+        .unwrap();
+
+        // protocol pre-filters
+        let protocol_prefilter_rejected_headers = IntCounter::new(
+            "protocol_prefilter_rejected_headers",
+            "number of received headers rejected before signature verification",
+        )
+        .unwrap();
+        let protocol_prefilter_rejected_endorsements = IntCounter::new(
+            "protocol_prefilter_rejected_endorsements",
+            "number of received endorsements rejected before signature verification",
+        )
+        .unwrap();
+
         // active connections IN
         let active_in_connections =
             IntGauge::new("active_in_connections", "active connections IN len").unwrap();
@@ -415,6 +749,18 @@ impl MassaMetrics {
                 let _ = prometheus::register(Box::new(final_cursor_period.clone()));
                 let _ = prometheus::register(Box::new(active_cursor_thread.clone()));
                 let _ = prometheus::register(Box::new(active_cursor_period.clone()));
+                let _ = prometheus::register(Box::new(execution_final_lane_utilization.clone()));
+                let _ =
+                    prometheus::register(Box::new(execution_candidate_lane_utilization.clone()));
+                let _ = prometheus::register(Box::new(execution_candidate_preemptions.clone()));
+                let _ = prometheus::register(Box::new(module_cache_lru_hits.clone()));
+                let _ = prometheus::register(Box::new(module_cache_hd_hits.clone()));
+                let _ = prometheus::register(Box::new(module_cache_misses.clone()));
+                let _ = prometheus::register(Box::new(module_cache_hit_ratio.clone()));
+                let _ = prometheus::register(Box::new(protocol_prefilter_rejected_headers.clone()));
+                let _ = prometheus::register(Box::new(
+                    protocol_prefilter_rejected_endorsements.clone(),
+                ));
                 let _ = prometheus::register(Box::new(active_out_connections.clone()));
                 let _ = prometheus::register(Box::new(block_cache_blocks_known_by_peer.clone()));
                 let _ = prometheus::register(Box::new(block_cache_checked_headers_size.clone()));
@@ -512,6 +858,15 @@ impl MassaMetrics {
                 active_cursor_period,
                 final_cursor_thread,
                 final_cursor_period,
+                execution_final_lane_utilization,
+                execution_candidate_lane_utilization,
+                execution_candidate_preemptions,
+                module_cache_lru_hits,
+                module_cache_hd_hits,
+                module_cache_misses,
+                module_cache_hit_ratio,
+                protocol_prefilter_rejected_headers,
+                protocol_prefilter_rejected_endorsements,
                 peers_bandwidth: Arc::new(RwLock::new(HashMap::new())),
                 tick_delay,
             },
@@ -547,6 +902,36 @@ impl MassaMetrics {
         self.final_cursor_period.set(period as i64);
     }
 
+    pub fn set_execution_lanes_utilization(&self, final_lane: f64, candidate_lane: f64) {
+        self.execution_final_lane_utilization.set(final_lane);
+        self.execution_candidate_lane_utilization
+            .set(candidate_lane);
+    }
+
+    pub fn inc_execution_candidate_preemptions(&self) {
+        self.execution_candidate_preemptions.inc();
+    }
+
+    pub fn inc_module_cache_stats(&self, lru_hits: u64, hd_hits: u64, misses: u64) {
+        self.module_cache_lru_hits.inc_by(lru_hits);
+        self.module_cache_hd_hits.inc_by(hd_hits);
+        self.module_cache_misses.inc_by(misses);
+
+        let hits = self.module_cache_lru_hits.get() + self.module_cache_hd_hits.get();
+        let loads = hits + self.module_cache_misses.get();
+        if loads > 0 {
+            self.module_cache_hit_ratio.set(hits as f64 / loads as f64);
+        }
+    }
+
+    pub fn inc_protocol_prefilter_rejected_headers(&self) {
+        self.protocol_prefilter_rejected_headers.inc();
+    }
+
+    pub fn inc_protocol_prefilter_rejected_endorsements(&self, count: u64) {
+        self.protocol_prefilter_rejected_endorsements.inc_by(count);
+    }
+
     pub fn set_consensus_period(&self, thread: usize, period: u64) {
         if let Some(g) = self.consensus_vec.get(thread) {
             g.set(period as f64);
@@ -761,3 +1146,33 @@ impl MassaMetrics {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_message_histograms() {
+        observe_protocol_message_size("test_handler", "in", 100);
+        observe_protocol_message_size("test_handler", "in", 300);
+        observe_protocol_message_size("test_handler", "out", 50);
+        observe_protocol_message_queue_wait("test_handler", Duration::from_millis(5));
+        observe_protocol_message_handling("test_handler", Duration::from_millis(2));
+
+        // sizes are recorded per direction
+        let received = PROTOCOL_MESSAGE_SIZE.with_label_values(&["test_handler", "in"]);
+        assert_eq!(received.get_sample_count(), 2);
+        assert_eq!(received.get_sample_sum(), 400.0);
+        let sent = PROTOCOL_MESSAGE_SIZE.with_label_values(&["test_handler", "out"]);
+        assert_eq!(sent.get_sample_count(), 1);
+        assert_eq!(sent.get_sample_sum(), 50.0);
+
+        // durations are recorded in seconds
+        let queue_wait = PROTOCOL_MESSAGE_QUEUE_WAIT.with_label_values(&["test_handler"]);
+        assert_eq!(queue_wait.get_sample_count(), 1);
+        assert!((queue_wait.get_sample_sum() - 0.005).abs() < 1e-9);
+        let handling = PROTOCOL_MESSAGE_HANDLING_DURATION.with_label_values(&["test_handler"]);
+        assert_eq!(handling.get_sample_count(), 1);
+        assert!((handling.get_sample_sum() - 0.002).abs() < 1e-9);
+    }
+}
@@ -9,7 +9,17 @@ use hyper::{
 use prometheus::{Encoder, TextEncoder};
 use tracing::{error, info};
 
-use crate::MetricsStopper;
+use crate::{get_bootstrap_progress, get_protocol_deserialization_top_offenders, MetricsStopper};
+
+/// Path of the debug endpoint listing the peers that sent us the most malformed messages.
+/// The number of listed peers can be set with the `count` query parameter.
+const DESERIALIZATION_FAILURES_PATH: &str = "/debug/protocol_deserialization_failures";
+
+/// Path of the debug endpoint showing the progress of the bootstrap of our node
+const BOOTSTRAP_PROGRESS_PATH: &str = "/debug/bootstrap_progress";
+
+/// Number of peers listed by default on the deserialization failures debug endpoint
+const DEFAULT_TOP_OFFENDERS_COUNT: usize = 20;
 
 #[allow(dead_code)]
 pub(crate) fn bind_metrics(addr: SocketAddr) -> MetricsStopper {
@@ -41,9 +51,44 @@ pub(crate) fn bind_metrics(addr: SocketAddr) -> MetricsStopper {
     }
 }
 
+/// Lists the peers that sent us the most messages that failed to be deserialized, one per line
+fn deserialization_failures_report(query: Option<&str>) -> String {
+    let count = query
+        .and_then(|query| {
+            query
+                .split('&')
+                .find_map(|param| param.strip_prefix("count="))
+                .and_then(|count| count.parse().ok())
+        })
+        .unwrap_or(DEFAULT_TOP_OFFENDERS_COUNT);
+    let mut report = String::new();
+    for (peer, failures) in get_protocol_deserialization_top_offenders(count) {
+        report.push_str(&format!("{}: {}\n", peer, failures));
+    }
+    report
+}
+
 #[allow(dead_code)]
 async fn serve_req(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
-    if req.uri().path() != "/metrics" {
+    if req.uri().path() == DESERIALIZATION_FAILURES_PATH {
+        Ok(Response::builder()
+            .status(200)
+            .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(Body::from(deserialization_failures_report(
+                req.uri().query(),
+            )))
+            .unwrap())
+    } else if req.uri().path() == BOOTSTRAP_PROGRESS_PATH {
+        let report = match get_bootstrap_progress() {
+            Some(progress) => format!("{}\n", progress),
+            None => "no bootstrap\n".to_string(),
+        };
+        Ok(Response::builder()
+            .status(200)
+            .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(Body::from(report))
+            .unwrap())
+    } else if req.uri().path() != "/metrics" {
         // return hyper error
         Ok(Response::builder()
             .status(404)
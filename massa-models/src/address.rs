@@ -2,10 +2,11 @@
 
 use crate::error::ModelsError;
 use crate::prehash::PreHashed;
+use bech32::{FromBase32, ToBase32, Variant};
 use massa_hash::{Hash, HashDeserializer, HASH_SIZE_BYTES};
 use massa_serialization::{
-    DeserializeError, Deserializer, SerializeError, Serializer, U64VarIntDeserializer,
-    U64VarIntSerializer,
+    DeserializeError, Deserializer, SerializeError, Serializer, U32VarIntDeserializer,
+    U32VarIntSerializer, U64VarIntDeserializer, U64VarIntSerializer,
 };
 use massa_signature::{PublicKey, PublicKeyV0};
 use nom::error::{context, ContextError, ErrorKind, ParseError};
@@ -25,8 +26,9 @@ pub enum Address {
 }
 
 #[allow(missing_docs)]
-/// Derived from a public key.
-#[transition::versioned(versions("0"))]
+/// Derived from the context of its creation.
+/// V0 addresses are all assigned to thread 0, V1 addresses are assigned a thread derived from their hash.
+#[transition::versioned(versions("0", "1"))]
 #[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct SCAddress(pub Hash);
 
@@ -37,10 +39,27 @@ pub struct SCAddress(pub Hash);
 pub struct UserAddress(pub Hash);
 
 const ADDRESS_PREFIX: char = 'A';
+/// human-readable part of the bech32m address format
+const ADDRESS_BECH32M_HRP: &str = "mas";
 // serialized with varint
 const USER_PREFIX: u64 = 0;
 const SC_PREFIX: u64 = 1;
 
+/// Size of the prefixed byte representation of an address: the type and version varints,
+/// which both fit in one byte as types and versions are lower than 128, then the hash
+pub const ADDRESS_PREFIXED_BYTES_SIZE: usize = 2 + HASH_SIZE_BYTES;
+
+/// String format of an address
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressFormat {
+    /// `A` + `U` or `S` + base58check of the version and hash
+    #[default]
+    Base58Check,
+    /// bech32m of the type, version and hash, with the `mas` human-readable part: checksummed and case-insensitive
+    Bech32m,
+}
+
 impl std::fmt::Display for Address {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -62,6 +81,7 @@ impl std::fmt::Display for SCAddress {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             SCAddress::SCAddressV0(address) => address.fmt(f),
+            SCAddress::SCAddressV1(address) => address.fmt(f),
         }
     }
 }
@@ -84,7 +104,7 @@ impl std::fmt::Display for UserAddress {
     }
 }
 
-#[transition::impl_version(versions("0"))]
+#[transition::impl_version(versions("0", "1"))]
 impl std::fmt::Display for SCAddress {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let u64_serializer = U64VarIntSerializer::new();
@@ -112,6 +132,10 @@ impl std::fmt::Debug for Address {
 
 impl ::serde::Serialize for Address {
     fn serialize<S: ::serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        if s.is_human_readable() {
+            // follow the display format
+            return s.collect_str(self);
+        }
         match self {
             Address::User(address) => address.serialize(s),
             Address::SC(address) => address.serialize(s),
@@ -131,6 +155,7 @@ impl ::serde::Serialize for SCAddress {
     fn serialize<S: ::serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
         match self {
             SCAddress::SCAddressV0(address) => address.serialize(s),
+            SCAddress::SCAddressV1(address) => address.serialize(s),
         }
     }
 }
@@ -146,7 +171,7 @@ impl ::serde::Serialize for UserAddress {
     }
 }
 
-#[transition::impl_version(versions("0"))]
+#[transition::impl_version(versions("0", "1"))]
 impl ::serde::Serialize for SCAddress {
     fn serialize<S: ::serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
         if s.is_human_readable() {
@@ -166,7 +191,9 @@ impl<'de> ::serde::Deserialize<'de> for Address {
                 type Value = Address;
 
                 fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                    formatter.write_str("A + {U | S} + base58::encode(version + hash)")
+                    formatter.write_str(
+                        "A + {U | S} + base58::encode(version + hash) or mas1 + bech32m::encode(type + version + hash)",
+                    )
                 }
 
                 fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
@@ -216,7 +243,60 @@ impl<'de> ::serde::Deserialize<'de> for Address {
 
 impl FromStr for Address {
     type Err = ModelsError;
+    /// Parses an address in the base58check or in the bech32m format
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_bech32m = s
+            .get(..ADDRESS_BECH32M_HRP.len() + 1)
+            .map_or(false, |prefix| {
+                prefix.eq_ignore_ascii_case(&format!("{}1", ADDRESS_BECH32M_HRP))
+            });
+        if is_bech32m {
+            Address::from_str_with_format(s, AddressFormat::Bech32m)
+        } else {
+            Address::from_str_with_format(s, AddressFormat::Base58Check)
+        }
+    }
+}
+
+impl Address {
+    /// Formats the address in a given format. `Display` always uses the base58check format
+    pub fn to_string_with_format(&self, format: AddressFormat) -> String {
+        match (format, self) {
+            (AddressFormat::Base58Check, Address::User(address)) => address.to_string(),
+            (AddressFormat::Base58Check, Address::SC(address)) => address.to_string(),
+            (AddressFormat::Bech32m, _) => bech32::encode(
+                ADDRESS_BECH32M_HRP,
+                self.to_prefixed_bytes().to_base32(),
+                Variant::Bech32m,
+            )
+            .expect("the bech32m human-readable part of addresses is valid"),
+        }
+    }
+
+    /// Parses an address in a given format
+    pub fn from_str_with_format(s: &str, format: AddressFormat) -> Result<Self, ModelsError> {
+        match format {
+            AddressFormat::Base58Check => Address::from_base58check_str(s),
+            AddressFormat::Bech32m => Address::from_bech32m_str(s),
+        }
+    }
+
+    fn from_bech32m_str(s: &str) -> Result<Self, ModelsError> {
+        let err = |reason: String| {
+            ModelsError::AddressParseError(format!("in Address from_bech32m_str: {}", reason))
+        };
+        let (hrp, data, variant) = bech32::decode(s).map_err(|e| err(e.to_string()))?;
+        if hrp != ADDRESS_BECH32M_HRP || variant != Variant::Bech32m {
+            return Err(err(format!(
+                "expected a bech32m string with the {} human-readable part",
+                ADDRESS_BECH32M_HRP
+            )));
+        }
+        let bytes = Vec::<u8>::from_base32(&data).map_err(|e| err(e.to_string()))?;
+        Address::from_prefixed_bytes(&bytes)
+    }
+
+    fn from_base58check_str(s: &str) -> Result<Self, ModelsError> {
         let err = Err(ModelsError::AddressParseError(s.to_string()));
 
         // Handle the prefix ("A{U|S}")
@@ -239,12 +319,34 @@ impl FromStr for Address {
 
 impl Address {
     /// Gets the associated thread. Depends on the `thread_count`
-    /// Returns None for SC addresses, even though we may want to get_thread on them in the future
+    /// and, for SC addresses, on their version (see `SCAddress`)
     pub fn get_thread(&self, thread_count: u8) -> u8 {
         match self {
             Address::User(addr) => addr.get_thread(thread_count),
-            // TODO: tmp behaviour, discuss how we would want this to work
-            Address::SC(_addr) => 0,
+            Address::SC(addr) => addr.get_thread(thread_count),
+        }
+    }
+
+    /// Returns true if the address is a smart contract address
+    pub fn is_sc(&self) -> bool {
+        matches!(self, Address::SC(_))
+    }
+
+    /// Fetches the version of the underlying user or SC address
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use massa_models::address::Address;
+    /// # use std::str::FromStr;
+    /// let address = Address::from_str("AU12cMW9zRKFDS43Z2W88VCmdQFxmHjAo54XvuVV34UzJeXRLXW9M").unwrap();
+    /// assert!(!address.is_sc());
+    /// assert_eq!(address.get_version(), 0);
+    /// ```
+    pub fn get_version(&self) -> u64 {
+        match self {
+            Address::User(UserAddress::UserAddressV0(addr)) => addr.get_version(),
+            Address::SC(SCAddress::SCAddressV0(addr)) => addr.get_version(),
+            Address::SC(SCAddress::SCAddressV1(addr)) => addr.get_version(),
         }
     }
 
@@ -255,12 +357,27 @@ impl Address {
     }
 
     /// Serialize the address as bytes. Includes the type and version prefixes
-    pub fn to_prefixed_bytes(self) -> Vec<u8> {
+    pub fn to_prefixed_bytes(self) -> [u8; ADDRESS_PREFIXED_BYTES_SIZE] {
         match self {
             Address::User(addr) => addr.to_prefixed_bytes(),
             Address::SC(addr) => addr.to_prefixed_bytes(),
         }
     }
+
+    /// Deserialize an address from its prefixed byte representation (see `to_prefixed_bytes`)
+    pub fn from_prefixed_bytes(bytes: &[u8]) -> Result<Self, ModelsError> {
+        let (rest, address) = AddressDeserializer::new()
+            .deserialize::<DeserializeError>(bytes)
+            .map_err(|err| {
+                ModelsError::AddressParseError(format!("in Address from_prefixed_bytes: {}", err))
+            })?;
+        if !rest.is_empty() {
+            return Err(ModelsError::AddressParseError(
+                "in Address from_prefixed_bytes: trailing bytes".to_string(),
+            ));
+        }
+        Ok(address)
+    }
 }
 
 impl UserAddress {
@@ -309,7 +426,7 @@ impl UserAddress {
     }
 
     /// Serialize the address as bytes. Includes the type and version prefixes
-    pub fn to_prefixed_bytes(self) -> Vec<u8> {
+    pub fn to_prefixed_bytes(self) -> [u8; ADDRESS_PREFIXED_BYTES_SIZE] {
         match self {
             UserAddress::UserAddressV0(addr) => addr.to_prefixed_bytes(),
         }
@@ -324,18 +441,13 @@ impl UserAddress {
     }
 
     /// Serialize the address as bytes. Includes the type and version prefixes
-    fn to_prefixed_bytes(self) -> Vec<u8> {
-        let mut buff = vec![];
-        let addr_type_ser = U64VarIntSerializer::new();
-        let addr_vers_ser = U64VarIntSerializer::new();
-        addr_type_ser
-            .serialize(&USER_PREFIX, &mut buff)
-            .expect("impl always returns Ok(())");
-        addr_vers_ser
-            .serialize(&Self::VERSION, &mut buff)
-            .expect("impl always returns Ok(())");
-        buff.extend_from_slice(&self.0.to_bytes()[..]);
-        buff
+    fn to_prefixed_bytes(self) -> [u8; ADDRESS_PREFIXED_BYTES_SIZE] {
+        let mut bytes = [0u8; ADDRESS_PREFIXED_BYTES_SIZE];
+        // one-byte varints
+        bytes[0] = USER_PREFIX as u8;
+        bytes[1] = Self::VERSION as u8;
+        bytes[2..].copy_from_slice(self.0.to_bytes());
+        bytes
     }
 
     /// Gets the associated thread. Depends on the `thread_count`
@@ -392,6 +504,9 @@ impl SCAddress {
             <SCAddress!["0"]>::VERSION => {
                 Ok(SCAddressVariant!["0"](<SCAddress!["0"]>::from_bytes(rest)?))
             }
+            <SCAddress!["1"]>::VERSION => {
+                Ok(SCAddressVariant!["1"](<SCAddress!["1"]>::from_bytes(rest)?))
+            }
             unhandled_version => Err(ModelsError::AddressParseError(format!(
                 "version {} is not handled for SCAddress",
                 unhandled_version
@@ -400,14 +515,88 @@ impl SCAddress {
     }
 
     /// Serialize the address as bytes. Includes the type and version prefixes
-    pub fn to_prefixed_bytes(self) -> Vec<u8> {
+    pub fn to_prefixed_bytes(self) -> [u8; ADDRESS_PREFIXED_BYTES_SIZE] {
         match self {
             SCAddress::SCAddressV0(addr) => addr.to_prefixed_bytes(),
+            SCAddress::SCAddressV1(addr) => addr.to_prefixed_bytes(),
+        }
+    }
+
+    /// Gets the associated thread. Depends on the `thread_count` and on the version
+    fn get_thread(&self, thread_count: u8) -> u8 {
+        match self {
+            SCAddress::SCAddressV0(addr) => addr.get_thread(thread_count),
+            SCAddress::SCAddressV1(addr) => addr.get_thread(thread_count),
+        }
+    }
+
+    /// Hash from which the execution creates the address of a smart contract:
+    /// `Hash("SC_ADDRESS" || execution_trail_hash || created_addr_index || nonce)`, with the integers in big endian.
+    ///
+    /// `created_addr_index` counts the addresses already created during the execution of the slot,
+    /// and `nonce` starts at 0, the execution only incrementing it if the resulting address already exists.
+    pub fn creation_hash(execution_trail_hash: &Hash, created_addr_index: u64, nonce: u64) -> Hash {
+        Hash::compute_from_tuple(&[
+            "SC_ADDRESS".as_bytes(),
+            execution_trail_hash.to_bytes(),
+            &created_addr_index.to_be_bytes(),
+            &nonce.to_be_bytes(),
+        ])
+    }
+
+    /// Derives the address of a smart contract the way the execution creates it, from [`SCAddress::creation_hash`]
+    /// and the version of the address component active at the slot of the creation.
+    ///
+    /// The execution trail hash changes with every executed operation, so the address is only known
+    /// once the operations executed before the creation are.
+    ///
+    /// ```
+    /// # use massa_hash::Hash;
+    /// # use massa_models::address::SCAddress;
+    /// let execution_trail_hash = Hash::compute_from(b"trail");
+    /// let address = SCAddress::derive(&execution_trail_hash, 0, 0, 1).unwrap();
+    /// assert_eq!(address, SCAddress::derive(&execution_trail_hash, 0, 0, 1).unwrap());
+    /// assert_ne!(address, SCAddress::derive(&execution_trail_hash, 1, 0, 1).unwrap());
+    /// assert!(SCAddress::derive(&execution_trail_hash, 0, 0, 2).is_err());
+    /// ```
+    pub fn derive(
+        execution_trail_hash: &Hash,
+        created_addr_index: u64,
+        nonce: u64,
+        version: u64,
+    ) -> Result<SCAddress, ModelsError> {
+        let hash = SCAddress::creation_hash(execution_trail_hash, created_addr_index, nonce);
+        match version {
+            0 => Ok(SCAddress::SCAddressV0(SCAddressV0(hash))),
+            1 => Ok(SCAddress::SCAddressV1(SCAddressV1(hash))),
+            v => Err(ModelsError::InvalidVersionError(format!(
+                "unknown smart contract address version: {}",
+                v
+            ))),
         }
     }
 }
 
 #[transition::impl_version(versions("0"))]
+impl SCAddress {
+    /// Gets the associated thread: always 0, kept for the addresses created before V1
+    fn get_thread(&self, _thread_count: u8) -> u8 {
+        0
+    }
+}
+
+#[transition::impl_version(versions("1"))]
+impl SCAddress {
+    /// Gets the associated thread. Depends on the `thread_count`.
+    /// Derived from the first byte of the hash, like for user addresses
+    fn get_thread(&self, thread_count: u8) -> u8 {
+        (self.0.to_bytes()[0])
+            .checked_shr(8 - thread_count.trailing_zeros())
+            .unwrap_or(0)
+    }
+}
+
+#[transition::impl_version(versions("0", "1"))]
 impl SCAddress {
     /// Fetches the version of the SC Address
     pub fn get_version(&self) -> u64 {
@@ -415,21 +604,16 @@ impl SCAddress {
     }
 }
 
-#[transition::impl_version(versions("0"))]
+#[transition::impl_version(versions("0", "1"))]
 impl SCAddress {
     /// Serialize the address as bytes. Includes the type and version prefixes
-    pub fn to_prefixed_bytes(self) -> Vec<u8> {
-        let mut buff = vec![];
-        let addr_type_ser = U64VarIntSerializer::new();
-        let addr_vers_ser = U64VarIntSerializer::new();
-        addr_type_ser
-            .serialize(&SC_PREFIX, &mut buff)
-            .expect("impl always returns Ok(())");
-        addr_vers_ser
-            .serialize(&Self::VERSION, &mut buff)
-            .expect("impl always returns Ok(())");
-        buff.extend_from_slice(&self.0.to_bytes()[..]);
-        buff
+    pub fn to_prefixed_bytes(self) -> [u8; ADDRESS_PREFIXED_BYTES_SIZE] {
+        let mut bytes = [0u8; ADDRESS_PREFIXED_BYTES_SIZE];
+        // one-byte varints
+        bytes[0] = SC_PREFIX as u8;
+        bytes[1] = Self::VERSION as u8;
+        bytes[2..].copy_from_slice(self.0.to_bytes());
+        bytes
     }
 
     /// Deserialize the address without considering the version byte
@@ -499,11 +683,12 @@ impl Serializer<SCAddress> for AddressSerializer {
         self.type_serializer.serialize(&SC_PREFIX, buffer)?;
         match value {
             SCAddress::SCAddressV0(addr) => self.serialize(addr, buffer),
+            SCAddress::SCAddressV1(addr) => self.serialize(addr, buffer),
         }
     }
 }
 
-#[transition::impl_version(versions("0"), structures("SCAddress"))]
+#[transition::impl_version(versions("0", "1"), structures("SCAddress"))]
 impl Serializer<SCAddress> for AddressSerializer {
     fn serialize(&self, value: &SCAddress, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
         self.version_serializer
@@ -626,12 +811,16 @@ impl Deserializer<SCAddress> for AddressDeserializer {
                 let (rest, addr) = self.deserialize(rest)?;
                 Ok((rest, SCAddressVariant!["0"](addr)))
             }
+            <SCAddress!["1"]>::VERSION => {
+                let (rest, addr) = self.deserialize(rest)?;
+                Ok((rest, SCAddressVariant!["1"](addr)))
+            }
             _ => Err(nom::Err::Error(E::from_error_kind(buffer, ErrorKind::Eof))),
         }
     }
 }
 
-#[transition::impl_version(versions("0"), structures("SCAddress"))]
+#[transition::impl_version(versions("0", "1"), structures("SCAddress"))]
 impl Deserializer<SCAddress> for AddressDeserializer {
     fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
         &self,
@@ -645,6 +834,123 @@ impl Deserializer<SCAddress> for AddressDeserializer {
     }
 }
 
+/// Minimal size of a serialized address
+const ADDRESS_MIN_SERIALIZED_SIZE: usize = ADDRESS_PREFIXED_BYTES_SIZE;
+
+/// Serializer for `Vec<Address>`, length-prefixed.
+/// The addresses are written directly into the buffer, without going through `to_prefixed_bytes`.
+#[derive(Default, Clone)]
+pub struct AddressesSerializer {
+    length_serializer: U32VarIntSerializer,
+    address_serializer: AddressSerializer,
+}
+
+impl AddressesSerializer {
+    /// Creates a new `AddressesSerializer`
+    pub fn new() -> Self {
+        Self {
+            length_serializer: U32VarIntSerializer::new(),
+            address_serializer: AddressSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<Vec<Address>> for AddressesSerializer {
+    /// ## Example:
+    /// ```
+    /// use massa_models::address::{Address, AddressesSerializer};
+    /// use massa_serialization::Serializer;
+    /// use std::str::FromStr;
+    ///
+    /// let addresses = vec![
+    ///     Address::from_str("AU12hgh5ULW9o8fJE9muLNXhQENaUUswQbxPyDSq8ridnDGu5gRiJ").unwrap(),
+    ///     Address::from_str("AS12htxRWiEm8jDJpJptr6cwEhWNcCSFWstN1MLSa96DDkVM9Y42G").unwrap(),
+    /// ];
+    /// let mut buffer = Vec::new();
+    /// AddressesSerializer::new().serialize(&addresses, &mut buffer).unwrap();
+    /// ```
+    fn serialize(&self, value: &Vec<Address>, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        let list_len: u32 = value.len().try_into().map_err(|_| {
+            SerializeError::NumberTooBig("could not encode Vec<Address> list length as u32".into())
+        })?;
+        self.length_serializer.serialize(&list_len, buffer)?;
+        buffer.reserve(value.len().saturating_mul(ADDRESS_MIN_SERIALIZED_SIZE));
+        for address in value {
+            self.address_serializer.serialize(address, buffer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deserializer for `Vec<Address>`, length-prefixed.
+/// The announced length is checked against its bound and against the size of the buffer
+/// before anything is allocated, and the addresses are parsed in place from the input slice.
+#[derive(Clone)]
+pub struct AddressesDeserializer {
+    length_deserializer: U32VarIntDeserializer,
+    address_deserializer: AddressDeserializer,
+}
+
+impl AddressesDeserializer {
+    /// Creates a new `AddressesDeserializer` accepting up to `max_addresses` addresses
+    pub const fn new(max_addresses: u32) -> Self {
+        Self {
+            length_deserializer: U32VarIntDeserializer::new(Included(0), Included(max_addresses)),
+            address_deserializer: AddressDeserializer::new(),
+        }
+    }
+}
+
+impl Deserializer<Vec<Address>> for AddressesDeserializer {
+    /// ## Example:
+    /// ```
+    /// use massa_models::address::{Address, AddressesDeserializer, AddressesSerializer};
+    /// use massa_serialization::{DeserializeError, Deserializer, Serializer};
+    /// use std::str::FromStr;
+    ///
+    /// let addresses = vec![
+    ///     Address::from_str("AU12hgh5ULW9o8fJE9muLNXhQENaUUswQbxPyDSq8ridnDGu5gRiJ").unwrap(),
+    ///     Address::from_str("AS12htxRWiEm8jDJpJptr6cwEhWNcCSFWstN1MLSa96DDkVM9Y42G").unwrap(),
+    /// ];
+    /// let mut buffer = Vec::new();
+    /// AddressesSerializer::new().serialize(&addresses, &mut buffer).unwrap();
+    /// let (rest, deserialized) = AddressesDeserializer::new(10)
+    ///     .deserialize::<DeserializeError>(&buffer)
+    ///     .unwrap();
+    /// assert!(rest.is_empty());
+    /// assert_eq!(deserialized, addresses);
+    /// ```
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], Vec<Address>, E> {
+        let (mut rest, count) = context("Failed length deserialization", |input| {
+            self.length_deserializer.deserialize(input)
+        })
+        .parse(buffer)?;
+
+        // reject lengths that the buffer can not hold before allocating
+        if rest.len() < (count as usize).saturating_mul(ADDRESS_MIN_SERIALIZED_SIZE) {
+            return Err(nom::Err::Error(E::add_context(
+                rest,
+                "Failed Vec<Address> deserialization: buffer too short for the announced length",
+                E::from_error_kind(rest, ErrorKind::Eof),
+            )));
+        }
+
+        let mut addresses = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let (next, address) = context("Failed Address deserialization", |input| {
+                self.address_deserializer.deserialize(input)
+            })
+            .parse(rest)?;
+            addresses.push(address);
+            rest = next;
+        }
+        Ok((rest, addresses))
+    }
+}
+
 /// Info for a given address on a given cycle
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExecutionAddressCycleInfo {
@@ -663,6 +969,7 @@ pub struct ExecutionAddressCycleInfo {
 #[cfg(test)]
 mod test {
     use crate::config::THREAD_COUNT;
+    use massa_signature::KeyPair;
 
     use super::*;
 
@@ -690,4 +997,144 @@ mod test {
 
         assert_ne!(thread_addr_0, thread_addr_1);
     }
+
+    #[test]
+    fn test_sc_address_get_thread() {
+        // first byte 0xFF: last thread for both user addresses and V1 SC addresses
+        let hash = massa_hash::Hash::from_bytes(&[0xFF; 32]);
+        let user_addr = Address::User(UserAddress::UserAddressV0(UserAddressV0(hash)));
+        let sc_addr_0 = Address::SC(SCAddress::SCAddressV0(SCAddressV0(hash)));
+        let sc_addr_1 = Address::SC(SCAddress::SCAddressV1(SCAddressV1(hash)));
+
+        assert_eq!(sc_addr_0.get_thread(THREAD_COUNT), 0);
+        assert_eq!(sc_addr_1.get_thread(THREAD_COUNT), THREAD_COUNT - 1);
+        assert_eq!(
+            sc_addr_1.get_thread(THREAD_COUNT),
+            user_addr.get_thread(THREAD_COUNT)
+        );
+
+        // the version is part of the string and binary representations
+        assert_ne!(sc_addr_0.to_string(), sc_addr_1.to_string());
+        assert_eq!(
+            Address::from_str(&sc_addr_1.to_string()).unwrap(),
+            sc_addr_1
+        );
+        let mut buffer = Vec::new();
+        AddressSerializer::new()
+            .serialize(&sc_addr_1, &mut buffer)
+            .unwrap();
+        let (rest, deserialized) = AddressDeserializer::new()
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(deserialized, sc_addr_1);
+    }
+
+    #[test]
+    fn test_sc_address_derive() {
+        let execution_trail_hash = massa_hash::Hash::compute_from("trail".as_bytes());
+        let expected_hash = massa_hash::Hash::compute_from_tuple(&[
+            "SC_ADDRESS".as_bytes(),
+            execution_trail_hash.to_bytes(),
+            &3u64.to_be_bytes(),
+            &1u64.to_be_bytes(),
+        ]);
+        assert_eq!(
+            SCAddress::creation_hash(&execution_trail_hash, 3, 1),
+            expected_hash
+        );
+        assert_eq!(
+            SCAddress::derive(&execution_trail_hash, 3, 1, 0).unwrap(),
+            SCAddress::SCAddressV0(SCAddressV0(expected_hash))
+        );
+        assert_eq!(
+            SCAddress::derive(&execution_trail_hash, 3, 1, 1).unwrap(),
+            SCAddress::SCAddressV1(SCAddressV1(expected_hash))
+        );
+        assert_ne!(
+            SCAddress::creation_hash(&execution_trail_hash, 3, 0),
+            expected_hash
+        );
+        assert!(SCAddress::derive(&execution_trail_hash, 3, 1, 2).is_err());
+    }
+
+    #[test]
+    fn test_addresses_deserializer_bounds() {
+        let hash = massa_hash::Hash::compute_from("ADDR".as_bytes());
+        let addresses = vec![
+            Address::User(UserAddress::UserAddressV0(UserAddressV0(hash))),
+            Address::SC(SCAddress::SCAddressV0(SCAddressV0(hash))),
+        ];
+        let mut buffer = Vec::new();
+        AddressesSerializer::new()
+            .serialize(&addresses, &mut buffer)
+            .unwrap();
+
+        // more addresses than allowed
+        assert!(AddressesDeserializer::new(1)
+            .deserialize::<DeserializeError>(&buffer)
+            .is_err());
+
+        // announced length larger than what the buffer can hold
+        let mut forged = Vec::new();
+        U32VarIntSerializer::new()
+            .serialize(&1000, &mut forged)
+            .unwrap();
+        forged.extend_from_slice(&buffer[1..]);
+        assert!(AddressesDeserializer::new(u32::MAX)
+            .deserialize::<DeserializeError>(&forged)
+            .is_err());
+
+        // truncated buffer
+        assert!(AddressesDeserializer::new(10)
+            .deserialize::<DeserializeError>(&buffer[..buffer.len() - 1])
+            .is_err());
+    }
+
+    #[test]
+    fn test_address_prefixed_bytes() {
+        let hash = massa_hash::Hash::compute_from("ADDR".as_bytes());
+        for addr in [
+            Address::User(UserAddress::UserAddressV0(UserAddressV0(hash))),
+            Address::SC(SCAddress::SCAddressV0(SCAddressV0(hash))),
+            Address::SC(SCAddress::SCAddressV1(SCAddressV1(hash))),
+        ] {
+            // same bytes as the serializer
+            let mut buffer = Vec::new();
+            AddressSerializer::new()
+                .serialize(&addr, &mut buffer)
+                .unwrap();
+            let bytes = addr.to_prefixed_bytes();
+            assert_eq!(&bytes[..], &buffer[..]);
+            assert_eq!(Address::from_prefixed_bytes(&bytes).unwrap(), addr);
+        }
+        let bytes =
+            Address::User(UserAddress::UserAddressV0(UserAddressV0(hash))).to_prefixed_bytes();
+        assert!(Address::from_prefixed_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Address::from_prefixed_bytes(&[&bytes[..], &[0]].concat()).is_err());
+    }
+
+    #[test]
+    fn test_address_bech32m() {
+        let hash = massa_hash::Hash::compute_from("ADDR".as_bytes());
+        let user_addr_0 = Address::User(UserAddress::UserAddressV0(UserAddressV0(hash)));
+        let sc_addr_0 = Address::SC(SCAddress::SCAddressV0(SCAddressV0(hash)));
+
+        for addr in [user_addr_0, sc_addr_0] {
+            let bech32m = addr.to_string_with_format(AddressFormat::Bech32m);
+            assert!(bech32m.starts_with("mas1"));
+            // both formats are detected, bech32m is case-insensitive
+            assert_eq!(Address::from_str(&bech32m).unwrap(), addr);
+            assert_eq!(Address::from_str(&bech32m.to_uppercase()).unwrap(), addr);
+            let base58check = addr.to_string_with_format(AddressFormat::Base58Check);
+            assert_eq!(Address::from_str(&base58check).unwrap(), addr);
+            assert!(Address::from_str_with_format(&bech32m, AddressFormat::Base58Check).is_err());
+
+            // a typo is caught by the checksum
+            let mut typo = bech32m.into_bytes();
+            let last = typo.len() - 1;
+            typo[last] = if typo[last] == b'q' { b'p' } else { b'q' };
+            assert!(Address::from_str(std::str::from_utf8(&typo).unwrap()).is_err());
+        }
+    }
 }
@@ -255,6 +255,136 @@ impl Amount {
     pub fn checked_rem_u64(&self, divisor: u64) -> Option<Amount> {
         Some(Amount(self.0.checked_rem(divisor)?))
     }
+
+    /// add another amount to self, returning `ModelsError::AmountOverflowError` on overflow
+    /// ```
+    /// # use massa_models::amount::Amount;
+    /// # use std::str::FromStr;
+    /// let amount_1 : Amount = Amount::from_str("42").unwrap();
+    /// let amount_2 : Amount = Amount::from_str("7").unwrap();
+    /// assert_eq!(amount_1.try_add(amount_2).unwrap(), Amount::from_str("49").unwrap());
+    /// assert!(Amount::MAX.try_add(amount_2).is_err());
+    /// ```
+    pub fn try_add(self, amount: Amount) -> Result<Self, ModelsError> {
+        self.checked_add(amount)
+            .ok_or(ModelsError::AmountOverflowError)
+    }
+
+    /// subtract another amount from self, returning `ModelsError::AmountUnderflowError` on underflow
+    /// ```
+    /// # use massa_models::amount::Amount;
+    /// # use std::str::FromStr;
+    /// let amount_1 : Amount = Amount::from_str("42").unwrap();
+    /// let amount_2 : Amount = Amount::from_str("7").unwrap();
+    /// assert_eq!(amount_1.try_sub(amount_2).unwrap(), Amount::from_str("35").unwrap());
+    /// assert!(amount_2.try_sub(amount_1).is_err());
+    /// ```
+    pub fn try_sub(self, amount: Amount) -> Result<Self, ModelsError> {
+        self.checked_sub(amount)
+            .ok_or(ModelsError::AmountUnderflowError)
+    }
+
+    /// multiply self with a `u64`, returning `ModelsError::AmountOverflowError` on overflow
+    /// ```
+    /// # use massa_models::amount::Amount;
+    /// # use std::str::FromStr;
+    /// let amount_1 : Amount = Amount::from_str("42").unwrap();
+    /// assert_eq!(amount_1.try_mul_u64(7).unwrap(), Amount::from_str("294").unwrap());
+    /// assert!(Amount::MAX.try_mul_u64(2).is_err());
+    /// ```
+    pub fn try_mul_u64(self, factor: u64) -> Result<Self, ModelsError> {
+        self.checked_mul_u64(factor)
+            .ok_or(ModelsError::AmountOverflowError)
+    }
+
+    /// divide self by a `u64`, returning `ModelsError::AmountDivisionByZeroError` if the factor is zero
+    /// ```
+    /// # use massa_models::amount::Amount;
+    /// # use std::str::FromStr;
+    /// let amount_1 : Amount = Amount::from_str("42").unwrap();
+    /// assert_eq!(amount_1.try_div_u64(7).unwrap(), Amount::from_str("6").unwrap());
+    /// assert!(amount_1.try_div_u64(0).is_err());
+    /// ```
+    pub fn try_div_u64(self, factor: u64) -> Result<Self, ModelsError> {
+        self.checked_div_u64(factor)
+            .ok_or(ModelsError::AmountDivisionByZeroError)
+    }
+
+    /// Parses an amount with an optional unit suffix: "1.5 MAS", "1500nMAS" or "1.5" (in MAS).
+    /// The format does not depend on the locale: the decimal separator is always `.`
+    /// and no digit grouping is allowed.
+    /// ```
+    /// # use massa_models::amount::Amount;
+    /// # use std::str::FromStr;
+    /// let amount = Amount::from_str("1.5").unwrap();
+    /// assert_eq!(Amount::from_str_with_unit("1.5 MAS").unwrap(), amount);
+    /// assert_eq!(Amount::from_str_with_unit("1500000000 nMAS").unwrap(), amount);
+    /// assert_eq!(Amount::from_str_with_unit("1500000000nMAS").unwrap(), amount);
+    /// assert_eq!(Amount::from_str_with_unit("1.5").unwrap(), amount);
+    /// assert!(Amount::from_str_with_unit("1.5 nMAS").is_err());
+    /// assert!(Amount::from_str_with_unit("1,5 MAS").is_err());
+    /// ```
+    pub fn from_str_with_unit(str_amount: &str) -> Result<Self, ModelsError> {
+        let str_amount = str_amount.trim();
+        let (value, unit) = match str_amount.strip_suffix(AmountUnit::NanoMas.symbol()) {
+            Some(value) => (value, AmountUnit::NanoMas),
+            None => match str_amount.strip_suffix(AmountUnit::Mas.symbol()) {
+                Some(value) => (value, AmountUnit::Mas),
+                None => (str_amount, AmountUnit::Mas),
+            },
+        };
+        let value = value.trim_end();
+        match unit {
+            AmountUnit::Mas => Amount::from_str(value),
+            AmountUnit::NanoMas => {
+                if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(ModelsError::AmountParseError(format!(
+                        "invalid {} amount: {}",
+                        AmountUnit::NanoMas.symbol(),
+                        value
+                    )));
+                }
+                value
+                    .parse::<u64>()
+                    .map(Amount::from_raw)
+                    .map_err(|_| ModelsError::AmountParseError("amount is too large".to_string()))
+            }
+        }
+    }
+
+    /// Formats the amount followed by the symbol of `unit`
+    /// ```
+    /// # use massa_models::amount::{Amount, AmountUnit};
+    /// # use std::str::FromStr;
+    /// let amount = Amount::from_str("1.5").unwrap();
+    /// assert_eq!(amount.to_string_with_unit(AmountUnit::Mas), "1.5 MAS");
+    /// assert_eq!(amount.to_string_with_unit(AmountUnit::NanoMas), "1500000000 nMAS");
+    /// ```
+    pub fn to_string_with_unit(&self, unit: AmountUnit) -> String {
+        match unit {
+            AmountUnit::Mas => format!("{} {}", self, unit.symbol()),
+            AmountUnit::NanoMas => format!("{} {}", self.0, unit.symbol()),
+        }
+    }
+}
+
+/// Units in which an amount can be written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AmountUnit {
+    /// MAS, the coin
+    Mas,
+    /// nMAS, the smallest fraction of a coin (`1 / AMOUNT_DECIMAL_FACTOR` MAS)
+    NanoMas,
+}
+
+impl AmountUnit {
+    /// Symbol of the unit, used as suffix
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            AmountUnit::Mas => "MAS",
+            AmountUnit::NanoMas => "nMAS",
+        }
+    }
 }
 
 /// display an Amount in decimal string form (like "10.33")
@@ -414,7 +544,8 @@ impl<'de> serde::de::Visitor<'de> for AmountVisitor {
     where
         E: serde::de::Error,
     {
-        Amount::from_str(value).map_err(|_| E::invalid_value(Unexpected::Str(value), &self))
+        Amount::from_str_with_unit(value)
+            .map_err(|_| E::invalid_value(Unexpected::Str(value), &self))
     }
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -433,3 +564,116 @@ impl serde::Serialize for Amount {
         serializer.serialize_str(&self.to_string())
     }
 }
+
+/// Serde support for amounts represented as an integer number of nMAS,
+/// to be used with `#[serde(with = "massa_models::amount::nano")]`.
+///
+/// Serializes as an integer. Deserializes from an integer number of nMAS,
+/// or from a string in any format accepted by `Amount::from_str_with_unit`.
+///
+/// ```
+/// # use massa_models::amount::Amount;
+/// # use std::str::FromStr;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Fee {
+///     #[serde(with = "massa_models::amount::nano")]
+///     fee: Amount,
+/// }
+/// let fee: Fee = serde_json::from_str(r#"{"fee": 1500}"#).unwrap();
+/// assert_eq!(fee.fee, Amount::from_str("0.0000015").unwrap());
+/// let fee: Fee = serde_json::from_str(r#"{"fee": "1.5 MAS"}"#).unwrap();
+/// assert_eq!(serde_json::to_string(&fee).unwrap(), r#"{"fee":1500000000}"#);
+/// ```
+pub mod nano {
+    use super::{Amount, AmountVisitor};
+    use std::fmt;
+
+    /// Serializes an amount as an integer number of nMAS
+    pub fn serialize<S>(amount: &Amount, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u64(amount.to_raw())
+    }
+
+    /// Deserializes an amount from an integer number of nMAS, or from a string
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Amount, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(NanoAmountVisitor)
+    }
+
+    struct NanoAmountVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for NanoAmountVisitor {
+        type Value = Amount;
+
+        fn visit_u64<E>(self, value: u64) -> Result<Amount, E>
+        where
+            E: serde::de::Error,
+        {
+            Ok(Amount::from_raw(value))
+        }
+
+        fn visit_i64<E>(self, value: i64) -> Result<Amount, E>
+        where
+            E: serde::de::Error,
+        {
+            u64::try_from(value)
+                .map(Amount::from_raw)
+                .map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(value), &self))
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Amount, E>
+        where
+            E: serde::de::Error,
+        {
+            serde::de::Visitor::visit_str(AmountVisitor, value)
+        }
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                formatter,
+                "an integer number of nMAS or a string representing a fixed-point currency amount"
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct NanoAmount {
+        #[serde(with = "nano")]
+        amount: Amount,
+    }
+
+    #[test]
+    fn test_amount_serde_representations() {
+        let amount = Amount::from_str("1.5").unwrap();
+
+        // default representation: decimal string, optionally with a unit
+        assert_eq!(serde_json::to_string(&amount).unwrap(), r#""1.5""#);
+        for repr in [r#""1.5""#, r#""1.5 MAS""#, r#""1500000000 nMAS""#] {
+            assert_eq!(serde_json::from_str::<Amount>(repr).unwrap(), amount);
+        }
+        assert!(serde_json::from_str::<Amount>("1500000000").is_err());
+
+        // nano representation: integer, strings still accepted
+        let nano_amount = NanoAmount { amount };
+        let serialized = serde_json::to_string(&nano_amount).unwrap();
+        assert_eq!(serialized, r#"{"amount":1500000000}"#);
+        assert_eq!(
+            serde_json::from_str::<NanoAmount>(&serialized).unwrap(),
+            nano_amount
+        );
+        assert_eq!(
+            serde_json::from_str::<NanoAmount>(r#"{"amount":"1.5 MAS"}"#).unwrap(),
+            nano_amount
+        );
+        assert!(serde_json::from_str::<NanoAmount>(r#"{"amount":-1}"#).is_err());
+    }
+}
@@ -46,6 +46,7 @@ use crate::block_id::BlockId;
 
 /// block
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Block {
     /// signed header
     pub header: SecuredHeader,
@@ -55,6 +56,7 @@ pub struct Block {
 
 /// filled block
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct FilledBlock {
     /// signed header
     pub header: SecuredHeader,
@@ -186,6 +188,7 @@ impl Serializer<Block> for BlockSerializer {
     ///             .unwrap(),
     ///         ],
     ///         denunciations: Vec::new(),
+    ///         extensions: Vec::new(),
     ///     },
     ///     BlockHeaderSerializer::new(),
     ///     &keypair,
@@ -289,6 +292,7 @@ impl Deserializer<Block> for BlockDeserializer {
     ///             .unwrap(),
     ///         ],
     ///         denunciations: Vec::new(),
+    ///         extensions: Vec::new(),
     ///     },
     ///     BlockHeaderSerializer::new(),
     ///     &keypair,
@@ -482,6 +486,7 @@ mod test {
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements: vec![endo1, endo2],
                 denunciations: Vec::new(), // FIXME
+                extensions: Vec::new(),
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -566,6 +571,7 @@ mod test {
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements: vec![],
                 denunciations: vec![],
+                extensions: Vec::new(),
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -659,6 +665,7 @@ mod test {
                 )
                 .unwrap()],
                 denunciations: vec![],
+                extensions: Vec::new(),
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -716,6 +723,7 @@ mod test {
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements: vec![],
                 denunciations: vec![],
+                extensions: Vec::new(),
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -768,6 +776,7 @@ mod test {
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements: vec![],
                 denunciations: vec![],
+                extensions: Vec::new(),
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -823,6 +832,7 @@ mod test {
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements: vec![],
                 denunciations: vec![],
+                extensions: Vec::new(),
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -901,6 +911,7 @@ mod test {
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements,
                 denunciations: vec![],
+                extensions: Vec::new(),
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -958,6 +969,7 @@ mod test {
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements: vec![],
                 denunciations: vec![],
+                extensions: Vec::new(),
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -1027,6 +1039,7 @@ mod test {
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements,
                 denunciations: vec![],
+                extensions: Vec::new(),
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -1101,6 +1114,7 @@ mod test {
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements: vec![endo1],
                 denunciations: vec![],
+                extensions: Vec::new(),
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -1187,6 +1201,7 @@ mod test {
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements: vec![endo1, endo2],
                 denunciations: vec![],
+                extensions: Vec::new(),
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -1,4 +1,8 @@
 use crate::block_id::{BlockId, BlockIdDeserializer, BlockIdSerializer};
+use crate::config::{
+    BLOCK_HEADER_EXTENSIONS_MIN_VERSION, MAX_BLOCK_HEADER_EXTENSIONS,
+    MAX_BLOCK_HEADER_EXTENSION_VALUE_LENGTH,
+};
 use crate::denunciation::{Denunciation, DenunciationDeserializer, DenunciationSerializer};
 use crate::endorsement::{
     Endorsement, EndorsementDeserializerLW, EndorsementId, EndorsementSerializer,
@@ -17,7 +21,7 @@ use massa_signature::PublicKey;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::error::{context, ContextError, ParseError};
-use nom::multi::{count, length_count};
+use nom::multi::{count, length_count, length_data};
 use nom::sequence::{preceded, tuple};
 use nom::{IResult, Parser};
 use serde::{Deserialize, Serialize};
@@ -27,6 +31,7 @@ use std::fmt::Formatter;
 
 /// block header
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct BlockHeader {
     /// current network version
     pub current_version: u32,
@@ -37,11 +42,196 @@ pub struct BlockHeader {
     /// parents
     pub parents: Vec<BlockId>,
     /// all operations hash
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::HashSchema")
+    )]
     pub operation_merkle_root: Hash,
     /// endorsements
     pub endorsements: Vec<SecureShareEndorsement>,
     /// denunciations
     pub denunciations: Vec<Denunciation>,
+    /// extension fields, sorted by strictly increasing tag.
+    /// Only serialized in block headers v2, i.e. when `current_version >= BLOCK_HEADER_EXTENSIONS_MIN_VERSION`,
+    /// and must be empty otherwise.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<BlockHeaderExtension>,
+}
+
+impl BlockHeader {
+    /// Returns true if the header is serialized with its extension section (block header v2)
+    pub fn has_extension_section(&self) -> bool {
+        self.current_version >= BLOCK_HEADER_EXTENSIONS_MIN_VERSION
+    }
+
+    /// Gets the value of the extension field with the given tag, if present
+    pub fn get_extension(&self, tag: u32) -> Option<&[u8]> {
+        self.extensions
+            .binary_search_by_key(&tag, |ext| ext.tag)
+            .ok()
+            .map(|index| self.extensions[index].value.as_slice())
+    }
+}
+
+/// Extension field of a block header, encoded as tag-length-value.
+///
+/// New header fields are given a tag and carried in the extension section,
+/// so that they can be added without changing the serialization of the header.
+/// Fields with a tag unknown to the node are kept as-is,
+/// which preserves the hash and the signature of the header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct BlockHeaderExtension {
+    /// type of the field
+    pub tag: u32,
+    /// raw value of the field
+    pub value: Vec<u8>,
+}
+
+/// Serializer for the extension section of a block header
+#[derive(Clone, Default)]
+pub struct BlockHeaderExtensionsSerializer {
+    u32_serializer: U32VarIntSerializer,
+}
+
+impl BlockHeaderExtensionsSerializer {
+    /// Creates a new `BlockHeaderExtensionsSerializer`
+    pub fn new() -> Self {
+        Self {
+            u32_serializer: U32VarIntSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<Vec<BlockHeaderExtension>> for BlockHeaderExtensionsSerializer {
+    /// ## Example:
+    /// ```rust
+    /// use massa_models::block_header::{BlockHeaderExtension, BlockHeaderExtensionsSerializer};
+    /// use massa_serialization::Serializer;
+    ///
+    /// let extensions = vec![
+    ///     BlockHeaderExtension { tag: 0, value: vec![1, 2, 3] },
+    ///     BlockHeaderExtension { tag: 4, value: vec![] },
+    /// ];
+    /// let mut buffer = Vec::new();
+    /// BlockHeaderExtensionsSerializer::new().serialize(&extensions, &mut buffer).unwrap();
+    /// assert_eq!(buffer, vec![2, 0, 3, 1, 2, 3, 4, 0]);
+    /// ```
+    fn serialize(
+        &self,
+        value: &Vec<BlockHeaderExtension>,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.u32_serializer.serialize(
+            &value.len().try_into().map_err(|err| {
+                SerializeError::GeneralError(format!("too many header extensions: {}", err))
+            })?,
+            buffer,
+        )?;
+        let mut previous_tag: Option<u32> = None;
+        for extension in value.iter() {
+            if let Some(prev) = previous_tag {
+                if extension.tag <= prev {
+                    return Err(SerializeError::GeneralError(format!(
+                        "header extension tags must be strictly increasing, found {} after {}",
+                        extension.tag, prev
+                    )));
+                }
+            }
+            previous_tag = Some(extension.tag);
+            self.u32_serializer.serialize(&extension.tag, buffer)?;
+            self.u32_serializer.serialize(
+                &extension.value.len().try_into().map_err(|err| {
+                    SerializeError::GeneralError(format!(
+                        "header extension value too long: {}",
+                        err
+                    ))
+                })?,
+                buffer,
+            )?;
+            buffer.extend(&extension.value);
+        }
+        Ok(())
+    }
+}
+
+/// Deserializer for the extension section of a block header
+#[derive(Clone)]
+pub struct BlockHeaderExtensionsDeserializer {
+    count_deserializer: U32VarIntDeserializer,
+    tag_deserializer: U32VarIntDeserializer,
+    value_length_deserializer: U32VarIntDeserializer,
+}
+
+impl BlockHeaderExtensionsDeserializer {
+    /// Creates a new `BlockHeaderExtensionsDeserializer`
+    pub fn new(max_extensions: u32, max_value_length: u32) -> Self {
+        Self {
+            count_deserializer: U32VarIntDeserializer::new(Included(0), Included(max_extensions)),
+            tag_deserializer: U32VarIntDeserializer::new(Included(0), Included(u32::MAX)),
+            value_length_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(max_value_length),
+            ),
+        }
+    }
+}
+
+impl Deserializer<Vec<BlockHeaderExtension>> for BlockHeaderExtensionsDeserializer {
+    /// ## Example:
+    /// ```rust
+    /// use massa_models::block_header::{BlockHeaderExtension, BlockHeaderExtensionsDeserializer};
+    /// use massa_serialization::{Deserializer, DeserializeError};
+    ///
+    /// let (rest, extensions) = BlockHeaderExtensionsDeserializer::new(16, 1024)
+    ///     .deserialize::<DeserializeError>(&[2, 0, 3, 1, 2, 3, 4, 0])
+    ///     .unwrap();
+    /// assert!(rest.is_empty());
+    /// assert_eq!(extensions[0], BlockHeaderExtension { tag: 0, value: vec![1, 2, 3] });
+    /// assert_eq!(extensions[1], BlockHeaderExtension { tag: 4, value: vec![] });
+    ///
+    /// // tags must be strictly increasing
+    /// assert!(BlockHeaderExtensionsDeserializer::new(16, 1024)
+    ///     .deserialize::<DeserializeError>(&[2, 4, 0, 4, 0])
+    ///     .is_err());
+    /// ```
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], Vec<BlockHeaderExtension>, E> {
+        let (rest, extensions): (&[u8], Vec<BlockHeaderExtension>) = context(
+            "Failed header extensions deserialization",
+            length_count(
+                context("Failed length deserialization", |input| {
+                    self.count_deserializer.deserialize(input)
+                }),
+                context("Failed header extension deserialization", |input| {
+                    let (rest, tag) = self.tag_deserializer.deserialize(input)?;
+                    let (rest, value) =
+                        length_data(|input| self.value_length_deserializer.deserialize(input))
+                            .parse(rest)?;
+                    Ok((
+                        rest,
+                        BlockHeaderExtension {
+                            tag,
+                            value: value.to_vec(),
+                        },
+                    ))
+                }),
+            ),
+        )
+        .parse(buffer)?;
+
+        // enforce a canonical encoding so that each header has a single serialization
+        if extensions.windows(2).any(|pair| pair[0].tag >= pair[1].tag) {
+            return Err(nom::Err::Failure(ContextError::add_context(
+                rest,
+                "Header extension tags must be strictly increasing",
+                ParseError::from_error_kind(rest, nom::error::ErrorKind::Fail),
+            )));
+        }
+        Ok((rest, extensions))
+    }
 }
 
 // TODO: gh-issue #3398
@@ -139,6 +329,7 @@ pub struct BlockHeaderSerializer {
     u32_serializer: U32VarIntSerializer,
     opt_serializer: OptionSerializer<u32, U32VarIntSerializer>,
     block_id_serializer: BlockIdSerializer,
+    extensions_serializer: BlockHeaderExtensionsSerializer,
 }
 
 impl BlockHeaderSerializer {
@@ -152,6 +343,7 @@ impl BlockHeaderSerializer {
             endorsement_content_serializer: EndorsementSerializerLW::new(),
             denunciation_serializer: DenunciationSerializer::new(),
             block_id_serializer: BlockIdSerializer::new(),
+            extensions_serializer: BlockHeaderExtensionsSerializer::new(),
         }
     }
 }
@@ -206,6 +398,7 @@ impl Serializer<BlockHeader> for BlockHeaderSerializer {
     ///     .unwrap(),
     ///    ],
     ///   denunciations: vec![],
+    ///   extensions: Vec::new(),
     /// };
     /// let mut buffer = vec![];
     /// BlockHeaderSerializer::new().serialize(&header, &mut buffer).unwrap();
@@ -258,10 +451,25 @@ impl Serializer<BlockHeader> for BlockHeaderSerializer {
                 .serialize(denunciation, buffer)?;
         }
 
+        // extension section (block header v2)
+        if value.has_extension_section() {
+            self.extensions_serializer
+                .serialize(&value.extensions, buffer)?;
+        } else if !value.extensions.is_empty() {
+            return Err(SerializeError::GeneralError(format!(
+                "header extensions require a network version of at least {}",
+                BLOCK_HEADER_EXTENSIONS_MIN_VERSION
+            )));
+        }
+
         Ok(())
     }
 }
 
+/// Context of the error returned when the serialized denunciations of a header exceed their maximum size,
+/// see `BlockHeaderDeserializer::with_max_denunciations_size`
+pub const DENUNCIATIONS_TOO_LARGE_ERROR: &str = "Denunciations exceed the maximum size";
+
 /// Deserializer for `BlockHeader`
 pub struct BlockHeaderDeserializer {
     slot_deserializer: SlotDeserializer,
@@ -273,9 +481,11 @@ pub struct BlockHeaderDeserializer {
     last_start_period: Option<u64>,
     denunciation_len_deserializer: U32VarIntDeserializer,
     denunciation_deserializer: DenunciationDeserializer,
+    max_denunciations_size: Option<usize>,
     network_versions_deserializer: U32VarIntDeserializer,
     opt_deserializer: OptionDeserializer<u32, U32VarIntDeserializer>,
     block_id_deserializer: BlockIdDeserializer,
+    extensions_deserializer: BlockHeaderExtensionsDeserializer,
 }
 
 impl BlockHeaderDeserializer {
@@ -314,7 +524,12 @@ impl BlockHeaderDeserializer {
                 thread_count,
                 endorsement_count,
             ),
+            max_denunciations_size: None,
             block_id_deserializer: BlockIdDeserializer::new(),
+            extensions_deserializer: BlockHeaderExtensionsDeserializer::new(
+                MAX_BLOCK_HEADER_EXTENSIONS,
+                MAX_BLOCK_HEADER_EXTENSION_VALUE_LENGTH,
+            ),
             thread_count,
             endorsement_count,
             last_start_period,
@@ -322,6 +537,28 @@ impl BlockHeaderDeserializer {
     }
 }
 
+impl BlockHeaderDeserializer {
+    /// Limits the serialized size of the denunciations of a header.
+    /// The size is checked after each denunciation, so that no denunciation is deserialized once the limit is exceeded.
+    pub fn with_max_denunciations_size(mut self, max_denunciations_size: usize) -> Self {
+        self.max_denunciations_size = Some(max_denunciations_size);
+        self
+    }
+
+    /// Deserializes the extension section, which is only present in block headers v2
+    fn deserialize_extensions<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        current_version: u32,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], Vec<BlockHeaderExtension>, E> {
+        if current_version >= BLOCK_HEADER_EXTENSIONS_MIN_VERSION {
+            self.extensions_deserializer.deserialize(buffer)
+        } else {
+            Ok((buffer, Vec::new()))
+        }
+    }
+}
+
 impl Deserializer<BlockHeader> for BlockHeaderDeserializer {
     /// ## Example:
     /// ```rust
@@ -366,6 +603,7 @@ impl Deserializer<BlockHeader> for BlockHeaderDeserializer {
     ///     .unwrap(),
     ///    ],
     ///    denunciations: vec![],
+    ///    extensions: Vec::new(),
     /// };
     /// let mut buffer = vec![];
     /// BlockHeaderSerializer::new().serialize(&header, &mut buffer).unwrap();
@@ -443,6 +681,11 @@ impl Deserializer<BlockHeader> for BlockHeaderDeserializer {
         .parse(buffer)?;
 
         if parents.is_empty() {
+            // As we have 0 endorsements & 0 denunciations, rest = [0, 0] (length 0 & length 0)
+            // As we want to return an empty "res" we use nom tag
+            let (rest2, _) = tag(&[0, 0])(rest)?;
+            let (rest2, extensions) = self.deserialize_extensions(current_version, rest2)?;
+
             let res = BlockHeader {
                 current_version,
                 announced_version,
@@ -451,6 +694,7 @@ impl Deserializer<BlockHeader> for BlockHeaderDeserializer {
                 operation_merkle_root,
                 endorsements: Vec::new(),
                 denunciations: Vec::new(),
+                extensions,
             };
 
             // TODO: gh-issue #3398
@@ -458,9 +702,6 @@ impl Deserializer<BlockHeader> for BlockHeaderDeserializer {
             res.assert_invariants(self.thread_count, self.endorsement_count)
                 .unwrap();
 
-            // As we have 0 endorsements & 0 denunciations, rest = [0, 0] (length 0 & length 0)
-            // As we want to return an empty "res" we use nom tag
-            let (rest2, _) = tag(&[0, 0])(rest)?;
             return Ok((rest2, res));
         }
 
@@ -510,18 +751,37 @@ impl Deserializer<BlockHeader> for BlockHeaderDeserializer {
 
         let (rest, denunciations): (&[u8], Vec<Denunciation>) = context(
             "Failed denunciations deserialization",
-            length_count::<&[u8], Denunciation, u32, E, _, _>(
-                context("Failed length deserialization", |input| {
-                    let (res, count) = self.denunciation_len_deserializer.deserialize(input)?;
-                    IResult::Ok((res, count))
-                }),
-                context("Failed denunciation deserialization", |input| {
-                    self.denunciation_deserializer.deserialize(input)
-                }),
-            ),
+            |input: &'a [u8]| {
+                let (mut rest, count) = context("Failed length deserialization", |input| {
+                    self.denunciation_len_deserializer.deserialize(input)
+                })
+                .parse(input)?;
+                let mut denunciations = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let (next, denunciation) =
+                        context("Failed denunciation deserialization", |input| {
+                            self.denunciation_deserializer.deserialize(input)
+                        })
+                        .parse(rest)?;
+                    rest = next;
+                    denunciations.push(denunciation);
+                    if let Some(max_size) = self.max_denunciations_size {
+                        if input.len() - rest.len() > max_size {
+                            return Err(nom::Err::Failure(ContextError::add_context(
+                                rest,
+                                DENUNCIATIONS_TOO_LARGE_ERROR,
+                                ParseError::from_error_kind(rest, nom::error::ErrorKind::TooLarge),
+                            )));
+                        }
+                    }
+                }
+                Ok((rest, denunciations))
+            },
         )
         .parse(rest)?;
 
+        let (rest, extensions) = self.deserialize_extensions(current_version, rest)?;
+
         let header = BlockHeader {
             current_version,
             announced_version,
@@ -530,6 +790,7 @@ impl Deserializer<BlockHeader> for BlockHeaderDeserializer {
             operation_merkle_root,
             endorsements,
             denunciations,
+            extensions,
         };
 
         // TODO: gh-issue #3398
@@ -575,6 +836,14 @@ impl std::fmt::Display for BlockHeader {
         if self.endorsements.is_empty() {
             writeln!(f, "\tNo endorsements found")?;
         }
+        for extension in self.extensions.iter() {
+            writeln!(
+                f,
+                "\tExtension {}: {} bytes",
+                extension.tag,
+                extension.value.len()
+            )?;
+        }
         Ok(())
     }
 }
@@ -619,6 +888,7 @@ mod test {
                 && self.operation_merkle_root == other.operation_merkle_root
                 && self.endorsements == other.endorsements
                 && self.denunciations == other.denunciations
+                && self.extensions == other.extensions
         }
     }
 
@@ -658,6 +928,7 @@ mod test {
             operation_merkle_root: Hash::compute_from("mno".as_bytes()),
             endorsements: vec![s_endorsement_1],
             denunciations: vec![de_a, de_b],
+            extensions: Vec::new(),
         };
 
         let mut buffer = Vec::new();
@@ -674,6 +945,28 @@ mod test {
 
         assert!(rem.is_empty());
         assert_eq!(block_header_1, block_header_der);
+
+        // the denunciations fit in the whole header, but not in a single byte
+        let der = BlockHeaderDeserializer::new(
+            THREAD_COUNT,
+            ENDORSEMENT_COUNT,
+            MAX_DENUNCIATIONS_PER_BLOCK_HEADER,
+            None,
+        );
+        der.with_max_denunciations_size(buffer.len())
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        let der = BlockHeaderDeserializer::new(
+            THREAD_COUNT,
+            ENDORSEMENT_COUNT,
+            MAX_DENUNCIATIONS_PER_BLOCK_HEADER,
+            None,
+        );
+        let err = der
+            .with_max_denunciations_size(1)
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap_err();
+        assert!(err.to_string().contains(DENUNCIATIONS_TOO_LARGE_ERROR));
     }
 
     #[test]
@@ -687,6 +980,7 @@ mod test {
             operation_merkle_root: Hash::compute_from("mno".as_bytes()),
             endorsements: vec![],
             denunciations: vec![],
+            extensions: Vec::new(),
         };
 
         let mut buffer = Vec::new();
@@ -705,6 +999,80 @@ mod test {
         assert_eq!(block_header_1, block_header_der);
     }
 
+    #[test]
+    fn test_block_header_v2_extensions_ser_der() {
+        let keypair = KeyPair::generate(0).unwrap();
+        let parents: Vec<BlockId> = (0..THREAD_COUNT)
+            .map(|i| BlockId::generate_from_hash(Hash::compute_from(&[i])))
+            .collect();
+        let mut header = BlockHeader {
+            current_version: 0,
+            announced_version: None,
+            slot: Slot::new(7, 1),
+            parents,
+            operation_merkle_root: Hash::compute_from("mno".as_bytes()),
+            endorsements: vec![],
+            denunciations: vec![],
+            extensions: vec![
+                BlockHeaderExtension {
+                    tag: 1,
+                    value: Hash::compute_from("state".as_bytes()).to_bytes().to_vec(),
+                },
+                BlockHeaderExtension {
+                    tag: 42,
+                    value: vec![],
+                },
+            ],
+        };
+        let der = BlockHeaderDeserializer::new(
+            THREAD_COUNT,
+            ENDORSEMENT_COUNT,
+            MAX_DENUNCIATIONS_PER_BLOCK_HEADER,
+            None,
+        );
+
+        // extensions cannot be carried by a v1 header
+        let mut buffer = Vec::new();
+        assert!(BlockHeaderSerializer::new()
+            .serialize(&header, &mut buffer)
+            .is_err());
+
+        header.current_version = BLOCK_HEADER_EXTENSIONS_MIN_VERSION;
+        assert!(header.has_extension_section());
+        assert_eq!(header.get_extension(42), Some(&[][..]));
+        assert_eq!(header.get_extension(2), None);
+
+        let secured_header: SecuredHeader =
+            BlockHeader::new_verifiable(header.clone(), BlockHeaderSerializer::new(), &keypair)
+                .unwrap();
+        let (rem, header_der) = der
+            .deserialize::<DeserializeError>(&secured_header.serialized_data)
+            .unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(header, header_der);
+        assert_eq!(
+            header_der.get_extension(1),
+            Some(&Hash::compute_from("state".as_bytes()).to_bytes()[..])
+        );
+        secured_header.verify_signature().unwrap();
+
+        // extensions must be sorted by strictly increasing tag
+        header.extensions.swap(0, 1);
+        let mut buffer = Vec::new();
+        assert!(BlockHeaderSerializer::new()
+            .serialize(&header, &mut buffer)
+            .is_err());
+
+        // a v2 header without the extension section is truncated
+        header.extensions.clear();
+        let mut buffer = Vec::new();
+        BlockHeaderSerializer::new()
+            .serialize(&header, &mut buffer)
+            .unwrap();
+        assert_eq!(buffer.pop(), Some(0));
+        assert!(der.deserialize::<DeserializeError>(&buffer).is_err());
+    }
+
     #[test]
     fn test_verify_sig_batch() {
         let (_slot, _keypair, secured_header_1, secured_header_2, secured_header_3) =
@@ -1,4 +1,5 @@
 use crate::error::ModelsError;
+use crate::id_prefix::BlockIdPrefix;
 use crate::prehash::PreHashed;
 use crate::secure_share::Id;
 use massa_hash::{Hash, HashDeserializer};
@@ -52,6 +53,11 @@ impl BlockId {
         }
     }
 
+    /// compact prefix of the block id, used as a key of the large caches
+    pub fn prefix(&self) -> BlockIdPrefix {
+        BlockIdPrefix::from_id(self)
+    }
+
     /// Generate a version 0 block id from an hash used only for tests
     #[cfg(any(test, feature = "test-exports"))]
     pub fn generate_from_hash(hash: Hash) -> BlockId {
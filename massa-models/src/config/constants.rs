@@ -299,6 +299,10 @@ pub const MAX_SIZE_CHANNEL_NETWORK_TO_BLOCK_HANDLER: usize = 10000;
 pub const MAX_SIZE_CHANNEL_NETWORK_TO_ENDORSEMENT_HANDLER: usize = 10000;
 /// Maximum size of channel used to send network events to the peer handler
 pub const MAX_SIZE_CHANNEL_NETWORK_TO_PEER_HANDLER: usize = 10000;
+/// Maximum size of channel used for commands in topic handler
+pub const MAX_SIZE_CHANNEL_COMMANDS_TOPICS: usize = 10000;
+/// Maximum size of channel used to send network events to the topic handler
+pub const MAX_SIZE_CHANNEL_NETWORK_TO_TOPIC_HANDLER: usize = 10000;
 /// Maximum number of peer in a announcement list of peer
 pub const MAX_PEERS_IN_ANNOUNCEMENT_LIST: u64 = 100;
 /// Maximum number of listeners for a peer
@@ -325,6 +329,23 @@ pub const MAX_DENUNCIATIONS_PER_BLOCK_HEADER: u32 = 128;
 pub const ROLL_COUNT_TO_SLASH_ON_DENUNCIATION: u64 = 1;
 /// Maximum size of executed denunciations
 pub const MAX_DENUNCIATION_CHANGES_LENGTH: u64 = 1_000;
+/// Maximum size in bytes of the proof carried by a versioned denunciation
+pub const MAX_DENUNCIATION_PROOF_LENGTH: u32 = 1_024;
+/// Maximum size in bytes of the serialized denunciations of a block header received from a peer
+pub const MAX_BLOCK_HEADER_DENUNCIATIONS_SIZE: u32 = 65_536;
+/// Maximum size in bytes of a serialized block header received from a peer
+pub const MAX_BLOCK_HEADER_SIZE: u32 = 131_072;
+
+//
+// Constants for block header extensions
+//
+
+/// Network version from which block headers carry an extension section (block header v2)
+pub const BLOCK_HEADER_EXTENSIONS_MIN_VERSION: u32 = 1;
+/// Max number of extension fields in a block header
+pub const MAX_BLOCK_HEADER_EXTENSIONS: u32 = 16;
+/// Max length (in bytes) of the value of a block header extension field
+pub const MAX_BLOCK_HEADER_EXTENSION_VALUE_LENGTH: u32 = 1_024;
 
 // Some checks at compile time that should not be ignored!
 #[allow(clippy::assertions_on_constants)]
@@ -33,32 +33,71 @@ use std::path::Path;
 /// 3. in path specified in `MASSA_CONFIG_OVERRIDE_PATH` environment variable (`config/config.toml` by default)
 #[inline]
 pub fn build_massa_settings<T: Deserialize<'static>>(app_name: &str, env_prefix: &str) -> T {
+    build_massa_settings_with_profile(app_name, env_prefix, None)
+}
+
+/// Merge the settings like `build_massa_settings`,
+/// with the settings of a network profile file merged right after the default configuration file
+pub fn build_massa_settings_with_profile<T: Deserialize<'static>>(
+    app_name: &str,
+    env_prefix: &str,
+    profile_path: Option<&Path>,
+) -> T {
+    try_build_massa_settings_with_profile(app_name, env_prefix, profile_path).unwrap()
+}
+
+/// Merge the settings like `build_massa_settings_with_profile`, returning an error instead of panicking
+/// if a file is missing or if the merged settings cannot be deserialized
+pub fn try_build_massa_settings_with_profile<T: Deserialize<'static>>(
+    app_name: &str,
+    env_prefix: &str,
+    profile_path: Option<&Path>,
+) -> Result<T, config::ConfigError> {
     let mut builder = config::Config::builder();
-    let config_path = std::env::var("MASSA_CONFIG_PATH")
-        .unwrap_or_else(|_| "base_config/config.toml".to_string());
+    for name in massa_settings_file_names(app_name, profile_path) {
+        builder = builder.add_source(config::File::with_name(&name));
+    }
+    builder
+        .add_source(config::Environment::with_prefix(env_prefix))
+        .build()?
+        .try_deserialize()
+}
+
+/// Names of the configuration files merged by `build_massa_settings_with_profile`, in merge order:
+/// the default configuration file, the network profile file, the override file and the user configuration.
+/// The optional files are only listed if they exist.
+/// Names are meant for `config::File::with_name`, so they may lack the file extension.
+pub fn massa_settings_file_names(app_name: &str, profile_path: Option<&Path>) -> Vec<String> {
+    let mut names = vec![std::env::var("MASSA_CONFIG_PATH")
+        .unwrap_or_else(|_| "base_config/config.toml".to_string())];
 
-    builder = builder.add_source(config::File::with_name(&config_path));
+    if let Some(profile_path) = profile_path {
+        names.push(profile_path.to_string_lossy().into_owned());
+    }
 
     let config_override_path = std::env::var("MASSA_CONFIG_OVERRIDE_PATH")
         .unwrap_or_else(|_| "config/config.toml".to_string());
 
     if Path::new(&config_override_path).is_file() {
-        builder = builder.add_source(config::File::with_name(&config_override_path));
+        names.push(config_override_path);
     }
 
     if let Some(proj_dirs) = ProjectDirs::from("com", "MassaLabs", app_name) {
         // Portable user config loading
         let user_config_path = proj_dirs.config_dir();
         if user_config_path.exists() {
-            let path_str = user_config_path.to_str().unwrap();
-            builder = builder.add_source(config::File::with_name(path_str));
+            names.push(user_config_path.to_str().unwrap().to_string());
         }
     }
 
-    let s = builder
-        .add_source(config::Environment::with_prefix(env_prefix))
-        .build()
-        .unwrap();
+    names
+}
 
-    s.try_deserialize().unwrap()
+/// Read a single configuration file, without merging it with the others,
+/// to inspect the keys it sets
+pub fn read_massa_settings_file(name: &str) -> Result<serde_json::Value, config::ConfigError> {
+    config::Config::builder()
+        .add_source(config::File::with_name(name))
+        .build()?
+        .try_deserialize()
 }
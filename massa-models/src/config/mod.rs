@@ -68,4 +68,7 @@ pub use compact_config::CompactConfig;
 
 // Export tool to read user setting file
 mod massa_settings;
-pub use massa_settings::build_massa_settings;
+pub use massa_settings::{
+    build_massa_settings, build_massa_settings_with_profile, massa_settings_file_names,
+    read_massa_settings_file, try_build_massa_settings_with_profile,
+};
@@ -37,6 +37,7 @@ use std::ops::Bound::{Excluded, Included};
 
 use nom::{
     error::{context, ContextError, ParseError},
+    multi::length_data,
     sequence::tuple,
     IResult, Parser,
 };
@@ -45,13 +46,15 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::block_header::{BlockHeaderDenunciationData, SecuredHeader};
+use crate::config::MAX_DENUNCIATION_PROOF_LENGTH;
 use crate::endorsement::{EndorsementDenunciationData, SecureShareEndorsement};
 use crate::slot::{Slot, SlotDeserializer, SlotSerializer};
 
 use crate::secure_share::Id;
 use massa_hash::{Hash, HashDeserializer, HashSerializer};
 use massa_serialization::{
-    Deserializer, SerializeError, Serializer, U32VarIntDeserializer, U32VarIntSerializer,
+    DeserializeError, Deserializer, OptionDeserializer, SerializeError, Serializer,
+    U32VarIntDeserializer, U32VarIntSerializer,
 };
 use massa_signature::{
     MassaSignatureError, PublicKey, PublicKeyDeserializer, Signature, SignatureDeserializer,
@@ -61,13 +64,34 @@ use variant_count::VariantCount;
 /// A Variant of Denunciation enum for endorsement
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct EndorsementDenunciation {
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::PublicKeySchema")
+    )]
     public_key: PublicKey,
     slot: Slot,
     index: u32,
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::HashSchema")
+    )]
     hash_1: Hash,
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::HashSchema")
+    )]
     hash_2: Hash,
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::SignatureSchema")
+    )]
     signature_1: Signature,
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::SignatureSchema")
+    )]
     signature_2: Signature,
 }
 
@@ -94,12 +118,33 @@ impl EndorsementDenunciation {
 /// A Variant of Denunciation enum for block header
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct BlockHeaderDenunciation {
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::PublicKeySchema")
+    )]
     public_key: PublicKey,
     slot: Slot,
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::HashSchema")
+    )]
     hash_1: Hash,
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::HashSchema")
+    )]
     hash_2: Hash,
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::SignatureSchema")
+    )]
     signature_1: Signature,
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::SignatureSchema")
+    )]
     signature_2: Signature,
 }
 
@@ -122,12 +167,129 @@ impl BlockHeaderDenunciation {
     }
 }
 
+/// Misbehavior kind of the proofs that a staker produced several block headers for the same slot
+pub const BLOCK_HEADER_EQUIVOCATION_KIND: u32 = 0;
+/// Misbehavior kind of the proofs that a staker produced several endorsements for the same slot and index
+pub const ENDORSEMENT_EQUIVOCATION_KIND: u32 = 1;
+/// Version of the equivocation proofs (both kinds):
+/// the 2 content hashes followed by the 2 signatures
+pub const EQUIVOCATION_PROOF_VERSION: u32 = 0;
+
+/// A Variant of Denunciation enum carrying a versioned proof of misbehavior
+///
+/// The proof is opaque to the serialization, so that new kinds of misbehavior
+/// (or new proof formats of an existing kind) do not change the serialization of denunciations.
+/// The denounced draw (block production or endorsement at `index`) is explicit
+/// so that a staker is slashed at most once per draw, whatever the kind of the proof.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct VersionedDenunciation {
+    /// public key of the denounced staker
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::PublicKeySchema")
+    )]
+    pub public_key: PublicKey,
+    /// slot of the denounced draw
+    pub slot: Slot,
+    /// endorsement index of the denounced draw, None for a block production draw
+    pub index: Option<u32>,
+    /// kind of misbehavior
+    pub kind: u32,
+    /// version of the proof format of this kind of misbehavior
+    pub version: u32,
+    /// proof of the misbehavior
+    pub proof: Vec<u8>,
+}
+
+impl VersionedDenunciation {
+    /// Decode an equivocation proof into the corresponding endorsement or block header denunciation
+    pub fn to_equivocation(&self) -> Result<Denunciation, DenunciationError> {
+        if self.version != EQUIVOCATION_PROOF_VERSION {
+            return Err(DenunciationError::InvalidInput(format!(
+                "unknown proof version {} for misbehavior kind {}",
+                self.version, self.kind
+            )));
+        }
+        let (hash_1, hash_2, signature_1, signature_2) =
+            deserialize_equivocation_proof(&self.proof)?;
+        match (self.kind, self.index) {
+            (BLOCK_HEADER_EQUIVOCATION_KIND, None) => {
+                Ok(Denunciation::BlockHeader(BlockHeaderDenunciation {
+                    public_key: self.public_key,
+                    slot: self.slot,
+                    hash_1,
+                    hash_2,
+                    signature_1,
+                    signature_2,
+                }))
+            }
+            (ENDORSEMENT_EQUIVOCATION_KIND, Some(index)) => {
+                Ok(Denunciation::Endorsement(EndorsementDenunciation {
+                    public_key: self.public_key,
+                    slot: self.slot,
+                    index,
+                    hash_1,
+                    hash_2,
+                    signature_1,
+                    signature_2,
+                }))
+            }
+            (kind, index) => Err(DenunciationError::InvalidInput(format!(
+                "misbehavior kind {} is not an equivocation of draw {:?}",
+                kind, index
+            ))),
+        }
+    }
+}
+
+/// Serialize the proof of an equivocation (format `EQUIVOCATION_PROOF_VERSION`)
+fn serialize_equivocation_proof(
+    hash_1: &Hash,
+    hash_2: &Hash,
+    signature_1: &Signature,
+    signature_2: &Signature,
+) -> Vec<u8> {
+    let mut proof = Vec::new();
+    proof.extend(hash_1.to_bytes());
+    proof.extend(hash_2.to_bytes());
+    proof.extend(signature_1.to_bytes());
+    proof.extend(signature_2.to_bytes());
+    proof
+}
+
+/// Deserialize the proof of an equivocation (format `EQUIVOCATION_PROOF_VERSION`)
+fn deserialize_equivocation_proof(
+    proof: &[u8],
+) -> Result<(Hash, Hash, Signature, Signature), DenunciationError> {
+    let hash_deserializer = HashDeserializer::new();
+    let signature_deserializer = SignatureDeserializer::new();
+    let (rest, parts) = tuple((
+        |input| hash_deserializer.deserialize::<DeserializeError>(input),
+        |input| hash_deserializer.deserialize(input),
+        |input| signature_deserializer.deserialize(input),
+        |input| signature_deserializer.deserialize(input),
+    ))
+    .parse(proof)
+    .map_err(|err| {
+        DenunciationError::InvalidInput(format!("invalid equivocation proof: {}", err))
+    })?;
+    if !rest.is_empty() {
+        return Err(DenunciationError::InvalidInput(
+            "invalid equivocation proof: trailing bytes".to_string(),
+        ));
+    }
+    Ok(parts)
+}
+
 /// A denunciation enum
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[allow(missing_docs)]
 pub enum Denunciation {
     Endorsement(EndorsementDenunciation),
     BlockHeader(BlockHeaderDenunciation),
+    Versioned(VersionedDenunciation),
 }
 
 #[allow(dead_code)]
@@ -149,6 +311,10 @@ impl Denunciation {
     ) -> Result<bool, DenunciationError> {
         match self {
             Denunciation::BlockHeader(_) => Ok(false),
+            Denunciation::Versioned(de) => match de.to_equivocation() {
+                Ok(equivocation) => equivocation.is_also_for_endorsement(s_endorsement),
+                Err(_) => Ok(false),
+            },
             Denunciation::Endorsement(endo_de) => {
                 let content_hash = s_endorsement.id.get_hash();
 
@@ -179,6 +345,10 @@ impl Denunciation {
     ) -> Result<bool, DenunciationError> {
         match self {
             Denunciation::Endorsement(_) => Ok(false),
+            Denunciation::Versioned(de) => match de.to_equivocation() {
+                Ok(equivocation) => equivocation.is_also_for_block_header(s_block_header),
+                Err(_) => Ok(false),
+            },
             Denunciation::BlockHeader(endo_bh) => {
                 let content_hash = s_block_header.id.get_hash();
 
@@ -202,8 +372,16 @@ impl Denunciation {
 
     /// Check if Denunciation is valid
     /// Should be used if received from the network (prevent against invalid or attacker crafted denunciation)
+    ///
+    /// Versioned denunciations are only valid if their proof is a valid equivocation proof,
+    /// other kinds of misbehavior are verified by the denunciation pool verifiers
     pub fn is_valid(&self) -> bool {
         let (signature_1, signature_2, hash_1, hash_2, public_key) = match self {
+            Denunciation::Versioned(de) => {
+                return de
+                    .to_equivocation()
+                    .map_or(false, |equivocation| equivocation.is_valid());
+            }
             Denunciation::Endorsement(de) => {
                 let hash_1 = EndorsementDenunciation::compute_hash_for_sig_verif(
                     &de.public_key,
@@ -258,6 +436,7 @@ impl Denunciation {
         match self {
             Denunciation::Endorsement(de) => &de.slot,
             Denunciation::BlockHeader(de) => &de.slot,
+            Denunciation::Versioned(de) => &de.slot,
         }
     }
 
@@ -266,6 +445,7 @@ impl Denunciation {
         match self {
             Denunciation::BlockHeader(_) => None,
             Denunciation::Endorsement(de) => Some(&de.index),
+            Denunciation::Versioned(de) => de.index.as_ref(),
         }
     }
 
@@ -274,6 +454,41 @@ impl Denunciation {
         match self {
             Denunciation::Endorsement(de) => &de.public_key,
             Denunciation::BlockHeader(de) => &de.public_key,
+            Denunciation::Versioned(de) => &de.public_key,
+        }
+    }
+
+    /// Express the denunciation as a versioned one,
+    /// endorsement and block header denunciations becoming equivocation proofs
+    pub fn to_versioned(&self) -> VersionedDenunciation {
+        match self {
+            Denunciation::Endorsement(de) => VersionedDenunciation {
+                public_key: de.public_key,
+                slot: de.slot,
+                index: Some(de.index),
+                kind: ENDORSEMENT_EQUIVOCATION_KIND,
+                version: EQUIVOCATION_PROOF_VERSION,
+                proof: serialize_equivocation_proof(
+                    &de.hash_1,
+                    &de.hash_2,
+                    &de.signature_1,
+                    &de.signature_2,
+                ),
+            },
+            Denunciation::BlockHeader(de) => VersionedDenunciation {
+                public_key: de.public_key,
+                slot: de.slot,
+                index: None,
+                kind: BLOCK_HEADER_EQUIVOCATION_KIND,
+                version: EQUIVOCATION_PROOF_VERSION,
+                proof: serialize_equivocation_proof(
+                    &de.hash_1,
+                    &de.hash_2,
+                    &de.signature_1,
+                    &de.signature_2,
+                ),
+            },
+            Denunciation::Versioned(de) => de.clone(),
         }
     }
 
@@ -399,6 +614,7 @@ impl TryFrom<(&SecuredHeader, &SecuredHeader)> for Denunciation {
 pub enum DenunciationTypeId {
     BlockHeader = 0,
     Endorsement = 1,
+    Versioned = 2,
 }
 
 impl From<&Denunciation> for DenunciationTypeId {
@@ -406,6 +622,7 @@ impl From<&Denunciation> for DenunciationTypeId {
         match value {
             Denunciation::Endorsement(_) => DenunciationTypeId::Endorsement,
             Denunciation::BlockHeader(_) => DenunciationTypeId::BlockHeader,
+            Denunciation::Versioned(_) => DenunciationTypeId::Versioned,
         }
     }
 }
@@ -646,10 +863,135 @@ impl Deserializer<BlockHeaderDenunciation> for BlockHeaderDenunciationDeserializ
     }
 }
 
+/// Serializer for `VersionedDenunciation`
+struct VersionedDenunciationSerializer {
+    slot_serializer: SlotSerializer,
+    u32_serializer: U32VarIntSerializer,
+}
+
+impl VersionedDenunciationSerializer {
+    /// Creates a new `VersionedDenunciationSerializer`
+    const fn new() -> Self {
+        Self {
+            slot_serializer: SlotSerializer::new(),
+            u32_serializer: U32VarIntSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<VersionedDenunciation> for VersionedDenunciationSerializer {
+    fn serialize(
+        &self,
+        value: &VersionedDenunciation,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.u32_serializer.serialize(&value.kind, buffer)?;
+        self.u32_serializer.serialize(&value.version, buffer)?;
+        buffer.extend(value.public_key.to_bytes());
+        self.slot_serializer.serialize(&value.slot, buffer)?;
+        // same layout as `OptionSerializer`
+        if let Some(index) = value.index {
+            buffer.push(b'1');
+            self.u32_serializer.serialize(&index, buffer)?;
+        } else {
+            buffer.push(b'0');
+        }
+        let proof_len: u32 = value.proof.len().try_into().map_err(|_| {
+            SerializeError::GeneralError("denunciation proof is too long".to_string())
+        })?;
+        if proof_len > MAX_DENUNCIATION_PROOF_LENGTH {
+            return Err(SerializeError::GeneralError(format!(
+                "denunciation proof length {} exceeds the maximum of {}",
+                proof_len, MAX_DENUNCIATION_PROOF_LENGTH
+            )));
+        }
+        self.u32_serializer.serialize(&proof_len, buffer)?;
+        buffer.extend(&value.proof);
+        Ok(())
+    }
+}
+
+/// Deserializer for `VersionedDenunciation`
+struct VersionedDenunciationDeserializer {
+    kind_deserializer: U32VarIntDeserializer,
+    version_deserializer: U32VarIntDeserializer,
+    pubkey_deserializer: PublicKeyDeserializer,
+    slot_deserializer: SlotDeserializer,
+    index_deserializer: OptionDeserializer<u32, U32VarIntDeserializer>,
+    proof_length_deserializer: U32VarIntDeserializer,
+}
+
+impl VersionedDenunciationDeserializer {
+    /// Creates a new `VersionedDenunciationDeserializer`
+    const fn new(thread_count: u8, endorsement_count: u32) -> Self {
+        Self {
+            kind_deserializer: U32VarIntDeserializer::new(Included(0), Included(u32::MAX)),
+            version_deserializer: U32VarIntDeserializer::new(Included(0), Included(u32::MAX)),
+            pubkey_deserializer: PublicKeyDeserializer::new(),
+            slot_deserializer: SlotDeserializer::new(
+                (Included(0), Included(u64::MAX)),
+                (Included(0), Excluded(thread_count)),
+            ),
+            index_deserializer: OptionDeserializer::new(U32VarIntDeserializer::new(
+                Included(0),
+                Excluded(endorsement_count),
+            )),
+            proof_length_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(MAX_DENUNCIATION_PROOF_LENGTH),
+            ),
+        }
+    }
+}
+
+impl Deserializer<VersionedDenunciation> for VersionedDenunciationDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], VersionedDenunciation, E> {
+        context(
+            "Failed Versioned Denunciation deserialization",
+            tuple((
+                context("Failed kind deserialization", |input| {
+                    self.kind_deserializer.deserialize(input)
+                }),
+                context("Failed version deserialization", |input| {
+                    self.version_deserializer.deserialize(input)
+                }),
+                context("Failed public key deserialization", |input| {
+                    self.pubkey_deserializer.deserialize(input)
+                }),
+                context("Failed slot deserialization", |input| {
+                    self.slot_deserializer.deserialize(input)
+                }),
+                context("Failed index deserialization", |input| {
+                    self.index_deserializer.deserialize(input)
+                }),
+                context(
+                    "Failed proof deserialization",
+                    length_data(|input| self.proof_length_deserializer.deserialize(input)),
+                ),
+            )),
+        )
+        .map(
+            |(kind, version, public_key, slot, index, proof)| VersionedDenunciation {
+                public_key,
+                slot,
+                index,
+                kind,
+                version,
+                proof: proof.to_vec(),
+            },
+        )
+        .parse(buffer)
+    }
+}
+
 /// Serializer for `Denunciation`
 pub struct DenunciationSerializer {
     endo_de_serializer: EndorsementDenunciationSerializer,
     blkh_de_serializer: BlockHeaderDenunciationSerializer,
+    versioned_de_serializer: VersionedDenunciationSerializer,
     type_id_serializer: U32VarIntSerializer,
 }
 
@@ -659,6 +1001,7 @@ impl DenunciationSerializer {
         Self {
             endo_de_serializer: EndorsementDenunciationSerializer::new(),
             blkh_de_serializer: BlockHeaderDenunciationSerializer::new(),
+            versioned_de_serializer: VersionedDenunciationSerializer::new(),
             type_id_serializer: U32VarIntSerializer::new(),
         }
     }
@@ -682,6 +1025,9 @@ impl Serializer<Denunciation> for DenunciationSerializer {
             Denunciation::BlockHeader(de) => {
                 self.blkh_de_serializer.serialize(de, buffer)?;
             }
+            Denunciation::Versioned(de) => {
+                self.versioned_de_serializer.serialize(de, buffer)?;
+            }
         }
         Ok(())
     }
@@ -691,6 +1037,7 @@ impl Serializer<Denunciation> for DenunciationSerializer {
 pub struct DenunciationDeserializer {
     endo_de_deserializer: EndorsementDenunciationDeserializer,
     blkh_de_deserializer: BlockHeaderDenunciationDeserializer,
+    versioned_de_deserializer: VersionedDenunciationDeserializer,
     type_id_deserializer: U32VarIntDeserializer,
 }
 
@@ -703,6 +1050,10 @@ impl DenunciationDeserializer {
                 endorsement_count,
             ),
             blkh_de_deserializer: BlockHeaderDenunciationDeserializer::new(thread_count),
+            versioned_de_deserializer: VersionedDenunciationDeserializer::new(
+                thread_count,
+                endorsement_count,
+            ),
             type_id_deserializer: U32VarIntDeserializer::new(
                 Included(0),
                 Excluded(DenunciationTypeId::VARIANT_COUNT as u32),
@@ -737,6 +1088,10 @@ impl Deserializer<Denunciation> for DenunciationDeserializer {
                 let (rem2, blkh_de) = self.blkh_de_deserializer.deserialize(rem)?;
                 IResult::Ok((rem2, Denunciation::BlockHeader(blkh_de)))
             }
+            DenunciationTypeId::Versioned => {
+                let (rem2, versioned_de) = self.versioned_de_deserializer.deserialize(rem)?;
+                IResult::Ok((rem2, Denunciation::Versioned(versioned_de)))
+            }
         }
     }
 }
@@ -806,6 +1161,15 @@ impl From<&Denunciation> for DenunciationIndex {
             Denunciation::BlockHeader(blkh_de) => {
                 DenunciationIndex::BlockHeader { slot: blkh_de.slot }
             }
+            Denunciation::Versioned(versioned_de) => match versioned_de.index {
+                Some(index) => DenunciationIndex::Endorsement {
+                    slot: versioned_de.slot,
+                    index,
+                },
+                None => DenunciationIndex::BlockHeader {
+                    slot: versioned_de.slot,
+                },
+            },
         }
     }
 }
@@ -1483,4 +1847,64 @@ mod tests {
         assert!(rem.is_empty());
         assert_eq!(denunciation_index_2, de_idx_der_res);
     }
+
+    #[test]
+    fn test_versioned_denunciation() {
+        let (_, _, s_endorsement_1, s_endorsement_2, s_endorsement_3) =
+            gen_endorsements_for_denunciation(None, None);
+        let (_, _, s_block_header_1, s_block_header_2, _) =
+            gen_block_headers_for_denunciation(None, None);
+
+        let de_ser = DenunciationSerializer::new();
+        let de_der = DenunciationDeserializer::new(THREAD_COUNT, ENDORSEMENT_COUNT);
+        for denunciation in [
+            Denunciation::try_from((&s_endorsement_1, &s_endorsement_2)).unwrap(),
+            Denunciation::try_from((&s_block_header_1, &s_block_header_2)).unwrap(),
+        ] {
+            let versioned = Denunciation::Versioned(denunciation.to_versioned());
+            assert!(versioned.is_valid());
+            assert_eq!(versioned.get_slot(), denunciation.get_slot());
+            assert_eq!(versioned.get_index(), denunciation.get_index());
+            assert_eq!(versioned.get_public_key(), denunciation.get_public_key());
+            // both forms denounce the same draw
+            assert_eq!(
+                DenunciationIndex::from(&versioned),
+                DenunciationIndex::from(&denunciation)
+            );
+            let Denunciation::Versioned(versioned_de) = &versioned else {
+                panic!("not a versioned denunciation");
+            };
+            assert_eq!(versioned_de.to_equivocation().unwrap(), denunciation);
+
+            let mut buffer = Vec::new();
+            de_ser.serialize(&versioned, &mut buffer).unwrap();
+            let (rem, de_der_res) = de_der.deserialize::<DeserializeError>(&buffer).unwrap();
+            assert!(rem.is_empty());
+            assert_eq!(versioned, de_der_res);
+        }
+
+        let denunciation = Denunciation::try_from((&s_endorsement_1, &s_endorsement_2)).unwrap();
+        assert!(Denunciation::Versioned(denunciation.to_versioned())
+            .is_also_for_endorsement(&s_endorsement_3)
+            .unwrap());
+
+        // unknown kinds of misbehavior are still (de)serializable, but not valid
+        let mut unknown = denunciation.to_versioned();
+        unknown.kind = 42;
+        unknown.version = 3;
+        let unknown = Denunciation::Versioned(unknown);
+        assert!(!unknown.is_valid());
+        let mut buffer = Vec::new();
+        de_ser.serialize(&unknown, &mut buffer).unwrap();
+        let (rem, de_der_res) = de_der.deserialize::<DeserializeError>(&buffer).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(unknown, de_der_res);
+
+        // proof too long
+        let mut too_long = denunciation.to_versioned();
+        too_long.proof = vec![0; MAX_DENUNCIATION_PROOF_LENGTH as usize + 1];
+        assert!(de_ser
+            .serialize(&Denunciation::Versioned(too_long), &mut Vec::new())
+            .is_err());
+    }
 }
@@ -223,6 +223,7 @@ impl Display for Endorsement {
 
 /// an endorsement, as sent in the network
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Endorsement {
     /// Slot in which the endorsement can be included
     pub slot: Slot,
@@ -626,4 +627,32 @@ mod tests {
         ];
         verify_signature_batch(&batch_2).unwrap();
     }
+
+    #[test]
+    fn test_secure_share_verify_batch() {
+        let endorsements: Vec<SecureShareEndorsement> = (0..4)
+            .map(|index| {
+                let content = Endorsement {
+                    slot: Slot::new(10, 1),
+                    index,
+                    endorsed_block: BlockId::generate_from_hash(Hash::compute_from(
+                        "blk".as_bytes(),
+                    )),
+                };
+                Endorsement::new_verifiable(
+                    content,
+                    EndorsementSerializer::new(),
+                    &KeyPair::generate(0).unwrap(),
+                )
+                .unwrap()
+            })
+            .collect();
+        SecureShareEndorsement::verify_batch(&endorsements[..1]).unwrap();
+        SecureShareEndorsement::verify_batch(&endorsements).unwrap();
+
+        // a single invalid signature makes the whole batch fail
+        let mut tampered = endorsements.clone();
+        tampered[2].signature = tampered[3].signature;
+        assert!(SecureShareEndorsement::verify_batch(&tampered).is_err());
+    }
 }
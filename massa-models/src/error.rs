@@ -54,6 +54,10 @@ pub enum ModelsError {
     InvalidRollUpdate(String),
     /// Ledger changes, Amount overflow
     AmountOverflowError,
+    /// Amount underflow
+    AmountUnderflowError,
+    /// Amount division by zero
+    AmountDivisionByZeroError,
     /// Wrong prefix for hash: expected {0}, got {1}
     WrongPrefix(String, String),
     /// Wrong operation id size deduced on join
@@ -62,6 +66,12 @@ pub enum ModelsError {
     OutdatedBootstrapCursor,
     /// Error raised {0}
     ErrorRaised(String),
+    /// short id parsing error: {0}
+    ShortIdParseError(String),
+    /// short id {0} does not match any known id
+    UnknownShortId(String),
+    /// short id {0} is ambiguous: it matches several known ids
+    AmbiguousShortId(String),
 }
 
 impl From<nom::Err<nom::error::Error<&[u8]>>> for ModelsError {
@@ -28,4 +28,6 @@ pub struct EventFilter {
     /// Some(false) means events coming from a succeeded sc execution
     /// None means both
     pub is_error: Option<bool>,
+    /// optional prefix of the event data
+    pub data_prefix: Option<String>,
 }
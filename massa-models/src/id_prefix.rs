@@ -0,0 +1,80 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Compact map keys made of the first bytes of the ids.
+//!
+//! Block, operation and endorsement ids are hashes: their first bytes are enough to tell them apart
+//! in the caches, while taking half the memory of the full ids in the large maps.
+//! Two different ids can still share a prefix, so the users of prefix keys must either tolerate
+//! collisions (e.g. a peer knowledge cache wrongly telling that a peer knows an id), or fall back
+//! to the full id stored with the value and treat a mismatch as a missing entry.
+
+use crate::prehash::PreHashed;
+use crate::secure_share::Id;
+use massa_hash::HASH_SIZE_BYTES;
+
+/// Size in bytes of the block id prefixes
+pub const BLOCK_ID_PREFIX_SIZE_BYTES: usize = 16;
+
+/// First `N` bytes of the hash of an id
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct IdPrefix<const N: usize>([u8; N]);
+
+/// Prefix of a block id
+pub type BlockIdPrefix = IdPrefix<BLOCK_ID_PREFIX_SIZE_BYTES>;
+
+// the hasher of the prehash maps reads the last 8 bytes of the keys
+impl<const N: usize> PreHashed for IdPrefix<N> {}
+
+impl<const N: usize> IdPrefix<N> {
+    /// Evaluated at compile time for each prefix size in use
+    const VALID_SIZE: () = assert!(
+        N >= 8 && N <= HASH_SIZE_BYTES,
+        "an id prefix must have between 8 bytes and the size of a hash"
+    );
+
+    /// Prefix of an id
+    pub fn from_id<I: Id>(id: &I) -> Self {
+        let () = Self::VALID_SIZE;
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(&id.get_hash().to_bytes()[..N]);
+        IdPrefix(bytes)
+    }
+
+    /// Returns true if the id starts with this prefix
+    pub fn matches<I: Id>(&self, id: &I) -> bool {
+        id.get_hash().to_bytes().starts_with(&self.0)
+    }
+
+    /// Bytes of the prefix
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_id::BlockId;
+    use crate::prehash::PreHashMap;
+    use massa_hash::Hash;
+
+    #[test]
+    fn test_block_id_prefix() {
+        let block_id = BlockId::generate_from_hash(Hash::compute_from(b"block"));
+        let prefix = block_id.prefix();
+        assert_eq!(
+            prefix.as_bytes()[..],
+            block_id.get_hash().to_bytes()[..BLOCK_ID_PREFIX_SIZE_BYTES]
+        );
+        assert!(prefix.matches(&block_id));
+
+        let other_id = BlockId::generate_from_hash(Hash::compute_from(b"other block"));
+        assert!(!prefix.matches(&other_id));
+
+        let mut map: PreHashMap<BlockIdPrefix, u32> = PreHashMap::default();
+        map.insert(prefix, 1);
+        map.insert(other_id.prefix(), 2);
+        assert_eq!(map.get(&block_id.prefix()), Some(&1));
+        assert_eq!(map.get(&other_id.prefix()), Some(&2));
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! JSON schemas of the models, enabled by the `json-schema` feature.
+//!
+//! The schemas describe the human-readable (JSON) serialization of the models,
+//! so that API consumers can generate their clients from them.
+//! Most models derive their schema. The types with a custom `Serialize` implementation
+//! (addresses, amounts, ids) and the foreign types (hashes, public keys, signatures)
+//! are serialized as strings in JSON and get their schema from this module.
+//!
+//! ```
+//! let definitions = massa_models::json_schema::definitions();
+//! assert!(definitions.contains_key("Operation"));
+//! assert!(definitions.contains_key("Address"));
+//! ```
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, Schema, SchemaObject, StringValidation},
+    JsonSchema, Map,
+};
+
+use crate::{
+    address::Address,
+    amount::Amount,
+    block::{Block, FilledBlock},
+    block_header::{BlockHeader, SecuredHeader},
+    block_id::BlockId,
+    denunciation::Denunciation,
+    endorsement::{Endorsement, EndorsementId, SecureShareEndorsement},
+    operation::{Operation, OperationId, OperationType, SecureShareOperation},
+    slot::{IndexedSlot, Slot},
+};
+
+/// Schema of a string, with a description and an optional pattern
+fn string_schema(description: &str, pattern: Option<&str>) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        metadata: Some(Box::new(Metadata {
+            description: Some(description.to_owned()),
+            ..Default::default()
+        })),
+        string: pattern.map(|pattern| {
+            Box::new(StringValidation {
+                pattern: Some(pattern.to_owned()),
+                ..Default::default()
+            })
+        }),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// Implements `JsonSchema` for a type serialized as a string in human-readable formats
+macro_rules! impl_string_json_schema {
+    ($ty:ty, $name:literal, $description:literal, $pattern:expr) => {
+        impl JsonSchema for $ty {
+            fn schema_name() -> String {
+                $name.to_owned()
+            }
+
+            fn json_schema(_: &mut SchemaGenerator) -> Schema {
+                string_schema($description, $pattern)
+            }
+        }
+    };
+}
+
+/// Schema of a `massa_hash::Hash` field, serialized in base58check.
+/// To be used with `#[schemars(with = "crate::json_schema::HashSchema")]`.
+pub struct HashSchema;
+
+/// Schema of a `massa_signature::PublicKey` field, serialized in base58check with its version prefix.
+/// To be used with `#[schemars(with = "crate::json_schema::PublicKeySchema")]`.
+pub struct PublicKeySchema;
+
+/// Schema of a `massa_signature::Signature` field, serialized in base58check with its version prefix.
+/// To be used with `#[schemars(with = "crate::json_schema::SignatureSchema")]`.
+pub struct SignatureSchema;
+
+impl_string_json_schema!(HashSchema, "Hash", "base58check encoded hash", None);
+impl_string_json_schema!(
+    PublicKeySchema,
+    "PublicKey",
+    "base58check encoded public key, prefixed with `P`",
+    Some("^P")
+);
+impl_string_json_schema!(
+    SignatureSchema,
+    "Signature",
+    "base58check encoded signature",
+    None
+);
+impl_string_json_schema!(
+    Address,
+    "Address",
+    "user address (prefixed with `AU`) or smart contract address (prefixed with `AS`)",
+    Some("^A[US]")
+);
+impl_string_json_schema!(
+    Amount,
+    "Amount",
+    "amount of coins in MAS, as a decimal string like \"10.33\"",
+    Some(r"^[0-9]+(\.[0-9]+)?$")
+);
+impl_string_json_schema!(
+    BlockId,
+    "BlockId",
+    "block id, prefixed with `B`",
+    Some("^B")
+);
+impl_string_json_schema!(
+    OperationId,
+    "OperationId",
+    "operation id, prefixed with `O`",
+    Some("^O")
+);
+impl_string_json_schema!(
+    EndorsementId,
+    "EndorsementId",
+    "endorsement id, prefixed with `E`",
+    Some("^E")
+);
+
+/// Gets the schema definitions of the models exposed by the API, indexed by schema name
+pub fn definitions() -> Map<String, Schema> {
+    let mut generator = SchemaGenerator::default();
+    generator.subschema_for::<Address>();
+    generator.subschema_for::<Amount>();
+    generator.subschema_for::<Slot>();
+    generator.subschema_for::<IndexedSlot>();
+    generator.subschema_for::<BlockId>();
+    generator.subschema_for::<OperationId>();
+    generator.subschema_for::<EndorsementId>();
+    generator.subschema_for::<Operation>();
+    generator.subschema_for::<OperationType>();
+    generator.subschema_for::<SecureShareOperation>();
+    generator.subschema_for::<Endorsement>();
+    generator.subschema_for::<SecureShareEndorsement>();
+    generator.subschema_for::<Denunciation>();
+    generator.subschema_for::<BlockHeader>();
+    generator.subschema_for::<SecuredHeader>();
+    generator.subschema_for::<Block>();
+    generator.subschema_for::<FilledBlock>();
+    generator.take_definitions()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_string_schemas_match_serialization() {
+        let address =
+            Address::from_str("AU12htxRWiEm8jDJpJptr6cwEhWNcCSFWstN1MLSa96DDkVM9Y42G").unwrap();
+        let amount = Amount::from_str("10.33").unwrap();
+        let definitions = definitions();
+        for (name, value) in [
+            ("Address", serde_json::to_value(address).unwrap()),
+            ("Amount", serde_json::to_value(amount).unwrap()),
+        ] {
+            let Schema::Object(schema) = &definitions[name] else {
+                panic!("{} schema is not an object", name);
+            };
+            assert_eq!(
+                schema.instance_type,
+                Some(InstanceType::String.into()),
+                "{} is not serialized as a string",
+                name
+            );
+            assert!(value.is_string(), "{} is not serialized as a string", name);
+        }
+    }
+
+    #[test]
+    fn test_datastore_schema() {
+        let definitions = serde_json::to_value(definitions()).unwrap();
+        let execute_sc = definitions["OperationType"]["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find_map(|variant| variant["properties"].get("ExecuteSC"))
+            .unwrap();
+        assert_eq!(execute_sc["properties"]["datastore"]["type"], "array");
+    }
+}
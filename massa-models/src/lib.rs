@@ -36,6 +36,11 @@ pub mod endorsement;
 pub mod error;
 /// execution related structures
 pub mod execution;
+/// JSON schemas of the models, for API client code generation
+#[cfg(feature = "json-schema")]
+pub mod json_schema;
+/// compact map keys made of id prefixes
+pub mod id_prefix;
 /// ledger related structures
 pub mod ledger;
 /// mapping grpc
@@ -44,6 +49,8 @@ pub mod mapping_grpc;
 pub mod node;
 /// operations
 pub mod operation;
+/// fluent operation construction, checked against the network limits
+pub mod operation_builder;
 /// smart contract output events
 pub mod output_event;
 /// pre-hashed trait, for hash less hashmap/set
@@ -54,6 +61,8 @@ pub mod rolls;
 pub mod secure_share;
 /// serialization
 pub mod serialization;
+/// abbreviated display forms of ids
+pub mod short_id;
 /// slots
 pub mod slot;
 /// various statistics
@@ -68,3 +77,6 @@ pub mod version;
 /// Test utils
 #[cfg(feature = "test-exports")]
 pub mod test_exports;
+
+#[cfg(test)]
+mod tests;
@@ -389,6 +389,7 @@ enum OperationTypeId {
 
 /// the operation as sent in the network
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 // Only for unit test, otherwise, comparison should be made between OperationId
 #[cfg_attr(test, derive(PartialEq))]
 pub struct Operation {
@@ -569,6 +570,7 @@ impl Deserializer<Operation> for OperationDeserializer {
 /// Type specific operation content
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub enum OperationType {
     /// transfer coins from sender to recipient
     Transaction {
@@ -596,6 +598,7 @@ pub enum OperationType {
         /// Max amount of coins allowed to be spent by the execution
         max_coins: Amount,
         /// A key-value store associating a hash to arbitrary bytes
+        #[cfg_attr(feature = "json-schema", schemars(with = "Vec<(Vec<u8>, Vec<u8>)>"))]
         #[serde_as(as = "Vec<(_, _)>")]
         datastore: Datastore,
     },
@@ -0,0 +1,506 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Fluent construction of operations, checked against the network limits before signing.
+//!
+//! ## Example
+//! ```rust
+//! # use massa_models::{address::Address, amount::Amount, operation_builder::OperationBuilder};
+//! # use massa_signature::KeyPair;
+//! # use std::str::FromStr;
+//! let keypair = KeyPair::generate(0).unwrap();
+//! let recipient = Address::from_public_key(&KeyPair::generate(0).unwrap().get_public_key());
+//! let operation = OperationBuilder::transaction(recipient, Amount::from_str("10").unwrap())
+//!     .fee(Amount::from_str("0.01").unwrap())
+//!     .current_period(1000)
+//!     .sign(&keypair)
+//!     .unwrap();
+//! assert_eq!(operation.content.expire_period, 1010);
+//! ```
+
+use crate::{
+    address::Address,
+    amount::Amount,
+    config::{
+        BASE_OPERATION_GAS_COST, MAX_DATASTORE_VALUE_LENGTH, MAX_FUNCTION_NAME_LENGTH,
+        MAX_GAS_PER_BLOCK, MAX_OPERATION_DATASTORE_ENTRY_COUNT, MAX_OPERATION_DATASTORE_KEY_LENGTH,
+        MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_PARAMETERS_SIZE, OPERATION_VALIDITY_PERIODS,
+    },
+    datastore::Datastore,
+    error::ModelsError,
+    operation::{Operation, OperationSerializer, OperationType, SecureShareOperation},
+    secure_share::SecureShareContent,
+};
+use displaydoc::Display;
+use massa_signature::KeyPair;
+use thiserror::Error;
+
+/// Reasons why an operation would be rejected by the network
+#[non_exhaustive]
+#[derive(Display, Error, Debug, Clone)]
+pub enum OperationBuilderError {
+    /// missing expire period: set it explicitly or set the current period
+    MissingExpirePeriod,
+    /// fee {fee} is lower than the minimal fee {min_fee}
+    FeeTooLow {
+        /// fee of the operation
+        fee: Amount,
+        /// minimal fee
+        min_fee: Amount,
+    },
+    /// expire period {expire_period} is lower than the current period {current_period}: the operation would never be included in a block
+    Expired {
+        /// expire period of the operation
+        expire_period: u64,
+        /// current period
+        current_period: u64,
+    },
+    /// expire period {expire_period} is more than {validity_periods} periods after the current period {current_period}: the operation cannot be included in a block yet
+    ExpirePeriodTooFar {
+        /// expire period of the operation
+        expire_period: u64,
+        /// current period
+        current_period: u64,
+        /// number of periods during which an operation can be included
+        validity_periods: u64,
+    },
+    /// max gas {max_gas} is higher than the maximal value {max_allowed} that fits in a block
+    MaxGasTooHigh {
+        /// max gas of the operation
+        max_gas: u64,
+        /// highest acceptable max gas
+        max_allowed: u64,
+    },
+    /// bytecode length {length} exceeds the maximum of {max}
+    BytecodeTooLong {
+        /// bytecode length
+        length: u64,
+        /// maximum length
+        max: u64,
+    },
+    /// function name length {length} exceeds the maximum of {max}
+    FunctionNameTooLong {
+        /// function name length
+        length: u64,
+        /// maximum length
+        max: u64,
+    },
+    /// parameter length {length} exceeds the maximum of {max}
+    ParameterTooLong {
+        /// parameter length
+        length: u64,
+        /// maximum length
+        max: u64,
+    },
+    /// datastore has {count} entries, more than the maximum of {max}
+    TooManyDatastoreEntries {
+        /// number of entries
+        count: u64,
+        /// maximum number of entries
+        max: u64,
+    },
+    /// datastore key length {length} exceeds the maximum of {max}
+    DatastoreKeyTooLong {
+        /// key length
+        length: u64,
+        /// maximum length
+        max: u64,
+    },
+    /// datastore value length {length} exceeds the maximum of {max}
+    DatastoreValueTooLong {
+        /// value length
+        length: u64,
+        /// maximum length
+        max: u64,
+    },
+    /// could not sign the operation: {0}
+    SignatureError(#[from] ModelsError),
+}
+
+/// Limits against which the `OperationBuilder` checks operations.
+/// The default values are the ones of `massa_models::config`.
+#[derive(Debug, Clone)]
+pub struct OperationLimits {
+    /// number of periods during which an operation can be included in a block
+    pub operation_validity_periods: u64,
+    /// maximal gas per block
+    pub max_gas_per_block: u64,
+    /// gas cost of any operation
+    pub base_operation_gas_cost: u64,
+    /// gas cost of the compilation of the bytecode of an `ExecuteSC` operation.
+    /// It is read from the gas costs file of the node, so it defaults to 0.
+    pub sp_compilation_cost: u64,
+    /// maximal length of the bytecode of an `ExecuteSC` operation
+    pub max_bytecode_length: u64,
+    /// maximal length of the function name of a `CallSC` operation
+    pub max_function_name_length: u16,
+    /// maximal length of the parameter of a `CallSC` operation
+    pub max_parameters_size: u32,
+    /// maximal number of entries of the datastore of an `ExecuteSC` operation
+    pub max_op_datastore_entry_count: u64,
+    /// maximal key length of the datastore of an `ExecuteSC` operation
+    pub max_op_datastore_key_length: u8,
+    /// maximal value length of the datastore of an `ExecuteSC` operation
+    pub max_op_datastore_value_length: u64,
+}
+
+impl Default for OperationLimits {
+    fn default() -> Self {
+        OperationLimits {
+            operation_validity_periods: OPERATION_VALIDITY_PERIODS,
+            max_gas_per_block: MAX_GAS_PER_BLOCK,
+            base_operation_gas_cost: BASE_OPERATION_GAS_COST,
+            sp_compilation_cost: 0,
+            max_bytecode_length: MAX_DATASTORE_VALUE_LENGTH,
+            max_function_name_length: MAX_FUNCTION_NAME_LENGTH,
+            max_parameters_size: MAX_PARAMETERS_SIZE,
+            max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+            max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
+            max_op_datastore_value_length: MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        }
+    }
+}
+
+/// Builds an `Operation`, checking it against the network limits before signing it
+#[derive(Debug, Clone)]
+pub struct OperationBuilder {
+    op: OperationType,
+    fee: Amount,
+    min_fee: Amount,
+    expire_period: Option<u64>,
+    current_period: Option<u64>,
+    limits: OperationLimits,
+}
+
+impl OperationBuilder {
+    /// Starts building an operation of the given type, with no fee
+    pub fn new(op: OperationType) -> Self {
+        OperationBuilder {
+            op,
+            fee: Amount::zero(),
+            min_fee: Amount::zero(),
+            expire_period: None,
+            current_period: None,
+            limits: OperationLimits::default(),
+        }
+    }
+
+    /// Starts building a coin transfer
+    pub fn transaction(recipient_address: Address, amount: Amount) -> Self {
+        Self::new(OperationType::Transaction {
+            recipient_address,
+            amount,
+        })
+    }
+
+    /// Starts building a roll purchase
+    pub fn roll_buy(roll_count: u64) -> Self {
+        Self::new(OperationType::RollBuy { roll_count })
+    }
+
+    /// Starts building a roll sale
+    pub fn roll_sell(roll_count: u64) -> Self {
+        Self::new(OperationType::RollSell { roll_count })
+    }
+
+    /// Starts building a smart contract execution
+    pub fn execute_sc(
+        data: Vec<u8>,
+        max_gas: u64,
+        max_coins: Amount,
+        datastore: Datastore,
+    ) -> Self {
+        Self::new(OperationType::ExecuteSC {
+            data,
+            max_gas,
+            max_coins,
+            datastore,
+        })
+    }
+
+    /// Starts building a smart contract call
+    pub fn call_sc(
+        target_addr: Address,
+        target_func: String,
+        param: Vec<u8>,
+        max_gas: u64,
+        coins: Amount,
+    ) -> Self {
+        Self::new(OperationType::CallSC {
+            target_addr,
+            target_func,
+            param,
+            max_gas,
+            coins,
+        })
+    }
+
+    /// Sets the fee of the operation
+    pub fn fee(mut self, fee: Amount) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Sets the minimal fee accepted by the node the operation is sent to
+    pub fn min_fee(mut self, min_fee: Amount) -> Self {
+        self.min_fee = min_fee;
+        self
+    }
+
+    /// Sets the period after which the operation cannot be included in a block anymore
+    pub fn expire_period(mut self, expire_period: u64) -> Self {
+        self.expire_period = Some(expire_period);
+        self
+    }
+
+    /// Sets the current period of the network, to check the expire period against it.
+    /// If no expire period is set, the operation expires as late as possible.
+    pub fn current_period(mut self, current_period: u64) -> Self {
+        self.current_period = Some(current_period);
+        self
+    }
+
+    /// Overrides the limits the operation is checked against
+    pub fn limits(mut self, limits: OperationLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Checks the operation and returns it, unsigned
+    pub fn build(self) -> Result<Operation, OperationBuilderError> {
+        if self.fee < self.min_fee {
+            return Err(OperationBuilderError::FeeTooLow {
+                fee: self.fee,
+                min_fee: self.min_fee,
+            });
+        }
+        let expire_period = self.check_expire_period()?;
+        self.check_type()?;
+        Ok(Operation {
+            fee: self.fee,
+            expire_period,
+            op: self.op,
+        })
+    }
+
+    /// Checks the operation and signs it with the given keypair
+    pub fn sign(self, keypair: &KeyPair) -> Result<SecureShareOperation, OperationBuilderError> {
+        let operation = self.build()?;
+        Ok(Operation::new_verifiable(
+            operation,
+            OperationSerializer::new(),
+            keypair,
+        )?)
+    }
+
+    fn check_expire_period(&self) -> Result<u64, OperationBuilderError> {
+        let validity_periods = self.limits.operation_validity_periods;
+        match (self.expire_period, self.current_period) {
+            (None, None) => Err(OperationBuilderError::MissingExpirePeriod),
+            (Some(expire_period), None) => Ok(expire_period),
+            (None, Some(current_period)) => Ok(current_period.saturating_add(validity_periods)),
+            (Some(expire_period), Some(current_period)) => {
+                if expire_period < current_period {
+                    Err(OperationBuilderError::Expired {
+                        expire_period,
+                        current_period,
+                    })
+                } else if expire_period > current_period.saturating_add(validity_periods) {
+                    Err(OperationBuilderError::ExpirePeriodTooFar {
+                        expire_period,
+                        current_period,
+                        validity_periods,
+                    })
+                } else {
+                    Ok(expire_period)
+                }
+            }
+        }
+    }
+
+    fn check_type(&self) -> Result<(), OperationBuilderError> {
+        let limits = &self.limits;
+        match &self.op {
+            OperationType::ExecuteSC {
+                data,
+                max_gas,
+                datastore,
+                ..
+            } => {
+                check_length(data.len(), limits.max_bytecode_length, |length, max| {
+                    OperationBuilderError::BytecodeTooLong { length, max }
+                })?;
+                check_max_gas(
+                    *max_gas,
+                    limits
+                        .max_gas_per_block
+                        .saturating_sub(limits.base_operation_gas_cost)
+                        .saturating_sub(limits.sp_compilation_cost),
+                )?;
+                check_length(
+                    datastore.len(),
+                    limits.max_op_datastore_entry_count,
+                    |count, max| OperationBuilderError::TooManyDatastoreEntries { count, max },
+                )?;
+                for (key, value) in datastore.iter() {
+                    check_length(
+                        key.len(),
+                        limits.max_op_datastore_key_length as u64,
+                        |length, max| OperationBuilderError::DatastoreKeyTooLong { length, max },
+                    )?;
+                    check_length(
+                        value.len(),
+                        limits.max_op_datastore_value_length,
+                        |length, max| OperationBuilderError::DatastoreValueTooLong { length, max },
+                    )?;
+                }
+            }
+            OperationType::CallSC {
+                target_func,
+                param,
+                max_gas,
+                ..
+            } => {
+                check_length(
+                    target_func.len(),
+                    limits.max_function_name_length as u64,
+                    |length, max| OperationBuilderError::FunctionNameTooLong { length, max },
+                )?;
+                check_length(
+                    param.len(),
+                    limits.max_parameters_size as u64,
+                    |length, max| OperationBuilderError::ParameterTooLong { length, max },
+                )?;
+                check_max_gas(
+                    *max_gas,
+                    limits
+                        .max_gas_per_block
+                        .saturating_sub(limits.base_operation_gas_cost),
+                )?;
+            }
+            OperationType::Transaction { .. }
+            | OperationType::RollBuy { .. }
+            | OperationType::RollSell { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+fn check_length(
+    length: usize,
+    max: u64,
+    error: impl FnOnce(u64, u64) -> OperationBuilderError,
+) -> Result<(), OperationBuilderError> {
+    let length = length as u64;
+    if length > max {
+        return Err(error(length, max));
+    }
+    Ok(())
+}
+
+fn check_max_gas(max_gas: u64, max_allowed: u64) -> Result<(), OperationBuilderError> {
+    if max_gas > max_allowed {
+        return Err(OperationBuilderError::MaxGasTooHigh {
+            max_gas,
+            max_allowed,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn recipient() -> Address {
+        Address::from_str("AU12cMW9zRKFDS43Z2W88VCmdQFxmHjAo54XvuVV34UzJeXRLXW9M").unwrap()
+    }
+
+    #[test]
+    fn test_operation_builder_expire_period() {
+        let builder = OperationBuilder::roll_buy(1);
+        assert!(matches!(
+            builder.clone().build(),
+            Err(OperationBuilderError::MissingExpirePeriod)
+        ));
+        assert_eq!(
+            builder
+                .clone()
+                .current_period(5)
+                .build()
+                .unwrap()
+                .expire_period,
+            5 + OPERATION_VALIDITY_PERIODS
+        );
+        assert!(matches!(
+            builder.clone().current_period(5).expire_period(4).build(),
+            Err(OperationBuilderError::Expired { .. })
+        ));
+        assert!(matches!(
+            builder
+                .clone()
+                .current_period(5)
+                .expire_period(6 + OPERATION_VALIDITY_PERIODS)
+                .build(),
+            Err(OperationBuilderError::ExpirePeriodTooFar { .. })
+        ));
+        assert_eq!(builder.expire_period(4).build().unwrap().expire_period, 4);
+    }
+
+    #[test]
+    fn test_operation_builder_limits() {
+        let fee = Amount::from_str("0.01").unwrap();
+        assert!(matches!(
+            OperationBuilder::transaction(recipient(), Amount::zero())
+                .fee(fee)
+                .min_fee(Amount::from_str("0.1").unwrap())
+                .expire_period(10)
+                .build(),
+            Err(OperationBuilderError::FeeTooLow { .. })
+        ));
+
+        let max_call_gas = MAX_GAS_PER_BLOCK - BASE_OPERATION_GAS_COST;
+        let call = |max_gas| {
+            OperationBuilder::call_sc(
+                recipient(),
+                "main".to_string(),
+                vec![],
+                max_gas,
+                Amount::zero(),
+            )
+            .expire_period(10)
+        };
+        assert!(call(max_call_gas).build().is_ok());
+        assert!(matches!(
+            call(max_call_gas + 1).build(),
+            Err(OperationBuilderError::MaxGasTooHigh { .. })
+        ));
+
+        let execute = |datastore| {
+            OperationBuilder::execute_sc(vec![0; 10], 1_000_000, Amount::zero(), datastore)
+                .expire_period(10)
+        };
+        let too_many_entries: Datastore = (0..=MAX_OPERATION_DATASTORE_ENTRY_COUNT)
+            .map(|i| (i.to_be_bytes().to_vec(), vec![]))
+            .collect();
+        assert!(matches!(
+            execute(too_many_entries).build(),
+            Err(OperationBuilderError::TooManyDatastoreEntries { .. })
+        ));
+        let long_key = Datastore::from([(
+            vec![0; MAX_OPERATION_DATASTORE_KEY_LENGTH as usize + 1],
+            vec![],
+        )]);
+        assert!(matches!(
+            execute(long_key).build(),
+            Err(OperationBuilderError::DatastoreKeyTooLong { .. })
+        ));
+        let keypair = KeyPair::generate(0).unwrap();
+        let operation = execute(Datastore::from([(b"key".to_vec(), b"value".to_vec())]))
+            .fee(fee)
+            .sign(&keypair)
+            .unwrap();
+        operation.verify_signature().unwrap();
+        assert_eq!(operation.content.fee, fee);
+    }
+}
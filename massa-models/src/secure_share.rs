@@ -4,7 +4,8 @@ use crate::{address::Address, error::ModelsError};
 use massa_hash::Hash;
 use massa_serialization::{Deserializer, SerializeError, Serializer};
 use massa_signature::{
-    KeyPair, PublicKey, PublicKeyDeserializer, Signature, SignatureDeserializer,
+    verify_signature_batch, KeyPair, PublicKey, PublicKeyDeserializer, Signature,
+    SignatureDeserializer,
 };
 use nom::{
     error::{context, ContextError, ParseError},
@@ -18,6 +19,7 @@ use serde::{Deserialize, Serialize};
 /// If the internal content is mutated, then it must be re-wrapped, as the assosciated
 /// signature, serialized data, etc. would no longer be in sync
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct SecureShare<T, ID>
 where
     T: Display + SecureShareContent,
@@ -33,8 +35,16 @@ where
     pub serialized_data: Vec<u8>,
 
     /// A cryptographically generated value using `serialized_data` and a public key.
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::SignatureSchema")
+    )]
     pub signature: Signature,
     /// The public-key component used in the generation of the signature
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "crate::json_schema::PublicKeySchema")
+    )]
     pub content_creator_pub_key: PublicKey,
     /// Derived from the same public key used to generate the signature
     pub content_creator_address: Address,
@@ -217,6 +227,27 @@ where
         )
     }
 
+    /// check the signatures of several secure shares at once, using ed25519 batch verification.
+    /// Returns an error if at least one signature is invalid, without telling which one.
+    pub fn verify_batch<'a, I>(items: I) -> Result<(), ModelsError>
+    where
+        I: IntoIterator<Item = &'a Self>,
+        T: 'a,
+        ID: 'a,
+    {
+        let batch: Vec<(Hash, Signature, PublicKey)> = items
+            .into_iter()
+            .map(|item| {
+                (
+                    item.compute_signed_hash(),
+                    item.signature,
+                    item.content_creator_pub_key,
+                )
+            })
+            .collect();
+        Ok(verify_signature_batch(&batch)?)
+    }
+
     /// Compute the signed hash
     pub fn compute_signed_hash(&self) -> Hash {
         self.content
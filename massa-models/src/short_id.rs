@@ -0,0 +1,210 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Abbreviated display forms of block ids, operation ids and addresses.
+//!
+//! The short form of an id is the beginning of its full string representation
+//! followed by a checksum of the full representation, for example `B1q4CBcuYo~7hVT`.
+//! The beginning keeps the type prefix so that the kind of id stays recognizable,
+//! and the checksum allows telling apart ids sharing the same beginning.
+//!
+//! A short form cannot be decoded on its own: it is resolved against a set of known ids,
+//! and the resolution fails if it matches none or several of them.
+
+use crate::error::ModelsError;
+use massa_hash::Hash;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Number of characters of the full representation kept in a short form
+pub const SHORT_ID_PREFIX_LENGTH: usize = 10;
+
+/// Minimal number of characters of the full representation accepted when parsing a short form
+pub const SHORT_ID_MIN_PREFIX_LENGTH: usize = 4;
+
+/// Number of characters of the checksum of a short form
+pub const SHORT_ID_CHECKSUM_LENGTH: usize = 4;
+
+/// Separator between the beginning of the full representation and the checksum
+pub const SHORT_ID_SEPARATOR: char = '~';
+
+/// Checksum of the full representation of an id
+fn short_id_checksum(full: &str) -> String {
+    bs58::encode(Hash::compute_from(full.as_bytes()).to_bytes())
+        .into_string()
+        .chars()
+        .take(SHORT_ID_CHECKSUM_LENGTH)
+        .collect()
+}
+
+/// Abbreviated form of an id
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShortId {
+    /// beginning of the full representation
+    prefix: String,
+    /// checksum of the full representation
+    checksum: String,
+}
+
+impl ShortId {
+    /// Computes the short form of an id from its full representation
+    pub fn new(id: &impl Display) -> Self {
+        let full = id.to_string();
+        ShortId {
+            prefix: full.chars().take(SHORT_ID_PREFIX_LENGTH).collect(),
+            checksum: short_id_checksum(&full),
+        }
+    }
+
+    /// Returns true if the id is abbreviated by this short form
+    pub fn matches(&self, id: &impl Display) -> bool {
+        let full = id.to_string();
+        full.starts_with(&self.prefix) && short_id_checksum(&full) == self.checksum
+    }
+
+    /// Finds the only id abbreviated by this short form among `candidates`.
+    ///
+    /// # Returns
+    /// An error if no candidate or several distinct candidates match
+    pub fn resolve<'a, T, I>(&self, candidates: I) -> Result<T, ModelsError>
+    where
+        T: Display + PartialEq + Clone + 'a,
+        I: IntoIterator<Item = &'a T>,
+    {
+        let mut found: Option<&T> = None;
+        for candidate in candidates {
+            if !self.matches(candidate) {
+                continue;
+            }
+            match found {
+                Some(previous) if previous != candidate => {
+                    return Err(ModelsError::AmbiguousShortId(self.to_string()));
+                }
+                _ => found = Some(candidate),
+            }
+        }
+        found
+            .cloned()
+            .ok_or_else(|| ModelsError::UnknownShortId(self.to_string()))
+    }
+}
+
+impl Display for ShortId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.prefix, SHORT_ID_SEPARATOR, self.checksum)
+    }
+}
+
+impl FromStr for ShortId {
+    type Err = ModelsError;
+
+    /// ## Example
+    /// ```rust
+    /// # use massa_models::short_id::ShortId;
+    /// # use std::str::FromStr;
+    /// let short_id = ShortId::from_str("B1q4CBcuYo~7hVT").unwrap();
+    /// assert_eq!(short_id.to_string(), "B1q4CBcuYo~7hVT");
+    /// assert!(ShortId::from_str("B1q~7hVT").is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prefix, checksum) = s.split_once(SHORT_ID_SEPARATOR).ok_or_else(|| {
+            ModelsError::ShortIdParseError(format!("missing '{}' separator", SHORT_ID_SEPARATOR))
+        })?;
+        if prefix.chars().count() < SHORT_ID_MIN_PREFIX_LENGTH {
+            return Err(ModelsError::ShortIdParseError(format!(
+                "at least {} characters of the id are required",
+                SHORT_ID_MIN_PREFIX_LENGTH
+            )));
+        }
+        if checksum.chars().count() != SHORT_ID_CHECKSUM_LENGTH {
+            return Err(ModelsError::ShortIdParseError(format!(
+                "the checksum must be {} characters long",
+                SHORT_ID_CHECKSUM_LENGTH
+            )));
+        }
+        if !s
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == SHORT_ID_SEPARATOR)
+        {
+            return Err(ModelsError::ShortIdParseError(
+                "invalid character".to_string(),
+            ));
+        }
+        Ok(ShortId {
+            prefix: prefix.to_string(),
+            checksum: checksum.to_string(),
+        })
+    }
+}
+
+/// Ids that can be displayed in an abbreviated form
+pub trait ToShortId: Display {
+    /// Short form of the id, for logs and user interfaces
+    fn to_short_id(&self) -> ShortId {
+        ShortId::new(self)
+    }
+}
+
+impl ToShortId for crate::block_id::BlockId {}
+impl ToShortId for crate::operation::OperationId {}
+impl ToShortId for crate::address::Address {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::Address;
+    use crate::block_id::BlockId;
+    use crate::secure_share::Id;
+
+    #[test]
+    fn test_short_id_roundtrip_and_resolution() {
+        let block_ids: Vec<BlockId> = (0u8..20)
+            .map(|i| BlockId::new(Hash::compute_from(&[i])))
+            .collect();
+        for block_id in &block_ids {
+            let short_id = block_id.to_short_id();
+            let parsed = ShortId::from_str(&short_id.to_string()).unwrap();
+            assert_eq!(parsed, short_id);
+            assert!(block_id.to_string().starts_with(&parsed.prefix));
+            assert_eq!(parsed.resolve(&block_ids).unwrap(), *block_id);
+        }
+
+        // a short form with a wrong checksum matches nothing
+        let mut wrong = block_ids[0].to_short_id();
+        wrong.checksum = short_id_checksum("something else");
+        assert!(matches!(
+            wrong.resolve(&block_ids),
+            Err(ModelsError::UnknownShortId(_))
+        ));
+
+        let address =
+            Address::from_str("AU12dG5xP1RDEB5ocdHkymNVvvSJmUL9BgHwCksDowqmGWxfpm93x").unwrap();
+        assert!(address.to_short_id().to_string().starts_with("AU12dG5xP1~"));
+    }
+
+    #[test]
+    fn test_short_id_rejects_ambiguity() {
+        // two distinct ids with the same full representation prefix and checksum can only be crafted,
+        // so ambiguity is checked with values displaying identically
+        #[derive(Clone, PartialEq)]
+        struct Fake(u8);
+        impl Display for Fake {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "B1same-representation")
+            }
+        }
+        let short_id = ShortId::new(&Fake(0));
+        assert!(matches!(
+            short_id.resolve(&[Fake(0), Fake(1)]),
+            Err(ModelsError::AmbiguousShortId(_))
+        ));
+        assert!(short_id.resolve(&[Fake(0), Fake(0)]).is_ok());
+    }
+
+    #[test]
+    fn test_short_id_parse_errors() {
+        assert!(ShortId::from_str("B1q4CBcuYo").is_err());
+        assert!(ShortId::from_str("B1~7hVT").is_err());
+        assert!(ShortId::from_str("B1q4CBcuYo~7h").is_err());
+        assert!(ShortId::from_str("B1q4C-cuYo~7hVT").is_err());
+    }
+}
@@ -14,6 +14,7 @@ use std::{cmp::Ordering, convert::TryInto};
 
 /// a point in time where a block is expected
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Slot {
     /// period
     pub period: u64,
@@ -325,10 +326,163 @@ impl Slot {
             .ok_or(ModelsError::PeriodOverflowError)?
             .saturating_sub(s.thread as u64))
     }
+
+    /// Returns an iterator over the slots from self to `end`, both included
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use massa_models::slot::Slot;
+    /// let mut slots = Slot::new(10, 1).slots_until(Slot::new(11, 0), 2);
+    /// assert_eq!(slots.next(), Some(Slot::new(10, 1)));
+    /// assert_eq!(slots.next(), Some(Slot::new(11, 0)));
+    /// assert_eq!(slots.next(), None);
+    /// ```
+    pub fn slots_until(&self, end: Slot, thread_count: u8) -> SlotRange {
+        SlotRange::inclusive(*self, end, thread_count)
+    }
+}
+
+/// Iterator over consecutive slots between two bounds, in slot order.
+///
+/// Iteration stops instead of panicking if the end of the slot space is reached.
+///
+/// ## Example
+/// ```rust
+/// # use massa_models::slot::{Slot, SlotRange};
+/// let slots: Vec<Slot> = SlotRange::inclusive(Slot::new(1, 1), Slot::new(2, 0), 2).collect();
+/// assert_eq!(slots, vec![Slot::new(1, 1), Slot::new(2, 0)]);
+/// let slots: Vec<Slot> = SlotRange::exclusive(Slot::new(1, 1), Slot::new(2, 0), 2).collect();
+/// assert_eq!(slots, vec![Slot::new(1, 1)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotRange {
+    /// next slot to yield, `None` once the range is exhausted
+    next: Option<Slot>,
+    /// last slot to yield (inclusive)
+    last: Slot,
+    /// thread count
+    thread_count: u8,
+}
+
+impl SlotRange {
+    /// range of slots from `start` to `end`, both included
+    pub fn inclusive(start: Slot, end: Slot, thread_count: u8) -> Self {
+        SlotRange {
+            next: Some(start),
+            last: end,
+            thread_count,
+        }
+    }
+
+    /// range of slots from `start` (included) to `end` (excluded)
+    pub fn exclusive(start: Slot, end: Slot, thread_count: u8) -> Self {
+        match end.get_prev_slot(thread_count) {
+            Ok(last) => SlotRange::inclusive(start, last, thread_count),
+            // nothing is strictly before the minimal slot
+            Err(_) => SlotRange {
+                next: None,
+                last: end,
+                thread_count,
+            },
+        }
+    }
+
+    /// range of all the slots from `start` onwards, until the end of the slot space
+    pub fn starting_at(start: Slot, thread_count: u8) -> Self {
+        SlotRange::inclusive(start, Slot::max(thread_count), thread_count)
+    }
+
+    /// range of all the slots of a given cycle
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use massa_models::slot::{Slot, SlotRange};
+    /// let mut cycle = SlotRange::cycle(1, 4, 2).unwrap();
+    /// assert_eq!(cycle.clone().count(), 8);
+    /// assert_eq!(cycle.next(), Some(Slot::new(4, 0)));
+    /// assert_eq!(cycle.last(), Some(Slot::new(7, 1)));
+    /// ```
+    pub fn cycle(
+        cycle: u64,
+        periods_per_cycle: u64,
+        thread_count: u8,
+    ) -> Result<Self, ModelsError> {
+        Ok(SlotRange::inclusive(
+            Slot::new_first_of_cycle(cycle, periods_per_cycle)?,
+            Slot::new_last_of_cycle(cycle, periods_per_cycle, thread_count)?,
+            thread_count,
+        ))
+    }
+
+    /// Restricts the range to the slots of a single thread,
+    /// stepping one period at a time instead of one slot at a time.
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use massa_models::slot::{Slot, SlotRange};
+    /// let slots: Vec<Slot> = SlotRange::inclusive(Slot::new(1, 1), Slot::new(3, 0), 4)
+    ///     .in_thread(0)
+    ///     .collect();
+    /// assert_eq!(slots, vec![Slot::new(2, 0), Slot::new(3, 0)]);
+    /// ```
+    pub fn in_thread(self, thread: u8) -> impl Iterator<Item = Slot> {
+        let last = self.last;
+        let periods = match self.next {
+            Some(next) if thread < self.thread_count && next <= last => {
+                let first_period = if thread < next.thread {
+                    next.period.checked_add(1)
+                } else {
+                    Some(next.period)
+                };
+                first_period.map(|first| first..=last.period)
+            }
+            _ => None,
+        };
+        periods
+            .into_iter()
+            .flatten()
+            .map(move |period| Slot::new(period, thread))
+            .take_while(move |slot| *slot <= last)
+    }
 }
 
+impl Iterator for SlotRange {
+    type Item = Slot;
+
+    fn next(&mut self) -> Option<Slot> {
+        let current = self.next.take()?;
+        if current > self.last {
+            return None;
+        }
+        if current < self.last {
+            // an overflow can only happen past `Slot::max`, which is never below `self.last`
+            self.next = current.get_next_slot(self.thread_count).ok();
+        }
+        Some(current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = match self.next {
+            Some(next) if next <= self.last => self
+                .last
+                .slots_since(&next, self.thread_count)
+                .ok()
+                .and_then(|count| count.checked_add(1))
+                .and_then(|count| usize::try_from(count).ok()),
+            _ => Some(0),
+        };
+        match remaining {
+            Some(count) => (count, Some(count)),
+            None => (usize::MAX, None),
+        }
+    }
+}
+
+impl std::iter::FusedIterator for SlotRange {}
+
 /// When an address is drawn to create an endorsement it is selected for a specific index
 #[derive(Debug, Clone, Deserialize, Serialize, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct IndexedSlot {
     /// slot
     pub slot: Slot,
@@ -341,3 +495,62 @@ impl std::fmt::Display for IndexedSlot {
         writeln!(f, "Slot: {}, Index: {}", self.slot, self.index)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_range_bounds() {
+        // empty ranges
+        assert_eq!(SlotRange::exclusive(Slot::min(), Slot::min(), 4).count(), 0);
+        assert_eq!(
+            SlotRange::inclusive(Slot::new(3, 0), Slot::new(2, 3), 4).count(),
+            0
+        );
+        assert_eq!(
+            SlotRange::exclusive(Slot::new(2, 1), Slot::new(2, 1), 4).size_hint(),
+            (0, Some(0))
+        );
+
+        // size hint matches the number of yielded slots
+        let range = SlotRange::inclusive(Slot::new(2, 3), Slot::new(5, 1), 4);
+        assert_eq!(range.size_hint(), (11, Some(11)));
+        assert_eq!(range.clone().count(), 11);
+        assert!(range.clone().zip(range.skip(1)).all(|(a, b)| a < b));
+
+        // iteration stops at the end of the slot space instead of overflowing
+        let mut range = SlotRange::starting_at(Slot::new(u64::MAX, 2), 4);
+        assert_eq!(range.next(), Some(Slot::new(u64::MAX, 2)));
+        assert_eq!(range.next(), Some(Slot::max(4)));
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next(), None);
+    }
+
+    #[test]
+    fn test_slot_range_cycle_and_thread() {
+        let periods_per_cycle = 8;
+        let thread_count = 3;
+        let cycle = SlotRange::cycle(2, periods_per_cycle, thread_count).unwrap();
+        let slots: Vec<Slot> = cycle.clone().collect();
+        assert_eq!(slots.len(), 24);
+        assert!(slots[0].is_first_of_cycle(periods_per_cycle));
+        assert!(slots[23].is_last_of_cycle(periods_per_cycle, thread_count));
+        assert!(slots
+            .iter()
+            .all(|slot| slot.get_cycle(periods_per_cycle) == 2));
+
+        let thread_slots: Vec<Slot> = cycle.in_thread(1).collect();
+        assert_eq!(thread_slots.len(), 8);
+        assert!(thread_slots.iter().all(|slot| slot.thread == 1));
+        assert_eq!(thread_slots[0], Slot::new(16, 1));
+
+        assert_eq!(
+            SlotRange::inclusive(Slot::new(0, 0), Slot::new(4, 0), 2)
+                .in_thread(2)
+                .count(),
+            0
+        );
+        assert!(SlotRange::cycle(u64::MAX, periods_per_cycle, thread_count).is_err());
+    }
+}
@@ -3,6 +3,7 @@
 use crate::slot::Slot;
 use massa_time::MassaTime;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt::Formatter;
 
 /// execution statistics
@@ -20,6 +21,14 @@ pub struct ExecutionStats {
     pub active_cursor: Slot,
     /// final execution cursor slot
     pub final_cursor: Slot,
+    /// number of slots candidate execution lags behind real time
+    pub candidate_execution_lag: u64,
+    /// number of SCE-final slots waiting for execution
+    pub final_execution_backlog: u64,
+    /// whether speculative execution is suspended because it lags too much (candidate results are stale)
+    pub speculative_execution_stale: bool,
+    /// whether candidate slots are executed (candidate results are unavailable otherwise)
+    pub candidate_execution_enabled: bool,
 }
 
 impl std::fmt::Display for ExecutionStats {
@@ -47,6 +56,27 @@ impl std::fmt::Display for ExecutionStats {
         )?;
         writeln!(f, "\tActive cursor: {}", self.active_cursor)?;
         writeln!(f, "\tFinal cursor: {}", self.final_cursor)?;
+        writeln!(
+            f,
+            "\tCandidate execution lag: {} slots",
+            self.candidate_execution_lag
+        )?;
+        writeln!(
+            f,
+            "\tFinal execution backlog: {} slots",
+            self.final_execution_backlog
+        )?;
+        if !self.candidate_execution_enabled {
+            writeln!(
+                f,
+                "\tCandidate execution is disabled: candidate results are unavailable"
+            )?;
+        } else if self.speculative_execution_stale {
+            writeln!(
+                f,
+                "\tSpeculative execution is lagging: candidate results are stale"
+            )?;
+        }
         Ok(())
     }
 }
@@ -64,6 +94,9 @@ pub struct NetworkStats {
     pub banned_peer_count: u64,
     /// active node count
     pub active_node_count: u64,
+    /// connected peers count per peer category (`default` for the peers without category)
+    #[serde(default)]
+    pub peers_per_category: BTreeMap<String, u64>,
 }
 
 impl std::fmt::Display for NetworkStats {
@@ -74,6 +107,9 @@ impl std::fmt::Display for NetworkStats {
         writeln!(f, "\tKnown peers: {}", self.known_peer_count)?;
         writeln!(f, "\tBanned peers: {}", self.banned_peer_count)?;
         writeln!(f, "\tActive nodes: {}", self.active_node_count)?;
+        for (category, count) in &self.peers_per_category {
+            writeln!(f, "\tPeers of category {}: {}", category, count)?;
+        }
         Ok(())
     }
 }
@@ -91,6 +127,9 @@ pub struct ConsensusStats {
     pub stale_block_count: u64,
     ///  number of actives cliques
     pub clique_count: u64,
+    /// number of blocks waiting to be processed (incoming, waiting for their slot or their dependencies)
+    #[serde(default)]
+    pub pending_block_count: u64,
 }
 
 impl std::fmt::Display for ConsensusStats {
@@ -109,6 +148,7 @@ impl std::fmt::Display for ConsensusStats {
         writeln!(f, "\tFinal block count: {}", self.final_block_count)?;
         writeln!(f, "\tStale block count: {}", self.stale_block_count)?;
         writeln!(f, "\tClique count: {}", self.clique_count)?;
+        writeln!(f, "\tPending block count: {}", self.pending_block_count)?;
         Ok(())
     }
 }
@@ -0,0 +1,3 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+mod serialization_proptests;
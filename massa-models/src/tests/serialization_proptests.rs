@@ -0,0 +1,261 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Property-based tests of the serialization of the core models:
+//! binary serializer/deserializer round-trips, `FromStr`/`Display` consistency
+//! and enforcement of the deserialization bounds on random instances.
+
+use std::ops::Bound::{Excluded, Included};
+use std::str::FromStr;
+
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use proptest::collection::{btree_map, vec};
+use proptest::prelude::*;
+
+use crate::{
+    address::{Address, AddressDeserializer, AddressSerializer, ADDRESS_PREFIXED_BYTES_SIZE},
+    amount::{Amount, AmountDeserializer, AmountSerializer},
+    block_id::{BlockId, BlockIdDeserializer, BlockIdSerializer},
+    config::{
+        MAX_DATASTORE_VALUE_LENGTH, MAX_FUNCTION_NAME_LENGTH, MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+        MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        MAX_PARAMETERS_SIZE, THREAD_COUNT,
+    },
+    endorsement::EndorsementId,
+    operation::{
+        OperationId, OperationIdDeserializer, OperationIdSerializer, OperationType,
+        OperationTypeDeserializer, OperationTypeSerializer,
+    },
+    secure_share::Id,
+    slot::{Slot, SlotDeserializer, SlotSerializer},
+};
+
+fn hash_strategy() -> impl Strategy<Value = Hash> {
+    any::<[u8; HASH_SIZE_BYTES]>().prop_map(|bytes| Hash::from_bytes(&bytes))
+}
+
+fn slot_strategy() -> impl Strategy<Value = Slot> {
+    (any::<u64>(), 0..THREAD_COUNT).prop_map(|(period, thread)| Slot::new(period, thread))
+}
+
+fn amount_strategy() -> impl Strategy<Value = Amount> {
+    any::<u64>().prop_map(Amount::from_raw)
+}
+
+/// Type and version prefixes of the supported addresses
+const ADDRESS_PREFIXES: [(u8, u8); 3] = [(0, 0), (1, 0), (1, 1)];
+
+fn address_strategy() -> impl Strategy<Value = Address> {
+    (
+        prop::sample::select(ADDRESS_PREFIXES.to_vec()),
+        hash_strategy(),
+    )
+        .prop_map(|((address_type, version), hash)| {
+            let mut bytes = [0u8; ADDRESS_PREFIXED_BYTES_SIZE];
+            bytes[0] = address_type;
+            bytes[1] = version;
+            bytes[2..].copy_from_slice(hash.to_bytes());
+            Address::from_prefixed_bytes(&bytes).unwrap()
+        })
+}
+
+fn operation_type_strategy() -> impl Strategy<Value = OperationType> {
+    prop_oneof![
+        (address_strategy(), amount_strategy()).prop_map(|(recipient_address, amount)| {
+            OperationType::Transaction {
+                recipient_address,
+                amount,
+            }
+        }),
+        any::<u64>().prop_map(|roll_count| OperationType::RollBuy { roll_count }),
+        any::<u64>().prop_map(|roll_count| OperationType::RollSell { roll_count }),
+        (
+            vec(any::<u8>(), 0..64),
+            any::<u64>(),
+            amount_strategy(),
+            btree_map(vec(any::<u8>(), 0..8), vec(any::<u8>(), 0..16), 0..4),
+        )
+            .prop_map(
+                |(data, max_gas, max_coins, datastore)| OperationType::ExecuteSC {
+                    data,
+                    max_gas,
+                    max_coins,
+                    datastore,
+                }
+            ),
+        (
+            address_strategy(),
+            "[a-zA-Z_]{0,16}",
+            vec(any::<u8>(), 0..64),
+            any::<u64>(),
+            amount_strategy(),
+        )
+            .prop_map(|(target_addr, target_func, param, max_gas, coins)| {
+                OperationType::CallSC {
+                    target_addr,
+                    target_func,
+                    param,
+                    max_gas,
+                    coins,
+                }
+            }),
+    ]
+}
+
+fn slot_deserializer() -> SlotDeserializer {
+    SlotDeserializer::new(
+        (Included(0), Included(u64::MAX)),
+        (Included(0), Excluded(THREAD_COUNT)),
+    )
+}
+
+fn operation_type_deserializer(max_parameters_size: u32) -> OperationTypeDeserializer {
+    OperationTypeDeserializer::new(
+        MAX_DATASTORE_VALUE_LENGTH,
+        MAX_FUNCTION_NAME_LENGTH,
+        max_parameters_size,
+        MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+        MAX_OPERATION_DATASTORE_KEY_LENGTH,
+        MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+    )
+}
+
+fn serialize<T, S: Serializer<T>>(serializer: &S, value: &T) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    serializer.serialize(value, &mut buffer).unwrap();
+    buffer
+}
+
+/// Deserializes a whole buffer, failing if bytes are left
+fn deserialize<T, D: Deserializer<T>>(deserializer: &D, buffer: &[u8]) -> Option<T> {
+    match deserializer.deserialize::<DeserializeError>(buffer) {
+        Ok((rest, value)) if rest.is_empty() => Some(value),
+        _ => None,
+    }
+}
+
+proptest! {
+    #[test]
+    fn slot_roundtrip(slot in slot_strategy()) {
+        let buffer = serialize(&SlotSerializer::new(), &slot);
+        prop_assert_eq!(deserialize(&slot_deserializer(), &buffer), Some(slot));
+        prop_assert_eq!(Slot::from_bytes_key(&slot.to_bytes_key()), slot);
+        let parsed = Slot::from_str(&format!("{},{}", slot.period, slot.thread)).unwrap();
+        prop_assert_eq!(parsed, slot);
+        let json = serde_json::to_string(&slot).unwrap();
+        prop_assert_eq!(serde_json::from_str::<Slot>(&json).unwrap(), slot);
+    }
+
+    #[test]
+    fn slot_thread_out_of_bounds(period in any::<u64>(), thread in THREAD_COUNT..=u8::MAX) {
+        let buffer = serialize(&SlotSerializer::new(), &Slot::new(period, thread));
+        prop_assert!(deserialize(&slot_deserializer(), &buffer).is_none());
+    }
+
+    #[test]
+    fn amount_roundtrip(amount in amount_strategy()) {
+        let deserializer = AmountDeserializer::new(Included(Amount::MIN), Included(Amount::MAX));
+        let buffer = serialize(&AmountSerializer::new(), &amount);
+        prop_assert_eq!(deserialize(&deserializer, &buffer), Some(amount));
+        prop_assert_eq!(Amount::from_str(&amount.to_string()).unwrap(), amount);
+        let json = serde_json::to_string(&amount).unwrap();
+        prop_assert_eq!(serde_json::from_str::<Amount>(&json).unwrap(), amount);
+    }
+
+    #[test]
+    fn amount_out_of_bounds(max in 0..u64::MAX - 1, excess in 1..u64::MAX) {
+        let amount = Amount::from_raw(max.saturating_add(excess));
+        let deserializer =
+            AmountDeserializer::new(Included(Amount::MIN), Included(Amount::from_raw(max)));
+        let buffer = serialize(&AmountSerializer::new(), &amount);
+        prop_assert!(deserialize(&deserializer, &buffer).is_none());
+    }
+
+    #[test]
+    fn address_roundtrip(address in address_strategy()) {
+        let buffer = serialize(&AddressSerializer::new(), &address);
+        prop_assert_eq!(deserialize(&AddressDeserializer::new(), &buffer), Some(address));
+        let prefixed_bytes = address.to_prefixed_bytes();
+        prop_assert_eq!(Address::from_prefixed_bytes(&prefixed_bytes).unwrap(), address);
+        prop_assert_eq!(Address::from_str(&address.to_string()).unwrap(), address);
+        let json = serde_json::to_string(&address).unwrap();
+        prop_assert_eq!(serde_json::from_str::<Address>(&json).unwrap(), address);
+    }
+
+    #[test]
+    fn address_bad_prefixes(
+        address_type in 0u8..0x80,
+        version in 0u8..0x80,
+        hash in hash_strategy(),
+    ) {
+        prop_assume!(!ADDRESS_PREFIXES.contains(&(address_type, version)));
+        let mut bytes = [0u8; ADDRESS_PREFIXED_BYTES_SIZE];
+        bytes[0] = address_type;
+        bytes[1] = version;
+        bytes[2..].copy_from_slice(hash.to_bytes());
+        prop_assert!(Address::from_prefixed_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn address_truncated(address in address_strategy(), len in 0..ADDRESS_PREFIXED_BYTES_SIZE) {
+        prop_assert!(Address::from_prefixed_bytes(&address.to_prefixed_bytes()[..len]).is_err());
+    }
+
+    #[test]
+    fn ids_roundtrip(hash in hash_strategy()) {
+        let block_id = BlockId::new(hash);
+        let buffer = serialize(&BlockIdSerializer::new(), &block_id);
+        prop_assert_eq!(deserialize(&BlockIdDeserializer::new(), &buffer), Some(block_id));
+        prop_assert_eq!(BlockId::from_str(&block_id.to_string()).unwrap(), block_id);
+
+        let operation_id = OperationId::new(hash);
+        let buffer = serialize(&OperationIdSerializer::new(), &operation_id);
+        prop_assert_eq!(
+            deserialize(&OperationIdDeserializer::new(), &buffer),
+            Some(operation_id)
+        );
+        prop_assert_eq!(OperationId::from_str(&operation_id.to_string()).unwrap(), operation_id);
+
+        let endorsement_id = EndorsementId::new(hash);
+        prop_assert_eq!(
+            EndorsementId::from_str(&endorsement_id.to_string()).unwrap(),
+            endorsement_id
+        );
+    }
+
+    #[test]
+    fn ids_bad_version(hash in hash_strategy(), version in 1u8..0x80) {
+        let mut buffer = serialize(&BlockIdSerializer::new(), &BlockId::new(hash));
+        buffer[0] = version;
+        prop_assert!(deserialize(&BlockIdDeserializer::new(), &buffer).is_none());
+
+        let mut buffer = serialize(&OperationIdSerializer::new(), &OperationId::new(hash));
+        buffer[0] = version;
+        prop_assert!(deserialize(&OperationIdDeserializer::new(), &buffer).is_none());
+    }
+
+    #[test]
+    fn operation_type_roundtrip(op in operation_type_strategy()) {
+        let buffer = serialize(&OperationTypeSerializer::new(), &op);
+        prop_assert_eq!(
+            deserialize(&operation_type_deserializer(MAX_PARAMETERS_SIZE), &buffer),
+            Some(op)
+        );
+    }
+
+    #[test]
+    fn operation_type_parameter_too_long(
+        target_addr in address_strategy(),
+        param in vec(any::<u8>(), 9..64),
+    ) {
+        let op = OperationType::CallSC {
+            target_addr,
+            target_func: "f".to_string(),
+            param,
+            max_gas: 0,
+            coins: Amount::MIN,
+        };
+        let buffer = serialize(&OperationTypeSerializer::new(), &op);
+        prop_assert!(deserialize(&operation_type_deserializer(8), &buffer).is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use massa_hash::Hash;
 use massa_sc_runtime::GasCosts;
 use std::path::PathBuf;
 
@@ -14,6 +15,9 @@ pub struct ModuleCacheConfig {
     pub hd_cache_size: usize,
     /// Amount of entries removed when `hd_cache_size` is reached
     pub snip_amount: usize,
+    /// Fingerprint of the compilation settings (node version, gas costs) the cached modules depend on.
+    /// The HD cache persists across restarts and is cleared when this fingerprint changes.
+    pub hd_cache_fingerprint: Hash,
     /// Maximum length of a module
     pub max_module_length: u64,
 }
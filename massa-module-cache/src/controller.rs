@@ -5,8 +5,11 @@ use schnellru::{ByLength, LruMap};
 use tracing::debug;
 
 use crate::{
-    config::ModuleCacheConfig, error::CacheError, hd_cache::HDCache, lru_cache::LRUCache,
-    types::ModuleInfo,
+    config::ModuleCacheConfig,
+    error::CacheError,
+    hd_cache::HDCache,
+    lru_cache::LRUCache,
+    types::{ModuleCacheStats, ModuleInfo},
 };
 
 /// `LruMap` specialization for `PreHashed` keys
@@ -23,6 +26,8 @@ pub struct ModuleCache {
     /// Disk stored cache.
     /// See the `HDCache` documentation for more information.
     hd_cache: HDCache,
+    /// Lookup statistics since the last call to `take_stats`
+    stats: ModuleCacheStats,
 }
 
 impl ModuleCache {
@@ -34,7 +39,9 @@ impl ModuleCache {
                 cfg.hd_cache_path.clone(),
                 cfg.hd_cache_size,
                 cfg.snip_amount,
+                cfg.hd_cache_fingerprint,
             ),
+            stats: Default::default(),
             cfg,
         }
     }
@@ -85,6 +92,11 @@ impl ModuleCache {
         self.hd_cache.set_invalid(hash, err_msg);
     }
 
+    /// Get the lookup statistics gathered since the previous call, and reset them
+    pub fn take_stats(&mut self) -> ModuleCacheStats {
+        std::mem::take(&mut self.stats)
+    }
+
     /// Load a cached module for execution
     ///
     /// Returns the module information, it can be:
@@ -109,13 +121,16 @@ impl ModuleCache {
         let hash = Hash::compute_from(bytecode);
         if let Some(lru_module_info) = self.lru_cache.get(hash) {
             debug!("load_module: {} present in lru", hash);
+            self.stats.lru_hits = self.stats.lru_hits.saturating_add(1);
             lru_module_info
         } else if let Some(hd_module_info) = self.hd_cache.get(hash, self.cfg.gas_costs.clone()) {
             debug!("load_module: {} missing in lru but present in hd", hash);
+            self.stats.hd_hits = self.stats.hd_hits.saturating_add(1);
             self.lru_cache.insert(hash, hd_module_info.clone());
             hd_module_info
         } else {
             debug!("load_module: {} missing", hash);
+            self.stats.misses = self.stats.misses.saturating_add(1);
             let module_info = self.compile_cached(bytecode, hash);
             self.hd_cache.insert(hash, module_info.clone());
             self.lru_cache.insert(hash, module_info.clone());
@@ -5,11 +5,12 @@ use massa_hash::Hash;
 use massa_sc_runtime::{GasCosts, RuntimeModule};
 use massa_serialization::{DeserializeError, Deserializer, Serializer};
 use rand::RngCore;
-use rocksdb::{Direction, IteratorMode, WriteBatch, DB};
+use rocksdb::{Direction, IteratorMode, Options, WriteBatch, DB};
 use std::path::PathBuf;
-use tracing::debug;
+use tracing::{debug, info};
 
 const OPEN_ERROR: &str = "critical: rocksdb open operation failed";
+const CF_ERROR: &str = "critical: rocksdb column family operation failed";
 const CRUD_ERROR: &str = "critical: rocksdb crud operation failed";
 const DATA_SER_ERROR: &str = "critical: metadata serialization failed";
 const DATA_DESER_ERROR: &str = "critical: metadata deserialization failed";
@@ -17,6 +18,8 @@ const MOD_SER_ERROR: &str = "critical: module serialization failed";
 const MOD_DESER_ERROR: &str = "critical: module deserialization failed";
 const MODULE_IDENT: u8 = 0u8;
 const DATA_IDENT: u8 = 1u8;
+const CACHE_INFO_CF: &str = "cache_info";
+const FINGERPRINT_KEY: &[u8] = b"fingerprint";
 
 /// Module key formatting macro
 #[macro_export]
@@ -54,13 +57,49 @@ pub(crate) struct HDCache {
 impl HDCache {
     /// Create a new HDCache
     ///
+    /// The cache persists across restarts: the entries stored at `path` are kept
+    /// as long as they were compiled with the same `fingerprint`, and cleared otherwise.
+    ///
     /// # Arguments
     /// * path: where to store the db
     /// * max_entry_count: maximum number of entries we want to keep in the db
     /// * amount_to_remove: how many entries are removed when `entry_count` reaches `max_entry_count`
-    pub fn new(path: PathBuf, max_entry_count: usize, snip_amount: usize) -> Self {
-        let db = DB::open_default(path).expect(OPEN_ERROR);
-        let entry_count = db.iterator(IteratorMode::Start).count();
+    /// * fingerprint: fingerprint of the compilation settings the cached modules depend on
+    pub fn new(
+        path: PathBuf,
+        max_entry_count: usize,
+        snip_amount: usize,
+        fingerprint: Hash,
+    ) -> Self {
+        let mut db_opts = Options::default();
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+
+        let mut db = DB::open_cf(&db_opts, &path, [CACHE_INFO_CF]).expect(OPEN_ERROR);
+        let stored_fingerprint = db
+            .get_cf(
+                db.cf_handle(CACHE_INFO_CF).expect(CF_ERROR),
+                FINGERPRINT_KEY,
+            )
+            .expect(CRUD_ERROR);
+        if stored_fingerprint.as_deref() != Some(&fingerprint.to_bytes()[..]) {
+            if db.iterator(IteratorMode::Start).next().is_some() {
+                info!("compilation settings changed: clearing the hd module cache");
+            }
+            drop(db);
+            DB::destroy(&db_opts, &path).expect(CRUD_ERROR);
+            db = DB::open_cf(&db_opts, &path, [CACHE_INFO_CF]).expect(OPEN_ERROR);
+            db.put_cf(
+                db.cf_handle(CACHE_INFO_CF).expect(CF_ERROR),
+                FINGERPRINT_KEY,
+                fingerprint.to_bytes(),
+            )
+            .expect(CRUD_ERROR);
+        }
+
+        // each entry is made of a module key and a metadata key
+        let entry_count = db.iterator(IteratorMode::Start).count() / 2;
+        debug!("(HD open) entry_count is: {}", entry_count);
 
         Self {
             db,
@@ -239,7 +278,7 @@ mod tests {
 
     fn setup() -> HDCache {
         let tmp_path = TempDir::new().unwrap().path().to_path_buf();
-        HDCache::new(tmp_path, 1000, 10)
+        HDCache::new(tmp_path, 1000, 10, Hash::compute_from(b"fingerprint"))
     }
 
     #[test]
@@ -313,4 +352,32 @@ mod tests {
             assert!(cached_module.is_none());
         }
     }
+
+    #[test]
+    #[serial]
+    fn test_persistence_across_restarts() {
+        let tmp_dir = TempDir::new().unwrap();
+        let fingerprint = Hash::compute_from(b"fingerprint");
+        let hash = Hash::compute_from(b"test_hash");
+        let gas_costs = GasCosts::default();
+
+        let mut cache = HDCache::new(tmp_dir.path().to_path_buf(), 1000, 10, fingerprint);
+        cache.insert(hash, make_default_module_info());
+        drop(cache);
+
+        // reopening with the same fingerprint keeps the compiled modules
+        let cache = HDCache::new(tmp_dir.path().to_path_buf(), 1000, 10, fingerprint);
+        assert_eq!(cache.entry_count, 1);
+        assert!(matches!(
+            cache.get(hash, gas_costs.clone()),
+            Some(ModuleInfo::Module(_))
+        ));
+        drop(cache);
+
+        // reopening with another fingerprint clears them
+        let other_fingerprint = Hash::compute_from(b"other_fingerprint");
+        let cache = HDCache::new(tmp_dir.path().to_path_buf(), 1000, 10, other_fingerprint);
+        assert_eq!(cache.entry_count, 0);
+        assert!(cache.get(hash, gas_costs).is_none());
+    }
 }
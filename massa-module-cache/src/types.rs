@@ -10,6 +10,17 @@ use nom::{
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use std::ops::Bound::Included;
 
+/// Hit and miss counts of the module cache lookups
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModuleCacheStats {
+    /// number of modules found in the LRU cache
+    pub lru_hits: u64,
+    /// number of modules missing in the LRU cache but found in the HD cache
+    pub hd_hits: u64,
+    /// number of modules found in neither cache, and therefore compiled
+    pub misses: u64,
+}
+
 /// Main type
 #[derive(Clone)]
 pub enum ModuleInfo {
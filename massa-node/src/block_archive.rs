@@ -0,0 +1,91 @@
+//! Archive of the final blocks pruned from the block graph, for archival nodes.
+//!
+//! The blocks are handed to a thread appending them to the archive file,
+//! so that the consensus thread does not wait for the disk while it prunes the graph.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::Path,
+    sync::mpsc::{sync_channel, Receiver, SyncSender},
+    thread::JoinHandle,
+};
+
+use massa_consensus_exports::{ArchivedBlock, BlockArchive};
+use tracing::warn;
+
+/// Maximum number of blocks waiting to be written to the archive file.
+/// Once it is reached, the consensus waits for the writer rather than losing blocks.
+const ARCHIVE_QUEUE_SIZE: usize = 1024;
+
+/// Appends the archived blocks to a file, one JSON object per line
+pub struct FileBlockArchive {
+    /// queue of the blocks to write
+    sender: Option<SyncSender<ArchivedBlock>>,
+    /// thread writing the queued blocks
+    writer_thread: Option<JoinHandle<()>>,
+}
+
+impl FileBlockArchive {
+    /// Opens the archive file in append mode, creating it and its directory if needed,
+    /// and starts the thread writing to it
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (sender, receiver) = sync_channel(ARCHIVE_QUEUE_SIZE);
+        let writer_thread = std::thread::Builder::new()
+            .name("block-archive-writer".into())
+            .spawn(move || write_blocks(BufWriter::new(file), receiver))?;
+        Ok(FileBlockArchive {
+            sender: Some(sender),
+            writer_thread: Some(writer_thread),
+        })
+    }
+}
+
+/// Writes the queued blocks until the archive is dropped, flushing once the queue is empty
+fn write_blocks(mut writer: BufWriter<File>, receiver: Receiver<ArchivedBlock>) {
+    while let Ok(block) = receiver.recv() {
+        for block in std::iter::once(block).chain(receiver.try_iter()) {
+            let result = serde_json::to_writer(&mut writer, &block)
+                .map_err(std::io::Error::from)
+                .and_then(|_| writeln!(writer));
+            if let Err(err) = result {
+                warn!(
+                    "could not archive block {} of slot {}: {}",
+                    block.block_id, block.slot, err
+                );
+            }
+        }
+        if let Err(err) = writer.flush() {
+            warn!("could not flush the block archive: {}", err);
+        }
+    }
+}
+
+impl BlockArchive for FileBlockArchive {
+    fn archive_block(&self, block: ArchivedBlock) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        if let Err(err) = sender.send(block) {
+            let block = err.0;
+            warn!(
+                "could not archive block {} of slot {}: the archive writer is stopped",
+                block.block_id, block.slot
+            );
+        }
+    }
+}
+
+impl Drop for FileBlockArchive {
+    fn drop(&mut self) {
+        // the writer stops once it has written the queued blocks
+        self.sender.take();
+        if let Some(writer_thread) = self.writer_thread.take() {
+            let _ = writer_thread.join();
+        }
+    }
+}
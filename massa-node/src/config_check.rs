@@ -0,0 +1,603 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Validation of the node configuration files, run by the `check-config` subcommand and at startup.
+//!
+//! The default configuration file is the schema: every key set by the other files
+//! (network profile, override file, user configuration) must exist in it, with the same type,
+//! except for the optional keys listed in `OPTIONAL_KEYS`.
+//! Durations are checked for unit mistakes and integers for out of range values,
+//! then the merged configuration is deserialized into the node settings.
+
+use std::{collections::BTreeMap, fmt};
+
+use displaydoc::Display;
+use massa_models::config::{
+    massa_settings_file_names, read_massa_settings_file, try_build_massa_settings_with_profile,
+};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::settings::{get_network_profile_from_args, Settings};
+
+/// Optional keys, absent from the default configuration file
+const OPTIONAL_KEYS: [(&str, ValueKind); 24] = [
+    ("network.routable_ip", ValueKind::String),
+    ("protocol.routable_ip", ValueKind::String),
+    ("protocol.traffic_capture_path", ValueKind::String),
+    ("protocol.socks5_proxy.address", ValueKind::String),
+    ("protocol.socks5_proxy.username", ValueKind::String),
+    ("protocol.socks5_proxy.password", ValueKind::String),
+    ("grpc.public.initial_stream_window_size", ValueKind::Integer),
+    (
+        "grpc.public.initial_connection_window_size",
+        ValueKind::Integer,
+    ),
+    ("grpc.public.tcp_keepalive", ValueKind::Integer),
+    ("grpc.public.http2_keepalive_interval", ValueKind::Integer),
+    ("grpc.public.http2_keepalive_timeout", ValueKind::Integer),
+    ("grpc.public.http2_adaptive_window", ValueKind::Boolean),
+    (
+        "grpc.private.initial_stream_window_size",
+        ValueKind::Integer,
+    ),
+    (
+        "grpc.private.initial_connection_window_size",
+        ValueKind::Integer,
+    ),
+    ("grpc.private.tcp_keepalive", ValueKind::Integer),
+    ("grpc.private.http2_keepalive_interval", ValueKind::Integer),
+    ("grpc.private.http2_keepalive_timeout", ValueKind::Integer),
+    ("grpc.private.http2_adaptive_window", ValueKind::Boolean),
+    ("versioning.mip_list_path", ValueKind::String),
+    ("factory.production_blacklist", ValueKind::Array),
+    ("network_profile.name", ValueKind::String),
+    ("network_profile.sandbox", ValueKind::Boolean),
+    ("bootstrap.bind", ValueKind::String),
+    ("ledger.initial_deferred_credits_path", ValueKind::String),
+];
+
+/// Tables whose keys are chosen by the user
+const FREE_FORM_TABLES: [&str; 3] = [
+    "protocol.peers_categories",
+    "protocol.peers_categories_message_rate_limits",
+    "protocol.peers_categories_bandwidth_budgets",
+];
+
+/// Keys holding a duration in milliseconds
+const MILLISECOND_KEYS: [&str; 53] = [
+    "cursor_delay",
+    "stats_time_window_duration",
+    "connect_timeout",
+    "read_timeout",
+    "write_timeout",
+    "read_error_timeout",
+    "write_error_timeout",
+    "retry_delay",
+    "max_ping",
+    "max_clock_delta",
+    "cache_duration",
+    "per_ip_min_interval",
+    "bootstrap_timeout",
+    "initial_delay",
+    "operation_max_future_start_delay",
+    "operation_pool_refresh_interval",
+    "ping_interval",
+    "stats_timespan",
+    "block_db_prune_interval",
+    "finality_stall_timeout",
+    "tick_delay",
+    "ask_block_timeout",
+    "max_block_propagation_time",
+    "block_propagation_tick",
+    "max_send_wait",
+    "operation_batch_proc_period",
+    "operation_announcement_interval",
+    "max_operations_propagation_time",
+    "operation_inventory_digest_interval",
+    "topic_anti_entropy_interval",
+    "max_endorsements_propagation_time",
+    "max_future_slot_time",
+    "try_connection_timer",
+    "try_connection_timer_same_peer",
+    "unban_everyone_timer",
+    "timeout_connection",
+    "message_timeout",
+    "tester_timeout",
+    "test_oldest_peer_cooldown",
+    "operation_seen_cache_max_age",
+    "peer_store_flush_interval",
+    "peer_store_max_age",
+    "port_mapping_lease_duration",
+    "port_mapping_timeout",
+    "outbound_rotation_interval",
+    "peer_reputation_recovery_period",
+    "timeout",
+    "tcp_keepalive",
+    "http2_keepalive_interval",
+    "http2_keepalive_timeout",
+    "max_cpu_profile_duration",
+    "poll_interval",
+    "request_timeout",
+];
+
+/// Allowed ranges of the integer keys with bounds narrower than their type
+const RANGES: [(&str, i64, i64); 6] = [
+    ("logging.level", 0, 4),
+    ("versioning.mip_stats_warn_announced_version", 0, 100),
+    ("grpc.public.max_frame_size", 16_384, 16_777_215),
+    ("grpc.private.max_frame_size", 16_384, 16_777_215),
+    ("protocol.block_body_data_chunks", 1, 128),
+    ("protocol.block_body_parity_chunks", 1, 128),
+];
+
+/// Type of a configuration value
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// a boolean
+    Boolean,
+    /// an integer
+    Integer,
+    /// a number
+    Float,
+    /// a string
+    String,
+    /// an array
+    Array,
+    /// a table
+    Table,
+    /// an empty value
+    Null,
+}
+
+impl ValueKind {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Bool(_) => ValueKind::Boolean,
+            Value::Number(number) if number.is_f64() => ValueKind::Float,
+            Value::Number(_) => ValueKind::Integer,
+            Value::String(_) => ValueKind::String,
+            Value::Array(_) => ValueKind::Array,
+            Value::Object(_) => ValueKind::Table,
+            Value::Null => ValueKind::Null,
+        }
+    }
+
+    /// Whether a value of kind `found` is accepted where a value of this kind is expected
+    fn accepts(&self, found: ValueKind) -> bool {
+        *self == found || (*self == ValueKind::Float && found == ValueKind::Integer)
+    }
+}
+
+/// Configuration error
+#[non_exhaustive]
+#[derive(Display, Error, Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// unknown key `{key}`
+    UnknownKey {
+        /// key set in the file
+        key: String,
+        /// closest known key
+        closest: Option<String>,
+    },
+    /// `{key}` should be {expected}, found {found}
+    WrongType {
+        /// key set in the file
+        key: String,
+        /// type of the key in the schema
+        expected: ValueKind,
+        /// type of the value set in the file
+        found: ValueKind,
+    },
+    /// `{key}` is a duration in milliseconds, {value} looks like a number of seconds
+    LikelySeconds {
+        /// key set in the file
+        key: String,
+        /// value set in the file
+        value: i64,
+    },
+    /// `{key}` is a duration in milliseconds, found the string "{value}"
+    DurationString {
+        /// key set in the file
+        key: String,
+        /// value set in the file
+        value: String,
+        /// the duration in milliseconds, if the string could be parsed
+        millis: Option<u64>,
+    },
+    /// `{key}` cannot be negative, found {value}
+    Negative {
+        /// key set in the file
+        key: String,
+        /// value set in the file
+        value: i64,
+    },
+    /// `{key}` should be between {min} and {max}, found {value}
+    OutOfRange {
+        /// key set in the file
+        key: String,
+        /// value set in the file
+        value: i64,
+        /// minimum allowed value
+        min: i64,
+        /// maximum allowed value
+        max: i64,
+    },
+    /// cannot read the configuration file: {0}
+    Unreadable(String),
+    /// invalid configuration: {0}
+    Invalid(String),
+}
+
+impl ConfigError {
+    /// Suggested fix, if any
+    pub fn suggestion(&self) -> Option<String> {
+        match self {
+            ConfigError::UnknownKey {
+                closest: Some(closest),
+                ..
+            } => Some(format!("did you mean `{}`?", closest)),
+            ConfigError::UnknownKey { closest: None, .. } => {
+                Some("remove it, it is ignored by the node".to_string())
+            }
+            ConfigError::WrongType { expected, .. } => Some(format!("set it to {}", expected)),
+            ConfigError::LikelySeconds { value, .. } => Some(format!(
+                "if you meant {} seconds, set it to {}",
+                value,
+                value.saturating_mul(1000)
+            )),
+            ConfigError::DurationString {
+                millis: Some(millis),
+                ..
+            } => Some(format!("set it to {}", millis)),
+            ConfigError::DurationString { millis: None, .. } => {
+                Some("set it to a number of milliseconds".to_string())
+            }
+            ConfigError::Negative { .. } => Some("set it to a positive value".to_string()),
+            ConfigError::OutOfRange {
+                value, min, max, ..
+            } => Some(format!("set it to {}", (*value).clamp(*min, *max))),
+            ConfigError::Unreadable(_) | ConfigError::Invalid(_) => None,
+        }
+    }
+}
+
+/// Problem found in the configuration
+#[derive(Debug, Clone)]
+pub struct ConfigIssue {
+    /// file in which the problem was found, `None` for the merged configuration
+    pub file: Option<String>,
+    /// the problem
+    pub error: ConfigError,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file)?;
+        }
+        write!(f, "{}", self.error)?;
+        if let Some(suggestion) = self.error.suggestion() {
+            write!(f, " ({})", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Known configuration keys, with their type and default value
+pub(crate) struct ConfigSchema {
+    keys: BTreeMap<String, (ValueKind, Option<Value>)>,
+}
+
+impl ConfigSchema {
+    /// Build the schema from the default configuration
+    pub(crate) fn from_default(default: &Value) -> Self {
+        let mut leaves = BTreeMap::new();
+        flatten(default, "", &mut leaves);
+        let mut keys: BTreeMap<_, _> = leaves
+            .into_iter()
+            .map(|(key, value)| (key, (ValueKind::of(&value), Some(value))))
+            .collect();
+        for (key, kind) in OPTIONAL_KEYS {
+            keys.entry(key.to_string()).or_insert((kind, None));
+        }
+        ConfigSchema { keys }
+    }
+
+    /// Closest known key of a mistyped key: a key of the same table with a similar name,
+    /// or a key with the same name in another table
+    fn closest(&self, key: &str) -> Option<String> {
+        let (table, name) = key.rsplit_once('.').unwrap_or(("", key));
+        let max_distance = (name.len() / 3).max(2);
+        self.keys
+            .keys()
+            .filter_map(|known| {
+                let (known_table, known_name) = known.rsplit_once('.').unwrap_or(("", known));
+                if known_table == table {
+                    let distance = edit_distance(name, known_name);
+                    (distance <= max_distance).then_some((distance, known))
+                } else {
+                    (known_name == name).then_some((usize::MAX, known))
+                }
+            })
+            .min()
+            .map(|(_, known)| known.clone())
+    }
+
+    /// Check the values set by a configuration file
+    pub(crate) fn check(&self, values: &Value) -> Vec<ConfigError> {
+        let mut leaves = BTreeMap::new();
+        flatten(values, "", &mut leaves);
+        let mut errors = Vec::new();
+        for (key, value) in leaves {
+            let Some((expected, default)) = self.keys.get(&key) else {
+                let closest = self.closest(&key);
+                errors.push(ConfigError::UnknownKey { key, closest });
+                continue;
+            };
+            let is_milliseconds = MILLISECOND_KEYS
+                .contains(&key.rsplit_once('.').map_or(key.as_str(), |(_, name)| name));
+            let found = ValueKind::of(&value);
+            if !expected.accepts(found) {
+                errors.push(match value {
+                    Value::String(value) if is_milliseconds => ConfigError::DurationString {
+                        millis: parse_duration_millis(&value),
+                        key,
+                        value,
+                    },
+                    _ => ConfigError::WrongType {
+                        key,
+                        expected: *expected,
+                        found,
+                    },
+                });
+                continue;
+            }
+            let Some(value) = value.as_i64() else {
+                continue;
+            };
+            let default = default.as_ref().and_then(Value::as_i64);
+            if let Some((_, min, max)) = RANGES.iter().find(|(name, _, _)| *name == key) {
+                if value < *min || value > *max {
+                    errors.push(ConfigError::OutOfRange {
+                        key,
+                        value,
+                        min: *min,
+                        max: *max,
+                    });
+                }
+            } else if value < 0 && default.map_or(true, |default| default >= 0) {
+                errors.push(ConfigError::Negative { key, value });
+            } else if is_milliseconds && looks_like_seconds(value, default) {
+                errors.push(ConfigError::LikelySeconds { key, value });
+            }
+        }
+        errors
+    }
+}
+
+/// Collect the leaf values of a configuration, indexed by their dotted key
+fn flatten(value: &Value, prefix: &str, leaves: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(table) if prefix.is_empty() || !FREE_FORM_TABLES.contains(&prefix) => {
+            for (name, value) in table {
+                let key = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+                flatten(value, &key, leaves);
+            }
+        }
+        _ => {
+            leaves.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+/// Whether a duration in milliseconds is likely a number of seconds:
+/// much smaller than the default duration, but in its order of magnitude once converted
+fn looks_like_seconds(value: i64, default: Option<i64>) -> bool {
+    let Some(default) = default else {
+        return false;
+    };
+    value > 0
+        && value.saturating_mul(10) <= default
+        && (default / 10..=default.saturating_mul(10)).contains(&value.saturating_mul(1000))
+}
+
+/// Parse a duration written with a unit, like "500ms", "10s", "5m" or "1h", into milliseconds
+fn parse_duration_millis(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let unit_start = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_start);
+    let number: f64 = number.trim().parse().ok()?;
+    let factor = match unit.trim() {
+        "ms" | "" => 1.0,
+        "s" => 1_000.0,
+        "m" | "min" => 60_000.0,
+        "h" => 3_600_000.0,
+        _ => return None,
+    };
+    let millis = number * factor;
+    (millis.is_finite() && millis >= 0.0 && millis <= u64::MAX as f64).then_some(millis as u64)
+}
+
+/// Levenshtein distance between two key names
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Check the configuration files loaded by the node, then the merged configuration
+pub fn check_config() -> Vec<ConfigIssue> {
+    let profile_path = get_network_profile_from_args();
+    let mut names = massa_settings_file_names("massa-node", profile_path.as_deref()).into_iter();
+    let Some(default_name) = names.next() else {
+        return Vec::new();
+    };
+    let schema = match read_massa_settings_file(&default_name) {
+        Ok(default) => ConfigSchema::from_default(&default),
+        Err(err) => {
+            return vec![ConfigIssue {
+                file: Some(default_name),
+                error: ConfigError::Unreadable(err.to_string()),
+            }];
+        }
+    };
+    let mut issues = Vec::new();
+    for name in names {
+        match read_massa_settings_file(&name) {
+            Ok(values) => {
+                issues.extend(schema.check(&values).into_iter().map(|error| ConfigIssue {
+                    file: Some(name.clone()),
+                    error,
+                }))
+            }
+            Err(err) => issues.push(ConfigIssue {
+                file: Some(name),
+                error: ConfigError::Unreadable(err.to_string()),
+            }),
+        }
+    }
+    if let Err(err) = try_build_massa_settings_with_profile::<Settings>(
+        "massa-node",
+        "MASSA_NODE",
+        profile_path.as_deref(),
+    ) {
+        issues.push(ConfigIssue {
+            file: None,
+            error: ConfigError::Invalid(err.to_string()),
+        });
+    }
+    issues
+}
+
+/// `check-config` subcommand: print the configuration problems, failing if there are any
+pub fn run_check_config() -> anyhow::Result<()> {
+    let issues = check_config();
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    if !issues.is_empty() {
+        return Err(anyhow::anyhow!(
+            "found {} problem(s) in the configuration",
+            issues.len()
+        ));
+    }
+    println!("configuration OK");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::NETWORK_PROFILES;
+    use serde_json::json;
+
+    fn schema() -> ConfigSchema {
+        ConfigSchema::from_default(&json!({
+            "logging": { "level": 2 },
+            "protocol": {
+                "ask_block_timeout": 10000,
+                "max_in_connections": 100,
+                "bind": "[::]:31244",
+                "peers_categories": { "Bootstrap": { "max_in_connections": 1 } },
+            },
+        }))
+    }
+
+    #[test]
+    fn test_check_config_values() {
+        let errors = schema().check(&json!({
+            "logging": { "level": 7 },
+            "protocol": {
+                "ask_block_timeot": 10000,
+                "ask_block_timeout": 10,
+                "max_in_connections": -1,
+                "bind": 31244,
+                "peers_categories": { "Custom": { "max_in_connections": 5 } },
+            },
+            "network": { "routable_ip": "1.2.3.4", "bind": "[::]:31244" },
+        }));
+        assert_eq!(
+            errors,
+            vec![
+                ConfigError::OutOfRange {
+                    key: "logging.level".to_string(),
+                    value: 7,
+                    min: 0,
+                    max: 4,
+                },
+                ConfigError::UnknownKey {
+                    key: "network.bind".to_string(),
+                    closest: Some("protocol.bind".to_string()),
+                },
+                ConfigError::UnknownKey {
+                    key: "protocol.ask_block_timeot".to_string(),
+                    closest: Some("protocol.ask_block_timeout".to_string()),
+                },
+                ConfigError::LikelySeconds {
+                    key: "protocol.ask_block_timeout".to_string(),
+                    value: 10,
+                },
+                ConfigError::WrongType {
+                    key: "protocol.bind".to_string(),
+                    expected: ValueKind::String,
+                    found: ValueKind::Integer,
+                },
+                ConfigError::Negative {
+                    key: "protocol.max_in_connections".to_string(),
+                    value: -1,
+                },
+            ]
+        );
+        assert_eq!(
+            errors[3].suggestion().as_deref(),
+            Some("if you meant 10 seconds, set it to 10000")
+        );
+    }
+
+    #[test]
+    fn test_check_config_duration_strings() {
+        let errors = schema().check(&json!({ "protocol": { "ask_block_timeout": "1.5s" } }));
+        assert_eq!(
+            errors,
+            vec![ConfigError::DurationString {
+                key: "protocol.ask_block_timeout".to_string(),
+                value: "1.5s".to_string(),
+                millis: Some(1500),
+            }]
+        );
+        assert_eq!(parse_duration_millis("2 min"), Some(120_000));
+        assert_eq!(parse_duration_millis("soon"), None);
+    }
+
+    #[test]
+    fn test_bundled_network_profiles_are_valid() {
+        let schema = ConfigSchema::from_default(
+            &read_massa_settings_file("base_config/config.toml").unwrap(),
+        );
+        for profile in NETWORK_PROFILES {
+            let values =
+                read_massa_settings_file(&format!("base_config/networks/{}.toml", profile))
+                    .unwrap();
+            assert_eq!(schema.check(&values), vec![], "{} profile", profile);
+        }
+    }
+}
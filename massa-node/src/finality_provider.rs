@@ -0,0 +1,17 @@
+//! Final state hashes announced to the peers during the protocol handshake, taken from the history kept by the execution.
+
+use massa_execution_exports::ExecutionController;
+use massa_hash::Hash;
+use massa_models::slot::Slot;
+use massa_protocol_exports::FinalityProvider;
+
+/// Provides the final state hashes of the latest SCE-final slots executed by the node
+pub struct ExecutionFinalityProvider(pub Box<dyn ExecutionController>);
+
+impl FinalityProvider for ExecutionFinalityProvider {
+    fn latest_final_state_hashes(&self, count: usize) -> Vec<(Slot, Hash)> {
+        let mut hashes = self.0.get_final_state_hashes(None);
+        hashes.drain(..hashes.len().saturating_sub(count));
+        hashes
+    }
+}
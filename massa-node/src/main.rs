@@ -5,27 +5,30 @@
 #![warn(unused_crate_dependencies)]
 extern crate massa_logging;
 
+use crate::config_check::{check_config, run_check_config};
 #[cfg(feature = "op_spammer")]
 use crate::operation_injector::start_operation_injector;
+use crate::secret_store::{
+    load_node_keypair, load_secret_stores, migrate_file_secrets, NodeSecretStores,
+};
 use crate::settings::SETTINGS;
 use crate::survey::MassaSurvey;
 
-use clap::{crate_version, Parser};
+use clap::{crate_version, Parser, Subcommand};
 use crossbeam_channel::TryRecvError;
 use dialoguer::Password;
 use massa_api::{ApiServer, ApiV2, Private, Public, RpcServer, StopHandle, API};
 use massa_api_exports::config::APIConfig;
 use massa_async_pool::AsyncPoolConfig;
 use massa_bootstrap::BootstrapError;
-use massa_bootstrap::{
-    get_state, start_bootstrap_server, BootstrapConfig, BootstrapManager, BootstrapTcpListener,
-    DefaultConnector,
-};
+use massa_bootstrap::{get_state, BootstrapConfig, BootstrapManager, DefaultConnector};
+#[cfg(feature = "serve_bootstrap")]
+use massa_bootstrap::{start_bootstrap_server, BootstrapTcpListener};
 use massa_channel::receiver::MassaReceiver;
 use massa_channel::MassaChannel;
 use massa_consensus_exports::events::ConsensusEvent;
 use massa_consensus_exports::{
-    ConsensusBroadcasts, ConsensusChannels, ConsensusConfig, ConsensusManager,
+    BlockArchive, ConsensusBroadcasts, ConsensusChannels, ConsensusConfig, ConsensusManager,
 };
 use massa_consensus_worker::start_consensus_worker;
 use massa_db_exports::{MassaDBConfig, MassaDBController};
@@ -35,11 +38,17 @@ use massa_execution_exports::{
     ExecutionChannels, ExecutionConfig, ExecutionManager, GasCosts, StorageCostsConstants,
 };
 use massa_execution_worker::start_execution_worker;
-use massa_factory_exports::{FactoryChannels, FactoryConfig, FactoryManager};
+#[cfg(feature = "factory")]
+use massa_factory_exports::{FactoryChannels, FactoryConfig};
+use massa_factory_exports::{FactoryManager, OperatorRevenue, ProductionBlacklist};
+#[cfg(feature = "factory")]
 use massa_factory_worker::start_factory;
 use massa_final_state::{FinalState, FinalStateConfig, FinalStateController};
+#[cfg(feature = "grpc")]
 use massa_grpc::config::{GrpcConfig, ServiceName};
-use massa_grpc::server::{MassaPrivateGrpc, MassaPublicGrpc};
+#[cfg(feature = "grpc")]
+use massa_grpc::server::{MassaPrivateGrpc, MassaPublicGrpc, StopHandle as GrpcStopHandle};
+use massa_hash::Hash;
 use massa_ledger_exports::LedgerConfig;
 use massa_ledger_worker::FinalLedger;
 use massa_logging::massa_trace;
@@ -50,24 +59,25 @@ use massa_models::config::constants::{
     CONSENSUS_BOOTSTRAP_PART_SIZE, DELTA_F0, DENUNCIATION_EXPIRE_PERIODS, ENDORSEMENT_COUNT,
     END_TIMESTAMP, GENESIS_KEY, GENESIS_TIMESTAMP, INITIAL_DRAW_SEED, LEDGER_COST_PER_BYTE,
     LEDGER_ENTRY_BASE_COST, LEDGER_ENTRY_DATASTORE_BASE_SIZE, MAX_ADVERTISE_LENGTH, MAX_ASYNC_GAS,
-    MAX_ASYNC_POOL_LENGTH, MAX_BLOCK_SIZE, MAX_BOOTSTRAP_BLOCKS, MAX_BOOTSTRAP_ERROR_LENGTH,
-    MAX_BYTECODE_LENGTH, MAX_CONSENSUS_BLOCKS_IDS, MAX_DATASTORE_ENTRY_COUNT,
-    MAX_DATASTORE_KEY_LENGTH, MAX_DATASTORE_VALUE_LENGTH, MAX_DEFERRED_CREDITS_LENGTH,
-    MAX_DENUNCIATIONS_PER_BLOCK_HEADER, MAX_DENUNCIATION_CHANGES_LENGTH,
-    MAX_ENDORSEMENTS_PER_MESSAGE, MAX_EXECUTED_OPS_CHANGES_LENGTH, MAX_EXECUTED_OPS_LENGTH,
-    MAX_FUNCTION_NAME_LENGTH, MAX_GAS_PER_BLOCK, MAX_LEDGER_CHANGES_COUNT, MAX_LISTENERS_PER_PEER,
-    MAX_OPERATIONS_PER_BLOCK, MAX_OPERATIONS_PER_MESSAGE, MAX_OPERATION_DATASTORE_ENTRY_COUNT,
-    MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH,
-    MAX_OPERATION_STORAGE_TIME, MAX_PARAMETERS_SIZE, MAX_PEERS_IN_ANNOUNCEMENT_LIST,
-    MAX_PRODUCTION_STATS_LENGTH, MAX_ROLLS_COUNT_LENGTH, MAX_SIZE_CHANNEL_COMMANDS_CONNECTIVITY,
-    MAX_SIZE_CHANNEL_COMMANDS_PEERS, MAX_SIZE_CHANNEL_COMMANDS_PEER_TESTERS,
-    MAX_SIZE_CHANNEL_COMMANDS_PROPAGATION_BLOCKS,
+    MAX_ASYNC_POOL_LENGTH, MAX_BLOCK_HEADER_DENUNCIATIONS_SIZE, MAX_BLOCK_HEADER_SIZE,
+    MAX_BLOCK_SIZE, MAX_BOOTSTRAP_BLOCKS, MAX_BOOTSTRAP_ERROR_LENGTH, MAX_BYTECODE_LENGTH,
+    MAX_CONSENSUS_BLOCKS_IDS, MAX_DATASTORE_ENTRY_COUNT, MAX_DATASTORE_KEY_LENGTH,
+    MAX_DATASTORE_VALUE_LENGTH, MAX_DEFERRED_CREDITS_LENGTH, MAX_DENUNCIATIONS_PER_BLOCK_HEADER,
+    MAX_DENUNCIATION_CHANGES_LENGTH, MAX_ENDORSEMENTS_PER_MESSAGE, MAX_EXECUTED_OPS_CHANGES_LENGTH,
+    MAX_EXECUTED_OPS_LENGTH, MAX_FUNCTION_NAME_LENGTH, MAX_GAS_PER_BLOCK, MAX_LEDGER_CHANGES_COUNT,
+    MAX_LISTENERS_PER_PEER, MAX_OPERATIONS_PER_BLOCK, MAX_OPERATIONS_PER_MESSAGE,
+    MAX_OPERATION_DATASTORE_ENTRY_COUNT, MAX_OPERATION_DATASTORE_KEY_LENGTH,
+    MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_OPERATION_STORAGE_TIME, MAX_PARAMETERS_SIZE,
+    MAX_PEERS_IN_ANNOUNCEMENT_LIST, MAX_PRODUCTION_STATS_LENGTH, MAX_ROLLS_COUNT_LENGTH,
+    MAX_SIZE_CHANNEL_COMMANDS_CONNECTIVITY, MAX_SIZE_CHANNEL_COMMANDS_PEERS,
+    MAX_SIZE_CHANNEL_COMMANDS_PEER_TESTERS, MAX_SIZE_CHANNEL_COMMANDS_PROPAGATION_BLOCKS,
     MAX_SIZE_CHANNEL_COMMANDS_PROPAGATION_ENDORSEMENTS,
     MAX_SIZE_CHANNEL_COMMANDS_PROPAGATION_OPERATIONS, MAX_SIZE_CHANNEL_COMMANDS_RETRIEVAL_BLOCKS,
     MAX_SIZE_CHANNEL_COMMANDS_RETRIEVAL_ENDORSEMENTS,
-    MAX_SIZE_CHANNEL_COMMANDS_RETRIEVAL_OPERATIONS, MAX_SIZE_CHANNEL_NETWORK_TO_BLOCK_HANDLER,
-    MAX_SIZE_CHANNEL_NETWORK_TO_ENDORSEMENT_HANDLER, MAX_SIZE_CHANNEL_NETWORK_TO_OPERATION_HANDLER,
-    MAX_SIZE_CHANNEL_NETWORK_TO_PEER_HANDLER, MIP_STORE_STATS_BLOCK_CONSIDERED,
+    MAX_SIZE_CHANNEL_COMMANDS_RETRIEVAL_OPERATIONS, MAX_SIZE_CHANNEL_COMMANDS_TOPICS,
+    MAX_SIZE_CHANNEL_NETWORK_TO_BLOCK_HANDLER, MAX_SIZE_CHANNEL_NETWORK_TO_ENDORSEMENT_HANDLER,
+    MAX_SIZE_CHANNEL_NETWORK_TO_OPERATION_HANDLER, MAX_SIZE_CHANNEL_NETWORK_TO_PEER_HANDLER,
+    MAX_SIZE_CHANNEL_NETWORK_TO_TOPIC_HANDLER, MIP_STORE_STATS_BLOCK_CONSIDERED,
     OPERATION_VALIDITY_PERIODS, PERIODS_PER_CYCLE, POS_MISS_RATE_DEACTIVATION_THRESHOLD,
     POS_SAVED_CYCLES, PROTOCOL_CONTROLLER_CHANNEL_SIZE, PROTOCOL_EVENT_CHANNEL_SIZE,
     ROLL_COUNT_TO_SLASH_ON_DENUNCIATION, ROLL_PRICE, SELECTOR_DRAW_CACHE_SIZE, T0, THREAD_COUNT,
@@ -86,36 +96,61 @@ use massa_pos_exports::{PoSConfig, SelectorConfig, SelectorManager};
 use massa_pos_worker::start_selector_worker;
 use massa_protocol_exports::{ProtocolConfig, ProtocolManager, TransportType};
 use massa_protocol_worker::{create_protocol_controller, start_protocol_controller};
+#[cfg(feature = "grpc")]
 use massa_signature::KeyPair;
 use massa_storage::Storage;
 use massa_time::MassaTime;
+#[cfg(feature = "grpc")]
 use massa_versioning::keypair_factory::KeyPairFactory;
-use massa_versioning::mips::get_mip_list;
+use massa_versioning::mips::{get_mip_list, get_mip_list_from_file};
 use massa_versioning::versioning::{MipStatsConfig, MipStore};
-use massa_wallet::Wallet;
+use massa_wallet::{SecretStore, Wallet};
 use num::rational::Ratio;
 use parking_lot::RwLock;
+#[cfg(feature = "grpc")]
 use settings::GrpcSettings;
 use std::collections::HashMap;
-use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Condvar, Mutex};
 use std::time::Duration;
-use std::{path::Path, process, sync::Arc};
+use std::{process, sync::Arc};
 
+use block_archive::FileBlockArchive;
+use finality_provider::ExecutionFinalityProvider;
+#[cfg(not(feature = "grpc"))]
+use stubs::GrpcStopHandle;
 use survey::MassaSurveyStopper;
 use tokio::sync::broadcast;
 use tracing::{debug, error, info, warn};
 use tracing_subscriber::filter::{filter_fn, LevelFilter};
+use verifier::{MassaVerifier, MassaVerifierStopper};
 
+mod block_archive;
+mod config_check;
+mod finality_provider;
 #[cfg(feature = "op_spammer")]
 mod operation_injector;
+mod secret_store;
 mod settings;
+mod stubs;
 mod survey;
+mod verifier;
+
+/// jemalloc is the global allocator of profiling builds, to take heap snapshots through the private API
+#[cfg(feature = "profiling")]
+#[global_allocator]
+static GLOBAL_ALLOCATOR: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+/// Enables the jemalloc heap profiler, sampling on average every 512 KiB of allocations
+#[cfg(feature = "profiling")]
+#[allow(non_upper_case_globals)]
+#[export_name = "_rjem_malloc_conf"]
+pub static malloc_conf: &[u8] = b"prof:true,prof_active:true,lg_prof_sample:19\0";
 
 async fn launch(
     args: &Args,
     node_wallet: Arc<RwLock<Wallet>>,
+    secret_stores: &NodeSecretStores,
     sig_int_toggled: Arc<(Mutex<bool>, Condvar)>,
 ) -> (
     MassaReceiver<ConsensusEvent>,
@@ -125,14 +160,15 @@ async fn launch(
     Box<dyn SelectorManager>,
     Box<dyn PoolManager>,
     Box<dyn ProtocolManager>,
-    Box<dyn FactoryManager>,
+    Option<Box<dyn FactoryManager>>,
     StopHandle,
     StopHandle,
     StopHandle,
-    Option<massa_grpc::server::StopHandle>,
-    Option<massa_grpc::server::StopHandle>,
+    Option<GrpcStopHandle>,
+    Option<GrpcStopHandle>,
     MetricsStopper,
     MassaSurveyStopper,
+    MassaVerifierStopper,
 ) {
     let now = MassaTime::now();
     // Do not start if genesis is in the future. This is meant to prevent nodes
@@ -262,10 +298,6 @@ async fn launch(
             std::fs::remove_dir_all(SETTINGS.ledger.disk_ledger_path.clone())
                 .expect("disk ledger delete failed");
         }
-        if SETTINGS.execution.hd_cache_path.exists() {
-            std::fs::remove_dir_all(SETTINGS.execution.hd_cache_path.clone())
-                .expect("disk hd cache delete failed");
-        }
     }
 
     let db_config = MassaDBConfig {
@@ -274,6 +306,7 @@ async fn launch(
         max_final_state_elements_size: MAX_BOOTSTRAP_FINAL_STATE_PARTS_SIZE.try_into().unwrap(),
         max_versioning_elements_size: MAX_BOOTSTRAP_VERSIONING_ELEMENTS_SIZE.try_into().unwrap(),
         thread_count: THREAD_COUNT,
+        state_write_buffer_size: SETTINGS.ledger.state_write_buffer_size,
     };
     let db = Arc::new(RwLock::new(
         Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>
@@ -332,7 +365,10 @@ async fn launch(
                 // The resulting MIP store will likely be updated by the boostrap process in order
                 // to get the latest information for the MIP store (new states, votes...)
 
-                let mip_list = get_mip_list();
+                let mip_list = match &SETTINGS.versioning.mip_list_path {
+                    Some(path) => get_mip_list_from_file(path).expect("could not load MIP list"),
+                    None => Vec::from(get_mip_list()),
+                };
                 debug!("MIP list: {:?}", mip_list);
                 let mip_store = MipStore::try_from((mip_list, mip_stats_config))
                     .expect("mip store creation failed");
@@ -354,6 +390,7 @@ async fn launch(
 
     let bootstrap_config: BootstrapConfig = BootstrapConfig {
         bootstrap_list: SETTINGS.bootstrap.bootstrap_list.clone(),
+        bootstrap_hostname_list: SETTINGS.bootstrap.bootstrap_hostname_list.clone(),
         bootstrap_protocol: SETTINGS.bootstrap.bootstrap_protocol,
         bootstrap_whitelist_path: SETTINGS.bootstrap.bootstrap_whitelist_path.clone(),
         bootstrap_blacklist_path: SETTINGS.bootstrap.bootstrap_blacklist_path.clone(),
@@ -409,7 +446,9 @@ async fn launch(
     let bootstrap_state = match get_state(
         &bootstrap_config,
         final_state.clone(),
-        DefaultConnector,
+        DefaultConnector {
+            socks5_proxy: SETTINGS.protocol.socks5_proxy.clone(),
+        },
         *VERSION,
         *GENESIS_TIMESTAMP,
         *END_TIMESTAMP,
@@ -478,11 +517,27 @@ async fn launch(
     )
     .expect("Failed to load gas costs");
 
+    // the compiled modules of the hd cache are kept across restarts as long as
+    // the node version and the gas costs they were compiled with do not change
+    let hd_cache_fingerprint = Hash::compute_from(
+        &[
+            VERSION.to_string().as_bytes(),
+            &std::fs::read(&SETTINGS.execution.abi_gas_costs_file)
+                .expect("Failed to read abi gas costs file"),
+            &std::fs::read(&SETTINGS.execution.wasm_gas_costs_file)
+                .expect("Failed to read wasm gas costs file"),
+        ]
+        .concat(),
+    );
+
     // launch execution module
     let execution_config = ExecutionConfig {
         max_final_events: SETTINGS.execution.max_final_events,
+        max_recent_operation_results: SETTINGS.execution.max_recent_operation_results,
         readonly_queue_length: SETTINGS.execution.readonly_queue_length,
         cursor_delay: SETTINGS.execution.cursor_delay,
+        max_candidate_execution_lag: SETTINGS.execution.max_candidate_execution_lag,
+        candidate_execution_enabled: SETTINGS.execution.candidate_execution_enabled,
         max_async_gas: MAX_ASYNC_GAS,
         async_msg_cst_gas_cost: ASYNC_MSG_CST_GAS_COST,
         max_gas_per_block: MAX_GAS_PER_BLOCK,
@@ -501,13 +556,16 @@ async fn launch(
         max_datastore_value_size: MAX_DATASTORE_VALUE_LENGTH,
         storage_costs_constants,
         max_read_only_gas: SETTINGS.execution.max_read_only_gas,
+        readonly_call_cache_size: SETTINGS.execution.readonly_call_cache_size,
         gas_costs: gas_costs.clone(),
+        abi_gas_costs_file: SETTINGS.execution.abi_gas_costs_file.clone(),
         base_operation_gas_cost: BASE_OPERATION_GAS_COST,
         last_start_period: final_state.read().get_last_start_period(),
         hd_cache_path: SETTINGS.execution.hd_cache_path.clone(),
         lru_cache_size: SETTINGS.execution.lru_cache_size,
         hd_cache_size: SETTINGS.execution.hd_cache_size,
         snip_amount: SETTINGS.execution.snip_amount,
+        hd_cache_fingerprint,
         roll_count_to_slash_on_denunciation: ROLL_COUNT_TO_SLASH_ON_DENUNCIATION,
         denunciation_expire_periods: DENUNCIATION_EXPIRE_PERIODS,
         broadcast_enabled: SETTINGS.api.enable_broadcast,
@@ -517,6 +575,19 @@ async fn launch(
         max_event_size: MAX_EVENT_DATA_SIZE,
         max_function_length: MAX_FUNCTION_NAME_LENGTH,
         max_parameter_length: MAX_PARAMETERS_SIZE,
+        execution_trace_enabled: SETTINGS.execution.execution_trace_enabled,
+        execution_trace_path: SETTINGS.execution.execution_trace_path.clone(),
+        execution_trace_format: SETTINGS.execution.execution_trace_format,
+        execution_checkpoint_interval: SETTINGS.execution.execution_checkpoint_interval,
+        execution_checkpoint_path: SETTINGS.execution.execution_checkpoint_path.clone(),
+        event_index_enabled: SETTINGS.execution.event_index_enabled,
+        event_index_path: SETTINGS.execution.event_index_path.clone(),
+        max_indexed_events: SETTINGS.execution.max_indexed_events,
+        contract_io_stats_enabled: SETTINGS.execution.contract_io_stats_enabled,
+        api_output_retention_slots: SETTINGS.execution.api_output_retention_slots,
+        stream_output_retention_slots: SETTINGS.execution.stream_output_retention_slots,
+        finality_output_retention_slots: SETTINGS.execution.finality_output_retention_slots,
+        final_state_hash_history_length: SETTINGS.execution.final_state_hash_history_length,
     };
 
     let execution_channels = ExecutionChannels {
@@ -605,6 +676,7 @@ async fn launch(
         max_simultaneous_ask_blocks_per_node: SETTINGS
             .protocol
             .max_simultaneous_ask_blocks_per_node,
+        max_block_bodies_in_flight: SETTINGS.protocol.max_block_bodies_in_flight,
         max_send_wait: SETTINGS.protocol.max_send_wait,
         operation_batch_buffer_capacity: SETTINGS.protocol.operation_batch_buffer_capacity,
         operation_announcement_buffer_capacity: SETTINGS
@@ -623,16 +695,60 @@ async fn launch(
         max_message_size: MAX_MESSAGE_SIZE as usize,
         max_ops_kept_for_propagation: SETTINGS.protocol.max_ops_kept_for_propagation,
         max_operations_propagation_time: SETTINGS.protocol.max_operations_propagation_time,
+        operation_inventory_digest_interval: SETTINGS.protocol.operation_inventory_digest_interval,
+        max_operations_per_inventory_digest: SETTINGS.protocol.max_operations_per_inventory_digest,
+        topic_anti_entropy_interval: SETTINGS.protocol.topic_anti_entropy_interval,
+        max_topic_payload_size: SETTINGS.protocol.max_topic_payload_size,
+        max_topic_items_per_message: SETTINGS.protocol.max_topic_items_per_message,
+        operation_sig_verification_threads: SETTINGS.protocol.operation_sig_verification_threads,
+        operation_sig_verification_batch_size: SETTINGS
+            .protocol
+            .operation_sig_verification_batch_size,
+        handshake_final_state_hashes: SETTINGS.protocol.handshake_final_state_hashes,
+        refuse_divergent_peers: SETTINGS.protocol.refuse_divergent_peers,
         max_endorsements_propagation_time: SETTINGS.protocol.max_endorsements_propagation_time,
+        max_future_slot_time: SETTINGS.protocol.max_future_slot_time,
         last_start_period: final_state.read().get_last_start_period(),
         max_endorsements_per_message: MAX_ENDORSEMENTS_PER_MESSAGE as u64,
         max_denunciations_in_block_header: MAX_DENUNCIATIONS_PER_BLOCK_HEADER,
+        max_block_header_denunciations_size: MAX_BLOCK_HEADER_DENUNCIATIONS_SIZE as usize,
+        max_block_header_size: MAX_BLOCK_HEADER_SIZE as usize,
         initial_peers: SETTINGS.protocol.initial_peers_file.clone(),
         listeners,
-        keypair_file: SETTINGS.protocol.keypair_file.clone(),
         max_blocks_kept_for_propagation: SETTINGS.protocol.max_blocks_kept_for_propagation,
         block_propagation_tick: SETTINGS.protocol.block_propagation_tick,
+        compact_block_relay: SETTINGS.protocol.compact_block_relay,
+        block_body_chunking: SETTINGS.protocol.block_body_chunking,
+        block_body_data_chunks: SETTINGS.protocol.block_body_data_chunks,
+        block_body_parity_chunks: SETTINGS.protocol.block_body_parity_chunks,
+        block_body_chunking_min_missing_operations: SETTINGS
+            .protocol
+            .block_body_chunking_min_missing_operations,
+        message_compression: SETTINGS.protocol.message_compression,
+        message_compression_threshold: SETTINGS.protocol.message_compression_threshold,
+        message_compression_level: SETTINGS.protocol.message_compression_level,
+        max_decompressed_message_size: SETTINGS.protocol.max_decompressed_message_size,
+        message_rate_limits: SETTINGS.protocol.message_rate_limits,
+        peers_categories_message_rate_limits: SETTINGS
+            .protocol
+            .peers_categories_message_rate_limits
+            .clone(),
+        bandwidth_budget: SETTINGS.protocol.bandwidth_budget,
+        peers_categories_bandwidth_budgets: SETTINGS
+            .protocol
+            .peers_categories_bandwidth_budgets
+            .clone(),
+        socks5_proxy: SETTINGS.protocol.socks5_proxy.clone(),
         asked_operations_buffer_capacity: SETTINGS.protocol.asked_operations_buffer_capacity,
+        max_ask_operations_size_per_peer: SETTINGS.protocol.max_ask_operations_size_per_peer,
+        peer_reputation_deprioritize_threshold: SETTINGS
+            .protocol
+            .peer_reputation_deprioritize_threshold,
+        peer_reputation_ban_threshold: SETTINGS.protocol.peer_reputation_ban_threshold,
+        peer_reputation_recovery_period: SETTINGS.protocol.peer_reputation_recovery_period,
+        broadcast_connection_events_channel_capacity: SETTINGS
+            .protocol
+            .broadcast_connection_events_channel_capacity,
         thread_tester_count: SETTINGS.protocol.thread_tester_count,
         max_operation_storage_time: MAX_OPERATION_STORAGE_TIME,
         max_size_channel_commands_propagation_blocks: MAX_SIZE_CHANNEL_COMMANDS_PROPAGATION_BLOCKS,
@@ -654,6 +770,8 @@ async fn launch(
         max_size_channel_network_to_endorsement_handler:
             MAX_SIZE_CHANNEL_NETWORK_TO_ENDORSEMENT_HANDLER,
         max_size_channel_network_to_peer_handler: MAX_SIZE_CHANNEL_NETWORK_TO_PEER_HANDLER,
+        max_size_channel_commands_topics: MAX_SIZE_CHANNEL_COMMANDS_TOPICS,
+        max_size_channel_network_to_topic_handler: MAX_SIZE_CHANNEL_NETWORK_TO_TOPIC_HANDLER,
         max_size_value_datastore: MAX_DATASTORE_VALUE_LENGTH,
         max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
         max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
@@ -681,10 +799,24 @@ async fn launch(
         try_connection_timer_same_peer: SETTINGS.protocol.try_connection_timer_same_peer,
         test_oldest_peer_cooldown: SETTINGS.protocol.test_oldest_peer_cooldown,
         rate_limit: SETTINGS.protocol.rate_limit,
+        traffic_capture_path: SETTINGS.protocol.traffic_capture_path.clone(),
+        operation_seen_cache_path: SETTINGS.protocol.operation_seen_cache_path.clone(),
+        operation_seen_cache_max_age: SETTINGS.protocol.operation_seen_cache_max_age,
+        peer_store_path: SETTINGS.protocol.peer_store_path.clone(),
+        peer_store_flush_interval: SETTINGS.protocol.peer_store_flush_interval,
+        peer_store_max_age: SETTINGS.protocol.peer_store_max_age,
+        port_mapping: SETTINGS.protocol.port_mapping,
+        port_mapping_lease_duration: SETTINGS.protocol.port_mapping_lease_duration,
+        port_mapping_timeout: SETTINGS.protocol.port_mapping_timeout,
+        outbound_max_per_group: SETTINGS.protocol.outbound_max_per_group,
+        outbound_rotation_interval: SETTINGS.protocol.outbound_rotation_interval,
+        outbound_rotation_percent: SETTINGS.protocol.outbound_rotation_percent,
+        outbound_asn_map_path: SETTINGS.protocol.outbound_asn_map_path.clone(),
     };
 
     let (protocol_controller, protocol_channels) =
         create_protocol_controller(protocol_config.clone());
+    let protocol_broadcasts = protocol_channels.broadcasts.clone();
 
     let consensus_config = ConsensusConfig {
         genesis_timestamp: *GENESIS_TIMESTAMP,
@@ -694,6 +826,7 @@ async fn launch(
         genesis_key: GENESIS_KEY.clone(),
         max_discarded_blocks: SETTINGS.consensus.max_discarded_blocks,
         max_future_processing_blocks: SETTINGS.consensus.max_future_processing_blocks,
+        draw_check_threads: SETTINGS.consensus.draw_check_threads,
         max_dependency_blocks: SETTINGS.consensus.max_dependency_blocks,
         delta_f0: DELTA_F0,
         operation_validity_periods: OPERATION_VALIDITY_PERIODS,
@@ -713,14 +846,32 @@ async fn launch(
         broadcast_filled_blocks_channel_capacity: SETTINGS
             .consensus
             .broadcast_filled_blocks_channel_capacity,
+        broadcast_final_periods_channel_capacity: SETTINGS
+            .consensus
+            .broadcast_final_periods_channel_capacity,
+        broadcast_fork_choice_channel_capacity: SETTINGS
+            .consensus
+            .broadcast_fork_choice_channel_capacity,
         last_start_period: final_state.read().get_last_start_period(),
         force_keep_final_periods_without_ops: SETTINGS
             .consensus
             .force_keep_final_periods_without_ops,
+        finality_stall_timeout: SETTINGS.consensus.finality_stall_timeout,
+        finality_stall_auto_resync: SETTINGS.consensus.finality_stall_auto_resync,
+        max_reorg_depth: SETTINGS.consensus.max_reorg_depth,
+        max_reorg_auto_resync: SETTINGS.consensus.max_reorg_auto_resync,
     };
 
     let (consensus_event_sender, consensus_event_receiver) =
         MassaChannel::new("consensus_event".to_string(), Some(CHANNEL_SIZE));
+    let block_archive: Option<Arc<dyn BlockArchive>> = if SETTINGS.consensus.block_archive_enabled {
+        Some(Arc::new(
+            FileBlockArchive::open(&SETTINGS.consensus.block_archive_path)
+                .expect("could not open the block archive"),
+        ))
+    } else {
+        None
+    };
     let consensus_channels = ConsensusChannels {
         execution_controller: execution_controller.clone(),
         selector_controller: selector_controller.clone(),
@@ -737,7 +888,16 @@ async fn launch(
                 consensus_config.broadcast_filled_blocks_channel_capacity,
             )
             .0,
+            final_period_sender: broadcast::channel(
+                consensus_config.broadcast_final_periods_channel_capacity,
+            )
+            .0,
+            fork_choice_sender: broadcast::channel(
+                consensus_config.broadcast_fork_choice_channel_capacity,
+            )
+            .0,
         },
+        block_archive,
     };
 
     let (consensus_controller, consensus_manager) = start_consensus_worker(
@@ -748,7 +908,10 @@ async fn launch(
         massa_metrics.clone(),
     );
 
-    let (protocol_manager, keypair, node_id) = start_protocol_controller(
+    // load the node key, or generate it and save it. Then derive nodeId
+    let keypair = load_node_keypair(secret_stores, &mip_store).expect("could not load node key");
+
+    let (protocol_manager, node_id) = start_protocol_controller(
         protocol_config.clone(),
         selector_controller.clone(),
         consensus_controller.clone(),
@@ -758,10 +921,15 @@ async fn launch(
         protocol_channels,
         mip_store.clone(),
         massa_metrics.clone(),
+        keypair.clone(),
+        Some(Arc::new(ExecutionFinalityProvider(
+            execution_controller.clone(),
+        ))),
     )
     .expect("could not start protocol controller");
 
     // launch factory
+    #[cfg(feature = "factory")]
     let factory_config = FactoryConfig {
         thread_count: THREAD_COUNT,
         genesis_timestamp: *GENESIS_TIMESTAMP,
@@ -776,21 +944,50 @@ async fn launch(
         stop_production_when_zero_connections: SETTINGS
             .factory
             .stop_production_when_zero_connections,
+        block_reward: BLOCK_REWARD,
+        endorsement_count: ENDORSEMENT_COUNT,
     };
+    let production_blacklist =
+        ProductionBlacklist::new(SETTINGS.factory.production_blacklist.clone())
+            .expect("invalid factory production blacklist");
+    let operator_revenue = OperatorRevenue::new(
+        SETTINGS.factory.operator_revenue_path.clone(),
+        SETTINGS.factory.operator_revenue_history_length,
+    )
+    .unwrap_or_else(|err| {
+        error!(
+            "could not load the operator revenue, starting without it and without persisting it: {}",
+            err
+        );
+        OperatorRevenue::in_memory(SETTINGS.factory.operator_revenue_history_length)
+    });
+    #[cfg(feature = "factory")]
     let factory_channels = FactoryChannels {
         selector: selector_controller.clone(),
         consensus: consensus_controller.clone(),
         pool: pool_controller.clone(),
         protocol: protocol_controller.clone(),
         storage: shared_storage.clone(),
+        production_blacklist: production_blacklist.clone(),
+        operator_revenue: operator_revenue.clone(),
     };
-    let factory_manager = start_factory(
-        factory_config,
-        node_wallet.clone(),
-        factory_channels,
-        mip_store.clone(),
-    );
+    // verifier nodes do not produce blocks nor endorsements
+    #[cfg(feature = "factory")]
+    let factory_manager = if SETTINGS.verifier.enabled {
+        info!("verifier mode enabled: block and endorsement production is disabled");
+        None
+    } else {
+        Some(start_factory(
+            factory_config,
+            node_wallet.clone(),
+            factory_channels,
+            mip_store.clone(),
+        ))
+    };
+    #[cfg(not(feature = "factory"))]
+    let factory_manager = stubs::factory_manager();
 
+    #[cfg(feature = "serve_bootstrap")]
     let bootstrap_manager = bootstrap_config.listen_addr.map(|addr| {
         let (listener_stopper, listener) =
             BootstrapTcpListener::create(&addr).unwrap_or_else(|_| {
@@ -813,6 +1010,8 @@ async fn launch(
         )
         .expect("Could not start bootstrap server")
     });
+    #[cfg(not(feature = "serve_bootstrap"))]
+    let bootstrap_manager = stubs::bootstrap_manager(&bootstrap_config);
 
     let api_config: APIConfig = APIConfig {
         bind_private: SETTINGS.api.bind_private,
@@ -833,6 +1032,12 @@ async fn launch(
         ping_interval: SETTINGS.api.ping_interval,
         enable_http: SETTINGS.api.enable_http,
         enable_ws: SETTINGS.api.enable_ws,
+        enable_short_ids: SETTINGS.api.enable_short_ids,
+        accept_operations: !SETTINGS.verifier.enabled,
+        db_path: SETTINGS.ledger.disk_ledger_path.clone(),
+        max_events_response_size: SETTINGS.api.max_events_response_size,
+        max_block_summaries_response_size: SETTINGS.api.max_block_summaries_response_size,
+        max_cpu_profile_duration: SETTINGS.api.max_cpu_profile_duration,
         max_datastore_value_length: MAX_DATASTORE_VALUE_LENGTH,
         max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
         max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
@@ -848,6 +1053,7 @@ async fn launch(
         t0: T0,
         periods_per_cycle: PERIODS_PER_CYCLE,
         last_start_period: final_state.read().get_last_start_period(),
+        address_format: SETTINGS.api.address_format,
     };
 
     // spawn Massa API
@@ -874,6 +1080,7 @@ async fn launch(
     api_config.enable_ws = false;
 
     // Whether to spawn gRPC PUBLIC API
+    #[cfg(feature = "grpc")]
     let grpc_public_handle = if SETTINGS.grpc.public.enabled {
         let grpc_public_config = configure_grpc(
             ServiceName::Public,
@@ -914,6 +1121,7 @@ async fn launch(
     };
 
     // Whether to spawn gRPC PRIVATE API
+    #[cfg(feature = "grpc")]
     let grpc_private_handle = if SETTINGS.grpc.private.enabled {
         let grpc_private_config = configure_grpc(
             ServiceName::Private,
@@ -955,6 +1163,8 @@ async fn launch(
     } else {
         None
     };
+    #[cfg(not(feature = "grpc"))]
+    let (grpc_public_handle, grpc_private_handle) = stubs::grpc_handles();
 
     #[cfg(feature = "op_spammer")]
     start_operation_injector(
@@ -966,16 +1176,24 @@ async fn launch(
         args.nb_op,
     );
 
-    // spawn private API
+    // spawn private API, with WebSockets if enabled for the connection events subscription
+    let private_api_config = APIConfig {
+        enable_ws: SETTINGS.api.enable_ws,
+        ..api_config.clone()
+    };
     let api_private = API::<Private>::new(
         protocol_controller.clone(),
+        consensus_controller.clone(),
         execution_controller.clone(),
-        api_config.clone(),
+        private_api_config.clone(),
         sig_int_toggled,
         node_wallet,
+        production_blacklist,
+        operator_revenue,
+        protocol_broadcasts,
     );
     let api_private_handle = api_private
-        .serve(&SETTINGS.api.bind_private, &api_config)
+        .serve(&SETTINGS.api.bind_private, &private_api_config)
         .await
         .expect("failed to start PRIVATE API");
     info!(
@@ -1006,6 +1224,9 @@ async fn launch(
         api_config.bind_public
     );
 
+    let massa_verifier_stopper =
+        MassaVerifier::run(SETTINGS.verifier.clone(), execution_controller.clone());
+
     let massa_survey_stopper = MassaSurvey::run(
         SETTINGS.metrics.tick_delay.to_duration(),
         execution_controller,
@@ -1065,10 +1286,12 @@ async fn launch(
         grpc_public_handle,
         metrics_stopper,
         massa_survey_stopper,
+        massa_verifier_stopper,
     )
 }
 
 // Get the configuration of the gRPC server
+#[cfg(feature = "grpc")]
 fn configure_grpc(
     name: ServiceName,
     settings: &GrpcSettings,
@@ -1149,7 +1372,7 @@ struct Managers {
     selector_manager: Box<dyn SelectorManager>,
     pool_manager: Box<dyn PoolManager>,
     protocol_manager: Box<dyn ProtocolManager>,
-    factory_manager: Box<dyn FactoryManager>,
+    factory_manager: Option<Box<dyn FactoryManager>>,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -1162,15 +1385,16 @@ async fn stop(
         mut selector_manager,
         mut pool_manager,
         mut protocol_manager,
-        mut factory_manager,
+        factory_manager,
     }: Managers,
     api_private_handle: StopHandle,
     api_public_handle: StopHandle,
     api_handle: StopHandle,
-    grpc_private_handle: Option<massa_grpc::server::StopHandle>,
-    grpc_public_handle: Option<massa_grpc::server::StopHandle>,
+    grpc_private_handle: Option<GrpcStopHandle>,
+    grpc_public_handle: Option<GrpcStopHandle>,
     mut metrics_stopper: MetricsStopper,
     mut massa_survey_stopper: MassaSurveyStopper,
+    mut massa_verifier_stopper: MassaVerifierStopper,
 ) {
     // stop bootstrap
     if let Some(bootstrap_manager) = bootstrap_manager {
@@ -1211,8 +1435,13 @@ async fn stop(
     // stop massa survey thread
     massa_survey_stopper.stop();
 
+    // stop massa verifier thread
+    massa_verifier_stopper.stop();
+
     // stop factory
-    factory_manager.stop();
+    if let Some(mut factory_manager) = factory_manager {
+        factory_manager.stop();
+    }
 
     // stop protocol controller
     protocol_manager.stop();
@@ -1250,6 +1479,17 @@ struct Args {
     #[arg(long = "restart-from-snapshot-at-period")]
     restart_from_snapshot_at_period: Option<u64>,
 
+    /// Network profile: mainnet, buildnet, sandbox or the path of a custom profile file
+    #[arg(long = "network", global = true)]
+    network: Option<String>,
+
+    /// Copy the node key and the staking keys files into the configured secret store backend, then exit
+    #[arg(long = "migrate-secrets")]
+    migrate_secrets: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[cfg(feature = "op_spammer")]
     /// number of operations
     #[arg(
@@ -1272,9 +1512,63 @@ struct Args {
     dl_interval: u64,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Check the configuration files for unknown keys, unit mistakes and invalid values, then exit.
+    /// Exits with a nonzero status if problems are found
+    CheckConfig,
+}
+
+/// Checks that the selected network profile matches the build and the ledger on disk,
+/// and records the network of the ledger
+fn check_network_profile() -> anyhow::Result<()> {
+    // a MIP list read from a file is only allowed on private networks
+    if SETTINGS.versioning.mip_list_path.is_some()
+        && SETTINGS
+            .network_profile
+            .as_ref()
+            .map_or(true, |profile| profile.name == "mainnet")
+    {
+        return Err(anyhow::anyhow!(
+            "versioning.mip_list_path can only be set on a private network"
+        ));
+    }
+    let Some(profile) = &SETTINGS.network_profile else {
+        return Ok(());
+    };
+    if profile.sandbox != cfg!(feature = "sandbox") {
+        return Err(anyhow::anyhow!(
+            "the {} network requires a node built {} the sandbox feature",
+            profile.name,
+            if profile.sandbox { "with" } else { "without" }
+        ));
+    }
+    let marker_path = SETTINGS.ledger.disk_ledger_path.with_file_name("network");
+    match std::fs::read_to_string(&marker_path) {
+        Ok(network) if network.trim() != profile.name => Err(anyhow::anyhow!(
+            "the ledger in {} belongs to the {} network, not to the {} network",
+            SETTINGS.ledger.disk_ledger_path.display(),
+            network.trim(),
+            profile.name
+        )),
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            if let Some(dir) = marker_path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&marker_path, &profile.name)?;
+            Ok(())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
 /// Load wallet, asking for passwords if necessary
-fn load_wallet(password: Option<String>, path: &Path) -> anyhow::Result<Arc<RwLock<Wallet>>> {
-    let password = if path.is_dir() {
+fn load_wallet(
+    password: Option<String>,
+    store: Arc<dyn SecretStore>,
+) -> anyhow::Result<Arc<RwLock<Wallet>>> {
+    let password = if !store.list_secrets()?.is_empty() {
         password.unwrap_or_else(|| {
             Password::new()
                 .with_prompt("Enter staking keys file password")
@@ -1290,15 +1584,18 @@ fn load_wallet(password: Option<String>, path: &Path) -> anyhow::Result<Arc<RwLo
                 .expect("IO error: Password reading failed, staking keys file couldn't be created")
         })
     };
-    Ok(Arc::new(RwLock::new(Wallet::new(
-        PathBuf::from(path),
-        password,
+    Ok(Arc::new(RwLock::new(Wallet::new_with_store(
+        store, password,
     )?)))
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
+    if let Some(Command::CheckConfig) = args.command {
+        return run_check_config();
+    }
+
     let tokio_rt = tokio::runtime::Builder::new_multi_thread()
         .thread_name_fn(|| {
             static ATOMIC_ID: AtomicUsize = AtomicUsize::new(0);
@@ -1344,12 +1641,21 @@ async fn run(args: Args) -> anyhow::Result<()> {
     }));
 
     info!("Node version : {}", *VERSION);
+    if let Some(profile) = &SETTINGS.network_profile {
+        info!("Network profile : {}", profile.name);
+    }
+    check_network_profile()?;
+    for issue in check_config() {
+        warn!("configuration: {}", issue);
+    }
+
+    if cur_args.migrate_secrets {
+        return migrate_file_secrets();
+    }
 
     // load or create wallet, asking for password if necessary
-    let node_wallet = load_wallet(
-        cur_args.password.clone(),
-        &SETTINGS.factory.staking_wallet_path,
-    )?;
+    let secret_stores = load_secret_stores()?;
+    let node_wallet = load_wallet(cur_args.password.clone(), secret_stores.staking.clone())?;
 
     // interrupt signal listener
     let sig_int_toggled = Arc::new((Mutex::new(false), Condvar::new()));
@@ -1384,7 +1690,14 @@ async fn run(args: Args) -> anyhow::Result<()> {
             grpc_public_handle,
             metrics_stopper,
             massa_survey_stopper,
-        ) = launch(&cur_args, node_wallet.clone(), Arc::clone(&sig_int_toggled)).await;
+            massa_verifier_stopper,
+        ) = launch(
+            &cur_args,
+            node_wallet.clone(),
+            &secret_stores,
+            Arc::clone(&sig_int_toggled),
+        )
+        .await;
 
         // loop over messages
         let restart = loop {
@@ -1398,6 +1711,12 @@ async fn run(args: Args) -> anyhow::Result<()> {
                     ConsensusEvent::Stop => {
                         break false;
                     }
+                    ConsensusEvent::FinalityStall(report) => {
+                        warn!("finality stall detected: {}", report);
+                    }
+                    ConsensusEvent::DeepReorg(report) => {
+                        warn!("deep reorganization held, block production is paused: {}. Confirm it with the node_confirm_reorg private API call, or bootstrap again", report);
+                    }
                 },
                 Err(TryRecvError::Disconnected) => {
                     error!("consensus_event_receiver.wait_event disconnected");
@@ -1451,6 +1770,7 @@ async fn run(args: Args) -> anyhow::Result<()> {
             grpc_public_handle,
             metrics_stopper,
             massa_survey_stopper,
+            massa_verifier_stopper,
         )
         .await;
 
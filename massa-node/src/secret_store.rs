@@ -0,0 +1,142 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Selection of the secret stores holding the node key and the staking keys
+
+use crate::settings::{SecretStoreBackend, SETTINGS};
+use anyhow::anyhow;
+use massa_signature::KeyPair;
+use massa_time::MassaTime;
+use massa_versioning::{
+    keypair_factory::KeyPairFactory,
+    versioning::MipStore,
+    versioning_factory::{FactoryStrategy, VersioningFactory},
+};
+use massa_wallet::{migrate_secrets, FileSecretStore, SecretStore};
+use std::{path::PathBuf, sync::Arc};
+use tracing::{info, warn};
+
+/// Secret stores of the node
+#[derive(Debug, Clone)]
+pub struct NodeSecretStores {
+    /// store holding the node key
+    pub node: Arc<dyn SecretStore>,
+    /// name of the node key in the node store
+    pub node_key_name: String,
+    /// store holding the staking keys
+    pub staking: Arc<dyn SecretStore>,
+}
+
+/// Name of the node key in the non-file backends
+const NODE_KEY_NAME: &str = "node_privkey";
+
+/// File stores: the node key is the `protocol.keypair_file` file
+/// and the staking keys are the files of the `factory.staking_wallet_path` directory
+fn file_secret_stores() -> anyhow::Result<NodeSecretStores> {
+    let keypair_file = &SETTINGS.protocol.keypair_file;
+    let node_key_name = keypair_file
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow!("invalid node key file: {}", keypair_file.display()))?
+        .to_string();
+    let node_dir = match keypair_file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    Ok(NodeSecretStores {
+        node: Arc::new(FileSecretStore::new(node_dir)),
+        node_key_name,
+        staking: Arc::new(FileSecretStore::new(
+            SETTINGS.factory.staking_wallet_path.clone(),
+        )),
+    })
+}
+
+/// Builds the secret stores of the configured backend
+pub fn load_secret_stores() -> anyhow::Result<NodeSecretStores> {
+    let settings = &SETTINGS.secret_store;
+    match settings.backend {
+        SecretStoreBackend::File => file_secret_stores(),
+        #[cfg(feature = "keychain")]
+        SecretStoreBackend::Keychain => Ok(NodeSecretStores {
+            node: Arc::new(massa_wallet::KeychainSecretStore::new(format!(
+                "{}.node",
+                settings.keychain_service
+            ))),
+            node_key_name: NODE_KEY_NAME.to_string(),
+            staking: Arc::new(massa_wallet::KeychainSecretStore::new(format!(
+                "{}.staking_wallets",
+                settings.keychain_service
+            ))),
+        }),
+        #[cfg(feature = "vault")]
+        SecretStoreBackend::Vault => {
+            let token = std::env::var(&settings.vault_token_env).map_err(|_| {
+                anyhow!(
+                    "the vault token must be set in the {} environment variable",
+                    settings.vault_token_env
+                )
+            })?;
+            let store = |namespace: &str| {
+                Arc::new(massa_wallet::VaultSecretStore::new(
+                    settings.vault_address.clone(),
+                    settings.vault_mount.clone(),
+                    format!("{}/{}", settings.vault_prefix, namespace),
+                    token.clone(),
+                ))
+            };
+            Ok(NodeSecretStores {
+                node: store("node"),
+                node_key_name: NODE_KEY_NAME.to_string(),
+                staking: store("staking_wallets"),
+            })
+        }
+        #[allow(unreachable_patterns)]
+        backend => Err(anyhow!(
+            "the {:?} secret store backend is not enabled in this build",
+            backend
+        )),
+    }
+}
+
+/// Loads the node key from its store, or creates it and saves it if there is none
+pub fn load_node_keypair(
+    stores: &NodeSecretStores,
+    mip_store: &MipStore,
+) -> anyhow::Result<KeyPair> {
+    if let Some(keypair) = stores.node.get_secret(&stores.node_key_name)? {
+        return serde_json::from_slice::<KeyPair>(&keypair)
+            .map_err(|err| anyhow!("could not load node key: {}", err));
+    }
+    let keypair_factory = KeyPairFactory {
+        mip_store: mip_store.clone(),
+    };
+    let keypair = keypair_factory.create(&(), FactoryStrategy::At(MassaTime::now()))?;
+    if let Err(e) = stores.node.set_secret(
+        &stores.node_key_name,
+        serde_json::to_string(&keypair)?.as_bytes(),
+    ) {
+        warn!("could not save node key: {}", e);
+    }
+    Ok(keypair)
+}
+
+/// Copies the node key and the staking keys of the file stores into the configured backend
+pub fn migrate_file_secrets() -> anyhow::Result<()> {
+    if SETTINGS.secret_store.backend == SecretStoreBackend::File {
+        return Err(anyhow!(
+            "secrets can only be migrated to a keychain or vault secret store backend"
+        ));
+    }
+    let from = file_secret_stores()?;
+    let to = load_secret_stores()?;
+    match from.node.get_secret(&from.node_key_name)? {
+        Some(keypair) => {
+            to.node.set_secret(&to.node_key_name, &keypair)?;
+            info!("migrated the node key");
+        }
+        None => warn!("no node key to migrate"),
+    }
+    let names = migrate_secrets(from.staking.as_ref(), to.staking.as_ref())?;
+    info!("migrated {} staking key(s)", names.len());
+    Ok(())
+}
@@ -4,14 +4,52 @@
 use std::{collections::HashMap, path::PathBuf};
 
 use massa_bootstrap::IpType;
-use massa_models::{config::build_massa_settings, node::NodeId};
-use massa_protocol_exports::PeerCategoryInfo;
+use massa_execution_exports::ExecutionTraceFormat;
+use massa_factory_exports::ProductionBlacklistWindow;
+use massa_models::{
+    address::AddressFormat, config::build_massa_settings_with_profile, node::NodeId,
+};
+use massa_protocol_exports::{
+    BandwidthBudget, MessageRateLimits, PeerCategoryInfo, Socks5ProxyConfig,
+};
 use massa_time::MassaTime;
 use serde::Deserialize;
 use std::net::{IpAddr, SocketAddr};
 
 lazy_static::lazy_static! {
-    pub static ref SETTINGS: Settings = build_massa_settings("massa-node", "MASSA_NODE");
+    pub static ref SETTINGS: Settings = build_massa_settings_with_profile(
+        "massa-node",
+        "MASSA_NODE",
+        get_network_profile_from_args().as_deref(),
+    );
+}
+
+/// Network profiles bundled in `base_config/networks`
+pub const NETWORK_PROFILES: [&str; 3] = ["mainnet", "buildnet", "sandbox"];
+
+/// Helper function to get the network profile file selected with the `--network` argument, for lazy_static evaluations
+pub fn get_network_profile_from_args() -> Option<PathBuf> {
+    let mut args = std::env::args();
+    let network = loop {
+        let arg = args.next()?;
+        if arg == "--network" {
+            break args.next()?;
+        }
+        if let Some(network) = arg.strip_prefix("--network=") {
+            break network.to_string();
+        }
+    };
+    Some(get_network_profile_path(&network))
+}
+
+/// Gets the file of a network profile: the file of a bundled profile if `network` is a profile name,
+/// otherwise `network` itself as a custom profile file
+pub fn get_network_profile_path(network: &str) -> PathBuf {
+    if NETWORK_PROFILES.contains(&network) {
+        PathBuf::from(format!("base_config/networks/{}.toml", network))
+    } else {
+        PathBuf::from(network)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -22,10 +60,15 @@ pub struct LoggingSettings {
 #[derive(Clone, Debug, Deserialize)]
 pub struct ExecutionSettings {
     pub max_final_events: usize,
+    pub max_recent_operation_results: usize,
     pub readonly_queue_length: usize,
     pub cursor_delay: MassaTime,
+    pub max_candidate_execution_lag: u64,
+    /// execute candidate slots, can be disabled on nodes that only serve final data
+    pub candidate_execution_enabled: bool,
     pub stats_time_window_duration: MassaTime,
     pub max_read_only_gas: u64,
+    pub readonly_call_cache_size: u32,
     pub abi_gas_costs_file: PathBuf,
     pub wasm_gas_costs_file: PathBuf,
     pub hd_cache_path: PathBuf,
@@ -34,6 +77,27 @@ pub struct ExecutionSettings {
     pub snip_amount: usize,
     /// slot execution outputs channel capacity
     pub broadcast_slot_execution_output_channel_capacity: usize,
+    /// record and dump slot execution traces
+    pub execution_trace_enabled: bool,
+    /// directory of the slot execution traces, named `{period}_{thread}_{final|candidate}`
+    pub execution_trace_path: PathBuf,
+    /// serialization format of the slot execution traces
+    pub execution_trace_format: ExecutionTraceFormat,
+    /// periodically checkpoint the execution state to avoid re-executing on restart
+    pub execution_checkpoint_interval: u64,
+    pub execution_checkpoint_path: PathBuf,
+    /// store final events on disk with secondary indexes
+    pub event_index_enabled: bool,
+    pub event_index_path: PathBuf,
+    pub max_indexed_events: usize,
+    /// record per-contract datastore access statistics
+    pub contract_io_stats_enabled: bool,
+    /// retention windows of the final execution outputs, per class of consumers
+    pub api_output_retention_slots: u64,
+    pub stream_output_retention_slots: u64,
+    pub finality_output_retention_slots: u64,
+    /// number of final slots whose final state hash is kept
+    pub final_state_hash_history_length: usize,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -47,12 +111,14 @@ pub struct LedgerSettings {
     pub disk_ledger_path: PathBuf,
     pub final_history_length: usize,
     pub initial_deferred_credits_path: Option<PathBuf>,
+    pub state_write_buffer_size: usize,
 }
 
 /// Bootstrap configuration.
 #[derive(Debug, Deserialize, Clone)]
 pub struct BootstrapSettings {
     pub bootstrap_list: Vec<(SocketAddr, NodeId)>,
+    pub bootstrap_hostname_list: Vec<(String, NodeId)>,
     pub bootstrap_protocol: IpType,
     pub bootstrap_whitelist_path: PathBuf,
     pub bootstrap_blacklist_path: PathBuf,
@@ -76,6 +142,7 @@ pub struct BootstrapSettings {
 
 /// Factory settings
 #[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(not(feature = "factory"), allow(dead_code))]
 pub struct FactorySettings {
     /// Initial delay
     pub initial_delay: MassaTime,
@@ -83,6 +150,13 @@ pub struct FactorySettings {
     pub staking_wallet_path: PathBuf,
     /// stop the production in case we are not connected to anyone
     pub stop_production_when_zero_connections: bool,
+    /// windows during which the node must not produce blocks nor endorsements
+    #[serde(default)]
+    pub production_blacklist: Vec<ProductionBlacklistWindow>,
+    /// file keeping the revenue of the produced blocks and endorsements
+    pub operator_revenue_path: PathBuf,
+    /// maximum number of produced blocks and endorsements whose revenue is kept
+    pub operator_revenue_history_length: usize,
 }
 
 /// Pool configuration, read from a file configuration
@@ -119,8 +193,15 @@ pub struct APISettings {
     pub ping_interval: MassaTime,
     pub enable_http: bool,
     pub enable_ws: bool,
+    // whether to add the short form of ids to block, operation and address infos
+    pub enable_short_ids: bool,
     // whether to broadcast for blocks, endorsement and operations
     pub enable_broadcast: bool,
+    pub max_events_response_size: usize,
+    pub max_block_summaries_response_size: usize,
+    pub max_cpu_profile_duration: MassaTime,
+    // format of the addresses in the HTTP API responses: base58check or bech32m
+    pub address_format: AddressFormat,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -139,6 +220,9 @@ pub struct Settings {
     pub grpc: GrpcApiSettings,
     pub metrics: MetricsSettings,
     pub versioning: VersioningSettings,
+    pub secret_store: SecretStoreSettings,
+    pub network_profile: Option<NetworkProfileSettings>,
+    pub verifier: VerifierSettings,
 }
 
 /// Consensus configuration
@@ -149,6 +233,8 @@ pub struct ConsensusSettings {
     pub max_discarded_blocks: usize,
     /// Maximum number of blocks allowed in `FutureIncomingBlocks`.
     pub max_future_processing_blocks: usize,
+    /// number of threads checking in parallel the draws of the headers processed at each slot (1 to check them sequentially)
+    pub draw_check_threads: usize,
     /// Maximum number of blocks allowed in `DependencyWaitingBlocks`.
     pub max_dependency_blocks: usize,
     /// stats time span
@@ -165,6 +251,22 @@ pub struct ConsensusSettings {
     pub broadcast_blocks_channel_capacity: usize,
     /// filled blocks channel capacity
     pub broadcast_filled_blocks_channel_capacity: usize,
+    /// final periods channel capacity
+    pub broadcast_final_periods_channel_capacity: usize,
+    /// fork choice events channel capacity
+    pub broadcast_fork_choice_channel_capacity: usize,
+    /// duration without finality progress after which a finality stall is reported (0 disables detection)
+    pub finality_stall_timeout: MassaTime,
+    /// bootstrap again automatically when a finality stall looks caused by the node being on a fork
+    pub finality_stall_auto_resync: bool,
+    /// maximum number of executed non-final blockclique blocks a reorganization may discard without operator confirmation (0 disables the guard)
+    pub max_reorg_depth: u64,
+    /// bootstrap again automatically instead of waiting for operator confirmation when a reorganization is held
+    pub max_reorg_auto_resync: bool,
+    /// archive the final blocks with their operations before they are pruned from the block graph
+    pub block_archive_enabled: bool,
+    /// file to which the archived blocks are appended, one JSON object per line
+    pub block_archive_path: PathBuf,
 }
 
 // TODO: Remove one date. Kept for retro compatibility.
@@ -184,6 +286,22 @@ pub struct MetricsSettings {
     pub tick_delay: MassaTime,
 }
 
+/// Verifier mode settings
+#[derive(Debug, Deserialize, Clone)]
+pub struct VerifierSettings {
+    /// run the node as a verifier: no block production and no operation submission,
+    /// the final state hashes are compared with the ones of the reference nodes
+    pub enabled: bool,
+    /// URLs of the public JSON-RPC API of the reference nodes
+    pub reference_nodes: Vec<String>,
+    /// interval at which the final state hashes are compared
+    pub poll_interval: MassaTime,
+    /// timeout of the requests to the reference nodes and to the webhook
+    pub request_timeout: MassaTime,
+    /// URL to which the divergence alerts are posted, if any
+    pub webhook_url: Option<String>,
+}
+
 /// Protocol Configuration, read from toml user configuration file
 #[derive(Debug, Deserialize, Clone)]
 pub struct ProtocolSettings {
@@ -195,6 +313,36 @@ pub struct ProtocolSettings {
     pub max_block_propagation_time: MassaTime,
     /// Block propagation tick interval, useful for propagating blocks quickly to newly connected peers.
     pub block_propagation_tick: MassaTime,
+    /// Announce blocks as compact blocks (header, operation short IDs and the operations the peer may miss)
+    /// instead of bare headers
+    pub compact_block_relay: bool,
+    /// Fetch the missing operations of a block as erasure-coded chunks of its body from several peers,
+    /// instead of asking a single peer for them
+    pub block_body_chunking: bool,
+    /// Number of data chunks a block body is split into
+    pub block_body_data_chunks: usize,
+    /// Number of parity chunks added to the data chunks of a block body
+    pub block_body_parity_chunks: usize,
+    /// Minimum number of missing operations of a block for its body to be fetched as chunks
+    pub block_body_chunking_min_missing_operations: usize,
+    /// Negotiate zstd compression of the large messages with the peers during the handshake
+    pub message_compression: bool,
+    /// Messages larger than this size (in bytes) are compressed for the peers that negotiated compression
+    pub message_compression_threshold: usize,
+    /// zstd compression level
+    pub message_compression_level: i32,
+    /// Maximum size of a decompressed message, protecting against decompression bombs
+    pub max_decompressed_message_size: usize,
+    /// Rate limits of the messages received from the peers of the default category
+    pub message_rate_limits: MessageRateLimits,
+    /// Rate limits of the messages received from the peers, by peer category
+    pub peers_categories_message_rate_limits: HashMap<String, MessageRateLimits>,
+    /// Bandwidth budget of the peers of the default category
+    pub bandwidth_budget: BandwidthBudget,
+    /// Bandwidth budgets of the peers, by peer category
+    pub peers_categories_bandwidth_budgets: HashMap<String, BandwidthBudget>,
+    /// SOCKS5 proxy through which the outbound peer and bootstrap connections are made, if any
+    pub socks5_proxy: Option<Socks5ProxyConfig>,
     /// max known blocks our node keeps in its knowledge cache
     pub max_known_blocks_size: usize,
     /// max cache size for which blocks a foreign node knows about
@@ -205,6 +353,16 @@ pub struct ProtocolSettings {
     pub max_known_ops_size: usize,
     /// size of the buffer of asked operations
     pub asked_operations_buffer_capacity: usize,
+    /// Maximum estimated size in bytes of the operations asked to a single peer per `operation_batch_proc_period`
+    pub max_ask_operations_size_per_peer: u64,
+    /// Reputation penalty above which a misbehaving peer is disconnected
+    pub peer_reputation_deprioritize_threshold: u64,
+    /// Reputation penalty above which a misbehaving peer is banned
+    pub peer_reputation_ban_threshold: u64,
+    /// Time needed by a peer to recover one point of reputation penalty
+    pub peer_reputation_recovery_period: MassaTime,
+    /// connection events channel capacity
+    pub broadcast_connection_events_channel_capacity: usize,
     /// max known operations of foreign nodes we keep in memory (by node)
     pub max_node_known_ops_size: usize,
     /// max known endorsements by our node that we kept in memory
@@ -213,6 +371,8 @@ pub struct ProtocolSettings {
     pub max_node_known_endorsements_size: usize,
     /// we ask for the same block `max_simultaneous_ask_blocks_per_node` times at the same time
     pub max_simultaneous_ask_blocks_per_node: usize,
+    /// max number of blocks whose operations are being downloaded at the same time, once their header is known
+    pub max_block_bodies_in_flight: usize,
     /// Max wait time for sending a Network or Node event.
     pub max_send_wait: MassaTime,
     /// Maximum number of batches in the memory buffer.
@@ -231,8 +391,28 @@ pub struct ProtocolSettings {
     pub max_ops_kept_for_propagation: usize,
     /// Time threshold after which operation are not propagated
     pub max_operations_propagation_time: MassaTime,
+    /// Interval at which the digest of the recently received operations is sent to the peers
+    pub operation_inventory_digest_interval: MassaTime,
+    /// Max number of the most recent operations included in an inventory digest, 0 to disable the digests
+    pub max_operations_per_inventory_digest: usize,
+    /// Interval at which the ids of the payloads kept for each topic are sent to the peers
+    pub topic_anti_entropy_interval: MassaTime,
+    /// Maximum size of a topic payload
+    pub max_topic_payload_size: usize,
+    /// Maximum number of payloads or payload ids in a topic message
+    pub max_topic_items_per_message: u64,
+    /// Number of threads verifying the signatures of the received operations, 0 for one per CPU core
+    pub operation_sig_verification_threads: usize,
+    /// Number of received operations whose signatures are verified together by a thread
+    pub operation_sig_verification_batch_size: usize,
+    /// Number of the latest final state hashes announced to the peers during the handshake
+    pub handshake_final_state_hashes: usize,
+    /// Refuse the peers whose announced final state hashes differ from ours
+    pub refuse_divergent_peers: bool,
     /// Time threshold after which operation are not propagated
     pub max_endorsements_propagation_time: MassaTime,
+    /// Maximum time by which the slot of a received header or endorsement can be ahead of the current time
+    pub max_future_slot_time: MassaTime,
     /// Path for initial peers
     pub initial_peers_file: PathBuf,
     /// Keypair
@@ -269,11 +449,38 @@ pub struct ProtocolSettings {
     pub test_oldest_peer_cooldown: MassaTime,
     /// Rate limitation to apply to the data stream (per second)
     pub rate_limit: u64,
+    /// File to capture inbound protocol messages to, for later replay (disabled if absent)
+    pub traffic_capture_path: Option<PathBuf>,
+    /// File to save the recently checked operation prefixes to on stop, reloaded on start (disabled if absent)
+    pub operation_seen_cache_path: Option<PathBuf>,
+    /// Maximum age of the saved checked operation prefixes
+    pub operation_seen_cache_max_age: MassaTime,
+    /// RocksDB database keeping the liveness history of the peers across restarts (disabled if absent)
+    pub peer_store_path: Option<PathBuf>,
+    /// Interval between two flushes of the peer records to the peer store
+    pub peer_store_flush_interval: MassaTime,
+    /// Age after which the peers that were not seen are evicted from the peer store
+    pub peer_store_max_age: MassaTime,
+    /// Map the listener ports on the router with UPnP or NAT-PMP and advertise its public IP
+    pub port_mapping: bool,
+    /// Lease duration of the port mappings
+    pub port_mapping_lease_duration: MassaTime,
+    /// Timeout of the port mapping requests
+    pub port_mapping_timeout: MassaTime,
+    /// Maximum number of outbound connections of the default category to a network group
+    pub outbound_max_per_group: usize,
+    /// Interval between two rotations of the outbound connections
+    pub outbound_rotation_interval: MassaTime,
+    /// Percentage of the outbound connections closed at each rotation
+    pub outbound_rotation_percent: u64,
+    /// Path of the prefix-to-ASN map used to group the peers
+    pub outbound_asn_map_path: Option<PathBuf>,
 }
 
 /// gRPC settings
 /// the gRPC settings
 #[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(not(feature = "grpc"), allow(dead_code))]
 pub struct GrpcSettings {
     /// whether to enable gRPC
     pub enabled: bool,
@@ -374,6 +581,46 @@ pub struct GrpcApiSettings {
 pub struct VersioningSettings {
     // Warn user to update its node if we reach this percentage for announced network versions
     pub(crate) mip_stats_warn_announced_version: u32,
+    // Private networks only: file defining the MIP list, used instead of the list built in the node
+    pub(crate) mip_list_path: Option<PathBuf>,
+}
+
+/// Network profile, read from the profile file selected with the `--network` argument
+#[derive(Debug, Deserialize, Clone)]
+pub struct NetworkProfileSettings {
+    /// name of the network
+    pub name: String,
+    /// whether the network requires a node built with the `sandbox` feature
+    pub sandbox: bool,
+}
+
+/// Backend holding the node key and the staking keys
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretStoreBackend {
+    /// files on the local disk
+    File,
+    /// keychain of the operating system
+    Keychain,
+    /// HashiCorp Vault KV v2 secrets engine
+    Vault,
+}
+
+/// Secret store configuration, read from toml user configuration file
+#[derive(Debug, Deserialize, Clone)]
+pub struct SecretStoreSettings {
+    /// backend holding the node key and the staking keys
+    pub backend: SecretStoreBackend,
+    /// keychain service under which the secrets are stored
+    pub keychain_service: String,
+    /// address of the vault server
+    pub vault_address: String,
+    /// mount path of the KV v2 secrets engine
+    pub vault_mount: String,
+    /// path prefix of the secrets in the secrets engine
+    pub vault_prefix: String,
+    /// environment variable holding the vault token
+    pub vault_token_env: String,
 }
 
 #[cfg(test)]
@@ -381,3 +628,30 @@ pub struct VersioningSettings {
 fn test_load_node_config() {
     let _ = *SETTINGS;
 }
+
+#[cfg(test)]
+#[test]
+fn test_load_network_profiles() {
+    let default_settings = &*SETTINGS;
+    for network in NETWORK_PROFILES {
+        let settings: Settings = build_massa_settings_with_profile(
+            "massa-node",
+            "MASSA_NODE",
+            Some(&get_network_profile_path(network)),
+        );
+        let profile = settings
+            .network_profile
+            .expect("the profile does not name its network");
+        assert_eq!(profile.name, network);
+        assert_eq!(profile.sandbox, network == "sandbox");
+        // only mainnet shares the storage of the default configuration
+        assert_eq!(
+            settings.ledger.disk_ledger_path == default_settings.ledger.disk_ledger_path,
+            network == "mainnet"
+        );
+    }
+    assert_eq!(
+        get_network_profile_path("config/custom_network.toml"),
+        PathBuf::from("config/custom_network.toml")
+    );
+}
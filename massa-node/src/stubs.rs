@@ -0,0 +1,74 @@
+//! Stand-ins for the subsystems left out of minimal builds.
+//!
+//! Nodes only serving RPC requests or indexing the chain can be built without the `factory`,
+//! `serve_bootstrap` and `grpc` features. The corresponding subsystems are then never started,
+//! as if they were disabled in the settings, and the settings enabling them are ignored with a warning.
+
+#[cfg(not(feature = "serve_bootstrap"))]
+use massa_bootstrap::{BootstrapConfig, BootstrapManager};
+#[cfg(not(feature = "factory"))]
+use massa_factory_exports::FactoryManager;
+
+#[cfg(not(all(feature = "factory", feature = "grpc")))]
+use crate::settings::SETTINGS;
+
+/// Factory of nodes built without the `factory` feature: blocks and endorsements are never produced
+#[cfg(not(feature = "factory"))]
+pub(crate) fn factory_manager() -> Option<Box<dyn FactoryManager>> {
+    if !SETTINGS.verifier.enabled {
+        tracing::info!(
+            "node built without the factory feature: block and endorsement production is disabled"
+        );
+    }
+    None
+}
+
+/// Bootstrap server of nodes built without the `serve_bootstrap` feature: other nodes cannot bootstrap from them
+#[cfg(not(feature = "serve_bootstrap"))]
+pub(crate) fn bootstrap_manager(config: &BootstrapConfig) -> Option<BootstrapManager> {
+    if let Some(addr) = config.listen_addr {
+        tracing::warn!(
+            "node built without the serve_bootstrap feature: ignoring bootstrap listen address {}",
+            addr
+        );
+    }
+    None
+}
+
+/// Stop handle of the gRPC servers of nodes built without the `grpc` feature, that are never started
+#[cfg(not(feature = "grpc"))]
+pub(crate) enum GrpcStopHandle {}
+
+#[cfg(not(feature = "grpc"))]
+impl GrpcStopHandle {
+    pub(crate) fn stop(self) {
+        match self {}
+    }
+}
+
+/// gRPC servers (public, private) of nodes built without the `grpc` feature
+#[cfg(not(feature = "grpc"))]
+pub(crate) fn grpc_handles() -> (Option<GrpcStopHandle>, Option<GrpcStopHandle>) {
+    if SETTINGS.grpc.public.enabled || SETTINGS.grpc.private.enabled {
+        tracing::warn!("node built without the grpc feature: ignoring the gRPC API settings");
+    }
+    (None, None)
+}
+
+/// These tests only run in minimal builds: `cargo test -p massa-node --no-default-features`
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "factory"))]
+    #[test]
+    fn test_factory_never_started() {
+        assert!(super::factory_manager().is_none());
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    #[test]
+    fn test_grpc_never_started() {
+        let (public_handle, private_handle) = super::grpc_handles();
+        assert!(public_handle.is_none());
+        assert!(private_handle.is_none());
+    }
+}
@@ -0,0 +1,204 @@
+//! Verifier mode: the node follows the finalized blocks and re-executes them without producing blocks,
+//! and periodically compares the final state hashes it computed with the ones of reference nodes.
+//! Each divergence is logged and posted to the configured webhook.
+
+use std::{collections::HashMap, thread::JoinHandle};
+
+use crossbeam_channel::{select, tick};
+use massa_api_exports::execution::FinalStateHashEntry;
+use massa_channel::{sender::MassaSender, MassaChannel};
+use massa_execution_exports::ExecutionController;
+use massa_hash::Hash;
+use massa_models::slot::Slot;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::settings::VerifierSettings;
+
+/// Divergence between the final state hash computed by the node and the one of a reference node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StateHashDivergence {
+    /// URL of the reference node
+    pub reference_node: String,
+    /// SCE-final slot
+    pub slot: Slot,
+    /// final state hash computed by the node
+    pub local_hash: Hash,
+    /// final state hash of the reference node
+    pub reference_hash: Hash,
+}
+
+/// Compares the final state hashes of the slots known by both the node and a reference node.
+/// Returns the latest compared slot, if any, and the slots whose hashes differ with the local and reference hashes.
+fn compare_final_state_hashes(
+    local: &[(Slot, Hash)],
+    reference: &[FinalStateHashEntry],
+) -> (Option<Slot>, Vec<(Slot, Hash, Hash)>) {
+    let local: HashMap<Slot, Hash> = local.iter().copied().collect();
+    let mut last_compared = None;
+    let mut mismatches = Vec::new();
+    for entry in reference {
+        let Some(local_hash) = local.get(&entry.slot) else {
+            continue;
+        };
+        if *local_hash != entry.final_state_hash {
+            mismatches.push((entry.slot, *local_hash, entry.final_state_hash));
+        }
+        last_compared = last_compared.max(Some(entry.slot));
+    }
+    (last_compared, mismatches)
+}
+
+/// Fetches the final state hashes of the slots after `after` from the public JSON-RPC API of a reference node
+fn fetch_final_state_hashes(
+    agent: &ureq::Agent,
+    reference_node: &str,
+    after: Option<Slot>,
+) -> Result<Vec<FinalStateHashEntry>, String> {
+    let response: serde_json::Value = agent
+        .post(reference_node)
+        .send_json(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "get_final_state_hashes",
+            "params": [after],
+        }))
+        .map_err(|err| err.to_string())?
+        .into_json()
+        .map_err(|err| err.to_string())?;
+    if let Some(error) = response.get("error") {
+        return Err(error.to_string());
+    }
+    serde_json::from_value(response["result"].clone()).map_err(|err| err.to_string())
+}
+
+pub struct MassaVerifier {}
+
+pub struct MassaVerifierStopper {
+    tx_stopper: Option<MassaSender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MassaVerifierStopper {
+    pub fn stop(&mut self) {
+        if let Some(tx) = self.tx_stopper.take() {
+            info!("MassaVerifier | Stopping");
+            if let Err(e) = tx.send(()) {
+                warn!(
+                    "failed to send stop signal to massa verifier thread: {:?}",
+                    e
+                );
+            }
+        }
+        if let Some(handle) = self.handle.take() {
+            match handle.join() {
+                Ok(_) => info!("MassaVerifier | Stopped"),
+                Err(_) => warn!("failed to join massa verifier thread"),
+            }
+        }
+    }
+}
+
+impl MassaVerifier {
+    pub fn run(
+        settings: VerifierSettings,
+        execution_controller: Box<dyn ExecutionController>,
+    ) -> MassaVerifierStopper {
+        if !settings.enabled {
+            return MassaVerifierStopper {
+                tx_stopper: None,
+                handle: None,
+            };
+        }
+        if settings.reference_nodes.is_empty() {
+            warn!("MassaVerifier | no reference node configured, the final state hashes will not be compared");
+        }
+        let (tx_stop, rx_stop) = MassaChannel::new("massa_verifier_stop".to_string(), Some(1));
+        let poll_tick = tick(settings.poll_interval.to_duration());
+        let agent = ureq::AgentBuilder::new()
+            .timeout(settings.request_timeout.to_duration())
+            .build();
+        // latest slot compared with each reference node
+        let mut last_compared: HashMap<String, Slot> = HashMap::new();
+        match std::thread::Builder::new()
+            .name("massa-verifier".to_string())
+            .spawn(move || loop {
+                select! {
+                    recv(rx_stop) -> _ => {
+                        break;
+                    },
+                    recv(poll_tick) -> _ => {
+                        let local = execution_controller.get_final_state_hashes(None);
+                        for reference_node in &settings.reference_nodes {
+                            let after = last_compared.get(reference_node).copied();
+                            let reference = match fetch_final_state_hashes(&agent, reference_node, after) {
+                                Ok(reference) => reference,
+                                Err(err) => {
+                                    warn!("MassaVerifier | failed to get the final state hashes of {}: {}", reference_node, err);
+                                    continue;
+                                }
+                            };
+                            let (compared, mismatches) = compare_final_state_hashes(&local, &reference);
+                            if let Some(slot) = compared {
+                                last_compared.insert(reference_node.clone(), slot);
+                            }
+                            for (slot, local_hash, reference_hash) in mismatches {
+                                let divergence = StateHashDivergence {
+                                    reference_node: reference_node.clone(),
+                                    slot,
+                                    local_hash,
+                                    reference_hash,
+                                };
+                                warn!("MassaVerifier | final state divergence at slot {} with {}: local hash {}, reference hash {}", slot, reference_node, local_hash, reference_hash);
+                                if let Some(webhook_url) = &settings.webhook_url {
+                                    if let Err(err) = agent.post(webhook_url).send_json(&divergence) {
+                                        warn!("MassaVerifier | failed to post the divergence alert: {}", err);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }) {
+            Ok(handle) => MassaVerifierStopper {
+                tx_stopper: Some(tx_stop),
+                handle: Some(handle),
+            },
+            Err(e) => {
+                warn!("failed to spawn massa verifier thread: {:?}", e);
+                MassaVerifierStopper {
+                    tx_stopper: None,
+                    handle: None,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_final_state_hashes() {
+        let hash = |period: u64| Hash::compute_from(&period.to_be_bytes());
+        let local: Vec<(Slot, Hash)> = (3..=6)
+            .map(|period| (Slot::new(period, 0), hash(period)))
+            .collect();
+        // the reference node is ahead of the node and diverges at period 5
+        let reference: Vec<FinalStateHashEntry> = (1..=8)
+            .map(|period| FinalStateHashEntry {
+                slot: Slot::new(period, 0),
+                final_state_hash: if period == 5 { hash(0) } else { hash(period) },
+            })
+            .collect();
+        let (compared, mismatches) = compare_final_state_hashes(&local, &reference);
+        assert_eq!(compared, Some(Slot::new(6, 0)));
+        assert_eq!(mismatches, vec![(Slot::new(5, 0), hash(5), hash(0))]);
+
+        // no slot in common
+        let (compared, mismatches) = compare_final_state_hashes(&local, &reference[..2]);
+        assert_eq!(compared, None);
+        assert!(mismatches.is_empty());
+    }
+}
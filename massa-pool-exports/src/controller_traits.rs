@@ -24,6 +24,9 @@ pub trait PoolController: Send + Sync {
     /// Add denunciation precursor to pool
     fn add_denunciation_precursor(&self, denunciation_precursor: DenunciationPrecursor);
 
+    /// Add already built denunciations to pool. They are verified before being accepted.
+    fn add_denunciations(&self, denunciations: Vec<Denunciation>);
+
     /// Asynchronously notify of new consensus final periods. Simply print a warning on failure.
     fn notify_final_cs_periods(&mut self, final_cs_periods: &[u64]);
 
@@ -0,0 +1,70 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+use massa_models::denunciation::{
+    DenunciationError, VersionedDenunciation, BLOCK_HEADER_EQUIVOCATION_KIND,
+    ENDORSEMENT_EQUIVOCATION_KIND,
+};
+
+/// Verifier of the proofs of a kind of misbehavior,
+/// used by the denunciation pool before accepting versioned denunciations.
+///
+/// Supporting a new kind of misbehavior consists in implementing this trait
+/// and adding the verifier to `default_denunciation_verifiers`.
+pub trait DenunciationVerifier: Send + Sync {
+    /// Kind of misbehavior whose proofs are verified
+    fn kind(&self) -> u32;
+
+    /// Verifies the proof of a denunciation of this kind
+    fn verify(&self, denunciation: &VersionedDenunciation) -> Result<(), DenunciationError>;
+}
+
+/// Verifier of the equivocation proofs:
+/// several block headers (or endorsements) signed by the same staker for the same draw
+pub struct EquivocationVerifier {
+    kind: u32,
+}
+
+impl EquivocationVerifier {
+    /// Verifier of the block header equivocation proofs
+    pub fn block_header() -> Self {
+        Self {
+            kind: BLOCK_HEADER_EQUIVOCATION_KIND,
+        }
+    }
+
+    /// Verifier of the endorsement equivocation proofs
+    pub fn endorsement() -> Self {
+        Self {
+            kind: ENDORSEMENT_EQUIVOCATION_KIND,
+        }
+    }
+}
+
+impl DenunciationVerifier for EquivocationVerifier {
+    fn kind(&self) -> u32 {
+        self.kind
+    }
+
+    fn verify(&self, denunciation: &VersionedDenunciation) -> Result<(), DenunciationError> {
+        if denunciation.kind != self.kind {
+            return Err(DenunciationError::InvalidInput(format!(
+                "expected misbehavior kind {}, got {}",
+                self.kind, denunciation.kind
+            )));
+        }
+        if !denunciation.to_equivocation()?.is_valid() {
+            return Err(DenunciationError::InvalidInput(
+                "invalid equivocation proof".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Verifiers of the kinds of misbehavior supported by the node
+pub fn default_denunciation_verifiers() -> Vec<Box<dyn DenunciationVerifier>> {
+    vec![
+        Box::new(EquivocationVerifier::block_header()),
+        Box::new(EquivocationVerifier::endorsement()),
+    ]
+}
@@ -8,10 +8,14 @@
 mod channels;
 mod config;
 mod controller_traits;
+mod denunciation_verifier;
 
 pub use channels::{PoolBroadcasts, PoolChannels};
 pub use config::PoolConfig;
 pub use controller_traits::{PoolController, PoolManager};
+pub use denunciation_verifier::{
+    default_denunciation_verifiers, DenunciationVerifier, EquivocationVerifier,
+};
 
 #[cfg(feature = "test-exports")]
 pub use controller_traits::{MockPoolController, MockPoolControllerWrapper};
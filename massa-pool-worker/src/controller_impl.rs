@@ -25,6 +25,8 @@ pub enum Command {
     AddItems(Storage),
     /// Add denunciation precursor to the pool
     AddDenunciationPrecursor(DenunciationPrecursor),
+    /// Add denunciations to the pool
+    AddDenunciations(Vec<Denunciation>),
     /// Notify of new final consensus periods
     NotifyFinalCsPeriods(Vec<u64>),
     /// Stop the worker
@@ -117,6 +119,22 @@ impl PoolController for PoolControllerImpl {
         }
     }
 
+    /// Add denunciations to pool
+    fn add_denunciations(&self, denunciations: Vec<Denunciation>) {
+        match self
+            .denunciations_input_sender
+            .try_send(Command::AddDenunciations(denunciations))
+        {
+            Err(TrySendError::Disconnected(_)) => {
+                warn!("Could not add denunciations to pool: worker is unreachable.");
+            }
+            Err(TrySendError::Full(_)) => {
+                warn!("Could not add denunciations to pool: worker channel is full.");
+            }
+            Ok(_) => {}
+        }
+    }
+
     /// Asynchronously notify of new final consensus periods. Simply print a warning on failure.
     fn notify_final_cs_periods(&mut self, final_cs_periods: &[u64]) {
         self.last_cs_final_periods = final_cs_periods.to_vec();
@@ -1,16 +1,19 @@
 //! Copyright (c) 2023 MASSA LABS <info@massa.net>
 
-use std::collections::{btree_map::Entry, BTreeMap};
+use std::collections::{btree_map::Entry, BTreeMap, HashMap};
 use tracing::debug;
 
 use massa_models::denunciation::DenunciationIndex;
 use massa_models::slot::Slot;
 use massa_models::{
     address::Address,
-    denunciation::{Denunciation, DenunciationPrecursor},
+    denunciation::{Denunciation, DenunciationError, DenunciationPrecursor},
     timeslots::get_closest_slot_to_timestamp,
 };
-use massa_pool_exports::{PoolChannels, PoolConfig};
+use massa_pool_exports::{
+    default_denunciation_verifiers, DenunciationVerifier, PoolChannels, PoolConfig,
+};
+use massa_signature::PublicKey;
 use massa_storage::Storage;
 use massa_time::MassaTime;
 
@@ -23,6 +26,8 @@ pub struct DenunciationPool {
     last_cs_final_periods: Vec<u64>,
     /// Internal cache for denunciations
     denunciations_cache: BTreeMap<DenunciationIndex, DenunciationStatus>,
+    /// Verifiers of the versioned denunciations, per misbehavior kind
+    verifiers: HashMap<u32, Box<dyn DenunciationVerifier>>,
 }
 
 impl DenunciationPool {
@@ -32,6 +37,10 @@ impl DenunciationPool {
             channels,
             last_cs_final_periods: vec![0u64; config.thread_count as usize],
             denunciations_cache: Default::default(),
+            verifiers: default_denunciation_verifiers()
+                .into_iter()
+                .map(|verifier| (verifier.kind(), verifier))
+                .collect(),
         }
     }
 
@@ -58,14 +67,70 @@ impl DenunciationPool {
     /// Add a denunciation precursor to the pool - can lead to a Denunciation creation
     /// Note that the Denunciation is stored in the denunciation pool internal cache
     pub fn add_denunciation_precursor(&mut self, denunciation_precursor: DenunciationPrecursor) {
-        let slot = denunciation_precursor.get_slot();
-
         // Do some checkups before adding the denunciation precursor
+        let index = match &denunciation_precursor {
+            DenunciationPrecursor::Endorsement(de_p) => Some(de_p.index),
+            DenunciationPrecursor::BlockHeader(_) => None,
+        };
+        if !self.is_slot_acceptable(denunciation_precursor.get_slot())
+            || !self.is_drawn(
+                denunciation_precursor.get_slot(),
+                index,
+                denunciation_precursor.get_public_key(),
+            )
+        {
+            return;
+        }
+
+        let key = DenunciationIndex::from(&denunciation_precursor);
+
+        let denunciation_: Option<Denunciation> = match self.denunciations_cache.entry(key) {
+            Entry::Occupied(mut eo) => match eo.get_mut() {
+                DenunciationStatus::Accumulating(de_p_) => {
+                    let de_p: &DenunciationPrecursor = de_p_;
+                    if *de_p != denunciation_precursor {
+                        match Denunciation::try_from((de_p, &denunciation_precursor)) {
+                            Ok(de) => {
+                                eo.insert(DenunciationStatus::DenunciationEmitted(de.clone()));
+                                Some(de)
+                            }
+                            Err(e) => {
+                                debug!("Denunciation pool cannot create denunciation from endorsements: {}", e);
+                                None
+                            }
+                        }
+                    } else {
+                        // same denunciation precursor - do nothing
+                        None
+                    }
+                }
+                DenunciationStatus::DenunciationEmitted(..) => {
+                    // Already 2 entries - so a Denunciation has already been created
+                    None
+                }
+            },
+            Entry::Vacant(ev) => {
+                ev.insert(DenunciationStatus::Accumulating(denunciation_precursor));
+                None
+            }
+        };
+
+        if let Some(denunciation) = denunciation_ {
+            debug!("Created a new denunciation : {:?}", denunciation);
+        }
 
+        // Because at the start of the function, we have already checked that DE precursor is not
+        // expired, there is no need to cleanup the cache here
+        // This is only needed when we are notified of new cs final periods and thus calling the
+        // cleanup function only when it is needed
+    }
+
+    /// Check that denunciations targeting the given slot can still be included in a block
+    fn is_slot_acceptable(&self, slot: &Slot) -> bool {
         if slot.period <= self.config.last_start_period {
             // denunciation created before last restart (can be 0 or >= 0 after a network restart) - ignored
             // Note: as we use '<=', also ignore denunciation created for genesis block
-            return;
+            return false;
         }
 
         let now = MassaTime::now();
@@ -87,101 +152,117 @@ impl DenunciationPool {
             &self.config.denunciation_expire_periods,
         ) {
             // too old - cannot be denounced anymore
-            return;
+            return false;
         }
 
         if slot.period.saturating_sub(slot_now.period) > self.config.denunciation_expire_periods {
             // too much in the future - ignored
-            return;
+            return false;
         }
 
-        // Get selected address from selector and check
-        // Note: If the public key of the header creator is not checked to match the PoS,
-        //       someone can spam with headers coming from various non-PoS-drawn pubkeys
-        //       and cause a problem
-        match &denunciation_precursor {
-            DenunciationPrecursor::Endorsement(de_p) => {
+        true
+    }
+
+    /// Check that the given public key was drawn to produce the block (index is None)
+    /// or the endorsement at the given index of the given slot
+    ///
+    /// Note: If the public key of the header creator is not checked to match the PoS,
+    ///       someone can spam with headers coming from various non-PoS-drawn pubkeys
+    ///       and cause a problem
+    fn is_drawn(&self, slot: &Slot, index: Option<u32>, public_key: &PublicKey) -> bool {
+        match index {
+            Some(index) => {
                 // Get selected address from selector and check
-                let selected = self.channels.selector.get_selection(de_p.slot);
+                let selected = self.channels.selector.get_selection(*slot);
                 match selected {
                     Ok(selection) => {
-                        if let Some(address) = selection.endorsements.get(de_p.index as usize) {
-                            let a = Address::from_public_key(&de_p.public_key);
+                        if let Some(address) = selection.endorsements.get(index as usize) {
+                            let a = Address::from_public_key(public_key);
                             if *address != a {
-                                debug!("Denunciation pool received a secure share endorsement but address was not selected: received {} but expected {} ({})", address, a, de_p.public_key);
-                                return;
+                                debug!("Denunciation pool received a secure share endorsement but address was not selected: received {} but expected {} ({})", address, a, public_key);
+                                return false;
                             }
                         } else {
                             debug!("Denunciation pool could not get selected address for endorsements at index");
-                            return;
+                            return false;
                         }
                     }
                     Err(e) => {
                         debug!("Cannot get producer from selector: {}", e);
-                        return;
+                        return false;
                     }
                 }
             }
-            DenunciationPrecursor::BlockHeader(de_p) => {
-                let selected_address = self.channels.selector.get_producer(de_p.slot);
+            None => {
+                let selected_address = self.channels.selector.get_producer(*slot);
                 match selected_address {
                     Ok(address) => {
-                        if address
-                            != Address::from_public_key(denunciation_precursor.get_public_key())
-                        {
+                        if address != Address::from_public_key(public_key) {
                             debug!("Denunciation pool received a secured header but address was not selected");
-                            return;
+                            return false;
                         }
                     }
                     Err(e) => {
                         debug!("Cannot get producer from selector: {}", e);
-                        return;
+                        return false;
                     }
                 }
             }
         }
+        true
+    }
 
-        let key = DenunciationIndex::from(&denunciation_precursor);
+    /// Add already built denunciations (e.g. versioned denunciations of new kinds of misbehavior)
+    /// to the pool, after checking their draw and verifying their proof
+    pub fn add_denunciations(&mut self, denunciations: Vec<Denunciation>) {
+        for denunciation in denunciations {
+            if !self.is_slot_acceptable(denunciation.get_slot())
+                || !self.is_drawn(
+                    denunciation.get_slot(),
+                    denunciation.get_index().copied(),
+                    denunciation.get_public_key(),
+                )
+            {
+                continue;
+            }
 
-        let denunciation_: Option<Denunciation> = match self.denunciations_cache.entry(key) {
-            Entry::Occupied(mut eo) => match eo.get_mut() {
-                DenunciationStatus::Accumulating(de_p_) => {
-                    let de_p: &DenunciationPrecursor = de_p_;
-                    if *de_p != denunciation_precursor {
-                        match Denunciation::try_from((de_p, &denunciation_precursor)) {
-                            Ok(de) => {
-                                eo.insert(DenunciationStatus::DenunciationEmitted(de.clone()));
-                                Some(de)
-                            }
-                            Err(e) => {
-                                debug!("Denunciation pool cannot create denunciation from endorsements: {}", e);
-                                None
-                            }
-                        }
-                    } else {
-                        // same denunciation precursor - do nothing
-                        None
+            let verification = match &denunciation {
+                Denunciation::Versioned(de) => match self.verifiers.get(&de.kind) {
+                    Some(verifier) => verifier.verify(de),
+                    None => {
+                        debug!(
+                            "Denunciation pool cannot verify denunciations of misbehavior kind {}",
+                            de.kind
+                        );
+                        continue;
+                    }
+                },
+                _ if denunciation.is_valid() => Ok(()),
+                _ => Err(DenunciationError::InvalidInput(
+                    "invalid denunciation".to_string(),
+                )),
+            };
+            if let Err(e) = verification {
+                debug!("Denunciation pool received an invalid denunciation: {}", e);
+                continue;
+            }
+
+            match self
+                .denunciations_cache
+                .entry(DenunciationIndex::from(&denunciation))
+            {
+                Entry::Occupied(mut eo) => {
+                    if let DenunciationStatus::Accumulating(_) = eo.get() {
+                        debug!("Added a new denunciation : {:?}", denunciation);
+                        eo.insert(DenunciationStatus::DenunciationEmitted(denunciation));
                     }
                 }
-                DenunciationStatus::DenunciationEmitted(..) => {
-                    // Already 2 entries - so a Denunciation has already been created
-                    None
+                Entry::Vacant(ev) => {
+                    debug!("Added a new denunciation : {:?}", denunciation);
+                    ev.insert(DenunciationStatus::DenunciationEmitted(denunciation));
                 }
-            },
-            Entry::Vacant(ev) => {
-                ev.insert(DenunciationStatus::Accumulating(denunciation_precursor));
-                None
             }
-        };
-
-        if let Some(denunciation) = denunciation_ {
-            debug!("Created a new denunciation : {:?}", denunciation);
         }
-
-        // Because at the start of the function, we have already checked that DE precursor is not
-        // expired, there is no need to cleanup the cache here
-        // This is only needed when we are notified of new cs final periods and thus calling the
-        // cleanup function only when it is needed
     }
 
     /// cleanup internal cache, removing too old denunciation
@@ -323,6 +404,7 @@ mod tests {
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements: vec![],
                 denunciations: vec![],
+                extensions: Vec::new(),
             };
 
             // create header
@@ -172,6 +172,26 @@ impl EndorsementPool {
                 );
                 // note that we don't want equivalent endorsements (slot, index, block etc...) to overwrite each other
                 if let Entry::Vacant(e) = self.endorsements_indexed.entry(key) {
+                    // one of our keys endorsing two different blocks at the same slot and index is a conflict
+                    if self
+                        .wallet
+                        .read()
+                        .keys
+                        .contains_key(&endo.content_creator_address)
+                        && self.endorsements_sorted[endo.content.slot.thread as usize]
+                            .keys()
+                            .any(|(slot, index, block_id)| {
+                                slot == &endo.content.slot
+                                    && index == &endo.content.index
+                                    && block_id != &endo.content.endorsed_block
+                            })
+                    {
+                        warn!(
+                            "endorsement {} of our address {} at slot {} index {} conflicts with another endorsement of the same slot and index",
+                            endo.id, endo.content_creator_address, endo.content.slot, endo.content.index
+                        );
+                        massa_metrics::inc_endorsement_production_conflict("equivocation");
+                    }
                     e.insert(endo.id);
                     if self.endorsements_sorted[endo.content.slot.thread as usize]
                         .insert(key, endo.id)
@@ -167,6 +167,10 @@ impl DenunciationPoolThread {
                     .denunciation_pool
                     .write()
                     .add_denunciation_precursor(de_p),
+                Ok(Command::AddDenunciations(denunciations)) => self
+                    .denunciation_pool
+                    .write()
+                    .add_denunciations(denunciations),
                 Ok(Command::AddItems(endorsements)) => self
                     .denunciation_pool
                     .write()
@@ -1625,7 +1625,7 @@ mod tests {
 
     use crate::MockSelectorController;
 
-    use massa_db_exports::{MassaDBConfig, MassaDBController};
+    use massa_db_exports::{MassaDBConfig, MassaDBController, STATE_CF_DEFAULT_WRITE_BUFFER_SIZE};
     use massa_db_worker::MassaDB;
     use massa_models::config::constants::{
         MAX_DEFERRED_CREDITS_LENGTH, MAX_PRODUCTION_STATS_LENGTH, MAX_ROLLS_COUNT_LENGTH,
@@ -1682,6 +1682,7 @@ mod tests {
             max_final_state_elements_size: 100_000,
             max_versioning_elements_size: 100_000,
             thread_count: 2,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>
@@ -1799,6 +1800,7 @@ mod tests {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: 2,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>
@@ -1991,6 +1993,7 @@ mod tests {
             max_final_state_elements_size: 100_000,
             max_versioning_elements_size: 100_000,
             thread_count: 2,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>
@@ -2090,6 +2093,7 @@ mod tests {
             max_final_state_elements_size: 100_000,
             max_versioning_elements_size: 100_000,
             thread_count: 2,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>
@@ -2264,6 +2268,7 @@ mod tests {
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
             thread_count: 2,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>
@@ -2366,6 +2371,7 @@ mod tests {
             path: tempdir.path().to_path_buf(),
             max_history_length: 10,
             thread_count: 2,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
             max_final_state_elements_size: 100,
             max_versioning_elements_size: 100,
         };
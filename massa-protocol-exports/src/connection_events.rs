@@ -0,0 +1,114 @@
+//! Events about the connections of the node, broadcast for the operators
+
+use std::net::SocketAddr;
+
+use massa_time::MassaTime;
+use serde::{Deserialize, Serialize};
+
+use crate::PeerId;
+
+/// Reason why a peer was disconnected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisconnectionReason {
+    /// the peer was banned
+    Banned,
+    /// the peer misbehaved too often and its connection was dropped
+    Deprioritized,
+    /// the connection was closed by the peer or lost
+    Closed,
+}
+
+/// Reason why a peer was banned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BanReason {
+    /// ban requested by the node operator or by a handler that caught the peer sending invalid data
+    Command,
+    /// the reputation of the peer dropped below the ban threshold
+    Reputation,
+}
+
+/// Class of the error that made a handshake fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandshakeErrorClass {
+    /// the data could not be sent or received
+    Transport,
+    /// the peer sent data that could not be deserialized
+    Malformed,
+    /// the peer runs an incompatible version
+    IncompatibleVersion,
+    /// the peer failed to prove its identity
+    InvalidSignature,
+    /// the peer has no slot left for us
+    NoSlot,
+    /// the peer follows a divergent finalized history
+    DivergentFinality,
+}
+
+/// Event about the connection of a peer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConnectionEvent {
+    /// a peer connected
+    Connected {
+        /// id of the peer
+        peer_id: PeerId,
+        /// address of the peer
+        address: SocketAddr,
+        /// time of the event
+        timestamp: MassaTime,
+    },
+    /// a peer disconnected
+    Disconnected {
+        /// id of the peer
+        peer_id: PeerId,
+        /// address of the peer
+        address: SocketAddr,
+        /// reason of the disconnection
+        reason: DisconnectionReason,
+        /// duration of the connection
+        duration: MassaTime,
+        /// time of the event
+        timestamp: MassaTime,
+    },
+    /// a peer was banned
+    Banned {
+        /// id of the peer
+        peer_id: PeerId,
+        /// reason of the ban
+        reason: BanReason,
+        /// time of the event
+        timestamp: MassaTime,
+    },
+    /// a handshake failed
+    HandshakeFailed {
+        /// address of the peer
+        address: SocketAddr,
+        /// id of the peer, if it was received
+        peer_id: Option<PeerId>,
+        /// class of the error
+        error_class: HandshakeErrorClass,
+        /// error message
+        error: String,
+        /// time of the event
+        timestamp: MassaTime,
+    },
+}
+
+/// Broadcasts used by the protocol worker to send events to the operators
+#[derive(Clone)]
+pub struct ProtocolBroadcasts {
+    /// Broadcast channel for connection events
+    pub connection_event_sender: tokio::sync::broadcast::Sender<ConnectionEvent>,
+}
+
+impl ProtocolBroadcasts {
+    /// Creates the broadcast channels, each one able to buffer `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        ProtocolBroadcasts {
+            connection_event_sender: tokio::sync::broadcast::channel(capacity).0,
+        }
+    }
+}
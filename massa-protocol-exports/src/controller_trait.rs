@@ -2,9 +2,10 @@
 
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use crate::error::ProtocolError;
-use crate::BootstrapPeers;
+use crate::{BootstrapPeers, PeerRecord, TopicConfig, TopicHandler, TopicId};
 
 use crate::PeerId;
 use massa_models::prehash::{PreHashMap, PreHashSet};
@@ -14,7 +15,7 @@ use massa_storage::Storage;
 use peernet::peer::PeerConnectionType;
 
 #[cfg(feature = "test-exports")]
-use std::sync::{Arc, RwLock};
+use std::sync::RwLock;
 
 #[cfg_attr(feature = "test-exports", mockall_wrap::wrap, mockall::automock)]
 pub trait ProtocolController: Send + Sync {
@@ -81,6 +82,20 @@ pub trait ProtocolController: Send + Sync {
     /// Unban a list of Peer Id
     fn unban_peers(&self, peer_ids: Vec<PeerId>) -> Result<(), ProtocolError>;
 
+    /// Get the liveness history of the known peers, as kept in the peer store
+    fn get_peer_records(&self) -> Result<Vec<PeerRecord>, ProtocolError>;
+
+    /// Register a topic, whose payloads received from the peers are passed to `handler`.
+    /// Registering a topic again replaces its settings and handler.
+    fn register_topic(
+        &self,
+        config: TopicConfig,
+        handler: Arc<dyn TopicHandler>,
+    ) -> Result<(), ProtocolError>;
+
+    /// Publish a payload on a registered topic
+    fn publish_on_topic(&self, topic: TopicId, payload: Vec<u8>) -> Result<(), ProtocolError>;
+
     /// Returns a boxed clone of self.
     /// Useful to allow cloning `Box<dyn ProtocolController>`.
     fn clone_box(&self) -> Box<dyn ProtocolController>;
@@ -0,0 +1,17 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Finalized history announced to the peers during the handshake.
+//!
+//! Both sides of a handshake announce the hashes of their final state at their latest final slots.
+//! When a peer announced a hash for a slot we also finalized, both hashes must match: otherwise the peer
+//! follows a divergent finalized history, because of a network partition or a fork. Such peers are reported,
+//! and refused if the `refuse_divergent_peers` option is set.
+
+use massa_hash::Hash;
+use massa_models::slot::Slot;
+
+/// Source of the final state hashes announced to the peers
+pub trait FinalityProvider: Send + Sync {
+    /// Hashes of the final state at the latest final slots, at most `count` of them, from oldest to newest
+    fn latest_final_state_hashes(&self, count: usize) -> Vec<(Slot, Hash)>;
+}
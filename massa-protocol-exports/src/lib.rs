@@ -1,18 +1,32 @@
 mod bootstrap_peers;
+mod connection_events;
 mod controller_trait;
 mod error;
+mod finality;
 mod peer_id;
+mod peer_record;
+mod proxy;
 mod settings;
+mod topics;
 
 pub use bootstrap_peers::{
     BootstrapPeers, BootstrapPeersDeserializer, BootstrapPeersSerializer, PeerData,
 };
+pub use connection_events::{
+    BanReason, ConnectionEvent, DisconnectionReason, HandshakeErrorClass, ProtocolBroadcasts,
+};
 pub use controller_trait::{ProtocolController, ProtocolManager};
 pub use error::ProtocolError;
+pub use finality::FinalityProvider;
 pub use peer_id::{PeerId, PeerIdDeserializer, PeerIdSerializer};
+pub use peer_record::PeerRecord;
 pub use peernet::peer::PeerConnectionType;
 pub use peernet::transports::TransportType;
-pub use settings::{PeerCategoryInfo, ProtocolConfig};
+pub use proxy::{ProxyTarget, Socks5ProxyConfig};
+pub use settings::{
+    BandwidthBudget, MessageRateLimit, MessageRateLimits, PeerCategoryInfo, ProtocolConfig,
+};
+pub use topics::{TopicConfig, TopicHandler, TopicId, TopicPropagation, TopicVerdict};
 
 #[cfg(any(test, feature = "test-exports"))]
 pub mod test_exports;
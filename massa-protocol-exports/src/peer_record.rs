@@ -0,0 +1,35 @@
+use std::{collections::HashMap, net::SocketAddr};
+
+use massa_time::MassaTime;
+use peernet::transports::TransportType;
+use serde::{Deserialize, Serialize};
+
+use crate::PeerId;
+
+/// Liveness history of a peer, kept in the peer store across restarts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerRecord {
+    /// id of the peer
+    pub peer_id: PeerId,
+    /// last time a handshake with the peer succeeded
+    pub last_seen: Option<MassaTime>,
+    /// duration of the last successful handshake with the peer
+    pub handshake_latency: Option<MassaTime>,
+    /// listeners advertised in the last announcement of the peer
+    pub listeners: HashMap<SocketAddr, TransportType>,
+    /// whether the peer is banned
+    pub banned: bool,
+}
+
+impl PeerRecord {
+    /// Record of a peer that was never seen
+    pub fn new(peer_id: PeerId) -> Self {
+        PeerRecord {
+            peer_id,
+            last_seen: None,
+            handshake_latency: None,
+            listeners: HashMap::new(),
+            banned: false,
+        }
+    }
+}
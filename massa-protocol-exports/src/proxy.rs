@@ -0,0 +1,221 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Minimal SOCKS5 client (RFC 1928, with the username/password authentication of RFC 1929),
+//! used to route the outbound connections of the node through a proxy such as Tor.
+
+use std::{
+    fmt::Display,
+    io::{self, Read, Write},
+    net::{IpAddr, SocketAddr, TcpStream},
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+const SOCKS_VERSION: u8 = 0x05;
+const AUTH_VERSION: u8 = 0x01;
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_USERNAME_PASSWORD: u8 = 0x02;
+const METHOD_NONE_ACCEPTABLE: u8 = 0xFF;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+/// SOCKS5 proxy through which the outbound connections are made
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct Socks5ProxyConfig {
+    /// address of the proxy
+    pub address: SocketAddr,
+    /// username, if the proxy requires authentication
+    pub username: Option<String>,
+    /// password, if the proxy requires authentication
+    pub password: Option<String>,
+}
+
+/// Destination of a connection made through a proxy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyTarget {
+    /// IP address and port
+    Address(SocketAddr),
+    /// host name, resolved by the proxy, and port
+    Hostname(String, u16),
+}
+
+impl ProxyTarget {
+    /// Parse a `host:port` destination, the host being either an IP address or a name
+    pub fn parse(target: &str) -> io::Result<Self> {
+        if let Ok(addr) = target.parse::<SocketAddr>() {
+            return Ok(ProxyTarget::Address(addr));
+        }
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid destination {}, expected host:port", target),
+            )
+        };
+        let (host, port) = target.rsplit_once(':').ok_or_else(invalid)?;
+        let port = port.parse::<u16>().map_err(|_| invalid())?;
+        if host.is_empty() || host.len() > u8::MAX as usize {
+            return Err(invalid());
+        }
+        Ok(ProxyTarget::Hostname(host.to_string(), port))
+    }
+}
+
+impl Display for ProxyTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProxyTarget::Address(addr) => write!(f, "{}", addr),
+            ProxyTarget::Hostname(host, port) => write!(f, "{}:{}", host, port),
+        }
+    }
+}
+
+fn proxy_error(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, message.into())
+}
+
+impl Socks5ProxyConfig {
+    /// Open a connection to `target` through the proxy.
+    /// Host names are resolved by the proxy, so that no DNS request leaves the node.
+    /// `timeout` applies to the connection to the proxy and to each read or write of the negotiation.
+    pub fn connect(
+        &self,
+        target: &ProxyTarget,
+        timeout: Option<Duration>,
+    ) -> io::Result<TcpStream> {
+        let mut stream = match timeout {
+            Some(timeout) => TcpStream::connect_timeout(&self.address, timeout)?,
+            None => TcpStream::connect(self.address)?,
+        };
+        stream.set_read_timeout(timeout)?;
+        stream.set_write_timeout(timeout)?;
+        self.authenticate(&mut stream)?;
+        Self::request_connect(&mut stream, target)?;
+        stream.set_read_timeout(None)?;
+        stream.set_write_timeout(None)?;
+        Ok(stream)
+    }
+
+    /// Negotiate the authentication method, and authenticate if credentials are configured
+    fn authenticate(&self, stream: &mut TcpStream) -> io::Result<()> {
+        let credentials = match (&self.username, &self.password) {
+            (Some(username), password) => Some((username.as_str(), password.as_deref())),
+            (None, _) => None,
+        };
+        let method = if credentials.is_some() {
+            METHOD_USERNAME_PASSWORD
+        } else {
+            METHOD_NO_AUTH
+        };
+        stream.write_all(&[SOCKS_VERSION, 1, method])?;
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply)?;
+        if reply[0] != SOCKS_VERSION {
+            return Err(proxy_error("the proxy is not a SOCKS5 proxy"));
+        }
+        match reply[1] {
+            METHOD_NO_AUTH => Ok(()),
+            METHOD_USERNAME_PASSWORD => {
+                let Some((username, password)) = credentials else {
+                    return Err(proxy_error("the proxy requires credentials"));
+                };
+                let password = password.unwrap_or_default();
+                if username.len() > u8::MAX as usize || password.len() > u8::MAX as usize {
+                    return Err(proxy_error("proxy credentials are too long"));
+                }
+                let mut request = vec![AUTH_VERSION, username.len() as u8];
+                request.extend(username.as_bytes());
+                request.push(password.len() as u8);
+                request.extend(password.as_bytes());
+                stream.write_all(&request)?;
+                let mut reply = [0u8; 2];
+                stream.read_exact(&mut reply)?;
+                if reply[1] != 0 {
+                    return Err(proxy_error("the proxy rejected the credentials"));
+                }
+                Ok(())
+            }
+            METHOD_NONE_ACCEPTABLE => Err(proxy_error(
+                "the proxy does not accept the authentication method",
+            )),
+            other => Err(proxy_error(format!(
+                "the proxy chose an unknown authentication method {}",
+                other
+            ))),
+        }
+    }
+
+    /// Ask the proxy to connect to the target
+    fn request_connect(stream: &mut TcpStream, target: &ProxyTarget) -> io::Result<()> {
+        let mut request = vec![SOCKS_VERSION, CMD_CONNECT, 0];
+        let port = match target {
+            ProxyTarget::Address(addr) => {
+                match addr.ip() {
+                    IpAddr::V4(ip) => {
+                        request.push(ATYP_IPV4);
+                        request.extend(ip.octets());
+                    }
+                    IpAddr::V6(ip) => {
+                        request.push(ATYP_IPV6);
+                        request.extend(ip.octets());
+                    }
+                }
+                addr.port()
+            }
+            ProxyTarget::Hostname(host, port) => {
+                request.push(ATYP_DOMAIN);
+                request.push(host.len() as u8);
+                request.extend(host.as_bytes());
+                *port
+            }
+        };
+        request.extend(port.to_be_bytes());
+        stream.write_all(&request)?;
+
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply)?;
+        if reply[1] != 0 {
+            return Err(proxy_error(format!(
+                "the proxy could not connect to {}: {}",
+                target,
+                reply_error(reply[1])
+            )));
+        }
+        // skip the address bound by the proxy
+        let address_length = match reply[3] {
+            ATYP_IPV4 => 4,
+            ATYP_IPV6 => 16,
+            ATYP_DOMAIN => {
+                let mut length = [0u8; 1];
+                stream.read_exact(&mut length)?;
+                length[0] as usize
+            }
+            other => {
+                return Err(proxy_error(format!(
+                    "the proxy replied with an unknown address type {}",
+                    other
+                )))
+            }
+        };
+        let mut bound_address = vec![0u8; address_length + 2];
+        stream.read_exact(&mut bound_address)?;
+        Ok(())
+    }
+}
+
+/// Description of a SOCKS5 reply code
+fn reply_error(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
@@ -11,6 +11,8 @@ use massa_time::MassaTime;
 use peernet::transports::TransportType;
 use serde::Deserialize;
 
+use crate::Socks5ProxyConfig;
+
 #[derive(Debug, Deserialize, Clone, Copy)]
 pub struct PeerCategoryInfo {
     pub allow_local_peers: bool,
@@ -19,11 +21,38 @@ pub struct PeerCategoryInfo {
     pub max_in_connections_per_ip: usize,
 }
 
+/// Token bucket limiting the rate of a type of message received from a peer
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct MessageRateLimit {
+    /// messages accepted per second, 0 disables the limit
+    pub rate: u64,
+    /// messages that can be received in a burst
+    pub burst: u64,
+}
+
+/// Bandwidth budget of the peers of a category, shared by all of them
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct BandwidthBudget {
+    /// bytes per second sent to the peers, 0 disables the budget
+    pub upload: u64,
+    /// bytes per second received from the peers, 0 disables the budget
+    pub download: u64,
+}
+
+/// Rate limits of the messages received from a peer, by message type
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct MessageRateLimits {
+    /// block headers and compact blocks
+    pub block_header: MessageRateLimit,
+    /// operation announcements
+    pub operation_announcement: MessageRateLimit,
+    /// requests for operations
+    pub ask_for_operations: MessageRateLimit,
+}
+
 /// Dynamic protocol configuration mix in static settings and constants configurations.
 #[derive(Debug, Deserialize, Clone)]
 pub struct ProtocolConfig {
-    /// self keypair
-    pub keypair_file: PathBuf,
     /// listeners from where we can receive messages
     pub listeners: HashMap<SocketAddr, TransportType>,
     /// initial peers path
@@ -36,6 +65,18 @@ pub struct ProtocolConfig {
     pub max_block_propagation_time: MassaTime,
     /// Block propagation tick interval, useful for propagating blocks quickly to newly connected peers.
     pub block_propagation_tick: MassaTime,
+    /// Announce blocks as compact blocks (header, operation short IDs and the operations the peer may miss)
+    /// instead of bare headers, to the peers that announced they understand them
+    pub compact_block_relay: bool,
+    /// Fetch the missing operations of a block as erasure-coded chunks of its body from several peers,
+    /// instead of asking a single peer for them
+    pub block_body_chunking: bool,
+    /// Number of data chunks a block body is split into
+    pub block_body_data_chunks: usize,
+    /// Number of parity chunks added to the data chunks of a block body
+    pub block_body_parity_chunks: usize,
+    /// Minimum number of missing operations of a block for its body to be fetched as chunks
+    pub block_body_chunking_min_missing_operations: usize,
     /// max known blocks of current nodes we keep in memory
     pub max_known_blocks_size: usize,
     /// max known blocks of foreign nodes we keep in memory (by node)
@@ -52,6 +93,8 @@ pub struct ProtocolConfig {
     pub max_node_known_endorsements_size: usize,
     /// we ask for the same block `max_simultaneous_ask_blocks_per_node` times at the same time
     pub max_simultaneous_ask_blocks_per_node: usize,
+    /// max number of blocks whose operations are being downloaded at the same time, once their header is known
+    pub max_block_bodies_in_flight: usize,
     /// Max wait time for sending a Network or Node event.
     pub max_send_wait: MassaTime,
     /// Maximum number of batches in the memory buffer.
@@ -64,6 +107,17 @@ pub struct ProtocolConfig {
     pub operation_batch_proc_period: MassaTime,
     /// Maximum number of asked operations in the memory buffer.
     pub asked_operations_buffer_capacity: usize,
+    /// Maximum estimated size in bytes of the operations asked to a single peer per `operation_batch_proc_period`.
+    /// Operations announced by several peers are spread among them within this budget.
+    pub max_ask_operations_size_per_peer: u64,
+    /// Reputation penalty above which a misbehaving peer is disconnected
+    pub peer_reputation_deprioritize_threshold: u64,
+    /// Reputation penalty above which a misbehaving peer is banned
+    pub peer_reputation_ban_threshold: u64,
+    /// Time needed by a peer to recover one point of reputation penalty
+    pub peer_reputation_recovery_period: MassaTime,
+    /// Capacity of the broadcast channel of the connection events
+    pub broadcast_connection_events_channel_capacity: usize,
     /// Interval at which operations are announced in batches.
     pub operation_announcement_interval: MassaTime,
     /// Maximum time we keep an operation in the storage
@@ -82,14 +136,52 @@ pub struct ProtocolConfig {
     pub t0: MassaTime,
     /// Genesis timestamp
     pub genesis_timestamp: MassaTime,
-    /// max number of operations kept in memory for propagation
+    /// max number of operations kept in memory for propagation, the ones with the lowest fee density being dropped first
     pub max_ops_kept_for_propagation: usize,
     /// max time we propagate operations
     pub max_operations_propagation_time: MassaTime,
+    /// interval at which the digest of the recently received operations is sent to the peers
+    pub operation_inventory_digest_interval: MassaTime,
+    /// max number of the most recent operations included in an inventory digest, 0 to disable the digests
+    pub max_operations_per_inventory_digest: usize,
+    /// interval at which the ids of the payloads kept for each topic are sent to the peers, so that they fetch the ones they missed
+    pub topic_anti_entropy_interval: MassaTime,
+    /// maximum size of a topic payload
+    pub max_topic_payload_size: usize,
+    /// maximum number of payloads or payload ids in a topic message
+    pub max_topic_items_per_message: u64,
+    /// number of threads verifying the signatures of the received operations, 0 for one per CPU core
+    pub operation_sig_verification_threads: usize,
+    /// number of received operations whose signatures are verified together by a thread
+    pub operation_sig_verification_batch_size: usize,
+    /// number of the latest final state hashes announced to the peers during the handshake
+    pub handshake_final_state_hashes: usize,
+    /// refuse the peers whose announced final state hashes differ from ours
+    pub refuse_divergent_peers: bool,
     /// max time we propagate endorsements
     pub max_endorsements_propagation_time: MassaTime,
+    /// Maximum time by which the slot of a received header or endorsement can be ahead of the current time
+    pub max_future_slot_time: MassaTime,
     /// Max message size
     pub max_message_size: usize,
+    /// Negotiate zstd compression of the large messages with the peers during the handshake
+    pub message_compression: bool,
+    /// Messages larger than this size (in bytes) are compressed for the peers that negotiated compression
+    pub message_compression_threshold: usize,
+    /// zstd compression level
+    pub message_compression_level: i32,
+    /// Maximum size of a decompressed message, protecting against decompression bombs
+    pub max_decompressed_message_size: usize,
+    /// Rate limits of the messages received from the peers of the default category
+    pub message_rate_limits: MessageRateLimits,
+    /// Rate limits of the messages received from the peers, by peer category
+    pub peers_categories_message_rate_limits: HashMap<String, MessageRateLimits>,
+    /// Bandwidth budget of the peers of the default category
+    pub bandwidth_budget: BandwidthBudget,
+    /// Bandwidth budgets of the peers, by peer category
+    pub peers_categories_bandwidth_budgets: HashMap<String, BandwidthBudget>,
+    /// SOCKS5 proxy through which the outbound peer connections are made, if any
+    pub socks5_proxy: Option<Socks5ProxyConfig>,
     /// number of thread tester
     pub thread_tester_count: u8,
     /// Max size of the channel for command to the connectivity thread
@@ -118,6 +210,10 @@ pub struct ProtocolConfig {
     pub max_size_channel_network_to_endorsement_handler: usize,
     /// Max size of channel that transfer message from network to peer handler
     pub max_size_channel_network_to_peer_handler: usize,
+    /// Max size of channel to send commands to the topic handler
+    pub max_size_channel_commands_topics: usize,
+    /// Max size of channel that transfer message from network to topic handler
+    pub max_size_channel_network_to_topic_handler: usize,
     /// endorsements per block
     pub endorsement_count: u32,
     /// running threads count
@@ -136,6 +232,10 @@ pub struct ProtocolConfig {
     pub max_op_datastore_value_length: u64,
     /// Maximum number of denunciations in a block header
     pub max_denunciations_in_block_header: u32,
+    /// Maximum size in bytes of the serialized denunciations of a received block header
+    pub max_block_header_denunciations_size: usize,
+    /// Maximum size in bytes of a received serialized block header
+    pub max_block_header_size: usize,
     /// Maximum number of endorsements that can be propagated in one message
     pub max_endorsements_per_message: u64,
     /// Maximum number of peers per announcement
@@ -174,4 +274,30 @@ pub struct ProtocolConfig {
     pub test_oldest_peer_cooldown: MassaTime,
     /// Rate limit to apply on the data stream
     pub rate_limit: u64,
+    /// If set, inbound protocol messages are captured to this file for later replay
+    pub traffic_capture_path: Option<PathBuf>,
+    /// If set, the prefixes of the recently checked operations are saved to this file on stop and reloaded on start
+    pub operation_seen_cache_path: Option<PathBuf>,
+    /// Maximum age of the checked operation prefixes saved to and reloaded from `operation_seen_cache_path`
+    pub operation_seen_cache_max_age: MassaTime,
+    /// If set, the liveness history of the peers is kept in a RocksDB database at this path and reloaded on start
+    pub peer_store_path: Option<PathBuf>,
+    /// Interval between two flushes of the peer records to the peer store
+    pub peer_store_flush_interval: MassaTime,
+    /// Peers that were not seen for this long are evicted from the peer store, unless they are banned
+    pub peer_store_max_age: MassaTime,
+    /// Map the listener ports on the gateway with UPnP or NAT-PMP, and advertise its public IP if `routable_ip` is not set
+    pub port_mapping: bool,
+    /// Lease duration of the port mappings, which are renewed at half of it
+    pub port_mapping_lease_duration: MassaTime,
+    /// Timeout of the requests to the gateway
+    pub port_mapping_timeout: MassaTime,
+    /// Maximum number of outbound connections of the default category to a network group (autonomous system, or /16 subnet)
+    pub outbound_max_per_group: usize,
+    /// Interval between two rotations of the outbound connections of the default category
+    pub outbound_rotation_interval: MassaTime,
+    /// Percentage of the outbound connections of the default category closed at each rotation, to be replaced by more diverse peers
+    pub outbound_rotation_percent: u64,
+    /// If set, the peers are grouped by autonomous system using the prefix-to-ASN map of this file
+    pub outbound_asn_map_path: Option<PathBuf>,
 }
@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
-use crate::{settings::PeerCategoryInfo, ProtocolConfig};
+use crate::{
+    settings::{BandwidthBudget, MessageRateLimit, MessageRateLimits, PeerCategoryInfo},
+    ProtocolConfig,
+};
 use massa_models::config::{ENDORSEMENT_COUNT, MAX_MESSAGE_SIZE};
 use massa_time::MassaTime;
 use tempfile::NamedTempFile;
@@ -10,18 +13,20 @@ const ONE_DAY_MS: u64 = 24 * 60 * 60 * 1000;
 impl Default for ProtocolConfig {
     fn default() -> Self {
         ProtocolConfig {
-            keypair_file: NamedTempFile::new()
-                .expect("cannot create temp file")
-                .path()
-                .to_path_buf(),
             ask_block_timeout: MassaTime::from_millis(10000),
             max_blocks_kept_for_propagation: 300,
             max_block_propagation_time: MassaTime::from_millis(40000),
             block_propagation_tick: MassaTime::from_millis(1000),
+            compact_block_relay: false,
+            block_body_chunking: false,
+            block_body_data_chunks: 4,
+            block_body_parity_chunks: 2,
+            block_body_chunking_min_missing_operations: 16,
             max_known_blocks_size: 100,
             max_node_known_blocks_size: 100,
             max_node_wanted_blocks_size: 100,
             max_simultaneous_ask_blocks_per_node: 10,
+            max_block_bodies_in_flight: 64,
             max_send_wait: MassaTime::from_millis(100),
             max_known_ops_size: 1000,
             max_node_known_ops_size: 1000,
@@ -32,6 +37,11 @@ impl Default for ProtocolConfig {
             max_operation_storage_time: MassaTime::from_millis(60000),
             operation_batch_proc_period: MassaTime::from_millis(200),
             asked_operations_buffer_capacity: 10000,
+            max_ask_operations_size_per_peer: 1_000_000,
+            peer_reputation_deprioritize_threshold: 200,
+            peer_reputation_ban_threshold: 500,
+            peer_reputation_recovery_period: MassaTime::from_millis(1000),
+            broadcast_connection_events_channel_capacity: 128,
             operation_announcement_interval: MassaTime::from_millis(150),
             max_operations_per_message: 1024,
             max_operations_per_block: 5000,
@@ -43,7 +53,17 @@ impl Default for ProtocolConfig {
             t0: MassaTime::from_millis(16000),
             max_ops_kept_for_propagation: 10000,
             max_operations_propagation_time: MassaTime::from_millis(30000),
+            operation_inventory_digest_interval: MassaTime::from_millis(2000),
+            max_operations_per_inventory_digest: 0,
+            topic_anti_entropy_interval: MassaTime::from_millis(2000),
+            max_topic_payload_size: 65536,
+            max_topic_items_per_message: 1024,
+            operation_sig_verification_threads: 2,
+            operation_sig_verification_batch_size: 64,
+            handshake_final_state_hashes: 16,
+            refuse_divergent_peers: false,
             max_endorsements_propagation_time: MassaTime::from_millis(60000),
+            max_future_slot_time: MassaTime::from_millis(64000),
             initial_peers: NamedTempFile::new()
                 .expect("cannot create temp file")
                 .path()
@@ -61,14 +81,34 @@ impl Default for ProtocolConfig {
             max_size_channel_network_to_endorsement_handler: 1000,
             max_size_channel_network_to_operation_handler: 10000,
             max_size_channel_network_to_peer_handler: 1000,
+            max_size_channel_commands_topics: 1000,
+            max_size_channel_network_to_topic_handler: 1000,
             max_size_channel_commands_peer_testers: 10000,
             max_size_channel_commands_peers: 300,
             max_message_size: MAX_MESSAGE_SIZE as usize,
+            message_compression: false,
+            message_compression_threshold: 4096,
+            message_compression_level: 3,
+            max_decompressed_message_size: 10_485_760,
+            message_rate_limits: MessageRateLimits {
+                block_header: MessageRateLimit { rate: 0, burst: 0 },
+                operation_announcement: MessageRateLimit { rate: 0, burst: 0 },
+                ask_for_operations: MessageRateLimit { rate: 0, burst: 0 },
+            },
+            peers_categories_message_rate_limits: HashMap::new(),
+            bandwidth_budget: BandwidthBudget {
+                upload: 0,
+                download: 0,
+            },
+            peers_categories_bandwidth_budgets: HashMap::new(),
+            socks5_proxy: None,
             endorsement_count: ENDORSEMENT_COUNT,
             max_size_value_datastore: 1_000_000,
             max_size_function_name: u16::MAX,
             max_size_call_sc_parameter: 10_000_000,
             max_denunciations_in_block_header: 100,
+            max_block_header_denunciations_size: 65_536,
+            max_block_header_size: 131_072,
             max_op_datastore_entry_count: 100000,
             max_op_datastore_key_length: u8::MAX,
             max_op_datastore_value_length: 1000000,
@@ -96,6 +136,19 @@ impl Default for ProtocolConfig {
             try_connection_timer_same_peer: MassaTime::from_millis(1000),
             test_oldest_peer_cooldown: MassaTime::from_millis(720000),
             rate_limit: 1024 * 1024 * 2,
+            traffic_capture_path: None,
+            operation_seen_cache_path: None,
+            operation_seen_cache_max_age: MassaTime::from_millis(600000),
+            peer_store_path: None,
+            peer_store_flush_interval: MassaTime::from_millis(60000),
+            peer_store_max_age: MassaTime::from_millis(2592000000),
+            port_mapping: false,
+            port_mapping_lease_duration: MassaTime::from_millis(3600000),
+            port_mapping_timeout: MassaTime::from_millis(3000),
+            outbound_max_per_group: 2,
+            outbound_rotation_interval: MassaTime::from_millis(1800000),
+            outbound_rotation_percent: 10,
+            outbound_asn_map_path: None,
         }
     }
 }
@@ -1,2 +1,3 @@
 pub mod config;
+pub mod network_simulator;
 pub mod tools;
@@ -0,0 +1,263 @@
+//! Deterministic in-process network simulator.
+//!
+//! The simulated nodes are identified by their peer ID and receive the messages sent to them on a channel.
+//! The messages travel over links with a latency, a jitter and a loss probability, and can be cut by
+//! network partitions. Time is virtual: it only moves forward when the test calls `advance`,
+//! which applies the scheduled network changes and delivers the messages that arrived, in arrival order.
+//! All the randomness comes from the seed of the simulator, so a scenario always plays out the same way.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashMap, HashSet},
+    sync::mpsc::{channel, Receiver, Sender},
+    time::Duration,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::PeerId;
+
+/// Conditions of the link from a node to another
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkConditions {
+    /// minimal time for a message to reach its destination
+    pub latency: Duration,
+    /// maximal random delay added to the latency
+    pub jitter: Duration,
+    /// probability that a message is lost, between 0 and 1
+    pub loss: f64,
+}
+
+impl LinkConditions {
+    /// Link delivering all the messages after `latency`
+    pub fn reliable(latency: Duration) -> Self {
+        LinkConditions {
+            latency,
+            jitter: Duration::ZERO,
+            loss: 0.0,
+        }
+    }
+}
+
+/// Change of the network applied at a scheduled time
+#[derive(Debug, Clone)]
+pub enum NetworkChange {
+    /// Set the conditions of the links without specific conditions
+    SetDefaultConditions(LinkConditions),
+    /// Set the conditions of the link from a node to another
+    SetLinkConditions {
+        /// sending node
+        from: PeerId,
+        /// receiving node
+        to: PeerId,
+        /// new conditions of the link
+        conditions: LinkConditions,
+    },
+    /// Split the network: two nodes can only communicate if they are in the same group,
+    /// the nodes that are in no group forming a group of their own.
+    /// The messages in flight between groups are lost.
+    Partition(Vec<HashSet<PeerId>>),
+    /// Remove the partition
+    Heal,
+}
+
+/// Counters of the simulated traffic
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimulationStats {
+    /// messages sent
+    pub sent: u64,
+    /// messages delivered to their destination
+    pub delivered: u64,
+    /// messages lost on their link
+    pub lost: u64,
+    /// messages dropped by a partition
+    pub partitioned: u64,
+}
+
+/// Message travelling on a link
+struct InFlight<M> {
+    from: PeerId,
+    to: PeerId,
+    message: M,
+}
+
+/// Deterministic simulated network carrying messages of type `M` between nodes
+pub struct NetworkSimulator<M> {
+    /// virtual time elapsed since the start of the simulation
+    now: Duration,
+    rng: StdRng,
+    nodes: HashMap<PeerId, Sender<(PeerId, M)>>,
+    default_conditions: LinkConditions,
+    link_conditions: HashMap<(PeerId, PeerId), LinkConditions>,
+    /// group index of the nodes in a group of the current partition, if any
+    partition: Option<HashMap<PeerId, usize>>,
+    /// messages in flight, indexed by arrival time and sending order
+    in_flight: BinaryHeap<Reverse<(Duration, u64)>>,
+    messages: HashMap<u64, InFlight<M>>,
+    /// scheduled changes, indexed by time and scheduling order
+    changes: BTreeMap<(Duration, u64), NetworkChange>,
+    /// counter ordering the messages and changes happening at the same time
+    sequence: u64,
+    stats: SimulationStats,
+}
+
+impl<M> NetworkSimulator<M> {
+    /// Creates an empty network whose links have the `default_conditions`
+    pub fn new(seed: u64, default_conditions: LinkConditions) -> Self {
+        NetworkSimulator {
+            now: Duration::ZERO,
+            rng: StdRng::seed_from_u64(seed),
+            nodes: HashMap::new(),
+            default_conditions,
+            link_conditions: HashMap::new(),
+            partition: None,
+            in_flight: BinaryHeap::new(),
+            messages: HashMap::new(),
+            changes: BTreeMap::new(),
+            sequence: 0,
+            stats: SimulationStats::default(),
+        }
+    }
+
+    /// Adds a node to the network.
+    /// Returns the receiver of the messages sent to the node, with the peer ID of their sender.
+    pub fn add_node(&mut self, peer_id: PeerId) -> Receiver<(PeerId, M)> {
+        let (sender, receiver) = channel();
+        self.nodes.insert(peer_id, sender);
+        receiver
+    }
+
+    /// Virtual time elapsed since the start of the simulation
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Counters of the traffic so far
+    pub fn stats(&self) -> SimulationStats {
+        self.stats
+    }
+
+    /// Number of messages in flight
+    pub fn in_flight_count(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Schedules a change of the network `delay` after the current time
+    pub fn schedule(&mut self, delay: Duration, change: NetworkChange) {
+        let sequence = self.next_sequence();
+        self.changes.insert((self.now + delay, sequence), change);
+    }
+
+    /// Applies a change of the network immediately
+    pub fn apply(&mut self, change: NetworkChange) {
+        match change {
+            NetworkChange::SetDefaultConditions(conditions) => {
+                self.default_conditions = conditions;
+            }
+            NetworkChange::SetLinkConditions {
+                from,
+                to,
+                conditions,
+            } => {
+                self.link_conditions.insert((from, to), conditions);
+            }
+            NetworkChange::Partition(groups) => {
+                let mut partition = HashMap::new();
+                for (index, group) in groups.into_iter().enumerate() {
+                    for peer_id in group {
+                        partition.insert(peer_id, index);
+                    }
+                }
+                self.partition = Some(partition);
+            }
+            NetworkChange::Heal => {
+                self.partition = None;
+            }
+        }
+    }
+
+    /// Whether two nodes can currently communicate
+    pub fn connected(&self, from: &PeerId, to: &PeerId) -> bool {
+        match &self.partition {
+            Some(partition) => partition.get(from) == partition.get(to),
+            None => true,
+        }
+    }
+
+    /// Sends a message from a node to another.
+    /// The message is lost if the link drops it or if the nodes are not connected when it arrives.
+    pub fn send(&mut self, from: PeerId, to: PeerId, message: M) {
+        self.stats.sent += 1;
+        let conditions = self
+            .link_conditions
+            .get(&(from, to))
+            .copied()
+            .unwrap_or(self.default_conditions);
+        if conditions.loss > 0.0 && self.rng.gen_bool(conditions.loss.min(1.0)) {
+            self.stats.lost += 1;
+            return;
+        }
+        let jitter = if conditions.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            self.rng.gen_range(Duration::ZERO..=conditions.jitter)
+        };
+        let sequence = self.next_sequence();
+        self.in_flight
+            .push(Reverse((self.now + conditions.latency + jitter, sequence)));
+        self.messages
+            .insert(sequence, InFlight { from, to, message });
+    }
+
+    /// Moves the virtual time forward by `duration`, applying the scheduled changes and
+    /// delivering the messages arriving in the meantime, in time order.
+    /// Returns the number of delivered messages.
+    pub fn advance(&mut self, duration: Duration) -> usize {
+        let end = self.now + duration;
+        let mut delivered = 0;
+        loop {
+            let next_change = self.changes.keys().next().copied();
+            let next_message = self.in_flight.peek().map(|Reverse(key)| *key);
+            // changes happen before the messages arriving at the same time
+            match (next_change, next_message) {
+                (Some(key), message)
+                    if key.0 <= end && message.map_or(true, |(time, _)| key.0 <= time) =>
+                {
+                    let change = self
+                        .changes
+                        .remove(&key)
+                        .expect("scheduled change disappeared");
+                    self.now = key.0;
+                    self.apply(change);
+                }
+                (_, Some((time, sequence))) if time <= end => {
+                    self.in_flight.pop();
+                    self.now = time;
+                    let InFlight { from, to, message } = self
+                        .messages
+                        .remove(&sequence)
+                        .expect("message in flight disappeared");
+                    if !self.connected(&from, &to) {
+                        self.stats.partitioned += 1;
+                        continue;
+                    }
+                    if let Some(sender) = self.nodes.get(&to) {
+                        // a node whose receiver was dropped has left the network
+                        if sender.send((from, message)).is_ok() {
+                            self.stats.delivered += 1;
+                            delivered += 1;
+                        }
+                    }
+                }
+                _ => break,
+            }
+        }
+        self.now = end;
+        delivered
+    }
+
+    fn next_sequence(&mut self) -> u64 {
+        self.sequence += 1;
+        self.sequence
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Application topics gossiped by the protocol on behalf of other subsystems.
+//!
+//! A subsystem registers a topic with its handler through the protocol controller, then publishes opaque payloads on it.
+//! The protocol propagates the payloads accepted by the handler to the peers supporting topics following the
+//! propagation policy of the topic, keeps the latest ones to answer the digests that the peers exchange periodically
+//! to recover the payloads they missed, and drops the payloads of the peers exceeding the rate limit of the topic.
+//! New message families are thus added by implementing `TopicHandler`, without another protocol handler.
+
+use crate::{MessageRateLimit, PeerId};
+
+/// Identifier of a topic, chosen by the subsystem registering it
+pub type TopicId = u64;
+
+/// Decision of a topic handler about a payload received from a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopicVerdict {
+    /// the payload is valid: it is kept and relayed to the other peers
+    Accept,
+    /// the payload is valid but not worth relaying (e.g. outdated): it is dropped
+    Ignore,
+    /// the payload is invalid: it is dropped and the peer is reported as misbehaving
+    Reject,
+}
+
+/// Handler of the payloads received on a topic
+pub trait TopicHandler: Send + Sync {
+    /// Checks and processes a payload received from a peer.
+    /// Called from the protocol thread gossiping the topics: it must not block.
+    fn handle_payload(&self, from: &PeerId, payload: &[u8]) -> TopicVerdict;
+}
+
+/// How the payloads accepted on a topic are propagated to the peers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopicPropagation {
+    /// the payloads are pushed to all the peers not known to have them
+    Flood,
+    /// the payloads are pushed to at most this many peers picked at random among the ones not known to have them,
+    /// the other peers fetching them from the digests
+    Fanout(usize),
+    /// only the IDs of the payloads are announced right away, the peers asking for the ones they miss:
+    /// saves bandwidth on topics with large payloads, at the cost of a round trip
+    Announce,
+}
+
+/// Gossip settings of a topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TopicConfig {
+    /// topic identifier
+    pub topic: TopicId,
+    /// maximum size of a payload in bytes, capped by the `max_topic_payload_size` of the protocol
+    pub max_payload_size: usize,
+    /// number of the latest accepted payloads kept to answer the digests of the peers
+    pub max_kept_payloads: usize,
+    /// payloads accepted from each peer, the ones beyond the limit being dropped
+    pub rate_limit: MessageRateLimit,
+    /// propagation policy of the accepted payloads
+    pub propagation: TopicPropagation,
+}
@@ -0,0 +1,352 @@
+//! Bandwidth budgets of the peer categories.
+//!
+//! The upload and download budgets of a category are shared by all of its peers, and enforced with
+//! token buckets of bytes holding up to `BURST_SECONDS` of budget. When a budget runs low, the traffic
+//! is shed by priority: the low-priority messages (operation announcements and inventory digests) are
+//! dropped as soon as the bucket is half empty, the normal messages when it cannot cover their size,
+//! and the high-priority messages (blocks and peer management) are never dropped but still use the budget.
+//!
+//! The upload budgets are enforced when the messages are serialized for a peer, and the download
+//! budgets when the messages received from a peer are dispatched to the handlers.
+
+use std::{
+    collections::HashMap,
+    ops::Bound::Included,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+use massa_protocol_exports::{BandwidthBudget, ProtocolConfig};
+use massa_serialization::{DeserializeError, Deserializer, U64VarIntDeserializer};
+use parking_lot::Mutex;
+use peernet::{
+    error::{PeerNetError, PeerNetResult},
+    messages::MessagesSerializer as PeerNetMessagesSerializer,
+};
+use tracing::debug;
+
+use crate::handlers::operation_handler::{OperationMessage, OperationMessageTypeId};
+use crate::messages::{Message, MessageTypeId};
+
+/// Category label of the peers of the default category
+const DEFAULT_CATEGORY: &str = "default";
+
+/// Seconds of budget that can be used in a burst
+const BURST_SECONDS: u64 = 2;
+
+/// Priority of a message when its bandwidth budget runs low
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TrafficPriority {
+    /// dropped first
+    Low,
+    Normal,
+    /// never dropped
+    High,
+}
+
+impl TrafficPriority {
+    /// Priority of a message sent to a peer
+    pub(crate) fn of_sent(message: &Message, high_priority: bool) -> Self {
+        match message {
+            _ if high_priority => TrafficPriority::High,
+            Message::Block(_) | Message::PeerManagement(_) => TrafficPriority::High,
+            Message::Operation(
+                OperationMessage::OperationsAnnouncement(_) | OperationMessage::InventoryDigest(_),
+            ) => TrafficPriority::Low,
+            _ => TrafficPriority::Normal,
+        }
+    }
+
+    /// Priority of a message received from a peer.
+    /// `data` is the message without its family type ID.
+    pub(crate) fn of_received(family: &MessageTypeId, data: &[u8]) -> Self {
+        match family {
+            MessageTypeId::Block | MessageTypeId::PeerManagement => TrafficPriority::High,
+            MessageTypeId::Operation => {
+                let id = U64VarIntDeserializer::new(Included(0), Included(u64::MAX))
+                    .deserialize::<DeserializeError>(data)
+                    .map(|(_, id)| id);
+                match id {
+                    Ok(id)
+                        if id == u64::from(OperationMessageTypeId::OperationsAnnouncement)
+                            || id == u64::from(OperationMessageTypeId::InventoryDigest) =>
+                    {
+                        TrafficPriority::Low
+                    }
+                    _ => TrafficPriority::Normal,
+                }
+            }
+            _ => TrafficPriority::Normal,
+        }
+    }
+
+    /// Name of the priority, used to label the metrics
+    fn name(&self) -> &'static str {
+        match self {
+            TrafficPriority::Low => "low",
+            TrafficPriority::Normal => "normal",
+            TrafficPriority::High => "high",
+        }
+    }
+}
+
+/// Direction of the traffic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Direction {
+    Upload,
+    Download,
+}
+
+impl Direction {
+    fn name(&self) -> &'static str {
+        match self {
+            Direction::Upload => "upload",
+            Direction::Download => "download",
+        }
+    }
+
+    fn rate(&self, budget: &BandwidthBudget) -> u64 {
+        match self {
+            Direction::Upload => budget.upload,
+            Direction::Download => budget.download,
+        }
+    }
+}
+
+/// Token bucket of bytes, counting thousandths of bytes so that the budget refills smoothly
+#[derive(Debug, Clone)]
+struct ByteBucket {
+    millibytes: u64,
+    last_refill: Instant,
+}
+
+impl ByteBucket {
+    fn full(rate: u64, now: Instant) -> Self {
+        ByteBucket {
+            millibytes: Self::capacity(rate),
+            last_refill: now,
+        }
+    }
+
+    fn capacity(rate: u64) -> u64 {
+        rate.saturating_mul(BURST_SECONDS).saturating_mul(1000)
+    }
+
+    /// Use `size` bytes of the bucket if its priority allows it, return false if the message must be dropped
+    fn try_use(&mut self, rate: u64, size: usize, priority: TrafficPriority, now: Instant) -> bool {
+        let elapsed_millis =
+            u64::try_from(now.saturating_duration_since(self.last_refill).as_millis())
+                .unwrap_or(u64::MAX);
+        self.millibytes = self
+            .millibytes
+            .saturating_add(elapsed_millis.saturating_mul(rate))
+            .min(Self::capacity(rate));
+        self.last_refill = now;
+
+        let size = (size as u64).saturating_mul(1000);
+        let required = match priority {
+            TrafficPriority::Low => size.saturating_add(Self::capacity(rate) / 2),
+            TrafficPriority::Normal => size,
+            TrafficPriority::High => 0,
+        };
+        if self.millibytes < required {
+            return false;
+        }
+        self.millibytes = self.millibytes.saturating_sub(size);
+        true
+    }
+}
+
+/// Bandwidth budgets of the peer categories
+pub(crate) struct BandwidthBudgets {
+    /// budget of the peers of the default category
+    default_budget: BandwidthBudget,
+    /// budgets of the peers, by category
+    categories_budgets: HashMap<String, BandwidthBudget>,
+    /// buckets of the categories, `None` for the default category
+    buckets: Mutex<HashMap<(Option<String>, Direction), ByteBucket>>,
+}
+
+impl BandwidthBudgets {
+    /// Budgets of the config, `None` if no budget is enabled
+    pub(crate) fn from_config(config: &ProtocolConfig) -> Option<Self> {
+        let enabled = std::iter::once(&config.bandwidth_budget)
+            .chain(config.peers_categories_bandwidth_budgets.values())
+            .any(|budget| budget.upload > 0 || budget.download > 0);
+        enabled.then(|| BandwidthBudgets {
+            default_budget: config.bandwidth_budget,
+            categories_budgets: config.peers_categories_bandwidth_budgets.clone(),
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Category whose budget applies to the peers of a category, `None` for the default budget
+    fn budget_category<'a>(&self, category: Option<&'a str>) -> Option<&'a str> {
+        category.filter(|category| self.categories_budgets.contains_key(*category))
+    }
+
+    fn budget(&self, category: Option<&str>) -> &BandwidthBudget {
+        self.budget_category(category)
+            .and_then(|category| self.categories_budgets.get(category))
+            .unwrap_or(&self.default_budget)
+    }
+
+    /// Whether the upload of the peers of a category is budgeted
+    pub(crate) fn limits_upload(&self, category: Option<&str>) -> bool {
+        self.budget(category).upload > 0
+    }
+
+    fn allow(
+        &self,
+        direction: Direction,
+        category: Option<&str>,
+        priority: TrafficPriority,
+        size: usize,
+    ) -> bool {
+        let rate = direction.rate(self.budget(category));
+        if rate == 0 {
+            return true;
+        }
+        // the peers of the categories without budget share the default budget
+        let category = self.budget_category(category);
+        let now = Instant::now();
+        let allowed = self
+            .buckets
+            .lock()
+            .entry((category.map(str::to_string), direction))
+            .or_insert_with(|| ByteBucket::full(rate, now))
+            .try_use(rate, size, priority, now);
+        if !allowed {
+            let category = category.unwrap_or(DEFAULT_CATEGORY);
+            debug!(
+                "dropping {} priority message of {} bytes (category {}): {} budget exceeded",
+                priority.name(),
+                size,
+                category,
+                direction.name()
+            );
+            massa_metrics::inc_protocol_bandwidth_budget_dropped_message(
+                direction.name(),
+                category,
+                priority.name(),
+            );
+        }
+        allowed
+    }
+
+    /// Whether a message of `size` bytes can be sent to a peer of the category
+    pub(crate) fn allow_upload(
+        &self,
+        category: Option<&str>,
+        priority: TrafficPriority,
+        size: usize,
+    ) -> bool {
+        self.allow(Direction::Upload, category, priority, size)
+    }
+
+    /// Whether a message of `size` bytes received from a peer of the category can be handled
+    pub(crate) fn allow_download(
+        &self,
+        category: Option<&str>,
+        priority: TrafficPriority,
+        size: usize,
+    ) -> bool {
+        self.allow(Direction::Download, category, priority, size)
+    }
+}
+
+/// Serializer of the messages sent to a peer whose category has an upload budget:
+/// the messages that do not fit in the budget are not serialized and `dropped` is set
+pub(crate) struct BudgetedMessagesSerializer<'a, S> {
+    pub serializer: &'a S,
+    pub budgets: &'a BandwidthBudgets,
+    pub category: Option<&'a str>,
+    pub priority: TrafficPriority,
+    pub dropped: AtomicBool,
+}
+
+impl<S: PeerNetMessagesSerializer<Message>> PeerNetMessagesSerializer<Message>
+    for BudgetedMessagesSerializer<'_, S>
+{
+    fn serialize(&self, message: &Message, buffer: &mut Vec<u8>) -> PeerNetResult<()> {
+        let mut data = Vec::new();
+        self.serializer.serialize(message, &mut data)?;
+        if !self
+            .budgets
+            .allow_upload(self.category, self.priority, data.len())
+        {
+            self.dropped.store(true, Ordering::Relaxed);
+            return Err(PeerNetError::HandlerError.error(
+                "BudgetedMessagesSerializer",
+                Some(String::from("Upload bandwidth budget exceeded")),
+            ));
+        }
+        buffer.extend_from_slice(&data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_serialization::{Serializer, U64VarIntSerializer};
+
+    #[test]
+    fn test_bandwidth_budgets_shed_by_priority() {
+        let config = ProtocolConfig {
+            bandwidth_budget: BandwidthBudget {
+                upload: 1000,
+                download: 0,
+            },
+            peers_categories_bandwidth_budgets: HashMap::from([(
+                "Bootstrap".to_string(),
+                BandwidthBudget {
+                    upload: 0,
+                    download: 0,
+                },
+            )]),
+            ..Default::default()
+        };
+        let budgets = BandwidthBudgets::from_config(&config).unwrap();
+        assert!(budgets.limits_upload(None));
+        assert!(!budgets.limits_upload(Some("Bootstrap")));
+
+        // the bucket holds 2000 bytes: low-priority messages stop once it is half empty
+        assert!(budgets.allow_upload(None, TrafficPriority::Low, 900));
+        assert!(!budgets.allow_upload(None, TrafficPriority::Low, 200));
+        // normal messages use what is left, high-priority messages are always sent
+        assert!(budgets.allow_upload(None, TrafficPriority::Normal, 1000));
+        assert!(!budgets.allow_upload(None, TrafficPriority::Normal, 200));
+        assert!(budgets.allow_upload(None, TrafficPriority::High, 5000));
+        assert!(!budgets.allow_upload(None, TrafficPriority::Normal, 100));
+
+        // categories without budget and directions without budget are not limited
+        assert!(budgets.allow_upload(Some("Bootstrap"), TrafficPriority::Low, 1_000_000));
+        assert!(budgets.allow_download(None, TrafficPriority::Low, 1_000_000));
+
+        assert!(BandwidthBudgets::from_config(&ProtocolConfig::default()).is_none());
+    }
+
+    #[test]
+    fn test_received_message_priority() {
+        let mut announcement = Vec::new();
+        U64VarIntSerializer::new()
+            .serialize(
+                &u64::from(OperationMessageTypeId::OperationsAnnouncement),
+                &mut announcement,
+            )
+            .unwrap();
+        assert_eq!(
+            TrafficPriority::of_received(&MessageTypeId::Operation, &announcement),
+            TrafficPriority::Low
+        );
+        assert_eq!(
+            TrafficPriority::of_received(&MessageTypeId::Block, &announcement),
+            TrafficPriority::High
+        );
+        assert_eq!(
+            TrafficPriority::of_received(&MessageTypeId::Endorsement, &announcement),
+            TrafficPriority::Normal
+        );
+    }
+}
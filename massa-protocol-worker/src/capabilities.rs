@@ -0,0 +1,345 @@
+//! Protocol version and capabilities negotiated with the peers during the handshake.
+//!
+//! After their announcement, peers send the set of optional features they support followed by the
+//! semver version of the protocol they speak. Nodes that do not know about them ignore these bytes.
+//! The capabilities used to be a single byte holding the compression flag: they are now a varint
+//! bitset, which keeps that encoding for the flags below 128. A peer that announced no protocol version
+//! speaks `LEGACY_PROTOCOL_VERSION`.
+//!
+//! Peers speaking another major version of the protocol are rejected. A feature is only used with
+//! a peer if both nodes announced it, so that new features can be rolled out gradually.
+
+use std::{collections::HashMap, fmt, ops::Bound::Included, sync::Arc};
+
+use massa_protocol_exports::{PeerId, ProtocolConfig};
+use massa_serialization::{
+    Deserializer, SerializeError, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
+};
+use nom::{
+    error::{context, ContextError, ParseError},
+    sequence::tuple,
+    IResult, Parser,
+};
+use parking_lot::RwLock;
+
+/// Version of the protocol spoken by our node
+pub(crate) const PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion {
+    major: 1,
+    minor: 1,
+    patch: 0,
+};
+
+/// Version of the protocol spoken by the peers that do not announce one
+pub(crate) const LEGACY_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion {
+    major: 1,
+    minor: 0,
+    patch: 0,
+};
+
+/// Semver version of the peer-to-peer protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProtocolVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl ProtocolVersion {
+    /// Whether the peers speaking these versions can talk to each other
+    pub fn is_compatible(&self, other: &ProtocolVersion) -> bool {
+        self.major == other.major
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Set of optional protocol features
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities(u64);
+
+impl Capabilities {
+    /// The peer accepts compressed messages, see `crate::compression`
+    pub const COMPRESSION: Capabilities = Capabilities(1 << 0);
+    /// The peer understands the `BlockMessage::CompactBlock` messages
+    pub const COMPACT_BLOCKS: Capabilities = Capabilities(1 << 1);
+    /// The peer understands the `OperationMessage::InventoryDigest` messages
+    pub const OPERATION_INVENTORY_DIGEST: Capabilities = Capabilities(1 << 2);
+    /// The peer understands the `TopicMessage` messages of the application topics
+    pub const TOPICS: Capabilities = Capabilities(1 << 3);
+    /// The peer understands the `AskForBlockInfo::OperationChunks` requests for erasure-coded chunks of block bodies
+    pub const BLOCK_BODY_CHUNKS: Capabilities = Capabilities(1 << 4);
+
+    /// Set with no features
+    pub const fn empty() -> Self {
+        Capabilities(0)
+    }
+
+    /// Set from its bits. Unknown bits are kept, they are dropped by the negotiation.
+    pub const fn from_bits(bits: u64) -> Self {
+        Capabilities(bits)
+    }
+
+    /// Bits of the set
+    pub const fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Whether all the features of `other` are in the set
+    pub const fn contains(&self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Features in either set
+    pub const fn union(self, other: Capabilities) -> Self {
+        Capabilities(self.0 | other.0)
+    }
+
+    /// Features in both sets
+    pub const fn intersection(self, other: Capabilities) -> Self {
+        Capabilities(self.0 & other.0)
+    }
+
+    /// Features supported by our node with this config
+    pub(crate) fn ours(config: &ProtocolConfig) -> Self {
+        let mut capabilities = Capabilities::COMPACT_BLOCKS
+            .union(Capabilities::OPERATION_INVENTORY_DIGEST)
+            .union(Capabilities::TOPICS)
+            // the chunks of the block bodies are always served, whether or not we ask for them
+            .union(Capabilities::BLOCK_BODY_CHUNKS);
+        if config.message_compression {
+            capabilities = capabilities.union(Capabilities::COMPRESSION);
+        }
+        capabilities
+    }
+}
+
+/// Protocol version and capabilities announced by a node in its handshake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AnnouncedFeatures {
+    pub capabilities: Capabilities,
+    /// `None` for the peers speaking `LEGACY_PROTOCOL_VERSION`
+    pub protocol_version: Option<ProtocolVersion>,
+}
+
+impl AnnouncedFeatures {
+    /// Features announced by our node with this config
+    pub(crate) fn ours(config: &ProtocolConfig) -> Self {
+        AnnouncedFeatures {
+            capabilities: Capabilities::ours(config),
+            protocol_version: Some(PROTOCOL_VERSION),
+        }
+    }
+
+    /// Negotiates the features used with a peer from what both nodes announced.
+    /// Returns `None` if the peer speaks an incompatible version of the protocol.
+    pub(crate) fn negotiate(&self, theirs: &AnnouncedFeatures) -> Option<NegotiatedCapabilities> {
+        let our_version = self.protocol_version.unwrap_or(LEGACY_PROTOCOL_VERSION);
+        let their_version = theirs.protocol_version.unwrap_or(LEGACY_PROTOCOL_VERSION);
+        if !our_version.is_compatible(&their_version) {
+            return None;
+        }
+        Some(NegotiatedCapabilities {
+            protocol_version: our_version.min(their_version),
+            capabilities: self.capabilities.intersection(theirs.capabilities),
+        })
+    }
+}
+
+/// Protocol version and features used with a peer, negotiated during the handshake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NegotiatedCapabilities {
+    /// lowest of the protocol versions of both nodes
+    pub protocol_version: ProtocolVersion,
+    /// features announced by both nodes
+    pub capabilities: Capabilities,
+}
+
+impl NegotiatedCapabilities {
+    /// Whether the feature can be used with the peer
+    pub fn supports(&self, capability: Capabilities) -> bool {
+        self.capabilities.contains(capability)
+    }
+}
+
+/// Capabilities negotiated with the peers during their last handshake
+pub(crate) type SharedPeerCapabilities = Arc<RwLock<HashMap<PeerId, NegotiatedCapabilities>>>;
+
+/// Serializer for `AnnouncedFeatures`
+#[derive(Default, Clone)]
+pub(crate) struct AnnouncedFeaturesSerializer {
+    u64_serializer: U64VarIntSerializer,
+}
+
+impl AnnouncedFeaturesSerializer {
+    pub(crate) fn new() -> Self {
+        Self {
+            u64_serializer: U64VarIntSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<AnnouncedFeatures> for AnnouncedFeaturesSerializer {
+    fn serialize(
+        &self,
+        value: &AnnouncedFeatures,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.u64_serializer
+            .serialize(&value.capabilities.bits(), buffer)?;
+        if let Some(version) = value.protocol_version {
+            self.u64_serializer.serialize(&version.major, buffer)?;
+            self.u64_serializer.serialize(&version.minor, buffer)?;
+            self.u64_serializer.serialize(&version.patch, buffer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deserializer for `AnnouncedFeatures`, accepting the shorter announcements of the older nodes
+/// and ignoring the trailing bytes that newer nodes may add
+#[derive(Clone)]
+pub(crate) struct AnnouncedFeaturesDeserializer {
+    u64_deserializer: U64VarIntDeserializer,
+}
+
+impl AnnouncedFeaturesDeserializer {
+    pub(crate) fn new() -> Self {
+        Self {
+            u64_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+        }
+    }
+}
+
+impl Deserializer<AnnouncedFeatures> for AnnouncedFeaturesDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], AnnouncedFeatures, E> {
+        if buffer.is_empty() {
+            return Ok((
+                buffer,
+                AnnouncedFeatures {
+                    capabilities: Capabilities::empty(),
+                    protocol_version: None,
+                },
+            ));
+        }
+        context("Failed AnnouncedFeatures deserialization", |input| {
+            let (input, bits) = context("Failed capabilities deserialization", |input| {
+                self.u64_deserializer.deserialize(input)
+            })
+            .parse(input)?;
+            let capabilities = Capabilities::from_bits(bits);
+            if input.is_empty() {
+                return Ok((
+                    input,
+                    AnnouncedFeatures {
+                        capabilities,
+                        protocol_version: None,
+                    },
+                ));
+            }
+            let (input, (major, minor, patch)) = context(
+                "Failed protocol version deserialization",
+                tuple((
+                    |input| self.u64_deserializer.deserialize(input),
+                    |input| self.u64_deserializer.deserialize(input),
+                    |input| self.u64_deserializer.deserialize(input),
+                )),
+            )
+            .parse(input)?;
+            Ok((
+                input,
+                AnnouncedFeatures {
+                    capabilities,
+                    protocol_version: Some(ProtocolVersion {
+                        major,
+                        minor,
+                        patch,
+                    }),
+                },
+            ))
+        })
+        .parse(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_serialization::DeserializeError;
+
+    fn deserialize(buffer: &[u8]) -> AnnouncedFeatures {
+        AnnouncedFeaturesDeserializer::new()
+            .deserialize::<DeserializeError>(buffer)
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn test_announced_features_negotiation() {
+        let config = ProtocolConfig {
+            message_compression: true,
+            ..Default::default()
+        };
+        let ours = AnnouncedFeatures::ours(&config);
+        let mut buffer = Vec::new();
+        AnnouncedFeaturesSerializer::new()
+            .serialize(&ours, &mut buffer)
+            .unwrap();
+        assert_eq!(deserialize(&buffer), ours);
+        // trailing bytes of newer nodes are ignored
+        buffer.push(42);
+        assert_eq!(deserialize(&buffer), ours);
+        assert_eq!(
+            ours.negotiate(&ours),
+            Some(NegotiatedCapabilities {
+                protocol_version: PROTOCOL_VERSION,
+                capabilities: Capabilities::ours(&config),
+            })
+        );
+
+        // older nodes announce the compression flag alone, or nothing
+        let legacy = ours.negotiate(&deserialize(&[1])).unwrap();
+        assert_eq!(legacy.protocol_version, LEGACY_PROTOCOL_VERSION);
+        assert!(legacy.supports(Capabilities::COMPRESSION));
+        assert!(!legacy.supports(Capabilities::COMPACT_BLOCKS));
+        assert!(!legacy.supports(Capabilities::OPERATION_INVENTORY_DIGEST));
+        assert!(!legacy.supports(Capabilities::BLOCK_BODY_CHUNKS));
+        let legacy = ours.negotiate(&deserialize(&[])).unwrap();
+        assert!(!legacy.supports(Capabilities::COMPRESSION));
+
+        // features are only used if both nodes announced them, unknown ones are dropped
+        let theirs = AnnouncedFeatures {
+            capabilities: Capabilities::from_bits(1 << 40).union(Capabilities::COMPRESSION),
+            protocol_version: Some(ProtocolVersion {
+                major: 1,
+                minor: 7,
+                patch: 3,
+            }),
+        };
+        let negotiated = AnnouncedFeatures::ours(&ProtocolConfig {
+            message_compression: false,
+            ..Default::default()
+        })
+        .negotiate(&theirs)
+        .unwrap();
+        assert_eq!(negotiated.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(negotiated.capabilities, Capabilities::empty());
+
+        // another major version is rejected
+        let theirs = AnnouncedFeatures {
+            capabilities: Capabilities::ours(&config),
+            protocol_version: Some(ProtocolVersion {
+                major: 2,
+                minor: 0,
+                patch: 0,
+            }),
+        };
+        assert_eq!(ours.negotiate(&theirs), None);
+    }
+}
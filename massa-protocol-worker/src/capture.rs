@@ -0,0 +1,257 @@
+//! Capture and replay of inbound protocol traffic.
+//!
+//! When `ProtocolConfig::traffic_capture_path` is set, every message received from the network
+//! is appended to that file along with its reception time and the id of the peer that sent it.
+//! The captured file can later be fed back through a `MessagesHandler` with `replay_capture`,
+//! in the same order, so that issues observed on live traffic can be reproduced in tests.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    ops::Bound::Included,
+    path::Path,
+};
+
+use massa_models::serialization::{VecU8Deserializer, VecU8Serializer};
+use massa_protocol_exports::{PeerId, PeerIdDeserializer, PeerIdSerializer, ProtocolError};
+use massa_serialization::{
+    DeserializeError, Deserializer, SerializeError, Serializer, U64VarIntDeserializer,
+    U64VarIntSerializer,
+};
+use massa_time::MassaTime;
+use nom::{
+    error::{context, ContextError, ParseError},
+    sequence::tuple,
+    IResult, Parser,
+};
+use parking_lot::Mutex;
+use peernet::{error::PeerNetResult, messages::MessagesHandler as PeerNetMessagesHandler};
+use tracing::warn;
+
+use crate::messages::MessagesHandler;
+
+/// Maximum size of a captured message
+const MAX_CAPTURED_MESSAGE_SIZE: u64 = 1_073_741_824;
+
+/// An inbound message as captured from the network
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedMessage {
+    /// reception time
+    pub timestamp: MassaTime,
+    /// peer that sent the message
+    pub peer_id: PeerId,
+    /// raw message, including its type id
+    pub data: Vec<u8>,
+}
+
+/// Serializer for `CapturedMessage`
+#[derive(Default, Clone)]
+pub struct CapturedMessageSerializer {
+    u64_serializer: U64VarIntSerializer,
+    peer_id_serializer: PeerIdSerializer,
+    data_serializer: VecU8Serializer,
+}
+
+impl CapturedMessageSerializer {
+    pub fn new() -> Self {
+        Self {
+            u64_serializer: U64VarIntSerializer::new(),
+            peer_id_serializer: PeerIdSerializer::new(),
+            data_serializer: VecU8Serializer::new(),
+        }
+    }
+}
+
+impl Serializer<CapturedMessage> for CapturedMessageSerializer {
+    fn serialize(
+        &self,
+        value: &CapturedMessage,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.u64_serializer
+            .serialize(&value.timestamp.as_millis(), buffer)?;
+        self.peer_id_serializer.serialize(&value.peer_id, buffer)?;
+        self.data_serializer.serialize(&value.data, buffer)
+    }
+}
+
+/// Deserializer for `CapturedMessage`
+#[derive(Clone)]
+pub struct CapturedMessageDeserializer {
+    u64_deserializer: U64VarIntDeserializer,
+    peer_id_deserializer: PeerIdDeserializer,
+    data_deserializer: VecU8Deserializer,
+}
+
+impl CapturedMessageDeserializer {
+    pub fn new() -> Self {
+        Self {
+            u64_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+            peer_id_deserializer: PeerIdDeserializer::new(),
+            data_deserializer: VecU8Deserializer::new(
+                Included(0),
+                Included(MAX_CAPTURED_MESSAGE_SIZE),
+            ),
+        }
+    }
+}
+
+impl Default for CapturedMessageDeserializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deserializer<CapturedMessage> for CapturedMessageDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], CapturedMessage, E> {
+        context(
+            "Failed CapturedMessage deserialization",
+            tuple((
+                context("Failed timestamp deserialization", |input| {
+                    self.u64_deserializer.deserialize(input)
+                }),
+                context("Failed peer_id deserialization", |input| {
+                    self.peer_id_deserializer.deserialize(input)
+                }),
+                context("Failed data deserialization", |input| {
+                    self.data_deserializer.deserialize(input)
+                }),
+            )),
+        )
+        .map(|(timestamp, peer_id, data)| CapturedMessage {
+            timestamp: MassaTime::from_millis(timestamp),
+            peer_id,
+            data,
+        })
+        .parse(buffer)
+    }
+}
+
+/// Appends inbound messages to a capture file
+pub struct TrafficCapture {
+    writer: Mutex<BufWriter<File>>,
+    serializer: CapturedMessageSerializer,
+}
+
+impl TrafficCapture {
+    /// Opens (or creates) the capture file, new messages are appended to it
+    pub fn new(path: &Path) -> Result<Self, ProtocolError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(TrafficCapture {
+            writer: Mutex::new(BufWriter::new(file)),
+            serializer: CapturedMessageSerializer::new(),
+        })
+    }
+
+    /// Records a message received from `peer_id`.
+    /// Failures are logged and otherwise ignored so that capture never disturbs the node.
+    pub fn record(&self, data: &[u8], peer_id: &PeerId) {
+        let message = CapturedMessage {
+            timestamp: MassaTime::now(),
+            peer_id: *peer_id,
+            data: data.to_vec(),
+        };
+        let mut buffer = Vec::new();
+        if let Err(err) = self.serializer.serialize(&message, &mut buffer) {
+            warn!("failed to serialize captured message: {}", err);
+            return;
+        }
+        let mut writer = self.writer.lock();
+        if let Err(err) = writer.write_all(&buffer).and_then(|_| writer.flush()) {
+            warn!("failed to write captured message: {}", err);
+        }
+    }
+}
+
+/// Reads all the messages of a capture file, in reception order
+pub fn read_capture(path: &Path) -> Result<Vec<CapturedMessage>, ProtocolError> {
+    let content = std::fs::read(path)?;
+    let deserializer = CapturedMessageDeserializer::new();
+    let mut messages = Vec::new();
+    let mut rest = content.as_slice();
+    while !rest.is_empty() {
+        let (new_rest, message) =
+            deserializer
+                .deserialize::<DeserializeError>(rest)
+                .map_err(|err| {
+                    ProtocolError::GeneralProtocolError(format!(
+                        "invalid capture file {}: {}",
+                        path.display(),
+                        err
+                    ))
+                })?;
+        messages.push(message);
+        rest = new_rest;
+    }
+    Ok(messages)
+}
+
+/// Feeds captured messages back through a messages handler, in capture order.
+/// Timestamps are not waited for so that replays are deterministic and fast.
+pub fn replay_capture(
+    messages: &[CapturedMessage],
+    handler: &MessagesHandler,
+) -> PeerNetResult<()> {
+    for message in messages {
+        handler.handle(&message.data, &message.peer_id)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_channel::MassaChannel;
+    use massa_signature::KeyPair;
+
+    #[test]
+    fn test_capture_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.bin");
+        let peer_a = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+        let peer_b = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+
+        // message type ids: 0 = block, 2 = operation
+        let capture = TrafficCapture::new(&path).unwrap();
+        capture.record(&[0, 1, 2, 3], &peer_a);
+        capture.record(&[2, 4, 5], &peer_b);
+        capture.record(&[0, 6], &peer_b);
+        drop(capture);
+
+        let messages = read_capture(&path).unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].peer_id, peer_a);
+        assert_eq!(messages[1].data, vec![2, 4, 5]);
+
+        let (sender_blocks, receiver_blocks) = MassaChannel::new("blocks".to_string(), None);
+        let (sender_endorsements, _receiver_endorsements) =
+            MassaChannel::new("endorsements".to_string(), None);
+        let (sender_operations, receiver_operations) =
+            MassaChannel::new("operations".to_string(), None);
+        let (sender_peers, _receiver_peers) = MassaChannel::new("peers".to_string(), None);
+        let (sender_topics, _receiver_topics) = MassaChannel::new("topics".to_string(), None);
+        let handler = MessagesHandler {
+            id_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+            sender_blocks,
+            sender_endorsements,
+            sender_operations,
+            sender_peers,
+            sender_topics,
+            capture: None,
+            max_decompressed_message_size: None,
+            rate_limiter: None,
+            bandwidth_budgets: None,
+        };
+        replay_capture(&messages, &handler).unwrap();
+
+        let (peer_id, data, _) = receiver_blocks.try_recv().unwrap();
+        assert_eq!((peer_id, data), (peer_a, vec![1, 2, 3]));
+        let (peer_id, data, _) = receiver_blocks.try_recv().unwrap();
+        assert_eq!((peer_id, data), (peer_b, vec![6]));
+        let (peer_id, data, _) = receiver_operations.try_recv().unwrap();
+        assert_eq!((peer_id, data), (peer_b, vec![4, 5]));
+    }
+}
@@ -0,0 +1,178 @@
+//! zstd compression of the large protocol messages.
+//!
+//! Peers announce whether they support compression with the `Capabilities::COMPRESSION` flag
+//! of their handshake (see `crate::capabilities`), and compression is only used between peers
+//! that both announced it.
+//!
+//! A compressed message is sent with the `MessageTypeId::Compressed` type ID,
+//! followed by the zstd compression of the whole original message (type ID included).
+//! Only the messages larger than `ProtocolConfig::message_compression_threshold` are compressed,
+//! and decompression stops at `ProtocolConfig::max_decompressed_message_size`
+//! to protect against decompression bombs.
+
+use std::io::{self, Read};
+
+use massa_protocol_exports::ProtocolConfig;
+use massa_serialization::{Serializer, U64VarIntSerializer};
+use peernet::{
+    error::{PeerNetError, PeerNetResult},
+    messages::MessagesSerializer as PeerNetMessagesSerializer,
+};
+
+use crate::messages::{Message, MessageTypeId, MessagesSerializer};
+
+/// Compression settings of the messages sent to the peers that negotiated compression
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MessageCompression {
+    /// messages larger than this size (in bytes) are compressed
+    pub threshold: usize,
+    /// zstd compression level
+    pub level: i32,
+}
+
+impl MessageCompression {
+    /// Compression settings of the config, `None` if compression is disabled
+    pub(crate) fn from_config(config: &ProtocolConfig) -> Option<Self> {
+        config.message_compression.then_some(MessageCompression {
+            threshold: config.message_compression_threshold,
+            level: config.message_compression_level,
+        })
+    }
+}
+
+/// Serializer of the messages sent to a peer that negotiated compression:
+/// the messages larger than the threshold are compressed if it makes them smaller
+pub(crate) struct CompressingMessagesSerializer<'a> {
+    pub serializer: &'a MessagesSerializer,
+    pub compression: MessageCompression,
+}
+
+impl PeerNetMessagesSerializer<Message> for CompressingMessagesSerializer<'_> {
+    fn serialize(&self, message: &Message, buffer: &mut Vec<u8>) -> PeerNetResult<()> {
+        let mut raw = Vec::new();
+        self.serializer.serialize(message, &mut raw)?;
+        if raw.len() <= self.compression.threshold {
+            buffer.extend_from_slice(&raw);
+            return Ok(());
+        }
+        let compressed = zstd::bulk::compress(&raw, self.compression.level).map_err(|err| {
+            PeerNetError::HandlerError.error(
+                "CompressingMessagesSerializer",
+                Some(format!("Failed to compress message: {}", err)),
+            )
+        })?;
+        let mut id = Vec::new();
+        U64VarIntSerializer::new()
+            .serialize(&u64::from(MessageTypeId::Compressed), &mut id)
+            .map_err(|err| {
+                PeerNetError::HandlerError.error(
+                    "CompressingMessagesSerializer",
+                    Some(format!("Failed to serialize id {}", err)),
+                )
+            })?;
+        if id.len() + compressed.len() >= raw.len() {
+            // incompressible message
+            buffer.extend_from_slice(&raw);
+        } else {
+            buffer.extend_from_slice(&id);
+            buffer.extend_from_slice(&compressed);
+        }
+        Ok(())
+    }
+}
+
+/// Error while decompressing a message
+#[derive(Debug)]
+pub(crate) enum DecompressionError {
+    /// the decompressed message is larger than the maximum size
+    TooLarge(usize),
+    /// the message is not valid zstd data
+    Invalid(io::Error),
+}
+
+impl std::fmt::Display for DecompressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecompressionError::TooLarge(max_size) => {
+                write!(f, "decompressed message larger than {} bytes", max_size)
+            }
+            DecompressionError::Invalid(err) => write!(f, "invalid compressed message: {}", err),
+        }
+    }
+}
+
+/// Decompress a message, failing if it is larger than `max_size` bytes once decompressed
+pub(crate) fn decompress_message(
+    data: &[u8],
+    max_size: usize,
+) -> Result<Vec<u8>, DecompressionError> {
+    let mut decompressed = Vec::new();
+    zstd::stream::read::Decoder::new(data)
+        .and_then(|decoder| {
+            decoder
+                .take((max_size as u64).saturating_add(1))
+                .read_to_end(&mut decompressed)
+        })
+        .map_err(DecompressionError::Invalid)?;
+    if decompressed.len() > max_size {
+        return Err(DecompressionError::TooLarge(max_size));
+    }
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::operation_handler::{OperationMessage, OperationMessageSerializer};
+    use massa_models::operation::{OperationPrefixId, OperationPrefixIds};
+
+    #[test]
+    fn test_compress_large_messages_only() {
+        let serializer = MessagesSerializer::new()
+            .with_operation_message_serializer(OperationMessageSerializer::new());
+        let compressing = CompressingMessagesSerializer {
+            serializer: &serializer,
+            compression: MessageCompression {
+                threshold: 1024,
+                level: 3,
+            },
+        };
+        let message = |count: usize| {
+            Message::Operation(OperationMessage::OperationsAnnouncement(
+                (0..count as u64)
+                    .map(|index| {
+                        let mut prefix = [7u8; 17];
+                        prefix[..8].copy_from_slice(&index.to_le_bytes());
+                        OperationPrefixId::from(&prefix)
+                    })
+                    .collect::<OperationPrefixIds>(),
+            ))
+        };
+
+        // small messages are sent as is
+        let mut raw = Vec::new();
+        serializer.serialize(&message(1), &mut raw).unwrap();
+        let mut sent = Vec::new();
+        compressing.serialize(&message(1), &mut sent).unwrap();
+        assert_eq!(sent, raw);
+
+        // large messages are compressed and decompressed to the original message
+        let mut raw = Vec::new();
+        serializer.serialize(&message(1000), &mut raw).unwrap();
+        let mut sent = Vec::new();
+        compressing.serialize(&message(1000), &mut sent).unwrap();
+        assert!(sent.len() < raw.len());
+        assert_eq!(sent[0] as u64, u64::from(MessageTypeId::Compressed));
+        assert_eq!(decompress_message(&sent[1..], raw.len()).unwrap(), raw);
+
+        // decompression stops at the maximum size
+        assert!(matches!(
+            decompress_message(&sent[1..], raw.len() - 1),
+            Err(DecompressionError::TooLarge(_))
+        ));
+        assert!(matches!(
+            decompress_message(&raw, raw.len()),
+            Err(DecompressionError::Invalid(_))
+        ));
+    }
+}
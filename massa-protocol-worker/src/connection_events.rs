@@ -0,0 +1,220 @@
+//! Tracking of the connections of the node, to broadcast their events to the operators.
+//!
+//! The handshake records the peers that connect and reports the handshakes that fail.
+//! The peer handler reports the bans and the deprioritized peers, and periodically compares
+//! the tracked peers with the active connections to report the disconnections.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::Arc,
+};
+
+use massa_protocol_exports::{
+    BanReason, ConnectionEvent, DisconnectionReason, HandshakeErrorClass, PeerId,
+    ProtocolBroadcasts,
+};
+use massa_time::MassaTime;
+use parking_lot::RwLock;
+
+/// Time given to a peer that completed its handshake to appear in the active connections.
+/// A peer that never appears is reported as disconnected once it elapsed.
+const CONNECTION_REGISTRATION_GRACE: MassaTime = MassaTime::from_millis(10_000);
+
+/// Connection tracker shared between the handshake and the peer handler
+pub(crate) type SharedConnectionTracker = Arc<RwLock<ConnectionTracker>>;
+
+/// Connection of a tracked peer
+#[derive(Debug, Clone)]
+struct TrackedConnection {
+    /// address of the peer
+    address: SocketAddr,
+    /// time at which the handshake succeeded
+    since: MassaTime,
+    /// whether the peer was already seen in the active connections
+    active: bool,
+    /// reason of the disconnection, if it was decided by us
+    disconnection_reason: Option<DisconnectionReason>,
+}
+
+/// Connections of the node, and the channel on which their events are broadcast
+pub(crate) struct ConnectionTracker {
+    broadcasts: ProtocolBroadcasts,
+    connections: HashMap<PeerId, TrackedConnection>,
+}
+
+impl ConnectionTracker {
+    pub(crate) fn new(broadcasts: ProtocolBroadcasts) -> Self {
+        ConnectionTracker {
+            broadcasts,
+            connections: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn new_shared(broadcasts: ProtocolBroadcasts) -> SharedConnectionTracker {
+        Arc::new(RwLock::new(Self::new(broadcasts)))
+    }
+
+    fn emit(&self, event: ConnectionEvent) {
+        // an error only means that nobody is subscribed
+        let _ = self.broadcasts.connection_event_sender.send(event);
+    }
+
+    /// Whether no connection is tracked
+    pub(crate) fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// A peer completed its handshake
+    pub(crate) fn connected(&mut self, peer_id: PeerId, address: SocketAddr) {
+        if self.connections.contains_key(&peer_id) {
+            // already connected, this connection will be dropped by the network layer
+            return;
+        }
+        let timestamp = MassaTime::now();
+        self.connections.insert(
+            peer_id,
+            TrackedConnection {
+                address,
+                since: timestamp,
+                active: false,
+                disconnection_reason: None,
+            },
+        );
+        self.emit(ConnectionEvent::Connected {
+            peer_id,
+            address,
+            timestamp,
+        });
+    }
+
+    /// A handshake failed
+    pub(crate) fn handshake_failed(
+        &self,
+        address: SocketAddr,
+        peer_id: Option<PeerId>,
+        error_class: HandshakeErrorClass,
+        error: String,
+    ) {
+        self.emit(ConnectionEvent::HandshakeFailed {
+            address,
+            peer_id,
+            error_class,
+            error,
+            timestamp: MassaTime::now(),
+        });
+    }
+
+    /// A peer was banned, its connection is being closed
+    pub(crate) fn banned(&mut self, peer_id: &PeerId, reason: BanReason) {
+        if let Some(connection) = self.connections.get_mut(peer_id) {
+            connection.disconnection_reason = Some(DisconnectionReason::Banned);
+        }
+        self.emit(ConnectionEvent::Banned {
+            peer_id: *peer_id,
+            reason,
+            timestamp: MassaTime::now(),
+        });
+    }
+
+    /// A peer was deprioritized, its connection is being closed
+    pub(crate) fn deprioritized(&mut self, peer_id: &PeerId) {
+        if let Some(connection) = self.connections.get_mut(peer_id) {
+            connection.disconnection_reason = Some(DisconnectionReason::Deprioritized);
+        }
+    }
+
+    /// Compare the tracked connections with the active ones and report the closed connections
+    pub(crate) fn refresh(&mut self, active_peers: &HashSet<PeerId>, now: MassaTime) {
+        let mut closed = Vec::new();
+        for (peer_id, connection) in self.connections.iter_mut() {
+            if active_peers.contains(peer_id) {
+                connection.active = true;
+            } else if connection.active
+                || now.saturating_sub(connection.since) > CONNECTION_REGISTRATION_GRACE
+            {
+                closed.push(*peer_id);
+            }
+        }
+        for peer_id in closed {
+            let Some(connection) = self.connections.remove(&peer_id) else {
+                continue;
+            };
+            self.emit(ConnectionEvent::Disconnected {
+                peer_id,
+                address: connection.address,
+                reason: connection
+                    .disconnection_reason
+                    .unwrap_or(DisconnectionReason::Closed),
+                duration: now.saturating_sub(connection.since),
+                timestamp: now,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_signature::KeyPair;
+
+    fn peer_id() -> PeerId {
+        PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key())
+    }
+
+    #[test]
+    fn test_connection_events() {
+        let broadcasts = ProtocolBroadcasts::new(16);
+        let mut receiver = broadcasts.connection_event_sender.subscribe();
+        let mut tracker = ConnectionTracker::new(broadcasts);
+        let (peer_a, peer_b) = (peer_id(), peer_id());
+        let address: SocketAddr = "127.0.0.1:31245".parse().unwrap();
+
+        tracker.connected(peer_a, address);
+        tracker.connected(peer_b, address);
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(ConnectionEvent::Connected { peer_id, .. }) if peer_id == peer_a
+        ));
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(ConnectionEvent::Connected { peer_id, .. }) if peer_id == peer_b
+        ));
+
+        // both peers are registered in the active connections
+        let now = MassaTime::now();
+        tracker.refresh(&HashSet::from([peer_a, peer_b]), now);
+        assert!(receiver.try_recv().is_err());
+
+        // peer A is banned, peer B closes its connection
+        tracker.banned(&peer_a, BanReason::Reputation);
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(ConnectionEvent::Banned {
+                reason: BanReason::Reputation,
+                ..
+            })
+        ));
+        let later = now.saturating_add(MassaTime::from_millis(5000));
+        tracker.refresh(&HashSet::new(), later);
+        let mut reasons = HashMap::new();
+        while let Ok(ConnectionEvent::Disconnected {
+            peer_id,
+            reason,
+            duration,
+            ..
+        }) = receiver.try_recv()
+        {
+            assert!(duration >= MassaTime::from_millis(5000));
+            reasons.insert(peer_id, reason);
+        }
+        assert_eq!(
+            reasons,
+            HashMap::from([
+                (peer_a, DisconnectionReason::Banned),
+                (peer_b, DisconnectionReason::Closed)
+            ])
+        );
+        assert!(tracker.is_empty());
+    }
+}
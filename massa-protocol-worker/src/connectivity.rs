@@ -14,12 +14,17 @@ use parking_lot::RwLock;
 use peernet::peer::PeerConnectionType;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::{collections::HashMap, net::IpAddr};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    net::IpAddr,
+};
 use std::{thread::JoinHandle, time::Duration};
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
 use crate::handlers::peer_handler::models::ConnectionMetadata;
 use crate::{
+    connection_events::SharedConnectionTracker,
+    dialer::OutboundDialer,
     handlers::peer_handler::models::{InitialPeers, PeerState, SharedPeerDB},
     ip::to_canonical,
     worker::ProtocolChannels,
@@ -29,8 +34,12 @@ use crate::{
     handlers::{
         block_handler::{cache::BlockCache, BlockHandler},
         endorsement_handler::{cache::EndorsementCache, EndorsementHandler},
-        operation_handler::{cache::OperationCache, OperationHandler},
+        operation_handler::{
+            cache::{OperationCache, SharedOperationCache},
+            OperationHandler, OperationSigVerifier,
+        },
         peer_handler::models::PeerMessageTuple,
+        topic_handler::TopicGossipHandler,
     },
     wrap_network::NetworkController,
 };
@@ -70,10 +79,15 @@ pub(crate) fn start_connectivity_thread(
         MassaSender<PeerMessageTuple>,
         MassaReceiver<PeerMessageTuple>,
     ),
+    channel_topics: (
+        MassaSender<PeerMessageTuple>,
+        MassaReceiver<PeerMessageTuple>,
+    ),
     initial_peers: InitialPeers,
     peer_db: SharedPeerDB,
     storage: Storage,
     protocol_channels: ProtocolChannels,
+    connection_tracker: SharedConnectionTracker,
     messages_handler: MessagesHandler,
     peer_categories: HashMap<String, (Vec<IpAddr>, PeerCategoryInfo)>,
     _default_category: PeerCategoryInfo,
@@ -81,6 +95,9 @@ pub(crate) fn start_connectivity_thread(
     mip_store: MipStore,
     massa_metrics: MassaMetrics,
 ) -> Result<(MassaSender<ConnectivityCommand>, JoinHandle<()>), ProtocolError> {
+    let dialer = OutboundDialer::new(&config)?;
+    // shared by the handlers receiving operations, on their own or along with blocks
+    let operation_sig_verifier = Arc::new(OperationSigVerifier::new(&config)?);
     let handle = std::thread::Builder::new()
     .name("protocol-connectivity".to_string())
     .spawn({
@@ -102,16 +119,23 @@ pub(crate) fn start_connectivity_thread(
             std::thread::sleep(Duration::from_millis(100));
 
             // Create cache outside of the op handler because it could be used by other handlers
-            let total_in_slots = config.peers_categories.values().map(|v| v.max_in_connections).sum::<usize>() + config.default_category_info.max_in_connections + 1;
-            let total_out_slots = config.peers_categories.values().map(| v| v.target_out_connections).sum::<usize>() + config.default_category_info.target_out_connections + 1;
             let operation_cache = Arc::new(RwLock::new(OperationCache::new(
                 config.max_known_ops_size.try_into().unwrap(),
                 config.max_node_known_ops_size.try_into().unwrap()
             )));
-            let endorsement_cache = Arc::new(RwLock::new(EndorsementCache::new(
-                config.max_known_endorsements_size.try_into().unwrap(),
-                (total_in_slots + total_out_slots).try_into().unwrap()
-            )));
+            load_operation_seen_cache(&config, &operation_cache);
+            let endorsement_cache = Arc::new(EndorsementCache::new(
+                config.max_known_endorsements_size.try_into().unwrap()
+            ).with_equivocation_hook(Box::new(|equivocation| {
+                warn!(
+                    "endorsement producer {} endorsed several blocks at slot {} index {}: endorsements {} and {}",
+                    equivocation.producer,
+                    equivocation.slot,
+                    equivocation.index,
+                    equivocation.first_endorsement,
+                    equivocation.conflicting_endorsement
+                );
+            })));
 
             let block_cache = Arc::new(RwLock::new(BlockCache::new(
                 config.max_known_blocks_size.try_into().unwrap(),
@@ -125,6 +149,7 @@ pub(crate) fn start_connectivity_thread(
                 peer_db.clone(),
                 channel_peers,
                 protocol_channels.peer_management_handler,
+                connection_tracker,
                 messages_handler,
                 network_controller.get_active_connections(),
                 peer_categories.iter().map(|(key, value)|(key.clone(), (value.0.clone(), value.1.target_out_connections))).collect(),
@@ -145,6 +170,7 @@ pub(crate) fn start_connectivity_thread(
                 sender_operations_propagation_ext.clone(),
                 protocol_channels.operation_handler_propagation.1.clone(),
                 peer_management_handler.sender.command_sender.clone(),
+                operation_sig_verifier.clone(),
                 massa_metrics.clone(),
             );
             let mut endorsement_handler = EndorsementHandler::new(
@@ -177,16 +203,27 @@ pub(crate) fn start_connectivity_thread(
                 peer_management_handler.sender.command_sender.clone(),
                 config.clone(),
                 endorsement_cache,
-                operation_cache,
+                operation_cache.clone(),
+                operation_sig_verifier,
                 block_cache,
                 storage.clone_without_refs(),
                 mip_store,
                 massa_metrics.clone(),
             );
 
+            let mut topic_handler = TopicGossipHandler::new(
+                config.clone(),
+                network_controller.get_active_connections(),
+                channel_topics.1,
+                protocol_channels.topic_handler.0.clone(),
+                protocol_channels.topic_handler.1.clone(),
+                peer_management_handler.sender.command_sender.clone(),
+            );
+
             let tick_metrics = tick(massa_metrics.tick_delay);
             let tick_try_connect = tick(config.try_connection_timer.to_duration());
             let tick_unban_everyone = tick(config.unban_everyone_timer.to_duration());
+            let tick_rotate_outbound = tick(config.outbound_rotation_interval.to_duration());
 
             //Try to connect to peers
             loop {
@@ -201,10 +238,13 @@ pub(crate) fn start_connectivity_thread(
                                 debug!("Stopped network controller");
                                 operation_handler.stop();
                                 debug!("Stopped operation handler");
+                                save_operation_seen_cache(&config, &operation_cache);
                                 endorsement_handler.stop();
                                 debug!("Stopped endorsement handler");
                                 block_handler.stop();
                                 debug!("Stopped block handler");
+                                topic_handler.stop();
+                                debug!("Stopped topic handler");
                                 peer_management_handler.stop();
                                 debug!("Stopped peer handler");
                                 break;
@@ -217,14 +257,20 @@ pub(crate) fn start_connectivity_thread(
                                     let peer_db_read = peer_db.read();
                                     (peer_db_read.get_banned_peer_count(), peer_db_read.get_known_peer_count())
                                 };
+                                let peers_connected = network_controller.get_active_connections().get_peers_connected();
+                                let mut peers_per_category: BTreeMap<String, u64> = BTreeMap::new();
+                                for (_, _, category) in peers_connected.values() {
+                                    *peers_per_category.entry(category.clone().unwrap_or_else(|| "default".to_string())).or_default() += 1;
+                                }
                                 let stats = NetworkStats {
                                     active_node_count,
                                     in_connection_count,
                                     out_connection_count,
                                     banned_peer_count,
                                     known_peer_count,
+                                    peers_per_category,
                                 };
-                                let peers: HashMap<PeerId, (SocketAddr, PeerConnectionType)> = network_controller.get_active_connections().get_peers_connected().into_iter().map(|(peer_id, peer)| {
+                                let peers: HashMap<PeerId, (SocketAddr, PeerConnectionType)> = peers_connected.into_iter().map(|(peer_id, peer)| {
                                     (peer_id, (peer.0, peer.1))
                                 }).collect();
                                 responder.try_send((stats, peers)).unwrap_or_else(|_| warn!("Failed to send stats to responder"));
@@ -248,154 +294,225 @@ pub(crate) fn start_connectivity_thread(
                     },
                     recv(tick_try_connect) -> _ => {
                         let active_conn = network_controller.get_active_connections();
-                        let peers_connected = active_conn.get_peers_connected();
-                        let peers_connection_queue = active_conn.get_peer_ids_out_connection_queue();
+                        connect_to_peers(
+                            &config,
+                            &peer_categories,
+                            &dialer,
+                            &peer_db,
+                            &active_conn.get_peers_connected(),
+                            &active_conn.get_peer_ids_out_connection_queue(),
+                            |addr| try_connect_peer(addr, &mut network_controller, &peer_db, &config),
+                        );
+                    }
+                    recv(tick_rotate_outbound) -> _ => {
+                        let mut active_conn = network_controller.get_active_connections();
+                        let rotated = dialer.select_rotation(&active_conn.get_peers_connected());
+                        if !rotated.is_empty() {
+                            debug!("Rotating {} outbound connections", rotated.len());
+                        }
+                        for peer_id in rotated {
+                            active_conn.shutdown_connection(&peer_id);
+                        }
+                    }
+                    recv(tick_unban_everyone) -> _ => {
+                        debug!("Periodic unban of every peer");
+                        let mut peer_db_write = peer_db.write();
+                        for (peer_id, peer_status) in peer_db_write.get_peers().clone() {
+                            if peer_status.state == PeerState::Banned {
+                                peer_db_write.unban_peer(&peer_id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }).expect("OS failed to start connectivity thread");
 
-                        let mut connection_slots = HashMap::new();
-                        connection_slots.insert("default", config.default_category_info.target_out_connections);
-                        for (category, infos) in peer_categories.iter() {
-                            connection_slots.insert(category, infos.1.target_out_connections);
+    // Start controller
+    Ok((protocol_channels.connectivity_thread.0, handle))
+}
+
+/// Connect to the best peers of the peer database, trying to fill the free outbound slots of each category.
+/// `try_connect` dials an address and succeeds if the connection is in progress.
+pub(crate) fn connect_to_peers(
+    config: &ProtocolConfig,
+    peer_categories: &HashMap<String, (Vec<IpAddr>, PeerCategoryInfo)>,
+    dialer: &OutboundDialer,
+    peer_db: &SharedPeerDB,
+    peers_connected: &HashMap<PeerId, (SocketAddr, PeerConnectionType, Option<String>)>,
+    peers_connection_queue: &HashSet<SocketAddr>,
+    mut try_connect: impl FnMut(SocketAddr) -> Result<(), ProtocolError>,
+) {
+    let mut connection_slots = HashMap::new();
+    connection_slots.insert(
+        "default",
+        config.default_category_info.target_out_connections,
+    );
+    for (category, infos) in peer_categories.iter() {
+        connection_slots.insert(category, infos.1.target_out_connections);
+    }
+
+    // Get all the addresses we can connect to, without any filter or prioritization done yet
+    let mut addresses_can_connect = Vec::new();
+    {
+        let peer_db_read = peer_db.read();
+        for (peer_id, peer_info) in peer_db_read.get_peers() {
+            // If peer already connected, decrement the slots for the given category, or default category if none
+            if let Some(peer) = peers_connected.get(peer_id) {
+                if peer.1 == PeerConnectionType::OUT {
+                    if let Some(ref peer_category) = &peer.2 {
+                        if let Some(slots) = connection_slots.get_mut(peer_category.as_str()) {
+                            *slots = slots.saturating_sub(1);
+                        } else {
+                            tracing::log::warn!("Category of connected peer {peer_category} not known in configuration");
                         }
+                    } else {
+                        let slots = connection_slots.get_mut("default").unwrap();
+                        *slots = slots.saturating_sub(1);
+                    }
+                }
+                continue;
+            }
 
-                        // Get all the addresses we can connect to, without any filter or prioritization done yet
-                        let mut addresses_can_connect  = Vec::new();
-                        {
-                            let peer_db_read = peer_db.read();
-                            for (peer_id, peer_info) in peer_db_read.get_peers() {
-
-                                // If peer already connected, decrement the slots for the given category, or default category if none
-                                if let Some(peer) = peers_connected.get(peer_id) {
-                                    if peer.1 == PeerConnectionType::OUT {
-                                        if let Some(ref peer_category) = &peer.2 {
-                                            if let Some(slots) = connection_slots.get_mut(peer_category.as_str()) {
-                                                *slots = slots.saturating_sub(1);
-                                            } else {
-                                                tracing::log::warn!("Category of connected peer {peer_category} not known in configuration"); 
-                                            }
-                                        } else {
-                                            let slots = connection_slots.get_mut("default").unwrap();
-                                            *slots = slots.saturating_sub(1);
-                                        }
-                                    }
-                                    continue;
-                                }
+            if peer_info.state == PeerState::Trusted {
+                if let Some(ref last_announce) = peer_info.last_announce {
+                    if last_announce.listeners.is_empty() {
+                        continue;
+                    }
+
+                    if let Some((addr, _)) = last_announce.listeners.iter().next() {
+                        let canonical_ip = to_canonical(addr.ip());
+                        let mut allowed_local_ips = false;
+                        // Check if the peer is in a category and we didn't reached out target yet
+                        let mut category_found = None;
+                        for (name, (ips, cat)) in peer_categories {
+                            if ips.contains(&canonical_ip) {
+                                category_found = Some(name);
+                                allowed_local_ips = cat.allow_local_peers;
+                            }
+                        }
 
-                                if peer_info.state == PeerState::Trusted {
-                                    if let Some(ref last_announce) = peer_info.last_announce {
-                                        if last_announce.listeners.is_empty() {
-                                            continue;
-                                        }
-
-                                        if let Some((addr, _)) = last_announce.listeners.iter().next() {
-                                            let canonical_ip = to_canonical(addr.ip());
-                                            let mut allowed_local_ips = false;
-                                            // Check if the peer is in a category and we didn't reached out target yet
-                                            let mut category_found = None;
-                                            for (name, (ips, cat)) in &peer_categories {
-                                                if ips.contains(&canonical_ip) {
-                                                    category_found = Some(name);
-                                                    allowed_local_ips = cat.allow_local_peers;
-                                                }
-                                            }
-
-                                            if peers_connection_queue.contains(addr) {
-                                                if let Some(peer_category) = category_found {
-                                                    if let Some(slots) = connection_slots.get_mut(peer_category.as_str()) {
-                                                        *slots = slots.saturating_sub(1);
-                                                    }
-                                                } else if let Some(v) = connection_slots.get_mut("default") {
-                                                    *v = v.saturating_sub(1);
-                                                }
-                                                continue;
-                                            }
-
-                                            let connection_metadata = peer_db_read.get_connection_metadata_or_default(addr);
-
-                                            // check if the peer last connect attempt has not been too recent
-                                            if let ConnectionMetadata { last_try_connect: Some(lt), .. } = connection_metadata {
-                                                let last_try_connect = lt.estimate_instant().expect("Time went backward");
-                                                if last_try_connect.elapsed() < config.try_connection_timer_same_peer.to_duration() {
-                                                    continue;
-                                                }
-                                            }
-
-                                            if config.listeners.iter().any(|(local_addr, _transport)| addr == local_addr) {
-                                                continue;
-                                            }
-
-                                            if !global(&canonical_ip) && !allowed_local_ips {
-                                                continue;
-                                            }
-
-                                            addresses_can_connect.push((*addr, connection_metadata, category_found));
-                                        } else {
-                                            tracing::log::warn!("No listeners for the peer {peer_id}"); 
-                                        }
-                                    }
+                        if peers_connection_queue.contains(addr) {
+                            if let Some(peer_category) = category_found {
+                                if let Some(slots) =
+                                    connection_slots.get_mut(peer_category.as_str())
+                                {
+                                    *slots = slots.saturating_sub(1);
                                 }
+                            } else if let Some(v) = connection_slots.get_mut("default") {
+                                *v = v.saturating_sub(1);
                             }
+                            continue;
                         }
 
-                        // Sort addresses using the metadata
-                        addresses_can_connect.sort_by(|a, b| a.1.cmp(&b.1));
+                        let connection_metadata =
+                            peer_db_read.get_connection_metadata_or_default(addr);
 
-                        // Connect to the given addresses, trying to fill all the slots available
-                        let mut addresses_connected = vec![];
-                        for (addr, _, category) in addresses_can_connect.iter() {
-                            if addresses_connected.contains(addr) {
+                        // check if the peer last connect attempt has not been too recent
+                        if let ConnectionMetadata {
+                            last_try_connect: Some(lt),
+                            ..
+                        } = connection_metadata
+                        {
+                            let last_try_connect =
+                                lt.estimate_instant().expect("Time went backward");
+                            if last_try_connect.elapsed()
+                                < config.try_connection_timer_same_peer.to_duration()
+                            {
                                 continue;
                             }
+                        }
 
-                            // Connect to the peer
-                            match category {
-                                // In case has a special category
-                                Some(cat) => {
-                                    for (name, slots) in connection_slots.iter_mut() {
-                                        if name == *cat && *slots > 0 {
-                                            // In case the connection succeeds, we take a place in a slot
-                                            if try_connect_peer(*addr, &mut network_controller, &peer_db, &config).is_ok() {
-                                                *slots = slots.saturating_sub(1);
-                                                addresses_connected.push(*addr);
-                                            }
-                                        }
-                                    }
-                                }
-
-                                // Default category
-                                None if connection_slots["default"] > 0 => {
-                                    // In case the connection succeeds, we take a place in a slot
-                                    if try_connect_peer(*addr, &mut network_controller, &peer_db, &config).is_err() {
-                                        if let Some(v) = connection_slots.get_mut("default") {
-                                            *v = v.saturating_sub(1);
-                                        }
-                                        addresses_connected.push(*addr);
-                                    }
-                                }
-                                None => continue,
-                            }
+                        if config
+                            .listeners
+                            .iter()
+                            .any(|(local_addr, _transport)| addr == local_addr)
+                        {
+                            continue;
+                        }
 
-                            // IF all slots are filled, stop
-                            if connection_slots.values().sum::<usize>() == 0 {
-                                break;
-                            }
+                        if !global(&canonical_ip) && !allowed_local_ips {
+                            continue;
                         }
+
+                        addresses_can_connect.push((*addr, connection_metadata, category_found));
+                    } else {
+                        tracing::log::warn!("No listeners for the peer {peer_id}");
                     }
-                    recv(tick_unban_everyone) -> _ => {
-                        debug!("Periodic unban of every peer");
-                        let mut peer_db_write = peer_db.write();
-                        for (peer_id, peer_status) in peer_db_write.get_peers().clone() {
-                            if peer_status.state == PeerState::Banned {
-                                peer_db_write.unban_peer(&peer_id);
-                            }
+                }
+            }
+        }
+    }
+
+    // Sort addresses using the metadata
+    addresses_can_connect.sort_by(|a, b| a.1.cmp(&b.1));
+
+    // Spread the connections of the default category over the network groups
+    let (mut addresses_can_connect, default_addresses): (Vec<_>, Vec<_>) = addresses_can_connect
+        .into_iter()
+        .partition(|(_, _, category)| category.is_some());
+    let group_counts = dialer.outbound_group_counts(
+        peers_connected
+            .values()
+            .filter(|(_, connection_type, category)| {
+                *connection_type == PeerConnectionType::OUT && category.is_none()
+            })
+            .map(|(addr, _, _)| addr)
+            .chain(peers_connection_queue.iter()),
+    );
+    let default_addresses = dialer.order_by_diversity(
+        default_addresses
+            .into_iter()
+            .map(|(addr, metadata, category)| (addr, (metadata, category)))
+            .collect(),
+        group_counts,
+    );
+    addresses_can_connect.extend(
+        default_addresses
+            .into_iter()
+            .map(|(addr, (metadata, category))| (addr, metadata, category)),
+    );
+
+    // Connect to the given addresses, trying to fill all the slots available
+    let mut addresses_connected = vec![];
+    for (addr, _, category) in addresses_can_connect.iter() {
+        if addresses_connected.contains(addr) {
+            continue;
+        }
+
+        // Connect to the peer
+        match category {
+            // In case has a special category
+            Some(cat) => {
+                for (name, slots) in connection_slots.iter_mut() {
+                    if name == *cat && *slots > 0 {
+                        // In case the connection succeeds, we take a place in a slot
+                        if try_connect(*addr).is_ok() {
+                            *slots = slots.saturating_sub(1);
+                            addresses_connected.push(*addr);
                         }
                     }
                 }
             }
+
+            // Default category
+            None if connection_slots["default"] > 0 => {
+                // In case the connection succeeds, we take a place in a slot
+                if try_connect(*addr).is_ok() {
+                    if let Some(v) = connection_slots.get_mut("default") {
+                        *v = v.saturating_sub(1);
+                    }
+                    addresses_connected.push(*addr);
+                }
+            }
+            None => continue,
         }
-    }).expect("OS failed to start connectivity thread");
 
-    // Start controller
-    Ok((protocol_channels.connectivity_thread.0, handle))
+        // IF all slots are filled, stop
+        if connection_slots.values().sum::<usize>() == 0 {
+            break;
+        }
+    }
 }
 
 // Attempt to connect to peer
@@ -418,3 +535,50 @@ fn try_connect_peer(
     }
     conn_res
 }
+
+// Reload the operation prefixes checked before the last stop, if enabled
+fn load_operation_seen_cache(config: &ProtocolConfig, operation_cache: &SharedOperationCache) {
+    let Some(path) = &config.operation_seen_cache_path else {
+        return;
+    };
+    if !path.exists() {
+        return;
+    }
+    match operation_cache
+        .write()
+        .load_checked_prefixes(path, config.operation_seen_cache_max_age)
+    {
+        Ok(count) => info!(
+            "loaded {} recently checked operation prefixes from {}",
+            count,
+            path.display()
+        ),
+        Err(err) => warn!(
+            "could not load the checked operation prefixes from {}: {}",
+            path.display(),
+            err
+        ),
+    }
+}
+
+// Save the recently checked operation prefixes so that they are not asked again after a restart, if enabled
+fn save_operation_seen_cache(config: &ProtocolConfig, operation_cache: &SharedOperationCache) {
+    let Some(path) = &config.operation_seen_cache_path else {
+        return;
+    };
+    match operation_cache
+        .read()
+        .save_checked_prefixes(path, config.operation_seen_cache_max_age)
+    {
+        Ok(count) => debug!(
+            "saved {} recently checked operation prefixes to {}",
+            count,
+            path.display()
+        ),
+        Err(err) => warn!(
+            "could not save the checked operation prefixes to {}: {}",
+            path.display(),
+            err
+        ),
+    }
+}
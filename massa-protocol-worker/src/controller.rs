@@ -1,4 +1,4 @@
-use std::{collections::HashMap, net::SocketAddr, time::Duration};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
 
 use massa_channel::{sender::MassaSender, MassaChannel};
 use massa_models::{
@@ -7,7 +7,10 @@ use massa_models::{
     prehash::{PreHashMap, PreHashSet},
     stats::NetworkStats,
 };
-use massa_protocol_exports::{BootstrapPeers, PeerId, ProtocolController, ProtocolError};
+use massa_protocol_exports::{
+    BootstrapPeers, PeerId, PeerRecord, ProtocolController, ProtocolError, TopicConfig,
+    TopicHandler, TopicId,
+};
 use massa_storage::Storage;
 use peernet::peer::PeerConnectionType;
 
@@ -21,6 +24,7 @@ use crate::{
         endorsement_handler::commands_propagation::EndorsementHandlerPropagationCommand,
         operation_handler::commands_propagation::OperationHandlerPropagationCommand,
         peer_handler::models::PeerManagementCmd,
+        topic_handler::commands::TopicHandlerCommand,
     },
 };
 
@@ -37,6 +41,7 @@ pub struct ProtocolControllerImpl {
     pub sender_endorsement_handler: Option<MassaSender<EndorsementHandlerPropagationCommand>>,
     pub sender_connectivity_thread: Option<MassaSender<ConnectivityCommand>>,
     pub sender_peer_management_thread: Option<MassaSender<PeerManagementCmd>>,
+    pub sender_topic_handler: Option<MassaSender<TopicHandlerCommand>>,
 }
 
 impl ProtocolControllerImpl {
@@ -47,6 +52,7 @@ impl ProtocolControllerImpl {
         sender_endorsement_handler: MassaSender<EndorsementHandlerPropagationCommand>,
         sender_connectivity_thread: MassaSender<ConnectivityCommand>,
         sender_peer_management_thread: MassaSender<PeerManagementCmd>,
+        sender_topic_handler: MassaSender<TopicHandlerCommand>,
     ) -> Self {
         ProtocolControllerImpl {
             sender_block_retrieval_handler: Some(sender_block_retrieval_handler),
@@ -55,6 +61,7 @@ impl ProtocolControllerImpl {
             sender_endorsement_handler: Some(sender_endorsement_handler),
             sender_connectivity_thread: Some(sender_connectivity_thread),
             sender_peer_management_thread: Some(sender_peer_management_thread),
+            sender_topic_handler: Some(sender_topic_handler),
         }
     }
 }
@@ -65,6 +72,7 @@ impl ProtocolController for ProtocolControllerImpl {
         drop(self.sender_operation_handler.take());
         drop(self.sender_endorsement_handler.take());
         drop(self.sender_block_retrieval_handler.take());
+        drop(self.sender_topic_handler.take());
     }
 
     /// Sends the order to propagate the header of a block
@@ -186,6 +194,40 @@ impl ProtocolController for ProtocolControllerImpl {
         })
     }
 
+    fn get_peer_records(&self) -> Result<Vec<PeerRecord>, ProtocolError> {
+        let (sender, receiver) = MassaChannel::new("get_peer_records".to_string(), Some(1));
+        self.sender_peer_management_thread
+            .as_ref()
+            .unwrap()
+            .try_send(PeerManagementCmd::GetPeerRecords { responder: sender })
+            .map_err(|_| {
+                ProtocolError::ChannelError("get_peer_records command send error".into())
+            })?;
+        receiver.recv_timeout(Duration::from_secs(10)).map_err(|_| {
+            ProtocolError::ChannelError("get_peer_records command receive error".into())
+        })
+    }
+
+    fn register_topic(
+        &self,
+        config: TopicConfig,
+        handler: Arc<dyn TopicHandler>,
+    ) -> Result<(), ProtocolError> {
+        self.sender_topic_handler
+            .as_ref()
+            .unwrap()
+            .try_send(TopicHandlerCommand::Register { config, handler })
+            .map_err(|_| ProtocolError::ChannelError("register_topic command send error".into()))
+    }
+
+    fn publish_on_topic(&self, topic: TopicId, payload: Vec<u8>) -> Result<(), ProtocolError> {
+        self.sender_topic_handler
+            .as_ref()
+            .unwrap()
+            .try_send(TopicHandlerCommand::Publish { topic, payload })
+            .map_err(|_| ProtocolError::ChannelError("publish_on_topic command send error".into()))
+    }
+
     fn clone_box(&self) -> Box<dyn ProtocolController> {
         Box::new(self.clone())
     }
@@ -0,0 +1,359 @@
+//! Selection of the outbound peers, spreading the connections over network groups to reduce the eclipse risk.
+//!
+//! An attacker usually controls many addresses in a few subnets or autonomous systems. The peers are grouped
+//! by autonomous system if an ASN map is configured, and by /16 subnet (/32 for IPv6) otherwise, and the dialer
+//! prefers the candidates of the groups with the fewest outbound connections, up to `outbound_max_per_group`
+//! connections per group. Among the candidates of equally represented groups, the ranking of the peer database
+//! (last successes and failures) is kept.
+//!
+//! A fraction of the outbound connections of the default category is periodically closed, from the most
+//! represented groups first, so that the freed slots are filled again by the dialer with more diverse peers.
+//!
+//! The ASN map is a text file with one `<network>/<prefix length> <ASN>` entry per line, the ASN being
+//! optionally prefixed by `AS`. Empty lines and lines starting with `#` are ignored.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::{IpAddr, SocketAddr},
+    path::Path,
+};
+
+use ip_rfc::global;
+use massa_protocol_exports::{PeerId, ProtocolConfig, ProtocolError};
+use peernet::peer::PeerConnectionType;
+
+use crate::ip::to_canonical;
+
+/// Length of the IPv4 prefixes grouped together when the ASN is unknown
+const IPV4_GROUP_PREFIX_LENGTH: u8 = 16;
+/// Length of the IPv6 prefixes grouped together when the ASN is unknown
+const IPV6_GROUP_PREFIX_LENGTH: u8 = 32;
+
+/// Group of network addresses likely to be controlled by the same operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum NetworkGroup {
+    /// autonomous system
+    Asn(u32),
+    /// subnet of the address, when its ASN is unknown
+    Subnet(IpAddr),
+    /// non-routable address, in its own group
+    Local(IpAddr),
+}
+
+/// Network prefix of an address, as an integer
+fn prefix_bits(ip: &IpAddr, length: u8) -> u128 {
+    let (bits, size) = match ip {
+        IpAddr::V4(ip) => (u32::from(*ip) as u128, 32u32),
+        IpAddr::V6(ip) => (u128::from(*ip), 128u32),
+    };
+    let host_bits = size - (length as u32).min(size);
+    bits.checked_shr(host_bits)
+        .map_or(0, |prefix| prefix << host_bits)
+}
+
+/// Map of the network prefixes to their autonomous system
+#[derive(Debug, Default)]
+pub(crate) struct AsnMap {
+    /// ASN of the IPv4 prefixes by prefix length, longest first
+    v4: BTreeMap<std::cmp::Reverse<u8>, HashMap<u128, u32>>,
+    /// ASN of the IPv6 prefixes by prefix length, longest first
+    v6: BTreeMap<std::cmp::Reverse<u8>, HashMap<u128, u32>>,
+}
+
+impl AsnMap {
+    /// Parse an ASN map
+    pub fn parse(content: &str) -> Result<Self, ProtocolError> {
+        let mut map = AsnMap::default();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                ProtocolError::GeneralProtocolError(format!(
+                    "invalid ASN map entry at line {}: {}",
+                    index + 1,
+                    line
+                ))
+            };
+            let (prefix, asn) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let (network, length) = prefix.split_once('/').ok_or_else(invalid)?;
+            let network: IpAddr = network.parse().map_err(|_| invalid())?;
+            let length: u8 = length.parse().map_err(|_| invalid())?;
+            let asn = asn.trim();
+            let asn: u32 = asn
+                .strip_prefix("AS")
+                .unwrap_or(asn)
+                .parse()
+                .map_err(|_| invalid())?;
+            let prefixes = match network {
+                IpAddr::V4(_) if length <= 32 => &mut map.v4,
+                IpAddr::V6(_) if length <= 128 => &mut map.v6,
+                _ => return Err(invalid()),
+            };
+            prefixes
+                .entry(std::cmp::Reverse(length))
+                .or_default()
+                .insert(prefix_bits(&network, length), asn);
+        }
+        Ok(map)
+    }
+
+    /// Load an ASN map from a file
+    pub fn load(path: &Path) -> Result<Self, ProtocolError> {
+        AsnMap::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// ASN of the longest prefix containing the address
+    pub fn lookup(&self, ip: &IpAddr) -> Option<u32> {
+        let prefixes = match ip {
+            IpAddr::V4(_) => &self.v4,
+            IpAddr::V6(_) => &self.v6,
+        };
+        prefixes
+            .iter()
+            .find_map(|(length, asns)| asns.get(&prefix_bits(ip, length.0)).copied())
+    }
+}
+
+/// Selection of the outbound peers
+pub(crate) struct OutboundDialer {
+    /// autonomous systems of the network prefixes, if configured
+    asn_map: Option<AsnMap>,
+    /// maximum number of outbound connections per network group
+    max_per_group: usize,
+    /// percentage of the outbound connections of the default category closed at each rotation
+    rotation_percent: u64,
+}
+
+impl OutboundDialer {
+    pub fn new(config: &ProtocolConfig) -> Result<Self, ProtocolError> {
+        let asn_map = match &config.outbound_asn_map_path {
+            Some(path) => Some(AsnMap::load(path)?),
+            None => None,
+        };
+        Ok(OutboundDialer {
+            asn_map,
+            max_per_group: config.outbound_max_per_group,
+            rotation_percent: config.outbound_rotation_percent,
+        })
+    }
+
+    /// Network group of an address
+    pub fn group(&self, ip: IpAddr) -> NetworkGroup {
+        let ip = to_canonical(ip);
+        if !global(&ip) {
+            return NetworkGroup::Local(ip);
+        }
+        if let Some(asn) = self.asn_map.as_ref().and_then(|map| map.lookup(&ip)) {
+            return NetworkGroup::Asn(asn);
+        }
+        let length = match ip {
+            IpAddr::V4(_) => IPV4_GROUP_PREFIX_LENGTH,
+            IpAddr::V6(_) => IPV6_GROUP_PREFIX_LENGTH,
+        };
+        let subnet = match ip {
+            IpAddr::V4(_) => IpAddr::V4((prefix_bits(&ip, length) as u32).into()),
+            IpAddr::V6(_) => IpAddr::V6(prefix_bits(&ip, length).into()),
+        };
+        NetworkGroup::Subnet(subnet)
+    }
+
+    /// Number of outbound connections (established or queued) per network group
+    pub fn outbound_group_counts<'a>(
+        &self,
+        outbound_addrs: impl IntoIterator<Item = &'a SocketAddr>,
+    ) -> HashMap<NetworkGroup, usize> {
+        let mut counts = HashMap::new();
+        for addr in outbound_addrs {
+            *counts.entry(self.group(addr.ip())).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Order the candidates, ranked by the peer database, so that the groups with the fewest outbound
+    /// connections come first, assuming that each candidate will be connected.
+    /// Candidates of groups that would exceed `max_per_group` connections are dropped.
+    pub fn order_by_diversity<T>(
+        &self,
+        candidates: Vec<(SocketAddr, T)>,
+        mut group_counts: HashMap<NetworkGroup, usize>,
+    ) -> Vec<(SocketAddr, T)> {
+        // candidates of each group, in ranking order
+        let mut by_group: HashMap<NetworkGroup, Vec<(usize, (SocketAddr, T))>> = HashMap::new();
+        for (rank, candidate) in candidates.into_iter().enumerate() {
+            by_group
+                .entry(self.group(candidate.0.ip()))
+                .or_default()
+                .push((rank, candidate));
+        }
+        for group_candidates in by_group.values_mut() {
+            group_candidates.reverse();
+        }
+
+        let mut ordered = Vec::new();
+        loop {
+            // least represented group, then best ranked candidate
+            let next = by_group
+                .iter()
+                .filter_map(|(group, group_candidates)| {
+                    let count = group_counts.get(group).copied().unwrap_or_default();
+                    let (rank, _) = group_candidates.last()?;
+                    (count < self.max_per_group).then_some((count, *rank, *group))
+                })
+                .min();
+            let Some((_, _, group)) = next else {
+                break;
+            };
+            let group_candidates = by_group.get_mut(&group).expect("group should exist");
+            let (_, candidate) = group_candidates
+                .pop()
+                .expect("group should have candidates");
+            if group_candidates.is_empty() {
+                by_group.remove(&group);
+            }
+            *group_counts.entry(group).or_default() += 1;
+            ordered.push(candidate);
+        }
+        ordered
+    }
+
+    /// Select the outbound connections of the default category to close for rotation,
+    /// from the most represented groups first
+    pub fn select_rotation(
+        &self,
+        peers_connected: &HashMap<PeerId, (SocketAddr, PeerConnectionType, Option<String>)>,
+    ) -> Vec<PeerId> {
+        let mut outbound: Vec<(PeerId, SocketAddr)> = peers_connected
+            .iter()
+            .filter(|(_, (_, connection_type, category))| {
+                *connection_type == PeerConnectionType::OUT && category.is_none()
+            })
+            .map(|(peer_id, (addr, _, _))| (*peer_id, *addr))
+            .collect();
+        let count = (outbound.len() as u64)
+            .saturating_mul(self.rotation_percent)
+            .div_ceil(100) as usize;
+        if count == 0 {
+            return Vec::new();
+        }
+        let group_counts = self.outbound_group_counts(outbound.iter().map(|(_, addr)| addr));
+        outbound.sort_by_key(|(peer_id, addr)| {
+            let count = group_counts
+                .get(&self.group(addr.ip()))
+                .copied()
+                .unwrap_or_default();
+            (std::cmp::Reverse(count), *peer_id)
+        });
+        outbound
+            .into_iter()
+            .take(count)
+            .map(|(peer_id, _)| peer_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_signature::KeyPair;
+
+    fn dialer(
+        max_per_group: usize,
+        rotation_percent: u64,
+        asn_map: Option<&str>,
+    ) -> OutboundDialer {
+        OutboundDialer {
+            asn_map: asn_map.map(|content| AsnMap::parse(content).unwrap()),
+            max_per_group,
+            rotation_percent,
+        }
+    }
+
+    fn addrs(ordered: Vec<(SocketAddr, ())>) -> Vec<String> {
+        ordered
+            .into_iter()
+            .map(|(addr, _)| addr.ip().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_dialer_groups() {
+        let dialer = dialer(
+            2,
+            0,
+            Some("# test map\n1.2.0.0/16 AS100\n1.2.3.0/24 200\n2001:db8::/32 AS300\n"),
+        );
+        assert_eq!(
+            dialer.group("1.2.3.4".parse().unwrap()),
+            NetworkGroup::Asn(200)
+        );
+        assert_eq!(
+            dialer.group("1.2.4.4".parse().unwrap()),
+            NetworkGroup::Asn(100)
+        );
+        assert_eq!(
+            dialer.group("5.6.7.8".parse().unwrap()),
+            NetworkGroup::Subnet("5.6.0.0".parse().unwrap())
+        );
+        assert_eq!(
+            dialer.group("::ffff:5.6.1.1".parse().unwrap()),
+            NetworkGroup::Subnet("5.6.0.0".parse().unwrap())
+        );
+        assert_eq!(
+            dialer.group("192.168.1.1".parse().unwrap()),
+            NetworkGroup::Local("192.168.1.1".parse().unwrap())
+        );
+        assert!(AsnMap::parse("1.2.0.0/33 100").is_err());
+        assert!(AsnMap::parse("1.2.0.0 100").is_err());
+    }
+
+    #[test]
+    fn test_dialer_orders_by_diversity() {
+        let dialer = dialer(2, 0, None);
+        let candidates = ["5.6.0.1", "5.6.0.2", "5.6.0.3", "7.8.0.1", "9.9.0.1"]
+            .iter()
+            .map(|ip| (SocketAddr::new(ip.parse().unwrap(), 31244), ()))
+            .collect();
+        // 9.9/16 already has an outbound connection
+        let group_counts =
+            dialer.outbound_group_counts([&"9.9.1.1:31244".parse::<SocketAddr>().unwrap()]);
+        assert_eq!(
+            addrs(dialer.order_by_diversity(candidates, group_counts)),
+            vec!["5.6.0.1", "7.8.0.1", "5.6.0.2", "9.9.0.1"]
+        );
+    }
+
+    #[test]
+    fn test_dialer_rotation() {
+        let peers: HashMap<PeerId, (SocketAddr, PeerConnectionType, Option<String>)> = [
+            ("5.6.0.1", PeerConnectionType::OUT, None),
+            ("5.6.0.2", PeerConnectionType::OUT, None),
+            ("7.8.0.1", PeerConnectionType::OUT, None),
+            ("5.6.0.3", PeerConnectionType::IN, None),
+            (
+                "5.6.0.4",
+                PeerConnectionType::OUT,
+                Some("Bootstrap".to_string()),
+            ),
+        ]
+        .into_iter()
+        .map(|(ip, connection_type, category)| {
+            (
+                PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key()),
+                (
+                    SocketAddr::new(ip.parse().unwrap(), 31244),
+                    connection_type,
+                    category,
+                ),
+            )
+        })
+        .collect();
+        assert!(dialer(2, 0, None).select_rotation(&peers).is_empty());
+        // 30% of the 3 rotatable connections rounded up, from the most represented group
+        let rotated = dialer(2, 30, None).select_rotation(&peers);
+        assert_eq!(rotated.len(), 1);
+        assert!(peers[&rotated[0]].0.ip().to_string().starts_with("5.6."));
+        assert_eq!(dialer(2, 100, None).select_rotation(&peers).len(), 3);
+    }
+}
@@ -0,0 +1,251 @@
+//! Final state hashes exchanged with the peers during the handshake, see `massa_protocol_exports::FinalityProvider`.
+//!
+//! They follow the announced features in the handshake: nodes that do not know about them ignore these bytes.
+//! The hashes announced by a peer are compared with ours for the slots finalized by both nodes.
+
+use std::{
+    collections::HashMap,
+    ops::Bound::{Excluded, Included},
+};
+
+use massa_hash::{Hash, HashDeserializer, HashSerializer};
+use massa_models::slot::{Slot, SlotDeserializer, SlotSerializer};
+use massa_protocol_exports::{FinalityProvider, ProtocolConfig};
+use massa_serialization::{
+    Deserializer, SerializeError, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
+};
+use nom::{
+    error::{context, ContextError, ParseError},
+    multi::length_count,
+    sequence::tuple,
+    IResult, Parser,
+};
+
+/// Maximum number of final state hashes accepted in the announcement of a peer,
+/// and number of our latest final state hashes they are compared with
+pub(crate) const MAX_ANNOUNCED_FINAL_STATE_HASHES: u64 = 256;
+
+/// Final state hashes announced by a node in its handshake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FinalityAnnouncement {
+    /// hashes of the final state at the latest final slots, from oldest to newest
+    pub final_state_hashes: Vec<(Slot, Hash)>,
+}
+
+impl FinalityAnnouncement {
+    /// Final state hashes announced by our node with this config
+    pub(crate) fn ours(config: &ProtocolConfig, provider: Option<&dyn FinalityProvider>) -> Self {
+        let count = config
+            .handshake_final_state_hashes
+            .min(MAX_ANNOUNCED_FINAL_STATE_HASHES as usize);
+        FinalityAnnouncement {
+            final_state_hashes: match provider {
+                Some(provider) if count > 0 => provider.latest_final_state_hashes(count),
+                _ => Vec::new(),
+            },
+        }
+    }
+}
+
+/// Comparison of the final state hashes announced by a peer with ours
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FinalityCheck {
+    /// the hashes of the slots finalized by both nodes match, up to this slot
+    Consistent(Slot),
+    /// the peer announced another final state hash than ours for this slot
+    Divergent {
+        slot: Slot,
+        ours: Hash,
+        theirs: Hash,
+    },
+    /// the nodes finalized none of the same slots lately, e.g. because one of them lags behind
+    Unknown,
+    /// the peer announced no final state hash
+    Unannounced,
+}
+
+impl FinalityCheck {
+    /// Compares the final state hashes announced by a peer with our latest ones
+    pub(crate) fn compare(ours: &[(Slot, Hash)], theirs: Option<&FinalityAnnouncement>) -> Self {
+        let Some(theirs) = theirs.filter(|theirs| !theirs.final_state_hashes.is_empty()) else {
+            return FinalityCheck::Unannounced;
+        };
+        let ours: HashMap<Slot, Hash> = ours.iter().copied().collect();
+        let mut last_consistent = None;
+        for (slot, their_hash) in &theirs.final_state_hashes {
+            match ours.get(slot) {
+                Some(our_hash) if our_hash != their_hash => {
+                    return FinalityCheck::Divergent {
+                        slot: *slot,
+                        ours: *our_hash,
+                        theirs: *their_hash,
+                    };
+                }
+                Some(_) => last_consistent = last_consistent.max(Some(*slot)),
+                None => {}
+            }
+        }
+        last_consistent.map_or(FinalityCheck::Unknown, FinalityCheck::Consistent)
+    }
+
+    /// Label of the result, used by the metrics
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            FinalityCheck::Consistent(_) => "consistent",
+            FinalityCheck::Divergent { .. } => "divergent",
+            FinalityCheck::Unknown => "unknown",
+            FinalityCheck::Unannounced => "unannounced",
+        }
+    }
+}
+
+/// Serializer for `FinalityAnnouncement`
+#[derive(Clone)]
+pub(crate) struct FinalityAnnouncementSerializer {
+    u64_serializer: U64VarIntSerializer,
+    slot_serializer: SlotSerializer,
+    hash_serializer: HashSerializer,
+}
+
+impl FinalityAnnouncementSerializer {
+    pub(crate) fn new() -> Self {
+        Self {
+            u64_serializer: U64VarIntSerializer::new(),
+            slot_serializer: SlotSerializer::new(),
+            hash_serializer: HashSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<FinalityAnnouncement> for FinalityAnnouncementSerializer {
+    fn serialize(
+        &self,
+        value: &FinalityAnnouncement,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.u64_serializer
+            .serialize(&(value.final_state_hashes.len() as u64), buffer)?;
+        for (slot, hash) in &value.final_state_hashes {
+            self.slot_serializer.serialize(slot, buffer)?;
+            self.hash_serializer.serialize(hash, buffer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deserializer for `FinalityAnnouncement`, ignoring the trailing bytes that newer nodes may add
+#[derive(Clone)]
+pub(crate) struct FinalityAnnouncementDeserializer {
+    length_deserializer: U64VarIntDeserializer,
+    slot_deserializer: SlotDeserializer,
+    hash_deserializer: HashDeserializer,
+}
+
+impl FinalityAnnouncementDeserializer {
+    pub(crate) fn new(thread_count: u8) -> Self {
+        Self {
+            length_deserializer: U64VarIntDeserializer::new(
+                Included(0),
+                Included(MAX_ANNOUNCED_FINAL_STATE_HASHES),
+            ),
+            slot_deserializer: SlotDeserializer::new(
+                (Included(0), Included(u64::MAX)),
+                (Included(0), Excluded(thread_count)),
+            ),
+            hash_deserializer: HashDeserializer::new(),
+        }
+    }
+}
+
+impl Deserializer<FinalityAnnouncement> for FinalityAnnouncementDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], FinalityAnnouncement, E> {
+        context(
+            "Failed FinalityAnnouncement deserialization",
+            length_count(
+                context("Failed length deserialization", |input| {
+                    self.length_deserializer.deserialize(input)
+                }),
+                tuple((
+                    context("Failed slot deserialization", |input| {
+                        self.slot_deserializer.deserialize(input)
+                    }),
+                    context("Failed final state hash deserialization", |input| {
+                        self.hash_deserializer.deserialize(input)
+                    }),
+                )),
+            ),
+        )
+        .map(|final_state_hashes| FinalityAnnouncement { final_state_hashes })
+        .parse(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_serialization::DeserializeError;
+
+    fn hashes(slots: std::ops::Range<u64>, salt: &[u8]) -> Vec<(Slot, Hash)> {
+        slots
+            .map(|period| {
+                (
+                    Slot::new(period, 1),
+                    Hash::compute_from_tuple(&[&period.to_be_bytes(), salt]),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_finality_announcement_comparison() {
+        let announcement = FinalityAnnouncement {
+            final_state_hashes: hashes(10..14, b"a"),
+        };
+        let mut buffer = Vec::new();
+        FinalityAnnouncementSerializer::new()
+            .serialize(&announcement, &mut buffer)
+            .unwrap();
+        // trailing bytes of newer nodes are ignored
+        buffer.push(42);
+        let (rest, deserialized) = FinalityAnnouncementDeserializer::new(2)
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert_eq!(rest, [42]);
+        assert_eq!(deserialized, announcement);
+
+        // only the slots finalized by both nodes are compared
+        assert_eq!(
+            FinalityCheck::compare(&hashes(12..20, b"a"), Some(&announcement)),
+            FinalityCheck::Consistent(Slot::new(13, 1))
+        );
+        assert_eq!(
+            FinalityCheck::compare(&hashes(14..20, b"a"), Some(&announcement)),
+            FinalityCheck::Unknown
+        );
+        let ours = hashes(0..20, b"b");
+        assert_eq!(
+            FinalityCheck::compare(&ours, Some(&announcement)),
+            FinalityCheck::Divergent {
+                slot: Slot::new(10, 1),
+                ours: ours[10].1,
+                theirs: announcement.final_state_hashes[0].1,
+            }
+        );
+        assert_eq!(
+            FinalityCheck::compare(&ours, None),
+            FinalityCheck::Unannounced
+        );
+        assert_eq!(
+            FinalityCheck::compare(
+                &ours,
+                Some(&FinalityAnnouncement {
+                    final_state_hashes: Vec::new()
+                })
+            ),
+            FinalityCheck::Unannounced
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Tracking of the block data requests (`AskForBlockInfo`) sent to peers, and of their retries.
+//!
+//! Each request waits for a response until a timeout that doubles with each request of the block
+//! that timed out, up to `MAX_ASK_BLOCK_BACKOFF_EXPONENT` doublings, so that a slow network is not flooded with retries.
+//! A peer that did not answer in time is ranked behind all the other candidates for that block,
+//! so that the next request fails over to another peer that announced it instead of stalling on a dead peer.
+//! The number of requests outstanding at the same time is limited per peer.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use massa_models::{
+    block_id::BlockId,
+    prehash::{PreHashMap, PreHashSet},
+};
+use massa_protocol_exports::PeerId;
+
+/// Maximum number of doublings of the timeout of the requests of a block
+const MAX_ASK_BLOCK_BACKOFF_EXPONENT: u32 = 3;
+
+/// Retry state of a block whose requests timed out
+#[derive(Debug, Default)]
+struct BlockRetryState {
+    /// number of requests of the block that timed out since the last response
+    timed_out_asks: u32,
+    /// peers that did not answer a request of the block in time
+    timed_out_peers: HashSet<PeerId>,
+}
+
+/// Outstanding block data requests and their retry policy
+pub(crate) struct BlockAskManager {
+    /// expiry of the requests waiting for a response, by peer and block
+    outstanding: HashMap<PeerId, PreHashMap<BlockId, Instant>>,
+    /// retry state of the blocks whose requests timed out
+    retries: PreHashMap<BlockId, BlockRetryState>,
+    /// timeout of the first request of a block
+    base_timeout: Duration,
+    /// maximum number of requests outstanding at the same time per peer
+    max_outstanding_per_peer: usize,
+}
+
+impl BlockAskManager {
+    pub(crate) fn new(base_timeout: Duration, max_outstanding_per_peer: usize) -> Self {
+        BlockAskManager {
+            outstanding: HashMap::new(),
+            retries: PreHashMap::default(),
+            base_timeout,
+            max_outstanding_per_peer,
+        }
+    }
+
+    /// Timeout of the next request of a block
+    pub(crate) fn timeout(&self, block_id: &BlockId) -> Duration {
+        let exponent = self
+            .retries
+            .get(block_id)
+            .map_or(0, |retry| retry.timed_out_asks)
+            .min(MAX_ASK_BLOCK_BACKOFF_EXPONENT);
+        self.base_timeout.saturating_mul(1 << exponent)
+    }
+
+    /// Whether no request is outstanding and no block is being retried
+    pub(crate) fn is_empty(&self) -> bool {
+        self.outstanding.is_empty() && self.retries.is_empty()
+    }
+
+    /// Number of requests outstanding for a peer
+    pub(crate) fn peer_load(&self, peer_id: &PeerId) -> usize {
+        self.outstanding.get(peer_id).map_or(0, |asked| asked.len())
+    }
+
+    /// Whether a peer can be sent another request
+    pub(crate) fn can_ask(&self, peer_id: &PeerId) -> bool {
+        self.peer_load(peer_id) < self.max_outstanding_per_peer
+    }
+
+    /// Whether a request of the block is waiting for a response
+    pub(crate) fn is_outstanding(&self, block_id: &BlockId) -> bool {
+        self.outstanding
+            .values()
+            .any(|asked| asked.contains_key(block_id))
+    }
+
+    /// Blocks with a request waiting for a response
+    pub(crate) fn outstanding_blocks(&self) -> PreHashSet<BlockId> {
+        self.outstanding
+            .values()
+            .flat_map(|asked| asked.keys())
+            .copied()
+            .collect()
+    }
+
+    /// Whether a peer did not answer a request of the block in time
+    pub(crate) fn has_timed_out(&self, peer_id: &PeerId, block_id: &BlockId) -> bool {
+        self.retries
+            .get(block_id)
+            .map_or(false, |retry| retry.timed_out_peers.contains(peer_id))
+    }
+
+    /// Records a request of a block sent to a peer
+    pub(crate) fn record_ask(&mut self, peer_id: PeerId, block_id: BlockId, now: Instant) {
+        let expiry = now
+            .checked_add(self.timeout(&block_id))
+            .expect("could not compute block ask expiry");
+        self.outstanding
+            .entry(peer_id)
+            .or_default()
+            .insert(block_id, expiry);
+    }
+
+    /// Records a response of a peer about a block: its request is not outstanding anymore,
+    /// and the timeouts of the block are reset as the retrieval progressed
+    pub(crate) fn record_response(&mut self, peer_id: &PeerId, block_id: &BlockId) {
+        if let Some(asked) = self.outstanding.get_mut(peer_id) {
+            asked.remove(block_id);
+            if asked.is_empty() {
+                self.outstanding.remove(peer_id);
+            }
+        }
+        if let Some(retry) = self.retries.get_mut(block_id) {
+            retry.timed_out_asks = 0;
+        }
+    }
+
+    /// Forgets the requests of blocks that do not need to be waited for anymore
+    pub(crate) fn remove_blocks(&mut self, block_ids: &PreHashSet<BlockId>) {
+        self.outstanding.retain(|_, asked| {
+            asked.retain(|block_id, _| !block_ids.contains(block_id));
+            !asked.is_empty()
+        });
+        self.retries
+            .retain(|block_id, _| !block_ids.contains(block_id));
+    }
+
+    /// Forgets the requests sent to disconnected peers and the blocks that are not wanted anymore
+    pub(crate) fn retain(
+        &mut self,
+        connected_peers: &HashSet<PeerId>,
+        is_wanted: impl Fn(&BlockId) -> bool,
+    ) {
+        self.outstanding.retain(|peer_id, asked| {
+            if !connected_peers.contains(peer_id) {
+                return false;
+            }
+            asked.retain(|block_id, _| is_wanted(block_id));
+            !asked.is_empty()
+        });
+        self.retries.retain(|block_id, _| is_wanted(block_id));
+    }
+
+    /// Removes the requests that timed out, and returns them so that the blocks are asked to other peers
+    pub(crate) fn collect_timeouts(&mut self, now: Instant) -> Vec<(PeerId, BlockId)> {
+        let mut timeouts = Vec::new();
+        self.outstanding.retain(|peer_id, asked| {
+            asked.retain(|block_id, expiry| {
+                if *expiry > now {
+                    return true;
+                }
+                timeouts.push((*peer_id, *block_id));
+                false
+            });
+            !asked.is_empty()
+        });
+        for (peer_id, block_id) in timeouts.iter() {
+            let retry = self.retries.entry(*block_id).or_default();
+            retry.timed_out_asks = retry.timed_out_asks.saturating_add(1);
+            retry.timed_out_peers.insert(*peer_id);
+        }
+        timeouts
+    }
+
+    /// Earliest expiry of the outstanding requests
+    pub(crate) fn next_expiry(&self) -> Option<Instant> {
+        self.outstanding
+            .values()
+            .flat_map(|asked| asked.values())
+            .min()
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+    use massa_signature::KeyPair;
+
+    #[test]
+    fn test_block_ask_backoff_and_failover() {
+        let peer_a = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+        let peer_b = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+        let block_id = BlockId::generate_from_hash(Hash::compute_from(b"block"));
+        let other_block_id = BlockId::generate_from_hash(Hash::compute_from(b"other"));
+        let base_timeout = Duration::from_millis(100);
+        let mut manager = BlockAskManager::new(base_timeout, 1);
+
+        let start = Instant::now();
+        manager.record_ask(peer_a, block_id, start);
+        assert!(manager.is_outstanding(&block_id));
+        assert_eq!(
+            manager.outstanding_blocks(),
+            PreHashSet::from_iter([block_id])
+        );
+        assert!(!manager.can_ask(&peer_a));
+        assert!(manager.can_ask(&peer_b));
+        assert_eq!(manager.next_expiry(), Some(start + base_timeout));
+        assert!(manager.collect_timeouts(start).is_empty());
+
+        // peer A does not answer: the block is asked to peer B with a doubled timeout
+        let now = start + base_timeout;
+        assert_eq!(manager.collect_timeouts(now), vec![(peer_a, block_id)]);
+        assert!(!manager.is_outstanding(&block_id));
+        assert!(manager.has_timed_out(&peer_a, &block_id));
+        assert!(!manager.has_timed_out(&peer_b, &block_id));
+        assert!(manager.can_ask(&peer_a));
+        assert_eq!(manager.timeout(&block_id), base_timeout * 2);
+        assert_eq!(manager.timeout(&other_block_id), base_timeout);
+        manager.record_ask(peer_b, block_id, now);
+        assert_eq!(manager.next_expiry(), Some(now + base_timeout * 2));
+
+        // the timeout is capped
+        for _ in 0..10 {
+            manager.record_ask(peer_b, block_id, now);
+            manager.collect_timeouts(now + base_timeout * 100);
+        }
+        assert_eq!(
+            manager.timeout(&block_id),
+            base_timeout * (1 << MAX_ASK_BLOCK_BACKOFF_EXPONENT)
+        );
+
+        // a response resets the backoff, but the peers that timed out stay ranked last
+        manager.record_ask(peer_b, block_id, now);
+        manager.record_response(&peer_b, &block_id);
+        assert!(!manager.is_outstanding(&block_id));
+        assert_eq!(manager.timeout(&block_id), base_timeout);
+        assert!(manager.has_timed_out(&peer_a, &block_id));
+
+        // disconnected peers and unwanted blocks are forgotten
+        manager.record_ask(peer_a, other_block_id, now);
+        manager.retain(&HashSet::from([peer_b]), |_| true);
+        assert!(!manager.is_outstanding(&other_block_id));
+        manager.remove_blocks(&PreHashSet::from_iter([block_id]));
+        assert!(manager.is_empty());
+    }
+}
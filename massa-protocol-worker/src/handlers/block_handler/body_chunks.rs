@@ -0,0 +1,190 @@
+//! Erasure coding of block bodies.
+//!
+//! Instead of asking a single peer for all the missing operations of a block,
+//! the body of the block can be fetched as Reed-Solomon chunks from several peers in parallel.
+//! The body is made of the operations of the block, serialized in block order without duplicates,
+//! and is split into `data_chunks` data chunks to which `parity_chunks` parity chunks are added.
+//! Any `data_chunks` of those chunks are enough to rebuild the body,
+//! so that a slow or unresponsive peer does not hold the block back.
+
+use massa_models::{
+    operation::{Operation, OperationDeserializer, OperationId, SecureShareOperation},
+    prehash::PreHashSet,
+    secure_share::{SecureShareDeserializer, SecureShareSerializer},
+};
+use massa_protocol_exports::ProtocolError;
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use nom::multi::count;
+use reed_solomon_erasure::galois_8::ReedSolomon;
+
+/// Maximum total number of chunks (data and parity) of a block body
+pub const MAX_BLOCK_BODY_CHUNKS: u32 = 256;
+
+/// IDs of the operations making the body of a block: the operations of the block in block order, without duplicates
+pub(crate) fn block_body_operation_ids(operation_ids: &[OperationId]) -> Vec<OperationId> {
+    let mut seen = PreHashSet::default();
+    operation_ids
+        .iter()
+        .filter(|op_id| seen.insert(**op_id))
+        .copied()
+        .collect()
+}
+
+/// Serialize the body of a block from its operations, given in body order
+pub(crate) fn serialize_block_body(
+    operations: &[SecureShareOperation],
+) -> Result<Vec<u8>, ProtocolError> {
+    let serializer = SecureShareSerializer::new();
+    let mut body = Vec::new();
+    for operation in operations {
+        serializer.serialize(operation, &mut body).map_err(|err| {
+            ProtocolError::GeneralProtocolError(format!("failed to serialize block body: {}", err))
+        })?;
+    }
+    Ok(body)
+}
+
+/// Deserialize the body of a block made of `operation_count` operations
+pub(crate) fn deserialize_block_body(
+    body: &[u8],
+    operation_count: usize,
+    operation_deserializer: &SecureShareDeserializer<Operation, OperationDeserializer>,
+) -> Result<Vec<SecureShareOperation>, ProtocolError> {
+    let (rest, operations) = count(
+        |input| operation_deserializer.deserialize::<DeserializeError>(input),
+        operation_count,
+    )(body)
+    .map_err(|err| {
+        ProtocolError::GeneralProtocolError(format!("failed to deserialize block body: {}", err))
+    })?;
+    if !rest.is_empty() {
+        return Err(ProtocolError::GeneralProtocolError(
+            "block body not fully consumed".to_string(),
+        ));
+    }
+    Ok(operations)
+}
+
+/// Size in bytes of each chunk of a block body of `body_size` bytes split into `data_chunks` data chunks
+pub(crate) fn block_body_chunk_size(body_size: usize, data_chunks: usize) -> usize {
+    body_size.div_ceil(data_chunks.max(1)).max(1)
+}
+
+fn reed_solomon(data_chunks: usize, parity_chunks: usize) -> Result<ReedSolomon, ProtocolError> {
+    ReedSolomon::new(data_chunks, parity_chunks).map_err(|err| {
+        ProtocolError::GeneralProtocolError(format!(
+            "invalid block body chunking ({} data chunks, {} parity chunks): {:?}",
+            data_chunks, parity_chunks, err
+        ))
+    })
+}
+
+/// Split a block body into `data_chunks` data chunks followed by `parity_chunks` parity chunks.
+/// The last data chunk is padded with zeros.
+pub(crate) fn encode_block_body(
+    body: &[u8],
+    data_chunks: usize,
+    parity_chunks: usize,
+) -> Result<Vec<Vec<u8>>, ProtocolError> {
+    let reed_solomon = reed_solomon(data_chunks, parity_chunks)?;
+    let chunk_size = block_body_chunk_size(body.len(), data_chunks);
+    let mut chunks: Vec<Vec<u8>> = (0..data_chunks + parity_chunks)
+        .map(|index| {
+            let start = (index * chunk_size).min(body.len());
+            let end = ((index + 1) * chunk_size).min(body.len());
+            let mut chunk = if index < data_chunks {
+                body[start..end].to_vec()
+            } else {
+                Vec::new()
+            };
+            chunk.resize(chunk_size, 0);
+            chunk
+        })
+        .collect();
+    reed_solomon.encode(&mut chunks).map_err(|err| {
+        ProtocolError::GeneralProtocolError(format!("failed to encode block body: {:?}", err))
+    })?;
+    Ok(chunks)
+}
+
+/// Rebuild a block body of `body_size` bytes from its chunks, indexed by chunk index.
+/// At least `data_chunks` chunks must be present.
+pub(crate) fn decode_block_body(
+    mut chunks: Vec<Option<Vec<u8>>>,
+    data_chunks: usize,
+    parity_chunks: usize,
+    body_size: usize,
+) -> Result<Vec<u8>, ProtocolError> {
+    reed_solomon(data_chunks, parity_chunks)?
+        .reconstruct_data(&mut chunks)
+        .map_err(|err| {
+            ProtocolError::GeneralProtocolError(format!("failed to decode block body: {:?}", err))
+        })?;
+    let mut body: Vec<u8> = chunks
+        .into_iter()
+        .take(data_chunks)
+        .flat_map(|chunk| chunk.expect("data chunks should have been rebuilt"))
+        .collect();
+    if body.len() < body_size {
+        return Err(ProtocolError::GeneralProtocolError(
+            "rebuilt block body is too short".to_string(),
+        ));
+    }
+    body.truncate(body_size);
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_protocol_exports::test_exports::tools;
+    use massa_signature::KeyPair;
+
+    #[test]
+    fn test_block_body_roundtrip_with_missing_chunks() {
+        let keypair = KeyPair::generate(0).unwrap();
+        let operations: Vec<_> = (0..10)
+            .map(|expire_period| {
+                tools::create_operation_with_expire_period(&keypair, expire_period)
+            })
+            .collect();
+        let mut operation_ids: Vec<_> = operations.iter().map(|op| op.id).collect();
+        operation_ids.push(operation_ids[0]);
+        assert_eq!(block_body_operation_ids(&operation_ids).len(), 10);
+
+        let body = serialize_block_body(&operations).unwrap();
+        let chunks = encode_block_body(&body, 4, 2).unwrap();
+        assert_eq!(chunks.len(), 6);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.len() == block_body_chunk_size(body.len(), 4)));
+
+        // any 4 chunks are enough to rebuild the body
+        let mut received: Vec<_> = chunks.into_iter().map(Some).collect();
+        received[0] = None;
+        received[3] = None;
+        let rebuilt = decode_block_body(received.clone(), 4, 2, body.len()).unwrap();
+        assert_eq!(rebuilt, body);
+        let deserializer = SecureShareDeserializer::new(OperationDeserializer::new(
+            1_000_000,
+            u16::MAX,
+            1_000_000,
+            128,
+            255,
+            1_000_000,
+        ));
+        let rebuilt_operations = deserialize_block_body(&rebuilt, 10, &deserializer).unwrap();
+        assert_eq!(
+            rebuilt_operations
+                .iter()
+                .map(|op| op.id)
+                .collect::<Vec<_>>(),
+            operation_ids[..10]
+        );
+        assert!(deserialize_block_body(&rebuilt, 9, &deserializer).is_err());
+
+        // but 3 are not
+        received[4] = None;
+        assert!(decode_block_body(received, 4, 2, body.len()).is_err());
+    }
+}
@@ -4,17 +4,21 @@ use std::{
     time::Instant,
 };
 
-use massa_models::{block_header::SecuredHeader, block_id::BlockId};
+use massa_models::{block_header::SecuredHeader, block_id::BlockId, id_prefix::BlockIdPrefix};
 use massa_protocol_exports::PeerId;
 use parking_lot::RwLock;
 use schnellru::{ByLength, LruMap};
 
-/// Cache on block knowledge by our node and its peers
+/// Cache on block knowledge by our node and its peers.
+///
+/// The blocks are indexed by the prefixes of their IDs to save memory.
+/// A prefix collision in the peer knowledge is tolerated, while the checked headers are
+/// only returned if their full ID matches.
 pub struct BlockCache {
     /// cache of previously checked headers
-    pub checked_headers: LruMap<BlockId, SecuredHeader>,
+    checked_headers: LruMap<BlockIdPrefix, SecuredHeader>,
     /// cache of blocks known by peers
-    pub blocks_known_by_peer: HashMap<PeerId, LruMap<BlockId, (bool, Instant)>>,
+    pub blocks_known_by_peer: HashMap<PeerId, LruMap<BlockIdPrefix, (bool, Instant)>>,
     /// max number of blocks known in peer knowledge cache
     pub max_known_blocks_by_peer: u32,
 }
@@ -40,9 +44,45 @@ impl BlockCache {
             .entry(*from_peer_id)
             .or_insert_with(|| LruMap::new(ByLength::new(self.max_known_blocks_by_peer)));
         for block_id in block_ids {
-            known_blocks.insert(*block_id, (known, now));
+            known_blocks.insert(block_id.prefix(), (known, now));
         }
     }
+
+    /// Knowledge of a block by a peer, and the time it was learnt
+    pub fn peer_known_block(
+        &self,
+        peer_id: &PeerId,
+        block_id: &BlockId,
+    ) -> Option<(bool, Instant)> {
+        self.blocks_known_by_peer
+            .get(peer_id)
+            .and_then(|known_blocks| known_blocks.peek(&block_id.prefix()).copied())
+    }
+
+    /// Previously checked header of a block, without updating its recency
+    pub fn peek_checked_header(&self, block_id: &BlockId) -> Option<&SecuredHeader> {
+        self.checked_headers
+            .peek(&block_id.prefix())
+            .filter(|header| header.id == *block_id)
+    }
+
+    /// Previously checked header of a block, marking it as recently used
+    pub fn get_checked_header(&mut self, block_id: &BlockId) -> Option<&SecuredHeader> {
+        self.checked_headers
+            .get(&block_id.prefix())
+            .map(|header| &*header)
+            .filter(|header| header.id == *block_id)
+    }
+
+    /// Mark a header as checked, replacing any header whose ID has the same prefix
+    pub fn insert_checked_header(&mut self, header: SecuredHeader) {
+        self.checked_headers.insert(header.id.prefix(), header);
+    }
+
+    /// Number of checked headers in the cache
+    pub fn checked_headers_len(&self) -> usize {
+        self.checked_headers.len()
+    }
 }
 
 impl BlockCache {
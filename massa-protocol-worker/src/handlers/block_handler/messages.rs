@@ -1,22 +1,47 @@
 use massa_models::{
-    block_header::{BlockHeader, BlockHeaderDeserializer, SecuredHeader},
+    block_header::{
+        BlockHeader, BlockHeaderDeserializer, SecuredHeader, DENUNCIATIONS_TOO_LARGE_ERROR,
+    },
     block_id::{BlockId, BlockIdDeserializer, BlockIdSerializer},
     operation::{
         OperationId, OperationIdSerializer, OperationIdsDeserializer, OperationsDeserializer,
         SecureShareOperation,
     },
-    secure_share::{SecureShareDeserializer, SecureShareSerializer},
+    secure_share::{Id, SecureShareDeserializer, SecureShareSerializer},
 };
 use massa_serialization::{
-    Deserializer, SerializeError, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
+    DeserializeError, Deserializer, SerializeError, Serializer, U32VarIntDeserializer,
+    U64VarIntDeserializer, U64VarIntSerializer,
 };
 use nom::{
-    error::{context, ContextError, ParseError},
+    error::{context, ContextError, ErrorKind, ParseError},
+    multi::{length_count, length_data},
+    number::complete::le_u64,
     sequence::tuple,
     IResult, Parser,
 };
 use num_enum::{IntoPrimitive, TryFromPrimitive};
-use std::ops::Bound::Included;
+use std::{
+    fmt::{Debug, Display},
+    ops::Bound::Included,
+};
+
+use super::body_chunks::MAX_BLOCK_BODY_CHUNKS;
+
+/// Size in bytes of the operation short IDs of compact blocks
+pub const OPERATION_SHORT_ID_SIZE_BYTES: usize = 8;
+
+/// Short ID of an operation in a compact block: the first bytes of its ID hash.
+///
+/// Short IDs are not unique: collisions are detected by the receiver
+/// through the operation merkle root of the block header.
+pub fn operation_short_id(operation_id: &OperationId) -> u64 {
+    u64::from_le_bytes(
+        operation_id.get_hash().to_bytes()[..OPERATION_SHORT_ID_SIZE_BYTES]
+            .try_into()
+            .expect("failed to truncate short ID from OperationId"),
+    )
+}
 
 /// Request block data
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -28,6 +53,15 @@ pub enum AskForBlockInfo {
     OperationIds,
     /// Ask for a subset of operations of the block
     Operations(Vec<OperationId>),
+    /// Ask for some erasure-coded chunks of the body of the block
+    OperationChunks {
+        /// number of data chunks the body is split into
+        data_chunks: u32,
+        /// number of parity chunks added to the data chunks
+        parity_chunks: u32,
+        /// indices of the requested chunks
+        indices: Vec<u32>,
+    },
 }
 
 /// Reply to a block data request
@@ -40,6 +74,13 @@ pub enum BlockInfoReply {
     OperationIds(Vec<OperationId>),
     /// Requested full operations of the block
     Operations(Vec<SecureShareOperation>),
+    /// Requested erasure-coded chunks of the body of the block
+    OperationChunks {
+        /// size of the body in bytes
+        body_size: u64,
+        /// chunks with their index
+        chunks: Vec<(u32, Vec<u8>)>,
+    },
     /// Block not found
     NotFound,
 }
@@ -64,6 +105,16 @@ pub enum BlockMessage {
         /// Block info reply.
         block_info: BlockInfoReply,
     },
+    /// Block header announced along with the short IDs of the block operations,
+    /// so that the receiver can rebuild the block from the operations it already knows.
+    CompactBlock {
+        /// Block header
+        header: SecuredHeader,
+        /// Short IDs of the operations of the block, in block order
+        short_op_ids: Vec<u64>,
+        /// Operations of the block that the receiver is not known to have
+        operations: Vec<SecureShareOperation>,
+    },
 }
 
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
@@ -72,6 +123,7 @@ pub enum MessageTypeId {
     Header,
     DataRequest,
     DataResponse,
+    CompactBlock,
 }
 
 impl From<&BlockMessage> for MessageTypeId {
@@ -80,6 +132,7 @@ impl From<&BlockMessage> for MessageTypeId {
             BlockMessage::Header(_) => MessageTypeId::Header,
             BlockMessage::DataRequest { .. } => MessageTypeId::DataRequest,
             BlockMessage::DataResponse { .. } => MessageTypeId::DataResponse,
+            BlockMessage::CompactBlock { .. } => MessageTypeId::CompactBlock,
         }
     }
 }
@@ -91,6 +144,86 @@ pub enum BlockInfoType {
     OperationIds = 1,
     Operations = 2,
     NotFound = 3,
+    OperationChunks = 4,
+}
+
+/// Context of the error returned when a serialized block header exceeds its maximum size
+pub const HEADER_TOO_LARGE_ERROR: &str = "Block header exceeds the maximum size";
+
+/// Part of a block message that exceeded its maximum size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OversizedBlockPart {
+    /// the whole serialized header
+    Header,
+    /// the serialized denunciations of the header
+    Denunciations,
+}
+
+impl Display for OversizedBlockPart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OversizedBlockPart::Header => write!(f, "header"),
+            OversizedBlockPart::Denunciations => write!(f, "denunciations"),
+        }
+    }
+}
+
+/// Error of the `BlockMessageDeserializer`, telling the oversized messages apart from the malformed ones
+pub struct BlockMessageDeserializeError<'a> {
+    /// part of the message that exceeded its maximum size, if that is why the deserialization failed
+    pub oversized: Option<OversizedBlockPart>,
+    inner: DeserializeError<'a>,
+}
+
+impl<'a> BlockMessageDeserializeError<'a> {
+    /// Part of the message that exceeded its maximum size, if that is why the deserialization failed
+    pub fn oversized_part(err: &nom::Err<Self>) -> Option<OversizedBlockPart> {
+        match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => err.oversized,
+            nom::Err::Incomplete(_) => None,
+        }
+    }
+}
+
+impl<'a> ParseError<&'a [u8]> for BlockMessageDeserializeError<'a> {
+    fn from_error_kind(input: &'a [u8], kind: ErrorKind) -> Self {
+        Self {
+            oversized: None,
+            inner: DeserializeError::from_error_kind(input, kind),
+        }
+    }
+
+    fn append(input: &'a [u8], kind: ErrorKind, other: Self) -> Self {
+        Self {
+            oversized: other.oversized,
+            inner: DeserializeError::append(input, kind, other.inner),
+        }
+    }
+}
+
+impl<'a> ContextError<&'a [u8]> for BlockMessageDeserializeError<'a> {
+    fn add_context(input: &'a [u8], ctx: &'static str, other: Self) -> Self {
+        Self {
+            oversized: other.oversized.or(match ctx {
+                HEADER_TOO_LARGE_ERROR => Some(OversizedBlockPart::Header),
+                DENUNCIATIONS_TOO_LARGE_ERROR => Some(OversizedBlockPart::Denunciations),
+                _ => None,
+            }),
+            inner: DeserializeError::add_context(input, ctx, other.inner),
+        }
+    }
+}
+
+impl<'a> Display for BlockMessageDeserializeError<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
+impl<'a> Debug for BlockMessageDeserializeError<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.inner, f)
+    }
 }
 
 #[derive(Default, Clone)]
@@ -154,6 +287,24 @@ impl Serializer<BlockMessage> for BlockMessageSerializer {
                                 .serialize(operation_id, buffer)?;
                         }
                     }
+                    AskForBlockInfo::OperationChunks {
+                        data_chunks,
+                        parity_chunks,
+                        indices,
+                    } => {
+                        self.id_serializer
+                            .serialize(&(BlockInfoType::OperationChunks as u64), buffer)?;
+                        self.length_serializer
+                            .serialize(&u64::from(*data_chunks), buffer)?;
+                        self.length_serializer
+                            .serialize(&u64::from(*parity_chunks), buffer)?;
+                        self.length_serializer
+                            .serialize(&(indices.len() as u64), buffer)?;
+                        for index in indices {
+                            self.length_serializer
+                                .serialize(&u64::from(*index), buffer)?;
+                        }
+                    }
                 }
             }
             BlockMessage::DataResponse {
@@ -186,12 +337,43 @@ impl Serializer<BlockMessage> for BlockMessageSerializer {
                             self.secure_share_serializer.serialize(operation, buffer)?;
                         }
                     }
+                    BlockInfoReply::OperationChunks { body_size, chunks } => {
+                        self.id_serializer
+                            .serialize(&(BlockInfoType::OperationChunks as u64), buffer)?;
+                        self.length_serializer.serialize(body_size, buffer)?;
+                        self.length_serializer
+                            .serialize(&(chunks.len() as u64), buffer)?;
+                        for (index, chunk) in chunks {
+                            self.length_serializer
+                                .serialize(&u64::from(*index), buffer)?;
+                            self.length_serializer
+                                .serialize(&(chunk.len() as u64), buffer)?;
+                            buffer.extend_from_slice(chunk);
+                        }
+                    }
                     BlockInfoReply::NotFound => {
                         self.id_serializer
                             .serialize(&(BlockInfoType::NotFound as u64), buffer)?;
                     }
                 }
             }
+            BlockMessage::CompactBlock {
+                header,
+                short_op_ids,
+                operations,
+            } => {
+                self.secure_share_serializer.serialize(header, buffer)?;
+                self.length_serializer
+                    .serialize(&(short_op_ids.len() as u64), buffer)?;
+                for short_op_id in short_op_ids {
+                    buffer.extend(short_op_id.to_le_bytes());
+                }
+                self.length_serializer
+                    .serialize(&(operations.len() as u64), buffer)?;
+                for operation in operations {
+                    self.secure_share_serializer.serialize(operation, buffer)?;
+                }
+            }
         }
         Ok(())
     }
@@ -203,6 +385,10 @@ pub struct BlockMessageDeserializer {
     block_id_deserializer: BlockIdDeserializer,
     operation_ids_deserializer: OperationIdsDeserializer,
     operations_deserializer: OperationsDeserializer,
+    short_op_ids_length_deserializer: U32VarIntDeserializer,
+    block_body_chunk_index_deserializer: U32VarIntDeserializer,
+    block_body_size_deserializer: U64VarIntDeserializer,
+    max_header_size: usize,
 }
 
 pub struct BlockMessageDeserializerArgs {
@@ -216,19 +402,25 @@ pub struct BlockMessageDeserializerArgs {
     pub max_op_datastore_key_length: u8,
     pub max_op_datastore_value_length: u64,
     pub max_denunciations_in_block_header: u32,
+    pub max_header_size: usize,
+    pub max_header_denunciations_size: usize,
     pub last_start_period: Option<u64>,
+    pub max_block_body_size: u64,
 }
 
 impl BlockMessageDeserializer {
     pub fn new(args: BlockMessageDeserializerArgs) -> Self {
         Self {
             id_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
-            block_header_deserializer: SecureShareDeserializer::new(BlockHeaderDeserializer::new(
-                args.thread_count,
-                args.endorsement_count,
-                args.max_denunciations_in_block_header,
-                args.last_start_period,
-            )),
+            block_header_deserializer: SecureShareDeserializer::new(
+                BlockHeaderDeserializer::new(
+                    args.thread_count,
+                    args.endorsement_count,
+                    args.max_denunciations_in_block_header,
+                    args.last_start_period,
+                )
+                .with_max_denunciations_size(args.max_header_denunciations_size),
+            ),
             block_id_deserializer: BlockIdDeserializer::new(),
             operation_ids_deserializer: OperationIdsDeserializer::new(
                 args.max_operations_per_block,
@@ -242,6 +434,39 @@ impl BlockMessageDeserializer {
                 args.max_op_datastore_key_length,
                 args.max_op_datastore_value_length,
             ),
+            short_op_ids_length_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(args.max_operations_per_block),
+            ),
+            block_body_chunk_index_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(MAX_BLOCK_BODY_CHUNKS),
+            ),
+            block_body_size_deserializer: U64VarIntDeserializer::new(
+                Included(0),
+                Included(args.max_block_body_size),
+            ),
+            max_header_size: args.max_header_size,
+        }
+    }
+
+    /// Deserializes a header without reading more than its maximum size:
+    /// a header that does not fit in these bytes is rejected without looking further.
+    fn deserialize_header<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        input: &'a [u8],
+    ) -> IResult<&'a [u8], SecuredHeader, E> {
+        let bounded = &input[..input.len().min(self.max_header_size)];
+        match self.block_header_deserializer.deserialize(bounded) {
+            Ok((rest, header)) => Ok((&input[bounded.len() - rest.len()..], header)),
+            Err(nom::Err::Error(_) | nom::Err::Failure(_)) if bounded.len() < input.len() => {
+                Err(nom::Err::Failure(ContextError::add_context(
+                    input,
+                    HEADER_TOO_LARGE_ERROR,
+                    ParseError::from_error_kind(input, ErrorKind::TooLarge),
+                )))
+            }
+            Err(err) => Err(err),
         }
     }
 }
@@ -261,7 +486,7 @@ impl Deserializer<BlockMessage> for BlockMessageDeserializer {
             })?;
             match id {
                 MessageTypeId::Header => context("Failed BlockHeader deserialization", |input| {
-                    self.block_header_deserializer.deserialize(input)
+                    self.deserialize_header(input)
                 })
                 .map(BlockMessage::Header)
                 .parse(buffer),
@@ -292,6 +517,32 @@ impl Deserializer<BlockMessage> for BlockMessageDeserializer {
                                     .map(|(rest, operation_ids)| {
                                         (rest, AskForBlockInfo::Operations(operation_ids))
                                     }),
+                                BlockInfoType::OperationChunks => tuple((
+                                    |input| {
+                                        self.block_body_chunk_index_deserializer.deserialize(input)
+                                    },
+                                    |input| {
+                                        self.block_body_chunk_index_deserializer.deserialize(input)
+                                    },
+                                    length_count(
+                                        |input| {
+                                            self.block_body_chunk_index_deserializer
+                                                .deserialize(input)
+                                        },
+                                        |input| {
+                                            self.block_body_chunk_index_deserializer
+                                                .deserialize(input)
+                                        },
+                                    ),
+                                ))
+                                .map(|(data_chunks, parity_chunks, indices)| {
+                                    AskForBlockInfo::OperationChunks {
+                                        data_chunks,
+                                        parity_chunks,
+                                        indices,
+                                    }
+                                })
+                                .parse(rest),
                                 BlockInfoType::NotFound => {
                                     Err(nom::Err::Error(ParseError::from_error_kind(
                                         buffer,
@@ -325,8 +576,7 @@ impl Deserializer<BlockMessage> for BlockMessageDeserializer {
                             })?;
                             match info_type {
                                 BlockInfoType::Header => self
-                                    .block_header_deserializer
-                                    .deserialize(rest)
+                                    .deserialize_header(rest)
                                     .map(|(rest, header)| (rest, BlockInfoReply::Header(header))),
                                 BlockInfoType::OperationIds => self
                                     .operation_ids_deserializer
@@ -340,6 +590,32 @@ impl Deserializer<BlockMessage> for BlockMessageDeserializer {
                                     .map(|(rest, operations)| {
                                         (rest, BlockInfoReply::Operations(operations))
                                     }),
+                                BlockInfoType::OperationChunks => tuple((
+                                    |input| self.block_body_size_deserializer.deserialize(input),
+                                    length_count(
+                                        |input| {
+                                            self.block_body_chunk_index_deserializer
+                                                .deserialize(input)
+                                        },
+                                        tuple((
+                                            |input| {
+                                                self.block_body_chunk_index_deserializer
+                                                    .deserialize(input)
+                                            },
+                                            length_data(|input| {
+                                                self.block_body_size_deserializer.deserialize(input)
+                                            }),
+                                        )),
+                                    ),
+                                ))
+                                .map(|(body_size, chunks)| BlockInfoReply::OperationChunks {
+                                    body_size,
+                                    chunks: chunks
+                                        .into_iter()
+                                        .map(|(index, chunk): (u32, &[u8])| (index, chunk.to_vec()))
+                                        .collect(),
+                                })
+                                .parse(rest),
                                 BlockInfoType::NotFound => Ok((rest, BlockInfoReply::NotFound)),
                             }
                         }),
@@ -350,6 +626,34 @@ impl Deserializer<BlockMessage> for BlockMessageDeserializer {
                     block_info,
                 })
                 .parse(buffer),
+                MessageTypeId::CompactBlock => context(
+                    "Failed CompactBlock deserialization",
+                    tuple((
+                        context("Failed BlockHeader deserialization", |input| {
+                            self.deserialize_header(input)
+                        }),
+                        context(
+                            "Failed short operation IDs deserialization",
+                            length_count(
+                                context("Failed length deserialization", |input| {
+                                    self.short_op_ids_length_deserializer.deserialize(input)
+                                }),
+                                context("Failed short operation ID deserialization", le_u64),
+                            ),
+                        ),
+                        context("Failed operations deserialization", |input| {
+                            self.operations_deserializer.deserialize(input)
+                        }),
+                    )),
+                )
+                .map(
+                    |(header, short_op_ids, operations)| BlockMessage::CompactBlock {
+                        header,
+                        short_op_ids,
+                        operations,
+                    },
+                )
+                .parse(buffer),
             }
         })
         .parse(buffer)
@@ -385,7 +689,10 @@ mod tests {
                 max_op_datastore_key_length: 1,
                 max_op_datastore_value_length: 1,
                 max_denunciations_in_block_header: 1,
+                max_header_size: 131_072,
+                max_header_denunciations_size: 65_536,
                 last_start_period: None,
+                max_block_body_size: 1,
             });
         let (rest, deserialized_message) = deserializer
             .deserialize::<DeserializeError>(&buffer)
@@ -471,7 +778,10 @@ mod tests {
                 max_op_datastore_key_length: 1,
                 max_op_datastore_value_length: 1,
                 max_denunciations_in_block_header: 1,
+                max_header_size: 131_072,
+                max_header_denunciations_size: 65_536,
                 last_start_period: None,
+                max_block_body_size: 1,
             });
         deserializer
             .deserialize::<DeserializeError>(&buffer)
@@ -488,7 +798,10 @@ mod tests {
                 max_op_datastore_key_length: 1,
                 max_op_datastore_value_length: 1,
                 max_denunciations_in_block_header: 1,
+                max_header_size: 131_072,
+                max_header_denunciations_size: 65_536,
                 last_start_period: None,
+                max_block_body_size: 1,
             });
         let (rest, deserialized_message) = deserializer
             .deserialize::<DeserializeError>(&buffer)
@@ -511,4 +824,221 @@ mod tests {
             _ => panic!("Wrong message type"),
         }
     }
+
+    #[test]
+    fn test_compact_block_message() {
+        use massa_models::slot::Slot;
+        use massa_protocol_exports::test_exports::tools;
+        use massa_signature::KeyPair;
+
+        let keypair = KeyPair::generate(0).unwrap();
+        let op_1 = tools::create_operation_with_expire_period(&keypair, 5);
+        let op_2 = tools::create_operation_with_expire_period(&keypair, 5);
+        let block = tools::create_block_with_operations(
+            &keypair,
+            Slot::new(1, 0),
+            vec![op_1.clone(), op_2.clone()],
+        );
+        let message = super::BlockMessage::CompactBlock {
+            header: block.content.header.clone(),
+            short_op_ids: vec![
+                super::operation_short_id(&op_1.id),
+                super::operation_short_id(&op_2.id),
+            ],
+            operations: vec![op_2.clone()],
+        };
+        let mut buffer = Vec::new();
+        let serializer = super::BlockMessageSerializer::new();
+        serializer.serialize(&message, &mut buffer).unwrap();
+        let args = |max_operations_per_block| super::BlockMessageDeserializerArgs {
+            thread_count: 2,
+            endorsement_count: 16,
+            max_operations_per_block,
+            max_datastore_value_length: 1,
+            max_function_name_length: 1,
+            max_parameters_size: 1,
+            max_op_datastore_entry_count: 1,
+            max_op_datastore_key_length: 1,
+            max_op_datastore_value_length: 1,
+            max_denunciations_in_block_header: 1,
+            max_header_size: 131_072,
+            max_header_denunciations_size: 65_536,
+            last_start_period: None,
+            max_block_body_size: 1,
+        };
+        super::BlockMessageDeserializer::new(args(1))
+            .deserialize::<DeserializeError>(&buffer)
+            .expect_err("Should raise error because there are two short IDs and only 1 allowed");
+        let (rest, deserialized_message) = super::BlockMessageDeserializer::new(args(2))
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert!(rest.is_empty());
+        match deserialized_message {
+            super::BlockMessage::CompactBlock {
+                header,
+                short_op_ids,
+                operations,
+            } => {
+                assert_eq!(header.id, block.id);
+                assert_eq!(
+                    short_op_ids,
+                    vec![
+                        super::operation_short_id(&op_1.id),
+                        super::operation_short_id(&op_2.id)
+                    ]
+                );
+                assert_eq!(operations.len(), 1);
+                assert_eq!(operations[0].id, op_2.id);
+            }
+            _ => panic!("Wrong message type"),
+        }
+    }
+
+    #[test]
+    fn test_oversized_header_message() {
+        use massa_models::slot::Slot;
+        use massa_protocol_exports::test_exports::tools;
+        use massa_signature::KeyPair;
+
+        let keypair = KeyPair::generate(0).unwrap();
+        let block = tools::create_block_with_operations(&keypair, Slot::new(1, 0), vec![]);
+        let serializer = super::BlockMessageSerializer::new();
+        let mut header_buffer = Vec::new();
+        serializer
+            .serialize(
+                &super::BlockMessage::Header(block.content.header.clone()),
+                &mut header_buffer,
+            )
+            .unwrap();
+        let mut compact_buffer = Vec::new();
+        serializer
+            .serialize(
+                &super::BlockMessage::CompactBlock {
+                    header: block.content.header.clone(),
+                    short_op_ids: vec![],
+                    operations: vec![],
+                },
+                &mut compact_buffer,
+            )
+            .unwrap();
+        let deserializer = |max_header_size| {
+            super::BlockMessageDeserializer::new(super::BlockMessageDeserializerArgs {
+                thread_count: 2,
+                endorsement_count: 16,
+                max_operations_per_block: 1,
+                max_datastore_value_length: 1,
+                max_function_name_length: 1,
+                max_parameters_size: 1,
+                max_op_datastore_entry_count: 1,
+                max_op_datastore_key_length: 1,
+                max_op_datastore_value_length: 1,
+                max_denunciations_in_block_header: 1,
+                max_header_size,
+                max_header_denunciations_size: 65_536,
+                last_start_period: None,
+                max_block_body_size: 1,
+            })
+        };
+
+        // the header fits exactly: the message type ID is not part of it
+        let header_size = header_buffer.len() - 1;
+        for buffer in [&header_buffer, &compact_buffer] {
+            let (rest, _) = deserializer(header_size)
+                .deserialize::<super::BlockMessageDeserializeError>(buffer)
+                .unwrap();
+            assert!(rest.is_empty());
+            let err = deserializer(header_size - 1)
+                .deserialize::<super::BlockMessageDeserializeError>(buffer)
+                .unwrap_err();
+            assert_eq!(
+                super::BlockMessageDeserializeError::oversized_part(&err),
+                Some(super::OversizedBlockPart::Header)
+            );
+        }
+
+        // a malformed header is not mistaken for an oversized one
+        let err = deserializer(header_size)
+            .deserialize::<super::BlockMessageDeserializeError>(&header_buffer[..header_size])
+            .unwrap_err();
+        assert_eq!(
+            super::BlockMessageDeserializeError::oversized_part(&err),
+            None
+        );
+    }
+
+    #[test]
+    fn test_block_body_chunks_messages() {
+        let block_id =
+            BlockId::from_str("B12DvrcQkzF1Wi8BVoNfc4n93CD3E2qhCNe7nVhnEQGWHZ24fEmg").unwrap();
+        let args = |max_block_body_size| super::BlockMessageDeserializerArgs {
+            thread_count: 1,
+            endorsement_count: 1,
+            max_operations_per_block: 1,
+            max_datastore_value_length: 1,
+            max_function_name_length: 1,
+            max_parameters_size: 1,
+            max_op_datastore_entry_count: 1,
+            max_op_datastore_key_length: 1,
+            max_op_datastore_value_length: 1,
+            max_denunciations_in_block_header: 1,
+            max_header_size: 131_072,
+            max_header_denunciations_size: 65_536,
+            last_start_period: None,
+            max_block_body_size,
+        };
+        let serializer = super::BlockMessageSerializer::new();
+
+        let request = super::BlockMessage::DataRequest {
+            block_id,
+            block_info: super::AskForBlockInfo::OperationChunks {
+                data_chunks: 4,
+                parity_chunks: 2,
+                indices: vec![1, 5],
+            },
+        };
+        let mut buffer = Vec::new();
+        serializer.serialize(&request, &mut buffer).unwrap();
+        let (rest, deserialized_request) = super::BlockMessageDeserializer::new(args(1))
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert!(rest.is_empty());
+        match deserialized_request {
+            super::BlockMessage::DataRequest { block_info, .. } => assert_eq!(
+                block_info,
+                super::AskForBlockInfo::OperationChunks {
+                    data_chunks: 4,
+                    parity_chunks: 2,
+                    indices: vec![1, 5],
+                }
+            ),
+            _ => panic!("Wrong message type"),
+        }
+
+        let reply = super::BlockMessage::DataResponse {
+            block_id,
+            block_info: super::BlockInfoReply::OperationChunks {
+                body_size: 10,
+                chunks: vec![(1, vec![1, 2, 3]), (5, vec![4, 5, 6])],
+            },
+        };
+        let mut buffer = Vec::new();
+        serializer.serialize(&reply, &mut buffer).unwrap();
+        super::BlockMessageDeserializer::new(args(9))
+            .deserialize::<DeserializeError>(&buffer)
+            .expect_err("Should raise error because the body is larger than allowed");
+        let (rest, deserialized_reply) = super::BlockMessageDeserializer::new(args(10))
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert!(rest.is_empty());
+        match deserialized_reply {
+            super::BlockMessage::DataResponse {
+                block_info: super::BlockInfoReply::OperationChunks { body_size, chunks },
+                ..
+            } => {
+                assert_eq!(body_size, 10);
+                assert_eq!(chunks, vec![(1, vec![1, 2, 3]), (5, vec![4, 5, 6])]);
+            }
+            _ => panic!("Wrong message type"),
+        }
+    }
 }
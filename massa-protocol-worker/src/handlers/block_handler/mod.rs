@@ -17,6 +17,8 @@ use self::{
     retrieval::start_retrieval_thread,
 };
 
+mod ask_manager;
+mod body_chunks;
 pub mod cache;
 pub mod commands_propagation;
 pub mod commands_retrieval;
@@ -37,6 +39,7 @@ use super::{
     },
     operation_handler::{
         cache::SharedOperationCache, commands_propagation::OperationHandlerPropagationCommand,
+        SharedOperationSigVerifier,
     },
     peer_handler::models::{PeerManagementCmd, PeerMessageTuple},
 };
@@ -65,6 +68,7 @@ impl BlockHandler {
         config: ProtocolConfig,
         endorsement_cache: SharedEndorsementCache,
         operation_cache: SharedOperationCache,
+        operation_sig_verifier: SharedOperationSigVerifier,
         cache: SharedBlockCache,
         storage: Storage,
         mip_store: MipStore,
@@ -83,7 +87,8 @@ impl BlockHandler {
             peer_cmd_sender.clone(),
             config.clone(),
             endorsement_cache,
-            operation_cache,
+            operation_cache.clone(),
+            operation_sig_verifier,
             cache.clone(),
             storage.clone_without_refs(),
             mip_store,
@@ -95,6 +100,7 @@ impl BlockHandler {
             peer_cmd_sender,
             config,
             cache,
+            operation_cache,
         );
         Self {
             block_retrieval_thread: Some((sender_ext, block_retrieval_thread)),
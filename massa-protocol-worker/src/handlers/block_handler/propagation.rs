@@ -10,13 +10,21 @@
 //!
 //! Here we need to announce block headers to other nodes that haven't sene them,
 //! and keep the blocks alive long enough for our peers to be able to retrieve them from us.
+//!
+//! If `compact_block_relay` is enabled, headers are announced as compact blocks
+//! that also carry the short IDs of the block operations and the operations the peer is not known to have,
+//! so that the peer can rebuild the block without asking for its data.
 
 use super::{
     cache::SharedBlockCache, commands_propagation::BlockHandlerPropagationCommand,
-    BlockMessageSerializer,
+    messages::operation_short_id, BlockMessageSerializer,
 };
 use crate::{
-    handlers::{block_handler::BlockMessage, peer_handler::models::PeerManagementCmd},
+    capabilities::Capabilities,
+    handlers::{
+        block_handler::BlockMessage, operation_handler::cache::SharedOperationCache,
+        peer_handler::models::PeerManagementCmd,
+    },
     messages::MessagesSerializer,
     wrap_network::ActiveConnectionsTrait,
 };
@@ -24,6 +32,8 @@ use crossbeam::channel::RecvTimeoutError;
 use massa_channel::{receiver::MassaReceiver, sender::MassaSender};
 use massa_models::block_header::SecuredHeader;
 use massa_models::block_id::BlockId;
+use massa_models::operation::{OperationId, OperationPrefixId};
+use massa_models::prehash::PreHashSet;
 use massa_protocol_exports::PeerId;
 use massa_protocol_exports::{ProtocolConfig, ProtocolError};
 use massa_storage::Storage;
@@ -37,9 +47,11 @@ struct BlockPropagationData {
     /// Time when propagation was initiated
     pub time_added: Instant,
     /// Storage holding the block and its dependencies during its propagation time
-    pub _storage: Storage,
+    pub storage: Storage,
     /// Clone of the block header to avoid locking storage during propagation
     pub header: SecuredHeader,
+    /// Clone of the block operation IDs to avoid locking storage during propagation
+    pub operation_ids: Vec<OperationId>,
 }
 
 pub struct PropagationThread {
@@ -49,6 +61,8 @@ pub struct PropagationThread {
     config: ProtocolConfig,
     /// Shared access to the block cache
     cache: SharedBlockCache,
+    /// Shared access to the operation cache, to know which operations peers miss
+    operation_cache: SharedOperationCache,
     /// Blocks stored for propagation
     stored_for_propagation: LruMap<BlockId, BlockPropagationData>,
     /// Shared access to the list of peers connected to us
@@ -73,21 +87,23 @@ impl PropagationThread {
                         BlockHandlerPropagationCommand::IntegratedBlock { block_id, storage } => {
                             debug!("received IntegratedBlock({})", block_id);
 
-                            // get the block header
-                            let header = match storage
-                                .read_blocks()
-                                .get(&block_id)
-                                .map(|block| block.content.header.clone())
-                            {
-                                Some(h) => h,
-                                None => {
-                                    warn!(
-                                        "claimed block {} absent from storage on propagation",
-                                        block_id
-                                    );
-                                    continue;
-                                }
-                            };
+                            // get the block header and operation IDs
+                            let (header, operation_ids) =
+                                match storage.read_blocks().get(&block_id).map(|block| {
+                                    (
+                                        block.content.header.clone(),
+                                        block.content.operations.clone(),
+                                    )
+                                }) {
+                                    Some(data) => data,
+                                    None => {
+                                        warn!(
+                                            "claimed block {} absent from storage on propagation",
+                                            block_id
+                                        );
+                                        continue;
+                                    }
+                                };
 
                             // Add the block and its dependencies to the propagation LRU
                             // to ensure they are stored for the time of the propagation.
@@ -95,8 +111,9 @@ impl PropagationThread {
                                 block_id,
                                 BlockPropagationData {
                                     time_added: Instant::now(),
-                                    _storage: storage,
+                                    storage,
                                     header,
+                                    operation_ids,
                                 },
                             );
 
@@ -116,7 +133,7 @@ impl PropagationThread {
                                 .blocks_known_by_peer
                                 .iter()
                                 .filter_map(|(peer_id, knowledge)| {
-                                    match knowledge.peek(&block_id) {
+                                    match knowledge.peek(&block_id.prefix()) {
                                         Some((true, _)) => Some(*peer_id),
                                         _ => None,
                                     }
@@ -170,25 +187,57 @@ impl PropagationThread {
         let mut cache_lock = self.cache.write();
         cache_lock.update_cache(&peers_connected);
         'peer_loop: for (peer_id, known_by_peer) in cache_lock.blocks_known_by_peer.iter_mut() {
-            for (block_id, BlockPropagationData { header, .. }) in
-                self.stored_for_propagation.iter()
+            for (
+                block_id,
+                BlockPropagationData {
+                    header,
+                    storage,
+                    operation_ids,
+                    ..
+                },
+            ) in self.stored_for_propagation.iter()
             {
                 // if the peer already knows about the block, do not propagate it
-                if let Some((true, _)) = known_by_peer.peek(block_id) {
+                if let Some((true, _)) = known_by_peer.peek(&block_id.prefix()) {
                     continue;
                 }
 
                 // try to propagate
                 debug!("announcing header {} to peer {}", block_id, peer_id);
+                let compact_block = self.config.compact_block_relay
+                    && self
+                        .active_connections
+                        .get_peer_capabilities(peer_id)
+                        .map_or(false, |negotiated| {
+                            negotiated.supports(Capabilities::COMPACT_BLOCKS)
+                        });
+                let (message, sent_operations) = if compact_block {
+                    Self::compact_block(
+                        &self.operation_cache,
+                        peer_id,
+                        header,
+                        operation_ids,
+                        storage,
+                    )
+                } else {
+                    (BlockMessage::Header(header.clone()), Vec::new())
+                };
                 match self.active_connections.send_to_peer(
                     peer_id,
                     &self.block_serializer,
-                    BlockMessage::Header(header.clone()).into(),
+                    message.into(),
                     true,
                 ) {
                     Ok(()) => {
                         // mark the block as known by the peer
-                        known_by_peer.insert(*block_id, (true, now));
+                        known_by_peer.insert(block_id.prefix(), (true, now));
+
+                        // mark the operations sent along the block as known by the peer
+                        if !sent_operations.is_empty() {
+                            self.operation_cache
+                                .write()
+                                .insert_peer_known_ops(peer_id, &sent_operations);
+                        }
                     }
                     Err(err) => {
                         warn!(
@@ -202,6 +251,42 @@ impl PropagationThread {
         }
     }
 
+    /// Build the compact block announcing a block to a peer.
+    ///
+    /// Returns the message and the prefixes of the operations it carries.
+    fn compact_block(
+        operation_cache: &SharedOperationCache,
+        peer_id: &PeerId,
+        header: &SecuredHeader,
+        operation_ids: &[OperationId],
+        storage: &Storage,
+    ) -> (BlockMessage, Vec<OperationPrefixId>) {
+        let short_op_ids = operation_ids.iter().map(operation_short_id).collect();
+
+        // send along the operations that the peer is not known to have
+        let mut missing_ids: PreHashSet<OperationId> = operation_ids.iter().copied().collect();
+        if let Some(known_ops) = operation_cache.read().ops_known_by_peer.get(peer_id) {
+            missing_ids.retain(|op_id| known_ops.peek(&op_id.prefix()).is_none());
+        }
+        let operations: Vec<_> = {
+            let ops_read = storage.read_operations();
+            missing_ids
+                .iter()
+                .filter_map(|op_id| ops_read.get(op_id).cloned())
+                .collect()
+        };
+        let sent_operations = operations.iter().map(|op| op.id.prefix()).collect();
+
+        (
+            BlockMessage::CompactBlock {
+                header: header.clone(),
+                short_op_ids,
+                operations,
+            },
+            sent_operations,
+        )
+    }
+
     /// try to ban a list of peers
     fn ban_peers(&mut self, peer_ids: &[PeerId]) {
         if let Err(err) = self
@@ -220,6 +305,7 @@ pub fn start_propagation_thread(
     peer_cmd_sender: MassaSender<PeerManagementCmd>,
     config: ProtocolConfig,
     cache: SharedBlockCache,
+    operation_cache: SharedOperationCache,
 ) -> JoinHandle<()> {
     std::thread::Builder::new()
         .name("protocol-block-handler-propagation".to_string())
@@ -236,6 +322,7 @@ pub fn start_propagation_thread(
                 receiver,
                 config,
                 cache,
+                operation_cache,
                 peer_cmd_sender,
                 active_connections,
                 block_serializer,
@@ -5,6 +5,7 @@ use std::{
 };
 
 use crate::{
+    capabilities::Capabilities,
     handlers::{
         endorsement_handler::{
             cache::SharedEndorsementCache,
@@ -14,9 +15,15 @@ use crate::{
         operation_handler::{
             cache::SharedOperationCache, commands_propagation::OperationHandlerPropagationCommand,
         },
-        peer_handler::models::{PeerManagementCmd, PeerMessageTuple},
+        peer_handler::{
+            models::{PeerManagementCmd, PeerMessageTuple},
+            reputation::{report_misbehavior, Misbehavior},
+        },
+    },
+    messages::{
+        DeserializationFailure, Message, MessageHandlingTimer, MessageTypeId, MessagesSerializer,
     },
-    messages::{Message, MessagesSerializer},
+    sanity::{check_header_producer, check_header_structure},
     wrap_network::ActiveConnectionsTrait,
 };
 use crossbeam::{
@@ -24,41 +31,50 @@ use crossbeam::{
     select,
 };
 use massa_channel::{receiver::MassaReceiver, sender::MassaSender};
-use massa_consensus_exports::ConsensusController;
+use massa_consensus_exports::{
+    header_check::{check_header_network_version, HeaderCheckError},
+    ConsensusController,
+};
 use massa_logging::massa_trace;
 use massa_metrics::MassaMetrics;
 use massa_models::{
     block::{Block, BlockSerializer},
     block_header::SecuredHeader,
     block_id::BlockId,
-    endorsement::EndorsementId,
     operation::{
-        compute_operations_hash, OperationId, OperationIdSerializer, SecureShareOperation,
+        compute_operations_hash, Operation, OperationDeserializer, OperationId,
+        OperationIdSerializer, SecureShareOperation,
     },
     prehash::{PreHashMap, PreHashSet},
-    secure_share::SecureShare,
-    timeslots::get_block_slot_timestamp,
+    secure_share::{SecureShare, SecureShareDeserializer},
+    slot::Slot,
 };
 use massa_pool_exports::PoolController;
 use massa_pos_exports::SelectorController;
 use massa_protocol_exports::PeerId;
 use massa_protocol_exports::{ProtocolConfig, ProtocolError};
-use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use massa_serialization::{Deserializer, Serializer};
 use massa_storage::Storage;
-use massa_time::TimeError;
+use massa_time::{MassaTime, TimeError};
 use massa_versioning::versioning::MipStore;
 use rand::thread_rng;
 use rand::{seq::SliceRandom, Rng};
+use schnellru::{ByLength, LruMap};
 use tracing::{debug, info, warn};
 
 use super::{
-    super::operation_handler::note_operations_from_peer,
+    super::operation_handler::{note_operations_from_peer, SharedOperationSigVerifier},
+    ask_manager::BlockAskManager,
+    body_chunks::{
+        block_body_chunk_size, block_body_operation_ids, decode_block_body, deserialize_block_body,
+        encode_block_body, serialize_block_body,
+    },
     cache::SharedBlockCache,
     commands_propagation::BlockHandlerPropagationCommand,
     commands_retrieval::BlockHandlerRetrievalCommand,
     messages::{
-        AskForBlockInfo, BlockInfoReply, BlockMessage, BlockMessageDeserializer,
-        BlockMessageDeserializerArgs,
+        operation_short_id, AskForBlockInfo, BlockInfoReply, BlockMessage,
+        BlockMessageDeserializeError, BlockMessageDeserializer, BlockMessageDeserializerArgs,
     },
     BlockMessageSerializer,
 };
@@ -73,6 +89,14 @@ pub(crate) struct BlockInfo {
     /// Operations and endorsements contained in the block,
     /// if we've received them already, and none otherwise.
     pub(crate) storage: Storage,
+    /// Chunks of the block body, if we are gathering them
+    pub(crate) body_chunks: Option<BlockBodyChunks>,
+    /// Whether gathering the block body as chunks failed,
+    /// in which case its missing operations are asked for directly
+    pub(crate) body_chunking_failed: bool,
+    /// Part of the missing operations asked to each peer,
+    /// when the missing operations are spread over several peers
+    pub(crate) asked_operations: HashMap<PeerId, PreHashSet<OperationId>>,
 }
 
 impl BlockInfo {
@@ -81,10 +105,54 @@ impl BlockInfo {
             header,
             operation_ids: None,
             storage,
+            body_chunks: None,
+            body_chunking_failed: false,
+            asked_operations: Default::default(),
         }
     }
 }
 
+/// Maximum number of times the missing chunks of a block body are asked for
+/// before falling back to asking for the missing operations of the block
+const MAX_BLOCK_BODY_CHUNK_ASK_ROUNDS: usize = 3;
+
+/// Minimum number of missing operations of a block asked to each peer
+/// when they are spread over several peers
+const MIN_OPERATIONS_ASKED_PER_PEER: usize = 16;
+
+/// Erasure-coded chunks of a block body being gathered from several peers
+#[derive(Debug, Clone)]
+pub(crate) struct BlockBodyChunks {
+    /// Size of the body in bytes, as announced by the first peer that sent us chunks
+    body_size: Option<u64>,
+    /// Received chunks, by chunk index
+    chunks: Vec<Option<Vec<u8>>>,
+    /// Number of times the missing chunks were asked for
+    ask_rounds: usize,
+}
+
+impl BlockBodyChunks {
+    fn new(chunk_count: usize) -> Self {
+        BlockBodyChunks {
+            body_size: None,
+            chunks: vec![None; chunk_count],
+            ask_rounds: 0,
+        }
+    }
+}
+
+/// Compact block waiting to be reconciled with the local operations
+struct CompactBlockInfo {
+    /// Peer that sent the compact block
+    from_peer_id: PeerId,
+    /// Header of the block, already checked
+    header: SecuredHeader,
+    /// Short IDs of the operations of the block, in block order
+    short_op_ids: Vec<u64>,
+    /// Operations sent along the compact block
+    storage: Storage,
+}
+
 pub struct RetrievalThread {
     active_connections: Box<dyn ActiveConnectionsTrait>,
     selector_controller: Box<dyn SelectorController>,
@@ -95,12 +163,16 @@ pub struct RetrievalThread {
     receiver: MassaReceiver<BlockHandlerRetrievalCommand>,
     block_message_serializer: MessagesSerializer,
     block_wishlist: PreHashMap<BlockId, BlockInfo>,
-    asked_blocks: HashMap<PeerId, PreHashMap<BlockId, Instant>>,
+    /// Compact blocks received before their block was wished for
+    pending_compact_blocks: LruMap<BlockId, CompactBlockInfo>,
+    /// Block data requests waiting for a response, and their retries
+    ask_manager: BlockAskManager,
     peer_cmd_sender: MassaSender<PeerManagementCmd>,
     sender_propagation_ops: MassaSender<OperationHandlerPropagationCommand>,
     sender_propagation_endorsements: MassaSender<EndorsementHandlerPropagationCommand>,
     endorsement_cache: SharedEndorsementCache,
     operation_cache: SharedOperationCache,
+    operation_sig_verifier: SharedOperationSigVerifier,
     next_timer_ask_block: Instant,
     cache: SharedBlockCache,
     config: ProtocolConfig,
@@ -108,6 +180,7 @@ pub struct RetrievalThread {
     mip_store: MipStore,
     massa_metrics: MassaMetrics,
     operation_id_serializer: OperationIdSerializer,
+    operation_deserializer: SecureShareDeserializer<Operation, OperationDeserializer>,
 }
 
 impl RetrievalThread {
@@ -124,7 +197,10 @@ impl RetrievalThread {
                 max_op_datastore_key_length: self.config.max_op_datastore_key_length,
                 max_op_datastore_value_length: self.config.max_op_datastore_value_length,
                 max_denunciations_in_block_header: self.config.max_denunciations_in_block_header,
+                max_header_size: self.config.max_block_header_size,
+                max_header_denunciations_size: self.config.max_block_header_denunciations_size,
                 last_start_period: Some(self.config.last_start_period),
+                max_block_body_size: self.config.max_serialized_operations_size_per_block as u64,
             });
 
         let tick_update_metrics = tick(self.massa_metrics.tick_delay);
@@ -133,16 +209,27 @@ impl RetrievalThread {
                 recv(self.receiver_network) -> msg => {
                     self.receiver_network.update_metrics();
                     match msg {
-                        Ok((peer_id, message)) => {
+                        Ok((peer_id, message, queued_at)) => {
+                            let _handling_timer = MessageHandlingTimer::start(MessageTypeId::Block.family(), queued_at);
                             let (rest, message) = match block_message_deserializer
-                                .deserialize::<DeserializeError>(&message) {
+                                .deserialize::<BlockMessageDeserializeError>(&message) {
                                 Ok((rest, message)) => (rest, message),
                                 Err(err) => {
-                                    warn!("Error in deserializing block message: {:?}", err);
+                                    if let Some(part) = BlockMessageDeserializeError::oversized_part(&err) {
+                                        DeserializationFailure::TooLarge.record(MessageTypeId::Block.family(), &peer_id);
+                                        report_misbehavior(&self.peer_cmd_sender, &peer_id, MessageTypeId::Block.family(), Misbehavior::OversizedMessage);
+                                        warn!("Peer {} sent a block message with an oversized {}", peer_id, part);
+                                    } else {
+                                        DeserializationFailure::Malformed.record(MessageTypeId::Block.family(), &peer_id);
+                                        report_misbehavior(&self.peer_cmd_sender, &peer_id, MessageTypeId::Block.family(), Misbehavior::InvalidMessage);
+                                        warn!("Error in deserializing block message: {:?}", err);
+                                    }
                                     continue;
                                 }
                             };
                             if !rest.is_empty() {
+                                DeserializationFailure::TrailingBytes.record(MessageTypeId::Block.family(), &peer_id);
+                                report_misbehavior(&self.peer_cmd_sender, &peer_id, MessageTypeId::Block.family(), Misbehavior::InvalidMessage);
                                 println!("Error: message not fully consumed");
                                 return;
                             }
@@ -155,9 +242,15 @@ impl RetrievalThread {
                                    self.update_block_retrieval();
                                 }
                                 BlockMessage::Header(header) => {
+                                    self.check_block_announcement(&peer_id, &header.id);
                                     self.on_block_header_received(peer_id, header);
                                     self.update_block_retrieval();
                                 }
+                                BlockMessage::CompactBlock{header, short_op_ids, operations} => {
+                                    self.check_block_announcement(&peer_id, &header.id);
+                                    self.on_compact_block_received(peer_id, header, short_op_ids, operations);
+                                    self.update_block_retrieval();
+                                }
                             }
                         },
                         Err(_) => {
@@ -178,6 +271,10 @@ impl RetrievalThread {
                                             block_id,
                                             BlockInfo::new(header, self.storage.clone_without_refs()),
                                         );
+                                        // rebuild the block from its compact block if we received it beforehand
+                                        if let Some(compact_block) = self.pending_compact_blocks.remove(&block_id) {
+                                            self.reconcile_compact_block(block_id, compact_block);
+                                        }
                                     }
                                     // Cleanup the knowledge that we asked this list of blocks to nodes.
                                     self.remove_asked_blocks(&remove);
@@ -213,7 +310,7 @@ impl RetrievalThread {
                             .sum();
 
                         self.massa_metrics.set_block_cache_metrics(
-                            block_read.checked_headers.len(),
+                            block_read.checked_headers_len(),
                             count,
                         );
                     }
@@ -254,7 +351,7 @@ impl RetrievalThread {
         // only applied if the response is successfully sent to the peer
         let mut block_knowledge_updates = PreHashSet::default();
         let mut operation_knowledge_updates = PreHashSet::default();
-        let mut endorsement_knowledge_updates = PreHashSet::default();
+        let mut endorsement_knowledge_updates = Vec::new();
 
         // retrieve block data from storage
         let stored_header_op_ids = self.storage.read_blocks().get(&block_id).map(|block| {
@@ -276,14 +373,7 @@ impl RetrievalThread {
 
                 // once sent, the peer will know about the endorsements in that block,
                 // no need to announce those endorsements to that peer anymore
-                endorsement_knowledge_updates.extend(
-                    header
-                        .content
-                        .endorsements
-                        .iter()
-                        .map(|e| e.id)
-                        .collect::<PreHashSet<EndorsementId>>(),
-                );
+                endorsement_knowledge_updates.extend(header.content.endorsements.iter().cloned());
 
                 BlockInfoReply::Header(header)
             }
@@ -327,6 +417,29 @@ impl RetrievalThread {
 
                 BlockInfoReply::Operations(returned_ops)
             }
+            (
+                Some((_, block_op_ids)),
+                AskForBlockInfo::OperationChunks {
+                    data_chunks,
+                    parity_chunks,
+                    indices,
+                },
+            ) => {
+                // the peer asked for chunks of the block body
+                match self.get_block_body_chunks(&block_op_ids, data_chunks, parity_chunks, indices)
+                {
+                    Ok((body_size, chunks)) => {
+                        BlockInfoReply::OperationChunks { body_size, chunks }
+                    }
+                    Err(err) => {
+                        debug!(
+                            "could not send body chunks of block {} to {}: {}",
+                            block_id, from_peer_id, err
+                        );
+                        BlockInfoReply::NotFound
+                    }
+                }
+            }
         };
 
         debug!(
@@ -371,17 +484,54 @@ impl RetrievalThread {
             );
         }
         if !endorsement_knowledge_updates.is_empty() {
-            self.endorsement_cache
-                .write()
-                .insert_peer_known_endorsements(
-                    &from_peer_id,
-                    &endorsement_knowledge_updates
-                        .into_iter()
-                        .collect::<Vec<_>>(),
-                );
+            self.endorsement_cache.insert_peer_known_endorsements(
+                &from_peer_id,
+                endorsement_knowledge_updates.iter(),
+            );
         }
     }
 
+    /// Get chunks of the body of a block, along with the size of the body.
+    ///
+    /// At most `data_chunks` distinct chunks are returned, so that the reply is not larger than the body itself.
+    /// Fails if some operations of the block are not in storage.
+    fn get_block_body_chunks(
+        &self,
+        block_op_ids: &[OperationId],
+        data_chunks: u32,
+        parity_chunks: u32,
+        mut indices: Vec<u32>,
+    ) -> Result<(u64, Vec<(u32, Vec<u8>)>), ProtocolError> {
+        let operations: Option<Vec<SecureShareOperation>> = {
+            let op_storage_lock = self.storage.read_operations();
+            block_body_operation_ids(block_op_ids)
+                .iter()
+                .map(|op_id| op_storage_lock.get(op_id).cloned())
+                .collect()
+        };
+        let Some(operations) = operations else {
+            return Err(ProtocolError::GeneralProtocolError(
+                "some operations of the block are missing".to_string(),
+            ));
+        };
+        let body = serialize_block_body(&operations)?;
+        let mut chunks = encode_block_body(&body, data_chunks as usize, parity_chunks as usize)?;
+        indices.sort_unstable();
+        indices.dedup();
+        indices.truncate(data_chunks as usize);
+        Ok((
+            body.len() as u64,
+            indices
+                .into_iter()
+                .filter_map(|index| {
+                    chunks
+                        .get_mut(index as usize)
+                        .map(|chunk| (index, std::mem::take(chunk)))
+                })
+                .collect(),
+        ))
+    }
+
     /// A peer sent us a response to one of our requests for block data
     fn on_block_info_received(
         &mut self,
@@ -407,6 +557,10 @@ impl RetrievalThread {
                 // and wait for them to have been procesed(i.e. added to storage).
                 self.on_block_full_operations_received(from_peer_id, block_id, operations);
             }
+            BlockInfoReply::OperationChunks { body_size, chunks } => {
+                // Gather the chunks and rebuild the block body once we have enough of them
+                self.on_block_body_chunks_received(from_peer_id, block_id, body_size, chunks);
+            }
             BlockInfoReply::NotFound => {
                 // The peer doesn't know about the block. Mark it as such.
                 self.cache
@@ -416,6 +570,23 @@ impl RetrievalThread {
         }
     }
 
+    /// Report a peer announcing a block it already told us it knows
+    fn check_block_announcement(&self, from_peer_id: &PeerId, block_id: &BlockId) {
+        let already_announced = self
+            .cache
+            .read()
+            .peer_known_block(from_peer_id, block_id)
+            .map_or(false, |(known, _)| known);
+        if already_announced {
+            report_misbehavior(
+                &self.peer_cmd_sender,
+                from_peer_id,
+                MessageTypeId::Block.family(),
+                Misbehavior::UselessAnnouncement,
+            );
+        }
+    }
+
     /// On block header received from a node.
     fn on_block_header_received(&mut self, from_peer_id: PeerId, header: SecuredHeader) {
         debug!("received header {} from {}", header.id, from_peer_id);
@@ -459,39 +630,189 @@ impl RetrievalThread {
         }
     }
 
+    /// On compact block received from a node.
+    ///
+    /// The header is processed as if it was announced alone,
+    /// then the block operations are rebuilt from their short IDs and the operations we know about,
+    /// either right away if we are looking for the block, or as soon as we start looking for it.
+    fn on_compact_block_received(
+        &mut self,
+        from_peer_id: PeerId,
+        header: SecuredHeader,
+        short_op_ids: Vec<u64>,
+        operations: Vec<SecureShareOperation>,
+    ) {
+        let block_id = header.id;
+        debug!("received compact block {} from {}", block_id, from_peer_id);
+        // Note that the number of short IDs and operations was checked at deserialization to not overflow the max per block.
+
+        // Check the header and update knowledge info
+        self.on_block_header_received(from_peer_id, header.clone());
+        if self.cache.read().peek_checked_header(&block_id).is_none() {
+            // the header was refused
+            return;
+        }
+
+        // Nothing to rebuild if we already have the block or its operation list
+        if self.storage.read_blocks().contains(&block_id)
+            || self
+                .block_wishlist
+                .get(&block_id)
+                .map_or(false, |info| info.operation_ids.is_some())
+        {
+            return;
+        }
+
+        // Check the operations sent along, ignoring the ones that are not in the block
+        let short_op_ids_set: HashSet<u64> = short_op_ids.iter().copied().collect();
+        let operations: Vec<_> = operations
+            .into_iter()
+            .filter(|op| short_op_ids_set.contains(&operation_short_id(&op.id)))
+            .collect();
+        if let Err(err) = note_operations_from_peer(
+            &self.storage,
+            &mut self.operation_cache,
+            &self.config,
+            operations.clone(),
+            &from_peer_id,
+            &mut self.sender_propagation_ops,
+            &mut self.pool_controller,
+            &self.operation_sig_verifier,
+        ) {
+            warn!(
+                "Peer id {} sent us operations for compact block {} but they failed validity checks: {}",
+                from_peer_id, block_id, err
+            );
+            if let Err(err) = self.ban_peers(&[from_peer_id]) {
+                warn!("Error while banning peer {} err: {:?}", from_peer_id, err);
+            }
+            return;
+        }
+        let mut storage = self.storage.clone_without_refs();
+        storage.store_operations(operations);
+
+        let compact_block = CompactBlockInfo {
+            from_peer_id,
+            header,
+            short_op_ids,
+            storage,
+        };
+        if self.block_wishlist.contains_key(&block_id) {
+            self.reconcile_compact_block(block_id, compact_block);
+        } else {
+            self.pending_compact_blocks.insert(block_id, compact_block);
+        }
+    }
+
+    /// Rebuild the operation list of a wished block from a compact block.
+    ///
+    /// The short IDs are resolved against the operations we know about.
+    /// If some of them can't be resolved unambiguously, or if the resolved list doesn't match
+    /// the operation merkle root of the header (short ID collision),
+    /// the operation list is left to be asked for as usual.
+    /// The operations sent along the compact block are kept in any case.
+    fn reconcile_compact_block(&mut self, block_id: BlockId, compact_block: CompactBlockInfo) {
+        let CompactBlockInfo {
+            from_peer_id,
+            header,
+            short_op_ids,
+            storage,
+        } = compact_block;
+
+        // check that we are looking for the operation list of that block
+        let wishlist_info = match self
+            .block_wishlist
+            .get_mut(&block_id)
+            .filter(|i| i.operation_ids.is_none())
+        {
+            Some(info) => info,
+            None => return,
+        };
+        let header = wishlist_info.header.get_or_insert(header);
+
+        // keep the operations sent along the compact block
+        wishlist_info.storage.extend(storage);
+
+        // resolve the short IDs
+        let mut matching_ops: HashMap<u64, Vec<OperationId>> = short_op_ids
+            .iter()
+            .map(|short_op_id| (*short_op_id, Vec::new()))
+            .collect();
+        for op_id in self.storage.read_operations().ids() {
+            if let Some(ops) = matching_ops.get_mut(&operation_short_id(op_id)) {
+                ops.push(*op_id);
+            }
+        }
+        let operation_ids: Option<Vec<OperationId>> = short_op_ids
+            .iter()
+            .map(|short_op_id| match matching_ops[short_op_id].as_slice() {
+                [op_id] => Some(*op_id),
+                _ => None,
+            })
+            .collect();
+        let Some(operation_ids) = operation_ids else {
+            debug!(
+                "could not resolve all the operations of compact block {} from {}",
+                block_id, from_peer_id
+            );
+            return;
+        };
+        if header.content.operation_merkle_root
+            != compute_operations_hash(&operation_ids, &self.operation_id_serializer)
+        {
+            debug!(
+                "operations resolved for compact block {} from {} don't match the header",
+                block_id, from_peer_id
+            );
+            return;
+        }
+
+        // mark the sender as knowing the block and its operations
+        self.cache
+            .write()
+            .insert_peer_known_block(&from_peer_id, &[block_id], true);
+        self.operation_cache.write().insert_peer_known_ops(
+            &from_peer_id,
+            &operation_ids
+                .iter()
+                .map(|op_id| op_id.prefix())
+                .collect::<Vec<_>>(),
+        );
+
+        // Save the rebuilt operation ID list to the wishlist
+        wishlist_info.operation_ids = Some(operation_ids);
+
+        // free up all the nodes that we asked for that operation list
+        self.remove_asked_blocks(&[block_id].into_iter().collect());
+    }
+
     /// Check if the incoming header network version is compatible with the current node
     fn check_network_version_compatibility(
         &self,
         header: &SecuredHeader,
     ) -> Result<(), ProtocolError> {
-        let slot = header.content.slot;
-        let timestamp = get_block_slot_timestamp(
+        check_header_network_version(
+            header,
+            &self.mip_store,
             self.config.thread_count,
             self.config.t0,
             self.config.genesis_timestamp,
-            slot,
-        )?;
-
-        let current_version = self.mip_store.get_network_version_active_at(timestamp);
-        if header.content.current_version != current_version {
-            // Received a block version different from current version given by mip store
-            return Err(ProtocolError::IncompatibleNetworkVersion {
-                local: current_version,
-                received: header.content.current_version,
-            });
-        }
-
-        if let Some(announced_version) = header.content.announced_version {
-            if announced_version <= current_version {
+        )
+        .map_err(|err| match err {
+            HeaderCheckError::IncompatibleNetworkVersion { local, received } => {
+                // Received a block version different from current version given by mip store
+                ProtocolError::IncompatibleNetworkVersion { local, received }
+            }
+            HeaderCheckError::OutdatedAnnouncedNetworkVersion { local, announced } => {
                 // Received an announced network version that is already known
-                return Err(ProtocolError::OutdatedAnnouncedNetworkVersion {
-                    local: current_version,
-                    announced_received: announced_version,
-                });
+                ProtocolError::OutdatedAnnouncedNetworkVersion {
+                    local,
+                    announced_received: announced,
+                }
             }
-        }
-
-        Ok(())
+            HeaderCheckError::ModelsError(err) => err.into(),
+            err => ProtocolError::InvalidBlock(err.to_string()),
+        })
     }
 
     /// Performs validity checks on a block header,
@@ -505,11 +826,10 @@ impl RetrievalThread {
     /// - Not genesis
     /// - Compatible version
     /// - Can compute a `BlockId`
-    /// - Valid signature
+    /// - Structure pre-filters, see `check_header_structure`
+    /// - Block producer matches the draws, if they are available
     /// - All endorsement are valid
-    /// - Endorsements have unique indices
-    /// - Endorsement slots match that of the block
-    /// - Endorsed blocks match the same-thread parent of the header
+    /// - Valid signature
     pub(crate) fn note_header_from_peer(
         &mut self,
         header: &SecuredHeader,
@@ -529,7 +849,7 @@ impl RetrievalThread {
         let is_new;
         {
             let mut cache_write = self.cache.write();
-            is_new = cache_write.checked_headers.get(&block_id).is_none();
+            is_new = cache_write.get_checked_header(&block_id).is_none();
             if !is_new {
                 // the header was previously verified
 
@@ -545,13 +865,8 @@ impl RetrievalThread {
         // if the header was previously verified, update peer knowledge information and return Ok(false)
         if !is_new {
             // mark the sender peer as knowing the endorsements in the block
-            {
-                let endorsement_ids: Vec<_> =
-                    header.content.endorsements.iter().map(|e| e.id).collect();
-                self.endorsement_cache
-                    .write()
-                    .insert_peer_known_endorsements(from_peer_id, &endorsement_ids);
-            }
+            self.endorsement_cache
+                .insert_peer_known_endorsements(from_peer_id, header.content.endorsements.iter());
 
             // mark the sender peer as knowing the operations of the block (if we know them)
             let opt_block_ops: Option<Vec<_>> =
@@ -572,6 +887,14 @@ impl RetrievalThread {
             return Ok(false);
         }
 
+        // cheap pre-filters, applied before signature verification
+        if let Err(err) = check_header_structure(header, &self.config, MassaTime::now())
+            .and_then(|_| check_header_producer(header, self.selector_controller.as_ref()))
+        {
+            self.massa_metrics.inc_protocol_prefilter_rejected_headers();
+            return Err(err);
+        }
+
         // check endorsements
         if let Err(err) = note_endorsements_from_peer(
             header.content.endorsements.clone(),
@@ -597,44 +920,9 @@ impl RetrievalThread {
             )));
         };
 
-        // check endorsement integrity within the context of the header
-        let mut used_endorsement_indices: HashSet<u32> =
-            HashSet::with_capacity(header.content.endorsements.len());
-        for endorsement in header.content.endorsements.iter() {
-            // check index reuse
-            if !used_endorsement_indices.insert(endorsement.content.index) {
-                return Err(ProtocolError::InvalidBlock(format!(
-                    "duplicate endorsement index: {}",
-                    endorsement.content.index
-                )));
-            }
-            // check slot
-            if endorsement.content.slot != header.content.slot {
-                return Err(ProtocolError::InvalidBlock(format!(
-                    "endorsement slot {} does not match header slot: {}",
-                    endorsement.content.slot, header.content.slot
-                )));
-            }
-            // check endorsed block
-            if endorsement.content.endorsed_block
-                != header.content.parents[header.content.slot.thread as usize]
-            {
-                return Err(ProtocolError::InvalidBlock(format!(
-                    "endorsed block {} does not match header parent: {}",
-                    endorsement.content.endorsed_block,
-                    header.content.parents[header.content.slot.thread as usize]
-                )));
-            }
-        }
-
         // mark the sender peer as knowing the endorsements in the block
-        {
-            let endorsement_ids: Vec<_> =
-                header.content.endorsements.iter().map(|e| e.id).collect();
-            self.endorsement_cache
-                .write()
-                .insert_peer_known_endorsements(from_peer_id, &endorsement_ids);
-        }
+        self.endorsement_cache
+            .insert_peer_known_endorsements(from_peer_id, header.content.endorsements.iter());
 
         {
             let mut cache_lock = self.cache.write();
@@ -647,7 +935,7 @@ impl RetrievalThread {
             );
 
             // mark us as knowing the header
-            cache_lock.checked_headers.insert(block_id, header.clone());
+            cache_lock.insert_checked_header(header.clone());
         }
 
         Ok(true)
@@ -660,13 +948,9 @@ impl RetrievalThread {
             .map_err(|err| ProtocolError::SendError(err.to_string()))
     }
 
-    /// Remove the given blocks from the local wishlist
+    /// Stop waiting for the responses to the requests of the given blocks
     pub(crate) fn remove_asked_blocks(&mut self, remove_hashes: &PreHashSet<BlockId>) {
-        for asked_blocks in self.asked_blocks.values_mut() {
-            for remove_h in remove_hashes {
-                asked_blocks.remove(remove_h);
-            }
-        }
+        self.ask_manager.remove_blocks(remove_hashes);
     }
 
     /// Mark a block as invalid
@@ -684,8 +968,9 @@ impl RetrievalThread {
         let mut peers_to_ban = Vec::new();
         {
             let cache_read = self.cache.read();
+            let block_id_prefix = block_id.prefix();
             for (peer_id, peer_known_blocks) in cache_read.blocks_known_by_peer.iter() {
-                if peer_known_blocks.peek(block_id).is_some() {
+                if peer_known_blocks.peek(&block_id_prefix).is_some() {
                     peers_to_ban.push(*peer_id);
                 }
             }
@@ -882,6 +1167,7 @@ impl RetrievalThread {
             &from_peer_id,
             &mut self.sender_propagation_ops,
             &mut self.pool_controller,
+            &self.operation_sig_verifier,
         ) {
             warn!(
                 "Peer id {} sent us operations for block id {} but they failed validity checks: {}",
@@ -900,6 +1186,7 @@ impl RetrievalThread {
 
         if wishlist_info.storage.get_op_refs().len() == block_ops_set.len() {
             // if we gathered all the ops, we should delete the asked history and mark the sender as knowing the block
+            wishlist_info.asked_operations.clear();
             self.remove_asked_blocks(&[block_id].into_iter().collect());
 
             // Mark the sender as knowing this block
@@ -907,23 +1194,313 @@ impl RetrievalThread {
                 .write()
                 .insert_peer_known_block(&from_peer_id, &[block_id], true);
         } else {
-            // otherwise, we should remove the current peer ask only and mark it as not knowing the block
-            // because it did not send us everything
-            if let Some(asked) = self.asked_blocks.get_mut(&from_peer_id) {
-                asked.remove(&block_id);
-            }
+            // otherwise, we should remove the current peer ask only.
+            // If we asked the peer for a part of the missing operations, it knows the block if it sent us its whole part,
+            // otherwise we mark it as not knowing the block because it did not send us everything.
+            let sent_asked_part = wishlist_info
+                .asked_operations
+                .remove(&from_peer_id)
+                .map_or(false, |asked_part| {
+                    asked_part.is_subset(wishlist_info.storage.get_op_refs())
+                });
+            self.ask_manager.record_response(&from_peer_id, &block_id);
 
-            // Mark the sender as not knowing this block
             self.cache
                 .write()
-                .insert_peer_known_block(&from_peer_id, &[block_id], false);
+                .insert_peer_known_block(&from_peer_id, &[block_id], sent_asked_part);
+        }
+    }
+
+    /// We received erasure-coded chunks of the body of a block.
+    ///
+    /// Once enough chunks are gathered, the body is rebuilt and its operations are processed
+    /// as if the peer that sent the last chunks had sent them in full.
+    /// If the body can't be rebuilt or doesn't match the operation list of the block,
+    /// its missing operations are asked for directly instead.
+    fn on_block_body_chunks_received(
+        &mut self,
+        from_peer_id: PeerId,
+        block_id: BlockId,
+        body_size: u64,
+        chunks: Vec<(u32, Vec<u8>)>,
+    ) {
+        debug!(
+            "received {} body chunks for block {} from {}",
+            chunks.len(),
+            block_id,
+            &from_peer_id
+        );
+        // Note that the body size was checked at deserialization to not overflow the max block size.
+
+        // the peer answered, no need to wait for it anymore
+        self.ask_manager.record_response(&from_peer_id, &block_id);
+
+        // check that the chunks are consistent with the announced body size
+        let data_chunks = self.config.block_body_data_chunks;
+        let parity_chunks = self.config.block_body_parity_chunks;
+        let chunk_size = block_body_chunk_size(body_size as usize, data_chunks);
+        if chunks.iter().any(|(index, chunk)| {
+            *index as usize >= data_chunks + parity_chunks || chunk.len() != chunk_size
+        }) {
+            warn!(
+                "Peer id {} sent us invalid body chunks for block id {}",
+                from_peer_id, block_id
+            );
+            if let Err(err) = self.ban_peers(&[from_peer_id]) {
+                warn!("Error while banning peer {} err: {:?}", from_peer_id, err);
+            }
+            return;
+        }
+
+        // Mark the sender as knowing this block
+        self.cache
+            .write()
+            .insert_peer_known_block(&from_peer_id, &[block_id], true);
+
+        // Ensure that we were looking for that data.
+        let wishlist_info = if let Some(info) = self
+            .block_wishlist
+            .get_mut(&block_id)
+            .filter(|i| i.operation_ids.is_some() && i.body_chunks.is_some())
+        {
+            info
+        } else {
+            debug!(
+                "Peer id {} sent us body chunks for block id {} but we were not looking for them",
+                from_peer_id, block_id
+            );
+            return;
+        };
+        let body_chunks = wishlist_info
+            .body_chunks
+            .as_mut()
+            .expect("body_chunks presence in wishlist should have been checked above");
+
+        // peers announcing different bodies can't be told apart: give up on chunks
+        if body_chunks
+            .body_size
+            .map_or(false, |size| size != body_size)
+        {
+            warn!(
+                "Peer id {} sent us body chunks for block id {} with a body size that differs from other peers",
+                from_peer_id, block_id
+            );
+            wishlist_info.body_chunks = None;
+            wishlist_info.body_chunking_failed = true;
+            return;
+        }
+        body_chunks.body_size = Some(body_size);
+        for (index, chunk) in chunks {
+            body_chunks.chunks[index as usize] = Some(chunk);
+        }
+        if body_chunks.chunks.iter().flatten().count() < data_chunks {
+            // wait for more chunks
+            return;
+        }
+
+        // rebuild the body and check that it matches the operation list of the block
+        let chunks = std::mem::take(&mut body_chunks.chunks);
+        wishlist_info.body_chunks = None;
+        let body_op_ids = block_body_operation_ids(
+            wishlist_info
+                .operation_ids
+                .as_ref()
+                .expect("operation_ids presence in wishlist should have been checked above"),
+        );
+        let operations = decode_block_body(chunks, data_chunks, parity_chunks, body_size as usize)
+            .and_then(|body| {
+                deserialize_block_body(&body, body_op_ids.len(), &self.operation_deserializer)
+            })
+            .and_then(|operations| {
+                if operations
+                    .iter()
+                    .map(|op| op.id)
+                    .eq(body_op_ids.iter().copied())
+                {
+                    Ok(operations)
+                } else {
+                    Err(ProtocolError::InvalidBlock(
+                        "rebuilt body doesn't match the operation list of the block".to_string(),
+                    ))
+                }
+            });
+        match operations {
+            Ok(operations) => {
+                self.on_block_full_operations_received(from_peer_id, block_id, operations);
+            }
+            Err(err) => {
+                warn!(
+                    "Could not rebuild the body of block id {} from its chunks, asking for its operations instead: {}",
+                    block_id, err
+                );
+                wishlist_info.body_chunking_failed = true;
+            }
+        }
+    }
+
+    /// Ask the missing chunks of the body of a block, spreading them over the given peers (from best to worst).
+    ///
+    /// Returns false if the missing operations of the block should be asked for directly instead:
+    /// chunking is disabled, too few operations are missing, no peer negotiated the chunked bodies,
+    /// or gathering the chunks failed.
+    fn ask_block_body_chunks(
+        &mut self,
+        block_id: BlockId,
+        missing_operations: usize,
+        peers: &[PeerId],
+        now: Instant,
+    ) -> bool {
+        if !self.config.block_body_chunking
+            || missing_operations < self.config.block_body_chunking_min_missing_operations
+        {
+            return false;
+        }
+        let data_chunks = self.config.block_body_data_chunks;
+        let parity_chunks = self.config.block_body_parity_chunks;
+        let Some(wishlist_info) = self.block_wishlist.get_mut(&block_id) else {
+            return false;
+        };
+        if wishlist_info.body_chunking_failed {
+            return false;
+        }
+        // older peers do not understand the requests for chunks: ask them for the operations if no peer does
+        let peers: Vec<PeerId> = peers
+            .iter()
+            .filter(|peer_id| {
+                self.active_connections
+                    .get_peer_capabilities(peer_id)
+                    .map_or(false, |negotiated| {
+                        negotiated.supports(Capabilities::BLOCK_BODY_CHUNKS)
+                    })
+            })
+            .copied()
+            .collect();
+        if peers.is_empty() {
+            return false;
+        }
+        let body_chunks = wishlist_info
+            .body_chunks
+            .get_or_insert_with(|| BlockBodyChunks::new(data_chunks + parity_chunks));
+        if body_chunks.ask_rounds >= MAX_BLOCK_BODY_CHUNK_ASK_ROUNDS {
+            debug!(
+                "could not gather the body chunks of block {}, asking for its operations instead",
+                block_id
+            );
+            wishlist_info.body_chunks = None;
+            wishlist_info.body_chunking_failed = true;
+            return false;
+        }
+
+        // spread the missing chunks over the best peers, at most `data_chunks` per peer
+        let missing_indices: Vec<u32> = body_chunks
+            .chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| chunk.is_none())
+            .map(|(index, _)| index as u32)
+            .collect();
+        let peer_count = peers.len().min(missing_indices.len());
+        if peer_count == 0 {
+            return true;
+        }
+        body_chunks.ask_rounds += 1;
+        let mut peer_indices = vec![Vec::new(); peer_count];
+        for (i, index) in missing_indices.into_iter().enumerate() {
+            peer_indices[i % peer_count].push(index);
+        }
+
+        for (peer_id, mut indices) in peers.iter().zip(peer_indices) {
+            indices.truncate(data_chunks);
+            let request = AskForBlockInfo::OperationChunks {
+                data_chunks: data_chunks as u32,
+                parity_chunks: parity_chunks as u32,
+                indices,
+            };
+            debug!(
+                "Sending ask for block {} data to {}: {:?}",
+                block_id, peer_id, &request
+            );
+            if let Err(err) = self.active_connections.send_to_peer(
+                peer_id,
+                &self.block_message_serializer,
+                Message::Block(Box::new(BlockMessage::DataRequest {
+                    block_id,
+                    block_info: request,
+                })),
+                true,
+            ) {
+                warn!(
+                    "Failed to send BlockDataRequest to peer {} err: {}",
+                    peer_id, err
+                );
+            } else {
+                self.ask_manager.record_ask(*peer_id, block_id, now);
+            }
+        }
+        true
+    }
+
+    /// Ask disjoint parts of the missing operations of a block to the given peers (from best to worst),
+    /// so that the operations we already hold are not downloaded again and no peer has to send them all.
+    ///
+    /// Returns false if the missing operations should be asked to a single peer instead:
+    /// too few operations are missing, or too few peers are known to hold the block.
+    fn ask_block_operations(
+        &mut self,
+        block_id: BlockId,
+        missing_operations: &[OperationId],
+        peers: &[PeerId],
+        now: Instant,
+    ) -> bool {
+        let Some(wishlist_info) = self.block_wishlist.get_mut(&block_id) else {
+            return false;
+        };
+        wishlist_info.asked_operations.clear();
+        let peer_count = peers
+            .len()
+            .min(missing_operations.len() / MIN_OPERATIONS_ASKED_PER_PEER);
+        if peer_count < 2 {
+            return false;
+        }
+
+        // spread the missing operations over the best peers
+        let mut peer_operations = vec![Vec::new(); peer_count];
+        for (i, op_id) in missing_operations.iter().enumerate() {
+            peer_operations[i % peer_count].push(*op_id);
+        }
+
+        let mut asked = false;
+        for (peer_id, operations) in peers.iter().zip(peer_operations) {
+            let asked_part: PreHashSet<OperationId> = operations.iter().copied().collect();
+            let request = AskForBlockInfo::Operations(operations);
+            debug!(
+                "Sending ask for block {} data to {}: {:?}",
+                block_id, peer_id, &request
+            );
+            if let Err(err) = self.active_connections.send_to_peer(
+                peer_id,
+                &self.block_message_serializer,
+                Message::Block(Box::new(BlockMessage::DataRequest {
+                    block_id,
+                    block_info: request,
+                })),
+                true,
+            ) {
+                warn!(
+                    "Failed to send BlockDataRequest to peer {} err: {}",
+                    peer_id, err
+                );
+            } else {
+                asked = true;
+                wishlist_info.asked_operations.insert(*peer_id, asked_part);
+                self.ask_manager.record_ask(*peer_id, block_id, now);
+            }
         }
+        asked
     }
 
     /// function that updates the global state of block retrieval
     pub(crate) fn update_block_retrieval(&mut self) {
-        let ask_block_timeout = self.config.ask_block_timeout.to_duration();
-
         // Init timer for next tick
         let now = Instant::now();
         let mut next_tick = now
@@ -931,7 +1508,7 @@ impl RetrievalThread {
             .ok_or(TimeError::TimeOverflowError)
             .expect("could not compute next block retrieval timer tick");
 
-        if self.asked_blocks.is_empty() && self.block_wishlist.is_empty() {
+        if self.ask_manager.is_empty() && self.block_wishlist.is_empty() {
             return;
         }
 
@@ -941,79 +1518,73 @@ impl RetrievalThread {
         // Update cache
         self.cache.write().update_cache(&connected_peers);
 
-        // Cleanup asked_blocks from all disconnected peers and blocks that are not in the wishlist anymore.
-        self.asked_blocks.retain(|peer_id, asked_blocks| {
-            if !connected_peers.contains(peer_id) {
-                return false;
-            }
-            asked_blocks.retain(|block_id, _| self.block_wishlist.contains_key(block_id));
-            !asked_blocks.is_empty()
+        // Forget the requests sent to disconnected peers and the blocks that are not in the wishlist anymore.
+        self.ask_manager.retain(&connected_peers, |block_id| {
+            self.block_wishlist.contains_key(block_id)
         });
 
-        // list of blocks that need to be asked
-        let mut to_ask: PreHashSet<BlockId> = self.block_wishlist.keys().copied().collect();
-        // the number of things already being asked to those peers
-        let mut peer_loads: HashMap<PeerId, usize> = Default::default();
-        for (peer_id, asked_blocks) in &mut self.asked_blocks {
-            // init the list of items to remove from asked_blocks
-            let mut to_remove_from_asked_blocks = Vec::new();
-            for (block_id, ask_time) in asked_blocks.iter() {
-                let expiry = ask_time
-                    .checked_add(ask_block_timeout)
-                    .expect("could not compute block ask expiry");
-                if expiry <= now {
-                    // the block has been asked for the block data a long time agp and did not respond
-
-                    // we mark this peer as not knowing this block
-                    self.cache
-                        .write()
-                        .insert_peer_known_block(peer_id, &[*block_id], false);
-
-                    // We mark the block for removal from the asked_blocks list.
-                    // This prevents us from re-detecting the timeout many times.
-                    to_remove_from_asked_blocks.push(*block_id);
-                } else {
-                    // this block was recently asked to this peer: no need to ask for the block for now
-
-                    to_ask.remove(block_id);
-
-                    // mark this peer as loaded with an angoing ask
-                    peer_loads
-                        .entry(*peer_id)
-                        .and_modify(|v| *v += 1)
-                        .or_insert(1);
-
-                    // update next tick
-                    next_tick = next_tick.min(expiry);
-                }
-            }
-            // remove the blocks marked for removal from asked_blocks
-            for remove_id in to_remove_from_asked_blocks {
-                asked_blocks.remove(&remove_id);
-            }
+        // the peers that did not answer in time are marked as not knowing the block,
+        // and the block is asked again to the next best peer
+        for (peer_id, block_id) in self.ask_manager.collect_timeouts(now) {
+            self.cache
+                .write()
+                .insert_peer_known_block(&peer_id, &[block_id], false);
+            report_misbehavior(
+                &self.peer_cmd_sender,
+                &peer_id,
+                MessageTypeId::Block.family(),
+                Misbehavior::Timeout,
+            );
+        }
+        if let Some(next_expiry) = self.ask_manager.next_expiry() {
+            next_tick = next_tick.min(next_expiry);
         }
 
+        // list of blocks that need to be asked: the ones without a request waiting for a response.
+        // Header-first pipeline: the headers of all the wanted blocks are asked first, as they are small
+        // and needed to validate the blocks. The bodies of the blocks whose header is known are then downloaded
+        // concurrently from distinct peers, oldest slot first, within a window of `max_block_bodies_in_flight` blocks.
+        let outstanding_blocks = self.ask_manager.outstanding_blocks();
+        let (mut headers_to_ask, bodies_to_ask) = select_blocks_to_ask(
+            self.block_wishlist
+                .iter()
+                .map(|(block_id, info)| (*block_id, info.header.as_ref().map(|h| h.content.slot))),
+            &outstanding_blocks,
+            self.config.max_block_bodies_in_flight,
+        );
+        headers_to_ask.shuffle(&mut thread_rng()); // shuffle ask order
+        let to_ask = headers_to_ask.into_iter().chain(bodies_to_ask);
+
         // for each block to ask, choose a peer to ask it from and perform the ask
-        let mut to_ask = to_ask.into_iter().collect::<Vec<_>>();
-        to_ask.shuffle(&mut thread_rng()); // shuffle ask order
         for block_id in to_ask {
+            // when downloading a body, spreading the load over the peers matters more than the freshness of their knowledge
+            let body_stage = self
+                .block_wishlist
+                .get(&block_id)
+                .map_or(false, |info| info.header.is_some());
             // prioritize peers by (max knowledge, min knowledge age, min load, max random)
             let mut peer_scores: Vec<_> = connected_peers
                 .iter()
                 .filter_map(|peer_id| {
                     // Get the peer load. Look for the minimum score for asking.
-                    let peer_load = peer_loads.get(peer_id).copied().unwrap_or_default();
-                    if peer_load >= self.config.max_simultaneous_ask_blocks_per_node {
+                    if !self.ask_manager.can_ask(peer_id) {
                         // this peer is already loaded with too many asks
                         return None;
                     }
+                    let peer_load = self.ask_manager.peer_load(peer_id);
+                    if self.ask_manager.has_timed_out(peer_id, &block_id) {
+                        // the peer did not answer a request of that block in time: fail over to the others first
+                        return Some((
+                            2i8,                       // timed out
+                            None,                      // N/A
+                            peer_load,                 // the lower the load the better
+                            thread_rng().gen::<u64>(), // random tie breaker,
+                            *peer_id,
+                        ));
+                    }
                     // get peer knowledge info about that block
-                    let peer_knowledge_of_block = self
-                        .cache
-                        .read()
-                        .blocks_known_by_peer
-                        .get(peer_id)
-                        .and_then(|blocks_known| blocks_known.peek(&block_id).copied());
+                    let peer_knowledge_of_block =
+                        self.cache.read().peer_known_block(peer_id, &block_id);
                     match peer_knowledge_of_block {
                         Some((false, info_t)) => {
                             // we think that the peer doesn't know the block
@@ -1038,9 +1609,11 @@ impl RetrievalThread {
                         Some((true, info_t)) => {
                             // we think that the peer knows the block
                             Some((
-                                -1i8,                                                           // best knowledge
-                                Some(now.saturating_duration_since(info_t).as_millis() as i64), // the newer the info the better
-                                peer_load,                 // the lower the load the better
+                                -1i8, // best knowledge
+                                (!body_stage).then(|| {
+                                    now.saturating_duration_since(info_t).as_millis() as i64
+                                }), // the newer the info the better
+                                peer_load, // the lower the load the better
                                 thread_rng().gen::<u64>(), // random tie breaker,
                                 *peer_id,
                             ))
@@ -1069,7 +1642,27 @@ impl RetrievalThread {
                 (true, true) => {
                     // gather missing block operations and perform necessary followups
                     match self.gather_missing_block_ops(&block_id) {
-                        Some(ops) => AskForBlockInfo::Operations(ops),
+                        Some(ops) => {
+                            // prefer gathering the block body as chunks from several peers
+                            let peers: Vec<_> = peer_scores
+                                .iter()
+                                .map(|(_, _, _, _, peer_id)| *peer_id)
+                                .collect();
+                            if self.ask_block_body_chunks(block_id, ops.len(), &peers, now) {
+                                continue;
+                            }
+                            // otherwise spread the missing operations over the peers we think know the block
+                            let peers_knowing_block: Vec<_> = peer_scores
+                                .iter()
+                                .filter(|(knowledge, _, _, _, _)| *knowledge < 0)
+                                .map(|(_, _, _, _, peer_id)| *peer_id)
+                                .collect();
+                            if self.ask_block_operations(block_id, &ops, &peers_knowing_block, now)
+                            {
+                                continue;
+                            }
+                            AskForBlockInfo::Operations(ops)
+                        }
                         None => continue,
                     }
                 }
@@ -1097,18 +1690,7 @@ impl RetrievalThread {
                     );
                 } else {
                     // The request was sent.
-
-                    // Update the asked_blocks list
-                    self.asked_blocks
-                        .entry(peer_id)
-                        .or_default()
-                        .insert(block_id, now);
-
-                    // Increment the load of the peer.
-                    peer_loads
-                        .entry(peer_id)
-                        .and_modify(|v| *v += 1)
-                        .or_insert(1);
+                    self.ask_manager.record_ask(peer_id, block_id, now);
 
                     // No need to look for other peers.
                     break;
@@ -1251,6 +1833,39 @@ impl RetrievalThread {
     }
 }
 
+/// Selects the wanted blocks to ask, given the slot of their header if it is known.
+/// The headers of the blocks without a pending request are all asked. The bodies of the blocks with a known header
+/// are asked oldest slot first, so that at most `max_bodies_in_flight` bodies are being downloaded.
+///
+/// # Returns
+/// The blocks whose header must be asked, and the blocks whose body must be asked
+fn select_blocks_to_ask(
+    wanted_blocks: impl IntoIterator<Item = (BlockId, Option<Slot>)>,
+    outstanding_blocks: &PreHashSet<BlockId>,
+    max_bodies_in_flight: usize,
+) -> (Vec<BlockId>, Vec<BlockId>) {
+    let mut headers_to_ask = Vec::new();
+    let mut bodies_to_ask = Vec::new();
+    let mut bodies_in_flight = 0usize;
+    for (block_id, header_slot) in wanted_blocks {
+        match (header_slot, outstanding_blocks.contains(&block_id)) {
+            (None, false) => headers_to_ask.push(block_id),
+            (Some(slot), false) => bodies_to_ask.push((slot, block_id)),
+            (Some(_), true) => bodies_in_flight += 1,
+            (None, true) => {}
+        }
+    }
+    bodies_to_ask.sort_unstable();
+    bodies_to_ask.truncate(max_bodies_in_flight.saturating_sub(bodies_in_flight));
+    (
+        headers_to_ask,
+        bodies_to_ask
+            .into_iter()
+            .map(|(_, block_id)| block_id)
+            .collect(),
+    )
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn start_retrieval_thread(
     active_connections: Box<dyn ActiveConnectionsTrait>,
@@ -1266,6 +1881,7 @@ pub fn start_retrieval_thread(
     config: ProtocolConfig,
     endorsement_cache: SharedEndorsementCache,
     operation_cache: SharedOperationCache,
+    operation_sig_verifier: SharedOperationSigVerifier,
     cache: SharedBlockCache,
     storage: Storage,
     mip_store: MipStore,
@@ -1273,6 +1889,14 @@ pub fn start_retrieval_thread(
 ) -> JoinHandle<()> {
     let block_message_serializer =
         MessagesSerializer::new().with_block_message_serializer(BlockMessageSerializer::new());
+    let operation_deserializer = SecureShareDeserializer::new(OperationDeserializer::new(
+        config.max_size_value_datastore,
+        config.max_size_function_name,
+        config.max_size_call_sc_parameter,
+        config.max_op_datastore_entry_count,
+        config.max_op_datastore_key_length,
+        config.max_op_datastore_value_length,
+    ));
     std::thread::Builder::new()
         .name("protocol-block-handler-retrieval".to_string())
         .spawn(move || {
@@ -1283,7 +1907,16 @@ pub fn start_retrieval_thread(
                 pool_controller,
                 next_timer_ask_block: Instant::now() + config.ask_block_timeout.to_duration(),
                 block_wishlist: PreHashMap::default(),
-                asked_blocks: HashMap::default(),
+                pending_compact_blocks: LruMap::new(ByLength::new(
+                    config
+                        .max_blocks_kept_for_propagation
+                        .try_into()
+                        .expect("max_blocks_kept_for_propagation does not fit in u32"),
+                )),
+                ask_manager: BlockAskManager::new(
+                    config.ask_block_timeout.to_duration(),
+                    config.max_simultaneous_ask_blocks_per_node,
+                ),
                 peer_cmd_sender,
                 sender_propagation_ops,
                 sender_propagation_endorsements,
@@ -1294,13 +1927,58 @@ pub fn start_retrieval_thread(
                 cache,
                 endorsement_cache,
                 operation_cache,
+                operation_sig_verifier,
                 config,
                 storage,
                 mip_store,
                 massa_metrics,
                 operation_id_serializer: OperationIdSerializer::new(),
+                operation_deserializer,
             };
             retrieval_thread.run();
         })
         .expect("OS failed to start block retrieval thread")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+
+    fn block_id(index: u8) -> BlockId {
+        BlockId::generate_from_hash(Hash::compute_from(&[index]))
+    }
+
+    #[test]
+    fn test_select_blocks_to_ask() {
+        // blocks 0 and 1 have no header, blocks 2 to 5 have one, at decreasing slots
+        let wanted_blocks = vec![
+            (block_id(0), None),
+            (block_id(1), None),
+            (block_id(2), Some(Slot::new(4, 0))),
+            (block_id(3), Some(Slot::new(3, 0))),
+            (block_id(4), Some(Slot::new(2, 0))),
+            (block_id(5), Some(Slot::new(1, 0))),
+        ];
+
+        // the headers are all asked, the bodies oldest first within the window
+        let (mut headers, bodies) =
+            select_blocks_to_ask(wanted_blocks.clone(), &PreHashSet::default(), 2);
+        headers.sort_unstable();
+        let mut expected_headers = vec![block_id(0), block_id(1)];
+        expected_headers.sort_unstable();
+        assert_eq!(headers, expected_headers);
+        assert_eq!(bodies, vec![block_id(5), block_id(4)]);
+
+        // the pending requests are not asked again, and the bodies being downloaded fill the window
+        let outstanding = PreHashSet::from_iter([block_id(0), block_id(5)]);
+        let (headers, bodies) = select_blocks_to_ask(wanted_blocks.clone(), &outstanding, 2);
+        assert_eq!(headers, vec![block_id(1)]);
+        assert_eq!(bodies, vec![block_id(4)]);
+
+        // no body is asked while the window is full
+        let outstanding = PreHashSet::from_iter([block_id(4), block_id(5)]);
+        let (_, bodies) = select_blocks_to_ask(wanted_blocks, &outstanding, 2);
+        assert!(bodies.is_empty());
+    }
+}
@@ -1,71 +1,347 @@
+//! Knowledge cache of the endorsements, shared between the endorsement and block handlers.
+//!
+//! Endorsements are identified by what they endorse: their slot, their index in the slot and
+//! the endorsed block. Each cached endorsement records whether we checked it and which peers know it,
+//! so that there is a single LRU for all the peers instead of one per peer.
+//! The cache is split into shards locked independently, and all the endorsements of a given
+//! slot and index fall in the same shard, which allows detecting producers endorsing several blocks
+//! for the same slot and index.
+
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
 };
 
-use massa_models::endorsement::EndorsementId;
+use massa_models::{
+    address::Address,
+    block_id::BlockId,
+    endorsement::{EndorsementId, SecureShareEndorsement},
+    slot::Slot,
+};
 use massa_protocol_exports::PeerId;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use schnellru::{ByLength, LruMap};
 
+/// Number of independently locked shards of the endorsement cache
+pub(crate) const ENDORSEMENT_CACHE_SHARD_COUNT: usize = 16;
+
+/// Key of an endorsement in the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EndorsementKey {
+    /// slot of the endorsement
+    pub slot: Slot,
+    /// index of the endorsement in the slot
+    pub index: u32,
+    /// block endorsed
+    pub endorsed_block: BlockId,
+}
+
+impl From<&SecureShareEndorsement> for EndorsementKey {
+    fn from(endorsement: &SecureShareEndorsement) -> Self {
+        EndorsementKey {
+            slot: endorsement.content.slot,
+            index: endorsement.content.index,
+            endorsed_block: endorsement.content.endorsed_block,
+        }
+    }
+}
+
+/// Endorsement producer that endorsed two different blocks for the same slot and index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndorsementEquivocation {
+    /// slot of the endorsements
+    pub slot: Slot,
+    /// index of the endorsements in the slot
+    pub index: u32,
+    /// producer of the endorsements
+    pub producer: Address,
+    /// first endorsement seen for that slot and index
+    pub first_endorsement: EndorsementId,
+    /// conflicting endorsement, endorsing another block
+    pub conflicting_endorsement: EndorsementId,
+}
+
+/// Hook called when an endorsement equivocation is detected
+pub type EndorsementEquivocationHook = Box<dyn Fn(&EndorsementEquivocation) + Send + Sync>;
+
+/// What we know about a cached endorsement
+struct EndorsementKnowledge {
+    /// ID of the endorsement
+    endorsement_id: EndorsementId,
+    /// whether we checked the endorsement ourselves
+    checked: bool,
+    /// connected peers that know the endorsement
+    known_by: HashSet<PeerId>,
+}
+
+impl EndorsementKnowledge {
+    fn new(endorsement_id: EndorsementId) -> Self {
+        EndorsementKnowledge {
+            endorsement_id,
+            checked: false,
+            known_by: HashSet::new(),
+        }
+    }
+}
+
+/// Shard of the endorsement cache
+struct EndorsementCacheShard {
+    /// knowledge about the endorsements
+    endorsements: LruMap<EndorsementKey, EndorsementKnowledge>,
+    /// first checked endorsement, and the block it endorses, for each slot and index
+    endorsed_blocks: LruMap<(Slot, u32), (BlockId, EndorsementId)>,
+}
+
+impl EndorsementCacheShard {
+    /// Knowledge about an endorsement, reset if the cached one under the same key has another ID
+    fn knowledge_mut(
+        &mut self,
+        key: EndorsementKey,
+        endorsement_id: EndorsementId,
+    ) -> Option<&mut EndorsementKnowledge> {
+        let knowledge = self
+            .endorsements
+            .get_or_insert(key, || EndorsementKnowledge::new(endorsement_id))?;
+        if knowledge.endorsement_id != endorsement_id {
+            *knowledge = EndorsementKnowledge::new(endorsement_id);
+        }
+        Some(knowledge)
+    }
+}
+
 /// Cache of endorsements
 pub struct EndorsementCache {
-    /// List of endorsements we checked recently
-    pub checked_endorsements: LruMap<EndorsementId, ()>,
-    /// List of endorsements known by peers
-    pub endorsements_known_by_peer: HashMap<PeerId, LruMap<EndorsementId, ()>>,
-    /// Maximum number of endorsements known by a peer
-    pub max_known_endorsements_by_peer: u32,
+    shards: Vec<RwLock<EndorsementCacheShard>>,
+    /// peers connected at the last cache update
+    connected_peers: Mutex<HashSet<PeerId>>,
+    /// hooks called on the detection of an endorsement equivocation
+    equivocation_hooks: Vec<EndorsementEquivocationHook>,
 }
 
 impl EndorsementCache {
-    /// Create a new EndorsementCache
-    pub fn new(max_known_endorsements: u32, max_known_endorsements_by_peer: u32) -> Self {
+    /// Create a new EndorsementCache holding up to about `max_known_endorsements` endorsements
+    pub fn new(max_known_endorsements: u32) -> Self {
+        let shard_capacity = max_known_endorsements
+            .div_ceil(ENDORSEMENT_CACHE_SHARD_COUNT as u32)
+            .max(1);
         Self {
-            checked_endorsements: LruMap::new(ByLength::new(max_known_endorsements)),
-            endorsements_known_by_peer: HashMap::new(),
-            max_known_endorsements_by_peer,
+            shards: (0..ENDORSEMENT_CACHE_SHARD_COUNT)
+                .map(|_| {
+                    RwLock::new(EndorsementCacheShard {
+                        endorsements: LruMap::new(ByLength::new(shard_capacity)),
+                        endorsed_blocks: LruMap::new(ByLength::new(shard_capacity)),
+                    })
+                })
+                .collect(),
+            connected_peers: Mutex::new(HashSet::new()),
+            equivocation_hooks: Vec::new(),
         }
     }
 
-    /// Mark a list of endorsement IDs prefixes as known by a peer
-    pub fn insert_peer_known_endorsements(
-        &mut self,
+    /// Add a hook called each time an endorsement equivocation is detected
+    pub fn with_equivocation_hook(mut self, hook: EndorsementEquivocationHook) -> Self {
+        self.equivocation_hooks.push(hook);
+        self
+    }
+
+    /// Shard holding the endorsements of a slot and index
+    fn shard(&self, slot: &Slot, index: u32) -> &RwLock<EndorsementCacheShard> {
+        let hash = slot
+            .period
+            .wrapping_mul(31)
+            .wrapping_add(slot.thread as u64)
+            .wrapping_mul(31)
+            .wrapping_add(index as u64);
+        &self.shards[(hash % self.shards.len() as u64) as usize]
+    }
+
+    /// Whether we already checked an endorsement
+    pub fn is_checked(&self, endorsement: &SecureShareEndorsement) -> bool {
+        let key = EndorsementKey::from(endorsement);
+        self.shard(&key.slot, key.index)
+            .read()
+            .endorsements
+            .peek(&key)
+            .map_or(false, |knowledge| {
+                knowledge.checked && knowledge.endorsement_id == endorsement.id
+            })
+    }
+
+    /// Whether a peer knows an endorsement
+    pub fn is_known_by_peer(&self, peer_id: &PeerId, endorsement: &SecureShareEndorsement) -> bool {
+        let key = EndorsementKey::from(endorsement);
+        self.shard(&key.slot, key.index)
+            .read()
+            .endorsements
+            .peek(&key)
+            .map_or(false, |knowledge| {
+                knowledge.endorsement_id == endorsement.id && knowledge.known_by.contains(peer_id)
+            })
+    }
+
+    /// Mark a list of endorsements as known by a peer
+    pub fn insert_peer_known_endorsements<'a>(
+        &self,
         peer_id: &PeerId,
-        endorsements: &[EndorsementId],
+        endorsements: impl IntoIterator<Item = &'a SecureShareEndorsement>,
     ) {
-        let known_endorsements = self
-            .endorsements_known_by_peer
-            .entry(*peer_id)
-            .or_insert_with(|| LruMap::new(ByLength::new(self.max_known_endorsements_by_peer)));
         for endorsement in endorsements {
-            known_endorsements.insert(*endorsement, ());
+            let key = EndorsementKey::from(endorsement);
+            let mut shard = self.shard(&key.slot, key.index).write();
+            if let Some(knowledge) = shard.knowledge_mut(key, endorsement.id) {
+                knowledge.known_by.insert(*peer_id);
+            }
         }
     }
 
-    /// Mark an endorsement ID as checked by us
-    pub fn insert_checked_endorsement(&mut self, enrodsement_id: EndorsementId) {
-        self.checked_endorsements.insert(enrodsement_id, ());
+    /// Mark a list of endorsements as checked by us, and report the equivocations they reveal
+    pub fn insert_checked_endorsements<'a>(
+        &self,
+        endorsements: impl IntoIterator<Item = &'a SecureShareEndorsement>,
+    ) {
+        let mut equivocations = Vec::new();
+        for endorsement in endorsements {
+            let key = EndorsementKey::from(endorsement);
+            let mut shard = self.shard(&key.slot, key.index).write();
+            if let Some(knowledge) = shard.knowledge_mut(key, endorsement.id) {
+                knowledge.checked = true;
+            }
+            match shard.endorsed_blocks.peek(&(key.slot, key.index)) {
+                Some((block_id, _)) if *block_id == key.endorsed_block => {}
+                Some((_, first_endorsement)) => {
+                    equivocations.push(EndorsementEquivocation {
+                        slot: key.slot,
+                        index: key.index,
+                        producer: endorsement.content_creator_address,
+                        first_endorsement: *first_endorsement,
+                        conflicting_endorsement: endorsement.id,
+                    });
+                }
+                None => {
+                    shard
+                        .endorsed_blocks
+                        .insert((key.slot, key.index), (key.endorsed_block, endorsement.id));
+                }
+            }
+        }
+        // hooks are called without holding any shard lock
+        for equivocation in equivocations.iter() {
+            for hook in self.equivocation_hooks.iter() {
+                hook(equivocation);
+            }
+        }
     }
 
     /// Update caches to remove all data from disconnected peers
-    pub fn update_cache(&mut self, peers_connected: &HashSet<PeerId>) {
-        // Remove disconnected peers from cache
-        self.endorsements_known_by_peer
-            .retain(|peer_id, _| peers_connected.contains(peer_id));
-
-        // Add new connected peers to cache
-        for peer_id in peers_connected {
-            match self.endorsements_known_by_peer.entry(*peer_id) {
-                std::collections::hash_map::Entry::Occupied(_) => {}
-                std::collections::hash_map::Entry::Vacant(entry) => {
-                    entry.insert(LruMap::new(ByLength::new(
-                        self.max_known_endorsements_by_peer,
-                    )));
-                }
+    pub fn update_cache(&self, peers_connected: &HashSet<PeerId>) {
+        let mut connected_peers = self.connected_peers.lock();
+        let any_disconnected = connected_peers
+            .iter()
+            .any(|peer_id| !peers_connected.contains(peer_id));
+        connected_peers.clone_from(peers_connected);
+        drop(connected_peers);
+        if !any_disconnected {
+            return;
+        }
+        for shard in self.shards.iter() {
+            for (_, knowledge) in shard.write().endorsements.iter_mut() {
+                knowledge
+                    .known_by
+                    .retain(|peer_id| peers_connected.contains(peer_id));
+            }
+        }
+    }
+
+    /// Number of endorsements we checked, and number of known endorsements summed over the peers
+    pub fn stats(&self) -> (usize, usize) {
+        let mut checked_count = 0;
+        let mut known_by_peer_count = 0;
+        for shard in self.shards.iter() {
+            for (_, knowledge) in shard.read().endorsements.iter() {
+                checked_count += knowledge.checked as usize;
+                known_by_peer_count += knowledge.known_by.len();
             }
         }
+        (checked_count, known_by_peer_count)
+    }
+
+    /// Group endorsements by the block they endorse, keeping their order within each block
+    pub fn group_by_endorsed_block(
+        endorsements: Vec<SecureShareEndorsement>,
+    ) -> Vec<(BlockId, Vec<SecureShareEndorsement>)> {
+        let mut positions: HashMap<BlockId, usize> = HashMap::new();
+        let mut groups: Vec<(BlockId, Vec<SecureShareEndorsement>)> = Vec::new();
+        for endorsement in endorsements {
+            let block_id = endorsement.content.endorsed_block;
+            let position = *positions.entry(block_id).or_insert_with(|| {
+                groups.push((block_id, Vec::new()));
+                groups.len() - 1
+            });
+            groups[position].1.push(endorsement);
+        }
+        groups
     }
 }
 
-pub type SharedEndorsementCache = Arc<RwLock<EndorsementCache>>;
+pub type SharedEndorsementCache = Arc<EndorsementCache>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+    use massa_models::endorsement::{Endorsement, EndorsementSerializer};
+    use massa_models::secure_share::SecureShareContent;
+    use massa_signature::KeyPair;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn endorsement(keypair: &KeyPair, index: u32, block: &[u8]) -> SecureShareEndorsement {
+        let content = Endorsement {
+            slot: Slot::new(10, 1),
+            index,
+            endorsed_block: BlockId::generate_from_hash(Hash::compute_from(block)),
+        };
+        Endorsement::new_verifiable(content, EndorsementSerializer::new(), keypair).unwrap()
+    }
+
+    #[test]
+    fn test_endorsement_cache_knowledge_and_equivocation() {
+        let keypair = KeyPair::generate(0).unwrap();
+        let peer_a = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+        let peer_b = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+        let equivocations = Arc::new(AtomicUsize::new(0));
+        let hook_equivocations = equivocations.clone();
+        let cache = EndorsementCache::new(100).with_equivocation_hook(Box::new(move |_| {
+            hook_equivocations.fetch_add(1, Ordering::Relaxed);
+        }));
+
+        let first = endorsement(&keypair, 0, b"a");
+        let other_index = endorsement(&keypair, 1, b"b");
+        assert!(!cache.is_checked(&first));
+        cache.insert_checked_endorsements([&first, &other_index]);
+        assert!(cache.is_checked(&first));
+        assert_eq!(equivocations.load(Ordering::Relaxed), 0);
+
+        cache.insert_peer_known_endorsements(&peer_a, [&first]);
+        cache.insert_peer_known_endorsements(&peer_b, [&first, &other_index]);
+        assert!(cache.is_known_by_peer(&peer_a, &first));
+        assert!(!cache.is_known_by_peer(&peer_a, &other_index));
+        assert_eq!(cache.stats(), (2, 3));
+
+        // disconnected peers are forgotten
+        cache.update_cache(&HashSet::from([peer_a, peer_b]));
+        cache.update_cache(&HashSet::from([peer_b]));
+        assert!(!cache.is_known_by_peer(&peer_a, &first));
+        assert!(cache.is_known_by_peer(&peer_b, &first));
+
+        // the same producer endorsing another block for the same slot and index
+        let conflicting = endorsement(&keypair, 0, b"c");
+        cache.insert_checked_endorsements([&conflicting]);
+        assert_eq!(equivocations.load(Ordering::Relaxed), 1);
+
+        let groups =
+            EndorsementCache::group_by_endorsed_block(vec![first.clone(), conflicting, first]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].1.len(), 2);
+        assert_eq!(groups[1].1.len(), 1);
+    }
+}
@@ -1,6 +1,8 @@
 use super::{
-    cache::SharedEndorsementCache, commands_propagation::EndorsementHandlerPropagationCommand,
-    messages::EndorsementMessageSerializer, EndorsementMessage,
+    cache::{EndorsementCache, SharedEndorsementCache},
+    commands_propagation::EndorsementHandlerPropagationCommand,
+    messages::EndorsementMessageSerializer,
+    EndorsementMessage,
 };
 use crate::{messages::MessagesSerializer, wrap_network::ActiveConnectionsTrait};
 use massa_channel::receiver::MassaReceiver;
@@ -65,7 +67,8 @@ impl PropagationThread {
         }
     }
 
-    /// Perform propagation of endorsements to the connected peers
+    /// Perform propagation of endorsements to the connected peers,
+    /// bundled by the block they endorse
     fn propagate_endorsements(&mut self, endorsements: Storage) {
         // get all the endorsements to send
         let endorsements: Vec<_> = {
@@ -80,55 +83,44 @@ impl PropagationThread {
         // get connected peers
         let peers_connected = self.active_connections.get_peer_ids_connected();
 
-        // get a write lock on the cache
-        let mut cache_write = self.cache.write();
-
         // mark that we have checked those endorsements
-        for endorsement in &endorsements {
-            cache_write.checked_endorsements.insert(endorsement.id, ());
-        }
+        self.cache.insert_checked_endorsements(endorsements.iter());
+
+        // forget the knowledge of the peers that disconnected
+        self.cache.update_cache(&peers_connected);
 
-        // Add peers that potentially don't exist in cache and remove the ones that disconnected
-        cache_write.update_cache(&peers_connected);
+        let bundles = EndorsementCache::group_by_endorsed_block(endorsements);
 
         // Propagate to peers
         'peer_loop: for peer_id in peers_connected {
-            // write access to the cache of which endorsements are known by the peer
-            let peer_knowledge = cache_write
-                .endorsements_known_by_peer
-                .get_mut(&peer_id)
-                .expect("update_cache should have added connected peer to cache");
-
-            // get endorsements that are not known by the peer
-            let to_send: Vec<_> = endorsements
-                .iter()
-                .filter(|endorsement| peer_knowledge.peek(&endorsement.id).is_none())
-                .collect();
+            for (_, bundle) in bundles.iter() {
+                // get endorsements of the bundle that are not known by the peer
+                let to_send: Vec<_> = bundle
+                    .iter()
+                    .filter(|endorsement| !self.cache.is_known_by_peer(&peer_id, endorsement))
+                    .collect();
 
-            if to_send.is_empty() {
-                // nothing to send to that peer, try the next one
-                continue 'peer_loop;
-            }
-
-            // send by chunks
-            for chunk in to_send.chunks(self.config.max_endorsements_per_message as usize) {
-                if let Err(err) = self.active_connections.send_to_peer(
-                    &peer_id,
-                    &self.endorsement_serializer,
-                    EndorsementMessage::Endorsements(chunk.iter().map(|&e| e.clone()).collect())
+                // send by chunks
+                for chunk in to_send.chunks(self.config.max_endorsements_per_message as usize) {
+                    if let Err(err) = self.active_connections.send_to_peer(
+                        &peer_id,
+                        &self.endorsement_serializer,
+                        EndorsementMessage::Endorsements(
+                            chunk.iter().map(|&e| e.clone()).collect(),
+                        )
                         .into(),
-                    false,
-                ) {
-                    warn!(
-                        "could not send endorsements batch to node {}: {}",
-                        peer_id, err
-                    );
-                    // try with next peer, this one is probably congested
-                    continue 'peer_loop;
-                }
-                // sent successfully: mark peer as knowing the endorsements that were sent to it
-                for endorsement in chunk {
-                    peer_knowledge.insert(endorsement.id, ());
+                        false,
+                    ) {
+                        warn!(
+                            "could not send endorsements batch to node {}: {}",
+                            peer_id, err
+                        );
+                        // try with next peer, this one is probably congested
+                        continue 'peer_loop;
+                    }
+                    // sent successfully: mark peer as knowing the endorsements that were sent to it
+                    self.cache
+                        .insert_peer_known_endorsements(&peer_id, chunk.iter().copied());
                 }
             }
         }
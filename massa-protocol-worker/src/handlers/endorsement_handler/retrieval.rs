@@ -6,8 +6,7 @@ use massa_logging::massa_trace;
 use massa_metrics::MassaMetrics;
 use massa_models::{
     endorsement::SecureShareEndorsement,
-    prehash::{CapacityAllocator, PreHashMap, PreHashSet},
-    timeslots::get_block_slot_timestamp,
+    prehash::{CapacityAllocator, PreHashMap},
 };
 use massa_pool_exports::PoolController;
 use massa_pos_exports::SelectorController;
@@ -21,8 +20,13 @@ use tracing::{debug, info, warn};
 use crate::{
     handlers::{
         endorsement_handler::messages::EndorsementMessage,
-        peer_handler::models::{PeerManagementCmd, PeerMessageTuple},
+        peer_handler::{
+            models::{PeerManagementCmd, PeerMessageTuple},
+            reputation::{report_misbehavior, Misbehavior},
+        },
     },
+    messages::{DeserializationFailure, MessageHandlingTimer, MessageTypeId},
+    sanity::{check_slot_plausibility, is_endorsement_stale},
     sig_verifier::verify_sigs_batch,
 };
 
@@ -57,7 +61,10 @@ impl RetrievalThread {
                 recv(self.receiver) -> msg => {
                     self.receiver.update_metrics();
                     match msg {
-                        Ok((peer_id, message)) => self.process_message(peer_id, message),
+                        Ok((peer_id, message, queued_at)) => {
+                            let _handling_timer = MessageHandlingTimer::start(MessageTypeId::Endorsement.family(), queued_at);
+                            self.process_message(peer_id, message)
+                        }
                         Err(_) => {
                             info!("Stop endorsement retrieval thread");
                             return;
@@ -83,14 +90,9 @@ impl RetrievalThread {
                 },
                 recv(tick_metrics) -> _ => {
                     // update metrics
-                    let cache_lock = self.cache.read();
-                    let count = cache_lock
-                        .endorsements_known_by_peer
-                        .values()
-                        .map(|v| v.len())
-                        .sum();
+                    let (checked_count, known_by_peer_count) = self.cache.stats();
                     self.metrics
-                        .set_endorsements_cache_metrics(cache_lock.checked_endorsements.len(), count);
+                        .set_endorsements_cache_metrics(checked_count, known_by_peer_count);
                 }
             }
         }
@@ -104,6 +106,14 @@ impl RetrievalThread {
         {
             Ok((rest, message)) => (rest, message),
             Err(err) => {
+                DeserializationFailure::Malformed
+                    .record(MessageTypeId::Endorsement.family(), &peer_id);
+                report_misbehavior(
+                    &self.peer_cmd_sender,
+                    &peer_id,
+                    MessageTypeId::Endorsement.family(),
+                    Misbehavior::InvalidMessage,
+                );
                 debug!(
                     "Error while deserializing message from peer {} err: {:?}",
                     peer_id, err
@@ -112,12 +122,47 @@ impl RetrievalThread {
             }
         };
         if !rest.is_empty() {
+            DeserializationFailure::TrailingBytes
+                .record(MessageTypeId::Endorsement.family(), &peer_id);
+            report_misbehavior(
+                &self.peer_cmd_sender,
+                &peer_id,
+                MessageTypeId::Endorsement.family(),
+                Misbehavior::InvalidMessage,
+            );
             debug!("Message not fully consumed");
             return;
         }
         match message {
-            EndorsementMessage::Endorsements(endorsements) => {
+            EndorsementMessage::Endorsements(mut endorsements) => {
                 debug!("Received endorsement message: Endorsement from {}", peer_id);
+                // pre-filters, applied before signature verification
+                let now = MassaTime::now();
+                let received_count = endorsements.len();
+                endorsements
+                    .retain(|endorsement| !is_endorsement_stale(endorsement, &self.config, now));
+                let stale_count = received_count - endorsements.len();
+                if stale_count > 0 {
+                    self.metrics
+                        .inc_protocol_prefilter_rejected_endorsements(stale_count as u64);
+                }
+                if let Err(err) = endorsements.iter().try_for_each(|endorsement| {
+                    check_slot_plausibility(&endorsement.content.slot, &self.config, now)
+                }) {
+                    self.metrics
+                        .inc_protocol_prefilter_rejected_endorsements(endorsements.len() as u64);
+                    warn!(
+                        "peer {} sent us implausible endorsements. Err = {}",
+                        peer_id, err
+                    );
+                    if let Err(err) = self.ban_peer(&peer_id) {
+                        warn!("Error while banning peer {} err: {:?}", peer_id, err);
+                    }
+                    return;
+                }
+                if endorsements.is_empty() {
+                    return;
+                }
                 if let Err(err) = note_endorsements_from_peer(
                     endorsements,
                     &peer_id,
@@ -159,6 +204,7 @@ impl RetrievalThread {
 /// Does not ban if the endorsement is invalid
 ///
 /// Checks performed:
+/// - Valid PoS draw, checked first as it is cheaper.
 /// - Valid signature.
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn note_endorsements_from_peer(
@@ -172,40 +218,14 @@ pub(crate) fn note_endorsements_from_peer(
     pool_controller: &mut dyn PoolController,
 ) -> Result<(), ProtocolError> {
     let mut new_endorsements = PreHashMap::with_capacity(endorsements.len());
-    let mut all_endorsement_ids = PreHashSet::with_capacity(endorsements.len());
-
-    // cache check
-    {
-        let cache_read = cache.read();
-        for endorsement in endorsements.into_iter() {
-            let endorsement_id = endorsement.id;
-            all_endorsement_ids.insert(endorsement_id);
 
-            // only consider the endorsement as new if we have not already checked it
-            if cache_read
-                .checked_endorsements
-                .peek(&endorsement_id)
-                .is_none()
-            {
-                new_endorsements.insert(endorsement_id, endorsement);
-            }
+    // cache check: only consider the endorsements we have not already checked as new
+    for endorsement in endorsements.iter() {
+        if !cache.is_checked(endorsement) {
+            new_endorsements.insert(endorsement.id, endorsement.clone());
         }
     }
 
-    // Batch signature verification
-    verify_sigs_batch(
-        &new_endorsements
-            .values()
-            .map(|endorsement| {
-                (
-                    endorsement.compute_signed_hash(),
-                    endorsement.signature,
-                    endorsement.content_creator_pub_key,
-                )
-            })
-            .collect::<Vec<_>>(),
-    )?;
-
     // Check PoS draws
     for endorsement in new_endorsements.values() {
         let selection = selector_controller
@@ -225,36 +245,20 @@ pub(crate) fn note_endorsements_from_peer(
         }
     }
 
-    {
-        let mut cache_write = cache.write();
+    // Batch signature verification
+    verify_sigs_batch(&new_endorsements.values().collect::<Vec<_>>())?;
 
-        // add to the cache of endorsements we have checked
-        for endorsement_id in all_endorsement_ids.iter() {
-            cache_write.insert_checked_endorsement(*endorsement_id);
-        }
+    // add to the cache of endorsements we have checked
+    cache.insert_checked_endorsements(endorsements.iter());
 
-        // add to the cache of endorsements known by the source node
-        cache_write.insert_peer_known_endorsements(
-            from_peer_id,
-            &all_endorsement_ids.iter().copied().collect::<Vec<_>>(),
-        );
-    }
+    // add to the cache of endorsements known by the source node
+    cache.insert_peer_known_endorsements(from_peer_id, endorsements.iter());
 
     // From there we note new endorsements and propagate them
 
     // Filter out endorsements if they are too old (max age of the inclusion slot: `max_endorsements_propagation_time`)
     let now = MassaTime::now();
-    new_endorsements.retain(|_id, endorsement| {
-        match get_block_slot_timestamp(
-            config.thread_count,
-            config.t0,
-            config.genesis_timestamp,
-            endorsement.content.slot,
-        ) {
-            Ok(t) => t.saturating_add(config.max_endorsements_propagation_time) >= now,
-            Err(_) => false,
-        }
-    });
+    new_endorsements.retain(|_id, endorsement| !is_endorsement_stale(endorsement, config, now));
 
     if new_endorsements.is_empty() {
         // no endorsements to note or propagate
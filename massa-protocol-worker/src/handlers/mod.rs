@@ -2,3 +2,4 @@ pub mod block_handler;
 pub mod endorsement_handler;
 pub mod operation_handler;
 pub mod peer_handler;
+pub mod topic_handler;
@@ -0,0 +1,149 @@
+//! Planning of the operations asked to peers.
+//!
+//! Announced operations are not simply asked to the peer that announced them:
+//! each operation is assigned to the least loaded of the connected peers known to have it,
+//! within a per-peer budget of estimated bytes asked per `operation_batch_proc_period`.
+//! This spreads large batches across peers instead of overloading the first announcer,
+//! and operations assigned to the same peer are coalesced into as few `AskForOperations` messages as possible.
+//! Operations that no candidate peer can serve within its budget are deferred to a later period.
+
+use std::collections::HashMap;
+
+use massa_models::operation::{OperationPrefixId, OperationPrefixIds};
+use massa_models::prehash::{CapacityAllocator, PreHashSet};
+use massa_protocol_exports::PeerId;
+
+/// Serialized size estimate of an operation before any operation was received
+const INITIAL_OPERATION_SIZE_ESTIMATE: u64 = 256;
+
+/// Plan of the operations to ask
+#[derive(Debug, Default)]
+pub struct AskPlan {
+    /// operations to ask to each peer
+    pub asks: HashMap<PeerId, OperationPrefixIds>,
+    /// operations that could not be assigned during this period
+    pub deferred: OperationPrefixIds,
+}
+
+/// Assigns operations to ask to peers within per-peer size budgets
+pub struct AskPlanner {
+    /// estimated bytes of operations asked to each peer during the current period
+    asked_bytes: HashMap<PeerId, u64>,
+    /// estimated serialized size of an operation, learned from the received operations
+    operation_size_estimate: u64,
+    /// maximal estimated bytes of operations asked to a peer per period
+    max_asked_bytes_per_peer: u64,
+}
+
+impl AskPlanner {
+    /// Creates a planner with a per-peer budget of estimated bytes per period
+    pub fn new(max_asked_bytes_per_peer: u64) -> Self {
+        AskPlanner {
+            asked_bytes: HashMap::new(),
+            operation_size_estimate: INITIAL_OPERATION_SIZE_ESTIMATE,
+            max_asked_bytes_per_peer,
+        }
+    }
+
+    /// Starts a new period, restoring the budgets of all peers
+    pub fn reset_period(&mut self) {
+        self.asked_bytes.clear();
+    }
+
+    /// Refines the operation size estimate with the size of a received operation
+    pub fn record_received_operation_size(&mut self, size: usize) {
+        // exponential moving average with a 1/8 weight for the new sample
+        self.operation_size_estimate = self
+            .operation_size_estimate
+            .saturating_mul(7)
+            .saturating_add(size as u64)
+            / 8;
+    }
+
+    /// Assigns each operation to the least loaded of its candidate peers that still has budget for it.
+    /// A peer that was asked nothing yet during the period always accepts one operation,
+    /// so that operations larger than the budget can still be retrieved.
+    ///
+    /// # Arguments
+    /// * `operations`: operations to ask, each with the peers known to have it, by order of preference
+    pub fn plan(&mut self, operations: Vec<(OperationPrefixId, Vec<PeerId>)>) -> AskPlan {
+        let mut plan = AskPlan::default();
+        for (op_id, candidates) in operations {
+            let chosen = candidates
+                .iter()
+                .map(|peer_id| (peer_id, self.asked_bytes.get(peer_id).copied().unwrap_or(0)))
+                .filter(|(_, asked)| {
+                    *asked == 0
+                        || asked.saturating_add(self.operation_size_estimate)
+                            <= self.max_asked_bytes_per_peer
+                })
+                // `min_by_key` keeps the first of the least loaded candidates
+                .min_by_key(|(_, asked)| *asked)
+                .map(|(peer_id, _)| *peer_id);
+            match chosen {
+                Some(peer_id) => {
+                    let asked = self.asked_bytes.entry(peer_id).or_insert(0);
+                    *asked = asked.saturating_add(self.operation_size_estimate);
+                    plan.asks
+                        .entry(peer_id)
+                        .or_insert_with(|| PreHashSet::with_capacity(1))
+                        .insert(op_id);
+                }
+                None => {
+                    plan.deferred.insert(op_id);
+                }
+            }
+        }
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+    use massa_models::operation::OperationId;
+    use massa_models::secure_share::Id;
+    use massa_signature::KeyPair;
+
+    fn op_prefix(i: u8) -> OperationPrefixId {
+        OperationId::new(Hash::compute_from(&[i])).prefix()
+    }
+
+    #[test]
+    fn test_ask_planner_spreads_and_defers() {
+        let peer_a = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+        let peer_b = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+        // room for two operations of the initial estimate per peer
+        let mut planner = AskPlanner::new(2 * INITIAL_OPERATION_SIZE_ESTIMATE);
+
+        let plan = planner.plan(
+            (0..5)
+                .map(|i| (op_prefix(i), vec![peer_a, peer_b]))
+                .collect(),
+        );
+        assert_eq!(plan.asks[&peer_a].len(), 2);
+        assert_eq!(plan.asks[&peer_b].len(), 2);
+        assert_eq!(plan.deferred.len(), 1);
+
+        // budgets are restored on the next period
+        planner.reset_period();
+        let plan = planner.plan(vec![(op_prefix(4), vec![peer_b])]);
+        assert!(plan.asks[&peer_b].contains(&op_prefix(4)));
+        assert!(plan.deferred.is_empty());
+
+        // a large operation is still asked to an idle peer
+        planner.reset_period();
+        for _ in 0..64 {
+            planner.record_received_operation_size(100_000);
+        }
+        let plan = planner.plan(vec![
+            (op_prefix(0), vec![peer_a, peer_b]),
+            (op_prefix(1), vec![peer_a, peer_b]),
+            (op_prefix(2), vec![peer_a, peer_b]),
+        ]);
+        assert_eq!(plan.asks[&peer_a].len(), 1);
+        assert_eq!(plan.asks[&peer_b].len(), 1);
+        assert_eq!(plan.deferred.len(), 1);
+    }
+}
@@ -1,10 +1,16 @@
 use std::{
     collections::{HashMap, HashSet},
+    ops::Bound::Included,
+    path::Path,
     sync::Arc,
 };
 
-use massa_models::operation::{OperationId, OperationPrefixId};
-use massa_protocol_exports::PeerId;
+use massa_models::operation::{OperationId, OperationPrefixId, OperationPrefixIdDeserializer};
+use massa_protocol_exports::{PeerId, ProtocolError};
+use massa_serialization::{
+    DeserializeError, Deserializer, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
+};
+use massa_time::MassaTime;
 use parking_lot::RwLock;
 use schnellru::{ByLength, LruMap};
 
@@ -12,8 +18,8 @@ use schnellru::{ByLength, LruMap};
 pub struct OperationCache {
     /// List of operations we checked recently
     pub checked_operations: LruMap<OperationId, ()>,
-    /// List of operation ID prefixes we checked recently
-    pub checked_operations_prefix: LruMap<OperationPrefixId, ()>,
+    /// List of operation ID prefixes we checked recently, with the time of the check
+    pub checked_operations_prefix: LruMap<OperationPrefixId, MassaTime>,
     /// List of operations known by peers
     pub ops_known_by_peer: HashMap<PeerId, LruMap<OperationPrefixId, ()>>,
     /// Maximum number of operations known by a peer
@@ -46,7 +52,75 @@ impl OperationCache {
     pub fn insert_checked_operation(&mut self, operation_id: OperationId) {
         self.checked_operations.insert(operation_id, ());
         self.checked_operations_prefix
-            .insert(operation_id.prefix(), ());
+            .insert(operation_id.prefix(), MassaTime::now());
+    }
+
+    /// Saves the operation ID prefixes checked less than `max_age` ago to `path`,
+    /// so that they are not asked again to peers after a restart.
+    ///
+    /// The file is a sequence of (check time in milliseconds as a varint, prefix) pairs, oldest first.
+    /// Returns the number of saved prefixes.
+    pub fn save_checked_prefixes(
+        &self,
+        path: &Path,
+        max_age: MassaTime,
+    ) -> Result<usize, ProtocolError> {
+        let min_time = MassaTime::now().saturating_sub(max_age);
+        let u64_serializer = U64VarIntSerializer::new();
+        let mut buffer = Vec::new();
+        let mut count = 0;
+        // the LRU iterates from the most recent entry: reverse it to keep the order on reload
+        let prefixes: Vec<_> = self
+            .checked_operations_prefix
+            .iter()
+            .take_while(|(_, checked_at)| **checked_at >= min_time)
+            .collect();
+        for (prefix, checked_at) in prefixes.into_iter().rev() {
+            u64_serializer
+                .serialize(&checked_at.as_millis(), &mut buffer)
+                .map_err(|err| ProtocolError::GeneralProtocolError(err.to_string()))?;
+            buffer.extend(Vec::<u8>::from(prefix));
+            count += 1;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // write to a temporary file first so that a crash never leaves a truncated file
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, buffer)?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(count)
+    }
+
+    /// Loads the operation ID prefixes saved by `save_checked_prefixes`,
+    /// skipping those checked more than `max_age` ago.
+    /// Returns the number of loaded prefixes.
+    pub fn load_checked_prefixes(
+        &mut self,
+        path: &Path,
+        max_age: MassaTime,
+    ) -> Result<usize, ProtocolError> {
+        let content = std::fs::read(path)?;
+        let min_time = MassaTime::now().saturating_sub(max_age);
+        let u64_deserializer = U64VarIntDeserializer::new(Included(0), Included(u64::MAX));
+        let prefix_deserializer = OperationPrefixIdDeserializer::new();
+        let mut count = 0;
+        let mut rest = content.as_slice();
+        while !rest.is_empty() {
+            let (new_rest, checked_at) = u64_deserializer
+                .deserialize::<DeserializeError>(rest)
+                .map_err(|err| ProtocolError::GeneralProtocolError(err.to_string()))?;
+            let (new_rest, prefix) = prefix_deserializer
+                .deserialize::<DeserializeError>(new_rest)
+                .map_err(|err| ProtocolError::GeneralProtocolError(err.to_string()))?;
+            rest = new_rest;
+            let checked_at = MassaTime::from_millis(checked_at);
+            if checked_at >= min_time {
+                self.checked_operations_prefix.insert(prefix, checked_at);
+                count += 1;
+            }
+        }
+        Ok(count)
     }
 
     /// Update caches to remove all data from disconnected peers
@@ -68,3 +142,57 @@ impl OperationCache {
 }
 
 pub type SharedOperationCache = Arc<RwLock<OperationCache>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+
+    #[test]
+    fn test_checked_prefixes_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("seen_operations.bin");
+        let max_age = MassaTime::from_millis(60000);
+
+        let mut cache = OperationCache::new(10, 10);
+        let old_prefix = OperationId::new(Hash::compute_from(b"old")).prefix();
+        cache
+            .checked_operations_prefix
+            .insert(old_prefix, MassaTime::from_millis(0));
+        let op_ids: Vec<OperationId> = (0u8..3)
+            .map(|i| OperationId::new(Hash::compute_from(&[i])))
+            .collect();
+        for op_id in op_ids.iter() {
+            cache.insert_checked_operation(*op_id);
+        }
+        assert_eq!(cache.save_checked_prefixes(&path, max_age).unwrap(), 3);
+
+        let mut restarted_cache = OperationCache::new(10, 10);
+        assert_eq!(
+            restarted_cache
+                .load_checked_prefixes(&path, max_age)
+                .unwrap(),
+            3
+        );
+        for op_id in op_ids.iter() {
+            assert!(restarted_cache
+                .checked_operations_prefix
+                .peek(&op_id.prefix())
+                .is_some());
+        }
+        assert!(restarted_cache
+            .checked_operations_prefix
+            .peek(&old_prefix)
+            .is_none());
+        // the most recently checked operation stays the most recent one
+        assert_eq!(
+            restarted_cache
+                .checked_operations_prefix
+                .iter()
+                .next()
+                .unwrap()
+                .0,
+            &op_ids[2].prefix()
+        );
+    }
+}
@@ -1,8 +1,13 @@
+use massa_protocol_exports::PeerId;
 use massa_storage::Storage;
 
+use super::inventory_digest::OperationInventoryDigest;
+
 #[derive(Clone)]
 pub enum OperationHandlerPropagationCommand {
     Stop,
     /// operations ids
     PropagateOperations(Storage),
+    /// digest of the operations recently received by a peer
+    PeerInventoryDigest(PeerId, OperationInventoryDigest),
 }
@@ -0,0 +1,215 @@
+//! Compact digest of the operations recently received by a node.
+//!
+//! The digest is a bloom filter over the operation ID prefixes. A node periodically sends
+//! the digest of its recent operations to its peers, and the peers stop announcing to it the
+//! operations that the digest contains. This avoids announcing again and again the same
+//! operations between peers that are already in sync.
+//!
+//! A false positive only means that an operation is not announced to a peer by this node:
+//! the peer still gets it from its other peers, or in the blocks.
+
+use std::ops::Bound::Included;
+
+use massa_models::operation::OperationPrefixId;
+use massa_serialization::{
+    Deserializer, SerializeError, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
+};
+use nom::{
+    bytes::complete::take,
+    error::{context, ContextError, ParseError},
+    IResult, Parser,
+};
+
+/// Number of bits of the digest per operation, giving a false positive rate of about 1%
+pub(crate) const INVENTORY_DIGEST_BITS_PER_OPERATION: usize = 10;
+
+/// Number of bits set in the digest for each operation
+const INVENTORY_DIGEST_HASH_COUNT: u64 = 7;
+
+/// Maximum number of bits set in the digest for each operation accepted from a peer
+const MAX_INVENTORY_DIGEST_HASH_COUNT: u64 = 32;
+
+/// Bloom filter over the prefixes of the operations recently received by a node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationInventoryDigest {
+    /// seed mixed in the positions of the bits, chosen by the sender
+    seed: u64,
+    /// number of bits set for each operation
+    hash_count: u64,
+    /// bits of the filter
+    bits: Vec<u8>,
+}
+
+impl OperationInventoryDigest {
+    /// Build the digest of a list of operation ID prefixes
+    pub fn new<'a>(
+        seed: u64,
+        prefixes: impl ExactSizeIterator<Item = &'a OperationPrefixId>,
+    ) -> Self {
+        let byte_count = prefixes
+            .len()
+            .saturating_mul(INVENTORY_DIGEST_BITS_PER_OPERATION)
+            .div_ceil(8)
+            .max(1);
+        let mut digest = OperationInventoryDigest {
+            seed,
+            hash_count: INVENTORY_DIGEST_HASH_COUNT,
+            bits: vec![0; byte_count],
+        };
+        for prefix in prefixes {
+            for position in digest.bit_positions(prefix) {
+                digest.bits[position / 8] |= 1 << (position % 8);
+            }
+        }
+        digest
+    }
+
+    /// Whether the operation may be in the digest. False positives are possible, false negatives are not.
+    pub fn contains(&self, prefix: &OperationPrefixId) -> bool {
+        self.bit_positions(prefix)
+            .all(|position| self.bits[position / 8] & (1 << (position % 8)) != 0)
+    }
+
+    /// Positions of the bits of an operation, by double hashing.
+    /// The prefixes are already uniformly distributed hashes, so their bytes are used directly.
+    fn bit_positions(&self, prefix: &OperationPrefixId) -> impl Iterator<Item = usize> {
+        let bytes: Vec<u8> = prefix.into();
+        let mut first = [0u8; 8];
+        let mut second = [0u8; 8];
+        first.copy_from_slice(&bytes[..8]);
+        second.copy_from_slice(&bytes[8..16]);
+        let h1 = u64::from_le_bytes(first) ^ self.seed;
+        let h2 = (u64::from_le_bytes(second) ^ self.seed.rotate_left(32)) | 1;
+        let bit_count = (self.bits.len() as u64) * 8;
+        (0..self.hash_count)
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bit_count) as usize)
+    }
+}
+
+/// Serializer for `OperationInventoryDigest`
+#[derive(Default, Clone)]
+pub struct OperationInventoryDigestSerializer {
+    u64_serializer: U64VarIntSerializer,
+}
+
+impl OperationInventoryDigestSerializer {
+    pub fn new() -> Self {
+        Self {
+            u64_serializer: U64VarIntSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<OperationInventoryDigest> for OperationInventoryDigestSerializer {
+    fn serialize(
+        &self,
+        value: &OperationInventoryDigest,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.u64_serializer.serialize(&value.seed, buffer)?;
+        self.u64_serializer.serialize(&value.hash_count, buffer)?;
+        self.u64_serializer
+            .serialize(&(value.bits.len() as u64), buffer)?;
+        buffer.extend(&value.bits);
+        Ok(())
+    }
+}
+
+/// Deserializer for `OperationInventoryDigest`
+pub struct OperationInventoryDigestDeserializer {
+    seed_deserializer: U64VarIntDeserializer,
+    hash_count_deserializer: U64VarIntDeserializer,
+    length_deserializer: U64VarIntDeserializer,
+}
+
+impl OperationInventoryDigestDeserializer {
+    /// Creates a deserializer accepting the digests of up to `max_operations` operations
+    pub fn new(max_operations: u64) -> Self {
+        let max_length = max_operations
+            .saturating_mul(INVENTORY_DIGEST_BITS_PER_OPERATION as u64)
+            .div_ceil(8)
+            .max(1);
+        Self {
+            seed_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+            hash_count_deserializer: U64VarIntDeserializer::new(
+                Included(1),
+                Included(MAX_INVENTORY_DIGEST_HASH_COUNT),
+            ),
+            length_deserializer: U64VarIntDeserializer::new(Included(1), Included(max_length)),
+        }
+    }
+}
+
+impl Deserializer<OperationInventoryDigest> for OperationInventoryDigestDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], OperationInventoryDigest, E> {
+        context("Failed OperationInventoryDigest deserialization", |input| {
+            let (input, seed) = context("Failed seed deserialization", |input| {
+                self.seed_deserializer.deserialize(input)
+            })
+            .parse(input)?;
+            let (input, hash_count) = context("Failed hash_count deserialization", |input| {
+                self.hash_count_deserializer.deserialize(input)
+            })
+            .parse(input)?;
+            let (input, length) = context("Failed bits length deserialization", |input| {
+                self.length_deserializer.deserialize(input)
+            })
+            .parse(input)?;
+            let (input, bits) =
+                context("Failed bits deserialization", take(length as usize)).parse(input)?;
+            Ok((
+                input,
+                OperationInventoryDigest {
+                    seed,
+                    hash_count,
+                    bits: bits.to_vec(),
+                },
+            ))
+        })
+        .parse(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+    use massa_models::{operation::OperationId, secure_share::Id};
+    use massa_serialization::DeserializeError;
+
+    fn prefixes(range: std::ops::Range<u64>) -> Vec<OperationPrefixId> {
+        range
+            .map(|i| OperationId::new(Hash::compute_from(&i.to_le_bytes())).prefix())
+            .collect()
+    }
+
+    #[test]
+    fn test_inventory_digest_membership_and_serialization() {
+        let inserted = prefixes(0..1000);
+        let digest = OperationInventoryDigest::new(42, inserted.iter());
+        assert!(inserted.iter().all(|prefix| digest.contains(prefix)));
+        let false_positives = prefixes(1000..11000)
+            .iter()
+            .filter(|prefix| digest.contains(prefix))
+            .count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+
+        let mut buffer = Vec::new();
+        OperationInventoryDigestSerializer::new()
+            .serialize(&digest, &mut buffer)
+            .unwrap();
+        let (rest, deserialized) = OperationInventoryDigestDeserializer::new(1000)
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(deserialized, digest);
+
+        // digests larger than the limit are rejected
+        assert!(OperationInventoryDigestDeserializer::new(500)
+            .deserialize::<DeserializeError>(&buffer)
+            .is_err());
+    }
+}
@@ -12,6 +12,11 @@ use nom::{
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use std::ops::Bound::Included;
 
+use super::inventory_digest::{
+    OperationInventoryDigest, OperationInventoryDigestDeserializer,
+    OperationInventoryDigestSerializer,
+};
+
 #[derive(Debug)]
 pub enum OperationMessage {
     /// Batch of operation ids
@@ -20,6 +25,9 @@ pub enum OperationMessage {
     AskForOperations(OperationPrefixIds),
     /// A list of operations
     Operations(Vec<SecureShareOperation>),
+    /// Digest of the operations recently received by the sender,
+    /// that do not need to be announced to it anymore
+    InventoryDigest(OperationInventoryDigest),
 }
 
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
@@ -28,6 +36,7 @@ pub enum MessageTypeId {
     OperationsAnnouncement = 0,
     AskForOperations = 1,
     Operations = 2,
+    InventoryDigest = 3,
 }
 
 impl From<&OperationMessage> for MessageTypeId {
@@ -36,6 +45,7 @@ impl From<&OperationMessage> for MessageTypeId {
             OperationMessage::OperationsAnnouncement(_) => MessageTypeId::OperationsAnnouncement,
             OperationMessage::AskForOperations(_) => MessageTypeId::AskForOperations,
             OperationMessage::Operations(_) => MessageTypeId::Operations,
+            OperationMessage::InventoryDigest(_) => MessageTypeId::InventoryDigest,
         }
     }
 }
@@ -45,6 +55,7 @@ pub struct OperationMessageSerializer {
     id_serializer: U64VarIntSerializer,
     operation_prefix_ids_serializer: OperationPrefixIdsSerializer,
     operations_serializer: OperationsSerializer,
+    inventory_digest_serializer: OperationInventoryDigestSerializer,
 }
 
 impl OperationMessageSerializer {
@@ -53,6 +64,7 @@ impl OperationMessageSerializer {
             id_serializer: U64VarIntSerializer::new(),
             operation_prefix_ids_serializer: OperationPrefixIdsSerializer::new(),
             operations_serializer: OperationsSerializer::new(),
+            inventory_digest_serializer: OperationInventoryDigestSerializer::new(),
         }
     }
 }
@@ -81,6 +93,9 @@ impl Serializer<OperationMessage> for OperationMessageSerializer {
             OperationMessage::Operations(operations) => {
                 self.operations_serializer.serialize(operations, buffer)?;
             }
+            OperationMessage::InventoryDigest(digest) => {
+                self.inventory_digest_serializer.serialize(digest, buffer)?;
+            }
         }
         Ok(())
     }
@@ -90,6 +105,7 @@ pub struct OperationMessageDeserializer {
     id_deserializer: U64VarIntDeserializer,
     operation_prefix_ids_deserializer: OperationPrefixIdsDeserializer,
     operations_deserializer: OperationsDeserializer,
+    inventory_digest_deserializer: OperationInventoryDigestDeserializer,
 }
 
 /// Limits used in the deserialization of `OperationMessage`
@@ -111,6 +127,8 @@ pub struct OperationMessageDeserializerArgs {
     pub max_op_datastore_key_length: u8,
     /// Maximum size of a op datastore value
     pub max_op_datastore_value_length: u64,
+    /// Maximum number of operations in an inventory digest
+    pub max_operations_per_inventory_digest: u64,
 }
 
 impl OperationMessageDeserializer {
@@ -129,6 +147,9 @@ impl OperationMessageDeserializer {
                 args.max_op_datastore_key_length,
                 args.max_op_datastore_value_length,
             ),
+            inventory_digest_deserializer: OperationInventoryDigestDeserializer::new(
+                args.max_operations_per_inventory_digest,
+            ),
         }
     }
 }
@@ -168,6 +189,13 @@ impl Deserializer<OperationMessage> for OperationMessageDeserializer {
                     .map(OperationMessage::Operations)
                     .parse(buffer)
                 }
+                MessageTypeId::InventoryDigest => {
+                    context("Failed InventoryDigest deserialization", |input| {
+                        self.inventory_digest_deserializer.deserialize(input)
+                    })
+                    .map(OperationMessage::InventoryDigest)
+                    .parse(buffer)
+                }
             }
         })
         .parse(buffer)
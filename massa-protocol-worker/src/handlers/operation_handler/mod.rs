@@ -14,15 +14,23 @@ use self::{
     retrieval::start_retrieval_thread,
 };
 
+mod ask_planner;
 pub mod cache;
 pub mod commands_propagation;
 pub mod commands_retrieval;
+mod inventory_digest;
 mod messages;
 mod propagation;
 mod retrieval;
+mod sig_verification;
 
-pub(crate) use messages::{OperationMessage, OperationMessageSerializer};
+pub(crate) use messages::{
+    MessageTypeId as OperationMessageTypeId, OperationMessage, OperationMessageSerializer,
+};
+#[cfg(test)]
+pub(crate) use messages::{OperationMessageDeserializer, OperationMessageDeserializerArgs};
 pub(crate) use retrieval::note_operations_from_peer;
+pub(crate) use sig_verification::{OperationSigVerifier, SharedOperationSigVerifier};
 
 use super::peer_handler::models::{PeerManagementCmd, PeerMessageTuple};
 
@@ -51,6 +59,7 @@ impl OperationHandler {
         local_sender: MassaSender<OperationHandlerPropagationCommand>,
         local_receiver: MassaReceiver<OperationHandlerPropagationCommand>,
         peer_cmd_sender: MassaSender<PeerManagementCmd>,
+        sig_verifier: SharedOperationSigVerifier,
         massa_metrics: MassaMetrics,
     ) -> Self {
         let operation_retrieval_thread = start_retrieval_thread(
@@ -63,6 +72,7 @@ impl OperationHandler {
             receiver_retrieval_ext,
             local_sender.clone(),
             peer_cmd_sender,
+            sig_verifier,
             massa_metrics.clone(),
         );
 
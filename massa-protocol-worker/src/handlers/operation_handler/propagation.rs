@@ -1,13 +1,13 @@
-use std::collections::VecDeque;
+use std::collections::{hash_map, BTreeSet, VecDeque};
 use std::{mem, thread::JoinHandle};
 
 use crossbeam::channel::RecvTimeoutError;
 use massa_channel::receiver::MassaReceiver;
 use massa_logging::massa_trace;
 use massa_metrics::MassaMetrics;
-use massa_models::operation::OperationId;
+use massa_models::operation::{OperationId, OperationPrefixId, SecureShareOperation};
 use massa_models::prehash::CapacityAllocator;
-use massa_models::prehash::PreHashSet;
+use massa_models::prehash::{PreHashMap, PreHashSet};
 use massa_protocol_exports::PeerId;
 use massa_protocol_exports::ProtocolConfig;
 use massa_protocol_exports::ProtocolError;
@@ -15,22 +15,34 @@ use massa_storage::Storage;
 use tracing::{debug, info, log::warn};
 
 use crate::{
-    handlers::operation_handler::OperationMessage, messages::MessagesSerializer,
-    wrap_network::ActiveConnectionsTrait,
+    capabilities::Capabilities, handlers::operation_handler::OperationMessage,
+    messages::MessagesSerializer, wrap_network::ActiveConnectionsTrait,
 };
 
 use super::{
     cache::SharedOperationCache, commands_propagation::OperationHandlerPropagationCommand,
-    OperationMessageSerializer,
+    inventory_digest::OperationInventoryDigest, OperationMessageSerializer,
 };
 
+/// Fee density of an operation: fee (in raw units) per byte of the serialized operation.
+/// The operations with the highest fee density are announced first and dropped last.
+fn fee_density(operation: &SecureShareOperation) -> u64 {
+    operation.content.fee.to_raw() / (operation.serialized_size() as u64).max(1)
+}
+
 struct PropagationThread {
     internal_receiver: MassaReceiver<OperationHandlerPropagationCommand>,
     active_connections: Box<dyn ActiveConnectionsTrait>,
     // times at which previous ops were announced
+    // (the ids of the ops dropped since are kept until the batch expires, and ignored then)
     stored_for_propagation: VecDeque<(std::time::Instant, PreHashSet<OperationId>)>,
+    // fee density and latest storage time of the ops kept for propagation
+    kept_ops: PreHashMap<OperationId, (u64, std::time::Instant)>,
+    // ops kept for propagation, by increasing fee density
+    kept_by_fee_density: BTreeSet<(u64, OperationId)>,
     op_storage: Storage,
-    next_batch: PreHashSet<OperationId>,
+    // ops to announce, by increasing fee density
+    next_batch: BTreeSet<(u64, OperationId)>,
     config: ProtocolConfig,
     cache: SharedOperationCache,
     operation_message_serializer: MessagesSerializer,
@@ -42,8 +54,18 @@ impl PropagationThread {
         let mut batch_deadline = std::time::Instant::now()
             .checked_add(self.config.operation_announcement_interval.to_duration())
             .expect("Can't init interval op propagation");
+        let mut digest_deadline = std::time::Instant::now()
+            .checked_add(
+                self.config
+                    .operation_inventory_digest_interval
+                    .to_duration(),
+            )
+            .expect("Can't init interval op inventory digest");
         loop {
-            match self.internal_receiver.recv_deadline(batch_deadline) {
+            match self
+                .internal_receiver
+                .recv_deadline(batch_deadline.min(digest_deadline))
+            {
                 Ok(internal_message) => {
                     match internal_message {
                         OperationHandlerPropagationCommand::PropagateOperations(operations) => {
@@ -56,14 +78,38 @@ impl PropagationThread {
                             }
 
                             // add to propagation storage
-                            let new_ops = operations.get_op_refs().clone();
-                            self.stored_for_propagation
-                                .push_back((std::time::Instant::now(), new_ops.clone()));
+                            let now = std::time::Instant::now();
+                            let new_ops: Vec<(u64, OperationId)> = {
+                                let ops = operations.read_operations();
+                                operations
+                                    .get_op_refs()
+                                    .iter()
+                                    .filter_map(|op_id| {
+                                        ops.get(op_id).map(|op| (fee_density(op), *op_id))
+                                    })
+                                    .collect()
+                            };
+                            self.stored_for_propagation.push_back((
+                                now,
+                                new_ops.iter().map(|(_, op_id)| *op_id).collect(),
+                            ));
+                            for (density, op_id) in new_ops.iter().copied() {
+                                if let Some((previous_density, _)) =
+                                    self.kept_ops.insert(op_id, (density, now))
+                                {
+                                    self.kept_by_fee_density.remove(&(previous_density, op_id));
+                                }
+                                self.kept_by_fee_density.insert((density, op_id));
+                            }
                             self.op_storage.extend(operations);
                             self.prune_propagation_storage();
 
-                            for op_id in new_ops {
-                                self.next_batch.insert(op_id);
+                            for (density, op_id) in new_ops {
+                                // skip the ops dropped right away because of their low fee density
+                                if !self.kept_ops.contains_key(&op_id) {
+                                    continue;
+                                }
+                                self.next_batch.insert((density, op_id));
                                 if self.next_batch.len()
                                     >= self.config.operation_announcement_buffer_capacity
                                 {
@@ -78,6 +124,12 @@ impl PropagationThread {
                                 }
                             }
                         }
+                        OperationHandlerPropagationCommand::PeerInventoryDigest(
+                            peer_id,
+                            digest,
+                        ) => {
+                            self.note_peer_inventory_digest(&peer_id, &digest);
+                        }
                         OperationHandlerPropagationCommand::Stop => {
                             info!("Stop operation propagation thread");
                             return;
@@ -85,10 +137,23 @@ impl PropagationThread {
                     }
                 }
                 Err(RecvTimeoutError::Timeout) => {
-                    self.announce_ops();
-                    batch_deadline = std::time::Instant::now()
-                        .checked_add(self.config.operation_announcement_interval.to_duration())
-                        .expect("Can't init interval op propagation");
+                    let now = std::time::Instant::now();
+                    if now >= batch_deadline {
+                        self.announce_ops();
+                        batch_deadline = std::time::Instant::now()
+                            .checked_add(self.config.operation_announcement_interval.to_duration())
+                            .expect("Can't init interval op propagation");
+                    }
+                    if now >= digest_deadline {
+                        self.send_inventory_digest();
+                        digest_deadline = std::time::Instant::now()
+                            .checked_add(
+                                self.config
+                                    .operation_inventory_digest_interval
+                                    .to_duration(),
+                            )
+                            .expect("Can't init interval op inventory digest");
+                    }
                 }
                 Err(RecvTimeoutError::Disconnected) => {
                     return;
@@ -105,44 +170,117 @@ impl PropagationThread {
         let max_op_prop_time = self.config.max_operations_propagation_time.to_duration();
         while let Some((t, _)) = self.stored_for_propagation.front() {
             if t.elapsed() > max_op_prop_time {
-                let (_, op_ids) = self
+                let (t, op_ids) = self
                     .stored_for_propagation
                     .pop_front()
                     .expect("there should be at least one element, checked above");
-                removed.extend(op_ids);
+                for op_id in op_ids {
+                    // ignore the ops already dropped, or stored again since
+                    if let hash_map::Entry::Occupied(occ) = self.kept_ops.entry(op_id) {
+                        if occ.get().1 == t {
+                            let (density, _) = occ.remove();
+                            self.kept_by_fee_density.remove(&(density, op_id));
+                            self.next_batch.remove(&(density, op_id));
+                            removed.insert(op_id);
+                        }
+                    }
+                }
             } else {
                 break;
             }
         }
 
-        // Cap cache size
-        // Note that we directly remove batches of operations, not individual operations
-        // to favor simplicity and performance over precision.
-        let mut excess_count = self
-            .stored_for_propagation
-            .iter()
-            .map(|(_, ops)| ops.len())
-            .sum::<usize>()
-            .saturating_sub(self.config.max_ops_kept_for_propagation);
-        while excess_count > 0 {
-            if let Some((_t, op_ids)) = self.stored_for_propagation.pop_front() {
-                excess_count = excess_count.saturating_sub(op_ids.len());
-                removed.extend(op_ids);
-            } else {
+        // Cap cache size, dropping the ops with the lowest fee density first
+        while self.kept_ops.len() > self.config.max_ops_kept_for_propagation {
+            let Some((density, op_id)) = self.kept_by_fee_density.pop_first() else {
                 break;
-            }
+            };
+            self.kept_ops.remove(&op_id);
+            self.next_batch.remove(&(density, op_id));
+            removed.insert(op_id);
         }
 
         // remove from storage
         self.op_storage.drop_operation_refs(&removed);
     }
 
+    /// Send the digest of the most recently received operations to the connected peers that understand it,
+    /// so that they stop announcing these operations to us
+    fn send_inventory_digest(&mut self) {
+        if self.config.max_operations_per_inventory_digest == 0 {
+            return;
+        }
+        let mut prefixes: Vec<OperationPrefixId> =
+            Vec::with_capacity(self.config.max_operations_per_inventory_digest);
+        'batches: for (t, op_ids) in self.stored_for_propagation.iter().rev() {
+            for op_id in op_ids {
+                if prefixes.len() >= self.config.max_operations_per_inventory_digest {
+                    break 'batches;
+                }
+                // ignore the ops dropped since, and the older entries of the ops stored again
+                if self.kept_ops.get(op_id).map(|(_, kept_at)| kept_at) == Some(t) {
+                    prefixes.push(op_id.prefix());
+                }
+            }
+        }
+        if prefixes.is_empty() {
+            return;
+        }
+        let digest = OperationInventoryDigest::new(rand::random(), prefixes.iter());
+        for peer_id in self.active_connections.get_peer_ids_connected() {
+            let supported = self
+                .active_connections
+                .get_peer_capabilities(&peer_id)
+                .map_or(false, |negotiated| {
+                    negotiated.supports(Capabilities::OPERATION_INVENTORY_DIGEST)
+                });
+            if !supported {
+                continue;
+            }
+            if let Err(err) = self.active_connections.send_to_peer(
+                &peer_id,
+                &self.operation_message_serializer,
+                OperationMessage::InventoryDigest(digest.clone()).into(),
+                false,
+            ) {
+                warn!("Failed to send InventoryDigest message to peer: {}", err);
+            }
+        }
+    }
+
+    /// Mark the operations waiting to be announced that are in the digest of a peer as known by the peer,
+    /// so that they are not announced to it
+    fn note_peer_inventory_digest(&mut self, peer_id: &PeerId, digest: &OperationInventoryDigest) {
+        let known_ops: Vec<OperationPrefixId> = self
+            .next_batch
+            .iter()
+            .map(|(_, op_id)| op_id.prefix())
+            .filter(|prefix| digest.contains(prefix))
+            .collect();
+        if known_ops.is_empty() {
+            return;
+        }
+        debug!(
+            "Skip the announcement of {} operations already received by {}",
+            known_ops.len(),
+            peer_id
+        );
+        self.cache
+            .write()
+            .insert_peer_known_ops(peer_id, &known_ops);
+    }
+
     fn announce_ops(&mut self) {
         // Quit if empty  to avoid iterating on nodes
         if self.next_batch.is_empty() {
             return;
         }
-        let operation_ids = mem::take(&mut self.next_batch);
+        // announce the ops with the highest fee density first
+        let operation_ids: Vec<OperationId> = mem::take(&mut self.next_batch)
+            .into_iter()
+            .rev()
+            .map(|(_, op_id)| op_id)
+            .collect();
         massa_trace!("protocol.protocol_worker.announce_ops.begin", {
             "operation_ids": operation_ids
         });
@@ -214,12 +352,12 @@ pub fn start_propagation_thread(
                 stored_for_propagation: VecDeque::with_capacity(
                     config.max_ops_kept_for_propagation,
                 ),
-                op_storage,
-                next_batch: PreHashSet::with_capacity(
-                    config
-                        .operation_announcement_buffer_capacity
-                        .saturating_add(1),
+                kept_ops: PreHashMap::with_capacity(
+                    config.max_ops_kept_for_propagation.saturating_add(1),
                 ),
+                kept_by_fee_density: BTreeSet::new(),
+                op_storage,
+                next_batch: BTreeSet::new(),
                 config,
                 cache,
                 _massa_metrics: massa_metrics,
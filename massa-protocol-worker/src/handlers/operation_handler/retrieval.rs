@@ -7,7 +7,6 @@ use massa_metrics::MassaMetrics;
 use massa_models::{
     operation::{OperationPrefixId, OperationPrefixIds, SecureShareOperation},
     prehash::{CapacityAllocator, PreHashMap, PreHashSet},
-    secure_share::Id,
     slot::Slot,
     timeslots::get_block_slot_timestamp,
 };
@@ -20,18 +19,22 @@ use massa_time::{MassaTime, TimeError};
 use schnellru::{ByLength, LruMap};
 
 use crate::{
-    handlers::peer_handler::models::{PeerManagementCmd, PeerMessageTuple},
-    messages::MessagesSerializer,
-    sig_verifier::verify_sigs_batch,
+    handlers::peer_handler::{
+        models::{PeerManagementCmd, PeerMessageTuple},
+        reputation::{report_misbehavior, Misbehavior},
+    },
+    messages::{DeserializationFailure, MessageHandlingTimer, MessageTypeId, MessagesSerializer},
     wrap_network::ActiveConnectionsTrait,
 };
 use tracing::{debug, info, warn};
 
 use super::{
+    ask_planner::AskPlanner,
     cache::SharedOperationCache,
     commands_propagation::OperationHandlerPropagationCommand,
     commands_retrieval::OperationHandlerRetrievalCommand,
     messages::{OperationMessage, OperationMessageDeserializer, OperationMessageDeserializerArgs},
+    sig_verification::{OperationSigVerifier, SharedOperationSigVerifier},
     OperationMessageSerializer,
 };
 
@@ -52,6 +55,7 @@ pub struct RetrievalThread {
     pool_controller: Box<dyn PoolController>,
     cache: SharedOperationCache,
     asked_operations: LruMap<OperationPrefixId, (Instant, Vec<PeerId>)>,
+    ask_planner: AskPlanner,
     active_connections: Box<dyn ActiveConnectionsTrait>,
     op_batch_buffer: VecDeque<OperationBatchItem>,
     storage: Storage,
@@ -60,6 +64,7 @@ pub struct RetrievalThread {
     receiver_ext: MassaReceiver<OperationHandlerRetrievalCommand>,
     operation_message_serializer: MessagesSerializer,
     peer_cmd_sender: MassaSender<PeerManagementCmd>,
+    sig_verifier: SharedOperationSigVerifier,
     _massa_metrics: MassaMetrics,
 }
 
@@ -75,6 +80,8 @@ impl RetrievalThread {
                 max_op_datastore_entry_count: self.config.max_op_datastore_entry_count,
                 max_op_datastore_key_length: self.config.max_op_datastore_key_length,
                 max_op_datastore_value_length: self.config.max_op_datastore_value_length,
+                max_operations_per_inventory_digest: self.config.max_operations_per_inventory_digest
+                    as u64,
             });
         let tick_ask_operations = tick(self.config.operation_batch_proc_period.to_duration());
 
@@ -83,22 +90,33 @@ impl RetrievalThread {
                 recv(self.receiver) -> msg => {
                     self.receiver.update_metrics();
                     match msg {
-                        Ok((peer_id, message)) => {
+                        Ok((peer_id, message, queued_at)) => {
+                            let _handling_timer = MessageHandlingTimer::start(MessageTypeId::Operation.family(), queued_at);
                             let (rest, message) = match operation_message_deserializer
                                 .deserialize::<DeserializeError>(&message) {
                                     Ok((rest, message)) => (rest, message),
                                     Err(err) => {
+                                        DeserializationFailure::Malformed.record(MessageTypeId::Operation.family(), &peer_id);
+                                        report_misbehavior(&self.peer_cmd_sender, &peer_id, MessageTypeId::Operation.family(), Misbehavior::InvalidMessage);
                                         warn!("Error when deserializing message from peer {}: Err = {}", peer_id, err);
                                         continue;
                                     }
                                 };
                             if !rest.is_empty() {
+                                DeserializationFailure::TrailingBytes.record(MessageTypeId::Operation.family(), &peer_id);
+                                report_misbehavior(&self.peer_cmd_sender, &peer_id, MessageTypeId::Operation.family(), Misbehavior::InvalidMessage);
                                 println!("Error: message not fully consumed");
                                 return;
                             }
                             match message {
                                 OperationMessage::Operations(ops) => {
                                     debug!("Received operation message: Operations from {}", peer_id);
+                                    for op in &ops {
+                                        self.ask_planner.record_received_operation_size(op.serialized_size());
+                                    }
+                                    if !self.were_operations_asked(&ops, &peer_id) {
+                                        report_misbehavior(&self.peer_cmd_sender, &peer_id, MessageTypeId::Operation.family(), Misbehavior::BandwidthAbuse);
+                                    }
                                     if let Err(err) = note_operations_from_peer(
                                         &self.storage,
                                         &mut self.cache,
@@ -106,7 +124,8 @@ impl RetrievalThread {
                                         ops,
                                         &peer_id,
                                         &mut self.internal_sender,
-                                        &mut self.pool_controller
+                                        &mut self.pool_controller,
+                                        &self.sig_verifier,
                                     ) {
                                         warn!("peer {} sent us critically incorrect operation, which may be an attack attempt by the remote peer or a loss of sync between us and the remote peer. Err = {}", peer_id, err);
 
@@ -129,6 +148,19 @@ impl RetrievalThread {
                                         warn!("error when processing asked operations received from peer {}: Err = {}", peer_id, err);
                                     }
                                 }
+                                OperationMessage::InventoryDigest(digest) => {
+                                    debug!("Received operation message: InventoryDigest from {}", peer_id);
+                                    // ignore digests from disconnected peers
+                                    if !self.active_connections.get_peer_ids_connected().contains(&peer_id) {
+                                        continue;
+                                    }
+                                    // the propagation thread knows the recent operations that the peer may have
+                                    if let Err(err) = self.internal_sender.try_send(
+                                        OperationHandlerPropagationCommand::PeerInventoryDigest(peer_id, digest),
+                                    ) {
+                                        warn!("Error sending inventory digest to propagation channel: {}", err);
+                                    }
+                                }
                             }
                         }
                         Err(_) => {
@@ -181,6 +213,9 @@ impl RetrievalThread {
     ///        op_batch_buf.push(now+op_batch_proc_period, peer_id, future_set)
     ///    ask ask_set to peer_id
     ///```
+    ///
+    /// The operations of `ask_set` are then spread by the `AskPlanner` among the connected peers known to have them,
+    /// starting with `peer_id`. Those that do not fit in the peer budgets of the current period join `future_set`.
     fn on_operations_announcements_received(
         &mut self,
         mut op_batch: OperationPrefixIds,
@@ -222,7 +257,7 @@ impl RetrievalThread {
                 }
                 None => None,
             };
-            if let Some((previous_ask_time, previous_ask_peers)) = opt_previous_ask {
+            if let Some((previous_ask_time, _)) = opt_previous_ask {
                 // Ask now if latest ask instant < now - operation_batch_proc_period
                 // otherwise add in future_set
                 if now
@@ -232,18 +267,57 @@ impl RetrievalThread {
                 {
                     count_reask += 1;
                     ask_set.insert(op_id);
-                    *previous_ask_time = now;
-                    previous_ask_peers.push(*peer_id);
                 } else {
                     future_set.insert(op_id);
                 }
             } else {
-                // the same peer announced this op for a second time, ask them immediately
                 ask_set.insert(op_id);
-                self.asked_operations.insert(op_id, (now, vec![*peer_id]));
             }
         } // EndOf for op_id in op_batch:
 
+        // spread the operations to ask among the peers known to have them, within their budgets
+        let plan = {
+            let connected_peers = self.active_connections.get_peer_ids_connected();
+            let cache_read = self.cache.read();
+            let operations = ask_set
+                .into_iter()
+                .map(|op_id| {
+                    let already_asked = self
+                        .asked_operations
+                        .peek(&op_id)
+                        .map(|(_, peers)| peers.clone())
+                        .unwrap_or_default();
+                    let mut candidates = vec![*peer_id];
+                    candidates.extend(cache_read.ops_known_by_peer.iter().filter_map(
+                        |(other_peer_id, known_ops)| {
+                            (other_peer_id != peer_id
+                                && connected_peers.contains(other_peer_id)
+                                && !already_asked.contains(other_peer_id)
+                                && known_ops.peek(&op_id).is_some())
+                            .then_some(*other_peer_id)
+                        },
+                    ));
+                    (op_id, candidates)
+                })
+                .collect();
+            self.ask_planner.plan(operations)
+        };
+        future_set.extend(plan.deferred);
+        for (asked_peer_id, op_ids) in &plan.asks {
+            for op_id in op_ids {
+                match self.asked_operations.get(op_id) {
+                    Some((previous_ask_time, previous_ask_peers)) => {
+                        *previous_ask_time = now;
+                        previous_ask_peers.push(*asked_peer_id);
+                    }
+                    None => {
+                        self.asked_operations
+                            .insert(*op_id, (now, vec![*asked_peer_id]));
+                    }
+                }
+            }
+        }
+
         if count_reask > 0 {
             massa_trace!("re-ask operations.", { "count": count_reask });
         }
@@ -258,19 +332,19 @@ impl RetrievalThread {
                 operations_prefix_ids: future_set,
             });
         }
-        if !ask_set.is_empty() {
+        for (asked_peer_id, op_ids) in plan.asks {
             debug!(
                 "Send ask operations of len {} to {}",
-                ask_set.len(),
-                peer_id
+                op_ids.len(),
+                asked_peer_id
             );
-            for sub_list in ask_set
+            for sub_list in op_ids
                 .into_iter()
                 .collect::<Vec<OperationPrefixId>>()
                 .chunks(self.config.max_operations_per_message as usize)
             {
                 if let Err(err) = self.active_connections.send_to_peer(
-                    peer_id,
+                    &asked_peer_id,
                     &self.operation_message_serializer,
                     OperationMessage::AskForOperations(
                         sub_list.iter().cloned().collect::<OperationPrefixIds>(),
@@ -289,6 +363,8 @@ impl RetrievalThread {
     }
 
     fn update_ask_operation(&mut self) -> Result<(), ProtocolError> {
+        // a new period starts: restore the ask budgets of all peers
+        self.ask_planner.reset_period();
         let now = Instant::now();
         while !self.op_batch_buffer.is_empty()
         // This unwrap is ok because we checked that it's not empty just before.
@@ -350,6 +426,15 @@ impl RetrievalThread {
         Ok(())
     }
 
+    /// Check that all the received operations were asked to the peer that sent them
+    fn were_operations_asked(&self, operations: &[SecureShareOperation], peer_id: &PeerId) -> bool {
+        operations.iter().all(|op| {
+            self.asked_operations
+                .peek(&op.id.prefix())
+                .map_or(false, |(_, peers)| peers.contains(peer_id))
+        })
+    }
+
     /// send a ban peer command to the peer handler
     fn ban_node(&mut self, peer_id: &PeerId) -> Result<(), ProtocolError> {
         massa_trace!("ban node from retrieval thread", { "peer_id": peer_id.to_string() });
@@ -359,6 +444,7 @@ impl RetrievalThread {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn note_operations_from_peer(
     base_storage: &Storage,
     operations_cache: &mut SharedOperationCache,
@@ -367,6 +453,7 @@ pub(crate) fn note_operations_from_peer(
     source_peer_id: &PeerId,
     ops_propagation_sender: &mut MassaSender<OperationHandlerPropagationCommand>,
     pool_controller: &mut Box<dyn PoolController>,
+    sig_verifier: &OperationSigVerifier,
 ) -> Result<(), ProtocolError> {
     massa_trace!("protocol.protocol_worker.note_operations_from_peer", { "peer": source_peer_id, "operations": operations });
     let now = MassaTime::now();
@@ -417,13 +504,8 @@ pub(crate) fn note_operations_from_peer(
         new_operations.retain(|op_id, _| cache_read.checked_operations.peek(op_id).is_none());
     }
 
-    // optimized signature verification
-    verify_sigs_batch(
-        &new_operations
-            .iter()
-            .map(|(op_id, op)| (*op_id.get_hash(), op.signature, op.content_creator_pub_key))
-            .collect::<Vec<_>>(),
-    )?;
+    // signature verification, in parallel batches on the dedicated pool
+    sig_verifier.verify(&new_operations.values().collect::<Vec<_>>())?;
 
     {
         // add to checked operations
@@ -474,6 +556,7 @@ pub fn start_retrieval_thread(
     receiver_ext: MassaReceiver<OperationHandlerRetrievalCommand>,
     internal_sender: MassaSender<OperationHandlerPropagationCommand>,
     peer_cmd_sender: MassaSender<PeerManagementCmd>,
+    sig_verifier: SharedOperationSigVerifier,
     massa_metrics: MassaMetrics,
 ) -> JoinHandle<()> {
     std::thread::Builder::new()
@@ -493,11 +576,13 @@ pub fn start_retrieval_thread(
                         .try_into()
                         .expect("asked_operations_buffer_capacity in config must be > 0"),
                 )),
+                ask_planner: AskPlanner::new(config.max_ask_operations_size_per_peer),
                 config,
                 operation_message_serializer: MessagesSerializer::new()
                     .with_operation_message_serializer(OperationMessageSerializer::new()),
                 op_batch_buffer: VecDeque::new(),
                 peer_cmd_sender,
+                sig_verifier,
                 _massa_metrics: massa_metrics,
             };
             retrieval_thread.run();
@@ -0,0 +1,151 @@
+//! Verification of the signatures of the received operations before they are stored.
+//!
+//! The operations received together are split into batches verified in parallel on a thread pool dedicated
+//! to them, so that a flood of operations does not compete with the other users of the global rayon pool.
+//! Once a batch holding an invalid signature is found, the batches not started yet are skipped:
+//! the whole set is rejected anyway.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use massa_models::operation::SecureShareOperation;
+use massa_models::secure_share::SecureShare;
+use massa_protocol_exports::{ProtocolConfig, ProtocolError};
+use rayon::{prelude::ParallelIterator, slice::ParallelSlice, ThreadPool, ThreadPoolBuilder};
+
+/// Verifier of the signatures of the received operations, shared by the handlers receiving operations
+pub(crate) type SharedOperationSigVerifier = Arc<OperationSigVerifier>;
+
+pub(crate) struct OperationSigVerifier {
+    pool: ThreadPool,
+    batch_size: usize,
+}
+
+impl OperationSigVerifier {
+    pub fn new(config: &ProtocolConfig) -> Result<Self, ProtocolError> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(config.operation_sig_verification_threads)
+            .thread_name(|index| format!("protocol-operation-sig-verifier-{}", index))
+            .build()
+            .map_err(|err| {
+                ProtocolError::GeneralProtocolError(format!(
+                    "could not start the operation signature verification threads: {}",
+                    err
+                ))
+            })?;
+        Ok(OperationSigVerifier {
+            pool,
+            batch_size: config.operation_sig_verification_batch_size.max(1),
+        })
+    }
+
+    /// Verifies the signatures of operations received together.
+    /// Returns an error if at least one of them fails to verify.
+    pub fn verify(&self, operations: &[&SecureShareOperation]) -> Result<(), ProtocolError> {
+        if operations.is_empty() {
+            return Ok(());
+        }
+        let start = Instant::now();
+        let batches = operations.len().div_ceil(self.batch_size) as u64;
+        let valid_batches = AtomicU64::new(0);
+        let invalid_batches = AtomicU64::new(0);
+        let verify_batch = |batch: &[&SecureShareOperation]| {
+            SecureShare::verify_batch(batch.iter().copied())
+                .map(|_| {
+                    valid_batches.fetch_add(1, Ordering::Relaxed);
+                })
+                .map_err(|_err| {
+                    invalid_batches.fetch_add(1, Ordering::Relaxed);
+                })
+        };
+        // a single batch is not worth a trip to the pool
+        let result = if batches == 1 {
+            verify_batch(operations)
+        } else {
+            self.pool.install(|| {
+                operations
+                    .par_chunks(self.batch_size)
+                    .try_for_each(verify_batch)
+            })
+        };
+
+        let valid_batches = valid_batches.into_inner();
+        let invalid_batches = invalid_batches.into_inner();
+        // the batches verified before the abort, valid or not, still count as verified signatures
+        let verified_signatures = ((valid_batches + invalid_batches) as usize)
+            .saturating_mul(self.batch_size)
+            .min(operations.len());
+        massa_metrics::observe_protocol_operation_signature_verification(
+            valid_batches,
+            invalid_batches,
+            batches.saturating_sub(valid_batches + invalid_batches),
+            verified_signatures,
+            start.elapsed(),
+        );
+        result.map_err(|_err| ProtocolError::WrongSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_models::{
+        address::Address,
+        amount::Amount,
+        operation::{Operation, OperationSerializer, OperationType, SecureShareOperation},
+        secure_share::SecureShareContent,
+    };
+    use massa_protocol_exports::{ProtocolConfig, ProtocolError};
+    use massa_signature::KeyPair;
+
+    use super::OperationSigVerifier;
+
+    fn operation(expire_period: u64) -> SecureShareOperation {
+        let keypair = KeyPair::generate(0).unwrap();
+        Operation::new_verifiable(
+            Operation {
+                fee: Amount::zero(),
+                op: OperationType::Transaction {
+                    recipient_address: Address::from_public_key(&keypair.get_public_key()),
+                    amount: Amount::zero(),
+                },
+                expire_period,
+            },
+            OperationSerializer::new(),
+            &keypair,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_operation_sig_verifier_rejects_invalid_batches() {
+        let config = ProtocolConfig {
+            operation_sig_verification_threads: 2,
+            operation_sig_verification_batch_size: 3,
+            ..Default::default()
+        };
+        let verifier = OperationSigVerifier::new(&config).unwrap();
+        let mut operations: Vec<SecureShareOperation> = (0..10).map(operation).collect();
+        verifier
+            .verify(&operations.iter().collect::<Vec<_>>())
+            .expect("valid signatures were rejected");
+        verifier
+            .verify(&operations[..2].iter().collect::<Vec<_>>())
+            .expect("valid signatures of a single batch were rejected");
+
+        // sign an operation with the key of another one
+        operations[7].content_creator_pub_key = operations[0].content_creator_pub_key;
+        assert!(matches!(
+            verifier.verify(&operations.iter().collect::<Vec<_>>()),
+            Err(ProtocolError::WrongSignature)
+        ));
+        assert!(matches!(
+            verifier.verify(&operations[6..8].iter().collect::<Vec<_>>()),
+            Err(ProtocolError::WrongSignature)
+        ));
+    }
+}
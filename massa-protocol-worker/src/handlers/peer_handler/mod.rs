@@ -1,5 +1,11 @@
 use std::net::IpAddr;
-use std::{collections::HashMap, net::SocketAddr, thread::JoinHandle, time::Duration};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::Arc,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
 
 use crossbeam::channel::tick;
 use crossbeam::select;
@@ -9,10 +15,12 @@ use massa_metrics::MassaMetrics;
 use massa_models::config::SIGNATURE_DESER_SIZE;
 use massa_models::version::{VersionDeserializer, VersionSerializer};
 use massa_protocol_exports::{
-    BootstrapPeers, PeerId, PeerIdDeserializer, PeerIdSerializer, ProtocolConfig,
+    BanReason, BootstrapPeers, FinalityProvider, HandshakeErrorClass, PeerId, PeerIdDeserializer,
+    PeerIdSerializer, ProtocolConfig,
 };
 use massa_serialization::{DeserializeError, Deserializer, Serializer};
 use massa_signature::Signature;
+use massa_time::MassaTime;
 use peernet::context::Context as _;
 use peernet::messages::MessagesSerializer as _;
 use rand::{rngs::StdRng, RngCore, SeedableRng};
@@ -25,12 +33,26 @@ use peernet::{
 };
 use tracing::log::{debug, error, info, warn};
 
+use crate::capabilities::{
+    AnnouncedFeatures, AnnouncedFeaturesDeserializer, AnnouncedFeaturesSerializer,
+    SharedPeerCapabilities,
+};
+use crate::connection_events::SharedConnectionTracker;
 use crate::context::Context;
+use crate::finality::{
+    FinalityAnnouncement, FinalityAnnouncementDeserializer, FinalityAnnouncementSerializer,
+    FinalityCheck, MAX_ANNOUNCED_FINAL_STATE_HASHES,
+};
 use crate::handlers::peer_handler::models::PeerState;
-use crate::messages::{Message, MessagesHandler, MessagesSerializer};
+use crate::messages::{
+    DeserializationFailure, Message, MessageHandlingTimer, MessageTypeId, MessagesHandler,
+    MessagesSerializer, HANDSHAKE_FAMILY,
+};
 use crate::wrap_network::ActiveConnectionsTrait;
 
 use self::models::PeerInfo;
+use self::reputation::{Misbehavior, PeerReputation, ReputationVerdict};
+use self::store::PeerStore;
 use self::{
     models::{
         InitialPeers, PeerManagementChannel, PeerManagementCmd, PeerMessageTuple, SharedPeerDB,
@@ -52,6 +74,8 @@ use self::{
 mod announcement;
 mod messages;
 pub mod models;
+pub mod reputation;
+mod store;
 mod tester;
 
 pub(crate) use messages::{PeerManagementMessage, PeerManagementMessageSerializer};
@@ -66,7 +90,7 @@ pub struct PeerManagementHandler {
 impl PeerManagementHandler {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        initial_peers: InitialPeers,
+        mut initial_peers: InitialPeers,
         peer_id: PeerId,
         peer_db: SharedPeerDB,
         (sender_msg, receiver_msg): (
@@ -77,6 +101,7 @@ impl PeerManagementHandler {
             MassaSender<PeerManagementCmd>,
             MassaReceiver<PeerManagementCmd>,
         ),
+        connection_tracker: SharedConnectionTracker,
         messages_handler: MessagesHandler,
         mut active_connections: Box<dyn ActiveConnectionsTrait>,
         target_out_connections: HashMap<String, (Vec<IpAddr>, usize)>,
@@ -86,6 +111,22 @@ impl PeerManagementHandler {
     ) -> Self {
         let message_serializer = PeerManagementMessageSerializer::new();
 
+        // the peers of the store are tested again, and the banned ones stay banned
+        let peer_store = config.peer_store_path.as_ref().map(|path| {
+            let store = PeerStore::new(path);
+            let records = store.load();
+            info!("loaded {} peers from the peer store", records.len());
+            for record in records.iter() {
+                if !record.banned && !record.listeners.is_empty() {
+                    initial_peers
+                        .entry(record.peer_id)
+                        .or_insert_with(|| record.listeners.clone());
+                }
+            }
+            peer_db.write().restore_peer_records(records);
+            store
+        });
+
         let ((test_sender, test_receiver), testers) = Tester::run(
             config,
             active_connections.clone(),
@@ -101,7 +142,10 @@ impl PeerManagementHandler {
         .spawn({
             let peer_db = peer_db.clone();
             let ticker = tick(Duration::from_secs(10));
+            let connections_ticker = tick(Duration::from_secs(1));
+            let store_ticker = tick(config.peer_store_flush_interval.to_duration());
             let config = config.clone();
+            let mut reputation = PeerReputation::new(&config);
             let message_serializer = MessagesSerializer::new()
                 .with_peer_management_message_serializer(PeerManagementMessageSerializer::new());
             let message_deserializer =
@@ -114,6 +158,7 @@ impl PeerManagementHandler {
                 loop {
                     select! {
                         recv(ticker) -> _ => {
+                            reputation.prune(Instant::now());
                             let peers_to_send = peer_db.read().get_rand_peers_to_send(100);
                             if peers_to_send.is_empty() {
                                 continue;
@@ -128,6 +173,18 @@ impl PeerManagementHandler {
                                }
                             }
                         }
+                        recv(connections_ticker) -> _ => {
+                            if connection_tracker.read().is_empty() {
+                                continue;
+                            }
+                            let active_peers = active_connections.get_peer_ids_connected();
+                            connection_tracker.write().refresh(&active_peers, MassaTime::now());
+                        }
+                        recv(store_ticker) -> _ => {
+                            if let Some(peer_store) = &peer_store {
+                                flush_peer_store(peer_store, &peer_db, config.peer_store_max_age);
+                            }
+                        }
                         recv(receiver_cmd) -> cmd => {
                             receiver_cmd.update_metrics();
                             // internal command
@@ -135,16 +192,22 @@ impl PeerManagementHandler {
                              Ok(PeerManagementCmd::Ban(peer_ids)) => {
                                 // remove running handshake ?
                                 for peer_id in peer_ids {
+                                    connection_tracker.write().banned(&peer_id, BanReason::Command);
                                     active_connections.shutdown_connection(&peer_id);
 
                                     // update peer_db
                                     peer_db.write().ban_peer(&peer_id);
+                                    reputation.forget(&peer_id);
                                 }
                             },
                              Ok(PeerManagementCmd::Unban(peer_ids)) => {
                                 for peer_id in peer_ids {
                                     peer_db.write().unban_peer(&peer_id);
+                                    reputation.forget(&peer_id);
                                 }
+                            },
+                             Ok(PeerManagementCmd::Report { peer_id, handler, misbehavior }) => {
+                                on_peer_misbehavior(&mut reputation, &mut active_connections, &peer_db, &connection_tracker, &peer_id, handler, misbehavior);
                             },
                              Ok(PeerManagementCmd::GetBootstrapPeers { responder }) => {
                                 let mut peers = peer_db.read().get_rand_peers_to_send(100);
@@ -159,10 +222,19 @@ impl PeerManagementHandler {
                                     warn!("error sending bootstrap peers: {:?}", err);
                                 }
                              },
+                             Ok(PeerManagementCmd::GetPeerRecords { responder }) => {
+                                let records = peer_db.read().get_peer_records();
+                                if let Err(err) = responder.try_send(records) {
+                                    warn!("error sending peer records: {:?}", err);
+                                }
+                             },
                              Ok(PeerManagementCmd::Stop) => {
                                 while let Ok(_msg) = test_receiver.try_recv() {
                                     // nothing to do just clean the channel
                                 }
+                                if let Some(peer_store) = &peer_store {
+                                    flush_peer_store(peer_store, &peer_db, config.peer_store_max_age);
+                                }
                                 return;
                              },
                             Err(e) => {
@@ -172,12 +244,13 @@ impl PeerManagementHandler {
                         },
                         recv(receiver_msg) -> msg => {
                             receiver_msg.update_metrics();
-                            let (peer_id, message) = match msg {
-                                Ok((peer_id, message)) => (peer_id, message),
+                            let (peer_id, message, queued_at) = match msg {
+                                Ok(msg) => msg,
                                 Err(_) => {
                                     return;
                                 }
                             };
+                            let _handling_timer = MessageHandlingTimer::start(MessageTypeId::PeerManagement.family(), queued_at);
                             // check if peer is banned
                             if let Some(peer) = peer_db.read().get_peers().get(&peer_id) {
                                 if peer.state == PeerState::Banned {
@@ -189,12 +262,16 @@ impl PeerManagementHandler {
                                 .deserialize::<DeserializeError>(&message) {
                                 Ok((rest, message)) => (rest, message),
                                 Err(e) => {
+                                    DeserializationFailure::Malformed.record(MessageTypeId::PeerManagement.family(), &peer_id);
                                     warn!("error when deserializing message: {:?}", e);
+                                    on_peer_misbehavior(&mut reputation, &mut active_connections, &peer_db, &connection_tracker, &peer_id, MessageTypeId::PeerManagement.family(), Misbehavior::InvalidMessage);
                                     continue;
                                 }
                             };
                             if !rest.is_empty() {
+                                DeserializationFailure::TrailingBytes.record(MessageTypeId::PeerManagement.family(), &peer_id);
                                 warn!("message not fully deserialized");
+                                on_peer_misbehavior(&mut reputation, &mut active_connections, &peer_db, &connection_tracker, &peer_id, MessageTypeId::PeerManagement.family(), Misbehavior::InvalidMessage);
                                 continue;
                             }
                             match message {
@@ -227,7 +304,9 @@ impl PeerManagementHandler {
                     &mut message,
                 )
                 .unwrap();
-            sender_msg.try_send((*peer_id, message)).unwrap();
+            sender_msg
+                .try_send((*peer_id, message, Instant::now()))
+                .unwrap();
         }
 
         Self {
@@ -256,6 +335,65 @@ impl PeerManagementHandler {
     }
 }
 
+/// Evict the peers that were not seen for `max_age` and save the remaining records to the peer store
+fn flush_peer_store(peer_store: &PeerStore, peer_db: &SharedPeerDB, max_age: MassaTime) {
+    let records = {
+        let mut peer_db = peer_db.write();
+        let evicted = peer_db.evict_peer_records(MassaTime::now().saturating_sub(max_age));
+        if evicted > 0 {
+            debug!("evicted {} peers from the peer store", evicted);
+        }
+        peer_db.get_peer_records()
+    };
+    peer_store.save(&records);
+}
+
+/// Record a misbehavior of a peer and disconnect or ban the peer if its reputation got too low
+fn on_peer_misbehavior(
+    reputation: &mut PeerReputation,
+    active_connections: &mut Box<dyn ActiveConnectionsTrait>,
+    peer_db: &SharedPeerDB,
+    connection_tracker: &SharedConnectionTracker,
+    peer_id: &PeerId,
+    handler: &'static str,
+    misbehavior: Misbehavior,
+) {
+    let now = Instant::now();
+    match reputation.report(peer_id, handler, misbehavior, now) {
+        ReputationVerdict::Keep => {}
+        ReputationVerdict::Deprioritize => {
+            info!(
+                "disconnecting peer {} after repeated misbehaviors (last: {:?} reported by the {} handler), penalties: {:?}",
+                peer_id,
+                misbehavior,
+                handler,
+                reputation.penalties(peer_id, now)
+            );
+            connection_tracker.write().deprioritized(peer_id);
+            active_connections.shutdown_connection(peer_id);
+        }
+        ReputationVerdict::Ban => {
+            warn!(
+                "banning peer {} after repeated misbehaviors (last: {:?} reported by the {} handler)",
+                peer_id, misbehavior, handler
+            );
+            connection_tracker
+                .write()
+                .banned(peer_id, BanReason::Reputation);
+            active_connections.shutdown_connection(peer_id);
+            peer_db.write().ban_peer(peer_id);
+        }
+    }
+}
+
+/// What is known about a failed handshake
+struct HandshakeFailure {
+    /// id of the peer, if it was received
+    peer_id: Option<PeerId>,
+    /// class of the error
+    class: HandshakeErrorClass,
+}
+
 #[derive(Clone)]
 pub struct MassaHandshake {
     pub announcement_serializer: AnnouncementSerializer,
@@ -267,10 +405,24 @@ pub struct MassaHandshake {
     peer_mngt_msg_serializer: MessagesSerializer,
     peer_id_serializer: PeerIdSerializer,
     peer_id_deserializer: PeerIdDeserializer,
+    features_serializer: AnnouncedFeaturesSerializer,
+    features_deserializer: AnnouncedFeaturesDeserializer,
+    finality_serializer: FinalityAnnouncementSerializer,
+    finality_deserializer: FinalityAnnouncementDeserializer,
+    /// source of the final state hashes announced to the peers
+    finality_provider: Option<Arc<dyn FinalityProvider>>,
+    /// protocol version and capabilities negotiated with the peers
+    pub(crate) peer_capabilities: SharedPeerCapabilities,
+    /// connections reported to the operators
+    pub(crate) connection_tracker: SharedConnectionTracker,
 }
 
 impl MassaHandshake {
-    pub fn new(peer_db: SharedPeerDB, config: ProtocolConfig) -> Self {
+    pub fn new(
+        peer_db: SharedPeerDB,
+        config: ProtocolConfig,
+        connection_tracker: SharedConnectionTracker,
+    ) -> Self {
         Self {
             peer_db,
             announcement_serializer: AnnouncementSerializer::new(),
@@ -281,28 +433,72 @@ impl MassaHandshake {
             ),
             version_serializer: VersionSerializer::new(),
             version_deserializer: VersionDeserializer::new(),
-            config,
             peer_id_serializer: PeerIdSerializer::new(),
             peer_id_deserializer: PeerIdDeserializer::new(),
             peer_mngt_msg_serializer: MessagesSerializer::new()
                 .with_peer_management_message_serializer(PeerManagementMessageSerializer::new()),
+            features_serializer: AnnouncedFeaturesSerializer::new(),
+            features_deserializer: AnnouncedFeaturesDeserializer::new(),
+            finality_serializer: FinalityAnnouncementSerializer::new(),
+            finality_deserializer: FinalityAnnouncementDeserializer::new(config.thread_count),
+            finality_provider: None,
+            peer_capabilities: Default::default(),
+            connection_tracker,
+            config,
         }
     }
 
+    /// Announce the final state hashes of this provider to the peers, and compare them with theirs
+    pub fn with_finality_provider(mut self, provider: Arc<dyn FinalityProvider>) -> Self {
+        self.finality_provider = Some(provider);
+        self
+    }
+
+    /// Compares the final state hashes announced by a peer with ours.
+    /// Fails if the peer follows a divergent finalized history and such peers are refused.
+    fn check_finality(
+        &self,
+        peer_id: &PeerId,
+        their_finality: Option<&FinalityAnnouncement>,
+        failure: &mut HandshakeFailure,
+    ) -> PeerNetResult<()> {
+        let Some(provider) = &self.finality_provider else {
+            return Ok(());
+        };
+        let ours = provider.latest_final_state_hashes(MAX_ANNOUNCED_FINAL_STATE_HASHES as usize);
+        let check = FinalityCheck::compare(&ours, their_finality);
+        massa_metrics::inc_protocol_handshake_finality_check(check.as_str());
+        if let FinalityCheck::Divergent { slot, ours, theirs } = check {
+            warn!(
+                "Peer {} follows a divergent finalized history: its final state hash at slot {} is {} while ours is {}. This may be a network partition or a fork",
+                peer_id, slot, theirs, ours
+            );
+            if self.config.refuse_divergent_peers {
+                failure.class = HandshakeErrorClass::DivergentFinality;
+                return Err(PeerNetError::HandshakeError.error(
+                    "Massa Handshake",
+                    Some(format!("Divergent final state hash at slot {}", slot)),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     fn handshake_fail(&mut self, addr: &SocketAddr) {
         let mut peer_db_write = self.peer_db.write();
         peer_db_write.set_try_connect_failure_or_insert(addr);
     }
-}
 
-impl InitConnectionHandler<PeerId, Context, MessagesHandler> for MassaHandshake {
-    fn perform_handshake(
+    /// Perform the handshake, recording in `failure` what is known about it in case it fails
+    fn try_handshake(
         &mut self,
         context: &Context,
         endpoint: &mut Endpoint,
         listeners: &HashMap<SocketAddr, TransportType>,
         messages_handler: MessagesHandler,
+        failure: &mut HandshakeFailure,
     ) -> PeerNetResult<PeerId> {
+        let start = Instant::now();
         let addr = *endpoint.get_target_addr();
         let mut bytes = vec![];
         self.peer_id_serializer
@@ -339,9 +535,33 @@ impl InitConnectionHandler<PeerId, Context, MessagesHandler> for MassaHandshake
                     Some(format!("Failed to serialize announcement: {}", err)),
                 )
             })?;
+        // protocol version and capabilities, ignored by the peers that do not know about them
+        let our_features = AnnouncedFeatures::ours(&self.config);
+        self.features_serializer
+            .serialize(&our_features, &mut bytes)
+            .map_err(|err| {
+                self.handshake_fail(&addr);
+                PeerNetError::HandshakeError.error(
+                    "Massa Handshake",
+                    Some(format!("Failed to serialize capabilities: {}", err)),
+                )
+            })?;
+        // latest final state hashes, ignored by the peers that do not know about them
+        let our_finality =
+            FinalityAnnouncement::ours(&self.config, self.finality_provider.as_deref());
+        self.finality_serializer
+            .serialize(&our_finality, &mut bytes)
+            .map_err(|err| {
+                self.handshake_fail(&addr);
+                PeerNetError::HandshakeError.error(
+                    "Massa Handshake",
+                    Some(format!("Failed to serialize final state hashes: {}", err)),
+                )
+            })?;
         endpoint.send::<PeerId>(&bytes)?;
         let received = endpoint.receive::<PeerId>()?;
         if received.len() < 32 {
+            failure.class = HandshakeErrorClass::Malformed;
             self.handshake_fail(&addr);
             return Err(PeerNetError::HandshakeError.error(
                 "Massa Handshake",
@@ -352,12 +572,14 @@ impl InitConnectionHandler<PeerId, Context, MessagesHandler> for MassaHandshake
             .peer_id_deserializer
             .deserialize::<DeserializeError>(&received)
             .map_err(|err| {
+                failure.class = HandshakeErrorClass::Malformed;
                 self.handshake_fail(&addr);
                 PeerNetError::HandshakeError.error(
                     "Massa Handshake",
                     Some(format!("Failed to deserialize peer id: {}", err)),
                 )
             })?;
+        failure.peer_id = Some(peer_id);
         {
             let peer_db_read = self.peer_db.read();
             if let Some(info) = peer_db_read.get_peers().get(&peer_id) {
@@ -382,32 +604,37 @@ impl InitConnectionHandler<PeerId, Context, MessagesHandler> for MassaHandshake
                 .version_deserializer
                 .deserialize::<DeserializeError>(received)
                 .map_err(|err| {
+                    failure.class = HandshakeErrorClass::Malformed;
+                    DeserializationFailure::Malformed.record(HANDSHAKE_FAMILY, &peer_id);
                     PeerNetError::HandshakeError.error(
                         "Massa Handshake",
                         Some(format!("Failed to deserialize version: {}", err)),
                     )
                 })?;
             if !self.config.version.is_compatible(&version) {
+                failure.class = HandshakeErrorClass::IncompatibleVersion;
                 return Err(PeerNetError::HandshakeError.error(
                     "Massa Handshake",
                     Some(format!("Received version incompatible: {}", version)),
                 ));
             }
-            let id = received.first().ok_or(
+            let id = received.first().ok_or_else(|| {
+                failure.class = HandshakeErrorClass::Malformed;
                 PeerNetError::HandshakeError
-                    .error("Massa Handshake", Some("Failed to get id".to_string())),
-            )?;
+                    .error("Massa Handshake", Some("Failed to get id".to_string()))
+            })?;
             match id {
                 0 => {
-                    let (_, announcement) = self
+                    let (features, announcement) = self
                         .announcement_deserializer
-                        .deserialize::<DeserializeError>(
-                            received.get(1..).ok_or(PeerNetError::HandshakeError.error(
-                                "Massa Handshake",
-                                Some("Failed to get data".to_string()),
-                            ))?,
-                        )
+                        .deserialize::<DeserializeError>(received.get(1..).ok_or_else(|| {
+                            failure.class = HandshakeErrorClass::Malformed;
+                            PeerNetError::HandshakeError
+                                .error("Massa Handshake", Some("Failed to get data".to_string()))
+                        })?)
                         .map_err(|err| {
+                            failure.class = HandshakeErrorClass::Malformed;
+                            DeserializationFailure::Malformed.record(HANDSHAKE_FAMILY, &peer_id);
                             PeerNetError::HandshakeError.error(
                                 "Massa Handshake",
                                 Some(format!("Failed to deserialize announcement: {}", err)),
@@ -417,9 +644,52 @@ impl InitConnectionHandler<PeerId, Context, MessagesHandler> for MassaHandshake
                         .verify_signature(&announcement.hash, &announcement.signature)
                         .is_err()
                     {
+                        failure.class = HandshakeErrorClass::InvalidSignature;
                         return Err(PeerNetError::HandshakeError
                             .error("Massa Handshake", Some("Invalid signature".to_string())));
                     }
+                    let (features_rest, their_features) = self
+                        .features_deserializer
+                        .deserialize::<DeserializeError>(features)
+                        .map_err(|err| {
+                            failure.class = HandshakeErrorClass::Malformed;
+                            DeserializationFailure::Malformed.record(HANDSHAKE_FAMILY, &peer_id);
+                            PeerNetError::HandshakeError.error(
+                                "Massa Handshake",
+                                Some(format!("Failed to deserialize capabilities: {}", err)),
+                            )
+                        })?;
+                    // the older nodes do not announce their final state hashes
+                    let their_finality = if features_rest.is_empty() {
+                        None
+                    } else {
+                        let (_, their_finality) = self
+                            .finality_deserializer
+                            .deserialize::<DeserializeError>(features_rest)
+                            .map_err(|err| {
+                                failure.class = HandshakeErrorClass::Malformed;
+                                DeserializationFailure::Malformed
+                                    .record(HANDSHAKE_FAMILY, &peer_id);
+                                PeerNetError::HandshakeError.error(
+                                    "Massa Handshake",
+                                    Some(format!(
+                                        "Failed to deserialize final state hashes: {}",
+                                        err
+                                    )),
+                                )
+                            })?;
+                        Some(their_finality)
+                    };
+                    let negotiated = our_features.negotiate(&their_features).ok_or_else(|| {
+                        failure.class = HandshakeErrorClass::IncompatibleVersion;
+                        PeerNetError::HandshakeError.error(
+                            "Massa Handshake",
+                            Some(format!(
+                                "Received protocol version incompatible: {:?}",
+                                their_features.protocol_version
+                            )),
+                        )
+                    })?;
                     let message = PeerManagementMessage::NewPeerConnected((
                         peer_id,
                         announcement.clone().listeners,
@@ -448,6 +718,7 @@ impl InitConnectionHandler<PeerId, Context, MessagesHandler> for MassaHandshake
                     let received = endpoint.receive::<PeerId>()?;
                     let other_random_bytes: &[u8; 32] =
                         received.as_slice().try_into().map_err(|_| {
+                            failure.class = HandshakeErrorClass::Malformed;
                             PeerNetError::HandshakeError.error(
                                 "Massa Handshake",
                                 Some("Failed to deserialize random bytes".to_string()),
@@ -472,6 +743,7 @@ impl InitConnectionHandler<PeerId, Context, MessagesHandler> for MassaHandshake
 
                     let other_signature =
                         Signature::from_bytes(received.as_slice()).map_err(|_| {
+                            failure.class = HandshakeErrorClass::Malformed;
                             PeerNetError::HandshakeError.error(
                                 "Massa Handshake",
                                 Some("Failed to sign 2 random bytes".to_string()),
@@ -482,23 +754,36 @@ impl InitConnectionHandler<PeerId, Context, MessagesHandler> for MassaHandshake
                     peer_id
                         .verify_signature(&self_random_hash, &other_signature)
                         .map_err(|err| {
+                            failure.class = HandshakeErrorClass::InvalidSignature;
                             PeerNetError::HandshakeError
                                 .error("Massa Handshake", Some(format!("Signature error {}", err)))
                         })?;
+                    self.check_finality(&peer_id, their_finality.as_ref(), failure)?;
+                    debug!(
+                        "Negotiated protocol {} with capabilities {:#b} with peer {}",
+                        negotiated.protocol_version,
+                        negotiated.capabilities.bits(),
+                        peer_id
+                    );
+                    self.peer_capabilities.write().insert(peer_id, negotiated);
                     Ok((peer_id, Some(announcement)))
                 }
                 1 => {
                     messages_handler.handle(
-                        received.get(1..).ok_or(
+                        received.get(1..).ok_or_else(|| {
+                            failure.class = HandshakeErrorClass::Malformed;
                             PeerNetError::HandshakeError
-                                .error("Massa Handshake", Some("Failed to get data".to_string())),
-                        )?,
+                                .error("Massa Handshake", Some("Failed to get data".to_string()))
+                        })?,
                         &peer_id,
                     )?;
                     Ok((peer_id, None))
                 }
-                _ => Err(PeerNetError::HandshakeError
-                    .error("Massa Handshake", Some("Invalid message id".to_string()))),
+                _ => {
+                    failure.class = HandshakeErrorClass::Malformed;
+                    Err(PeerNetError::HandshakeError
+                        .error("Massa Handshake", Some("Invalid message id".to_string())))
+                }
             }
         };
         {
@@ -519,6 +804,7 @@ impl InitConnectionHandler<PeerId, Context, MessagesHandler> for MassaHandshake
                             last_announce: Some(announcement.clone()),
                             state: PeerState::Trusted,
                         });
+                    peer_db_write.record_handshake(peer_id, start.elapsed());
                 }
                 Ok((_peer_id, None)) => {
                     peer_db_write
@@ -529,6 +815,7 @@ impl InitConnectionHandler<PeerId, Context, MessagesHandler> for MassaHandshake
                             info.state = PeerState::HandshakeFailed;
                         });
                     peer_db_write.set_try_connect_failure_or_insert(&addr);
+                    failure.class = HandshakeErrorClass::NoSlot;
                     return Err(PeerNetError::HandshakeError.error(
                         "Massa Handshake",
                         Some("Distant peer don't have slot for us.".to_string()),
@@ -560,6 +847,39 @@ impl InitConnectionHandler<PeerId, Context, MessagesHandler> for MassaHandshake
 
         res.map(|(id, _)| id)
     }
+}
+
+impl InitConnectionHandler<PeerId, Context, MessagesHandler> for MassaHandshake {
+    fn perform_handshake(
+        &mut self,
+        context: &Context,
+        endpoint: &mut Endpoint,
+        listeners: &HashMap<SocketAddr, TransportType>,
+        messages_handler: MessagesHandler,
+    ) -> PeerNetResult<PeerId> {
+        let addr = *endpoint.get_target_addr();
+        let mut failure = HandshakeFailure {
+            peer_id: None,
+            class: HandshakeErrorClass::Transport,
+        };
+        let rate_limiter = messages_handler.rate_limiter.clone();
+        let res = self.try_handshake(context, endpoint, listeners, messages_handler, &mut failure);
+        match &res {
+            Ok(peer_id) => {
+                if let Some(rate_limiter) = rate_limiter {
+                    rate_limiter.register_peer(*peer_id, addr.ip());
+                }
+                self.connection_tracker.write().connected(*peer_id, addr);
+            }
+            Err(err) => self.connection_tracker.read().handshake_failed(
+                addr,
+                failure.peer_id,
+                failure.class,
+                err.to_string(),
+            ),
+        }
+        res
+    }
 
     fn fallback_function(
         &mut self,
@@ -620,13 +940,17 @@ mod tests {
     use std::{collections::HashMap, ops::Deref, sync::Arc};
 
     use massa_channel::MassaChannel;
-    use massa_protocol_exports::ProtocolConfig;
+    use massa_hash::Hash;
+    use massa_models::slot::Slot;
+    use massa_protocol_exports::{FinalityProvider, ProtocolBroadcasts, ProtocolConfig};
     use massa_serialization::U64VarIntDeserializer;
     use massa_signature::KeyPair;
     use parking_lot::RwLock;
     use peernet::{peer::InitConnectionHandler, transports::endpoint::Endpoint};
 
-    use crate::{context::Context, messages::MessagesHandler};
+    use crate::{
+        connection_events::ConnectionTracker, context::Context, messages::MessagesHandler,
+    };
 
     use super::models::PeerDB;
 
@@ -636,8 +960,13 @@ mod tests {
         let (sender_endorsements, _) = MassaChannel::new(String::from("test_endorsements"), None);
         let (sender_operations, _) = MassaChannel::new(String::from("test_operations"), None);
         let (sender_peers, _) = MassaChannel::new(String::from("test_peers"), None);
+        let (sender_topics, _) = MassaChannel::new(String::from("test_topics"), None);
         let shared_peer_db = Arc::new(RwLock::new(PeerDB::default()));
-        let mut handshake = super::MassaHandshake::new(shared_peer_db, ProtocolConfig::default());
+        let mut handshake = super::MassaHandshake::new(
+            shared_peer_db,
+            ProtocolConfig::default(),
+            ConnectionTracker::new_shared(ProtocolBroadcasts::new(16)),
+        );
         let our_keypair = KeyPair::generate(0).unwrap();
         let messages_handlers = MessagesHandler {
             id_deserializer: U64VarIntDeserializer::new(
@@ -648,6 +977,11 @@ mod tests {
             sender_endorsements,
             sender_operations,
             sender_peers,
+            sender_topics,
+            capture: None,
+            max_decompressed_message_size: None,
+            rate_limiter: None,
+            bandwidth_budgets: None,
         };
         let (local_sender, remote_receiver) =
             MassaChannel::new(String::from("Test_transport_local_to_remote"), None);
@@ -691,14 +1025,114 @@ mod tests {
         thread.join().unwrap();
     }
 
+    /// Announces fixed final state hashes
+    struct FixedFinality(Vec<(Slot, Hash)>);
+
+    impl FinalityProvider for FixedFinality {
+        fn latest_final_state_hashes(&self, count: usize) -> Vec<(Slot, Hash)> {
+            self.0.iter().rev().take(count).rev().copied().collect()
+        }
+    }
+
+    #[test]
+    fn test_handshake_refuses_divergent_finality() {
+        let (sender_blocks, _) = MassaChannel::new(String::from("test_blocks"), None);
+        let (sender_endorsements, _) = MassaChannel::new(String::from("test_endorsements"), None);
+        let (sender_operations, _) = MassaChannel::new(String::from("test_operations"), None);
+        let (sender_peers, _) = MassaChannel::new(String::from("test_peers"), None);
+        let (sender_topics, _) = MassaChannel::new(String::from("test_topics"), None);
+        let config = ProtocolConfig {
+            refuse_divergent_peers: true,
+            ..Default::default()
+        };
+        let handshake = |salt: &[u8]| {
+            super::MassaHandshake::new(
+                Arc::new(RwLock::new(PeerDB::default())),
+                config.clone(),
+                ConnectionTracker::new_shared(ProtocolBroadcasts::new(16)),
+            )
+            .with_finality_provider(Arc::new(FixedFinality(
+                (0..4u64)
+                    .map(|period| {
+                        (
+                            Slot::new(period, 0),
+                            Hash::compute_from_tuple(&[&period.to_be_bytes(), salt]),
+                        )
+                    })
+                    .collect(),
+            )))
+        };
+        let messages_handlers = MessagesHandler {
+            id_deserializer: U64VarIntDeserializer::new(
+                std::ops::Bound::Included(0),
+                std::ops::Bound::Included(u64::MAX),
+            ),
+            sender_blocks,
+            sender_endorsements,
+            sender_operations,
+            sender_peers,
+            sender_topics,
+            capture: None,
+            max_decompressed_message_size: None,
+            rate_limiter: None,
+            bandwidth_budgets: None,
+        };
+        let (local_sender, remote_receiver) =
+            MassaChannel::new(String::from("Test_transport_local_to_remote"), None);
+        let (remote_sender, local_receiver) =
+            MassaChannel::new(String::from("Test_transport_remote_to_local"), None);
+        let mut endpoint = Endpoint::MockEndpoint((
+            (*local_sender.deref()).clone(),
+            (*local_receiver.deref()).clone(),
+            "127.0.0.1:0".parse().unwrap(),
+        ));
+        let thread = std::thread::spawn({
+            let mut handshake = handshake(b"theirs");
+            let messages_handlers = messages_handlers.clone();
+            let mut endpoint = Endpoint::MockEndpoint((
+                (*remote_sender.deref()).clone(),
+                (*remote_receiver.deref()).clone(),
+                "127.0.0.1:0".parse().unwrap(),
+            ));
+            move || {
+                let context = Context {
+                    our_keypair: KeyPair::generate(0).unwrap(),
+                };
+                handshake.perform_handshake(
+                    &context,
+                    &mut endpoint,
+                    &HashMap::default(),
+                    messages_handlers,
+                )
+            }
+        });
+        let context = Context {
+            our_keypair: KeyPair::generate(0).unwrap(),
+        };
+        let res = handshake(b"ours").perform_handshake(
+            &context,
+            &mut endpoint,
+            &HashMap::default(),
+            messages_handlers,
+        );
+        // both nodes finalized the same slots with different final state hashes
+        assert!(res.is_err());
+        assert!(thread.join().unwrap().is_err());
+    }
+
     #[test]
     fn test_handshake_wrong_data_received() {
         let (sender_blocks, _) = MassaChannel::new(String::from("test_blocks"), None);
         let (sender_endorsements, _) = MassaChannel::new(String::from("test_endorsements"), None);
         let (sender_operations, _) = MassaChannel::new(String::from("test_operations"), None);
         let (sender_peers, _) = MassaChannel::new(String::from("test_peers"), None);
+        let (sender_topics, _) = MassaChannel::new(String::from("test_topics"), None);
         let shared_peer_db = Arc::new(RwLock::new(PeerDB::default()));
-        let mut handshake = super::MassaHandshake::new(shared_peer_db, ProtocolConfig::default());
+        let mut handshake = super::MassaHandshake::new(
+            shared_peer_db,
+            ProtocolConfig::default(),
+            ConnectionTracker::new_shared(ProtocolBroadcasts::new(16)),
+        );
         let our_keypair = KeyPair::generate(0).unwrap();
         let messages_handlers = MessagesHandler {
             id_deserializer: U64VarIntDeserializer::new(
@@ -709,6 +1143,11 @@ mod tests {
             sender_endorsements,
             sender_operations,
             sender_peers,
+            sender_topics,
+            capture: None,
+            max_decompressed_message_size: None,
+            rate_limiter: None,
+            bandwidth_budgets: None,
         };
         let (local_sender, _) =
             MassaChannel::new(String::from("Test_transport_local_to_remote"), None);
@@ -742,8 +1181,13 @@ mod tests {
         let (sender_endorsements, _) = MassaChannel::new(String::from("test_endorsements"), None);
         let (sender_operations, _) = MassaChannel::new(String::from("test_operations"), None);
         let (sender_peers, _) = MassaChannel::new(String::from("test_peers"), None);
+        let (sender_topics, _) = MassaChannel::new(String::from("test_topics"), None);
         let shared_peer_db = Arc::new(RwLock::new(PeerDB::default()));
-        let mut handshake = super::MassaHandshake::new(shared_peer_db, ProtocolConfig::default());
+        let mut handshake = super::MassaHandshake::new(
+            shared_peer_db,
+            ProtocolConfig::default(),
+            ConnectionTracker::new_shared(ProtocolBroadcasts::new(16)),
+        );
         let our_keypair = KeyPair::generate(0).unwrap();
         let messages_handlers = MessagesHandler {
             id_deserializer: U64VarIntDeserializer::new(
@@ -754,6 +1198,11 @@ mod tests {
             sender_endorsements,
             sender_operations,
             sender_peers,
+            sender_topics,
+            capture: None,
+            max_decompressed_message_size: None,
+            rate_limiter: None,
+            bandwidth_budgets: None,
         };
         let (local_sender, _) =
             MassaChannel::new(String::from("Test_transport_local_to_remote"), None);
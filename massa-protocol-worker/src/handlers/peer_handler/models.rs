@@ -1,5 +1,5 @@
 use massa_channel::sender::MassaSender;
-use massa_protocol_exports::{BootstrapPeers, PeerId};
+use massa_protocol_exports::{BootstrapPeers, PeerId, PeerRecord};
 use massa_time::MassaTime;
 use parking_lot::RwLock;
 use peernet::transports::TransportType;
@@ -7,13 +7,14 @@ use rand::seq::SliceRandom;
 use rand::{thread_rng, Rng};
 use std::cmp::Ordering;
 use std::collections::HashSet;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 use tracing::log::info;
 
 use crate::wrap_peer_db::PeerDBTrait;
 
 use super::announcement::Announcement;
+use super::reputation::Misbehavior;
 
 const THREE_DAYS_MS: u64 = 3 * 24 * 60 * 60 * 1_000;
 
@@ -155,11 +156,14 @@ pub struct PeerDB {
     pub try_connect_history: HashMap<SocketAddr, ConnectionMetadata>,
     /// peers currently tested
     pub peers_in_test: HashSet<SocketAddr>,
+    /// liveness history of the peers, kept in the peer store
+    pub records: HashMap<PeerId, PeerRecord>,
 }
 
 pub type SharedPeerDB = Arc<RwLock<dyn PeerDBTrait>>;
 
-pub type PeerMessageTuple = (PeerId, Vec<u8>);
+/// Message received from a peer: the peer, the serialized message and the instant it was queued for its handler
+pub type PeerMessageTuple = (PeerId, Vec<u8>, Instant);
 
 #[derive(Clone, Debug)]
 pub struct PeerInfo {
@@ -180,9 +184,18 @@ pub enum PeerState {
 pub enum PeerManagementCmd {
     Ban(Vec<PeerId>),
     Unban(Vec<PeerId>),
+    /// A handler reports a misbehavior of a peer, see `reputation`
+    Report {
+        peer_id: PeerId,
+        handler: &'static str,
+        misbehavior: Misbehavior,
+    },
     GetBootstrapPeers {
         responder: MassaSender<BootstrapPeers>,
     },
+    GetPeerRecords {
+        responder: MassaSender<Vec<PeerRecord>>,
+    },
     Stop,
 }
 
@@ -195,6 +208,10 @@ impl PeerDBTrait for PeerDB {
     fn ban_peer(&mut self, peer_id: &PeerId) {
         if let Some(peer) = self.peers.get_mut(peer_id) {
             peer.state = PeerState::Banned;
+            self.records
+                .entry(*peer_id)
+                .or_insert_with(|| PeerRecord::new(*peer_id))
+                .banned = true;
             info!("Banned peer: {:?}", peer_id);
         } else {
             info!("Tried to ban unknown peer: {:?}", peer_id);
@@ -205,6 +222,9 @@ impl PeerDBTrait for PeerDB {
         if let Some(peer) = self.peers.get_mut(peer_id) {
             // We set the state to HandshakeFailed to force the peer to be tested again
             peer.state = PeerState::HandshakeFailed;
+            if let Some(record) = self.records.get_mut(peer_id) {
+                record.banned = false;
+            }
             info!("Unbanned peer: {:?}", peer_id);
         } else {
             info!("Tried to unban unknown peer: {:?}", peer_id);
@@ -351,4 +371,53 @@ impl PeerDBTrait for PeerDB {
     fn get_tested_addresses(&self) -> &HashMap<SocketAddr, MassaTime> {
         &self.tested_addresses
     }
+
+    /// Record a successful handshake with a peer, along with the listeners of its last announcement
+    fn record_handshake(&mut self, peer_id: &PeerId, latency: Duration) {
+        let record = self
+            .records
+            .entry(*peer_id)
+            .or_insert_with(|| PeerRecord::new(*peer_id));
+        record.last_seen = Some(MassaTime::now());
+        record.handshake_latency = Some(MassaTime::from_millis(latency.as_millis() as u64));
+        if let Some(peer) = self.peers.get(peer_id) {
+            if let Some(last_announce) = &peer.last_announce {
+                record.listeners = last_announce.listeners.clone();
+            }
+            record.banned = peer.state == PeerState::Banned;
+        }
+    }
+
+    fn get_peer_records(&self) -> Vec<PeerRecord> {
+        self.records.values().cloned().collect()
+    }
+
+    /// Restore the records of the peer store. The banned peers stay banned.
+    fn restore_peer_records(&mut self, records: Vec<PeerRecord>) {
+        for record in records {
+            if record.banned {
+                self.peers
+                    .entry(record.peer_id)
+                    .and_modify(|info| info.state = PeerState::Banned)
+                    .or_insert(PeerInfo {
+                        last_announce: None,
+                        state: PeerState::Banned,
+                    });
+            }
+            self.records.insert(record.peer_id, record);
+        }
+    }
+
+    /// Evict the records of the peers that were not seen since `min_last_seen`, unless they are banned.
+    /// Returns the number of evicted records.
+    fn evict_peer_records(&mut self, min_last_seen: MassaTime) -> usize {
+        let count = self.records.len();
+        self.records.retain(|_, record| {
+            record.banned
+                || record
+                    .last_seen
+                    .map_or(false, |last_seen| last_seen >= min_last_seen)
+        });
+        count - self.records.len()
+    }
 }
@@ -0,0 +1,264 @@
+//! Reputation of the peers.
+//!
+//! Handlers report the misbehaviors of the peers they talk to (invalid or oversized messages, timeouts,
+//! useless announcements, bandwidth abuse) to the peer handler, which keeps a score per peer and per handler.
+//! Each misbehavior costs the peer a number of points, and points are recovered over time.
+//! A peer whose total penalty reaches the deprioritization threshold is disconnected so that its slot
+//! goes to another peer, and a peer whose total penalty reaches the ban threshold is banned.
+//!
+//! Misbehaviors that prove the peer is malicious (e.g. invalid signatures) still trigger an immediate ban.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use massa_channel::sender::MassaSender;
+use massa_protocol_exports::{PeerId, ProtocolConfig};
+use tracing::warn;
+
+use super::models::PeerManagementCmd;
+
+/// Misbehavior of a peer reported by a handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Misbehavior {
+    /// the peer sent a message that could not be deserialized
+    InvalidMessage,
+    /// the peer did not answer a request in time
+    Timeout,
+    /// the peer announced something it had already announced to us
+    UselessAnnouncement,
+    /// the peer sent us data we did not ask for
+    BandwidthAbuse,
+    /// the peer sent a message exceeding a size limit, which honest nodes never do
+    OversizedMessage,
+}
+
+impl Misbehavior {
+    /// Number of reputation points lost by a peer for this misbehavior
+    pub fn penalty(&self) -> u64 {
+        match self {
+            Misbehavior::InvalidMessage => 100,
+            Misbehavior::Timeout => 10,
+            Misbehavior::UselessAnnouncement => 2,
+            Misbehavior::BandwidthAbuse => 50,
+            Misbehavior::OversizedMessage => 200,
+        }
+    }
+}
+
+/// Report a misbehavior of a peer to the peer handler
+pub(crate) fn report_misbehavior(
+    peer_cmd_sender: &MassaSender<PeerManagementCmd>,
+    peer_id: &PeerId,
+    handler: &'static str,
+    misbehavior: Misbehavior,
+) {
+    if let Err(err) = peer_cmd_sender.try_send(PeerManagementCmd::Report {
+        peer_id: *peer_id,
+        handler,
+        misbehavior,
+    }) {
+        warn!(
+            "error when reporting a misbehavior of peer {}: {}",
+            peer_id, err
+        );
+    }
+}
+
+/// Action to take on a peer after one of its misbehaviors was reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReputationVerdict {
+    /// nothing to do
+    Keep,
+    /// the peer just crossed the deprioritization threshold: its connection should be dropped
+    Deprioritize,
+    /// the peer crossed the ban threshold: it should be banned
+    Ban,
+}
+
+/// Reputation of a peer
+#[derive(Debug, Clone)]
+struct PeerScore {
+    /// points lost by the peer, by reporting handler
+    penalties: HashMap<&'static str, u64>,
+    /// last time the penalties were updated
+    last_update: Instant,
+    /// whether the peer was deprioritized since its penalty last went below the threshold
+    deprioritized: bool,
+}
+
+impl PeerScore {
+    fn new(now: Instant) -> Self {
+        PeerScore {
+            penalties: HashMap::new(),
+            last_update: now,
+            deprioritized: false,
+        }
+    }
+
+    fn total(&self) -> u64 {
+        self.penalties
+            .values()
+            .fold(0, |acc, penalty| acc.saturating_add(*penalty))
+    }
+
+    /// Recover one point per handler for each elapsed recovery period
+    fn recover(&mut self, now: Instant, recovery_period: Duration) {
+        let recovery_period = recovery_period.max(Duration::from_millis(1));
+        let elapsed = now.saturating_duration_since(self.last_update);
+        let recovered =
+            u32::try_from(elapsed.as_millis() / recovery_period.as_millis()).unwrap_or(u32::MAX);
+        if recovered == 0 {
+            return;
+        }
+        for penalty in self.penalties.values_mut() {
+            *penalty = penalty.saturating_sub(u64::from(recovered));
+        }
+        self.penalties.retain(|_, penalty| *penalty > 0);
+        // keep the remainder of the current period
+        self.last_update = self
+            .last_update
+            .checked_add(recovery_period.saturating_mul(recovered))
+            .map_or(now, |last_update| last_update.min(now));
+    }
+}
+
+/// Scores of the peers that misbehaved recently
+pub struct PeerReputation {
+    scores: HashMap<PeerId, PeerScore>,
+    /// total penalty above which a peer is disconnected
+    deprioritize_threshold: u64,
+    /// total penalty above which a peer is banned
+    ban_threshold: u64,
+    /// time needed to recover one point of penalty
+    recovery_period: Duration,
+}
+
+impl PeerReputation {
+    pub fn new(config: &ProtocolConfig) -> Self {
+        PeerReputation {
+            scores: HashMap::new(),
+            deprioritize_threshold: config.peer_reputation_deprioritize_threshold,
+            ban_threshold: config.peer_reputation_ban_threshold,
+            recovery_period: config.peer_reputation_recovery_period.to_duration(),
+        }
+    }
+
+    /// Record a misbehavior of a peer reported by a handler, and return what to do with the peer
+    pub fn report(
+        &mut self,
+        peer_id: &PeerId,
+        handler: &'static str,
+        misbehavior: Misbehavior,
+        now: Instant,
+    ) -> ReputationVerdict {
+        let score = self
+            .scores
+            .entry(*peer_id)
+            .or_insert_with(|| PeerScore::new(now));
+        score.recover(now, self.recovery_period);
+        let penalty = score.penalties.entry(handler).or_default();
+        *penalty = penalty.saturating_add(misbehavior.penalty());
+
+        let total = score.total();
+        if total >= self.ban_threshold {
+            self.scores.remove(peer_id);
+            ReputationVerdict::Ban
+        } else if total >= self.deprioritize_threshold {
+            if score.deprioritized {
+                ReputationVerdict::Keep
+            } else {
+                score.deprioritized = true;
+                ReputationVerdict::Deprioritize
+            }
+        } else {
+            score.deprioritized = false;
+            ReputationVerdict::Keep
+        }
+    }
+
+    /// Current penalty of a peer, by reporting handler
+    pub fn penalties(&mut self, peer_id: &PeerId, now: Instant) -> HashMap<&'static str, u64> {
+        match self.scores.get_mut(peer_id) {
+            Some(score) => {
+                score.recover(now, self.recovery_period);
+                score.penalties.clone()
+            }
+            None => HashMap::new(),
+        }
+    }
+
+    /// Forget the peers that recovered all their points
+    pub fn prune(&mut self, now: Instant) {
+        let recovery_period = self.recovery_period;
+        self.scores.retain(|_, score| {
+            score.recover(now, recovery_period);
+            !score.penalties.is_empty()
+        });
+    }
+
+    /// Forget the reputation of a peer
+    pub fn forget(&mut self, peer_id: &PeerId) {
+        self.scores.remove(peer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_signature::KeyPair;
+
+    fn reputation() -> PeerReputation {
+        PeerReputation {
+            scores: HashMap::new(),
+            deprioritize_threshold: 200,
+            ban_threshold: 500,
+            recovery_period: Duration::from_secs(1),
+        }
+    }
+
+    fn peer_id() -> PeerId {
+        PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key())
+    }
+
+    #[test]
+    fn test_misbehaving_peer_is_deprioritized_then_banned() {
+        let mut reputation = reputation();
+        let peer_id = peer_id();
+        let now = Instant::now();
+        let verdicts: Vec<_> = (0..5)
+            .map(|_| reputation.report(&peer_id, "block", Misbehavior::InvalidMessage, now))
+            .collect();
+        assert_eq!(
+            verdicts,
+            vec![
+                ReputationVerdict::Keep,
+                ReputationVerdict::Deprioritize,
+                ReputationVerdict::Keep,
+                ReputationVerdict::Keep,
+                ReputationVerdict::Ban,
+            ]
+        );
+        // the reputation of a banned peer is forgotten
+        assert!(reputation.penalties(&peer_id, now).is_empty());
+    }
+
+    #[test]
+    fn test_penalties_are_tracked_per_handler_and_recovered() {
+        let mut reputation = reputation();
+        let peer_id = peer_id();
+        let now = Instant::now();
+        reputation.report(&peer_id, "block", Misbehavior::Timeout, now);
+        reputation.report(&peer_id, "operation", Misbehavior::BandwidthAbuse, now);
+        let penalties = reputation.penalties(&peer_id, now);
+        assert_eq!(penalties.get("block"), Some(&10));
+        assert_eq!(penalties.get("operation"), Some(&50));
+
+        let later = now + Duration::from_millis(10_500);
+        let penalties = reputation.penalties(&peer_id, later);
+        assert_eq!(penalties.get("block"), None);
+        assert_eq!(penalties.get("operation"), Some(&40));
+
+        reputation.prune(now + Duration::from_secs(60));
+        assert!(reputation.scores.is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+//! On-disk store of the liveness history of the peers.
+//!
+//! The peer records are stored in a RocksDB database, keyed by the public key of the peers,
+//! so that a restarting node remembers the peers it saw, how fast they answered and which ones it banned,
+//! instead of starting again from the initial peers only.
+//! The peer handler flushes the records of its peer database periodically and when it stops.
+
+use std::{collections::HashSet, path::Path};
+
+use massa_protocol_exports::{PeerId, PeerRecord};
+use rocksdb::{IteratorMode, WriteBatch, DB};
+use tracing::log::warn;
+
+const OPEN_ERROR: &str = "critical: rocksdb open operation failed";
+const CRUD_ERROR: &str = "critical: rocksdb crud operation failed";
+const RECORD_SER_ERROR: &str = "critical: peer record serialization failed";
+
+/// Key of the record of a peer
+fn record_key(peer_id: &PeerId) -> Vec<u8> {
+    peer_id.get_public_key().to_bytes()
+}
+
+/// On-disk store of the peer records
+pub(crate) struct PeerStore {
+    /// RocksDB database
+    db: DB,
+}
+
+impl PeerStore {
+    /// Opens the store at `path`, creating it if needed
+    pub fn new(path: &Path) -> Self {
+        PeerStore {
+            db: DB::open_default(path).expect(OPEN_ERROR),
+        }
+    }
+
+    /// Reads all the stored records. Records that cannot be read are skipped.
+    pub fn load(&self) -> Vec<PeerRecord> {
+        self.db
+            .iterator(IteratorMode::Start)
+            .map(|item| item.expect(CRUD_ERROR))
+            .filter_map(
+                |(_, value)| match serde_json::from_slice::<PeerRecord>(&value) {
+                    Ok(record) => Some(record),
+                    Err(err) => {
+                        warn!("could not read a record of the peer store: {}", err);
+                        None
+                    }
+                },
+            )
+            .collect()
+    }
+
+    /// Replaces the content of the store with `records`
+    pub fn save(&self, records: &[PeerRecord]) {
+        let kept: HashSet<Vec<u8>> = records
+            .iter()
+            .map(|record| record_key(&record.peer_id))
+            .collect();
+        let mut batch = WriteBatch::default();
+        for (key, _) in self
+            .db
+            .iterator(IteratorMode::Start)
+            .map(|item| item.expect(CRUD_ERROR))
+        {
+            if !kept.contains(key.as_ref()) {
+                batch.delete(key);
+            }
+        }
+        for record in records {
+            batch.put(
+                record_key(&record.peer_id),
+                serde_json::to_vec(record).expect(RECORD_SER_ERROR),
+            );
+        }
+        self.db.write(batch).expect(CRUD_ERROR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_signature::KeyPair;
+    use massa_time::MassaTime;
+    use peernet::transports::TransportType;
+    use tempfile::TempDir;
+
+    fn record(banned: bool) -> PeerRecord {
+        let mut record = PeerRecord::new(PeerId::from_public_key(
+            KeyPair::generate(0).unwrap().get_public_key(),
+        ));
+        record.last_seen = Some(MassaTime::from_millis(1000));
+        record.handshake_latency = Some(MassaTime::from_millis(20));
+        record
+            .listeners
+            .insert("127.0.0.1:31244".parse().unwrap(), TransportType::Tcp);
+        record.banned = banned;
+        record
+    }
+
+    fn sorted(mut records: Vec<PeerRecord>) -> Vec<PeerRecord> {
+        records.sort_by_key(|record| record.peer_id);
+        records
+    }
+
+    #[test]
+    fn test_peer_store_save_and_reload() {
+        let dir = TempDir::new().unwrap();
+        let records = vec![record(false), record(true)];
+        {
+            let store = PeerStore::new(dir.path());
+            assert!(store.load().is_empty());
+            store.save(&records);
+        }
+
+        // the records survive a restart
+        let store = PeerStore::new(dir.path());
+        assert_eq!(sorted(store.load()), sorted(records.clone()));
+
+        // the records that are not saved anymore are removed
+        let replaced = vec![records[1].clone(), record(false)];
+        store.save(&replaced);
+        assert_eq!(sorted(store.load()), sorted(replaced));
+    }
+}
@@ -3,7 +3,7 @@ use std::{
     io::Read,
     net::{IpAddr, SocketAddr},
     thread::JoinHandle,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::{ip::to_canonical, messages::MessagesHandler};
@@ -85,6 +85,7 @@ impl Tester {
         let our_version = config.version;
 
         let exec_handshake = || {
+            let start = Instant::now();
             let mut socket =
                 std::net::TcpStream::connect_timeout(&addr, config.tester_timeout.into())
                     .map_err(|e| PeerNetError::PeerConnectionError.new("connect", e, None))?;
@@ -202,6 +203,7 @@ impl Tester {
                                     last_announce: Some(announcement),
                                     state: super::PeerState::Trusted,
                                 });
+                            peer_db_write.record_handshake(&peer_id, start.elapsed());
                         }
                         Ok(peer_id)
                     }
@@ -0,0 +1,203 @@
+//! State of the registered topics, owned by the topic gossip thread.
+//!
+//! Payloads are identified by the hash of their topic and content. For each topic, the latest accepted
+//! payloads are kept to answer the requests of the peers, and the IDs of the payloads already handled
+//! are remembered so that a payload is passed to its handler once, whether it was accepted or not.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
+
+use massa_hash::Hash;
+use massa_protocol_exports::{PeerId, TopicConfig, TopicHandler, TopicId};
+use schnellru::{ByLength, LruMap};
+
+use crate::rate_limit::TokenBucket;
+
+/// Number of handled payload IDs remembered per kept payload
+const SEEN_IDS_PER_KEPT_PAYLOAD: u32 = 4;
+
+/// ID of a payload published on a topic
+pub(crate) fn payload_id(topic: TopicId, payload: &[u8]) -> Hash {
+    Hash::compute_from_tuple(&[&topic.to_be_bytes(), payload])
+}
+
+/// Number of payloads kept for a topic
+fn kept_capacity(config: &TopicConfig) -> u32 {
+    u32::try_from(config.max_kept_payloads)
+        .unwrap_or(u32::MAX)
+        .max(1)
+}
+
+/// State of a registered topic
+pub(crate) struct TopicState {
+    pub config: TopicConfig,
+    pub handler: Arc<dyn TopicHandler>,
+    /// latest accepted payloads, by ID
+    kept: LruMap<Hash, Vec<u8>>,
+    /// IDs of the payloads already handled
+    seen: LruMap<Hash, ()>,
+    /// IDs of the kept payloads known by each connected peer
+    known_by_peer: HashMap<PeerId, LruMap<Hash, ()>>,
+    /// payloads each peer is still allowed to send
+    buckets: HashMap<PeerId, TokenBucket>,
+}
+
+impl TopicState {
+    pub fn new(config: TopicConfig, handler: Arc<dyn TopicHandler>) -> Self {
+        let kept_capacity = kept_capacity(&config);
+        TopicState {
+            config,
+            handler,
+            kept: LruMap::new(ByLength::new(kept_capacity)),
+            seen: LruMap::new(ByLength::new(
+                kept_capacity.saturating_mul(SEEN_IDS_PER_KEPT_PAYLOAD),
+            )),
+            known_by_peer: HashMap::new(),
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Replace the settings and handler of the topic, keeping the latest payloads that fit the new settings
+    pub fn update(&mut self, config: TopicConfig, handler: Arc<dyn TopicHandler>) {
+        let mut updated = TopicState::new(config, handler);
+        let kept: Vec<(Hash, Vec<u8>)> = self
+            .kept
+            .iter()
+            .map(|(id, payload)| (*id, payload.clone()))
+            .collect();
+        // the LRU iterates from latest to oldest
+        for (id, payload) in kept.into_iter().rev() {
+            if payload.len() <= config.max_payload_size {
+                updated.keep(id, payload);
+            }
+        }
+        *self = updated;
+    }
+
+    /// Whether the payload was already handled
+    pub fn is_seen(&self, id: &Hash) -> bool {
+        self.seen.peek(id).is_some()
+    }
+
+    /// Remember that the payload was handled without keeping it
+    pub fn mark_seen(&mut self, id: Hash) {
+        self.seen.insert(id, ());
+    }
+
+    /// Keep an accepted payload
+    pub fn keep(&mut self, id: Hash, payload: Vec<u8>) {
+        self.seen.insert(id, ());
+        self.kept.insert(id, payload);
+    }
+
+    /// Kept payload with this ID
+    pub fn get_kept(&self, id: &Hash) -> Option<&Vec<u8>> {
+        self.kept.peek(id)
+    }
+
+    /// IDs of the kept payloads, from latest to oldest
+    pub fn kept_ids(&self) -> Vec<Hash> {
+        self.kept.iter().map(|(id, _)| *id).collect()
+    }
+
+    /// Whether a peer knows a payload
+    pub fn is_known_by_peer(&self, peer_id: &PeerId, id: &Hash) -> bool {
+        self.known_by_peer
+            .get(peer_id)
+            .map_or(false, |known| known.peek(id).is_some())
+    }
+
+    /// Mark payloads as known by a peer
+    pub fn insert_peer_known<'a>(
+        &mut self,
+        peer_id: &PeerId,
+        ids: impl IntoIterator<Item = &'a Hash>,
+    ) {
+        let capacity = kept_capacity(&self.config);
+        let known = self
+            .known_by_peer
+            .entry(*peer_id)
+            .or_insert_with(|| LruMap::new(ByLength::new(capacity)));
+        for id in ids {
+            known.insert(*id, ());
+        }
+    }
+
+    /// Take a token of the rate limit of a peer, return false if the peer exceeded the limit
+    pub fn try_take_token(&mut self, peer_id: &PeerId, now: Instant) -> bool {
+        let limit = self.config.rate_limit;
+        if limit.rate == 0 {
+            return true;
+        }
+        self.buckets
+            .entry(*peer_id)
+            .or_insert_with(|| TokenBucket::full(&limit, now))
+            .try_take(&limit, now)
+    }
+
+    /// Forget the peers that disconnected
+    pub fn update_peers(&mut self, peers_connected: &HashSet<PeerId>) {
+        self.known_by_peer
+            .retain(|peer_id, _| peers_connected.contains(peer_id));
+        self.buckets
+            .retain(|peer_id, _| peers_connected.contains(peer_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use massa_protocol_exports::{
+        MessageRateLimit, PeerId, TopicConfig, TopicPropagation, TopicVerdict,
+    };
+    use massa_signature::KeyPair;
+
+    use super::*;
+
+    struct AcceptAll;
+
+    impl TopicHandler for AcceptAll {
+        fn handle_payload(&self, _from: &PeerId, _payload: &[u8]) -> TopicVerdict {
+            TopicVerdict::Accept
+        }
+    }
+
+    #[test]
+    fn test_topic_state_keeps_latest_payloads_and_limits_peers() {
+        let config = TopicConfig {
+            topic: 1,
+            max_payload_size: 16,
+            max_kept_payloads: 2,
+            rate_limit: MessageRateLimit { rate: 1, burst: 2 },
+            propagation: TopicPropagation::Flood,
+        };
+        let mut state = TopicState::new(config, Arc::new(AcceptAll));
+        let ids: Vec<Hash> = (0..3u8).map(|i| payload_id(1, &[i])).collect();
+        assert_ne!(payload_id(1, &[0]), payload_id(2, &[0]));
+        for (i, id) in ids.iter().enumerate() {
+            state.keep(*id, vec![i as u8]);
+        }
+        // only the latest payloads are kept, the older ones are still known as handled
+        assert_eq!(state.kept_ids(), vec![ids[2], ids[1]]);
+        assert!(state.get_kept(&ids[0]).is_none());
+        assert!(state.is_seen(&ids[0]));
+
+        let peer_id = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+        state.insert_peer_known(&peer_id, [&ids[1]]);
+        assert!(state.is_known_by_peer(&peer_id, &ids[1]));
+        assert!(!state.is_known_by_peer(&peer_id, &ids[2]));
+
+        let now = Instant::now();
+        assert!(state.try_take_token(&peer_id, now));
+        assert!(state.try_take_token(&peer_id, now));
+        assert!(!state.try_take_token(&peer_id, now));
+        assert!(state.try_take_token(&peer_id, now + Duration::from_secs(1)));
+
+        state.update_peers(&HashSet::new());
+        assert!(!state.is_known_by_peer(&peer_id, &ids[1]));
+    }
+}
@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use massa_protocol_exports::{TopicConfig, TopicHandler, TopicId};
+
+#[derive(Clone)]
+pub enum TopicHandlerCommand {
+    Stop,
+    /// Register a topic, or replace the settings and handler of a registered one
+    Register {
+        config: TopicConfig,
+        handler: Arc<dyn TopicHandler>,
+    },
+    /// Publish a payload on a registered topic
+    Publish {
+        topic: TopicId,
+        payload: Vec<u8>,
+    },
+}
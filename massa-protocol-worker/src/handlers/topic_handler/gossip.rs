@@ -0,0 +1,410 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    thread::JoinHandle,
+    time::Instant,
+};
+
+use crossbeam::{channel::tick, select};
+use massa_channel::{receiver::MassaReceiver, sender::MassaSender};
+use massa_hash::Hash;
+use massa_protocol_exports::{
+    PeerId, ProtocolConfig, TopicConfig, TopicHandler, TopicId, TopicPropagation, TopicVerdict,
+};
+use massa_serialization::{DeserializeError, Deserializer};
+use rand::{seq::SliceRandom, thread_rng};
+use tracing::{debug, info, warn};
+
+use crate::{
+    capabilities::Capabilities,
+    handlers::peer_handler::{
+        models::{PeerManagementCmd, PeerMessageTuple},
+        reputation::{report_misbehavior, Misbehavior},
+    },
+    messages::{DeserializationFailure, MessageHandlingTimer, MessageTypeId, MessagesSerializer},
+    wrap_network::ActiveConnectionsTrait,
+};
+
+use super::{
+    cache::{payload_id, TopicState},
+    commands::TopicHandlerCommand,
+    messages::{
+        TopicMessage, TopicMessageDeserializer, TopicMessageDeserializerArgs,
+        TopicMessageSerializer,
+    },
+};
+
+pub struct GossipThread {
+    receiver: MassaReceiver<PeerMessageTuple>,
+    receiver_ext: MassaReceiver<TopicHandlerCommand>,
+    peer_cmd_sender: MassaSender<PeerManagementCmd>,
+    active_connections: Box<dyn ActiveConnectionsTrait>,
+    config: ProtocolConfig,
+    topics: HashMap<TopicId, TopicState>,
+    topic_message_serializer: MessagesSerializer,
+    topic_message_deserializer: TopicMessageDeserializer,
+}
+
+impl GossipThread {
+    fn run(&mut self) {
+        let tick_anti_entropy = tick(self.config.topic_anti_entropy_interval.to_duration());
+        loop {
+            select! {
+                recv(self.receiver) -> msg => {
+                    self.receiver.update_metrics();
+                    match msg {
+                        Ok((peer_id, message, queued_at)) => {
+                            let _handling_timer = MessageHandlingTimer::start(MessageTypeId::Topic.family(), queued_at);
+                            self.process_message(peer_id, message);
+                        }
+                        Err(_) => {
+                            info!("Stop topic gossip thread");
+                            return;
+                        }
+                    }
+                },
+                recv(self.receiver_ext) -> msg => {
+                    self.receiver_ext.update_metrics();
+                    match msg {
+                        Ok(TopicHandlerCommand::Register { config, handler }) => {
+                            self.register_topic(config, handler);
+                        }
+                        Ok(TopicHandlerCommand::Publish { topic, payload }) => {
+                            self.publish(topic, payload);
+                        }
+                        Ok(TopicHandlerCommand::Stop) | Err(_) => {
+                            info!("Stop topic gossip thread");
+                            return;
+                        }
+                    }
+                },
+                recv(tick_anti_entropy) -> _ => {
+                    self.send_digests();
+                }
+            }
+        }
+    }
+
+    /// Register a topic, capping its payload size to the one of the protocol
+    fn register_topic(&mut self, mut config: TopicConfig, handler: Arc<dyn TopicHandler>) {
+        config.max_payload_size = config
+            .max_payload_size
+            .min(self.config.max_topic_payload_size);
+        match self.topics.get_mut(&config.topic) {
+            Some(state) => state.update(config, handler),
+            None => {
+                self.topics
+                    .insert(config.topic, TopicState::new(config, handler));
+            }
+        }
+    }
+
+    /// Keep a payload published by our node and propagate it to the peers
+    fn publish(&mut self, topic: TopicId, payload: Vec<u8>) {
+        let Some(state) = self.topics.get_mut(&topic) else {
+            warn!("cannot publish on topic {}: it is not registered", topic);
+            return;
+        };
+        if payload.len() > state.config.max_payload_size {
+            warn!(
+                "cannot publish a payload of {} bytes on topic {}: the limit is {} bytes",
+                payload.len(),
+                topic,
+                state.config.max_payload_size
+            );
+            return;
+        }
+        let id = payload_id(topic, &payload);
+        if state.is_seen(&id) {
+            return;
+        }
+        state.keep(id, payload);
+        self.propagate(topic, &[id]);
+    }
+
+    /// Process incoming message
+    fn process_message(&mut self, peer_id: PeerId, message: Vec<u8>) {
+        let (rest, message) = match self
+            .topic_message_deserializer
+            .deserialize::<DeserializeError>(&message)
+        {
+            Ok((rest, message)) => (rest, message),
+            Err(err) => {
+                DeserializationFailure::Malformed.record(MessageTypeId::Topic.family(), &peer_id);
+                self.report(&peer_id, Misbehavior::InvalidMessage);
+                debug!(
+                    "Error while deserializing message from peer {} err: {:?}",
+                    peer_id, err
+                );
+                return;
+            }
+        };
+        if !rest.is_empty() {
+            DeserializationFailure::TrailingBytes.record(MessageTypeId::Topic.family(), &peer_id);
+            self.report(&peer_id, Misbehavior::InvalidMessage);
+            debug!("Message not fully consumed");
+            return;
+        }
+        match message {
+            TopicMessage::Payloads { topic, payloads } => {
+                self.note_payloads_from_peer(&peer_id, topic, payloads);
+            }
+            TopicMessage::Digest { topic, ids } => {
+                // topics registered by other subsystems than ours are ignored
+                let Some(state) = self.topics.get_mut(&topic) else {
+                    return;
+                };
+                state.insert_peer_known(&peer_id, ids.iter());
+                let missing: Vec<Hash> = ids.into_iter().filter(|id| !state.is_seen(id)).collect();
+                if missing.is_empty() {
+                    return;
+                }
+                if let Err(err) = self.active_connections.send_to_peer(
+                    &peer_id,
+                    &self.topic_message_serializer,
+                    TopicMessage::AskForPayloads {
+                        topic,
+                        ids: missing,
+                    }
+                    .into(),
+                    false,
+                ) {
+                    warn!("Failed to send AskForPayloads message to peer: {}", err);
+                }
+            }
+            TopicMessage::AskForPayloads { topic, ids } => {
+                self.send_payloads(&peer_id, topic, &ids);
+            }
+        }
+    }
+
+    /// Pass the payloads received from a peer to the handler of their topic, and propagate the accepted ones
+    fn note_payloads_from_peer(
+        &mut self,
+        peer_id: &PeerId,
+        topic: TopicId,
+        payloads: Vec<Vec<u8>>,
+    ) {
+        let Some(state) = self.topics.get_mut(&topic) else {
+            return;
+        };
+        let now = Instant::now();
+        let mut accepted = Vec::new();
+        let mut misbehavior = None;
+        for payload in payloads {
+            if payload.len() > state.config.max_payload_size {
+                misbehavior = Some(Misbehavior::InvalidMessage);
+                break;
+            }
+            let id = payload_id(topic, &payload);
+            state.insert_peer_known(peer_id, [&id]);
+            if state.is_seen(&id) {
+                continue;
+            }
+            // payloads above the limit are dropped without being marked as handled,
+            // so that they can be fetched again from the digests of the other peers
+            if !state.try_take_token(peer_id, now) {
+                debug!(
+                    "dropping payload of topic {} from peer {}: rate limit exceeded",
+                    topic, peer_id
+                );
+                continue;
+            }
+            match state.handler.handle_payload(peer_id, &payload) {
+                TopicVerdict::Accept => {
+                    state.keep(id, payload);
+                    accepted.push(id);
+                }
+                TopicVerdict::Ignore => state.mark_seen(id),
+                TopicVerdict::Reject => {
+                    state.mark_seen(id);
+                    misbehavior = Some(Misbehavior::InvalidMessage);
+                    break;
+                }
+            }
+        }
+        if let Some(misbehavior) = misbehavior {
+            debug!(
+                "peer {} sent an invalid payload on topic {}",
+                peer_id, topic
+            );
+            self.report(peer_id, misbehavior);
+        }
+        if !accepted.is_empty() {
+            self.propagate(topic, &accepted);
+        }
+    }
+
+    /// Send the kept payloads of a topic with these IDs to a peer, batched up to the maximum payload size
+    fn send_payloads(&mut self, peer_id: &PeerId, topic: TopicId, ids: &[Hash]) {
+        let Some(state) = self.topics.get_mut(&topic) else {
+            return;
+        };
+        let mut batches: Vec<(Vec<Hash>, Vec<Vec<u8>>)> = Vec::new();
+        let mut batch_size = 0;
+        for id in ids {
+            let Some(payload) = state.get_kept(id) else {
+                continue;
+            };
+            let full = batches.last().map_or(true, |(batch_ids, _)| {
+                batch_ids.len() as u64 >= self.config.max_topic_items_per_message
+                    || batch_size + payload.len() > self.config.max_topic_payload_size
+            });
+            if full {
+                batches.push((Vec::new(), Vec::new()));
+                batch_size = 0;
+            }
+            let (batch_ids, batch_payloads) = batches.last_mut().expect("batch just pushed");
+            batch_ids.push(*id);
+            batch_payloads.push(payload.clone());
+            batch_size += payload.len();
+        }
+        for (batch_ids, payloads) in batches {
+            if let Err(err) = self.active_connections.send_to_peer(
+                peer_id,
+                &self.topic_message_serializer,
+                TopicMessage::Payloads { topic, payloads }.into(),
+                false,
+            ) {
+                warn!("Failed to send Payloads message to peer: {}", err);
+                return;
+            }
+            state.insert_peer_known(peer_id, batch_ids.iter());
+        }
+    }
+
+    /// Connected peers that understand the topic messages
+    fn topic_peers(&self) -> HashSet<PeerId> {
+        self.active_connections
+            .get_peer_ids_connected()
+            .into_iter()
+            .filter(|peer_id| {
+                self.active_connections
+                    .get_peer_capabilities(peer_id)
+                    .map_or(false, |negotiated| {
+                        negotiated.supports(Capabilities::TOPICS)
+                    })
+            })
+            .collect()
+    }
+
+    /// Propagate kept payloads to the peers that do not know them, following the propagation policy of the topic
+    fn propagate(&mut self, topic: TopicId, ids: &[Hash]) {
+        let topic_peers = self.topic_peers();
+        let Some(state) = self.topics.get(&topic) else {
+            return;
+        };
+        let mut targets: Vec<(PeerId, Vec<Hash>)> = topic_peers
+            .into_iter()
+            .filter_map(|peer_id| {
+                let to_send: Vec<Hash> = ids
+                    .iter()
+                    .filter(|id| !state.is_known_by_peer(&peer_id, id))
+                    .copied()
+                    .collect();
+                (!to_send.is_empty()).then_some((peer_id, to_send))
+            })
+            .collect();
+        let propagation = state.config.propagation;
+        if let TopicPropagation::Fanout(fanout) = propagation {
+            targets.shuffle(&mut thread_rng());
+            targets.truncate(fanout);
+        }
+        for (peer_id, to_send) in targets {
+            match propagation {
+                TopicPropagation::Flood | TopicPropagation::Fanout(_) => {
+                    self.send_payloads(&peer_id, topic, &to_send);
+                }
+                TopicPropagation::Announce => {
+                    let max_ids = (self.config.max_topic_items_per_message as usize).max(1);
+                    for ids in to_send.chunks(max_ids) {
+                        if let Err(err) = self.active_connections.send_to_peer(
+                            &peer_id,
+                            &self.topic_message_serializer,
+                            TopicMessage::Digest {
+                                topic,
+                                ids: ids.to_vec(),
+                            }
+                            .into(),
+                            false,
+                        ) {
+                            warn!("Failed to send topic Digest message to peer: {}", err);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Anti-entropy: send to each peer the IDs of the kept payloads it is not known to have,
+    /// so that it asks for the ones it missed
+    fn send_digests(&mut self) {
+        let peers_connected = self.active_connections.get_peer_ids_connected();
+        let topic_peers = self.topic_peers();
+        let max_ids = self.config.max_topic_items_per_message as usize;
+        for (topic, state) in self.topics.iter_mut() {
+            state.update_peers(&peers_connected);
+            let kept_ids = state.kept_ids();
+            for peer_id in topic_peers.iter() {
+                let ids: Vec<Hash> = kept_ids
+                    .iter()
+                    .filter(|id| !state.is_known_by_peer(peer_id, id))
+                    .take(max_ids)
+                    .copied()
+                    .collect();
+                if ids.is_empty() {
+                    continue;
+                }
+                if let Err(err) = self.active_connections.send_to_peer(
+                    peer_id,
+                    &self.topic_message_serializer,
+                    TopicMessage::Digest { topic: *topic, ids }.into(),
+                    false,
+                ) {
+                    warn!("Failed to send topic Digest message to peer: {}", err);
+                }
+            }
+        }
+    }
+
+    fn report(&self, peer_id: &PeerId, misbehavior: Misbehavior) {
+        report_misbehavior(
+            &self.peer_cmd_sender,
+            peer_id,
+            MessageTypeId::Topic.family(),
+            misbehavior,
+        );
+    }
+}
+
+pub fn start_gossip_thread(
+    receiver: MassaReceiver<PeerMessageTuple>,
+    receiver_ext: MassaReceiver<TopicHandlerCommand>,
+    peer_cmd_sender: MassaSender<PeerManagementCmd>,
+    active_connections: Box<dyn ActiveConnectionsTrait>,
+    config: ProtocolConfig,
+) -> JoinHandle<()> {
+    let topic_message_deserializer = TopicMessageDeserializer::new(TopicMessageDeserializerArgs {
+        max_payload_size: config.max_topic_payload_size,
+        max_items_per_message: config.max_topic_items_per_message,
+    });
+    std::thread::Builder::new()
+        .name("protocol-topic-handler-gossip".to_string())
+        .spawn(move || {
+            let topic_message_serializer = MessagesSerializer::new()
+                .with_topic_message_serializer(TopicMessageSerializer::new());
+            let mut gossip_thread = GossipThread {
+                receiver,
+                receiver_ext,
+                peer_cmd_sender,
+                active_connections,
+                config,
+                topics: HashMap::new(),
+                topic_message_serializer,
+                topic_message_deserializer,
+            };
+            gossip_thread.run();
+        })
+        .expect("OS failed to start topic gossip thread")
+}
@@ -0,0 +1,258 @@
+use massa_hash::{Hash, HashDeserializer, HashSerializer};
+use massa_models::serialization::{VecU8Deserializer, VecU8Serializer};
+use massa_protocol_exports::TopicId;
+use massa_serialization::{
+    Deserializer, SerializeError, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
+};
+use nom::{
+    error::{context, ContextError, ParseError},
+    multi::length_count,
+    sequence::tuple,
+    IResult, Parser,
+};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use std::ops::Bound::Included;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopicMessage {
+    /// Payloads published on a topic
+    Payloads {
+        topic: TopicId,
+        payloads: Vec<Vec<u8>>,
+    },
+    /// IDs of the latest payloads the sender kept for a topic
+    Digest { topic: TopicId, ids: Vec<Hash> },
+    /// Ask for the payloads of a topic with these IDs
+    AskForPayloads { topic: TopicId, ids: Vec<Hash> },
+}
+
+#[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
+#[repr(u64)]
+pub enum MessageTypeId {
+    Payloads = 0,
+    Digest = 1,
+    AskForPayloads = 2,
+}
+
+impl From<&TopicMessage> for MessageTypeId {
+    fn from(message: &TopicMessage) -> Self {
+        match message {
+            TopicMessage::Payloads { .. } => MessageTypeId::Payloads,
+            TopicMessage::Digest { .. } => MessageTypeId::Digest,
+            TopicMessage::AskForPayloads { .. } => MessageTypeId::AskForPayloads,
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct TopicMessageSerializer {
+    u64_serializer: U64VarIntSerializer,
+    payload_serializer: VecU8Serializer,
+    hash_serializer: HashSerializer,
+}
+
+impl TopicMessageSerializer {
+    pub fn new() -> Self {
+        Self {
+            u64_serializer: U64VarIntSerializer::new(),
+            payload_serializer: VecU8Serializer::new(),
+            hash_serializer: HashSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<TopicMessage> for TopicMessageSerializer {
+    fn serialize(&self, value: &TopicMessage, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        self.u64_serializer.serialize(
+            &MessageTypeId::from(value).try_into().map_err(|_| {
+                SerializeError::GeneralError(String::from("Failed to serialize id"))
+            })?,
+            buffer,
+        )?;
+        match value {
+            TopicMessage::Payloads { topic, payloads } => {
+                self.u64_serializer.serialize(topic, buffer)?;
+                self.u64_serializer
+                    .serialize(&(payloads.len() as u64), buffer)?;
+                for payload in payloads {
+                    self.payload_serializer.serialize(payload, buffer)?;
+                }
+            }
+            TopicMessage::Digest { topic, ids } | TopicMessage::AskForPayloads { topic, ids } => {
+                self.u64_serializer.serialize(topic, buffer)?;
+                self.u64_serializer.serialize(&(ids.len() as u64), buffer)?;
+                for id in ids {
+                    self.hash_serializer.serialize(id, buffer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct TopicMessageDeserializerArgs {
+    pub max_payload_size: usize,
+    pub max_items_per_message: u64,
+}
+
+pub struct TopicMessageDeserializer {
+    id_deserializer: U64VarIntDeserializer,
+    topic_deserializer: U64VarIntDeserializer,
+    length_deserializer: U64VarIntDeserializer,
+    payload_deserializer: VecU8Deserializer,
+    hash_deserializer: HashDeserializer,
+}
+
+impl TopicMessageDeserializer {
+    pub fn new(args: TopicMessageDeserializerArgs) -> Self {
+        Self {
+            id_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+            topic_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+            length_deserializer: U64VarIntDeserializer::new(
+                Included(0),
+                Included(args.max_items_per_message),
+            ),
+            payload_deserializer: VecU8Deserializer::new(
+                Included(0),
+                Included(args.max_payload_size as u64),
+            ),
+            hash_deserializer: HashDeserializer::new(),
+        }
+    }
+
+    fn deserialize_ids<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], (TopicId, Vec<Hash>), E> {
+        tuple((
+            context("Failed topic deserialization", |input| {
+                self.topic_deserializer.deserialize(input)
+            }),
+            length_count(
+                context("Failed length deserialization", |input| {
+                    self.length_deserializer.deserialize(input)
+                }),
+                context("Failed payload id deserialization", |input| {
+                    self.hash_deserializer.deserialize(input)
+                }),
+            ),
+        ))
+        .parse(buffer)
+    }
+}
+
+impl Deserializer<TopicMessage> for TopicMessageDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], TopicMessage, E> {
+        context("Failed TopicMessage deserialization", |buffer| {
+            let (buffer, raw_id) = self.id_deserializer.deserialize(buffer)?;
+            let id = MessageTypeId::try_from(raw_id).map_err(|_| {
+                nom::Err::Error(ParseError::from_error_kind(
+                    buffer,
+                    nom::error::ErrorKind::Eof,
+                ))
+            })?;
+            match id {
+                MessageTypeId::Payloads => context(
+                    "Failed Payloads deserialization",
+                    tuple((
+                        context("Failed topic deserialization", |input| {
+                            self.topic_deserializer.deserialize(input)
+                        }),
+                        length_count(
+                            context("Failed length deserialization", |input| {
+                                self.length_deserializer.deserialize(input)
+                            }),
+                            context("Failed payload deserialization", |input| {
+                                self.payload_deserializer.deserialize(input)
+                            }),
+                        ),
+                    )),
+                )
+                .map(|(topic, payloads)| TopicMessage::Payloads { topic, payloads })
+                .parse(buffer),
+                MessageTypeId::Digest => context("Failed Digest deserialization", |input| {
+                    self.deserialize_ids(input)
+                })
+                .map(|(topic, ids)| TopicMessage::Digest { topic, ids })
+                .parse(buffer),
+                MessageTypeId::AskForPayloads => {
+                    context("Failed AskForPayloads deserialization", |input| {
+                        self.deserialize_ids(input)
+                    })
+                    .map(|(topic, ids)| TopicMessage::AskForPayloads { topic, ids })
+                    .parse(buffer)
+                }
+            }
+        })
+        .parse(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_hash::Hash;
+    use massa_serialization::{DeserializeError, Deserializer, Serializer};
+
+    use super::{
+        TopicMessage, TopicMessageDeserializer, TopicMessageDeserializerArgs,
+        TopicMessageSerializer,
+    };
+
+    fn deserializer() -> TopicMessageDeserializer {
+        TopicMessageDeserializer::new(TopicMessageDeserializerArgs {
+            max_payload_size: 8,
+            max_items_per_message: 2,
+        })
+    }
+
+    #[test]
+    fn test_topic_messages_roundtrip() {
+        let ids = vec![Hash::compute_from(b"a"), Hash::compute_from(b"b")];
+        let messages = [
+            TopicMessage::Payloads {
+                topic: u64::MAX,
+                payloads: vec![vec![], vec![1; 8]],
+            },
+            TopicMessage::Digest {
+                topic: 3,
+                ids: ids.clone(),
+            },
+            TopicMessage::AskForPayloads { topic: 0, ids },
+        ];
+        for message in messages {
+            let mut buffer = Vec::new();
+            TopicMessageSerializer::new()
+                .serialize(&message, &mut buffer)
+                .expect("Failed to serialize message");
+            let (rest, deserialized) = deserializer()
+                .deserialize::<DeserializeError>(&buffer)
+                .expect("Failed to deserialize message");
+            assert!(rest.is_empty());
+            assert_eq!(deserialized, message);
+        }
+    }
+
+    #[test]
+    fn test_topic_messages_limits() {
+        let too_large = TopicMessage::Payloads {
+            topic: 1,
+            payloads: vec![vec![1; 9]],
+        };
+        let too_many = TopicMessage::Digest {
+            topic: 1,
+            ids: vec![Hash::compute_from(b"a"); 3],
+        };
+        for message in [too_large, too_many] {
+            let mut buffer = Vec::new();
+            TopicMessageSerializer::new()
+                .serialize(&message, &mut buffer)
+                .expect("Failed to serialize message");
+            deserializer()
+                .deserialize::<DeserializeError>(&buffer)
+                .expect_err("Should fail because the message exceeds the limits");
+        }
+    }
+}
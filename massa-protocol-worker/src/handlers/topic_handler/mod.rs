@@ -0,0 +1,51 @@
+use std::thread::JoinHandle;
+
+use massa_channel::{receiver::MassaReceiver, sender::MassaSender};
+use massa_protocol_exports::ProtocolConfig;
+
+use crate::wrap_network::ActiveConnectionsTrait;
+
+use self::{commands::TopicHandlerCommand, gossip::start_gossip_thread};
+
+pub mod cache;
+pub mod commands;
+mod gossip;
+mod messages;
+
+pub(crate) use messages::{TopicMessage, TopicMessageSerializer};
+
+use super::peer_handler::models::{PeerManagementCmd, PeerMessageTuple};
+
+/// Gossips the payloads of the topics registered by other subsystems
+pub struct TopicGossipHandler {
+    pub topic_gossip_thread: Option<(MassaSender<TopicHandlerCommand>, JoinHandle<()>)>,
+}
+
+impl TopicGossipHandler {
+    pub fn new(
+        config: ProtocolConfig,
+        active_connections: Box<dyn ActiveConnectionsTrait>,
+        receiver_network: MassaReceiver<PeerMessageTuple>,
+        sender_ext: MassaSender<TopicHandlerCommand>,
+        receiver_ext: MassaReceiver<TopicHandlerCommand>,
+        peer_cmd_sender: MassaSender<PeerManagementCmd>,
+    ) -> Self {
+        let topic_gossip_thread = start_gossip_thread(
+            receiver_network,
+            receiver_ext,
+            peer_cmd_sender,
+            active_connections,
+            config,
+        );
+        Self {
+            topic_gossip_thread: Some((sender_ext, topic_gossip_thread)),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some((tx, thread)) = self.topic_gossip_thread.take() {
+            let _ = tx.send(TopicHandlerCommand::Stop);
+            thread.join().unwrap();
+        }
+    }
+}
@@ -1,10 +1,21 @@
+mod bandwidth;
+mod capabilities;
+pub mod capture;
+mod compression;
+mod connection_events;
 mod connectivity;
 mod context;
 mod controller;
+mod dialer;
+mod finality;
 mod handlers;
 mod ip;
 mod manager;
 mod messages;
+mod port_mapping;
+mod proxy_relay;
+mod rate_limit;
+mod sanity;
 mod sig_verifier;
 mod worker;
 mod wrap_network;
@@ -5,16 +5,23 @@ use massa_protocol_exports::ProtocolManager;
 use tracing::info;
 
 use crate::connectivity::ConnectivityCommand;
+use crate::port_mapping::PortMapper;
 
 /// protocol manager used to stop the protocol
 pub struct ProtocolManagerImpl {
     connectivity_thread: Option<(MassaSender<ConnectivityCommand>, JoinHandle<()>)>,
+    /// mappings of the listener ports on the gateway, if enabled
+    port_mapper: Option<PortMapper>,
 }
 
 impl ProtocolManagerImpl {
-    pub fn new(connectivity_thread: (MassaSender<ConnectivityCommand>, JoinHandle<()>)) -> Self {
+    pub fn new(
+        connectivity_thread: (MassaSender<ConnectivityCommand>, JoinHandle<()>),
+        port_mapper: Option<PortMapper>,
+    ) -> Self {
         Self {
             connectivity_thread: Some(connectivity_thread),
+            port_mapper,
         }
     }
 }
@@ -31,5 +38,8 @@ impl ProtocolManager for ProtocolManagerImpl {
                 .join()
                 .expect("connectivity thread panicked on try to join");
         }
+        if let Some(port_mapper) = self.port_mapper.as_mut() {
+            port_mapper.stop();
+        }
     }
 }
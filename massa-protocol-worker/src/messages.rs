@@ -10,8 +10,12 @@ use peernet::{
         MessagesHandler as PeerNetMessagesHandler, MessagesSerializer as PeerNetMessagesSerializer,
     },
 };
+use std::{sync::Arc, time::Instant};
 use tracing::debug;
 
+use crate::bandwidth::{BandwidthBudgets, TrafficPriority};
+use crate::capture::TrafficCapture;
+use crate::compression::{decompress_message, DecompressionError};
 use crate::handlers::{
     block_handler::{BlockMessage, BlockMessageSerializer},
     endorsement_handler::{EndorsementMessage, EndorsementMessageSerializer},
@@ -19,7 +23,9 @@ use crate::handlers::{
     peer_handler::{
         models::PeerMessageTuple, PeerManagementMessage, PeerManagementMessageSerializer,
     },
+    topic_handler::{TopicMessage, TopicMessageSerializer},
 };
+use crate::rate_limit::MessageRateLimiter;
 
 #[derive(Debug)]
 pub enum Message {
@@ -27,6 +33,7 @@ pub enum Message {
     Endorsement(EndorsementMessage),
     Operation(OperationMessage),
     PeerManagement(Box<PeerManagementMessage>),
+    Topic(TopicMessage),
 }
 
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
@@ -36,6 +43,80 @@ pub enum MessageTypeId {
     Endorsement = 1,
     Operation = 2,
     PeerManagement = 3,
+    /// zstd compressed message, see `crate::compression`
+    Compressed = 4,
+    /// payload of an application topic, see `crate::handlers::topic_handler`
+    Topic = 5,
+}
+
+impl MessageTypeId {
+    /// Name of the message family, used to label the (de)serialization failure metrics
+    pub(crate) fn family(&self) -> &'static str {
+        match self {
+            MessageTypeId::Block => "block",
+            MessageTypeId::Endorsement => "endorsement",
+            MessageTypeId::Operation => "operation",
+            MessageTypeId::PeerManagement => "peer_management",
+            MessageTypeId::Compressed => "compressed",
+            MessageTypeId::Topic => "topic",
+        }
+    }
+}
+
+/// Records, when dropped, the time spent by a handler processing a received message
+pub(crate) struct MessageHandlingTimer {
+    family: &'static str,
+    start: Instant,
+}
+
+impl MessageHandlingTimer {
+    /// Record the time the message of the given family waited in the queue of its handler, and start timing its handling
+    pub(crate) fn start(family: &'static str, queued_at: Instant) -> Self {
+        let start = Instant::now();
+        massa_metrics::observe_protocol_message_queue_wait(
+            family,
+            start.saturating_duration_since(queued_at),
+        );
+        MessageHandlingTimer { family, start }
+    }
+}
+
+impl Drop for MessageHandlingTimer {
+    fn drop(&mut self) {
+        massa_metrics::observe_protocol_message_handling(self.family, self.start.elapsed());
+    }
+}
+
+/// Family of the message type ID wrapping every message, used to label the deserialization failure metrics
+pub(crate) const ENVELOPE_FAMILY: &str = "envelope";
+
+/// Family of the handshake messages, used to label the deserialization failure metrics
+pub(crate) const HANDSHAKE_FAMILY: &str = "handshake";
+
+/// Kind of failure to deserialize a message received from a peer
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum DeserializationFailure {
+    /// Unknown message type
+    InvalidMessageType,
+    /// The message could not be parsed
+    Malformed,
+    /// The message was parsed but bytes were left
+    TrailingBytes,
+    /// The message is larger than allowed once decompressed
+    TooLarge,
+}
+
+impl DeserializationFailure {
+    /// Count the failure to deserialize a message of the given family received from a peer
+    pub(crate) fn record(self, family: &'static str, peer_id: &PeerId) {
+        let kind = match self {
+            DeserializationFailure::InvalidMessageType => "invalid_message_type",
+            DeserializationFailure::Malformed => "malformed",
+            DeserializationFailure::TrailingBytes => "trailing_bytes",
+            DeserializationFailure::TooLarge => "too_large",
+        };
+        massa_metrics::inc_protocol_deserialization_failure(family, kind, &peer_id.to_string());
+    }
 }
 
 impl From<&Message> for MessageTypeId {
@@ -45,6 +126,7 @@ impl From<&Message> for MessageTypeId {
             Message::Endorsement(_) => MessageTypeId::Endorsement,
             Message::Operation(_) => MessageTypeId::Operation,
             Message::PeerManagement(_) => MessageTypeId::PeerManagement,
+            Message::Topic(_) => MessageTypeId::Topic,
         }
     }
 }
@@ -74,6 +156,12 @@ impl From<PeerManagementMessage> for Message {
     }
 }
 
+impl From<TopicMessage> for Message {
+    fn from(message: TopicMessage) -> Self {
+        Self::Topic(message)
+    }
+}
+
 #[derive(Clone)]
 pub struct MessagesSerializer {
     id_serializer: U64VarIntSerializer,
@@ -81,6 +169,7 @@ pub struct MessagesSerializer {
     operation_message_serializer: Option<OperationMessageSerializer>,
     endorsement_message_serializer: Option<EndorsementMessageSerializer>,
     peer_management_message_serializer: Option<PeerManagementMessageSerializer>,
+    topic_message_serializer: Option<TopicMessageSerializer>,
 }
 
 impl Default for MessagesSerializer {
@@ -97,6 +186,7 @@ impl MessagesSerializer {
             operation_message_serializer: None,
             endorsement_message_serializer: None,
             peer_management_message_serializer: None,
+            topic_message_serializer: None,
         }
     }
 
@@ -131,11 +221,19 @@ impl MessagesSerializer {
         self.peer_management_message_serializer = Some(peer_management_message_serializer);
         self
     }
+
+    pub fn with_topic_message_serializer(
+        mut self,
+        topic_message_serializer: TopicMessageSerializer,
+    ) -> Self {
+        self.topic_message_serializer = Some(topic_message_serializer);
+        self
+    }
 }
 
-impl PeerNetMessagesSerializer<Message> for MessagesSerializer {
+impl MessagesSerializer {
     /// Serialize the message
-    fn serialize(&self, message: &Message, buffer: &mut Vec<u8>) -> PeerNetResult<()> {
+    fn serialize_message(&self, message: &Message, buffer: &mut Vec<u8>) -> PeerNetResult<()> {
         self.id_serializer
             .serialize(
                 &MessageTypeId::from(message).try_into().map_err(|_| {
@@ -213,7 +311,40 @@ impl PeerNetMessagesSerializer<Message> for MessagesSerializer {
                     ))
                 }
             }
+            Message::Topic(message) => {
+                if let Some(serializer) = &self.topic_message_serializer {
+                    serializer.serialize(message, buffer).map_err(|err| {
+                        PeerNetError::HandlerError.error(
+                            "MessagesSerializer",
+                            Some(format!("Failed to serialize message: {}", err)),
+                        )
+                    })
+                } else {
+                    Err(PeerNetError::HandlerError.error(
+                        "MessagesSerializer",
+                        Some("TopicMessageSerializer not initialized".to_string()),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl PeerNetMessagesSerializer<Message> for MessagesSerializer {
+    /// Serialize the message, recording its size and counting failures by message family
+    fn serialize(&self, message: &Message, buffer: &mut Vec<u8>) -> PeerNetResult<()> {
+        let start_len = buffer.len();
+        let family = MessageTypeId::from(message).family();
+        let result = self.serialize_message(message, buffer);
+        match result {
+            Ok(()) => massa_metrics::observe_protocol_message_size(
+                family,
+                "out",
+                buffer.len().saturating_sub(start_len),
+            ),
+            Err(_) => massa_metrics::inc_protocol_serialization_failure(family),
         }
+        result
     }
 }
 
@@ -224,59 +355,143 @@ pub struct MessagesHandler {
     pub sender_endorsements: MassaSender<PeerMessageTuple>,
     pub sender_operations: MassaSender<PeerMessageTuple>,
     pub sender_peers: MassaSender<PeerMessageTuple>,
+    pub sender_topics: MassaSender<PeerMessageTuple>,
+    /// records inbound messages when traffic capture is enabled
+    pub capture: Option<Arc<TrafficCapture>>,
+    /// maximum size of a decompressed message, `None` if compression is disabled
+    pub max_decompressed_message_size: Option<usize>,
+    /// drops the messages of the peers exceeding their rate limits, `None` if rate limiting is disabled
+    pub rate_limiter: Option<Arc<MessageRateLimiter>>,
+    /// drops the messages exceeding the download budget of the category of their peer, `None` if no budget is enabled
+    pub bandwidth_budgets: Option<Arc<BandwidthBudgets>>,
 }
 
 impl PeerNetMessagesHandler<PeerId> for MessagesHandler {
     fn handle(&self, data: &[u8], peer_id: &PeerId) -> PeerNetResult<()> {
+        if let Some(capture) = &self.capture {
+            capture.record(data, peer_id);
+        }
+        self.dispatch(data, peer_id, true)
+    }
+}
+
+impl MessagesHandler {
+    /// Send a message to the channel of its handler, decompressing it first if needed.
+    /// A compressed message cannot wrap another compressed message.
+    fn dispatch(&self, data: &[u8], peer_id: &PeerId, allow_compressed: bool) -> PeerNetResult<()> {
         let (data, raw_id) = self
             .id_deserializer
             .deserialize::<DeserializeError>(data)
             .map_err(|err| {
+                DeserializationFailure::Malformed.record(ENVELOPE_FAMILY, peer_id);
                 PeerNetError::HandlerError.error(
                     "MessagesHandler",
                     Some(format!("Failed to deserialize message type id: {}", err)),
                 )
             })?;
         let id = MessageTypeId::try_from(raw_id).map_err(|_| {
+            DeserializationFailure::InvalidMessageType.record(ENVELOPE_FAMILY, peer_id);
             PeerNetError::HandlerError.error(
                 "MessagesHandler",
                 Some(String::from("Invalid message type id")),
             )
         })?;
+        massa_metrics::observe_protocol_message_size(id.family(), "in", data.len());
+        if let Some(rate_limiter) = &self.rate_limiter {
+            if !rate_limiter.allow(&id, data, peer_id) {
+                return Ok(());
+            }
+        }
+        if let Some(bandwidth_budgets) = &self.bandwidth_budgets {
+            // decompressed messages were already counted in their compressed form
+            if allow_compressed {
+                let category = self
+                    .rate_limiter
+                    .as_ref()
+                    .and_then(|rate_limiter| rate_limiter.peer_category(peer_id));
+                let priority = TrafficPriority::of_received(&id, data);
+                if !bandwidth_budgets.allow_download(category.as_deref(), priority, data.len()) {
+                    return Ok(());
+                }
+            }
+        }
         match id {
             // Blocks are high-priority: we block if the channel is full.
             // This means that the sender will be blocked until the message is sent.
-            MessageTypeId::Block => {
-                self.sender_blocks
-                    .send((*peer_id, data.to_vec()))
-                    .map_err(|err| {
-                        PeerNetError::HandlerError.error(
-                            "MessagesHandler",
-                            Some(format!("Failed to send block message to channel: {}", err)),
-                        )
-                    })
-            }
+            MessageTypeId::Block => self
+                .sender_blocks
+                .send((*peer_id, data.to_vec(), Instant::now()))
+                .map_err(|err| {
+                    PeerNetError::HandlerError.error(
+                        "MessagesHandler",
+                        Some(format!("Failed to send block message to channel: {}", err)),
+                    )
+                }),
             // Endorsements are low priority: we just drop the message if the channel is full
             MessageTypeId::Endorsement => {
-                if let Err(err) = self.sender_endorsements.try_send((*peer_id, data.to_vec())) {
+                if let Err(err) =
+                    self.sender_endorsements
+                        .try_send((*peer_id, data.to_vec(), Instant::now()))
+                {
                     debug!("Failed to send endorsement message to channel: {}", err)
                 }
                 Ok(())
             }
             // Operations are low priority: we just drop the message if the channel is full
             MessageTypeId::Operation => {
-                if let Err(err) = self.sender_operations.try_send((*peer_id, data.to_vec())) {
+                if let Err(err) =
+                    self.sender_operations
+                        .try_send((*peer_id, data.to_vec(), Instant::now()))
+                {
                     debug!("Failed to send operation message to channel: {}", err)
                 }
                 Ok(())
             }
             // Peer management messages are low priority: we just drop the message if the channel is full
             MessageTypeId::PeerManagement => {
-                if let Err(err) = self.sender_peers.try_send((*peer_id, data.to_vec())) {
+                if let Err(err) =
+                    self.sender_peers
+                        .try_send((*peer_id, data.to_vec(), Instant::now()))
+                {
                     debug!("Failed to send peer message to channel: {}", err)
                 }
                 Ok(())
             }
+            // Topic payloads are low priority: we just drop the message if the channel is full,
+            // the anti-entropy digests let us fetch the payloads we missed
+            MessageTypeId::Topic => {
+                if let Err(err) =
+                    self.sender_topics
+                        .try_send((*peer_id, data.to_vec(), Instant::now()))
+                {
+                    debug!("Failed to send topic message to channel: {}", err)
+                }
+                Ok(())
+            }
+            MessageTypeId::Compressed => {
+                let max_size = match self.max_decompressed_message_size {
+                    Some(max_size) if allow_compressed => max_size,
+                    _ => {
+                        DeserializationFailure::InvalidMessageType.record(ENVELOPE_FAMILY, peer_id);
+                        return Err(PeerNetError::HandlerError.error(
+                            "MessagesHandler",
+                            Some(String::from("Unexpected compressed message")),
+                        ));
+                    }
+                };
+                let decompressed = decompress_message(data, max_size).map_err(|err| {
+                    let failure = match err {
+                        DecompressionError::TooLarge(_) => DeserializationFailure::TooLarge,
+                        DecompressionError::Invalid(_) => DeserializationFailure::Malformed,
+                    };
+                    failure.record(MessageTypeId::Compressed.family(), peer_id);
+                    PeerNetError::HandlerError.error(
+                        "MessagesHandler",
+                        Some(format!("Failed to decompress message: {}", err)),
+                    )
+                })?;
+                self.dispatch(&decompressed, peer_id, false)
+            }
         }
     }
 }
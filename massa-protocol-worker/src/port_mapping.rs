@@ -0,0 +1,305 @@
+//! Mapping of the listener ports on the router of the node, and discovery of its public IP.
+//!
+//! Nodes behind a home router cannot be reached by the other peers unless the router forwards the ports
+//! of their listeners. When enabled, the node asks its gateway to forward them with UPnP IGD, or with NAT-PMP
+//! if no UPnP gateway answers, and gets its public IP from the gateway. That IP is advertised in the
+//! announcements of the node, unless `routable_ip` is set. The mappings are leased: they are renewed
+//! at half of their lease duration, and removed when the node stops.
+
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crossbeam::channel::{bounded, tick, Sender};
+use crossbeam::select;
+use igd_next::{PortMappingProtocol, SearchOptions};
+use ip_rfc::global;
+use massa_protocol_exports::{ProtocolConfig, TransportType};
+use natpmp::{Natpmp, Response};
+use tracing::{debug, info, warn};
+
+/// Description of the mappings, shown by the routers
+const MAPPING_DESCRIPTION: &str = "massa node";
+
+/// Interval at which the NAT-PMP responses are polled
+const NATPMP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Protocol of a mapped port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MappedProtocol {
+    Tcp,
+    Udp,
+}
+
+impl From<TransportType> for MappedProtocol {
+    fn from(transport: TransportType) -> Self {
+        match transport {
+            TransportType::Tcp => MappedProtocol::Tcp,
+            TransportType::Quic => MappedProtocol::Udp,
+        }
+    }
+}
+
+impl From<MappedProtocol> for PortMappingProtocol {
+    fn from(protocol: MappedProtocol) -> Self {
+        match protocol {
+            MappedProtocol::Tcp => PortMappingProtocol::TCP,
+            MappedProtocol::Udp => PortMappingProtocol::UDP,
+        }
+    }
+}
+
+impl From<MappedProtocol> for natpmp::Protocol {
+    fn from(protocol: MappedProtocol) -> Self {
+        match protocol {
+            MappedProtocol::Tcp => natpmp::Protocol::TCP,
+            MappedProtocol::Udp => natpmp::Protocol::UDP,
+        }
+    }
+}
+
+/// Gateway that maps the ports
+enum Gateway {
+    Upnp {
+        gateway: igd_next::Gateway,
+        /// address of the node on the network of the gateway
+        local_ip: IpAddr,
+    },
+    NatPmp(Natpmp),
+}
+
+impl Gateway {
+    /// Find a gateway, trying UPnP first
+    fn search(timeout: Duration) -> Result<Self, String> {
+        let upnp_error = match igd_next::search_gateway(SearchOptions {
+            timeout: Some(timeout),
+            ..Default::default()
+        }) {
+            Ok(gateway) => {
+                let local_ip = local_ip_towards(gateway.addr)
+                    .map_err(|err| format!("could not get the local address: {}", err))?;
+                return Ok(Gateway::Upnp { gateway, local_ip });
+            }
+            Err(err) => err,
+        };
+        debug!("no UPnP gateway found ({}), trying NAT-PMP", upnp_error);
+        Natpmp::new()
+            .map(Gateway::NatPmp)
+            .map_err(|err| format!("no UPnP nor NAT-PMP gateway found: {:?}", err))
+    }
+
+    /// Public IP of the gateway
+    fn external_ip(&mut self, timeout: Duration) -> Result<IpAddr, String> {
+        match self {
+            Gateway::Upnp { gateway, .. } => gateway
+                .get_external_ip()
+                .map_err(|err| format!("UPnP external IP request failed: {}", err)),
+            Gateway::NatPmp(natpmp) => {
+                natpmp
+                    .send_public_address_request()
+                    .map_err(|err| format!("NAT-PMP public address request failed: {:?}", err))?;
+                match natpmp_response(natpmp, timeout)? {
+                    Response::Gateway(response) => Ok(IpAddr::V4(*response.public_address())),
+                    _ => Err("unexpected NAT-PMP response".to_string()),
+                }
+            }
+        }
+    }
+
+    /// Forward `port` of the gateway to the same port of the node, for `lease` (removes the mapping if zero)
+    fn map_port(
+        &mut self,
+        protocol: MappedProtocol,
+        port: u16,
+        lease: Duration,
+        timeout: Duration,
+    ) -> Result<(), String> {
+        let lease_secs = lease.as_secs().try_into().unwrap_or(u32::MAX);
+        match self {
+            Gateway::Upnp { gateway, local_ip } => {
+                let result = if lease_secs == 0 {
+                    gateway
+                        .remove_port(protocol.into(), port)
+                        .map_err(|err| err.to_string())
+                } else {
+                    gateway
+                        .add_port(
+                            protocol.into(),
+                            port,
+                            SocketAddr::new(*local_ip, port),
+                            lease_secs,
+                            MAPPING_DESCRIPTION,
+                        )
+                        .map_err(|err| err.to_string())
+                };
+                result.map_err(|err| format!("UPnP mapping of port {} failed: {}", port, err))
+            }
+            Gateway::NatPmp(natpmp) => {
+                natpmp
+                    .send_port_mapping_request(protocol.into(), port, port, lease_secs)
+                    .map_err(|err| format!("NAT-PMP mapping of port {} failed: {:?}", port, err))?;
+                match natpmp_response(natpmp, timeout)? {
+                    Response::TCP(response) | Response::UDP(response)
+                        if lease_secs == 0 || response.public_port() == port =>
+                    {
+                        Ok(())
+                    }
+                    Response::TCP(response) | Response::UDP(response) => Err(format!(
+                        "NAT-PMP gateway mapped port {} to another public port {}",
+                        port,
+                        response.public_port()
+                    )),
+                    _ => Err("unexpected NAT-PMP response".to_string()),
+                }
+            }
+        }
+    }
+}
+
+/// Address of the node on the interface used to reach `addr`
+fn local_ip_towards(addr: SocketAddr) -> std::io::Result<IpAddr> {
+    let unspecified: IpAddr = match addr {
+        SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = UdpSocket::bind((unspecified, 0))?;
+    socket.connect(addr)?;
+    Ok(socket.local_addr()?.ip())
+}
+
+/// Wait for the response to the last NAT-PMP request
+fn natpmp_response(natpmp: &mut Natpmp, timeout: Duration) -> Result<Response, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match natpmp.read_response_or_retry() {
+            Ok(response) => return Ok(response),
+            Err(natpmp::Error::NATPMP_TRYAGAIN) if Instant::now() < deadline => {
+                thread::sleep(NATPMP_POLL_INTERVAL);
+            }
+            Err(err) => return Err(format!("NAT-PMP request failed: {:?}", err)),
+        }
+    }
+}
+
+/// Port mappings of the listeners of the node, renewed until they are stopped
+pub(crate) struct PortMapper {
+    /// public IP of the gateway
+    external_ip: IpAddr,
+    /// channel to stop the renewal thread, and its handle
+    renewal_thread: Option<(Sender<()>, JoinHandle<()>)>,
+}
+
+impl PortMapper {
+    /// Map the ports of the listeners of the node. Returns `None` if no gateway could map them.
+    pub fn start(config: &ProtocolConfig) -> Option<Self> {
+        let timeout = config.port_mapping_timeout.to_duration();
+        let lease = config.port_mapping_lease_duration.to_duration();
+        let ports: Vec<(MappedProtocol, u16)> = config
+            .listeners
+            .iter()
+            .map(|(addr, transport)| ((*transport).into(), addr.port()))
+            .collect();
+
+        let mut gateway = match Gateway::search(timeout) {
+            Ok(gateway) => gateway,
+            Err(err) => {
+                warn!("port mapping disabled: {}", err);
+                return None;
+            }
+        };
+        let external_ip = match gateway.external_ip(timeout) {
+            Ok(ip) if global(&ip) => ip,
+            Ok(ip) => {
+                warn!(
+                    "port mapping disabled: the gateway is behind another NAT (its external IP {} is not routable)",
+                    ip
+                );
+                return None;
+            }
+            Err(err) => {
+                warn!("port mapping disabled: {}", err);
+                return None;
+            }
+        };
+        for (protocol, port) in ports.iter() {
+            if let Err(err) = gateway.map_port(*protocol, *port, lease, timeout) {
+                warn!("port mapping disabled: {}", err);
+                return None;
+            }
+        }
+        info!(
+            "mapped the listener ports {:?} on the gateway, public IP is {}",
+            ports, external_ip
+        );
+
+        let (stop_sender, stop_receiver) = bounded::<()>(1);
+        let renewal_thread = thread::Builder::new()
+            .name("protocol-port-mapping".into())
+            .spawn(move || {
+                let renewal_ticker = tick(lease / 2);
+                loop {
+                    select! {
+                        recv(renewal_ticker) -> _ => {
+                            for (protocol, port) in ports.iter() {
+                                if let Err(err) = gateway.map_port(*protocol, *port, lease, timeout) {
+                                    warn!("could not renew the port mapping: {}", err);
+                                }
+                            }
+                        }
+                        recv(stop_receiver) -> _ => {
+                            for (protocol, port) in ports.iter() {
+                                if let Err(err) = gateway.map_port(*protocol, *port, Duration::ZERO, timeout) {
+                                    debug!("could not remove the port mapping: {}", err);
+                                }
+                            }
+                            return;
+                        }
+                    }
+                }
+            })
+            .expect("OS failed to start port mapping thread");
+
+        Some(PortMapper {
+            external_ip,
+            renewal_thread: Some((stop_sender, renewal_thread)),
+        })
+    }
+
+    /// Public IP of the gateway
+    pub fn external_ip(&self) -> IpAddr {
+        self.external_ip
+    }
+
+    /// Remove the mappings and stop renewing them
+    pub fn stop(&mut self) {
+        if let Some((stop_sender, join_handle)) = self.renewal_thread.take() {
+            let _ = stop_sender.send(());
+            join_handle
+                .join()
+                .expect("port mapping thread panicked on try to join");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_port_mapping_local_address() {
+        assert_eq!(
+            local_ip_towards("127.0.0.1:5351".parse().unwrap()).unwrap(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST)
+        );
+        assert_eq!(
+            PortMappingProtocol::from(MappedProtocol::from(TransportType::Quic)),
+            PortMappingProtocol::UDP
+        );
+        assert_eq!(
+            PortMappingProtocol::from(MappedProtocol::from(TransportType::Tcp)),
+            PortMappingProtocol::TCP
+        );
+    }
+}
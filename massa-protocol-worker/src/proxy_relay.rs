@@ -0,0 +1,128 @@
+//! Outbound peer connections through a SOCKS5 proxy.
+//!
+//! The network layer only opens direct TCP connections. To reach a peer through the proxy,
+//! the connection to the peer is opened through the proxy and exposed on a loopback port
+//! that relays the traffic both ways, then the network layer connects to that port.
+//! The port accepts a single connection, and is closed if nobody connects to it in time.
+//! Note that the peers reached this way appear with a loopback address in the active connections.
+
+use std::{
+    io,
+    net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use massa_protocol_exports::{ProxyTarget, Socks5ProxyConfig};
+use tracing::debug;
+
+/// Interval at which the relay checks whether the network layer connected to it
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Connect to `target` through the proxy, and return the loopback address relaying the connection
+pub(crate) fn open_proxy_relay(
+    proxy: &Socks5ProxyConfig,
+    target: SocketAddr,
+    timeout: Duration,
+) -> io::Result<SocketAddr> {
+    let proxied = proxy.connect(&ProxyTarget::Address(target), Some(timeout))?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    listener.set_nonblocking(true)?;
+    let relay_addr = listener.local_addr()?;
+    thread::Builder::new()
+        .name("protocol-proxy-relay".into())
+        .spawn(move || {
+            let deadline = Instant::now() + timeout;
+            let local = loop {
+                match listener.accept() {
+                    Ok((local, _)) => break local,
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                        if Instant::now() >= deadline {
+                            debug!("proxy relay to {} was not used in time", target);
+                            return;
+                        }
+                        thread::sleep(ACCEPT_POLL_INTERVAL);
+                    }
+                    Err(err) => {
+                        debug!("proxy relay to {} could not accept: {}", target, err);
+                        return;
+                    }
+                }
+            };
+            drop(listener);
+            if let Err(err) = relay(local, proxied) {
+                debug!("proxy relay to {} stopped: {}", target, err);
+            }
+        })?;
+    Ok(relay_addr)
+}
+
+/// Copy the traffic between the two streams until one of them is closed
+fn relay(local: TcpStream, proxied: TcpStream) -> io::Result<()> {
+    local.set_nonblocking(false)?;
+    let (mut local_read, mut proxied_write) = (local.try_clone()?, proxied.try_clone()?);
+    let upstream = thread::Builder::new()
+        .name("protocol-proxy-relay-up".into())
+        .spawn(move || {
+            let _ = io::copy(&mut local_read, &mut proxied_write);
+            let _ = proxied_write.shutdown(Shutdown::Write);
+        })?;
+    let (mut proxied_read, mut local_write) = (proxied, local);
+    let _ = io::copy(&mut proxied_read, &mut local_write);
+    let _ = local_write.shutdown(Shutdown::Both);
+    let _ = proxied_read.shutdown(Shutdown::Both);
+    let _ = upstream.join();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    /// SOCKS5 proxy accepting a single connection without authentication, and echoing its traffic
+    fn start_echo_proxy() -> (SocketAddr, thread::JoinHandle<SocketAddr>) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).unwrap();
+            assert_eq!(greeting, [5, 1, 0]);
+            stream.write_all(&[5, 0]).unwrap();
+            let mut request = [0u8; 10];
+            stream.read_exact(&mut request).unwrap();
+            assert_eq!(request[..4], [5, 1, 0, 1]);
+            let target = SocketAddr::from((
+                [request[4], request[5], request[6], request[7]],
+                u16::from_be_bytes([request[8], request[9]]),
+            ));
+            stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).unwrap();
+            let mut echo = stream.try_clone().unwrap();
+            io::copy(&mut stream, &mut echo).unwrap();
+            target
+        });
+        (addr, handle)
+    }
+
+    #[test]
+    fn test_connection_relayed_through_proxy() {
+        let (proxy_addr, proxy) = start_echo_proxy();
+        let proxy_config = Socks5ProxyConfig {
+            address: proxy_addr,
+            username: None,
+            password: None,
+        };
+        let target: SocketAddr = "203.0.113.7:31245".parse().unwrap();
+        let relay_addr = open_proxy_relay(&proxy_config, target, Duration::from_secs(5)).unwrap();
+        assert!(relay_addr.ip().is_loopback());
+
+        let mut stream = TcpStream::connect(relay_addr).unwrap();
+        stream.write_all(b"ping").unwrap();
+        let mut answer = [0u8; 4];
+        stream.read_exact(&mut answer).unwrap();
+        assert_eq!(&answer, b"ping");
+        stream.shutdown(Shutdown::Both).unwrap();
+        assert_eq!(proxy.join().unwrap(), target);
+    }
+}
@@ -0,0 +1,289 @@
+//! Rate limiting of the messages received from the peers.
+//!
+//! The messages that are cheap to send but expensive to process (block headers, operation
+//! announcements and requests for operations) are limited per peer with token buckets,
+//! configured per message type and per peer category.
+//! The messages above the limit are dropped before reaching the channels of the handlers,
+//! so that a single peer cannot flood a handler thread.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    ops::Bound::Included,
+    time::{Duration, Instant},
+};
+
+use massa_protocol_exports::{MessageRateLimit, MessageRateLimits, PeerId, ProtocolConfig};
+use massa_serialization::{DeserializeError, Deserializer, U64VarIntDeserializer};
+use parking_lot::{Mutex, RwLock};
+use tracing::debug;
+
+use crate::handlers::{
+    block_handler::messages::MessageTypeId as BlockMessageTypeId,
+    operation_handler::OperationMessageTypeId,
+};
+use crate::ip::to_canonical;
+use crate::messages::MessageTypeId;
+
+/// Category label of the peers of the default category
+const DEFAULT_CATEGORY: &str = "default";
+
+/// Interval at which the buckets of the peers that stopped sending messages are dropped
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Type of a rate limited message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum RateLimitedMessage {
+    BlockHeader,
+    OperationAnnouncement,
+    AskForOperations,
+}
+
+impl RateLimitedMessage {
+    /// Type of a received message, `None` if it is not rate limited.
+    /// `data` is the message without its family type ID.
+    fn classify(family: &MessageTypeId, data: &[u8]) -> Option<Self> {
+        let (_, id) = U64VarIntDeserializer::new(Included(0), Included(u64::MAX))
+            .deserialize::<DeserializeError>(data)
+            .ok()?;
+        match family {
+            MessageTypeId::Block
+                if id == u64::from(BlockMessageTypeId::Header)
+                    || id == u64::from(BlockMessageTypeId::CompactBlock) =>
+            {
+                Some(RateLimitedMessage::BlockHeader)
+            }
+            MessageTypeId::Operation
+                if id == u64::from(OperationMessageTypeId::OperationsAnnouncement) =>
+            {
+                Some(RateLimitedMessage::OperationAnnouncement)
+            }
+            MessageTypeId::Operation
+                if id == u64::from(OperationMessageTypeId::AskForOperations) =>
+            {
+                Some(RateLimitedMessage::AskForOperations)
+            }
+            _ => None,
+        }
+    }
+
+    /// Name of the message type, used to label the metrics
+    fn name(&self) -> &'static str {
+        match self {
+            RateLimitedMessage::BlockHeader => "block_header",
+            RateLimitedMessage::OperationAnnouncement => "operation_announcement",
+            RateLimitedMessage::AskForOperations => "ask_for_operations",
+        }
+    }
+
+    fn limit(&self, limits: &MessageRateLimits) -> MessageRateLimit {
+        match self {
+            RateLimitedMessage::BlockHeader => limits.block_header,
+            RateLimitedMessage::OperationAnnouncement => limits.operation_announcement,
+            RateLimitedMessage::AskForOperations => limits.ask_for_operations,
+        }
+    }
+}
+
+/// Token bucket, counting thousandths of tokens so that slow rates refill smoothly
+#[derive(Debug, Clone)]
+pub(crate) struct TokenBucket {
+    millitokens: u64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn full(limit: &MessageRateLimit, now: Instant) -> Self {
+        TokenBucket {
+            millitokens: limit.burst.saturating_mul(1000),
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &MessageRateLimit, now: Instant) {
+        let elapsed_millis =
+            u64::try_from(now.saturating_duration_since(self.last_refill).as_millis())
+                .unwrap_or(u64::MAX);
+        self.millitokens = self
+            .millitokens
+            .saturating_add(elapsed_millis.saturating_mul(limit.rate))
+            .min(limit.burst.saturating_mul(1000));
+        self.last_refill = now;
+    }
+
+    /// Take a token from the bucket, return false if it is empty
+    pub(crate) fn try_take(&mut self, limit: &MessageRateLimit, now: Instant) -> bool {
+        self.refill(limit, now);
+        if self.millitokens < 1000 {
+            return false;
+        }
+        self.millitokens -= 1000;
+        true
+    }
+}
+
+struct Buckets {
+    buckets: HashMap<(PeerId, RateLimitedMessage), TokenBucket>,
+    last_prune: Instant,
+}
+
+/// Rate limiter of the messages received from the peers
+pub(crate) struct MessageRateLimiter {
+    /// limits of the peers of the default category
+    default_limits: MessageRateLimits,
+    /// limits of the peers, by category
+    categories_limits: HashMap<String, MessageRateLimits>,
+    /// category of the IPs listed in the categories
+    ip_categories: HashMap<IpAddr, String>,
+    /// category of the peers that connected from an IP of a category
+    peer_categories: RwLock<HashMap<PeerId, String>>,
+    buckets: Mutex<Buckets>,
+}
+
+impl MessageRateLimiter {
+    pub(crate) fn new(config: &ProtocolConfig, ip_categories: HashMap<IpAddr, String>) -> Self {
+        MessageRateLimiter {
+            default_limits: config.message_rate_limits,
+            categories_limits: config.peers_categories_message_rate_limits.clone(),
+            ip_categories,
+            peer_categories: RwLock::new(HashMap::new()),
+            buckets: Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                last_prune: Instant::now(),
+            }),
+        }
+    }
+
+    /// Record the IP from which a peer connected, to apply the limits of its category
+    pub(crate) fn register_peer(&self, peer_id: PeerId, ip: IpAddr) {
+        let mut peer_categories = self.peer_categories.write();
+        match self.ip_categories.get(&to_canonical(ip)) {
+            Some(category) => {
+                peer_categories.insert(peer_id, category.clone());
+            }
+            None => {
+                peer_categories.remove(&peer_id);
+            }
+        }
+    }
+
+    /// Category of a peer, `None` for the default category
+    pub(crate) fn peer_category(&self, peer_id: &PeerId) -> Option<String> {
+        self.peer_categories.read().get(peer_id).cloned()
+    }
+
+    /// Whether a message received from a peer is within its rate limit.
+    /// `data` is the message without its family type ID.
+    pub(crate) fn allow(&self, family: &MessageTypeId, data: &[u8], peer_id: &PeerId) -> bool {
+        let Some(message) = RateLimitedMessage::classify(family, data) else {
+            return true;
+        };
+        let category = self.peer_category(peer_id);
+        let limits = category
+            .as_ref()
+            .and_then(|category| self.categories_limits.get(category))
+            .unwrap_or(&self.default_limits);
+        let limit = message.limit(limits);
+        if limit.rate == 0 {
+            return true;
+        }
+
+        let now = Instant::now();
+        let allowed = {
+            let mut buckets = self.buckets.lock();
+            if now.saturating_duration_since(buckets.last_prune) > PRUNE_INTERVAL {
+                buckets.prune(now);
+            }
+            buckets
+                .buckets
+                .entry((*peer_id, message))
+                .or_insert_with(|| TokenBucket::full(&limit, now))
+                .try_take(&limit, now)
+        };
+        if !allowed {
+            let category = category.as_deref().unwrap_or(DEFAULT_CATEGORY);
+            debug!(
+                "dropping {} message from peer {} (category {}): rate limit exceeded",
+                message.name(),
+                peer_id,
+                category
+            );
+            massa_metrics::inc_protocol_rate_limited_message(message.name(), category);
+        }
+        allowed
+    }
+}
+
+impl Buckets {
+    /// Drop the buckets that were not used since the last prune, their peers stopped sending rate limited messages
+    fn prune(&mut self, now: Instant) {
+        let last_prune = self.last_prune;
+        self.buckets
+            .retain(|_, bucket| bucket.last_refill > last_prune);
+        self.last_prune = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_serialization::{Serializer, U64VarIntSerializer};
+    use massa_signature::KeyPair;
+
+    fn limits(rate: u64, burst: u64) -> MessageRateLimits {
+        let limit = MessageRateLimit { rate, burst };
+        MessageRateLimits {
+            block_header: limit,
+            operation_announcement: limit,
+            ask_for_operations: limit,
+        }
+    }
+
+    fn message(id: impl Into<u64>) -> Vec<u8> {
+        let mut data = Vec::new();
+        U64VarIntSerializer::new()
+            .serialize(&id.into(), &mut data)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_message_rate_limits() {
+        let config = ProtocolConfig {
+            message_rate_limits: limits(1, 3),
+            peers_categories_message_rate_limits: HashMap::from([(
+                "Bootstrap".to_string(),
+                limits(1, 5),
+            )]),
+            ..Default::default()
+        };
+        let bootstrap_ip: IpAddr = "1.2.3.4".parse().unwrap();
+        let limiter = MessageRateLimiter::new(
+            &config,
+            HashMap::from([(bootstrap_ip, "Bootstrap".to_string())]),
+        );
+        let peer_id = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+        let announcement = message(OperationMessageTypeId::OperationsAnnouncement);
+        let operations = message(OperationMessageTypeId::Operations);
+
+        let accepted = (0..10)
+            .filter(|_| limiter.allow(&MessageTypeId::Operation, &announcement, &peer_id))
+            .count();
+        assert_eq!(accepted, 3);
+        // other message types are not limited
+        assert!((0..10).all(|_| limiter.allow(&MessageTypeId::Operation, &operations, &peer_id)));
+        assert!(limiter.allow(
+            &MessageTypeId::Block,
+            &message(BlockMessageTypeId::Header),
+            &peer_id
+        ));
+
+        // the limits of the category of the peer apply once it connected from one of its IPs
+        let other_peer_id = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+        limiter.register_peer(other_peer_id, bootstrap_ip);
+        let accepted = (0..10)
+            .filter(|_| limiter.allow(&MessageTypeId::Operation, &announcement, &other_peer_id))
+            .count();
+        assert_eq!(accepted, 5);
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Cheap structural pre-filters applied to received headers and endorsements
+//! before their signatures are verified, so that implausible objects are rejected
+//! without spending CPU time on signature verification.
+
+use std::collections::HashSet;
+
+use massa_consensus_exports::header_check::{self, HeaderCheckError};
+use massa_models::{
+    block_header::SecuredHeader, endorsement::SecureShareEndorsement, slot::Slot,
+    timeslots::get_block_slot_timestamp,
+};
+use massa_pos_exports::SelectorController;
+use massa_protocol_exports::{ProtocolConfig, ProtocolError};
+use massa_time::MassaTime;
+
+/// Checks that a slot is not too far in the future:
+/// its timestamp must not exceed `now` by more than `max_future_slot_time`.
+pub(crate) fn check_slot_plausibility(
+    slot: &Slot,
+    config: &ProtocolConfig,
+    now: MassaTime,
+) -> Result<(), ProtocolError> {
+    let timestamp = get_block_slot_timestamp(
+        config.thread_count,
+        config.t0,
+        config.genesis_timestamp,
+        *slot,
+    )?;
+    if timestamp > now.saturating_add(config.max_future_slot_time) {
+        return Err(ProtocolError::GeneralProtocolError(format!(
+            "slot {} is too far in the future",
+            slot
+        )));
+    }
+    Ok(())
+}
+
+/// Returns true if an endorsement received outside of a header is too old to be noted or propagated
+pub(crate) fn is_endorsement_stale(
+    endorsement: &SecureShareEndorsement,
+    config: &ProtocolConfig,
+    now: MassaTime,
+) -> bool {
+    match get_block_slot_timestamp(
+        config.thread_count,
+        config.t0,
+        config.genesis_timestamp,
+        endorsement.content.slot,
+    ) {
+        Ok(t) => t.saturating_add(config.max_endorsements_propagation_time) < now,
+        Err(_) => true,
+    }
+}
+
+/// Checks the structure of a header and of its endorsements.
+///
+/// Checks performed:
+/// - Slot not too far in the future
+/// - No more endorsements than `endorsement_count`
+/// - Endorsements have unique indices
+/// - Endorsement slots match that of the block
+/// - Endorsed blocks match the same-thread parent of the header
+pub(crate) fn check_header_structure(
+    header: &SecuredHeader,
+    config: &ProtocolConfig,
+    now: MassaTime,
+) -> Result<(), ProtocolError> {
+    check_slot_plausibility(&header.content.slot, config, now)
+        .map_err(|err| ProtocolError::InvalidBlock(err.to_string()))?;
+
+    if header.content.endorsements.len() > config.endorsement_count as usize {
+        return Err(ProtocolError::InvalidBlock(format!(
+            "too many endorsements: {}",
+            header.content.endorsements.len()
+        )));
+    }
+
+    let Some(parent) = header
+        .content
+        .parents
+        .get(header.content.slot.thread as usize)
+    else {
+        return Err(ProtocolError::InvalidBlock(format!(
+            "no parent in thread {}",
+            header.content.slot.thread
+        )));
+    };
+    let mut used_endorsement_indices: HashSet<u32> =
+        HashSet::with_capacity(header.content.endorsements.len());
+    for endorsement in header.content.endorsements.iter() {
+        // check index reuse
+        if !used_endorsement_indices.insert(endorsement.content.index) {
+            return Err(ProtocolError::InvalidBlock(format!(
+                "duplicate endorsement index: {}",
+                endorsement.content.index
+            )));
+        }
+        // check slot
+        if endorsement.content.slot != header.content.slot {
+            return Err(ProtocolError::InvalidBlock(format!(
+                "endorsement slot {} does not match header slot: {}",
+                endorsement.content.slot, header.content.slot
+            )));
+        }
+        // check endorsed block
+        if &endorsement.content.endorsed_block != parent {
+            return Err(ProtocolError::InvalidBlock(format!(
+                "endorsed block {} does not match header parent: {}",
+                endorsement.content.endorsed_block, parent
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the creator of a header is the producer drawn for its slot.
+/// The check is skipped if the draws of the slot are not available yet.
+pub(crate) fn check_header_producer(
+    header: &SecuredHeader,
+    selector_controller: &dyn SelectorController,
+) -> Result<(), ProtocolError> {
+    match header_check::check_header_producer(header, selector_controller) {
+        Ok(()) | Err(HeaderCheckError::DrawsUnavailable(_)) => Ok(()),
+        Err(err) => Err(ProtocolError::InvalidBlock(err.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_plausibility() {
+        let config = ProtocolConfig {
+            thread_count: 2,
+            t0: MassaTime::from_millis(16000),
+            genesis_timestamp: MassaTime::from_millis(0),
+            max_future_slot_time: MassaTime::from_millis(32000),
+            ..Default::default()
+        };
+        let now = MassaTime::from_millis(160000);
+        // slot (10, 0) is at 160s: current slot
+        assert!(check_slot_plausibility(&Slot::new(10, 0), &config, now).is_ok());
+        // slot (12, 0) is at 192s: at the edge of the window
+        assert!(check_slot_plausibility(&Slot::new(12, 0), &config, now).is_ok());
+        // slot (12, 1) is at 200s: too far in the future
+        assert!(check_slot_plausibility(&Slot::new(12, 1), &config, now).is_err());
+    }
+}
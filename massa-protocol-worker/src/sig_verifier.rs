@@ -2,29 +2,36 @@
 
 //! Optimized batch signature verifier
 
-use massa_hash::Hash;
+use std::fmt::Display;
+
+use massa_models::secure_share::{Id, SecureShare, SecureShareContent};
 use massa_protocol_exports::ProtocolError;
-use massa_signature::{verify_signature_batch, PublicKey, Signature};
 use rayon::{prelude::ParallelIterator, slice::ParallelSlice};
 
 //TODO: Benchmark
 /// Limit for small batch optimization
 const SMALL_BATCH_LIMIT: usize = 2;
 
-/// Efficiently verifies a batch of signatures in parallel.
+/// Efficiently verifies the signatures of a batch of secure shares in parallel.
 /// Returns an error if at least one of them fails to verify.
-pub fn verify_sigs_batch(ops: &[(Hash, Signature, PublicKey)]) -> Result<(), ProtocolError> {
+pub fn verify_sigs_batch<T, ID>(items: &[&SecureShare<T, ID>]) -> Result<(), ProtocolError>
+where
+    T: Display + SecureShareContent + Sync,
+    ID: Id + Sync,
+{
     // if it's a small batch, use single-core verification
-    if ops.len() <= SMALL_BATCH_LIMIT {
-        return verify_signature_batch(ops).map_err(|_err| ProtocolError::WrongSignature);
+    if items.len() <= SMALL_BATCH_LIMIT {
+        return SecureShare::verify_batch(items.iter().copied())
+            .map_err(|_err| ProtocolError::WrongSignature);
     }
 
     // otherwise, use parallel batch verif
 
     // compute chunk size for parallelization
-    let chunk_size = std::cmp::max(1, ops.len() / rayon::current_num_threads());
+    let chunk_size = std::cmp::max(1, items.len() / rayon::current_num_threads());
     // process chunks in parallel
-    ops.par_chunks(chunk_size)
-        .try_for_each(verify_signature_batch)
+    items
+        .par_chunks(chunk_size)
+        .try_for_each(|chunk| SecureShare::verify_batch(chunk.iter().copied()))
         .map_err(|_err| ProtocolError::WrongSignature)
 }
@@ -1,7 +1,9 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
+use crate::handlers::block_handler::messages::operation_short_id;
 use crate::handlers::block_handler::{AskForBlockInfo, BlockInfoReply, BlockMessage};
 use crate::handlers::operation_handler::OperationMessage;
 use crate::messages::Message;
@@ -45,10 +47,14 @@ impl PeerIdMatchers {
     }
 }
 
+type AskedOperationsParts = Arc<Mutex<HashMap<PeerId, Vec<OperationId>>>>;
+
 #[allow(clippy::large_enum_variant)]
 enum TestsStepMatch {
     // Match an ask asking for the block infos and match the block infos asked
     AskData((PeerIdMatchers, BlockId, AskForBlockInfo)),
+    // Match an ask asking for some operations of the block, and record the operations asked to each peer
+    AskOperationsPart((PeerIdMatchers, BlockId, AskedOperationsParts)),
     // Match a send of a block header and match the block header sent
     SendHeader((PeerIdMatchers, BlockId, SecuredHeader)),
     // Match a send of information and match the block infos sent
@@ -102,6 +108,41 @@ fn block_retrieval_mock(
                     }
                 });
             }
+            TestsStepMatch::AskOperationsPart((node_peer_id, asked_block_id, asked_parts)) => {
+                node_peer_id.extend(&mut peer_ids);
+                shared_active_connections.set_expectations({
+                    |active_connections| {
+                        active_connections
+                            .expect_send_to_peer()
+                            .times(1)
+                            .in_sequence(&mut sequence)
+                            .returning(move |peer_id, _, message, high_priority| {
+                                assert!(node_peer_id.matches(peer_id));
+                                match message {
+                                    Message::Block(message) => match *message {
+                                        BlockMessage::DataRequest {
+                                            block_id,
+                                            block_info: AskForBlockInfo::Operations(operations),
+                                        } => {
+                                            assert_eq!(block_id, asked_block_id);
+                                            asked_parts
+                                                .lock()
+                                                .unwrap()
+                                                .insert(*peer_id, operations);
+                                        }
+                                        _ => {
+                                            panic!("Node didn't receive the ask operations message")
+                                        }
+                                    },
+                                    _ => panic!("Node didn't receive the ask operations message"),
+                                }
+                                assert!(high_priority);
+                                waitpoint_trigger_handle.trigger();
+                                Ok(())
+                            });
+                    }
+                });
+            }
             TestsStepMatch::SendData((node_peer_id, sent_block_id, _sent_infos)) => {
                 node_peer_id.extend(&mut peer_ids);
                 shared_active_connections.set_expectations({
@@ -317,6 +358,127 @@ fn test_full_ask_block_workflow() {
     waitpoint.wait();
 }
 
+#[test]
+fn test_missing_operations_spread_over_peers_knowing_the_block() {
+    let protocol_config = ProtocolConfig {
+        thread_count: 2,
+        ..Default::default()
+    };
+
+    let block_creator = KeyPair::generate(0).unwrap();
+    let operations: Vec<_> = (0..32)
+        .map(|_| ProtocolTestUniverse::create_operation(&block_creator, 5))
+        .collect();
+    let op_ids: Vec<OperationId> = operations.iter().map(|op| op.id).collect();
+    let op_thread = operations[0]
+        .content_creator_address
+        .get_thread(protocol_config.thread_count);
+    let block = ProtocolTestUniverse::create_block(
+        &block_creator,
+        Slot::new(1, op_thread),
+        operations.clone(),
+        vec![],
+        vec![],
+    );
+    let node_a_keypair = KeyPair::generate(0).unwrap();
+    let node_a_peer_id = PeerId::from_public_key(node_a_keypair.get_public_key());
+    let node_b_keypair = KeyPair::generate(0).unwrap();
+    let node_b_peer_id = PeerId::from_public_key(node_b_keypair.get_public_key());
+    let both_peers =
+        PeerIdMatchers::AmongPeerIds(vec![node_a_peer_id, node_b_peer_id].into_iter().collect());
+    let asked_parts = AskedOperationsParts::default();
+
+    let waitpoint = WaitPoint::new();
+    let mut foreign_controllers = ProtocolForeignControllers::new_with_mocks();
+    ProtocolTestUniverse::peer_db_boilerplate(&mut foreign_controllers.peer_db.write());
+    foreign_controllers
+        .consensus_controller
+        .expect_register_block_header()
+        .returning(move |block_id, header| {
+            assert_eq!(block_id, block.id);
+            assert_eq!(header.id, block.content.header.id);
+        });
+    // each peer sends its part of the operations
+    foreign_controllers
+        .pool_controller
+        .set_expectations(|pool_controller| {
+            pool_controller
+                .expect_add_operations()
+                .times(2)
+                .returning(|_| {});
+        });
+    block_retrieval_mock(
+        vec![
+            TestsStepMatch::AskData((both_peers.clone(), block.id, AskForBlockInfo::OperationIds)),
+            TestsStepMatch::AskOperationsPart((both_peers.clone(), block.id, asked_parts.clone())),
+            TestsStepMatch::AskOperationsPart((both_peers, block.id, asked_parts.clone())),
+            TestsStepMatch::BlockManaged((block.id, false)),
+        ],
+        &mut foreign_controllers,
+        waitpoint.get_trigger_handle(),
+    );
+
+    let universe = ProtocolTestUniverse::new(foreign_controllers, protocol_config);
+
+    // both peers announce the block
+    for peer_id in [node_a_peer_id, node_b_peer_id] {
+        universe.mock_message_receive(
+            &peer_id,
+            Message::Block(Box::new(BlockMessage::Header(block.content.header.clone()))),
+        );
+    }
+
+    universe
+        .module_controller
+        .send_wishlist_delta(
+            vec![(block.id, Some(block.content.header.clone()))]
+                .into_iter()
+                .collect(),
+            PreHashSet::<BlockId>::default(),
+        )
+        .unwrap();
+    waitpoint.wait();
+
+    // both peers send the operation list, so that they are known to hold the operations
+    for peer_id in [node_a_peer_id, node_b_peer_id] {
+        universe.mock_message_receive(
+            &peer_id,
+            Message::Block(Box::new(BlockMessage::DataResponse {
+                block_id: block.id,
+                block_info: BlockInfoReply::OperationIds(op_ids.clone()),
+            })),
+        );
+    }
+    waitpoint.wait();
+    waitpoint.wait();
+
+    // the missing operations are split in disjoint parts, one per peer
+    let parts = asked_parts.lock().unwrap().clone();
+    assert_eq!(parts.len(), 2);
+    let mut all_asked: Vec<OperationId> = parts.values().flatten().copied().collect();
+    all_asked.sort_unstable();
+    let mut expected = op_ids.clone();
+    expected.sort_unstable();
+    assert_eq!(all_asked, expected);
+
+    for (peer_id, part) in parts {
+        universe.mock_message_receive(
+            &peer_id,
+            Message::Block(Box::new(BlockMessage::DataResponse {
+                block_id: block.id,
+                block_info: BlockInfoReply::Operations(
+                    operations
+                        .iter()
+                        .filter(|op| part.contains(&op.id))
+                        .cloned()
+                        .collect(),
+                ),
+            })),
+        );
+    }
+    waitpoint.wait();
+}
+
 #[test]
 fn test_empty_block() {
     let protocol_config = ProtocolConfig {
@@ -998,3 +1160,70 @@ fn test_protocol_does_propagate_operations_received_in_blocks() {
     waitpoint.wait();
     waitpoint.wait();
 }
+
+#[test]
+fn test_block_rebuilt_from_compact_block() {
+    let protocol_config = ProtocolConfig {
+        thread_count: 2,
+        ask_block_timeout: MassaTime::from_millis(100),
+        ..Default::default()
+    };
+
+    let block_creator = KeyPair::generate(0).unwrap();
+    let op_1 = ProtocolTestUniverse::create_operation(&block_creator, 5);
+    let op_thread = op_1
+        .content_creator_address
+        .get_thread(protocol_config.thread_count);
+    let block = ProtocolTestUniverse::create_block(
+        &block_creator,
+        Slot::new(1, op_thread),
+        vec![op_1.clone()],
+        vec![],
+        vec![],
+    );
+    let node_a_keypair = KeyPair::generate(0).unwrap();
+    let node_a_peer_id = PeerId::from_public_key(node_a_keypair.get_public_key());
+
+    let waitpoint = WaitPoint::new();
+    let header_waitpoint = WaitPoint::new();
+    let header_waitpoint_trigger_handle = header_waitpoint.get_trigger_handle();
+    let mut foreign_controllers = ProtocolForeignControllers::new_with_mocks();
+    ProtocolTestUniverse::peer_db_boilerplate(&mut foreign_controllers.peer_db.write());
+    foreign_controllers
+        .consensus_controller
+        .expect_register_block_header()
+        .return_once(move |block_id, header| {
+            assert_eq!(block_id, block.id);
+            assert_eq!(header.id, block.content.header.id);
+            header_waitpoint_trigger_handle.trigger();
+        });
+    // the block is rebuilt without asking for its data
+    block_retrieval_mock(
+        vec![TestsStepMatch::BlockManaged((block.id, true))],
+        &mut foreign_controllers,
+        waitpoint.get_trigger_handle(),
+    );
+
+    let universe = ProtocolTestUniverse::new(foreign_controllers, protocol_config);
+
+    universe.mock_message_receive(
+        &node_a_peer_id,
+        Message::Block(Box::new(BlockMessage::CompactBlock {
+            header: block.content.header.clone(),
+            short_op_ids: vec![operation_short_id(&op_1.id)],
+            operations: vec![op_1],
+        })),
+    );
+    header_waitpoint.wait();
+
+    universe
+        .module_controller
+        .send_wishlist_delta(
+            vec![(block.id, Some(block.content.header.clone()))]
+                .into_iter()
+                .collect(),
+            PreHashSet::<BlockId>::default(),
+        )
+        .unwrap();
+    waitpoint.wait();
+}
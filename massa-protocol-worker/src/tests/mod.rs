@@ -18,8 +18,11 @@ use crate::{create_protocol_controller, start_protocol_controller};
 mod ban_nodes_scenarios;
 mod block_scenarios;
 mod endorsements_scenarios;
+mod network_simulation_scenarios;
 mod operations_scenarios;
+mod peer_churn_scenarios;
 mod peer_priorization;
+mod topic_scenarios;
 mod universe;
 
 #[test]
@@ -59,8 +62,7 @@ fn basic() {
     config1
         .listeners
         .insert("127.0.0.1:8081".parse().unwrap(), TransportType::Tcp);
-    config1.keypair_file = "./src/tests/test_keypair1.json".to_string().into();
-    let keypair_bs58_check_encoded = read_to_string(&config1.keypair_file)
+    let keypair_bs58_check_encoded = read_to_string("./src/tests/test_keypair1.json")
         .map_err(|err| {
             std::io::Error::new(err.kind(), format!("could not load node key file: {}", err))
         })
@@ -71,8 +73,7 @@ fn basic() {
     config2
         .listeners
         .insert("127.0.0.1:8082".parse().unwrap(), TransportType::Tcp);
-    config2.keypair_file = "./src/tests/test_keypair2.json".to_string().into();
-    let keypair_bs58_check_encoded = read_to_string(&config2.keypair_file)
+    let keypair_bs58_check_encoded = read_to_string("./src/tests/test_keypair2.json")
         .map_err(|err| {
             std::io::Error::new(err.kind(), format!("could not load node key file: {}", err))
         })
@@ -156,7 +157,7 @@ fn basic() {
     .0;
 
     // Setup the protocols
-    let (mut manager1, _, _) = start_protocol_controller(
+    let (mut manager1, _) = start_protocol_controller(
         config1,
         selector_controller1,
         consensus_controller1,
@@ -166,9 +167,11 @@ fn basic() {
         channels1,
         mip_store.clone(),
         metrics.clone(),
+        keypair1,
+        None,
     )
     .expect("Failed to start protocol 1");
-    let (mut manager2, _, _) = start_protocol_controller(
+    let (mut manager2, _) = start_protocol_controller(
         config2,
         selector_controller2,
         consensus_controller2,
@@ -178,6 +181,8 @@ fn basic() {
         channels2,
         mip_store,
         metrics,
+        keypair2,
+        None,
     )
     .expect("Failed to start protocol 2");
 
@@ -222,8 +227,7 @@ fn stop_with_controller_still_exists() {
     config1
         .listeners
         .insert("127.0.0.1:8083".parse().unwrap(), TransportType::Tcp);
-    config1.keypair_file = "./src/tests/test_keypair1.json".to_string().into();
-    let keypair_bs58_check_encoded = read_to_string(&config1.keypair_file)
+    let keypair_bs58_check_encoded = read_to_string("./src/tests/test_keypair1.json")
         .map_err(|err| {
             std::io::Error::new(err.kind(), format!("could not load node key file: {}", err))
         })
@@ -234,8 +238,7 @@ fn stop_with_controller_still_exists() {
     config2
         .listeners
         .insert("127.0.0.1:8086".parse().unwrap(), TransportType::Tcp);
-    config2.keypair_file = "./src/tests/test_keypair2.json".to_string().into();
-    let keypair_bs58_check_encoded = read_to_string(&config2.keypair_file)
+    let keypair_bs58_check_encoded = read_to_string("./src/tests/test_keypair2.json")
         .map_err(|err| {
             std::io::Error::new(err.kind(), format!("could not load node key file: {}", err))
         })
@@ -317,7 +320,7 @@ fn stop_with_controller_still_exists() {
     // Setup the protocols
     let (mut sender_manager1, channels1) = create_protocol_controller(config1.clone());
     let (mut sender_manager2, channels2) = create_protocol_controller(config2.clone());
-    let (mut manager1, _, _) = start_protocol_controller(
+    let (mut manager1, _) = start_protocol_controller(
         config1,
         selector_controller1,
         consensus_controller1,
@@ -327,9 +330,11 @@ fn stop_with_controller_still_exists() {
         channels1,
         mip_store.clone(),
         metrics.clone(),
+        keypair1,
+        None,
     )
     .expect("Failed to start protocol 1");
-    let (mut manager2, _, _) = start_protocol_controller(
+    let (mut manager2, _) = start_protocol_controller(
         config2,
         selector_controller2,
         consensus_controller2,
@@ -339,6 +344,8 @@ fn stop_with_controller_still_exists() {
         channels2,
         mip_store,
         metrics,
+        keypair2,
+        None,
     )
     .expect("Failed to start protocol 2");
 
@@ -0,0 +1,207 @@
+// In these tests, operation announcements serialized with the protocol messages serializers are gossiped
+// between nodes of the simulated network: each node announces the operations it learns to its neighbours,
+// and re-announces them at each propagation tick to the neighbours that did not announce them back.
+// The scenarios check that a partition stops the propagation until it heals, and that lossy links with
+// jitter only delay it, identically for a given seed.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Bound::Included,
+    sync::mpsc::Receiver,
+    time::Duration,
+};
+
+use massa_models::operation::{OperationPrefixId, OperationPrefixIds};
+use massa_protocol_exports::{
+    test_exports::network_simulator::{
+        LinkConditions, NetworkChange, NetworkSimulator, SimulationStats,
+    },
+    PeerId, ProtocolConfig,
+};
+use massa_serialization::{DeserializeError, Deserializer, U64VarIntDeserializer};
+use massa_signature::KeyPair;
+use peernet::messages::MessagesSerializer as PeerNetMessagesSerializer;
+
+use crate::{
+    handlers::operation_handler::{
+        OperationMessage, OperationMessageDeserializer, OperationMessageDeserializerArgs,
+        OperationMessageSerializer,
+    },
+    messages::{Message, MessageTypeId, MessagesSerializer},
+};
+
+const NODE_COUNT: usize = 12;
+const PROPAGATION_TICK: Duration = Duration::from_millis(100);
+
+/// Node gossiping operation announcements
+struct GossipNode {
+    peer_id: PeerId,
+    neighbours: Vec<PeerId>,
+    receiver: Receiver<(PeerId, Vec<u8>)>,
+    known: HashSet<OperationPrefixId>,
+    /// operations each neighbour is known to know
+    known_by_neighbour: HashMap<PeerId, HashSet<OperationPrefixId>>,
+}
+
+fn deserializer() -> OperationMessageDeserializer {
+    let config = ProtocolConfig::default();
+    OperationMessageDeserializer::new(OperationMessageDeserializerArgs {
+        max_operations_prefix_ids: config.max_operations_per_message as u32,
+        max_operations: config.max_operations_per_message as u32,
+        max_datastore_value_length: config.max_op_datastore_value_length,
+        max_function_name_length: config.max_size_function_name,
+        max_parameters_size: config.max_size_call_sc_parameter,
+        max_op_datastore_entry_count: config.max_op_datastore_entry_count,
+        max_op_datastore_key_length: config.max_op_datastore_key_length,
+        max_op_datastore_value_length: config.max_op_datastore_value_length,
+        max_operations_per_inventory_digest: config.max_operations_per_inventory_digest as u64,
+    })
+}
+
+/// Decodes the operation announcements received on the wire
+fn decode_announcement(data: &[u8]) -> OperationPrefixIds {
+    let (rest, family) = U64VarIntDeserializer::new(Included(0), Included(u64::MAX))
+        .deserialize::<DeserializeError>(data)
+        .unwrap();
+    assert_eq!(family, u64::from(MessageTypeId::Operation));
+    match deserializer()
+        .deserialize::<DeserializeError>(rest)
+        .unwrap()
+    {
+        (_, OperationMessage::OperationsAnnouncement(prefixes)) => prefixes,
+        (_, message) => panic!("unexpected message {:?}", message),
+    }
+}
+
+/// Ring of nodes, each also linked to the node across the ring
+fn build_network(
+    seed: u64,
+    conditions: LinkConditions,
+) -> (NetworkSimulator<Vec<u8>>, Vec<GossipNode>) {
+    let mut simulator = NetworkSimulator::new(seed, conditions);
+    let peer_ids: Vec<PeerId> = (0..NODE_COUNT)
+        .map(|_| PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key()))
+        .collect();
+    let nodes = (0..NODE_COUNT)
+        .map(|index| {
+            let neighbours: Vec<PeerId> = [
+                (index + 1) % NODE_COUNT,
+                (index + NODE_COUNT - 1) % NODE_COUNT,
+                (index + NODE_COUNT / 2) % NODE_COUNT,
+            ]
+            .iter()
+            .map(|neighbour| peer_ids[*neighbour])
+            .collect();
+            GossipNode {
+                peer_id: peer_ids[index],
+                known_by_neighbour: neighbours
+                    .iter()
+                    .map(|neighbour| (*neighbour, HashSet::new()))
+                    .collect(),
+                neighbours,
+                receiver: simulator.add_node(peer_ids[index]),
+                known: HashSet::new(),
+            }
+        })
+        .collect();
+    (simulator, nodes)
+}
+
+/// Runs one propagation tick: the nodes handle the announcements they received,
+/// then announce the operations they know to the neighbours not known to know them
+fn propagation_tick(simulator: &mut NetworkSimulator<Vec<u8>>, nodes: &mut [GossipNode]) {
+    let serializer = MessagesSerializer::new()
+        .with_operation_message_serializer(OperationMessageSerializer::new());
+    for node in nodes.iter_mut() {
+        while let Ok((from, data)) = node.receiver.try_recv() {
+            let prefixes = decode_announcement(&data);
+            node.known.extend(prefixes.iter().copied());
+            node.known_by_neighbour
+                .entry(from)
+                .or_default()
+                .extend(prefixes);
+        }
+        for neighbour in &node.neighbours {
+            let known_by_neighbour = &node.known_by_neighbour[neighbour];
+            let to_announce: OperationPrefixIds =
+                node.known.difference(known_by_neighbour).copied().collect();
+            if to_announce.is_empty() {
+                continue;
+            }
+            let mut data = Vec::new();
+            serializer
+                .serialize(
+                    &Message::Operation(OperationMessage::OperationsAnnouncement(to_announce)),
+                    &mut data,
+                )
+                .unwrap();
+            simulator.send(node.peer_id, *neighbour, data);
+        }
+    }
+    simulator.advance(PROPAGATION_TICK);
+}
+
+fn operation_prefix(index: u8) -> OperationPrefixId {
+    OperationPrefixId::from(&[index; 17])
+}
+
+#[test]
+fn test_partition_stops_propagation_until_healed() {
+    let (mut simulator, mut nodes) =
+        build_network(42, LinkConditions::reliable(Duration::from_millis(30)));
+    let side_a: HashSet<PeerId> = nodes[..NODE_COUNT / 2]
+        .iter()
+        .map(|node| node.peer_id)
+        .collect();
+    simulator.apply(NetworkChange::Partition(vec![side_a.clone()]));
+    simulator.schedule(Duration::from_secs(2), NetworkChange::Heal);
+
+    nodes[0].known.insert(operation_prefix(1));
+    for _ in 0..15 {
+        propagation_tick(&mut simulator, &mut nodes);
+    }
+    // only the side of the partition of the first node knows the operation
+    for node in &nodes {
+        assert_eq!(
+            node.known.contains(&operation_prefix(1)),
+            side_a.contains(&node.peer_id)
+        );
+    }
+    assert!(simulator.stats().partitioned > 0);
+
+    // once healed, the operation reaches the whole network
+    for _ in 0..15 {
+        propagation_tick(&mut simulator, &mut nodes);
+    }
+    assert!(simulator.now() >= Duration::from_secs(3));
+    assert!(nodes
+        .iter()
+        .all(|node| node.known.contains(&operation_prefix(1))));
+}
+
+#[test]
+fn test_lossy_links_delay_propagation_deterministically() {
+    let conditions = LinkConditions {
+        latency: Duration::from_millis(20),
+        jitter: Duration::from_millis(150),
+        loss: 0.3,
+    };
+    let run = |seed: u64| -> (SimulationStats, usize) {
+        let (mut simulator, mut nodes) = build_network(seed, conditions);
+        for (index, node) in nodes.iter_mut().enumerate() {
+            node.known.insert(operation_prefix(index as u8));
+        }
+        let mut ticks = 0;
+        while nodes.iter().any(|node| node.known.len() < NODE_COUNT) {
+            assert!(ticks < 200, "the operations were not propagated");
+            propagation_tick(&mut simulator, &mut nodes);
+            ticks += 1;
+        }
+        (simulator.stats(), ticks)
+    };
+    let (stats, ticks) = run(7);
+    assert!(stats.lost > 0);
+    assert_eq!(stats.partitioned, 0);
+    // the same seed plays the same scenario
+    assert_eq!(run(7), (stats, ticks));
+}
@@ -1,9 +1,15 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use std::collections::HashSet;
+use std::str::FromStr;
 use std::time::Duration;
 
-use massa_models::operation::{OperationPrefixId, SecureShareOperation};
+use massa_models::address::Address;
+use massa_models::amount::Amount;
+use massa_models::operation::{
+    Operation, OperationPrefixId, OperationSerializer, OperationType, SecureShareOperation,
+};
+use massa_models::secure_share::SecureShareContent;
 use massa_models::{block_id::BlockId, prehash::PreHashSet, slot::Slot};
 use massa_protocol_exports::PeerId;
 use massa_protocol_exports::ProtocolConfig;
@@ -351,6 +357,71 @@ fn test_protocol_batches_propagation_of_operations_received_over_the_network_and
     waitpoint.wait();
 }
 
+#[test]
+fn test_protocol_propagates_operations_by_fee_density() {
+    let protocol_config = ProtocolConfig {
+        thread_count: 2,
+        max_ops_kept_for_propagation: 2,
+        max_operations_per_message: 1,
+        ..Default::default()
+    };
+    let block_creator = KeyPair::generate(0).unwrap();
+    let create_operation_with_fee = |fee: &str| {
+        let content = Operation {
+            fee: Amount::from_str(fee).unwrap(),
+            op: OperationType::Transaction {
+                recipient_address: Address::from_public_key(
+                    &KeyPair::generate(0).unwrap().get_public_key(),
+                ),
+                amount: Amount::default(),
+            },
+            expire_period: 1,
+        };
+        Operation::new_verifiable(content, OperationSerializer::new(), &block_creator).unwrap()
+    };
+    let low_fee_operation = create_operation_with_fee("0.01");
+    let high_fee_operation = create_operation_with_fee("3");
+    let medium_fee_operation = create_operation_with_fee("2");
+    let node_b_keypair = KeyPair::generate(0).unwrap();
+    let node_b_peer_id = PeerId::from_public_key(node_b_keypair.get_public_key());
+
+    let waitpoint = WaitPoint::new();
+    let waitpoint_trigger_handle = waitpoint.get_trigger_handle();
+    let mut foreign_controllers = ProtocolForeignControllers::new_with_mocks();
+    ProtocolTestUniverse::peer_db_boilerplate(&mut foreign_controllers.peer_db.write());
+    // the low fee operation is dropped to keep at most 2 operations,
+    // and the others are announced (one per message) by decreasing fee density
+    operation_workflow_mock(
+        vec![
+            TestsStepMatch::OperationsPropagated((
+                node_b_peer_id,
+                vec![high_fee_operation.id.into_prefix()],
+                true,
+            )),
+            TestsStepMatch::OperationsPropagated((
+                node_b_peer_id,
+                vec![medium_fee_operation.id.into_prefix()],
+                true,
+            )),
+        ],
+        &mut foreign_controllers,
+        waitpoint_trigger_handle,
+    );
+    let mut universe = ProtocolTestUniverse::new(foreign_controllers, protocol_config);
+
+    universe.storage.store_operations(vec![
+        low_fee_operation,
+        high_fee_operation,
+        medium_fee_operation,
+    ]);
+    universe
+        .module_controller
+        .propagate_operations(universe.storage.clone())
+        .unwrap();
+    waitpoint.wait();
+    waitpoint.wait();
+}
+
 #[test]
 fn test_protocol_propagates_operations_only_to_nodes_that_dont_know_about_it_indirect_knowledge_via_header(
 ) {
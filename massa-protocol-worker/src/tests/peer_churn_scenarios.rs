@@ -0,0 +1,400 @@
+// In these tests, thousands of peers are simulated in-process, with scripted churn: peers connect and
+// disconnect, time out, fail their handshakes, get banned and unbanned, and new peers are discovered.
+// The real peer database and outbound connection logic run against this simulated network, and the
+// invariants are checked after each round: the outbound quotas of the categories and network groups are
+// respected, only trusted peers are dialed, the peer database only holds the known peers, and once the churn
+// stops, every category is filled up to its target.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use massa_protocol_exports::{PeerCategoryInfo, PeerId, ProtocolConfig, ProtocolError};
+use massa_signature::KeyPair;
+use massa_time::MassaTime;
+use parking_lot::RwLock;
+use peernet::{peer::PeerConnectionType, transports::TransportType};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    connectivity::connect_to_peers,
+    dialer::OutboundDialer,
+    handlers::peer_handler::{
+        announcement::Announcement,
+        models::{PeerDB, PeerInfo, PeerState, SharedPeerDB},
+    },
+    wrap_peer_db::PeerDBTrait,
+};
+
+const PEER_COUNT: usize = 3000;
+/// The first peers are in the bootstrap category
+const BOOTSTRAP_PEER_COUNT: usize = 30;
+/// Peers known when the simulation starts, the other ones are discovered over the rounds
+const INITIAL_KNOWN_PEER_COUNT: usize = 500;
+const DISCOVERED_PEERS_PER_ROUND: usize = 20;
+/// Peers sharing the same /16 subnet
+const PEERS_PER_SUBNET: usize = 4;
+const CHURN_ROUNDS: usize = 200;
+const QUIET_ROUNDS: usize = 30;
+const UNBAN_EVERYONE_ROUNDS: usize = 25;
+const BOOTSTRAP_CATEGORY: &str = "Bootstrap";
+const DEFAULT_TARGET_OUT_CONNECTIONS: usize = 8;
+const BOOTSTRAP_TARGET_OUT_CONNECTIONS: usize = 3;
+const MAX_PER_GROUP: usize = 2;
+
+/// Churn applied at each round
+struct Churn {
+    /// probability that a connected peer disconnects
+    disconnect: f64,
+    /// probability that a known peer is banned
+    ban: f64,
+    /// probability that an outbound connection times out
+    connect_timeout: f64,
+    /// probability that the handshake of an established connection fails
+    handshake_failure: f64,
+}
+
+const CHURN: Churn = Churn {
+    disconnect: 0.05,
+    ban: 0.3,
+    connect_timeout: 0.1,
+    handshake_failure: 0.1,
+};
+
+const QUIET: Churn = Churn {
+    disconnect: 0.0,
+    ban: 0.0,
+    connect_timeout: 0.1,
+    handshake_failure: 0.1,
+};
+
+struct SimulatedPeer {
+    peer_id: PeerId,
+    addr: SocketAddr,
+    announcement: Announcement,
+    /// unreachable peers time out on every connection
+    reachable: bool,
+    bootstrap: bool,
+}
+
+struct ChurnSimulation {
+    config: ProtocolConfig,
+    peer_categories: HashMap<String, (Vec<IpAddr>, PeerCategoryInfo)>,
+    dialer: OutboundDialer,
+    peer_db: Arc<RwLock<PeerDB>>,
+    peers: Vec<SimulatedPeer>,
+    peer_by_addr: HashMap<SocketAddr, usize>,
+    /// number of peers discovered so far, the first ones of `peers`
+    known: usize,
+    /// established outbound connections
+    connected: HashMap<PeerId, (SocketAddr, PeerConnectionType, Option<String>)>,
+    /// outbound connections in progress
+    queue: HashSet<SocketAddr>,
+    rng: StdRng,
+}
+
+impl ChurnSimulation {
+    fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let config = ProtocolConfig {
+            try_connection_timer_same_peer: MassaTime::from_millis(0),
+            default_category_info: PeerCategoryInfo {
+                allow_local_peers: false,
+                target_out_connections: DEFAULT_TARGET_OUT_CONNECTIONS,
+                max_in_connections: 10,
+                max_in_connections_per_ip: 1,
+            },
+            outbound_max_per_group: MAX_PER_GROUP,
+            ..Default::default()
+        };
+        let peers: Vec<SimulatedPeer> = (0..PEER_COUNT)
+            .map(|index| {
+                let subnet = index / PEERS_PER_SUBNET;
+                let ip = IpAddr::V4(Ipv4Addr::new(
+                    11 + (subnet % 89) as u8,
+                    (subnet / 89) as u8,
+                    0,
+                    1 + (index % PEERS_PER_SUBNET) as u8,
+                ));
+                let addr = SocketAddr::new(ip, 31244);
+                let keypair = KeyPair::generate(0).unwrap();
+                SimulatedPeer {
+                    peer_id: PeerId::from_public_key(keypair.get_public_key()),
+                    addr,
+                    announcement: Announcement::new(
+                        [(addr, TransportType::Tcp)].into_iter().collect(),
+                        Some(ip),
+                        &keypair,
+                    )
+                    .unwrap(),
+                    reachable: rng.gen_bool(0.8),
+                    bootstrap: index < BOOTSTRAP_PEER_COUNT,
+                }
+            })
+            .collect();
+        let peer_categories = [(
+            BOOTSTRAP_CATEGORY.to_string(),
+            (
+                peers
+                    .iter()
+                    .filter(|peer| peer.bootstrap)
+                    .map(|peer| peer.addr.ip())
+                    .collect(),
+                PeerCategoryInfo {
+                    allow_local_peers: false,
+                    target_out_connections: BOOTSTRAP_TARGET_OUT_CONNECTIONS,
+                    max_in_connections: 5,
+                    max_in_connections_per_ip: 1,
+                },
+            ),
+        )]
+        .into_iter()
+        .collect();
+        let mut simulation = ChurnSimulation {
+            dialer: OutboundDialer::new(&config).unwrap(),
+            config,
+            peer_categories,
+            peer_db: Arc::new(RwLock::new(PeerDB::default())),
+            peer_by_addr: peers
+                .iter()
+                .enumerate()
+                .map(|(index, peer)| (peer.addr, index))
+                .collect(),
+            peers,
+            known: 0,
+            connected: HashMap::new(),
+            queue: HashSet::new(),
+            rng,
+        };
+        simulation.discover(INITIAL_KNOWN_PEER_COUNT);
+        simulation
+    }
+
+    /// The peer tester checked new peers, which are trusted
+    fn discover(&mut self, count: usize) {
+        let mut peer_db = self.peer_db.write();
+        let end = (self.known + count).min(PEER_COUNT);
+        for peer in &self.peers[self.known..end] {
+            peer_db.peers.insert(
+                peer.peer_id,
+                PeerInfo {
+                    last_announce: Some(peer.announcement.clone()),
+                    state: PeerState::Trusted,
+                },
+            );
+        }
+        self.known = end;
+    }
+
+    fn category(peer: &SimulatedPeer) -> Option<String> {
+        peer.bootstrap.then(|| BOOTSTRAP_CATEGORY.to_string())
+    }
+
+    fn run_round(&mut self, round: usize, churn: &Churn) {
+        self.complete_connections(churn);
+        self.apply_churn(round, churn);
+        self.discover(DISCOVERED_PEERS_PER_ROUND);
+        self.dial(churn);
+        self.check_invariants();
+    }
+
+    /// The connections in progress complete their handshake, or fail
+    fn complete_connections(&mut self, churn: &Churn) {
+        let mut peer_db = self.peer_db.write();
+        for addr in std::mem::take(&mut self.queue) {
+            let peer = &self.peers[self.peer_by_addr[&addr]];
+            if self.rng.gen_bool(churn.handshake_failure) {
+                peer_db.set_try_connect_failure_or_insert(&addr);
+                if let Some(info) = peer_db.peers.get_mut(&peer.peer_id) {
+                    info.state = PeerState::HandshakeFailed;
+                }
+                continue;
+            }
+            peer_db.set_try_connect_success_or_insert(&addr);
+            if let Some(info) = peer_db.peers.get_mut(&peer.peer_id) {
+                info.state = PeerState::Trusted;
+                info.last_announce = Some(peer.announcement.clone());
+            }
+            peer_db.record_handshake(
+                &peer.peer_id,
+                Duration::from_millis(self.rng.gen_range(10..200)),
+            );
+            self.connected.insert(
+                peer.peer_id,
+                (addr, PeerConnectionType::OUT, Self::category(peer)),
+            );
+        }
+    }
+
+    /// Peers disconnect, get banned and unbanned, and the peers whose handshake failed are tested again
+    fn apply_churn(&mut self, round: usize, churn: &Churn) {
+        let rng = &mut self.rng;
+        self.connected
+            .retain(|_, _| !rng.gen_bool(churn.disconnect));
+
+        let mut peer_db = self.peer_db.write();
+        if self.rng.gen_bool(churn.ban) {
+            let peer = &self.peers[self.rng.gen_range(0..self.known)];
+            peer_db.ban_peer(&peer.peer_id);
+            self.connected.remove(&peer.peer_id);
+            self.queue.remove(&peer.addr);
+        }
+        if round % UNBAN_EVERYONE_ROUNDS == 0 {
+            for peer in &self.peers[..self.known] {
+                if peer_db.peers[&peer.peer_id].state == PeerState::Banned {
+                    peer_db.unban_peer(&peer.peer_id);
+                }
+            }
+        }
+        for peer in &self.peers[..self.known] {
+            let info = peer_db.peers.get_mut(&peer.peer_id).unwrap();
+            if info.state == PeerState::HandshakeFailed && self.rng.gen_bool(0.5) {
+                info.state = PeerState::Trusted;
+            }
+        }
+    }
+
+    /// Dial the peers selected by the outbound connection logic
+    fn dial(&mut self, churn: &Churn) {
+        let ChurnSimulation {
+            config,
+            peer_categories,
+            dialer,
+            peer_db,
+            peers,
+            peer_by_addr,
+            connected,
+            queue,
+            rng,
+            ..
+        } = self;
+        let shared_peer_db: SharedPeerDB = peer_db.clone();
+        let mut dialed = Vec::new();
+        let mut in_progress = Vec::new();
+        connect_to_peers(
+            config,
+            peer_categories,
+            dialer,
+            &shared_peer_db,
+            connected,
+            queue,
+            |addr| {
+                let peer = &peers[peer_by_addr[&addr]];
+                assert!(!dialed.contains(&addr), "{} dialed twice", addr);
+                assert!(
+                    !connected.contains_key(&peer.peer_id) && !queue.contains(&addr),
+                    "{} dialed while connected",
+                    addr
+                );
+                assert_eq!(
+                    peer_db.read().peers[&peer.peer_id].state,
+                    PeerState::Trusted,
+                    "untrusted peer {} dialed",
+                    addr
+                );
+                dialed.push(addr);
+                let mut peer_db = peer_db.write();
+                peer_db.set_try_connect_success_or_insert(&addr);
+                if peer.reachable && !rng.gen_bool(churn.connect_timeout) {
+                    in_progress.push(addr);
+                    Ok(())
+                } else {
+                    peer_db.set_try_connect_failure_or_insert(&addr);
+                    Err(ProtocolError::GeneralProtocolError(
+                        "connection timed out".to_string(),
+                    ))
+                }
+            },
+        );
+        queue.extend(in_progress);
+    }
+
+    /// Outbound connections (established or in progress) per category, `None` being the default category
+    fn outbound_per_category(&self) -> HashMap<Option<String>, Vec<SocketAddr>> {
+        let mut outbound: HashMap<Option<String>, Vec<SocketAddr>> = HashMap::new();
+        for (addr, _, category) in self.connected.values() {
+            outbound.entry(category.clone()).or_default().push(*addr);
+        }
+        for addr in &self.queue {
+            let peer = &self.peers[self.peer_by_addr[addr]];
+            outbound
+                .entry(Self::category(peer))
+                .or_default()
+                .push(*addr);
+        }
+        outbound
+    }
+
+    fn check_invariants(&self) {
+        // the quotas of the categories and network groups are respected
+        let outbound = self.outbound_per_category();
+        let default_outbound = outbound.get(&None::<String>).cloned().unwrap_or_default();
+        assert!(default_outbound.len() <= DEFAULT_TARGET_OUT_CONNECTIONS);
+        assert!(
+            outbound
+                .get(&Some(BOOTSTRAP_CATEGORY.to_string()))
+                .map_or(0, |addrs| addrs.len())
+                <= BOOTSTRAP_TARGET_OUT_CONNECTIONS
+        );
+        assert!(self
+            .dialer
+            .outbound_group_counts(default_outbound.iter())
+            .values()
+            .all(|count| *count <= MAX_PER_GROUP));
+
+        // the banned peers are disconnected
+        let peer_db = self.peer_db.read();
+        for peer_id in self.connected.keys() {
+            assert_ne!(peer_db.peers[peer_id].state, PeerState::Banned);
+        }
+
+        // the peer database only holds the known peers
+        let known_ids: HashSet<PeerId> = self.peers[..self.known]
+            .iter()
+            .map(|peer| peer.peer_id)
+            .collect();
+        let known_addrs: HashSet<SocketAddr> = self.peers[..self.known]
+            .iter()
+            .map(|peer| peer.addr)
+            .collect();
+        assert_eq!(peer_db.peers.len(), self.known);
+        assert!(peer_db.peers.keys().all(|id| known_ids.contains(id)));
+        assert!(peer_db.records.keys().all(|id| known_ids.contains(id)));
+        assert!(peer_db
+            .try_connect_history
+            .keys()
+            .all(|addr| known_addrs.contains(addr)));
+        assert!(peer_db.peers_in_test.is_empty());
+    }
+}
+
+#[test]
+fn test_outbound_connections_under_churn() {
+    let mut simulation = ChurnSimulation::new(42);
+    for round in 1..=CHURN_ROUNDS {
+        simulation.run_round(round, &CHURN);
+    }
+    for round in CHURN_ROUNDS + 1..=CHURN_ROUNDS + QUIET_ROUNDS {
+        simulation.run_round(round, &QUIET);
+    }
+
+    // once the churn stops, every category is filled up to its target
+    let outbound = simulation.outbound_per_category();
+    assert_eq!(
+        outbound[&None::<String>].len(),
+        DEFAULT_TARGET_OUT_CONNECTIONS
+    );
+    assert_eq!(
+        outbound[&Some(BOOTSTRAP_CATEGORY.to_string())].len(),
+        BOOTSTRAP_TARGET_OUT_CONNECTIONS
+    );
+
+    // the records of the peers that were not seen recently are evicted, except the banned ones
+    let mut peer_db = simulation.peer_db.write();
+    assert!(!peer_db.records.is_empty());
+    peer_db.evict_peer_records(MassaTime::now().saturating_add(MassaTime::from_millis(1)));
+    assert!(peer_db.records.values().all(|record| record.banned));
+}
@@ -0,0 +1,287 @@
+use std::{
+    collections::HashSet,
+    sync::{mpsc, Arc, Mutex},
+    time::Duration,
+};
+
+use massa_protocol_exports::{
+    MessageRateLimit, PeerConnectionType, PeerId, ProtocolConfig, TopicConfig, TopicHandler,
+    TopicPropagation, TopicVerdict,
+};
+use massa_signature::KeyPair;
+use massa_test_framework::TestUniverse;
+
+use crate::{
+    capabilities::{Capabilities, NegotiatedCapabilities, PROTOCOL_VERSION},
+    handlers::topic_handler::{cache::payload_id, TopicMessage},
+    messages::Message,
+    wrap_network::MockActiveConnectionsTraitWrapper,
+};
+
+use super::universe::{ProtocolForeignControllers, ProtocolTestUniverse};
+
+const TOPIC: u64 = 7;
+
+/// Accepts the payloads, forwarding them to the test
+struct ForwardingHandler(Mutex<mpsc::Sender<(PeerId, Vec<u8>)>>);
+
+impl TopicHandler for ForwardingHandler {
+    fn handle_payload(&self, from: &PeerId, payload: &[u8]) -> TopicVerdict {
+        self.0
+            .lock()
+            .unwrap()
+            .send((*from, payload.to_vec()))
+            .unwrap();
+        TopicVerdict::Accept
+    }
+}
+
+/// Active connections of peers supporting the topics, forwarding the messages sent to them to the test
+fn topic_peers_connections(
+    peer_ids: HashSet<PeerId>,
+    sent: mpsc::Sender<(PeerId, TopicMessage)>,
+) -> MockActiveConnectionsTraitWrapper {
+    let mut shared_active_connections = MockActiveConnectionsTraitWrapper::new();
+    let sent = Mutex::new(sent);
+    shared_active_connections.set_expectations(move |active_connections| {
+        let connected = peer_ids.clone();
+        active_connections
+            .expect_get_peer_ids_connected()
+            .returning(move || connected.clone());
+        active_connections
+            .expect_shutdown_connection()
+            .returning(|_| ());
+        active_connections
+            .expect_get_peer_capabilities()
+            .returning(|_| {
+                Some(NegotiatedCapabilities {
+                    protocol_version: PROTOCOL_VERSION,
+                    capabilities: Capabilities::TOPICS,
+                })
+            });
+        active_connections
+            .expect_get_peers_connected()
+            .returning(move || {
+                peer_ids
+                    .iter()
+                    .map(|peer_id| {
+                        (
+                            *peer_id,
+                            (
+                                "127.0.0.1:8080".parse().unwrap(),
+                                PeerConnectionType::OUT,
+                                None,
+                            ),
+                        )
+                    })
+                    .collect()
+            });
+        active_connections.expect_send_to_peer().returning(
+            move |peer_id, _, message, high_priority| {
+                if let Message::Topic(message) = message {
+                    assert!(!high_priority);
+                    sent.lock().unwrap().send((*peer_id, message)).unwrap();
+                }
+                Ok(())
+            },
+        );
+    });
+    shared_active_connections
+}
+
+fn recv_sent(receiver: &mpsc::Receiver<(PeerId, TopicMessage)>) -> (PeerId, TopicMessage) {
+    receiver
+        .recv_timeout(Duration::from_secs(5))
+        .expect("no topic message sent")
+}
+
+#[test]
+fn test_protocol_gossips_topic_payloads() {
+    let node_a_peer_id = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+    let node_b_peer_id = PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key());
+    let (sent_tx, sent_rx) = mpsc::channel();
+    let (handled_tx, handled_rx) = mpsc::channel();
+
+    let mut foreign_controllers = ProtocolForeignControllers::new_with_mocks();
+    ProtocolTestUniverse::peer_db_boilerplate(&mut foreign_controllers.peer_db.write());
+    let shared_active_connections =
+        topic_peers_connections(HashSet::from([node_a_peer_id, node_b_peer_id]), sent_tx);
+    foreign_controllers
+        .network_controller
+        .expect_get_active_connections()
+        .returning(move || Box::new(shared_active_connections.clone()));
+    let universe = ProtocolTestUniverse::new(foreign_controllers, ProtocolConfig::default());
+
+    universe
+        .module_controller
+        .register_topic(
+            TopicConfig {
+                topic: TOPIC,
+                max_payload_size: 64,
+                max_kept_payloads: 16,
+                rate_limit: MessageRateLimit { rate: 0, burst: 0 },
+                propagation: TopicPropagation::Flood,
+            },
+            Arc::new(ForwardingHandler(Mutex::new(handled_tx))),
+        )
+        .unwrap();
+
+    // a published payload is flooded to the peers
+    universe
+        .module_controller
+        .publish_on_topic(TOPIC, b"published".to_vec())
+        .unwrap();
+    let mut receivers = HashSet::new();
+    for _ in 0..2 {
+        let (peer_id, message) = recv_sent(&sent_rx);
+        assert_eq!(
+            message,
+            TopicMessage::Payloads {
+                topic: TOPIC,
+                payloads: vec![b"published".to_vec()],
+            }
+        );
+        receivers.insert(peer_id);
+    }
+    assert_eq!(receivers, HashSet::from([node_a_peer_id, node_b_peer_id]));
+
+    // a payload received from a peer is passed to the handler and relayed to the other peers
+    universe.mock_message_receive(
+        &node_a_peer_id,
+        Message::Topic(TopicMessage::Payloads {
+            topic: TOPIC,
+            payloads: vec![b"received".to_vec()],
+        }),
+    );
+    assert_eq!(
+        handled_rx.recv_timeout(Duration::from_secs(5)).unwrap(),
+        (node_a_peer_id, b"received".to_vec())
+    );
+    assert_eq!(
+        recv_sent(&sent_rx),
+        (
+            node_b_peer_id,
+            TopicMessage::Payloads {
+                topic: TOPIC,
+                payloads: vec![b"received".to_vec()],
+            }
+        )
+    );
+
+    // the payloads missing from the digest of a peer are asked for
+    let missed = payload_id(TOPIC, b"missed");
+    universe.mock_message_receive(
+        &node_a_peer_id,
+        Message::Topic(TopicMessage::Digest {
+            topic: TOPIC,
+            ids: vec![missed, payload_id(TOPIC, b"received")],
+        }),
+    );
+    assert_eq!(
+        recv_sent(&sent_rx),
+        (
+            node_a_peer_id,
+            TopicMessage::AskForPayloads {
+                topic: TOPIC,
+                ids: vec![missed],
+            }
+        )
+    );
+
+    // the kept payloads are sent to the peers asking for them
+    universe.mock_message_receive(
+        &node_b_peer_id,
+        Message::Topic(TopicMessage::AskForPayloads {
+            topic: TOPIC,
+            ids: vec![payload_id(TOPIC, b"published"), missed],
+        }),
+    );
+    assert_eq!(
+        recv_sent(&sent_rx),
+        (
+            node_b_peer_id,
+            TopicMessage::Payloads {
+                topic: TOPIC,
+                payloads: vec![b"published".to_vec()],
+            }
+        )
+    );
+}
+
+#[test]
+fn test_protocol_follows_topic_propagation_policies() {
+    let peer_ids: HashSet<PeerId> = (0..3)
+        .map(|_| PeerId::from_public_key(KeyPair::generate(0).unwrap().get_public_key()))
+        .collect();
+    let (sent_tx, sent_rx) = mpsc::channel();
+    let (handled_tx, _handled_rx) = mpsc::channel();
+    let handler = Arc::new(ForwardingHandler(Mutex::new(handled_tx)));
+
+    let mut foreign_controllers = ProtocolForeignControllers::new_with_mocks();
+    ProtocolTestUniverse::peer_db_boilerplate(&mut foreign_controllers.peer_db.write());
+    let shared_active_connections = topic_peers_connections(peer_ids.clone(), sent_tx);
+    foreign_controllers
+        .network_controller
+        .expect_get_active_connections()
+        .returning(move || Box::new(shared_active_connections.clone()));
+    let universe = ProtocolTestUniverse::new(foreign_controllers, ProtocolConfig::default());
+    let topic_config = |topic, propagation| TopicConfig {
+        topic,
+        max_payload_size: 64,
+        max_kept_payloads: 16,
+        rate_limit: MessageRateLimit { rate: 0, burst: 0 },
+        propagation,
+    };
+
+    // only the ID of a payload published on an announced topic is sent to the peers
+    universe
+        .module_controller
+        .register_topic(
+            topic_config(TOPIC, TopicPropagation::Announce),
+            handler.clone(),
+        )
+        .unwrap();
+    universe
+        .module_controller
+        .publish_on_topic(TOPIC, b"announced".to_vec())
+        .unwrap();
+    let mut receivers = HashSet::new();
+    for _ in 0..3 {
+        let (peer_id, message) = recv_sent(&sent_rx);
+        assert_eq!(
+            message,
+            TopicMessage::Digest {
+                topic: TOPIC,
+                ids: vec![payload_id(TOPIC, b"announced")],
+            }
+        );
+        receivers.insert(peer_id);
+    }
+    assert_eq!(receivers, peer_ids);
+
+    // a payload published on a topic with a fanout of 1 is pushed to a single peer
+    universe
+        .module_controller
+        .register_topic(
+            topic_config(TOPIC + 1, TopicPropagation::Fanout(1)),
+            handler,
+        )
+        .unwrap();
+    universe
+        .module_controller
+        .publish_on_topic(TOPIC + 1, b"fanned out".to_vec())
+        .unwrap();
+    let (peer_id, message) = recv_sent(&sent_rx);
+    assert!(peer_ids.contains(&peer_id));
+    assert_eq!(
+        message,
+        TopicMessage::Payloads {
+            topic: TOPIC + 1,
+            payloads: vec![b"fanned out".to_vec()],
+        }
+    );
+    // the other peers only learn about it from the digests
+    while let Ok((_, message)) = sent_rx.recv_timeout(Duration::from_millis(500)) {
+        assert!(!matches!(message, TopicMessage::Payloads { .. }));
+    }
+}
@@ -2,7 +2,7 @@ use massa_channel::MassaChannel;
 use massa_consensus_exports::{ConsensusController, MockConsensusController};
 use massa_models::config::MIP_STORE_STATS_BLOCK_CONSIDERED;
 use massa_pool_exports::{MockPoolControllerWrapper, PoolController};
-use massa_pos_exports::{MockSelectorControllerWrapper, SelectorController};
+use massa_pos_exports::{MockSelectorControllerWrapper, PosError, SelectorController};
 use massa_protocol_exports::{
     PeerCategoryInfo, PeerConnectionType, PeerId, ProtocolConfig, ProtocolController,
     ProtocolError, ProtocolManager,
@@ -15,11 +15,11 @@ use parking_lot::{RwLock, RwLockWriteGuard};
 use peernet::messages::{MessagesHandler as _, MessagesSerializer as _};
 use std::{
     collections::{HashMap, HashSet},
-    fs::read_to_string,
     sync::Arc,
 };
 
 use crate::{
+    connection_events::ConnectionTracker,
     connectivity::start_connectivity_thread,
     create_protocol_controller,
     handlers::{
@@ -27,6 +27,7 @@ use crate::{
         endorsement_handler::EndorsementMessageSerializer,
         operation_handler::OperationMessageSerializer,
         peer_handler::{models::SharedPeerDB, PeerManagementMessageSerializer},
+        topic_handler::TopicMessageSerializer,
     },
     manager::ProtocolManagerImpl,
     messages::{Message, MessagesHandler, MessagesSerializer},
@@ -37,7 +38,7 @@ use massa_metrics::MassaMetrics;
 use massa_versioning::versioning::{MipStatsConfig, MipStore};
 use num::rational::Ratio;
 use std::ops::Bound::Included;
-use tracing::{debug, log::warn};
+use tracing::debug;
 
 pub struct ProtocolTestUniverse {
     pub module_controller: Box<dyn ProtocolController>,
@@ -58,10 +59,17 @@ pub struct ProtocolForeignControllers {
 
 impl ProtocolForeignControllers {
     pub fn new_with_mocks() -> Self {
+        let mut selector_controller = Box::new(MockSelectorControllerWrapper::new());
+        // no draws are available, so the block producer pre-filter is skipped
+        selector_controller.set_expectations(|selector_controller| {
+            selector_controller
+                .expect_get_producer()
+                .returning(|slot| Err(PosError::CycleUnavailable(slot.period)));
+        });
         Self {
             consensus_controller: Box::new(MockConsensusController::new()),
             pool_controller: Box::new(MockPoolControllerWrapper::new()),
-            selector_controller: Box::new(MockSelectorControllerWrapper::new()),
+            selector_controller,
             network_controller: Box::new(MockNetworkController::new()),
             peer_db: Arc::new(RwLock::new(MockPeerDBTrait::new())),
         }
@@ -93,7 +101,8 @@ impl TestUniverse for ProtocolTestUniverse {
                 .with_block_message_serializer(BlockMessageSerializer::new())
                 .with_endorsement_message_serializer(EndorsementMessageSerializer::new())
                 .with_operation_message_serializer(OperationMessageSerializer::new())
-                .with_peer_management_message_serializer(PeerManagementMessageSerializer::new()),
+                .with_peer_management_message_serializer(PeerManagementMessageSerializer::new())
+                .with_topic_message_serializer(TopicMessageSerializer::new()),
             storage,
             module_manager: protocol_manager,
         };
@@ -143,6 +152,9 @@ impl ProtocolTestUniverse {
             mock_active_connections
                 .expect_shutdown_connection()
                 .returning(move |_| ());
+            mock_active_connections
+                .expect_get_peer_capabilities()
+                .returning(|_| None);
             mock_active_connections
                 .expect_get_peers_connected()
                 .returning(move || {
@@ -181,21 +193,7 @@ pub fn start_protocol_controller_with_mock_network(
     ),
     ProtocolError,
 > {
-    // try to read node keypair from file, otherwise generate it & write to file. Then derive nodeId
-    let keypair = if std::path::Path::is_file(&config.keypair_file) {
-        // file exists: try to load it
-        let keypair_bs58_check_encoded = read_to_string(&config.keypair_file).map_err(|err| {
-            std::io::Error::new(err.kind(), format!("could not load node key file: {}", err))
-        })?;
-        serde_json::from_slice::<KeyPair>(keypair_bs58_check_encoded.as_bytes())?
-    } else {
-        // node file does not exist: generate the key and save it
-        let keypair = KeyPair::generate(0).unwrap();
-        if let Err(e) = std::fs::write(&config.keypair_file, serde_json::to_string(&keypair)?) {
-            warn!("could not generate node key file: {}", e);
-        }
-        keypair
-    };
+    let keypair = KeyPair::generate(0).unwrap();
     debug!("starting protocol controller with mock network");
 
     let (sender_operations, receiver_operations) = MassaChannel::new(
@@ -214,6 +212,10 @@ pub fn start_protocol_controller_with_mock_network(
         "peers".to_string(),
         Some(config.max_size_channel_network_to_peer_handler),
     );
+    let (sender_topics, receiver_topics) = MassaChannel::new(
+        "topics".to_string(),
+        Some(config.max_size_channel_network_to_topic_handler),
+    );
 
     // Register channels for handlers
     let message_handlers: MessagesHandler = MessagesHandler {
@@ -221,7 +223,12 @@ pub fn start_protocol_controller_with_mock_network(
         sender_endorsements: sender_endorsements.clone(),
         sender_operations: sender_operations.clone(),
         sender_peers: sender_peers.clone(),
+        sender_topics: sender_topics.clone(),
         id_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+        capture: None,
+        max_decompressed_message_size: None,
+        rate_limiter: None,
+        bandwidth_budgets: None,
     };
 
     let (controller, channels) = create_protocol_controller(config.clone());
@@ -242,9 +249,11 @@ pub fn start_protocol_controller_with_mock_network(
         (sender_endorsements, receiver_endorsements),
         (sender_operations, receiver_operations),
         (sender_peers, receiver_peers),
+        (sender_topics, receiver_topics),
         HashMap::default(),
         peer_db,
         storage,
+        ConnectionTracker::new_shared(channels.broadcasts.clone()),
         channels,
         message_handlers.clone(),
         HashMap::default(),
@@ -265,7 +274,7 @@ pub fn start_protocol_controller_with_mock_network(
         .0,
     )?;
 
-    let manager = ProtocolManagerImpl::new(connectivity_thread_handle);
+    let manager = ProtocolManagerImpl::new(connectivity_thread_handle, None);
 
     Ok((message_handlers, controller, Box::new(manager)))
 }
@@ -5,27 +5,26 @@ use massa_models::node::NodeId;
 use massa_pool_exports::PoolController;
 use massa_pos_exports::SelectorController;
 use massa_protocol_exports::{
-    BootstrapPeers, PeerData, PeerId, ProtocolConfig, ProtocolController, ProtocolError,
-    ProtocolManager,
+    BootstrapPeers, FinalityProvider, PeerData, PeerId, ProtocolBroadcasts, ProtocolConfig,
+    ProtocolController, ProtocolError, ProtocolManager,
 };
 use massa_serialization::U64VarIntDeserializer;
 use massa_signature::KeyPair;
 use massa_storage::Storage;
-use massa_time::MassaTime;
-use massa_versioning::{
-    keypair_factory::KeyPairFactory,
-    versioning::MipStore,
-    versioning_factory::{FactoryStrategy, VersioningFactory},
-};
+use massa_versioning::versioning::MipStore;
 use parking_lot::RwLock;
 use peernet::{
     config::{PeerNetCategoryInfo, PeerNetConfiguration},
     network_manager::PeerNetManager,
 };
-use std::{collections::HashMap, fs::read_to_string, ops::Bound::Included, sync::Arc};
-use tracing::{debug, log::warn};
+use std::{collections::HashMap, ops::Bound::Included, sync::Arc};
+use tracing::{debug, info};
 
 use crate::{
+    bandwidth::BandwidthBudgets,
+    capture::TrafficCapture,
+    compression::MessageCompression,
+    connection_events::ConnectionTracker,
     connectivity::{start_connectivity_thread, ConnectivityCommand},
     context::Context,
     controller::ProtocolControllerImpl,
@@ -46,10 +45,13 @@ use crate::{
             models::{PeerDB, PeerManagementCmd},
             MassaHandshake,
         },
+        topic_handler::commands::TopicHandlerCommand,
     },
     ip::to_canonical,
     manager::ProtocolManagerImpl,
     messages::MessagesHandler,
+    port_mapping::PortMapper,
+    rate_limit::MessageRateLimiter,
     wrap_network::NetworkControllerImpl,
 };
 
@@ -86,6 +88,11 @@ pub struct ProtocolChannels {
         MassaSender<PeerManagementCmd>,
         MassaReceiver<PeerManagementCmd>,
     ),
+    pub topic_handler: (
+        MassaSender<TopicHandlerCommand>,
+        MassaReceiver<TopicHandlerCommand>,
+    ),
+    pub broadcasts: ProtocolBroadcasts,
 }
 
 /// This function exists because consensus need the protocol controller and we need consensus controller.
@@ -131,6 +138,10 @@ pub fn create_protocol_controller(
         "peer_management_ext".to_string(),
         Some(config.max_size_channel_commands_peers),
     );
+    let (sender_topics_ext, receiver_topics_ext) = MassaChannel::new(
+        "topics_ext".to_string(),
+        Some(config.max_size_channel_commands_topics),
+    );
     (
         Box::new(ProtocolControllerImpl::new(
             sender_blocks_retrieval_ext.clone(),
@@ -139,6 +150,7 @@ pub fn create_protocol_controller(
             sender_endorsements_propagation_ext.clone(),
             sender_connectivity_ext.clone(),
             sender_peer_management_ext.clone(),
+            sender_topics_ext.clone(),
         )),
         ProtocolChannels {
             operation_handler_retrieval: (
@@ -164,6 +176,10 @@ pub fn create_protocol_controller(
             ),
             connectivity_thread: (sender_connectivity_ext, receiver_connectivity_ext),
             peer_management_handler: (sender_peer_management_ext, receiver_peer_management_ext),
+            topic_handler: (sender_topics_ext, receiver_topics_ext),
+            broadcasts: ProtocolBroadcasts::new(
+                config.broadcast_connection_events_channel_capacity,
+            ),
         },
     )
 }
@@ -175,9 +191,10 @@ pub fn create_protocol_controller(
 /// * `consensus_controller`: interact with consensus module
 /// * `bootstrap_peers`: list of peers to connect to retrieved from the bootstrap
 /// * `storage`: Shared storage to fetch data that are fetch across all modules
+/// * `finality_provider`: source of the final state hashes compared with the ones of the peers during the handshake
 #[allow(clippy::too_many_arguments)]
 pub fn start_protocol_controller(
-    config: ProtocolConfig,
+    mut config: ProtocolConfig,
     selector_controller: Box<dyn SelectorController>,
     consensus_controller: Box<dyn ConsensusController>,
     bootstrap_peers: Option<BootstrapPeers>,
@@ -186,7 +203,9 @@ pub fn start_protocol_controller(
     protocol_channels: ProtocolChannels,
     mip_store: MipStore,
     massa_metrics: MassaMetrics,
-) -> Result<(Box<dyn ProtocolManager>, KeyPair, NodeId), ProtocolError> {
+    keypair: KeyPair,
+    finality_provider: Option<Arc<dyn FinalityProvider>>,
+) -> Result<(Box<dyn ProtocolManager>, NodeId), ProtocolError> {
     debug!("starting protocol controller");
     let peer_db = Arc::new(RwLock::new(PeerDB::default()));
 
@@ -206,6 +225,35 @@ pub fn start_protocol_controller(
         "sender_peers".to_string(),
         Some(config.max_size_channel_network_to_peer_handler),
     );
+    let (sender_topics, receiver_topics) = MassaChannel::new(
+        "sender_topics".to_string(),
+        Some(config.max_size_channel_network_to_topic_handler),
+    );
+
+    let capture = match &config.traffic_capture_path {
+        Some(path) => {
+            info!("capturing inbound protocol traffic to {}", path.display());
+            Some(Arc::new(TrafficCapture::new(path)?))
+        }
+        None => None,
+    };
+
+    let initial_peers_infos = serde_json::from_str::<HashMap<PeerId, PeerData>>(
+        &std::fs::read_to_string(&config.initial_peers)?,
+    )?;
+
+    // the messages of the peers are rate limited according to the category of their IP
+    let ip_categories = initial_peers_infos
+        .values()
+        .filter(|data| config.peers_categories.contains_key(&data.category))
+        .flat_map(|data| {
+            data.listeners
+                .keys()
+                .map(|addr| (to_canonical(addr.ip()), data.category.clone()))
+        })
+        .collect();
+    let rate_limiter = Arc::new(MessageRateLimiter::new(&config, ip_categories));
+    let bandwidth_budgets = BandwidthBudgets::from_config(&config).map(Arc::new);
 
     // Register channels for handlers
     let message_handlers: MessagesHandler = MessagesHandler {
@@ -213,32 +261,37 @@ pub fn start_protocol_controller(
         sender_endorsements: sender_endorsements.clone(),
         sender_operations: sender_operations.clone(),
         sender_peers: sender_peers.clone(),
+        sender_topics: sender_topics.clone(),
         id_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+        capture,
+        max_decompressed_message_size: config
+            .message_compression
+            .then_some(config.max_decompressed_message_size),
+        rate_limiter: Some(rate_limiter),
+        bandwidth_budgets: bandwidth_budgets.clone(),
     };
 
-    // try to read node keypair from file, otherwise generate it & write to file. Then derive nodeId
-    let keypair = if std::path::Path::is_file(&config.keypair_file) {
-        // file exists: try to load it
-        let keypair_bs58_check_encoded = read_to_string(&config.keypair_file).map_err(|err| {
-            std::io::Error::new(err.kind(), format!("could not load node key file: {}", err))
-        })?;
-        serde_json::from_slice::<KeyPair>(keypair_bs58_check_encoded.as_bytes())?
+    // the public IP of the gateway is advertised if no routable IP is configured
+    let port_mapper = if config.port_mapping {
+        PortMapper::start(&config)
     } else {
-        // node file does not exist: generate the key and save it
-        // MERGE TODO
-        let keypair_factory = KeyPairFactory {
-            mip_store: mip_store.clone(),
-        };
-        let now = MassaTime::now();
-        let keypair = keypair_factory.create(&(), FactoryStrategy::At(now))?;
-        if let Err(e) = std::fs::write(&config.keypair_file, serde_json::to_string(&keypair)?) {
-            warn!("could not generate node key file: {}", e);
-        }
-        keypair
+        None
     };
+    if let Some(port_mapper) = &port_mapper {
+        if config.routable_ip.is_none() {
+            config.routable_ip = Some(port_mapper.external_ip());
+        }
+    }
 
+    let connection_tracker = ConnectionTracker::new_shared(protocol_channels.broadcasts.clone());
+    let mut handshake =
+        MassaHandshake::new(peer_db.clone(), config.clone(), connection_tracker.clone());
+    if let Some(finality_provider) = finality_provider {
+        handshake = handshake.with_finality_provider(finality_provider);
+    }
+    let peer_capabilities = handshake.peer_capabilities.clone();
     let mut peernet_config = PeerNetConfiguration::default(
-        MassaHandshake::new(peer_db.clone(), config.clone()),
+        handshake,
         message_handlers.clone(),
         Context {
             our_keypair: keypair.clone(),
@@ -250,10 +303,6 @@ pub fn start_protocol_controller(
     peernet_config.rate_limit = config.rate_limit;
     peernet_config.rate_bucket_size = config.rate_limit.saturating_mul(2);
 
-    let initial_peers_infos = serde_json::from_str::<HashMap<PeerId, PeerData>>(
-        &std::fs::read_to_string(&config.initial_peers)?,
-    )?;
-
     let initial_peers = if let Some(bootstrap_peers) = bootstrap_peers {
         //TODO: Remove when we will be able to test the bootstrap peer even if someone else found them full
         bootstrap_peers
@@ -314,9 +363,13 @@ pub fn start_protocol_controller(
     };
     peernet_config.max_in_connections = config.max_in_connections;
 
-    let network_controller = Box::new(NetworkControllerImpl::new(PeerNetManager::new(
-        peernet_config,
-    )));
+    let network_controller = Box::new(NetworkControllerImpl::new(
+        PeerNetManager::new(peernet_config),
+        MessageCompression::from_config(&config),
+        bandwidth_budgets,
+        peer_capabilities,
+        config.socks5_proxy.clone(),
+    ));
 
     let connectivity_thread_handle = start_connectivity_thread(
         PeerId::from_public_key(keypair.get_public_key()),
@@ -328,10 +381,12 @@ pub fn start_protocol_controller(
         (sender_endorsements, receiver_endorsements),
         (sender_operations, receiver_operations),
         (sender_peers, receiver_peers),
+        (sender_topics, receiver_topics),
         initial_peers,
         peer_db,
         storage,
         protocol_channels,
+        connection_tracker,
         message_handlers,
         config
             .peers_categories
@@ -369,11 +424,7 @@ pub fn start_protocol_controller(
         massa_metrics,
     )?;
 
-    let manager = ProtocolManagerImpl::new(connectivity_thread_handle);
+    let manager = ProtocolManagerImpl::new(connectivity_thread_handle, port_mapper);
 
-    Ok((
-        Box::new(manager),
-        keypair.clone(),
-        NodeId::new(keypair.get_public_key()),
-    ))
+    Ok((Box::new(manager), NodeId::new(keypair.get_public_key())))
 }
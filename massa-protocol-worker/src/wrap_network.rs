@@ -1,9 +1,13 @@
 use std::{
     collections::{HashMap, HashSet},
     net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
-use massa_protocol_exports::{PeerId, ProtocolError};
+use massa_protocol_exports::{PeerId, ProtocolError, Socks5ProxyConfig};
 use peernet::{
     network_manager::{PeerNetManager, SharedActiveConnections},
     peer::PeerConnectionType,
@@ -11,13 +15,17 @@ use peernet::{
 };
 
 use crate::{
+    bandwidth::{BandwidthBudgets, BudgetedMessagesSerializer, TrafficPriority},
+    capabilities::{Capabilities, NegotiatedCapabilities, SharedPeerCapabilities},
+    compression::{CompressingMessagesSerializer, MessageCompression},
     context::Context,
     handlers::peer_handler::MassaHandshake,
     messages::{Message, MessagesHandler, MessagesSerializer},
+    proxy_relay::open_proxy_relay,
 };
 
 #[cfg(test)]
-use std::sync::{Arc, RwLock};
+use std::sync::RwLock;
 
 #[cfg_attr(test, mockall_wrap::wrap, mockall::automock)]
 pub trait ActiveConnectionsTrait: Send + Sync {
@@ -38,6 +46,8 @@ pub trait ActiveConnectionsTrait: Send + Sync {
     fn get_nb_in_connections(&self) -> usize;
     fn shutdown_connection(&mut self, peer_id: &PeerId);
     fn get_peers_connections_bandwidth(&self) -> HashMap<String, (u64, u64)>;
+    /// Protocol version and capabilities negotiated with a peer during its handshake
+    fn get_peer_capabilities(&self, peer_id: &PeerId) -> Option<NegotiatedCapabilities>;
 }
 
 impl Clone for Box<dyn ActiveConnectionsTrait> {
@@ -46,7 +56,18 @@ impl Clone for Box<dyn ActiveConnectionsTrait> {
     }
 }
 
-impl ActiveConnectionsTrait for SharedActiveConnections<PeerId> {
+/// Active connections of the node,
+/// compressing the large messages sent to the peers that negotiated compression
+/// and enforcing the upload budgets of the peer categories
+#[derive(Clone)]
+pub struct MassaActiveConnections {
+    connections: SharedActiveConnections<PeerId>,
+    compression: Option<MessageCompression>,
+    bandwidth_budgets: Option<Arc<BandwidthBudgets>>,
+    peer_capabilities: SharedPeerCapabilities,
+}
+
+impl ActiveConnectionsTrait for MassaActiveConnections {
     fn send_to_peer(
         &self,
         peer_id: &PeerId,
@@ -54,11 +75,76 @@ impl ActiveConnectionsTrait for SharedActiveConnections<PeerId> {
         message: Message,
         high_priority: bool,
     ) -> Result<(), ProtocolError> {
-        if let Some(connection) = self.read().connections.get(peer_id) {
-            connection
-                .send_channels
-                .try_send(message_serializer, message, high_priority)
-                .map_err(|err| ProtocolError::SendError(err.to_string()))
+        let compression = self.compression.filter(|_| {
+            self.get_peer_capabilities(peer_id)
+                .map_or(false, |negotiated| {
+                    negotiated.supports(Capabilities::COMPRESSION)
+                })
+        });
+        if let Some(connection) = self.connections.read().connections.get(peer_id) {
+            let category = connection.category_name.as_deref();
+            let budgets = self
+                .bandwidth_budgets
+                .as_deref()
+                .filter(|budgets| budgets.limits_upload(category));
+            let Some(budgets) = budgets else {
+                return match compression {
+                    Some(compression) => connection.send_channels.try_send(
+                        &CompressingMessagesSerializer {
+                            serializer: message_serializer,
+                            compression,
+                        },
+                        message,
+                        high_priority,
+                    ),
+                    None => connection.send_channels.try_send(
+                        message_serializer,
+                        message,
+                        high_priority,
+                    ),
+                }
+                .map_err(|err| ProtocolError::SendError(err.to_string()));
+            };
+
+            // the messages that do not fit in the upload budget of the category are dropped
+            let priority = TrafficPriority::of_sent(&message, high_priority);
+            let (result, dropped) = match compression {
+                Some(compression) => {
+                    let serializer = BudgetedMessagesSerializer {
+                        serializer: &CompressingMessagesSerializer {
+                            serializer: message_serializer,
+                            compression,
+                        },
+                        budgets,
+                        category,
+                        priority,
+                        dropped: AtomicBool::new(false),
+                    };
+                    let result =
+                        connection
+                            .send_channels
+                            .try_send(&serializer, message, high_priority);
+                    (result, serializer.dropped.load(Ordering::Relaxed))
+                }
+                None => {
+                    let serializer = BudgetedMessagesSerializer {
+                        serializer: message_serializer,
+                        budgets,
+                        category,
+                        priority,
+                        dropped: AtomicBool::new(false),
+                    };
+                    let result =
+                        connection
+                            .send_channels
+                            .try_send(&serializer, message, high_priority);
+                    (result, serializer.dropped.load(Ordering::Relaxed))
+                }
+            };
+            match result {
+                Err(_) if dropped => Ok(()),
+                result => result.map_err(|err| ProtocolError::SendError(err.to_string())),
+            }
         } else {
             Err(ProtocolError::PeerDisconnected(peer_id.to_string()))
         }
@@ -69,13 +155,19 @@ impl ActiveConnectionsTrait for SharedActiveConnections<PeerId> {
     }
 
     fn get_peer_ids_connected(&self) -> HashSet<PeerId> {
-        self.read().connections.keys().cloned().collect()
+        self.connections
+            .read()
+            .connections
+            .keys()
+            .cloned()
+            .collect()
     }
 
     fn get_peers_connected(
         &self,
     ) -> HashMap<PeerId, (SocketAddr, PeerConnectionType, Option<String>)> {
-        self.read()
+        self.connections
+            .read()
             .connections
             .iter()
             .map(|(peer_id, connection)| {
@@ -92,29 +184,33 @@ impl ActiveConnectionsTrait for SharedActiveConnections<PeerId> {
     }
 
     fn get_nb_out_connections(&self) -> usize {
-        self.read().nb_out_connections
+        self.connections.read().nb_out_connections
     }
 
     fn get_nb_in_connections(&self) -> usize {
-        self.read().nb_in_connections
+        self.connections.read().nb_in_connections
     }
 
     fn shutdown_connection(&mut self, peer_id: &PeerId) {
-        if let Some(connection) = self.write().connections.get_mut(peer_id) {
+        if let Some(connection) = self.connections.write().connections.get_mut(peer_id) {
             connection.shutdown();
         }
     }
 
     fn get_peers_connections_bandwidth(&self) -> HashMap<String, (u64, u64)> {
         let mut map = HashMap::new();
-        for (peerid, conn) in self.read().connections.iter() {
+        for (peerid, conn) in self.connections.read().connections.iter() {
             map.insert(peerid.to_string(), conn.endpoint.get_bandwidth());
         }
         map
     }
 
     fn get_peer_ids_out_connection_queue(&self) -> HashSet<SocketAddr> {
-        self.read().out_connection_queue.clone()
+        self.connections.read().out_connection_queue.clone()
+    }
+
+    fn get_peer_capabilities(&self, peer_id: &PeerId) -> Option<NegotiatedCapabilities> {
+        self.peer_capabilities.read().get(peer_id).copied()
     }
 }
 
@@ -142,19 +238,38 @@ pub trait NetworkController: Send + Sync {
 
 pub struct NetworkControllerImpl {
     peernet_manager: PeerNetManager<PeerId, Context, MassaHandshake, MessagesHandler>,
+    compression: Option<MessageCompression>,
+    bandwidth_budgets: Option<Arc<BandwidthBudgets>>,
+    peer_capabilities: SharedPeerCapabilities,
+    socks5_proxy: Option<Socks5ProxyConfig>,
 }
 
 impl NetworkControllerImpl {
     pub fn new(
         peernet_manager: PeerNetManager<PeerId, Context, MassaHandshake, MessagesHandler>,
+        compression: Option<MessageCompression>,
+        bandwidth_budgets: Option<Arc<BandwidthBudgets>>,
+        peer_capabilities: SharedPeerCapabilities,
+        socks5_proxy: Option<Socks5ProxyConfig>,
     ) -> Self {
-        Self { peernet_manager }
+        Self {
+            peernet_manager,
+            compression,
+            bandwidth_budgets,
+            peer_capabilities,
+            socks5_proxy,
+        }
     }
 }
 
 impl NetworkController for NetworkControllerImpl {
     fn get_active_connections(&self) -> Box<dyn ActiveConnectionsTrait> {
-        Box::new(self.peernet_manager.active_connections.clone())
+        Box::new(MassaActiveConnections {
+            connections: self.peernet_manager.active_connections.clone(),
+            compression: self.compression,
+            bandwidth_budgets: self.bandwidth_budgets.clone(),
+            peer_capabilities: self.peer_capabilities.clone(),
+        })
     }
 
     fn start_listener(
@@ -182,6 +297,16 @@ impl NetworkController for NetworkControllerImpl {
         addr: SocketAddr,
         timeout: std::time::Duration,
     ) -> Result<(), ProtocolError> {
+        // route the connection through the proxy, if any
+        let addr = match &self.socks5_proxy {
+            Some(proxy) => open_proxy_relay(proxy, addr, timeout).map_err(|err| {
+                ProtocolError::GeneralProtocolError(format!(
+                    "could not connect to {} through the proxy: {}",
+                    addr, err
+                ))
+            })?,
+            None => addr,
+        };
         //TODO: Change when we support multiple transports
         self.peernet_manager
             .try_connect(TransportType::Tcp, addr, timeout)
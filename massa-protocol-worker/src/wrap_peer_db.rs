@@ -5,7 +5,7 @@ use std::{
     time::Duration,
 };
 
-use massa_protocol_exports::{PeerId, TransportType};
+use massa_protocol_exports::{PeerId, PeerRecord, TransportType};
 
 #[cfg_attr(test, mockall::automock)]
 pub trait PeerDBTrait: Send + Sync {
@@ -35,6 +35,10 @@ pub trait PeerDBTrait: Send + Sync {
     fn get_peers_in_test(&self) -> &HashSet<SocketAddr>;
     fn insert_tested_address(&mut self, addr: &SocketAddr, time: massa_time::MassaTime);
     fn get_tested_addresses(&self) -> &HashMap<SocketAddr, massa_time::MassaTime>;
+    fn record_handshake(&mut self, peer_id: &PeerId, latency: Duration);
+    fn get_peer_records(&self) -> Vec<PeerRecord>;
+    fn restore_peer_records(&mut self, records: Vec<PeerRecord>);
+    fn evict_peer_records(&mut self, min_last_seen: massa_time::MassaTime) -> usize;
 }
 
 impl Clone for Box<dyn PeerDBTrait> {
@@ -81,6 +81,11 @@ impl OperationIndexes {
         self.operations.contains_key(id)
     }
 
+    /// Iterates over the IDs of all the operations in global storage.
+    pub fn ids(&self) -> impl Iterator<Item = &OperationId> {
+        self.operations.keys()
+    }
+
     /// Get operations created by an address
     /// Arguments:
     /// * `address`: the address to get the operations created by
@@ -3,7 +3,9 @@ use crate::{
     versioning_factory::{FactoryError, FactoryStrategy, VersioningFactory},
 };
 use massa_hash::Hash;
-use massa_models::address::{Address, SCAddress, SCAddressV0, UserAddress, UserAddressV0};
+use massa_models::address::{
+    Address, SCAddress, SCAddressV0, SCAddressV1, UserAddress, UserAddressV0,
+};
 
 #[derive(Clone)]
 pub struct AddressFactory {
@@ -42,6 +44,13 @@ impl VersioningFactory for AddressFactory {
                 }
                 AddressArgs::SC { hash } => Address::SC(SCAddress::SCAddressV0(SCAddressV0(*hash))),
             },
+            // SC addresses are assigned to a thread derived from their hash, user addresses are unchanged
+            1 => match args {
+                AddressArgs::User { hash } => {
+                    Address::User(UserAddress::UserAddressV0(UserAddressV0(*hash)))
+                }
+                AddressArgs::SC { hash } => Address::SC(SCAddress::SCAddressV1(SCAddressV1(*hash))),
+            },
             v => return Err(FactoryError::UnimplementedVersion(v)),
         };
 
@@ -1,10 +1,8 @@
-#[allow(unused_imports)]
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
 
-#[allow(unused_imports)]
 use massa_time::MassaTime;
 
-#[allow(unused_imports)]
 use crate::versioning::{MipComponent, MipInfo, MipState};
 
 pub fn get_mip_list() -> [(MipInfo, MipState); 0] {
@@ -30,3 +28,234 @@ pub fn get_mip_list() -> [(MipInfo, MipState); 0] {
     #[allow(clippy::let_and_return)]
     mip_list
 }
+
+/// Definition of a MIP, as written in a MIP list file
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct MipDefinition {
+    /// MIP name or descriptive name
+    pub name: String,
+    /// Network version announced in block headers once the MIP is deployed
+    pub version: u32,
+    /// Components concerned by the MIP (case insensitive names of `MipComponent` variants), and their new versions
+    pub components: BTreeMap<String, u32>,
+    /// Timestamp at which the MIP starts to be announced
+    pub start: MassaTime,
+    /// Timestamp at which the deployment is considered failed
+    pub timeout: MassaTime,
+    /// Delay between lock in and activation
+    pub activation_delay: MassaTime,
+}
+
+/// Content of a MIP list file
+#[derive(Debug, Clone, serde::Deserialize)]
+struct MipListFile {
+    #[serde(default)]
+    mips: Vec<MipDefinition>,
+}
+
+/// Errors when loading a MIP list from a file
+#[derive(thiserror::Error, Debug)]
+pub enum MipListError {
+    /// Could not read the MIP list file: {0}
+    #[error("could not read the MIP list file: {0}")]
+    Read(String),
+    /// Invalid MIP definition: {0}
+    #[error("invalid MIP definition: {0}")]
+    Invalid(String),
+}
+
+/// Reads a MIP list from a TOML file, for private networks staging their own upgrades.
+///
+/// The file holds a `[[mips]]` array of tables, each deserialized into a `MipDefinition`.
+/// The lock in threshold is network wide (`VERSIONING_THRESHOLD_TRANSITION_ACCEPTED`) and is not defined per MIP.
+pub fn get_mip_list_from_file(path: &Path) -> Result<Vec<(MipInfo, MipState)>, MipListError> {
+    let file: MipListFile = config::Config::builder()
+        .add_source(config::File::from(path).format(config::FileFormat::Toml))
+        .build()
+        .and_then(|c| c.try_deserialize())
+        .map_err(|err| MipListError::Read(format!("{}: {}", path.display(), err)))?;
+    get_mip_list_from_definitions(file.mips)
+}
+
+/// Validates MIP definitions and builds the corresponding MIP list.
+///
+/// Checks performed:
+/// - Names are not empty and are unique
+/// - Network versions are strictly increasing, starting above 0
+/// - Each MIP has at least one component
+/// - Each MIP starts before its timeout, and after the timeout of the previous MIP
+///
+/// The remaining checks (component versions, activation delay...) are done by the `MipStore` built from the list.
+pub fn get_mip_list_from_definitions(
+    definitions: Vec<MipDefinition>,
+) -> Result<Vec<(MipInfo, MipState)>, MipListError> {
+    let mut names = HashSet::with_capacity(definitions.len());
+    let mut previous: Option<&MipDefinition> = None;
+    for definition in definitions.iter() {
+        if definition.name.is_empty() {
+            return Err(MipListError::Invalid("empty MIP name".to_string()));
+        }
+        if !names.insert(definition.name.as_str()) {
+            return Err(MipListError::Invalid(format!(
+                "duplicate MIP name: {}",
+                definition.name
+            )));
+        }
+        if definition.components.is_empty() {
+            return Err(MipListError::Invalid(format!(
+                "{} has no component",
+                definition.name
+            )));
+        }
+        if definition.start >= definition.timeout {
+            return Err(MipListError::Invalid(format!(
+                "{} starts after its timeout",
+                definition.name
+            )));
+        }
+        let (min_version, min_start) = match previous {
+            Some(prev) => (prev.version.saturating_add(1), prev.timeout),
+            None => (1, MassaTime::from_millis(0)),
+        };
+        if definition.version < min_version {
+            return Err(MipListError::Invalid(format!(
+                "{} has version {}, expected at least {}",
+                definition.name, definition.version, min_version
+            )));
+        }
+        if definition.start < min_start {
+            return Err(MipListError::Invalid(format!(
+                "{} starts before the timeout of the previous MIP",
+                definition.name
+            )));
+        }
+        previous = Some(definition);
+    }
+
+    definitions
+        .into_iter()
+        .map(|definition| {
+            let components = definition
+                .components
+                .iter()
+                .map(|(name, version)| Ok((parse_mip_component(name)?, *version)))
+                .collect::<Result<BTreeMap<_, _>, MipListError>>()?;
+            Ok((
+                MipInfo {
+                    name: definition.name,
+                    version: definition.version,
+                    components,
+                    start: definition.start,
+                    timeout: definition.timeout,
+                    activation_delay: definition.activation_delay,
+                },
+                MipState::new(MassaTime::from_millis(0)),
+            ))
+        })
+        .collect()
+}
+
+/// Finds the `MipComponent` with the given name, ignoring case
+/// (configuration keys are lower cased when read)
+fn parse_mip_component(name: &str) -> Result<MipComponent, MipListError> {
+    // the last variant is the hidden __Nonexhaustive one
+    (0..(MipComponent::VARIANT_COUNT - 1) as u32)
+        .map(MipComponent::from)
+        .find(|component| format!("{:?}", component).eq_ignore_ascii_case(name))
+        .ok_or_else(|| MipListError::Invalid(format!("unknown MIP component: {}", name)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::versioning::{MipStatsConfig, MipStore};
+    use massa_models::config::{MIP_STORE_STATS_BLOCK_CONSIDERED, VERSIONING_ACTIVATION_DELAY_MIN};
+    use num::rational::Ratio;
+    use std::io::Write;
+
+    fn definition(name: &str, version: u32, start: u64, timeout: u64) -> MipDefinition {
+        MipDefinition {
+            name: name.to_string(),
+            version,
+            components: BTreeMap::from([("Address".to_string(), version)]),
+            start: MassaTime::from_millis(start),
+            timeout: MassaTime::from_millis(timeout),
+            activation_delay: VERSIONING_ACTIVATION_DELAY_MIN,
+        }
+    }
+
+    #[test]
+    fn test_mip_list_from_definitions() {
+        let mip_list = get_mip_list_from_definitions(vec![
+            definition("MIP-0001", 1, 1000, 2000),
+            definition("MIP-0002", 2, 2000, 3000),
+        ])
+        .unwrap();
+        assert_eq!(mip_list.len(), 2);
+        assert_eq!(mip_list[1].0.name, "MIP-0002");
+
+        let mip_stats_config = MipStatsConfig {
+            block_count_considered: MIP_STORE_STATS_BLOCK_CONSIDERED,
+            warn_announced_version_ratio: Ratio::new_raw(30, 100),
+        };
+        let mip_store = MipStore::try_from((mip_list, mip_stats_config)).unwrap();
+        assert_eq!(mip_store.get_mip_status().len(), 2);
+
+        // duplicate name
+        assert!(get_mip_list_from_definitions(vec![
+            definition("MIP-0001", 1, 1000, 2000),
+            definition("MIP-0001", 2, 2000, 3000),
+        ])
+        .is_err());
+        // version not increasing
+        assert!(get_mip_list_from_definitions(vec![
+            definition("MIP-0001", 2, 1000, 2000),
+            definition("MIP-0002", 2, 2000, 3000),
+        ])
+        .is_err());
+        // overlapping time ranges
+        assert!(get_mip_list_from_definitions(vec![
+            definition("MIP-0001", 1, 1000, 2000),
+            definition("MIP-0002", 2, 1500, 3000),
+        ])
+        .is_err());
+        // unknown component
+        let mut unknown_component = definition("MIP-0001", 1, 1000, 2000);
+        unknown_component
+            .components
+            .insert("Unknown".to_string(), 1);
+        assert!(get_mip_list_from_definitions(vec![unknown_component]).is_err());
+        // timeout before start
+        assert!(
+            get_mip_list_from_definitions(vec![definition("MIP-0001", 1, 2000, 1000)]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_mip_list_from_file() {
+        let mut file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        write!(
+            file,
+            r#"
+[[mips]]
+    name = "MIP-0001"
+    version = 1
+    start = 1000
+    timeout = 2000
+    activation_delay = {}
+    [mips.components]
+        address = 1
+        KeyPair = 1
+"#,
+            VERSIONING_ACTIVATION_DELAY_MIN.as_millis()
+        )
+        .unwrap();
+
+        let mip_list = get_mip_list_from_file(file.path()).unwrap();
+        assert_eq!(mip_list.len(), 1);
+        assert_eq!(
+            mip_list[0].0.components,
+            BTreeMap::from([(MipComponent::Address, 1), (MipComponent::KeyPair, 1)])
+        );
+    }
+}
@@ -770,6 +770,16 @@ impl<const N: usize> TryFrom<([(MipInfo, MipState); N], MipStatsConfig)> for Mip
     }
 }
 
+impl TryFrom<(Vec<(MipInfo, MipState)>, MipStatsConfig)> for MipStore {
+    type Error = UpdateWithError;
+
+    fn try_from(
+        (value, cfg): (Vec<(MipInfo, MipState)>, MipStatsConfig),
+    ) -> Result<Self, Self::Error> {
+        MipStoreRaw::try_from((value, cfg)).map(|store_raw| Self(Arc::new(RwLock::new(store_raw))))
+    }
+}
+
 /// Statistics in MipStoreRaw
 #[derive(Debug, Clone, PartialEq)]
 pub struct MipStatsConfig {
@@ -1555,6 +1565,16 @@ impl<const N: usize> TryFrom<([(MipInfo, MipState); N], MipStatsConfig)> for Mip
 
     fn try_from(
         (value, cfg): ([(MipInfo, MipState); N], MipStatsConfig),
+    ) -> Result<Self, Self::Error> {
+        Self::try_from((Vec::from(value), cfg))
+    }
+}
+
+impl TryFrom<(Vec<(MipInfo, MipState)>, MipStatsConfig)> for MipStoreRaw {
+    type Error = UpdateWithError;
+
+    fn try_from(
+        (value, cfg): (Vec<(MipInfo, MipState)>, MipStatsConfig),
     ) -> Result<Self, Self::Error> {
         // Build an empty store
         let mut store = Self {
@@ -1564,7 +1584,7 @@ impl<const N: usize> TryFrom<([(MipInfo, MipState); N], MipStatsConfig)> for Mip
 
         // Build another one with given value
         let other_store = Self {
-            store: BTreeMap::from(value),
+            store: BTreeMap::from_iter(value),
             stats: MipStoreStats::new(cfg),
         };
 
@@ -1586,7 +1606,9 @@ mod test {
     use super::*;
 
     use assert_matches::assert_matches;
-    use massa_db_exports::{MassaDBConfig, MassaDBController, MassaIteratorMode};
+    use massa_db_exports::{
+        MassaDBConfig, MassaDBController, MassaIteratorMode, STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
+    };
     use massa_db_worker::MassaDB;
     use more_asserts::{assert_gt, assert_le};
     use parking_lot::RwLock;
@@ -2544,6 +2566,7 @@ mod test {
             max_final_state_elements_size: 100_000,
             max_versioning_elements_size: 100_000,
             thread_count: THREAD_COUNT,
+            state_write_buffer_size: STATE_CF_DEFAULT_WRITE_BUFFER_SIZE,
         };
         let db = Arc::new(RwLock::new(
             Box::new(MassaDB::new(db_config)) as Box<(dyn MassaDBController + 'static)>
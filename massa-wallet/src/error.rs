@@ -24,4 +24,6 @@ pub enum WalletError {
     MissingKeyError(Address),
     /// `MassaCipher` error: {0}
     MassaCipherError(#[from] massa_cipher::CipherError),
+    /// Secret store error: {0}
+    SecretStoreError(String),
 }
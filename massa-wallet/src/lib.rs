@@ -5,6 +5,11 @@
 #![warn(unused_crate_dependencies)]
 
 pub use error::WalletError;
+#[cfg(feature = "keychain")]
+pub use secret_store::KeychainSecretStore;
+#[cfg(feature = "vault")]
+pub use secret_store::VaultSecretStore;
+pub use secret_store::{migrate_secrets, FileSecretStore, SecretStore};
 
 use massa_cipher::{decrypt, encrypt, CipherData, Salt};
 use massa_hash::Hash;
@@ -19,16 +24,19 @@ use std::collections::hash_map::Entry;
 use std::collections::HashSet;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
+use zeroize::{Zeroize, Zeroizing};
 
 mod error;
+mod secret_store;
 
 /// Contains the keypairs created in the wallet.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug)]
 pub struct Wallet {
     /// Keypairs and addresses
     pub keys: PreHashMap<Address, KeyPair>,
-    /// Path to the file containing the keypairs (encrypted)
-    wallet_path: PathBuf,
+    /// Store of the encrypted keypairs, one secret per keypair
+    store: Arc<dyn SecretStore>,
     /// Password
     password: String,
 }
@@ -46,46 +54,54 @@ struct WalletFileFormat {
     public_key: Vec<u8>,
 }
 
+impl Drop for Wallet {
+    /// Wipes the password from memory.
+    /// The secret keys are wiped by their own destructor.
+    /// Their memory is not locked meanwhile: it may be swapped to disk while the wallet is open.
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
+}
+
 //TODO: Use exports and mock it
 impl Wallet {
     /// Generates a new wallet initialized with the provided file content
     pub fn new(path: PathBuf, password: String) -> Result<Wallet, WalletError> {
-        if path.is_dir() {
-            let mut keys = PreHashMap::default();
-            for entry in std::fs::read_dir(&path)? {
-                let entry = entry?;
-                let path = entry.path();
-                if path.is_file() {
-                    let content = &std::fs::read(&path)?[..];
-                    let wallet = serde_yaml::from_slice::<WalletFileFormat>(content)?;
-                    let secret_key = decrypt(
-                        &password,
-                        CipherData {
-                            salt: wallet.salt,
-                            nonce: wallet.nonce,
-                            encrypted_bytes: wallet.ciphered_data,
-                        },
-                    )?;
-                    keys.insert(
-                        Address::from_str(&wallet.address)?,
-                        KeyPair::from_bytes(&secret_key)?,
-                    );
-                }
-            }
-            Ok(Wallet {
-                keys,
-                wallet_path: path,
-                password,
-            })
-        } else {
-            let wallet = Wallet {
-                keys: PreHashMap::default(),
-                wallet_path: path,
-                password,
+        if !path.is_dir() {
+            std::fs::create_dir_all(&path)?;
+        }
+        Wallet::new_with_store(Arc::new(FileSecretStore::new(path)), password)
+    }
+
+    /// Generates a new wallet initialized with the keypairs of a secret store
+    pub fn new_with_store(
+        store: Arc<dyn SecretStore>,
+        password: String,
+    ) -> Result<Wallet, WalletError> {
+        let mut keys = PreHashMap::default();
+        for name in store.list_secrets()? {
+            let Some(content) = store.get_secret(&name)? else {
+                continue;
             };
-            wallet.save()?;
-            Ok(wallet)
+            let wallet = serde_yaml::from_slice::<WalletFileFormat>(&content)?;
+            let secret_key = Zeroizing::new(decrypt(
+                &password,
+                CipherData {
+                    salt: wallet.salt,
+                    nonce: wallet.nonce,
+                    encrypted_bytes: wallet.ciphered_data,
+                },
+            )?);
+            keys.insert(
+                Address::from_str(&wallet.address)?,
+                KeyPair::from_bytes(&secret_key)?,
+            );
         }
+        Ok(Wallet {
+            keys,
+            store,
+            password,
+        })
     }
 
     /// Sign arbitrary message with the associated keypair
@@ -154,19 +170,11 @@ impl Wallet {
         self.keys.keys().copied().collect()
     }
 
-    /// Save the wallets in a directory, each wallet in a yaml file.
+    /// Save the wallets in the secret store, each wallet in a yaml secret.
     pub fn save(&self) -> Result<(), WalletError> {
-        let mut existing_keys: HashSet<PathBuf> = HashSet::new();
-        if !self.wallet_path.exists() {
-            std::fs::create_dir_all(&self.wallet_path)?;
-        } else {
-            let read_dir = std::fs::read_dir(&self.wallet_path)?;
-            for path in read_dir {
-                existing_keys.insert(path?.path());
-            }
-        }
-        let mut persisted_keys: HashSet<PathBuf> = HashSet::new();
-        // write the keys in the directory
+        let existing_keys: HashSet<String> = self.store.list_secrets()?.into_iter().collect();
+        let mut persisted_keys: HashSet<String> = HashSet::new();
+        // write the keys in the store
         for (addr, keypair) in &self.keys {
             let encrypted_secret = encrypt(&self.password, &keypair.to_bytes())?;
             let file_formatted = WalletFileFormat {
@@ -179,15 +187,14 @@ impl Wallet {
                 public_key: keypair.get_public_key().to_bytes().to_vec(),
             };
             let ser_keys = serde_yaml::to_string(&file_formatted)?;
-            let file_path = self.wallet_path.join(format!("wallet_{}.yaml", addr));
+            let name = format!("wallet_{}.yaml", addr);
 
-            std::fs::write(&file_path, ser_keys)?;
-            persisted_keys.insert(file_path);
+            self.store.set_secret(&name, ser_keys.as_bytes())?;
+            persisted_keys.insert(name);
         }
 
-        let to_remove = existing_keys.difference(&persisted_keys);
-        for path in to_remove {
-            std::fs::remove_file(path)?;
+        for name in existing_keys.difference(&persisted_keys) {
+            self.store.delete_secret(name)?;
         }
 
         Ok(())
@@ -0,0 +1,379 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+//! Storage backends for the node and staking secrets.
+//!
+//! Secrets are opaque byte strings identified by a name within a store.
+//! The default backend keeps each secret in a file of a directory.
+//! The OS keychain (`keychain` feature) and HashiCorp Vault (`vault` feature) backends
+//! let operators keep the secrets in their key-management infrastructure instead.
+
+use crate::WalletError;
+use std::fmt::Debug;
+use std::io::Write;
+use std::path::PathBuf;
+use zeroize::Zeroizing;
+
+/// Storage backend of named secrets
+pub trait SecretStore: Send + Sync + Debug {
+    /// Gets a secret, or None if there is no secret with that name
+    fn get_secret(&self, name: &str) -> Result<Option<Zeroizing<Vec<u8>>>, WalletError>;
+
+    /// Creates or overwrites a secret
+    fn set_secret(&self, name: &str, secret: &[u8]) -> Result<(), WalletError>;
+
+    /// Deletes a secret. Deleting a missing secret is not an error.
+    fn delete_secret(&self, name: &str) -> Result<(), WalletError>;
+
+    /// Lists the names of the secrets of the store
+    fn list_secrets(&self) -> Result<Vec<String>, WalletError>;
+}
+
+/// Copies all the secrets of a store into another one, overwriting the secrets with the same name.
+///
+/// Returns the names of the copied secrets.
+pub fn migrate_secrets(
+    from: &dyn SecretStore,
+    to: &dyn SecretStore,
+) -> Result<Vec<String>, WalletError> {
+    let names = from.list_secrets()?;
+    for name in &names {
+        if let Some(secret) = from.get_secret(name)? {
+            to.set_secret(name, &secret)?;
+        }
+    }
+    Ok(names)
+}
+
+/// Secret store keeping each secret in a file of a directory, named after the secret
+#[derive(Debug, Clone)]
+pub struct FileSecretStore {
+    /// directory containing the secret files
+    dir: PathBuf,
+}
+
+impl FileSecretStore {
+    /// Creates a store of the secrets of a directory. The directory is created on the first write.
+    pub fn new(dir: PathBuf) -> Self {
+        FileSecretStore { dir }
+    }
+
+    fn secret_path(&self, name: &str) -> Result<PathBuf, WalletError> {
+        if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            return Err(WalletError::SecretStoreError(format!(
+                "invalid secret name: {}",
+                name
+            )));
+        }
+        Ok(self.dir.join(name))
+    }
+}
+
+impl SecretStore for FileSecretStore {
+    fn get_secret(&self, name: &str) -> Result<Option<Zeroizing<Vec<u8>>>, WalletError> {
+        match std::fs::read(self.secret_path(name)?) {
+            Ok(secret) => Ok(Some(Zeroizing::new(secret))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn set_secret(&self, name: &str, secret: &[u8]) -> Result<(), WalletError> {
+        let path = self.secret_path(name)?;
+        std::fs::create_dir_all(&self.dir)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // only the owner of the node may read its secrets
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        // a file created by an earlier version may still be readable by others
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        file.write_all(secret)?;
+        Ok(())
+    }
+
+    fn delete_secret(&self, name: &str) -> Result<(), WalletError> {
+        match std::fs::remove_file(self.secret_path(name)?) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn list_secrets(&self) -> Result<Vec<String>, WalletError> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.path().is_file() {
+                if let Some(name) = entry.file_name().to_str() {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort_unstable();
+        Ok(names)
+    }
+}
+
+/// Secret store keeping the secrets in the keychain of the operating system
+/// (Keychain on macOS, Credential Manager on Windows, Secret Service on Linux).
+///
+/// The keychain cannot enumerate entries: the names of the secrets are kept in an index entry of the service.
+#[cfg(feature = "keychain")]
+#[derive(Debug, Clone)]
+pub struct KeychainSecretStore {
+    /// keychain service under which the secrets are stored
+    service: String,
+}
+
+#[cfg(feature = "keychain")]
+impl KeychainSecretStore {
+    /// name of the keychain entry listing the secrets of the service
+    const INDEX_ENTRY: &'static str = "__massa_secret_index__";
+
+    /// Creates a store of the secrets of a keychain service
+    pub fn new(service: String) -> Self {
+        KeychainSecretStore { service }
+    }
+
+    fn entry(&self, name: &str) -> Result<keyring::Entry, WalletError> {
+        keyring::Entry::new(&self.service, name)
+            .map_err(|err| WalletError::SecretStoreError(err.to_string()))
+    }
+
+    fn read_index(&self) -> Result<Vec<String>, WalletError> {
+        match self.entry(Self::INDEX_ENTRY)?.get_password() {
+            Ok(index) => Ok(index.lines().map(str::to_string).collect()),
+            Err(keyring::Error::NoEntry) => Ok(Vec::new()),
+            Err(err) => Err(WalletError::SecretStoreError(err.to_string())),
+        }
+    }
+
+    fn write_index(&self, names: &[String]) -> Result<(), WalletError> {
+        self.entry(Self::INDEX_ENTRY)?
+            .set_password(&names.join("\n"))
+            .map_err(|err| WalletError::SecretStoreError(err.to_string()))
+    }
+}
+
+#[cfg(feature = "keychain")]
+impl SecretStore for KeychainSecretStore {
+    fn get_secret(&self, name: &str) -> Result<Option<Zeroizing<Vec<u8>>>, WalletError> {
+        match self.entry(name)?.get_password() {
+            Ok(secret) => {
+                let secret = Zeroizing::new(secret);
+                Ok(Some(Zeroizing::new(
+                    bs58::decode(secret.as_str()).into_vec().map_err(|err| {
+                        WalletError::SecretStoreError(format!(
+                            "invalid keychain secret {}: {}",
+                            name, err
+                        ))
+                    })?,
+                )))
+            }
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(WalletError::SecretStoreError(err.to_string())),
+        }
+    }
+
+    fn set_secret(&self, name: &str, secret: &[u8]) -> Result<(), WalletError> {
+        let encoded = Zeroizing::new(bs58::encode(secret).into_string());
+        self.entry(name)?
+            .set_password(&encoded)
+            .map_err(|err| WalletError::SecretStoreError(err.to_string()))?;
+        let mut names = self.read_index()?;
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+            self.write_index(&names)?;
+        }
+        Ok(())
+    }
+
+    fn delete_secret(&self, name: &str) -> Result<(), WalletError> {
+        match self.entry(name)?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(err) => return Err(WalletError::SecretStoreError(err.to_string())),
+        }
+        let mut names = self.read_index()?;
+        names.retain(|n| n != name);
+        self.write_index(&names)
+    }
+
+    fn list_secrets(&self) -> Result<Vec<String>, WalletError> {
+        self.read_index()
+    }
+}
+
+/// Secret store keeping the secrets in a HashiCorp Vault KV version 2 secrets engine.
+/// Each secret is stored bs58-encoded under the `value` key of `<mount>/<prefix>/<name>`.
+#[cfg(feature = "vault")]
+pub struct VaultSecretStore {
+    /// address of the Vault server, e.g. `https://vault.example.com:8200`
+    address: String,
+    /// mount path of the KV version 2 secrets engine
+    mount: String,
+    /// path of the secrets in the secrets engine
+    prefix: String,
+    /// Vault token
+    token: Zeroizing<String>,
+    /// HTTP agent
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "vault")]
+impl Debug for VaultSecretStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VaultSecretStore")
+            .field("address", &self.address)
+            .field("mount", &self.mount)
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "vault")]
+impl VaultSecretStore {
+    /// Creates a store of the secrets under a path of a Vault KV version 2 secrets engine
+    pub fn new(address: String, mount: String, prefix: String, token: String) -> Self {
+        VaultSecretStore {
+            address: address.trim_end_matches('/').to_string(),
+            mount: mount.trim_matches('/').to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+            token: Zeroizing::new(token),
+            agent: ureq::Agent::new(),
+        }
+    }
+
+    fn url(&self, kind: &str, name: &str) -> String {
+        format!(
+            "{}/v1/{}/{}/{}/{}",
+            self.address, self.mount, kind, self.prefix, name
+        )
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        self.agent
+            .request(method, url)
+            .set("X-Vault-Token", self.token.as_str())
+    }
+}
+
+#[cfg(feature = "vault")]
+fn vault_error(err: impl std::fmt::Display) -> WalletError {
+    WalletError::SecretStoreError(format!("vault request failed: {}", err))
+}
+
+#[cfg(feature = "vault")]
+impl SecretStore for VaultSecretStore {
+    fn get_secret(&self, name: &str) -> Result<Option<Zeroizing<Vec<u8>>>, WalletError> {
+        let response = match self.request("GET", &self.url("data", name)).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            Err(err) => return Err(vault_error(err)),
+        };
+        let body: serde_json::Value = response.into_json().map_err(vault_error)?;
+        let encoded = body["data"]["data"]["value"]
+            .as_str()
+            .ok_or_else(|| vault_error(format!("secret {} has no value", name)))?;
+        let secret = bs58::decode(encoded).into_vec().map_err(vault_error)?;
+        Ok(Some(Zeroizing::new(secret)))
+    }
+
+    fn set_secret(&self, name: &str, secret: &[u8]) -> Result<(), WalletError> {
+        let encoded = Zeroizing::new(bs58::encode(secret).into_string());
+        self.request("POST", &self.url("data", name))
+            .send_json(serde_json::json!({ "data": { "value": encoded.as_str() } }))
+            .map_err(vault_error)?;
+        Ok(())
+    }
+
+    fn delete_secret(&self, name: &str) -> Result<(), WalletError> {
+        match self.request("DELETE", &self.url("metadata", name)).call() {
+            Ok(_) | Err(ureq::Error::Status(404, _)) => Ok(()),
+            Err(err) => Err(vault_error(err)),
+        }
+    }
+
+    fn list_secrets(&self) -> Result<Vec<String>, WalletError> {
+        let response = match self.request("LIST", &self.url("metadata", "")).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(Vec::new()),
+            Err(err) => return Err(vault_error(err)),
+        };
+        let body: serde_json::Value = response.into_json().map_err(vault_error)?;
+        Ok(body["data"]["keys"]
+            .as_array()
+            .map(|keys| {
+                keys.iter()
+                    .filter_map(|key| key.as_str())
+                    // sub-directories end with a slash
+                    .filter(|key| !key.ends_with('/'))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_secret_store_migration() {
+        let from_dir = TempDir::new().unwrap();
+        let to_dir = TempDir::new().unwrap();
+        let from = FileSecretStore::new(from_dir.path().join("secrets"));
+        let to = FileSecretStore::new(to_dir.path().to_path_buf());
+
+        assert!(from.list_secrets().unwrap().is_empty());
+        from.set_secret("node_privkey.key", b"node").unwrap();
+        from.set_secret("wallet_1.yaml", b"staking").unwrap();
+        assert!(from.set_secret("../escape", b"nope").is_err());
+
+        let migrated = migrate_secrets(&from, &to).unwrap();
+        assert_eq!(migrated, vec!["node_privkey.key", "wallet_1.yaml"]);
+        assert_eq!(
+            to.get_secret("wallet_1.yaml").unwrap().unwrap().as_slice(),
+            b"staking"
+        );
+
+        to.delete_secret("wallet_1.yaml").unwrap();
+        to.delete_secret("wallet_1.yaml").unwrap();
+        assert!(to.get_secret("wallet_1.yaml").unwrap().is_none());
+        assert_eq!(to.list_secrets().unwrap(), vec!["node_privkey.key"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_secret_store_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let store = FileSecretStore::new(dir.path().to_path_buf());
+        let path = dir.path().join("node_privkey.key");
+        std::fs::write(&path, b"old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        store.set_secret("node_privkey.key", b"node").unwrap();
+        store.set_secret("wallet_1.yaml", b"staking").unwrap();
+        for name in ["node_privkey.key", "wallet_1.yaml"] {
+            let mode = std::fs::metadata(dir.path().join(name))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(
+            store
+                .get_secret("node_privkey.key")
+                .unwrap()
+                .unwrap()
+                .as_slice(),
+            b"node"
+        );
+    }
+}